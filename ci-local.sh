@@ -138,6 +138,13 @@ run_check "Documentation" "cargo doc --no-deps --document-private-items --all-fe
 
 run_check "Feature: none" "cargo check --no-default-features"
 run_check "Feature: serde" "cargo check --no-default-features --features serde"
+run_check "Feature: wire" "cargo check --no-default-features --features wire"
+run_check "Feature: testing" "cargo check --no-default-features --features testing"
+run_check "Feature: rayon" "cargo check --no-default-features --features rayon"
+run_check "Feature: futures-io" "cargo check --no-default-features --features futures-io"
+run_check "Feature: tokio" "cargo check --no-default-features --features tokio"
+run_check "Feature: vfs" "cargo check --no-default-features --features vfs"
+run_check "Feature: cap-std" "cargo check --no-default-features --features cap-std"
 run_check "Feature: all" "cargo check --all-features"
 
 # MSRV check (if rustup available)
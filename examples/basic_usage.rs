@@ -45,9 +45,7 @@ impl FsRead for SimpleFs {
             .unwrap()
             .get(path)
             .cloned()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("read", path))
     }
 
     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
@@ -75,31 +73,27 @@ impl FsRead for SimpleFs {
 
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
         if self.dirs.read().unwrap().contains(path) {
-            Ok(Metadata {
-                file_type: FileType::Directory,
-                size: 0,
-                permissions: Permissions::default_dir(),
-                created: SystemTime::UNIX_EPOCH,
-                modified: SystemTime::UNIX_EPOCH,
-                accessed: SystemTime::UNIX_EPOCH,
-                inode: 0,
-                nlink: 1,
-            })
+            Ok(Metadata::default()
+                .with_file_type(FileType::Directory)
+                .with_size(0)
+                .with_permissions(Permissions::default_dir())
+                .with_created(SystemTime::UNIX_EPOCH)
+                .with_modified(SystemTime::UNIX_EPOCH)
+                .with_accessed(SystemTime::UNIX_EPOCH)
+                .with_inode(0)
+                .with_nlink(1))
         } else if let Some(data) = self.files.read().unwrap().get(path) {
-            Ok(Metadata {
-                file_type: FileType::File,
-                size: data.len() as u64,
-                permissions: Permissions::default_file(),
-                created: SystemTime::UNIX_EPOCH,
-                modified: SystemTime::UNIX_EPOCH,
-                accessed: SystemTime::UNIX_EPOCH,
-                inode: 0,
-                nlink: 1,
-            })
+            Ok(Metadata::default()
+                .with_file_type(FileType::File)
+                .with_size(data.len() as u64)
+                .with_permissions(Permissions::default_file())
+                .with_created(SystemTime::UNIX_EPOCH)
+                .with_modified(SystemTime::UNIX_EPOCH)
+                .with_accessed(SystemTime::UNIX_EPOCH)
+                .with_inode(0)
+                .with_nlink(1))
         } else {
-            Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            Err(FsError::not_found("metadata", path))
         }
     }
 
@@ -135,16 +129,14 @@ impl FsWrite for SimpleFs {
             .unwrap()
             .remove(path)
             .map(|_| ())
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("remove_file", path))
     }
 
     fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.remove(from).ok_or_else(|| FsError::NotFound {
-            path: from.to_path_buf(),
-        })?;
+        let data = files
+            .remove(from)
+            .ok_or_else(|| FsError::not_found("rename", from))?;
         files.insert(to.to_path_buf(), data);
         Ok(())
     }
@@ -156,9 +148,9 @@ impl FsWrite for SimpleFs {
 
     fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.get_mut(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let data = files
+            .get_mut(path)
+            .ok_or_else(|| FsError::not_found("truncate", path))?;
         data.resize(size as usize, 0);
         Ok(())
     }
@@ -172,9 +164,7 @@ impl FsWrite for SimpleFs {
 impl FsDir for SimpleFs {
     fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
         if !self.dirs.read().unwrap().contains(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("read_dir", path));
         }
 
         let mut entries = Vec::new();
@@ -184,13 +174,12 @@ impl FsDir for SimpleFs {
             if let Some(parent) = file_path.parent() {
                 if parent == path {
                     if let Some(name) = file_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: file_path.clone(),
-                            file_type: FileType::File,
-                            size: data.len() as u64,
-                            inode: 0,
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(file_path.clone())
+                            .with_file_type(FileType::File)
+                            .with_size(data.len() as u64)
+                            .with_inode(0)));
                     }
                 }
             }
@@ -201,13 +190,12 @@ impl FsDir for SimpleFs {
             if let Some(parent) = dir_path.parent() {
                 if parent == path && dir_path != path {
                     if let Some(name) = dir_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: dir_path.clone(),
-                            file_type: FileType::Directory,
-                            size: 0,
-                            inode: 0,
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(dir_path.clone())
+                            .with_file_type(FileType::Directory)
+                            .with_size(0)
+                            .with_inode(0)));
                     }
                 }
             }
@@ -239,9 +227,7 @@ impl FsDir for SimpleFs {
 
     fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
         if !self.dirs.write().unwrap().remove(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_dir", path));
         }
         Ok(())
     }
@@ -331,7 +317,7 @@ fn main() {
     println!("\n8. Error handling...");
     match fs.read(Path::new("/nonexistent.txt")) {
         Ok(_) => println!("   Unexpected success"),
-        Err(FsError::NotFound { path }) => {
+        Err(FsError::NotFound { path, .. }) => {
             println!("   Correctly got NotFound for: {}", path.display());
         }
         Err(e) => println!("   Unexpected error: {e}"),
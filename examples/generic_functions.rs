@@ -37,7 +37,7 @@ fn count_files_recursive<B: Fs>(fs: &B, dir: &Path) -> Result<usize, FsError> {
             FileType::Directory => {
                 count += count_files_recursive(fs, &entry.path)?;
             }
-            FileType::Symlink => {} // Skip symlinks in basic count
+            _ => {} // Skip symlinks and special entries in basic count
         }
     }
     Ok(count)
@@ -298,9 +298,7 @@ impl FsRead for DemoFs {
             .unwrap()
             .get(path)
             .cloned()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("read", path))
     }
     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
         String::from_utf8(self.read(path)?).map_err(|_| FsError::InvalidData {
@@ -321,9 +319,9 @@ impl FsRead for DemoFs {
         Ok(self.get_file_type(path).is_some())
     }
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let ft = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let ft = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("metadata", path))?;
         let size = if ft == FileType::File {
             self.files
                 .read()
@@ -334,16 +332,15 @@ impl FsRead for DemoFs {
         } else {
             0
         };
-        Ok(Metadata {
-            file_type: ft,
-            size,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode: self.inodes.read().unwrap().get(path).copied().unwrap_or(0),
-            nlink: 1,
-        })
+        Ok(Metadata::default()
+            .with_file_type(ft)
+            .with_size(size)
+            .with_permissions(Permissions::default_file())
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(self.inodes.read().unwrap().get(path).copied().unwrap_or(0))
+            .with_nlink(1))
     }
     fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
         Ok(Box::new(std::io::Cursor::new(self.read(path)?)))
@@ -375,9 +372,7 @@ impl FsWrite for DemoFs {
             .unwrap()
             .remove(path)
             .map(|_| ())
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("remove_file", path))
     }
     fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
         let data = self
@@ -385,9 +380,7 @@ impl FsWrite for DemoFs {
             .write()
             .unwrap()
             .remove(from)
-            .ok_or_else(|| FsError::NotFound {
-                path: from.to_path_buf(),
-            })?;
+            .ok_or_else(|| FsError::not_found("rename", from))?;
         self.files.write().unwrap().insert(to.to_path_buf(), data);
         self.assign_inode(to);
         Ok(())
@@ -401,9 +394,7 @@ impl FsWrite for DemoFs {
             .write()
             .unwrap()
             .get_mut(path)
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })?
+            .ok_or_else(|| FsError::not_found("truncate", path))?
             .resize(size as usize, 0);
         Ok(())
     }
@@ -415,34 +406,30 @@ impl FsWrite for DemoFs {
 impl FsDir for DemoFs {
     fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
         if !self.dirs.read().unwrap().contains(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("read_dir", path));
         }
         let mut entries = Vec::new();
         for (fp, data) in self.files.read().unwrap().iter() {
             if fp.parent() == Some(path) {
                 if let Some(name) = fp.file_name() {
-                    entries.push(Ok(DirEntry {
-                        name: name.to_string_lossy().into(),
-                        path: fp.clone(),
-                        file_type: FileType::File,
-                        size: data.len() as u64,
-                        inode: 0,
-                    }));
+                    entries.push(Ok(DirEntry::default()
+                        .with_name(name.to_string_lossy())
+                        .with_path(fp.clone())
+                        .with_file_type(FileType::File)
+                        .with_size(data.len() as u64)
+                        .with_inode(0)));
                 }
             }
         }
         for dp in self.dirs.read().unwrap().iter() {
             if dp.parent() == Some(path) && dp != path {
                 if let Some(name) = dp.file_name() {
-                    entries.push(Ok(DirEntry {
-                        name: name.to_string_lossy().into(),
-                        path: dp.clone(),
-                        file_type: FileType::Directory,
-                        size: 0,
-                        inode: 0,
-                    }));
+                    entries.push(Ok(DirEntry::default()
+                        .with_name(name.to_string_lossy())
+                        .with_path(dp.clone())
+                        .with_file_type(FileType::Directory)
+                        .with_size(0)
+                        .with_inode(0)));
                 }
             }
         }
@@ -470,9 +457,7 @@ impl FsDir for DemoFs {
     }
     fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
         if !self.dirs.write().unwrap().remove(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_dir", path));
         }
         Ok(())
     }
@@ -511,28 +496,25 @@ impl FsLink for DemoFs {
             })
     }
     fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let ft = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
-        Ok(Metadata {
-            file_type: ft,
-            size: 0,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode: 0,
-            nlink: 1,
-        })
+        let ft = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("symlink_metadata", path))?;
+        Ok(Metadata::default()
+            .with_file_type(ft)
+            .with_size(0)
+            .with_permissions(Permissions::default_file())
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(0)
+            .with_nlink(1))
     }
 }
 
 impl FsPermissions for DemoFs {
     fn set_permissions(&self, path: &Path, _perm: Permissions) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("set_permissions", path));
         }
         Ok(())
     }
@@ -544,9 +526,7 @@ impl FsSync for DemoFs {
     }
     fn fsync(&self, path: &Path) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("fsync", path));
         }
         Ok(())
     }
@@ -554,16 +534,11 @@ impl FsSync for DemoFs {
 
 impl FsStats for DemoFs {
     fn statfs(&self) -> Result<StatFs, FsError> {
-        Ok(StatFs {
-            total_bytes: 100 * 1024 * 1024,
-            used_bytes: 50 * 1024 * 1024,
-            available_bytes: 50 * 1024 * 1024,
-            total_inodes: 10000,
-            used_inodes: 1000,
-            available_inodes: 9000,
-            block_size: 4096,
-            max_name_len: 255,
-        })
+        Ok(StatFs::default()
+            .with_bytes(100 * 1024 * 1024, 50 * 1024 * 1024, 50 * 1024 * 1024)
+            .with_inodes(10000, 1000, 9000)
+            .with_block_size(4096)
+            .with_max_name_len(255))
     }
 }
 
@@ -574,9 +549,7 @@ impl FsInode for DemoFs {
             .unwrap()
             .get(path)
             .copied()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("path_to_inode", path))
     }
     fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
         self.inode_to_path
@@ -600,9 +573,7 @@ impl FsHandles for DemoFs {
         if flags.create && !self.files.read().unwrap().contains_key(path) {
             self.write(path, &[])?;
         } else if !self.files.read().unwrap().contains_key(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("open", path));
         }
         let id = self.next_handle.fetch_add(1, Ordering::SeqCst);
         self.handles
@@ -692,9 +663,7 @@ impl FsLock for DemoFs {
 
 impl FsXattr for DemoFs {
     fn get_xattr(&self, path: &Path, _name: &str) -> Result<Vec<u8>, FsError> {
-        Err(FsError::NotFound {
-            path: path.to_path_buf(),
-        })
+        Err(FsError::not_found("get_xattr", path))
     }
     fn set_xattr(&self, _path: &Path, _name: &str, _value: &[u8]) -> Result<(), FsError> {
         Ok(())
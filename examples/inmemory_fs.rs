@@ -10,6 +10,9 @@
 //! - Testing and mocking
 //! - In-memory caching layers
 //! - Learning how each trait method should behave
+//!
+//! To use an in-memory backend in tests, enable the `testing` feature and use
+//! `anyfs_backend::testing::MemFs` instead of copying this file.
 
 use anyfs_backend::*;
 use std::collections::HashMap;
@@ -167,9 +170,7 @@ impl FsRead for InMemoryFs {
             .unwrap()
             .get(path)
             .cloned()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("read", path))
     }
 
     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
@@ -197,9 +198,9 @@ impl FsRead for InMemoryFs {
     }
 
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let file_type = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let file_type = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("metadata", path))?;
 
         let size = if file_type == FileType::File {
             self.files
@@ -214,16 +215,15 @@ impl FsRead for InMemoryFs {
 
         let inode = self.inodes.read().unwrap().get(path).copied().unwrap_or(0);
 
-        Ok(Metadata {
-            file_type,
-            size,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode,
-            nlink: 1,
-        })
+        Ok(Metadata::default()
+            .with_file_type(file_type)
+            .with_size(size)
+            .with_permissions(Permissions::default_file())
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(inode)
+            .with_nlink(1))
     }
 
     fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
@@ -262,9 +262,7 @@ impl FsWrite for InMemoryFs {
             .write()
             .unwrap()
             .remove(path)
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })?;
+            .ok_or_else(|| FsError::not_found("remove_file", path))?;
 
         // Clean up inode mapping
         if let Some(inode) = self.inodes.write().unwrap().remove(path) {
@@ -279,9 +277,9 @@ impl FsWrite for InMemoryFs {
 
     fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.remove(from).ok_or_else(|| FsError::NotFound {
-            path: from.to_path_buf(),
-        })?;
+        let data = files
+            .remove(from)
+            .ok_or_else(|| FsError::not_found("rename", from))?;
         files.insert(to.to_path_buf(), data);
         drop(files);
 
@@ -311,9 +309,9 @@ impl FsWrite for InMemoryFs {
 
     fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.get_mut(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let data = files
+            .get_mut(path)
+            .ok_or_else(|| FsError::not_found("truncate", path))?;
         data.resize(size as usize, 0);
         Ok(())
     }
@@ -337,9 +335,7 @@ impl FsWrite for InMemoryFs {
 impl FsDir for InMemoryFs {
     fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
         if !self.dirs.read().unwrap().contains(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("read_dir", path));
         }
 
         let mut entries = Vec::new();
@@ -349,19 +345,19 @@ impl FsDir for InMemoryFs {
             if let Some(parent) = file_path.parent() {
                 if parent == path {
                     if let Some(name) = file_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: file_path.clone(),
-                            file_type: FileType::File,
-                            size: data.len() as u64,
-                            inode: self
-                                .inodes
-                                .read()
-                                .unwrap()
-                                .get(file_path)
-                                .copied()
-                                .unwrap_or(0),
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(file_path.clone())
+                            .with_file_type(FileType::File)
+                            .with_size(data.len() as u64)
+                            .with_inode(
+                                self.inodes
+                                    .read()
+                                    .unwrap()
+                                    .get(file_path)
+                                    .copied()
+                                    .unwrap_or(0),
+                            )));
                     }
                 }
             }
@@ -372,19 +368,19 @@ impl FsDir for InMemoryFs {
             if let Some(parent) = dir_path.parent() {
                 if parent == path && dir_path != path {
                     if let Some(name) = dir_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: dir_path.clone(),
-                            file_type: FileType::Directory,
-                            size: 0,
-                            inode: self
-                                .inodes
-                                .read()
-                                .unwrap()
-                                .get(dir_path)
-                                .copied()
-                                .unwrap_or(0),
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(dir_path.clone())
+                            .with_file_type(FileType::Directory)
+                            .with_size(0)
+                            .with_inode(
+                                self.inodes
+                                    .read()
+                                    .unwrap()
+                                    .get(dir_path)
+                                    .copied()
+                                    .unwrap_or(0),
+                            )));
                     }
                 }
             }
@@ -395,19 +391,19 @@ impl FsDir for InMemoryFs {
             if let Some(parent) = link_path.parent() {
                 if parent == path {
                     if let Some(name) = link_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: link_path.clone(),
-                            file_type: FileType::Symlink,
-                            size: 0,
-                            inode: self
-                                .inodes
-                                .read()
-                                .unwrap()
-                                .get(link_path)
-                                .copied()
-                                .unwrap_or(0),
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(link_path.clone())
+                            .with_file_type(FileType::Symlink)
+                            .with_size(0)
+                            .with_inode(
+                                self.inodes
+                                    .read()
+                                    .unwrap()
+                                    .get(link_path)
+                                    .copied()
+                                    .unwrap_or(0),
+                            )));
                     }
                 }
             }
@@ -429,9 +425,7 @@ impl FsDir for InMemoryFs {
         // Check parent exists
         if let Some(parent) = path.parent() {
             if parent != Path::new("") && parent != Path::new("/") && !dirs.contains(parent) {
-                return Err(FsError::NotFound {
-                    path: parent.to_path_buf(),
-                });
+                return Err(FsError::not_found("create_dir", parent));
             }
         }
 
@@ -476,15 +470,11 @@ impl FsDir for InMemoryFs {
             .any(|p| p.parent() == Some(path) && p != path);
 
         if has_files || has_subdirs {
-            return Err(FsError::DirectoryNotEmpty {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::directory_not_empty("remove_dir", path));
         }
 
         if !self.dirs.write().unwrap().remove(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_dir", path));
         }
 
         // Clean up inode
@@ -554,11 +544,10 @@ impl FsLink for InMemoryFs {
     }
 
     fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
-        // For in-memory fs, we just copy the data (true hard links would share data)
+        // For brevity we just copy the data; anyfs_backend::LinkStore shares it for real
         let data = self.read(original)?;
         self.write(link, &data)?;
 
-        // In a real implementation, you'd update nlink count
         Ok(())
     }
 
@@ -575,20 +564,19 @@ impl FsLink for InMemoryFs {
     }
 
     fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let file_type = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
-
-        Ok(Metadata {
-            file_type,
-            size: 0,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode: self.inodes.read().unwrap().get(path).copied().unwrap_or(0),
-            nlink: 1,
-        })
+        let file_type = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("symlink_metadata", path))?;
+
+        Ok(Metadata::default()
+            .with_file_type(file_type)
+            .with_size(0)
+            .with_permissions(Permissions::default_file())
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(self.inodes.read().unwrap().get(path).copied().unwrap_or(0))
+            .with_nlink(1))
     }
 }
 
@@ -600,9 +588,7 @@ impl FsPermissions for InMemoryFs {
     fn set_permissions(&self, path: &Path, _perm: Permissions) -> Result<(), FsError> {
         // Verify path exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("set_permissions", path));
         }
 
         // In a real implementation, you'd store and enforce permissions
@@ -623,9 +609,7 @@ impl FsSync for InMemoryFs {
     fn fsync(&self, path: &Path) -> Result<(), FsError> {
         // Verify path exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("fsync", path));
         }
 
         // In-memory filesystem is always synchronized
@@ -643,16 +627,15 @@ impl FsStats for InMemoryFs {
         let used_bytes: u64 = files.values().map(|d| d.len() as u64).sum();
         let used_inodes = files.len() + self.dirs.read().unwrap().len();
 
-        Ok(StatFs {
-            total_bytes: 1024 * 1024 * 100, // 100 MB
-            used_bytes,
-            available_bytes: 1024 * 1024 * 100 - used_bytes,
-            total_inodes: 100_000,
-            used_inodes: used_inodes as u64,
-            available_inodes: 100_000 - used_inodes as u64,
-            block_size: 4096,
-            max_name_len: 255,
-        })
+        Ok(StatFs::default()
+            .with_bytes(
+                1024 * 1024 * 100,
+                used_bytes,
+                1024 * 1024 * 100 - used_bytes,
+            )
+            .with_inodes(100_000, used_inodes as u64, 100_000 - used_inodes as u64)
+            .with_block_size(4096)
+            .with_max_name_len(255))
     }
 }
 
@@ -667,9 +650,7 @@ impl FsInode for InMemoryFs {
             .unwrap()
             .get(path)
             .copied()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("path_to_inode", path))
     }
 
     fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
@@ -705,9 +686,7 @@ impl FsHandles for InMemoryFs {
                 self.write(path, &[])?;
             }
         } else if !self.files.read().unwrap().contains_key(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("open", path));
         }
 
         let handle_id = self.next_handle.fetch_add(1, Ordering::SeqCst);
@@ -738,9 +717,9 @@ impl FsHandles for InMemoryFs {
         }
 
         let files = self.files.read().unwrap();
-        let data = files.get(&open_file.path).ok_or(FsError::NotFound {
-            path: open_file.path.clone(),
-        })?;
+        let data = files
+            .get(&open_file.path)
+            .ok_or(FsError::not_found("read_at", open_file.path.clone()))?;
 
         let start = offset as usize;
         if start >= data.len() {
@@ -882,9 +861,7 @@ impl FsXattr for InMemoryFs {
     fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
         // Check file exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("get_xattr", path));
         }
 
         self.xattrs
@@ -901,9 +878,7 @@ impl FsXattr for InMemoryFs {
     fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
         // Check file exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("set_xattr", path));
         }
 
         self.xattrs
@@ -919,9 +894,7 @@ impl FsXattr for InMemoryFs {
     fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
         // Check file exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_xattr", path));
         }
 
         self.xattrs
@@ -940,9 +913,7 @@ impl FsXattr for InMemoryFs {
     fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
         // Check file exists
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("list_xattr", path));
         }
 
         Ok(self
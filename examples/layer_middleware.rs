@@ -453,9 +453,7 @@ impl FsRead for MemoryFs {
             .unwrap()
             .get(path)
             .cloned()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("read", path))
     }
     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
         String::from_utf8(self.read(path)?).map_err(|_| FsError::InvalidData {
@@ -478,31 +476,27 @@ impl FsRead for MemoryFs {
     }
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
         if self.dirs.read().unwrap().contains(path) {
-            Ok(Metadata {
-                file_type: FileType::Directory,
-                size: 0,
-                permissions: Permissions::default_dir(),
-                created: SystemTime::UNIX_EPOCH,
-                modified: SystemTime::UNIX_EPOCH,
-                accessed: SystemTime::UNIX_EPOCH,
-                inode: 0,
-                nlink: 1,
-            })
+            Ok(Metadata::default()
+                .with_file_type(FileType::Directory)
+                .with_size(0)
+                .with_permissions(Permissions::default_dir())
+                .with_created(SystemTime::UNIX_EPOCH)
+                .with_modified(SystemTime::UNIX_EPOCH)
+                .with_accessed(SystemTime::UNIX_EPOCH)
+                .with_inode(0)
+                .with_nlink(1))
         } else if let Some(data) = self.files.read().unwrap().get(path) {
-            Ok(Metadata {
-                file_type: FileType::File,
-                size: data.len() as u64,
-                permissions: Permissions::default_file(),
-                created: SystemTime::UNIX_EPOCH,
-                modified: SystemTime::UNIX_EPOCH,
-                accessed: SystemTime::UNIX_EPOCH,
-                inode: 0,
-                nlink: 1,
-            })
+            Ok(Metadata::default()
+                .with_file_type(FileType::File)
+                .with_size(data.len() as u64)
+                .with_permissions(Permissions::default_file())
+                .with_created(SystemTime::UNIX_EPOCH)
+                .with_modified(SystemTime::UNIX_EPOCH)
+                .with_accessed(SystemTime::UNIX_EPOCH)
+                .with_inode(0)
+                .with_nlink(1))
         } else {
-            Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            Err(FsError::not_found("metadata", path))
         }
     }
     fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
@@ -533,9 +527,7 @@ impl FsWrite for MemoryFs {
             .unwrap()
             .remove(path)
             .map(|_| ())
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("remove_file", path))
     }
     fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
         let data = self
@@ -543,9 +535,7 @@ impl FsWrite for MemoryFs {
             .write()
             .unwrap()
             .remove(from)
-            .ok_or_else(|| FsError::NotFound {
-                path: from.to_path_buf(),
-            })?;
+            .ok_or_else(|| FsError::not_found("rename", from))?;
         self.files.write().unwrap().insert(to.to_path_buf(), data);
         Ok(())
     }
@@ -558,9 +548,7 @@ impl FsWrite for MemoryFs {
             .write()
             .unwrap()
             .get_mut(path)
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })?
+            .ok_or_else(|| FsError::not_found("truncate", path))?
             .resize(size as usize, 0);
         Ok(())
     }
@@ -572,34 +560,30 @@ impl FsWrite for MemoryFs {
 impl FsDir for MemoryFs {
     fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
         if !self.dirs.read().unwrap().contains(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("read_dir", path));
         }
         let mut entries = Vec::new();
         for (fp, data) in self.files.read().unwrap().iter() {
             if fp.parent() == Some(path) {
                 if let Some(name) = fp.file_name() {
-                    entries.push(Ok(DirEntry {
-                        name: name.to_string_lossy().into(),
-                        path: fp.clone(),
-                        file_type: FileType::File,
-                        size: data.len() as u64,
-                        inode: 0,
-                    }));
+                    entries.push(Ok(DirEntry::default()
+                        .with_name(name.to_string_lossy())
+                        .with_path(fp.clone())
+                        .with_file_type(FileType::File)
+                        .with_size(data.len() as u64)
+                        .with_inode(0)));
                 }
             }
         }
         for dp in self.dirs.read().unwrap().iter() {
             if dp.parent() == Some(path) && dp != path {
                 if let Some(name) = dp.file_name() {
-                    entries.push(Ok(DirEntry {
-                        name: name.to_string_lossy().into(),
-                        path: dp.clone(),
-                        file_type: FileType::Directory,
-                        size: 0,
-                        inode: 0,
-                    }));
+                    entries.push(Ok(DirEntry::default()
+                        .with_name(name.to_string_lossy())
+                        .with_path(dp.clone())
+                        .with_file_type(FileType::Directory)
+                        .with_size(0)
+                        .with_inode(0)));
                 }
             }
         }
@@ -625,9 +609,7 @@ impl FsDir for MemoryFs {
     }
     fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
         if !self.dirs.write().unwrap().remove(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_dir", path));
         }
         Ok(())
     }
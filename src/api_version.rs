@@ -0,0 +1,169 @@
+//! # API Version
+//!
+//! Backends loaded as plugins are compiled separately from the host. If the
+//! two were built against different trait definitions, calls through a
+//! `dyn Fs` land in the wrong vtable slot. [`ANYFS_API_VERSION`] identifies
+//! the trait definitions; [`declare_backend!`](crate::declare_backend)
+//! records the version a backend was built against, and the host checks it
+//! with [`compatible_with`] before using the backend.
+//!
+//! The version is independent of the crate version: it changes whenever a
+//! trait gains, loses or changes a method, and only then.
+//!
+//! ## Loading a Plugin
+//!
+//! `declare_backend!(MyBackend, export)` in a `cdylib` exports a `u32`
+//! static named [`BACKEND_API_VERSION_SYMBOL`]. The loader reads it (e.g.
+//! with `libloading`) and refuses the library unless
+//! [`compatible_with`] accepts it, before resolving any other symbol.
+
+use crate::FsError;
+
+/// Version of the trait definitions in this build of the crate.
+///
+/// | Version | Change |
+/// |---------|--------|
+/// | 1 | Initial trait definitions |
+/// | 2 | [`FsDir::read_dir_with`](crate::FsDir::read_dir_with) |
+/// | 3 | [`FsWrite::set_times`](crate::FsWrite::set_times) |
+pub const ANYFS_API_VERSION: u32 = 3;
+
+/// Name of the static exported by `declare_backend!(T, export)`.
+pub const BACKEND_API_VERSION_SYMBOL: &str = "ANYFS_BACKEND_API_VERSION";
+
+/// Returns `true` if a backend built against API `version` can be used by
+/// this build.
+///
+/// Versions must match exactly: any trait change reorders vtables, so a
+/// backend built against version 2, before [`FsWrite`](crate::FsWrite)
+/// gained `set_times`, is rejected by this build.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{compatible_with, ANYFS_API_VERSION};
+///
+/// assert!(compatible_with(ANYFS_API_VERSION));
+/// assert!(!compatible_with(2));
+/// assert!(!compatible_with(ANYFS_API_VERSION + 1));
+/// ```
+pub fn compatible_with(version: u32) -> bool {
+    version == ANYFS_API_VERSION
+}
+
+/// A backend that recorded the API version it was built against, through
+/// [`declare_backend!`](crate::declare_backend).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{declare_backend, DeclaredBackend, ANYFS_API_VERSION};
+///
+/// struct Sqlite;
+/// declare_backend!(Sqlite);
+///
+/// assert_eq!(Sqlite::NAME, "Sqlite");
+/// assert_eq!(Sqlite::API_VERSION, ANYFS_API_VERSION);
+/// assert!(Sqlite::check_api_version().is_ok());
+/// ```
+pub trait DeclaredBackend {
+    /// Name of the backend type.
+    const NAME: &'static str;
+    /// [`ANYFS_API_VERSION`] of the build the backend was compiled in.
+    const API_VERSION: u32;
+
+    /// Fail unless this build can use the backend.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Backend`] naming both versions if they are not
+    ///   [`compatible_with`] each other
+    fn check_api_version() -> Result<(), FsError> {
+        if compatible_with(Self::API_VERSION) {
+            Ok(())
+        } else {
+            Err(FsError::Backend(format!(
+                "{} was built against anyfs API version {}, this build has version {}",
+                Self::NAME,
+                Self::API_VERSION,
+                ANYFS_API_VERSION
+            )))
+        }
+    }
+}
+
+/// Record the [`ANYFS_API_VERSION`] a backend is built against.
+///
+/// `declare_backend!(T)` implements [`DeclaredBackend`] for `T`.
+/// `declare_backend!(T, export)` also exports the version as an unmangled
+/// `u32` static named [`BACKEND_API_VERSION_SYMBOL`], for hosts loading
+/// the backend from a shared library; use it once per library.
+///
+/// [`DeclaredBackend`]: crate::DeclaredBackend
+/// [`ANYFS_API_VERSION`]: crate::ANYFS_API_VERSION
+/// [`BACKEND_API_VERSION_SYMBOL`]: crate::BACKEND_API_VERSION_SYMBOL
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{compatible_with, declare_backend};
+///
+/// pub struct S3Backend;
+/// declare_backend!(S3Backend, export);
+///
+/// // What a host reads from the loaded library
+/// assert!(compatible_with(ANYFS_BACKEND_API_VERSION));
+/// ```
+#[macro_export]
+macro_rules! declare_backend {
+    ($ty:ty) => {
+        impl $crate::DeclaredBackend for $ty {
+            const NAME: &'static str = ::std::stringify!($ty);
+            const API_VERSION: u32 = $crate::ANYFS_API_VERSION;
+        }
+    };
+    ($ty:ty, export) => {
+        $crate::declare_backend!($ty);
+
+        #[no_mangle]
+        pub static ANYFS_BACKEND_API_VERSION: u32 = $crate::ANYFS_API_VERSION;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Old;
+
+    impl DeclaredBackend for Old {
+        const NAME: &'static str = "Old";
+        const API_VERSION: u32 = 0;
+    }
+
+    #[test]
+    fn mismatched_versions_fail_loudly() {
+        let err = Old::check_api_version().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Old was built against anyfs API version 0"));
+    }
+
+    #[test]
+    fn only_the_current_version_is_compatible() {
+        assert_eq!(ANYFS_API_VERSION, 3);
+        assert!(compatible_with(3));
+        assert!(!compatible_with(1));
+        assert!(!compatible_with(2));
+        assert!(!compatible_with(4));
+    }
+
+    #[test]
+    fn exported_symbol_matches_its_name() {
+        struct Plugin;
+        declare_backend!(Plugin, export);
+        assert_eq!(BACKEND_API_VERSION_SYMBOL, "ANYFS_BACKEND_API_VERSION");
+        assert_eq!(ANYFS_BACKEND_API_VERSION, ANYFS_API_VERSION);
+        assert!(Plugin::check_api_version().is_ok());
+    }
+}
@@ -0,0 +1,605 @@
+//! # Async Stream Adapters
+//!
+//! Backends stream through blocking [`Read`] and [`Write`] objects
+//! ([`FsRead::open_read`](crate::FsRead::open_read),
+//! [`FsWrite::open_write`](crate::FsWrite::open_write)). Polling them from
+//! an async task would block the executor. [`AsyncReader`] and
+//! [`AsyncWriter`] move the blocking calls to a helper thread and expose
+//! [`futures_io::AsyncRead`] and [`futures_io::AsyncWrite`] instead
+//! (requires the `futures-io` feature).
+//!
+//! ## Backpressure
+//!
+//! Each adapter owns a fixed pool of [`AsyncStreamPolicy::buffers`]
+//! buffers that travel between the task and the helper thread and are
+//! reused, never reallocated. A reader's thread stops reading when every
+//! buffer is waiting to be consumed; a writer's task gets `Pending` when
+//! every buffer is waiting to be written. Memory per stream is bounded by
+//! `buffers × buffer_size`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{AsyncReader, AsyncStreamPolicy, Fs, FsError};
+//! use std::path::Path;
+//!
+//! fn body<B: Fs>(backend: &B, path: &Path) -> Result<AsyncReader, FsError> {
+//!     let reader = backend.open_read(path)?;
+//!     Ok(AsyncReader::new(reader, AsyncStreamPolicy::default()))
+//! }
+//! ```
+
+use std::io::{self, Read, Write};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+/// Buffer pool settings for [`AsyncReader`] and [`AsyncWriter`].
+///
+/// The default is 4 buffers of 64 KiB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsyncStreamPolicy {
+    /// Bytes per buffer (at least 1).
+    pub buffer_size: usize,
+    /// Buffers in flight between the task and the helper thread
+    /// (at least 1).
+    pub buffers: usize,
+}
+
+impl Default for AsyncStreamPolicy {
+    fn default() -> Self {
+        Self {
+            buffer_size: 64 << 10,
+            buffers: 4,
+        }
+    }
+}
+
+impl AsyncStreamPolicy {
+    /// Set the buffer size (builder style).
+    pub fn with_buffer_size(mut self, bytes: usize) -> Self {
+        self.buffer_size = bytes;
+        self
+    }
+
+    /// Set the number of buffers (builder style).
+    pub fn with_buffers(mut self, count: usize) -> Self {
+        self.buffers = count;
+        self
+    }
+
+    fn normalized(self) -> Self {
+        Self {
+            buffer_size: self.buffer_size.max(1),
+            buffers: self.buffers.max(1),
+        }
+    }
+}
+
+/// Where the helper thread leaves the waker of the task waiting on it.
+#[derive(Default)]
+struct WakeSlot(Mutex<Option<Waker>>);
+
+impl WakeSlot {
+    fn register(&self, waker: &Waker) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(waker.clone());
+    }
+
+    fn wake(&self) {
+        let waker = self.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Receive from `rx` without blocking, registering the task's waker
+/// first when nothing is there yet.
+fn poll_recv<T>(rx: &Receiver<T>, slot: &WakeSlot, cx: &Context<'_>) -> Poll<Option<T>> {
+    match rx.try_recv() {
+        Ok(item) => return Poll::Ready(Some(item)),
+        Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+        Err(TryRecvError::Empty) => {}
+    }
+    slot.register(cx.waker());
+    // The thread may have sent between the first check and registering
+    match rx.try_recv() {
+        Ok(item) => Poll::Ready(Some(item)),
+        Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        Err(TryRecvError::Empty) => Poll::Pending,
+    }
+}
+
+fn helper_gone() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "stream helper thread stopped")
+}
+
+/// [`AsyncRead`] over a blocking reader, read on a helper thread.
+///
+/// The thread reads ahead into free buffers and waits when none is left.
+/// Dropping the adapter stops the thread after its current read.
+pub struct AsyncReader {
+    filled: Receiver<io::Result<Vec<u8>>>,
+    free: Sender<Vec<u8>>,
+    slot: Arc<WakeSlot>,
+    /// Buffer being consumed, and how much of it has been.
+    current: Option<(Vec<u8>, usize)>,
+    done: bool,
+}
+
+impl AsyncReader {
+    /// Read `reader` on a new helper thread.
+    pub fn new(reader: Box<dyn Read + Send>, policy: AsyncStreamPolicy) -> Self {
+        let policy = policy.normalized();
+        let (filled_tx, filled) = mpsc::channel();
+        let (free, free_rx) = mpsc::channel();
+        for _ in 0..policy.buffers {
+            // Cannot fail: the receiver is alive
+            let _ = free.send(Vec::with_capacity(policy.buffer_size));
+        }
+        let slot = Arc::new(WakeSlot::default());
+        let thread_slot = Arc::clone(&slot);
+        std::thread::spawn(move || {
+            read_ahead(
+                reader,
+                policy.buffer_size,
+                &free_rx,
+                &filled_tx,
+                &thread_slot,
+            );
+            thread_slot.wake();
+        });
+        Self {
+            filled,
+            free,
+            slot,
+            current: None,
+            done: false,
+        }
+    }
+}
+
+/// Helper thread body: fill free buffers until EOF, an error, or the
+/// adapter is dropped. An empty buffer marks EOF.
+fn read_ahead(
+    mut reader: Box<dyn Read + Send>,
+    buffer_size: usize,
+    free: &Receiver<Vec<u8>>,
+    filled: &Sender<io::Result<Vec<u8>>>,
+    slot: &WakeSlot,
+) {
+    while let Ok(mut buf) = free.recv() {
+        buf.resize(buffer_size, 0);
+        let result = loop {
+            match reader.read(&mut buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                other => break other,
+            }
+        };
+        let last = !matches!(result, Ok(n) if n > 0);
+        let message = result.map(|n| {
+            buf.truncate(n);
+            buf
+        });
+        if filled.send(message).is_err() {
+            return;
+        }
+        slot.wake();
+        if last {
+            return;
+        }
+    }
+}
+
+impl AsyncRead for AsyncReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if out.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            if let Some((buf, pos)) = &mut this.current {
+                let n = out.len().min(buf.len() - *pos);
+                out[..n].copy_from_slice(&buf[*pos..*pos + n]);
+                *pos += n;
+                if *pos == buf.len() {
+                    if let Some((mut buf, _)) = this.current.take() {
+                        buf.clear();
+                        // The thread may have stopped; the buffer is then dropped
+                        let _ = this.free.send(buf);
+                    }
+                }
+                return Poll::Ready(Ok(n));
+            }
+            if this.done {
+                return Poll::Ready(Ok(0));
+            }
+            match poll_recv(&this.filled, &this.slot, cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(Err(helper_gone())),
+                Poll::Ready(Some(Err(e))) => {
+                    this.done = true;
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Ready(Some(Ok(buf))) if buf.is_empty() => this.done = true,
+                Poll::Ready(Some(Ok(buf))) => this.current = Some((buf, 0)),
+            }
+        }
+    }
+}
+
+/// Work sent to an [`AsyncWriter`]'s helper thread.
+enum WriteCmd {
+    Data(Vec<u8>),
+    Flush,
+    Close,
+}
+
+/// Replies from an [`AsyncWriter`]'s helper thread.
+enum WriteReply {
+    /// A buffer was written and can be reused.
+    Free(Vec<u8>),
+    Flushed(io::Result<()>),
+    Closed,
+    /// Writing failed; the thread has stopped.
+    Failed(io::Error),
+}
+
+/// [`AsyncWrite`] over a blocking writer, written on a helper thread.
+///
+/// Writes fill a pooled buffer; full buffers go to the thread, and
+/// `poll_write` returns `Pending` while every buffer is in flight.
+/// `poll_flush` hands over the partial buffer and flushes the writer;
+/// `poll_close` also drops it on the helper thread, so backends that commit
+/// on drop have committed when it returns. Write errors surface from the
+/// next poll.
+///
+/// Dropping the adapter without closing it drops the writer on the helper
+/// thread once the buffers already handed over are written; the partial
+/// buffer is lost.
+pub struct AsyncWriter {
+    work: Option<Sender<WriteCmd>>,
+    replies: Receiver<WriteReply>,
+    slot: Arc<WakeSlot>,
+    /// Buffers back from the thread, ready for reuse.
+    free: Vec<Vec<u8>>,
+    /// Buffer being filled.
+    current: Option<Vec<u8>>,
+    buffer_size: usize,
+    flushing: bool,
+    /// Answer to the flush in progress, once the thread sent it.
+    flushed: Option<io::Result<()>>,
+    closing: bool,
+    closed: bool,
+    failed: Option<io::ErrorKind>,
+}
+
+impl AsyncWriter {
+    /// Write to `writer` on a new helper thread.
+    pub fn new(writer: Box<dyn Write + Send>, policy: AsyncStreamPolicy) -> Self {
+        let policy = policy.normalized();
+        let (work, work_rx) = mpsc::channel();
+        let (reply_tx, replies) = mpsc::channel();
+        let slot = Arc::new(WakeSlot::default());
+        let thread_slot = Arc::clone(&slot);
+        std::thread::spawn(move || {
+            write_behind(writer, &work_rx, &reply_tx, &thread_slot);
+            thread_slot.wake();
+        });
+        Self {
+            work: Some(work),
+            replies,
+            slot,
+            free: (0..policy.buffers)
+                .map(|_| Vec::with_capacity(policy.buffer_size))
+                .collect(),
+            current: None,
+            buffer_size: policy.buffer_size,
+            flushing: false,
+            flushed: None,
+            closing: false,
+            closed: false,
+            failed: None,
+        }
+    }
+
+    fn send(&mut self, cmd: WriteCmd) -> io::Result<()> {
+        let sent = self.work.as_ref().map(|work| work.send(cmd));
+        match sent {
+            Some(Ok(())) => Ok(()),
+            _ => Err(self.fail(helper_gone())),
+        }
+    }
+
+    /// Record a failure; later calls report its kind.
+    fn fail(&mut self, e: io::Error) -> io::Error {
+        self.failed = Some(e.kind());
+        self.work = None;
+        e
+    }
+
+    fn check(&self) -> io::Result<()> {
+        match self.failed {
+            Some(kind) => Err(io::Error::new(kind, "an earlier write failed")),
+            None if self.closed || self.closing => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "writer is closed",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Hand the partly filled buffer, if any, to the thread.
+    fn submit_partial(&mut self) -> io::Result<()> {
+        match self.current.take() {
+            Some(buf) if !buf.is_empty() => self.send(WriteCmd::Data(buf)),
+            other => {
+                self.current = other;
+                Ok(())
+            }
+        }
+    }
+
+    /// Process one reply from the thread, or register for the next.
+    fn poll_reply(&mut self, cx: &Context<'_>) -> Poll<io::Result<()>> {
+        let reply = match poll_recv(&self.replies, &self.slot, cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(None) => return Poll::Ready(Err(self.fail(helper_gone()))),
+            Poll::Ready(Some(reply)) => reply,
+        };
+        match reply {
+            WriteReply::Free(buf) => self.free.push(buf),
+            WriteReply::Flushed(result) => self.flushed = Some(result),
+            WriteReply::Closed => {
+                self.closed = true;
+                self.work = None;
+            }
+            WriteReply::Failed(e) => return Poll::Ready(Err(self.fail(e))),
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Helper thread body: write buffers and answer flushes until closed,
+/// failed, or the adapter is dropped.
+fn write_behind(
+    mut writer: Box<dyn Write + Send>,
+    work: &Receiver<WriteCmd>,
+    replies: &Sender<WriteReply>,
+    slot: &WakeSlot,
+) {
+    while let Ok(cmd) = work.recv() {
+        let reply = match cmd {
+            WriteCmd::Data(mut buf) => match writer.write_all(&buf) {
+                Ok(()) => {
+                    buf.clear();
+                    WriteReply::Free(buf)
+                }
+                Err(e) => WriteReply::Failed(e),
+            },
+            WriteCmd::Flush => WriteReply::Flushed(writer.flush()),
+            WriteCmd::Close => {
+                drop(writer);
+                let _ = replies.send(WriteReply::Closed);
+                return;
+            }
+        };
+        let stop = matches!(reply, WriteReply::Failed(_));
+        if replies.send(reply).is_err() || stop {
+            return;
+        }
+        slot.wake();
+    }
+}
+
+impl AsyncWrite for AsyncWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.check()?;
+        if data.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let mut buf = match this.current.take() {
+            Some(buf) => buf,
+            None => loop {
+                if let Some(buf) = this.free.pop() {
+                    break buf;
+                }
+                if this.poll_reply(cx)?.is_pending() {
+                    return Poll::Pending;
+                }
+            },
+        };
+        let n = data.len().min(this.buffer_size - buf.len());
+        buf.extend_from_slice(&data[..n]);
+        if buf.len() == this.buffer_size {
+            this.send(WriteCmd::Data(buf))?;
+        } else {
+            this.current = Some(buf);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        if let Some(kind) = this.failed {
+            return Poll::Ready(Err(io::Error::new(kind, "an earlier write failed")));
+        }
+        if !this.flushing {
+            this.submit_partial()?;
+            this.send(WriteCmd::Flush)?;
+            this.flushing = true;
+        }
+        loop {
+            if let Some(result) = this.flushed.take() {
+                this.flushing = false;
+                return Poll::Ready(result);
+            }
+            if this.poll_reply(cx)?.is_pending() {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !self.closing {
+            match self.as_mut().poll_flush(cx) {
+                Poll::Ready(Ok(())) if !self.closed => {}
+                other => return other,
+            }
+            self.send(WriteCmd::Close)?;
+            self.closing = true;
+        }
+        let this = self.get_mut();
+        while !this.closed {
+            if this.poll_reply(cx)?.is_pending() {
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor: poll, park until woken, repeat.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            std::thread::park();
+        }
+    }
+
+    fn read_all(mut reader: AsyncReader, chunk: usize) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut buf = vec![0; chunk];
+        loop {
+            let n = block_on(std::future::poll_fn(|cx| {
+                Pin::new(&mut reader).poll_read(cx, &mut buf)
+            }))?;
+            if n == 0 {
+                return Ok(out);
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    fn write_all(writer: &mut AsyncWriter, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let n = block_on(std::future::poll_fn(|cx| {
+                Pin::new(&mut *writer).poll_write(cx, data)
+            }))?;
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    /// Records everything written, and whether it was dropped.
+    struct Sink {
+        data: Arc<Mutex<Vec<u8>>>,
+        dropped: Arc<AtomicUsize>,
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for Sink {
+        fn drop(&mut self) {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn reader_streams_through_small_pool() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let policy = AsyncStreamPolicy::default()
+            .with_buffer_size(7)
+            .with_buffers(2);
+        let reader = AsyncReader::new(Box::new(io::Cursor::new(data.clone())), policy);
+        assert_eq!(read_all(reader, 5).unwrap(), data);
+    }
+
+    #[test]
+    fn reader_reports_errors() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone"))
+            }
+        }
+        let reader = AsyncReader::new(Box::new(Broken), AsyncStreamPolicy::default());
+        let err = read_all(reader, 16).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    #[test]
+    fn writer_delivers_everything_and_drops_on_close() {
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let sink = Sink {
+            data: Arc::clone(&data),
+            dropped: Arc::clone(&dropped),
+        };
+        let policy = AsyncStreamPolicy::default()
+            .with_buffer_size(3)
+            .with_buffers(2);
+        let mut writer = AsyncWriter::new(Box::new(sink), policy);
+
+        write_all(&mut writer, b"hello, ").unwrap();
+        block_on(std::future::poll_fn(|cx| {
+            Pin::new(&mut writer).poll_flush(cx)
+        }))
+        .unwrap();
+        assert_eq!(*data.lock().unwrap(), b"hello, ");
+
+        write_all(&mut writer, b"world").unwrap();
+        block_on(std::future::poll_fn(|cx| {
+            Pin::new(&mut writer).poll_close(cx)
+        }))
+        .unwrap();
+        assert_eq!(*data.lock().unwrap(), b"hello, world");
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert!(write_all(&mut writer, b"!").is_err());
+    }
+}
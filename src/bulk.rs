@@ -0,0 +1,148 @@
+//! # Bulk Operations
+//!
+//! [`run`] executes many independent [`FsOp`]s against one backend with a
+//! fixed number of worker threads, so a tool syncing thousands of files
+//! doesn't open thousands of connections to a remote backend at once.
+//!
+//! Each op gets its own result, in the order the ops were given; one
+//! failure doesn't stop the others.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{bulk, Fs, FsError, FsOp};
+//! use std::path::PathBuf;
+//!
+//! fn delete_all<B: Fs>(backend: &B, paths: Vec<PathBuf>) -> Vec<(PathBuf, FsError)> {
+//!     let ops = paths.iter().map(|path| FsOp::RemoveFile { path: path.clone() });
+//!     bulk::run(backend, ops, 8)
+//!         .into_iter()
+//!         .zip(paths)
+//!         .filter_map(|(result, path)| result.err().map(|e| (path, e)))
+//!         .collect()
+//! }
+//! ```
+
+use std::sync::Mutex;
+
+use crate::dispatch::execute;
+use crate::{Fs, FsError, FsOp, FsOpResult};
+
+/// Perform every op in `ops` on `fs`, at most `max_concurrency` at a time.
+///
+/// Returns one result per op, in the same order as `ops`. Each op calls
+/// the matching method on `fs`, as [`Dispatcher`](crate::Dispatcher) does.
+///
+/// With `max_concurrency` of 0 or 1 the ops run one after another on the
+/// calling thread. Otherwise up to `max_concurrency` scoped threads pull
+/// ops from a shared queue; all of them have finished when `run` returns.
+///
+/// # Panics
+///
+/// If an op panics, the panic is resumed on the calling thread once the
+/// other workers have stopped.
+pub fn run<B, I>(fs: &B, ops: I, max_concurrency: usize) -> Vec<Result<FsOpResult, FsError>>
+where
+    B: Fs + ?Sized,
+    I: IntoIterator<Item = FsOp>,
+{
+    let ops: Vec<FsOp> = ops.into_iter().collect();
+    let workers = max_concurrency.min(ops.len());
+    if workers <= 1 {
+        return ops.into_iter().map(|op| execute(fs, op)).collect();
+    }
+
+    let len = ops.len();
+    let queue = Mutex::new(ops.into_iter().enumerate());
+    let next = || queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+    let mut done: Vec<(usize, Result<FsOpResult, FsError>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while let Some((index, op)) = next() {
+                        done.push((index, execute(fs, op)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    debug_assert_eq!(done.len(), len);
+    done.sort_unstable_by_key(|(index, _)| *index);
+    done.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsBuilder;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn reads(count: usize) -> Vec<FsOp> {
+        (0..count)
+            .map(|i| FsOp::Read {
+                path: PathBuf::from(format!("/{i}")),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn results_keep_op_order_and_errors_stay_per_op() {
+        let fs = FsBuilder::new()
+            .on_read(|path| {
+                if path == Path::new("/3") {
+                    return Err(FsError::not_found("read", path));
+                }
+                Ok(path.to_string_lossy().into_owned().into_bytes())
+            })
+            .build();
+
+        let results = run(&fs, reads(10), 4);
+        assert_eq!(results.len(), 10);
+        for (i, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(FsOpResult::Bytes(data)) => assert_eq!(data, format!("/{i}").as_bytes()),
+                Err(FsError::NotFound { .. }) => assert_eq!(i, 3),
+                other => panic!("unexpected result for op {i}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn concurrency_never_exceeds_limit() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let fs = FsBuilder::new()
+            .on_read({
+                let (active, peak) = (Arc::clone(&active), Arc::clone(&peak));
+                move |_| {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(5));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    Ok(Vec::new())
+                }
+            })
+            .build();
+
+        let results = run(&fs, reads(24), 3);
+        assert!(results.iter().all(Result::is_ok));
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+
+        peak.store(0, Ordering::SeqCst);
+        run(&fs, reads(4), 0);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+}
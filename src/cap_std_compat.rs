@@ -0,0 +1,547 @@
+//! # cap-std Interop
+//!
+//! Capability-based sandboxing in both directions (requires the `cap-std`
+//! feature).
+//!
+//! | Type | Direction |
+//! |------|-----------|
+//! | [`CapStdFs`] | A [`cap_std::fs::Dir`] as an AnyFS [`Fs`]: every path, symlinks included, stays inside the directory |
+//! | [`CapDir`] | Any [`Fs`] as a capability-style directory handle: relative paths only, no way above the handle |
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{CapStdFs, Fs, FsError};
+//! use cap_std::{ambient_authority, fs::Dir};
+//!
+//! fn sandbox(root: &std::path::Path) -> Result<impl Fs, FsError> {
+//!     let dir = Dir::open_ambient_dir(root, ambient_authority())
+//!         .map_err(|e| FsError::io("open", root, e))?;
+//!     Ok(CapStdFs::new(dir))
+//! }
+//! ```
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use cap_std::fs::{Dir, OpenOptions};
+
+use crate::path_resolver::normalize;
+use crate::{DirEntry, FileType, Fs, FsDir, FsError, FsRead, FsWrite, Metadata, ReadDirIter};
+
+// =============================================================================
+// cap-std → AnyFS
+// =============================================================================
+
+/// A [`cap_std::fs::Dir`] as an AnyFS backend.
+///
+/// `/` is the directory itself. Paths are normalized lexically first, so
+/// `..` stops at `/`; cap-std then refuses any symlink that would lead
+/// outside the directory. Nothing outside the directory can be reached.
+///
+/// Metadata reports type, size and timestamps; permissions and inodes are
+/// left at their defaults.
+#[derive(Debug)]
+pub struct CapStdFs {
+    dir: Dir,
+}
+
+impl CapStdFs {
+    /// Serve the contents of `dir`.
+    pub fn new(dir: Dir) -> Self {
+        Self { dir }
+    }
+
+    /// The wrapped directory.
+    pub fn dir(&self) -> &Dir {
+        &self.dir
+    }
+
+    /// Unwrap the directory.
+    pub fn into_dir(self) -> Dir {
+        self.dir
+    }
+}
+
+/// Path relative to the directory, `.` for `/`.
+fn relative(path: &Path) -> PathBuf {
+    let normalized = normalize(path);
+    let rest: PathBuf = normalized
+        .components()
+        .filter(|c| !matches!(c, Component::RootDir | Component::Prefix(_)))
+        .collect();
+    if rest.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        rest
+    }
+}
+
+fn io_error(operation: &'static str, path: &Path, error: io::Error) -> FsError {
+    match error.kind() {
+        io::ErrorKind::NotFound => FsError::not_found(operation, path),
+        io::ErrorKind::AlreadyExists => FsError::already_exists(operation, path),
+        io::ErrorKind::PermissionDenied => FsError::permission_denied(operation, path),
+        _ => FsError::io(operation, path, error),
+    }
+}
+
+fn convert_metadata(meta: &cap_std::fs::Metadata) -> Metadata {
+    let time = |t: io::Result<cap_std::time::SystemTime>| {
+        t.map(|t| t.into_std()).unwrap_or(std::time::UNIX_EPOCH)
+    };
+    let file_type = meta.file_type();
+    Metadata {
+        file_type: if file_type.is_dir() {
+            FileType::Directory
+        } else if file_type.is_symlink() {
+            FileType::Symlink
+        } else {
+            FileType::File
+        },
+        size: meta.len(),
+        created: time(meta.created()),
+        modified: time(meta.modified()),
+        accessed: time(meta.accessed()),
+        ..Metadata::default()
+    }
+}
+
+impl CapStdFs {
+    fn call<T>(
+        &self,
+        operation: &'static str,
+        path: &Path,
+        f: impl FnOnce(&Dir, &Path) -> io::Result<T>,
+    ) -> Result<T, FsError> {
+        f(&self.dir, &relative(path)).map_err(|e| io_error(operation, path, e))
+    }
+}
+
+impl FsRead for CapStdFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.call("read", path, |dir, p| dir.read(p))
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        let data = self.call("read_to_string", path, |dir, p| dir.read(p))?;
+        String::from_utf8(data).map_err(|e| FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: e.to_string(),
+        })
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        self.call("read_range", path, |dir, p| {
+            let mut file = dir.open(p)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut data = Vec::new();
+            file.take(len as u64).read_to_end(&mut data)?;
+            Ok(data)
+        })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.call("exists", path, |dir, p| dir.try_exists(p))
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let meta = self.call("metadata", path, |dir, p| dir.metadata(p))?;
+        Ok(convert_metadata(&meta))
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        let file = self.call("open_read", path, |dir, p| dir.open(p))?;
+        Ok(Box::new(file))
+    }
+}
+
+impl FsWrite for CapStdFs {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.call("write", path, |dir, p| dir.write(p, data))
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.call("append", path, |dir, p| {
+            dir.open_with(p, OpenOptions::new().append(true).create(true))?
+                .write_all(data)
+        })
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.call("remove_file", path, |dir, p| dir.remove_file(p))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let to = relative(to);
+        self.call("rename", from, |dir, p| dir.rename(p, dir, &to))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let to = relative(to);
+        self.call("copy", from, |dir, p| dir.copy(p, dir, &to).map(|_| ()))
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.call("truncate", path, |dir, p| {
+            dir.open_with(p, OpenOptions::new().write(true))?
+                .set_len(size)
+        })
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        let file = self.call("open_write", path, |dir, p| dir.create(p))?;
+        Ok(Box::new(file))
+    }
+}
+
+impl FsDir for CapStdFs {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        let base = normalize(path);
+        let entries = self.call("read_dir", path, |dir, p| dir.read_dir(p))?;
+        let entries = entries
+            .map(|entry| {
+                let entry = entry.map_err(|e| io_error("read_dir", &base, e))?;
+                let name = entry.file_name();
+                let child = base.join(&name);
+                let meta = entry
+                    .metadata()
+                    .map_err(|e| io_error("read_dir", &child, e))?;
+                let meta = convert_metadata(&meta);
+                Ok(DirEntry {
+                    path: child,
+                    file_type: meta.file_type,
+                    size: meta.size,
+                    ..DirEntry::default()
+                }
+                .with_name_os(name))
+            })
+            .collect();
+        Ok(ReadDirIter::from_vec(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.call("create_dir", path, |dir, p| dir.create_dir(p))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.call("create_dir_all", path, |dir, p| dir.create_dir_all(p))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.call("remove_dir", path, |dir, p| dir.remove_dir(p))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.call("remove_dir_all", path, |dir, p| dir.remove_dir_all(p))
+    }
+}
+
+// =============================================================================
+// AnyFS → capability handle
+// =============================================================================
+
+/// A directory of an AnyFS backend, used the way cap-std uses
+/// [`cap_std::fs::Dir`].
+///
+/// Holding a `CapDir` grants access to its subtree and nothing else:
+/// methods take paths relative to the handle, absolute paths are refused,
+/// and `..` may not climb above the handle. Hand out
+/// [`open_dir`](Self::open_dir) results to give code a narrower view.
+///
+/// Confinement is lexical. The backend still follows symlinks while
+/// resolving a path, so a link inside the subtree can reach outside it
+/// unless the backend has no symlinks or resolves inside a root of its own
+/// (see [`ResolvePolicy`](crate::ResolvePolicy)).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CapDir, Fs, FsError};
+/// use std::path::Path;
+///
+/// fn plugin_data<B: Fs>(backend: B) -> Result<CapDir<B>, FsError> {
+///     let root = CapDir::new(backend);
+///     let data = root.open_dir(Path::new("plugins/example"))?;
+///
+///     // The plugin can't name anything outside its directory
+///     assert!(data.read(Path::new("../other/secret")).is_err());
+///     assert!(data.read(Path::new("/etc/passwd")).is_err());
+///     Ok(data)
+/// }
+/// ```
+pub struct CapDir<B> {
+    backend: Arc<B>,
+    base: PathBuf,
+}
+
+impl<B> Clone for CapDir<B> {
+    fn clone(&self) -> Self {
+        Self {
+            backend: Arc::clone(&self.backend),
+            base: self.base.clone(),
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for CapDir<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapDir")
+            .field("base", &self.base)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: Fs> CapDir<B> {
+    /// A handle to the backend's root, `/`.
+    pub fn new(backend: B) -> Self {
+        Self::from_arc(Arc::new(backend))
+    }
+
+    /// A handle to the root of a shared backend.
+    pub fn from_arc(backend: Arc<B>) -> Self {
+        Self {
+            backend,
+            base: PathBuf::from("/"),
+        }
+    }
+
+    /// Where this handle points in the backend.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// Backend path for `path`, if it stays inside the handle.
+    fn resolve(&self, path: &Path) -> Result<PathBuf, FsError> {
+        let denied = |reason: &str| FsError::AccessDenied {
+            path: path.to_path_buf(),
+            reason: reason.into(),
+        };
+        let mut resolved = self.base.clone();
+        let mut depth = 0usize;
+        for component in path.components() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => {
+                    return Err(denied("absolute path given to a directory handle"))
+                }
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if depth == 0 {
+                        return Err(denied("path escapes the directory handle"));
+                    }
+                    resolved.pop();
+                    depth -= 1;
+                }
+                Component::Normal(name) => {
+                    resolved.push(name);
+                    depth += 1;
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// A handle to the subdirectory `path`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::AccessDenied`] if `path` is absolute or escapes
+    /// - [`FsError::NotADirectory`] if `path` isn't a directory
+    /// - Errors from [`FsRead::metadata`]
+    pub fn open_dir(&self, path: &Path) -> Result<Self, FsError> {
+        let base = self.resolve(path)?;
+        if self.backend.metadata(&base)?.file_type != FileType::Directory {
+            return Err(FsError::not_a_directory("open_dir", base));
+        }
+        Ok(Self {
+            backend: Arc::clone(&self.backend),
+            base,
+        })
+    }
+
+    /// [`FsRead::read`] inside the handle.
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.backend.read(&self.resolve(path)?)
+    }
+
+    /// [`FsRead::read_to_string`] inside the handle.
+    pub fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        self.backend.read_to_string(&self.resolve(path)?)
+    }
+
+    /// [`FsRead::exists`] inside the handle.
+    pub fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.backend.exists(&self.resolve(path)?)
+    }
+
+    /// [`FsRead::metadata`] inside the handle.
+    pub fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.backend.metadata(&self.resolve(path)?)
+    }
+
+    /// [`FsRead::open_read`] inside the handle.
+    pub fn open(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        self.backend.open_read(&self.resolve(path)?)
+    }
+
+    /// [`FsWrite::write`] inside the handle.
+    pub fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.backend.write(&self.resolve(path)?, data)
+    }
+
+    /// [`FsWrite::open_write`] inside the handle.
+    pub fn create(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.backend.open_write(&self.resolve(path)?)
+    }
+
+    /// [`FsWrite::remove_file`] inside the handle.
+    pub fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.backend.remove_file(&self.resolve(path)?)
+    }
+
+    /// [`FsWrite::rename`] from this handle to `to_dir`, which must share
+    /// the backend.
+    pub fn rename(&self, from: &Path, to_dir: &Self, to: &Path) -> Result<(), FsError> {
+        self.backend
+            .rename(&self.resolve(from)?, &to_dir.resolve(to)?)
+    }
+
+    /// [`FsWrite::copy`] from this handle to `to_dir`, which must share the
+    /// backend.
+    pub fn copy(&self, from: &Path, to_dir: &Self, to: &Path) -> Result<(), FsError> {
+        self.backend
+            .copy(&self.resolve(from)?, &to_dir.resolve(to)?)
+    }
+
+    /// [`FsDir::read_dir`] inside the handle. Entry paths are relative to
+    /// the handle.
+    pub fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        let base = self.base.clone();
+        let entries = self.backend.read_dir(&self.resolve(path)?)?;
+        Ok(ReadDirIter::new(entries.map(move |entry| {
+            entry.map(|mut entry| {
+                if let Ok(rest) = entry.path.strip_prefix(&base) {
+                    entry.path = rest.to_path_buf();
+                }
+                entry
+            })
+        })))
+    }
+
+    /// [`FsDir::create_dir`] inside the handle.
+    pub fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.backend.create_dir(&self.resolve(path)?)
+    }
+
+    /// [`FsDir::create_dir_all`] inside the handle.
+    pub fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.backend.create_dir_all(&self.resolve(path)?)
+    }
+
+    /// [`FsDir::remove_dir`] inside the handle.
+    pub fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.backend.remove_dir(&self.resolve(path)?)
+    }
+
+    /// [`FsDir::remove_dir_all`] inside the handle.
+    pub fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.backend.remove_dir_all(&self.resolve(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsBuilder;
+    use cap_std::ambient_authority;
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("anyfs-cap-std-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn cap_std_dir_works_as_backend() {
+        let temp = TempDir::new("backend");
+        let fs = CapStdFs::new(Dir::open_ambient_dir(&temp.0, ambient_authority()).unwrap());
+
+        fs.create_dir_all(Path::new("/a/b")).unwrap();
+        fs.write(Path::new("/a/b/f.txt"), b"hello").unwrap();
+        fs.append(Path::new("/a/b/f.txt"), b" world").unwrap();
+        assert_eq!(
+            fs.read_range(Path::new("/a/b/f.txt"), 6, 5).unwrap(),
+            b"world"
+        );
+        assert_eq!(fs.metadata(Path::new("/a/b/f.txt")).unwrap().size, 11);
+
+        fs.rename(Path::new("/a/b/f.txt"), Path::new("/g.txt"))
+            .unwrap();
+        fs.truncate(Path::new("/g.txt"), 5).unwrap();
+        assert_eq!(std::fs::read(temp.0.join("g.txt")).unwrap(), b"hello");
+
+        let names: Vec<String> = fs
+            .read_dir(Path::new("/a"))
+            .unwrap()
+            .map(|e| e.unwrap().name)
+            .collect();
+        assert_eq!(names, ["b"]);
+
+        // `..` stops at the directory root
+        assert_eq!(fs.read(Path::new("/../../g.txt")).unwrap(), b"hello");
+        assert!(matches!(
+            fs.read(Path::new("/missing")),
+            Err(FsError::NotFound { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_the_directory() {
+        let temp = TempDir::new("symlink");
+        std::os::unix::fs::symlink("/", temp.0.join("escape")).unwrap();
+        let fs = CapStdFs::new(Dir::open_ambient_dir(&temp.0, ambient_authority()).unwrap());
+        assert!(fs.read_dir(Path::new("/escape")).is_err());
+    }
+
+    #[test]
+    fn cap_dir_refuses_escapes() {
+        let backend = FsBuilder::new()
+            .on_read(|path| Ok(path.to_string_lossy().into_owned().into_bytes()))
+            .on_metadata(|_| {
+                Ok(Metadata {
+                    file_type: FileType::Directory,
+                    ..Metadata::default()
+                })
+            })
+            .build();
+        let root = CapDir::new(backend);
+        let jail = root.open_dir(Path::new("srv/app")).unwrap();
+        assert_eq!(jail.base(), Path::new("/srv/app"));
+
+        assert_eq!(
+            jail.read(Path::new("x/../y.txt")).unwrap(),
+            b"/srv/app/y.txt"
+        );
+        for path in ["../secret", "/etc/passwd", "a/../../b"] {
+            assert!(matches!(
+                jail.read(Path::new(path)),
+                Err(FsError::AccessDenied { .. })
+            ));
+        }
+    }
+}
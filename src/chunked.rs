@@ -0,0 +1,387 @@
+//! # Chunked Uploads
+//!
+//! [`ChunkedWriter`] turns a byte stream into fixed-size chunks and hands
+//! them to a [`ChunkSink`]: a multipart upload, or any [`FsHandles`]
+//! backend through [`HandleChunks`]. A [`ChunkPolicy`] sets the chunk size,
+//! how many chunks are uploaded at once, and whether each chunk carries a
+//! CRC-32.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{ChunkPolicy, ChunkedWriter, FsError, FsHandles, HandleChunks, OpenFlags};
+//! use std::io::Write;
+//! use std::path::Path;
+//!
+//! fn upload<B: FsHandles>(backend: &B, data: &[u8]) -> Result<u64, FsError> {
+//!     let handle = backend.open(Path::new("/big.bin"), OpenFlags::WRITE)?;
+//!     let policy = ChunkPolicy::default().with_chunk_size(1 << 20).with_parallelism(4);
+//!     let mut writer = ChunkedWriter::new(HandleChunks::new(backend, handle), policy);
+//!     writer.write_all(data)?;
+//!     let written = writer.finish();
+//!     backend.close(handle)?;
+//!     written
+//! }
+//! ```
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::{FsError, FsHandles, Handle};
+
+/// One chunk handed to a [`ChunkSink`].
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk<'a> {
+    /// Position of the chunk in the stream, from 0.
+    pub index: u64,
+    /// Byte offset of the chunk in the stream.
+    pub offset: u64,
+    /// The chunk's bytes. Every chunk but the last is exactly
+    /// [`ChunkPolicy::chunk_size`] long.
+    pub data: &'a [u8],
+    /// CRC-32 (IEEE) of `data`, if [`ChunkPolicy::checksums`] is on.
+    pub checksum: Option<u32>,
+}
+
+/// Destination of a [`ChunkedWriter`].
+///
+/// With [`ChunkPolicy::parallelism`] above 1, `put_chunk` is called from
+/// several threads at once, with chunks in any order.
+pub trait ChunkSink: Send + Sync {
+    /// Store one chunk.
+    fn put_chunk(&self, chunk: Chunk<'_>) -> Result<(), FsError>;
+
+    /// Called once after the last chunk is stored, with the number of chunks
+    /// and the total length. Multipart uploads commit here.
+    ///
+    /// The default does nothing.
+    fn complete(&self, chunks: u64, len: u64) -> Result<(), FsError> {
+        let _ = (chunks, len);
+        Ok(())
+    }
+}
+
+/// How a [`ChunkedWriter`] splits and uploads a stream.
+///
+/// The default uses 8 MiB chunks, one at a time, without checksums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkPolicy {
+    /// Bytes per chunk (at least 1).
+    pub chunk_size: usize,
+    /// Chunks uploaded at once (at least 1). Up to this many chunks are
+    /// buffered in memory.
+    pub parallelism: usize,
+    /// Whether to compute [`Chunk::checksum`].
+    pub checksums: bool,
+}
+
+impl Default for ChunkPolicy {
+    fn default() -> Self {
+        Self {
+            chunk_size: 8 << 20,
+            parallelism: 1,
+            checksums: false,
+        }
+    }
+}
+
+impl ChunkPolicy {
+    /// Set the chunk size (builder style).
+    pub fn with_chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes;
+        self
+    }
+
+    /// Set how many chunks are uploaded at once (builder style).
+    pub fn with_parallelism(mut self, chunks: usize) -> Self {
+        self.parallelism = chunks;
+        self
+    }
+
+    /// Turn per-chunk checksums on or off (builder style).
+    pub fn with_checksums(mut self, on: bool) -> Self {
+        self.checksums = on;
+        self
+    }
+}
+
+/// [`Write`] adapter that uploads a stream in chunks.
+///
+/// Full chunks are uploaded in batches of [`ChunkPolicy::parallelism`].
+/// [`flush`](Write::flush) does nothing, so every chunk but the last keeps
+/// the configured size; call [`finish`](Self::finish) to upload the rest.
+/// Dropping the writer without finishing discards buffered data.
+///
+/// After a chunk fails, every later call returns an error.
+#[derive(Debug)]
+pub struct ChunkedWriter<S: ChunkSink> {
+    sink: S,
+    policy: ChunkPolicy,
+    /// Full chunks waiting for a batch.
+    full: Vec<Vec<u8>>,
+    /// The chunk being filled.
+    current: Vec<u8>,
+    /// Index of the first chunk in `full`.
+    next_index: u64,
+    /// Bytes accepted so far.
+    len: u64,
+    failed: bool,
+}
+
+impl<S: ChunkSink> ChunkedWriter<S> {
+    /// Create a writer uploading to `sink`.
+    pub fn new(sink: S, policy: ChunkPolicy) -> Self {
+        let policy = ChunkPolicy {
+            chunk_size: policy.chunk_size.max(1),
+            parallelism: policy.parallelism.max(1),
+            ..policy
+        };
+        Self {
+            sink,
+            policy,
+            full: Vec::with_capacity(policy.parallelism),
+            current: Vec::new(),
+            next_index: 0,
+            len: 0,
+            failed: false,
+        }
+    }
+
+    /// The sink.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Upload the remaining data, then call [`ChunkSink::complete`].
+    ///
+    /// Returns the total number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from the sink
+    pub fn finish(mut self) -> Result<u64, FsError> {
+        self.check()?;
+        if !self.current.is_empty() {
+            let last = std::mem::take(&mut self.current);
+            self.full.push(last);
+        }
+        self.upload()?;
+        self.sink.complete(self.next_index, self.len)?;
+        Ok(self.len)
+    }
+
+    fn check(&self) -> Result<(), FsError> {
+        if self.failed {
+            return Err(FsError::io(
+                "chunked_write",
+                PathBuf::new(),
+                io::Error::new(io::ErrorKind::Other, "an earlier chunk failed"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Upload every chunk in `full`.
+    fn upload(&mut self) -> Result<(), FsError> {
+        let chunk_size = self.policy.chunk_size as u64;
+        let first = self.next_index;
+        let checksums = self.policy.checksums;
+        let sink = &self.sink;
+        let put = |i: usize, data: &[u8]| {
+            let index = first + i as u64;
+            sink.put_chunk(Chunk {
+                index,
+                offset: index * chunk_size,
+                data,
+                checksum: checksums.then(|| crc32(data)),
+            })
+        };
+        let result = if self.full.len() <= 1 {
+            self.full
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, data)| put(i, data))
+        } else {
+            let put = &put;
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = self
+                    .full
+                    .iter()
+                    .enumerate()
+                    .map(|(i, data)| scope.spawn(move || put(i, data)))
+                    .collect();
+                workers.into_iter().try_for_each(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+            })
+        };
+        self.next_index += self.full.len() as u64;
+        self.full.clear();
+        if result.is_err() {
+            self.failed = true;
+        }
+        result
+    }
+}
+
+impl<S: ChunkSink> Write for ChunkedWriter<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        let room = self.policy.chunk_size - self.current.len();
+        let n = buf.len().min(room);
+        if self.current.capacity() == 0 {
+            self.current.reserve_exact(self.policy.chunk_size);
+        }
+        self.current.extend_from_slice(&buf[..n]);
+        self.len += n as u64;
+        if self.current.len() == self.policy.chunk_size {
+            let chunk = std::mem::take(&mut self.current);
+            self.full.push(chunk);
+            if self.full.len() == self.policy.parallelism {
+                self.upload()?;
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// [`ChunkSink`] writing chunks to an open handle with
+/// [`write_at`](FsHandles::write_at).
+///
+/// Never closes the handle.
+#[derive(Debug)]
+pub struct HandleChunks<'a, B: FsHandles + ?Sized> {
+    backend: &'a B,
+    handle: Handle,
+}
+
+impl<'a, B: FsHandles + ?Sized> HandleChunks<'a, B> {
+    /// Write chunks to `handle` on `backend`.
+    pub fn new(backend: &'a B, handle: Handle) -> Self {
+        Self { backend, handle }
+    }
+}
+
+impl<B: FsHandles + ?Sized> ChunkSink for HandleChunks<'_, B> {
+    fn put_chunk(&self, chunk: Chunk<'_>) -> Result<(), FsError> {
+        let mut written = 0;
+        while written < chunk.data.len() {
+            let offset = chunk.offset + written as u64;
+            let n = self
+                .backend
+                .write_at(self.handle, &chunk.data[written..], offset)?;
+            if n == 0 {
+                return Err(FsError::io(
+                    "write_at",
+                    PathBuf::new(),
+                    io::Error::from(io::ErrorKind::WriteZero),
+                ));
+            }
+            written += n;
+        }
+        Ok(())
+    }
+}
+
+/// CRC-32 (IEEE 802.3, as used by zlib and S3 checksums).
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Seen = Arc<Mutex<Vec<(u64, u64, Vec<u8>, Option<u32>)>>>;
+
+    /// Records every chunk it receives.
+    #[derive(Default)]
+    struct Recorder {
+        chunks: Seen,
+        completed: Arc<Mutex<Option<(u64, u64)>>>,
+        fail_at: Option<u64>,
+    }
+
+    impl ChunkSink for Recorder {
+        fn put_chunk(&self, chunk: Chunk<'_>) -> Result<(), FsError> {
+            if self.fail_at == Some(chunk.index) {
+                return Err(FsError::Backend("upload refused".into()));
+            }
+            self.chunks.lock().unwrap().push((
+                chunk.index,
+                chunk.offset,
+                chunk.data.to_vec(),
+                chunk.checksum,
+            ));
+            Ok(())
+        }
+
+        fn complete(&self, chunks: u64, len: u64) -> Result<(), FsError> {
+            *self.completed.lock().unwrap() = Some((chunks, len));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn splits_stream_into_fixed_chunks() {
+        let recorder = Recorder::default();
+        let seen = Arc::clone(&recorder.chunks);
+        let completed = Arc::clone(&recorder.completed);
+        let policy = ChunkPolicy::default()
+            .with_chunk_size(4)
+            .with_parallelism(3)
+            .with_checksums(true);
+        let mut writer = ChunkedWriter::new(recorder, policy);
+
+        writer.write_all(b"0123456789").unwrap();
+        writer.write_all(b"abcdef!").unwrap();
+        // Three full chunks went up as one batch; the fourth is waiting
+        assert_eq!(seen.lock().unwrap().len(), 3);
+
+        assert_eq!(writer.finish().unwrap(), 17);
+        let mut chunks = seen.lock().unwrap().clone();
+        chunks.sort();
+        let offsets: Vec<u64> = chunks.iter().map(|c| c.1).collect();
+        assert_eq!(offsets, [0, 4, 8, 12, 16]);
+        assert_eq!(chunks[3].2, b"cdef");
+        assert_eq!(chunks[4].2, b"!");
+        assert_eq!(chunks[0].3, Some(crc32(b"0123")));
+        assert_eq!(*completed.lock().unwrap(), Some((5, 17)));
+    }
+
+    #[test]
+    fn failed_chunk_poisons_writer() {
+        let recorder = Recorder {
+            fail_at: Some(1),
+            ..Recorder::default()
+        };
+        let completed = Arc::clone(&recorder.completed);
+        let mut writer = ChunkedWriter::new(recorder, ChunkPolicy::default().with_chunk_size(2));
+
+        assert!(writer.write_all(b"abcd").is_err());
+        assert!(writer.write_all(b"ef").is_err());
+        assert!(writer.finish().is_err());
+        assert_eq!(*completed.lock().unwrap(), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! # Clocks
+//!
+//! [`Clock`] is the time source for code that stamps metadata. Backends and
+//! helpers that take one instead of calling `SystemTime::now()` can be
+//! tested with exact timestamps.
+//!
+//! | Clock | Behavior |
+//! |-------|----------|
+//! | [`SystemClock`] | The real time |
+//! | [`FixedClock`] | Always the same instant |
+//! | [`SteppingClock`] | Advances by a fixed step on every reading |
+//!
+//! [`TestClock`](crate::testing::TestClock) (with the `testing` feature) is
+//! moved by hand instead.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Clock, FixedClock, Metadata, SystemClock};
+/// use std::time::SystemTime;
+///
+/// fn new_file_metadata(clock: &dyn Clock) -> Metadata {
+///     let now = clock.now();
+///     Metadata::default()
+///         .with_created(now)
+///         .with_modified(now)
+///         .with_accessed(now)
+/// }
+///
+/// let fixed = FixedClock::new(SystemTime::UNIX_EPOCH);
+/// assert_eq!(new_file_metadata(&fixed).modified, SystemTime::UNIX_EPOCH);
+/// assert!(new_file_metadata(&SystemClock).modified > SystemTime::UNIX_EPOCH);
+/// ```
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// The system clock, `SystemTime::now()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock stopped at one instant.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Clock, FixedClock};
+/// use std::time::{Duration, SystemTime};
+///
+/// let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// let clock = FixedClock::new(at);
+/// assert_eq!(clock.now(), at);
+/// assert_eq!(clock.now(), at);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl FixedClock {
+    /// A clock always reading `at`.
+    pub fn new(at: SystemTime) -> Self {
+        Self(at)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// A clock that moves forward by `step` each time it is read.
+///
+/// Every reading is distinct and ordered, so "modified after" checks behave
+/// the same on every run, however fast the code under test is.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Clock, SteppingClock};
+/// use std::time::{Duration, SystemTime};
+///
+/// let clock = SteppingClock::new(SystemTime::UNIX_EPOCH, Duration::from_secs(1));
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH);
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+/// ```
+#[derive(Debug)]
+pub struct SteppingClock {
+    next: Mutex<SystemTime>,
+    step: Duration,
+}
+
+impl SteppingClock {
+    /// A clock first reading `start`, then `start + step`, and so on.
+    pub fn new(start: SystemTime, step: Duration) -> Self {
+        Self {
+            next: Mutex::new(start),
+            step,
+        }
+    }
+
+    /// The time the next reading will return, without advancing.
+    pub fn peek(&self) -> SystemTime {
+        *self.next.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for SteppingClock {
+    fn now(&self) -> SystemTime {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let now = *next;
+        *next = now + self.step;
+        now
+    }
+}
@@ -0,0 +1,432 @@
+//! # Backends From Closures
+//!
+//! [`FsBuilder`] assembles an [`Fs`](crate::Fs) from one closure per
+//! operation, for mocks and thin adapters over remote APIs that don't
+//! deserve a struct of their own. Operations without a closure fail with
+//! [`FsError::NotSupported`].
+//!
+//! A few operations fall back to others when their own closure is unset:
+//!
+//! | Operation | Falls back to |
+//! |-----------|---------------|
+//! | `read_to_string`, `read_range`, `open_read` | `read` |
+//! | `exists` | `metadata` (`NotFound` means `false`) |
+//! | `read_dir_with` | `read_dir`, sorted when the options ask for it |
+
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+use crate::{FsDir, FsError, FsMaybeFull, FsRead, FsWrite, Metadata, ReadDirIter, ReadDirOptions};
+
+type PathFn<T> = Box<dyn Fn(&Path) -> Result<T, FsError> + Send + Sync>;
+type DataFn = Box<dyn Fn(&Path, &[u8]) -> Result<(), FsError> + Send + Sync>;
+type TwoPathFn = Box<dyn Fn(&Path, &Path) -> Result<(), FsError> + Send + Sync>;
+type TruncateFn = Box<dyn Fn(&Path, u64) -> Result<(), FsError> + Send + Sync>;
+
+/// Builder for a [`ClosureFs`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsError, FsRead, FsWrite};
+/// use std::collections::HashMap;
+/// use std::path::{Path, PathBuf};
+/// use std::sync::{Arc, Mutex};
+///
+/// let store = Arc::new(Mutex::new(HashMap::<PathBuf, Vec<u8>>::new()));
+/// let (reads, writes) = (store.clone(), store);
+///
+/// let fs = FsBuilder::new()
+///     .on_read(move |path| {
+///         reads.lock().unwrap().get(path).cloned().ok_or_else(|| FsError::not_found("read", path))
+///     })
+///     .on_write(move |path, data| {
+///         writes.lock().unwrap().insert(path.to_path_buf(), data.to_vec());
+///         Ok(())
+///     })
+///     .build();
+///
+/// fs.write(Path::new("/a.txt"), b"hello").unwrap();
+/// assert_eq!(fs.read_to_string(Path::new("/a.txt")).unwrap(), "hello");
+/// assert!(matches!(
+///     fs.remove_file(Path::new("/a.txt")),
+///     Err(FsError::NotSupported { operation: "remove_file" })
+/// ));
+/// ```
+#[derive(Debug, Default)]
+pub struct FsBuilder {
+    fs: ClosureFs,
+}
+
+impl FsBuilder {
+    /// A builder with no operations set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finish building.
+    pub fn build(self) -> ClosureFs {
+        self.fs
+    }
+
+    /// Handle [`FsRead::read`], and the operations that fall back to it.
+    pub fn on_read(
+        mut self,
+        f: impl Fn(&Path) -> Result<Vec<u8>, FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.read = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsRead::exists`].
+    pub fn on_exists(
+        mut self,
+        f: impl Fn(&Path) -> Result<bool, FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.exists = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsRead::metadata`], and `exists` if it has no closure.
+    pub fn on_metadata(
+        mut self,
+        f: impl Fn(&Path) -> Result<Metadata, FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.metadata = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::write`].
+    pub fn on_write(
+        mut self,
+        f: impl Fn(&Path, &[u8]) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.write = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::append`].
+    pub fn on_append(
+        mut self,
+        f: impl Fn(&Path, &[u8]) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.append = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::remove_file`].
+    pub fn on_remove_file(
+        mut self,
+        f: impl Fn(&Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.remove_file = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::rename`].
+    pub fn on_rename(
+        mut self,
+        f: impl Fn(&Path, &Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.rename = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::copy`].
+    pub fn on_copy(
+        mut self,
+        f: impl Fn(&Path, &Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.copy = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::truncate`].
+    pub fn on_truncate(
+        mut self,
+        f: impl Fn(&Path, u64) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.truncate = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsWrite::open_write`].
+    pub fn on_open_write(
+        mut self,
+        f: impl Fn(&Path) -> Result<Box<dyn Write + Send>, FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.open_write = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsDir::read_dir`], and `read_dir_with`.
+    pub fn on_read_dir(
+        mut self,
+        f: impl Fn(&Path) -> Result<ReadDirIter, FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.read_dir = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsDir::create_dir`].
+    pub fn on_create_dir(
+        mut self,
+        f: impl Fn(&Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.create_dir = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsDir::create_dir_all`].
+    pub fn on_create_dir_all(
+        mut self,
+        f: impl Fn(&Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.create_dir_all = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsDir::remove_dir`].
+    pub fn on_remove_dir(
+        mut self,
+        f: impl Fn(&Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.remove_dir = Some(Box::new(f));
+        self
+    }
+
+    /// Handle [`FsDir::remove_dir_all`].
+    pub fn on_remove_dir_all(
+        mut self,
+        f: impl Fn(&Path) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.fs.remove_dir_all = Some(Box::new(f));
+        self
+    }
+}
+
+/// An [`Fs`](crate::Fs) whose operations are closures, built with
+/// [`FsBuilder`].
+#[derive(Default)]
+pub struct ClosureFs {
+    read: Option<PathFn<Vec<u8>>>,
+    exists: Option<PathFn<bool>>,
+    metadata: Option<PathFn<Metadata>>,
+    write: Option<DataFn>,
+    append: Option<DataFn>,
+    remove_file: Option<PathFn<()>>,
+    rename: Option<TwoPathFn>,
+    copy: Option<TwoPathFn>,
+    truncate: Option<TruncateFn>,
+    open_write: Option<PathFn<Box<dyn Write + Send>>>,
+    read_dir: Option<PathFn<ReadDirIter>>,
+    create_dir: Option<PathFn<()>>,
+    create_dir_all: Option<PathFn<()>>,
+    remove_dir: Option<PathFn<()>>,
+    remove_dir_all: Option<PathFn<()>>,
+}
+
+impl ClosureFs {
+    fn hook<'a, F: ?Sized>(
+        hook: &'a Option<Box<F>>,
+        operation: &'static str,
+    ) -> Result<&'a F, FsError> {
+        hook.as_deref().ok_or(FsError::NotSupported { operation })
+    }
+}
+
+impl fmt::Debug for ClosureFs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClosureFs").finish_non_exhaustive()
+    }
+}
+
+impl FsRead for ClosureFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        Self::hook(&self.read, "read")?(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        let data = Self::hook(&self.read, "read_to_string")?(path)?;
+        String::from_utf8(data).map_err(|e| FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: e.to_string(),
+        })
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let data = Self::hook(&self.read, "read_range")?(path)?;
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let end = start.saturating_add(len).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        if let Some(exists) = &self.exists {
+            return exists(path);
+        }
+        match Self::hook(&self.metadata, "exists")?(path) {
+            Ok(_) => Ok(true),
+            Err(FsError::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        Self::hook(&self.metadata, "metadata")?(path)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        let data = Self::hook(&self.read, "open_read")?(path)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+}
+
+impl FsWrite for ClosureFs {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        Self::hook(&self.write, "write")?(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        Self::hook(&self.append, "append")?(path, data)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        Self::hook(&self.remove_file, "remove_file")?(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        Self::hook(&self.rename, "rename")?(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        Self::hook(&self.copy, "copy")?(from, to)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        Self::hook(&self.truncate, "truncate")?(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        Self::hook(&self.open_write, "open_write")?(path)
+    }
+}
+
+impl FsDir for ClosureFs {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        Self::hook(&self.read_dir, "read_dir")?(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        let entries = Self::hook(&self.read_dir, "read_dir_with")?(path)?;
+        Ok(if options.sorted {
+            entries.sorted()
+        } else {
+            entries
+        })
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        Self::hook(&self.create_dir, "create_dir")?(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        Self::hook(&self.create_dir_all, "create_dir_all")?(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        Self::hook(&self.remove_dir, "remove_dir")?(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        Self::hook(&self.remove_dir_all, "remove_dir_all")?(path)
+    }
+}
+
+/// No Layer 2 components: every accessor returns `None`.
+impl FsMaybeFull for ClosureFs {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirEntry, FileType, Fs};
+
+    #[test]
+    fn unset_operations_are_not_supported() {
+        let fs: Box<dyn Fs> = Box::new(FsBuilder::new().build());
+        let p = Path::new("/x");
+        assert!(matches!(
+            fs.read(p),
+            Err(FsError::NotSupported { operation: "read" })
+        ));
+        assert!(matches!(
+            fs.exists(p),
+            Err(FsError::NotSupported {
+                operation: "exists"
+            })
+        ));
+        assert!(matches!(
+            fs.create_dir_all(p),
+            Err(FsError::NotSupported {
+                operation: "create_dir_all"
+            })
+        ));
+        assert!(matches!(
+            fs.read_dir_with(p, ReadDirOptions::sorted()),
+            Err(FsError::NotSupported {
+                operation: "read_dir_with"
+            })
+        ));
+        let maybe: &dyn FsMaybeFull = &FsBuilder::new().build();
+        assert!(maybe.as_link().is_none() && maybe.as_stats().is_none());
+    }
+
+    #[test]
+    fn derived_operations_use_their_fallbacks() {
+        let fs = FsBuilder::new()
+            .on_read(|_| Ok(b"hello world".to_vec()))
+            .on_metadata(|path| match path.to_str() {
+                Some("/dir") => Ok(Metadata {
+                    file_type: FileType::Directory,
+                    ..Metadata::default()
+                }),
+                _ => Err(FsError::not_found("metadata", path)),
+            })
+            .build();
+        let p = Path::new("/f");
+
+        assert_eq!(fs.read_range(p, 6, 100).unwrap(), b"world");
+        assert_eq!(fs.read_range(p, 50, 1).unwrap(), b"");
+        assert_eq!(fs.read_to_string(p).unwrap(), "hello world");
+        let mut text = String::new();
+        fs.open_read(p).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello world");
+
+        assert!(fs.exists(Path::new("/dir")).unwrap());
+        assert!(!fs.exists(p).unwrap());
+    }
+
+    #[test]
+    fn read_dir_with_sorts_the_read_dir_listing() {
+        let fs = FsBuilder::new()
+            .on_read_dir(|path| {
+                let entries = ["b", "c", "a"].map(|name| {
+                    Ok(DirEntry::default()
+                        .with_name(name)
+                        .with_path(path.join(name)))
+                });
+                Ok(ReadDirIter::from_vec(entries.into()))
+            })
+            .build();
+
+        let names: Vec<_> = fs
+            .read_dir_with(Path::new("/"), ReadDirOptions::sorted())
+            .unwrap()
+            .map(|entry| entry.unwrap().name)
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+}
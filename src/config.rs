@@ -0,0 +1,232 @@
+//! # Backend Configuration
+//!
+//! Orchestration tools configure backends they know nothing about from a
+//! declarative file. [`BackendConfig`] holds the settings most backends
+//! share, with backend-specific ones as string [`options`](BackendConfig::options);
+//! with the `serde` feature it reads from any serde format (TOML, JSON,
+//! YAML). A backend that implements [`Configure`] applies it.
+//!
+//! | Type | Purpose |
+//! |------|---------|
+//! | [`BackendConfig`] | Root, read-only flag, cache limits, credentials, extra options |
+//! | [`CredentialsRef`] | Where credentials are found, never the secret itself |
+//! | [`Configure`] | Apply a `BackendConfig` to a backend |
+//!
+//! With `serde`, a TOML table looks like:
+//!
+//! ```toml
+//! root = "/srv/data"
+//! read_only = true
+//! credentials = { source = "env", var = "S3_SECRET" }
+//!
+//! [cache]
+//! max_bytes = 67108864
+//! write_policy = "WriteThrough"
+//!
+//! [options]
+//! region = "eu-west-1"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::{CachePolicy, FsError};
+
+/// Declarative settings for a backend.
+///
+/// Every field is optional; a default config changes nothing. Backends
+/// ignore settings that don't apply to them, except unknown
+/// [`options`](Self::options) keys, which
+/// [`check_options`](Self::check_options) rejects so typos surface.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{BackendConfig, CachePolicy, CredentialsRef};
+///
+/// let config = BackendConfig {
+///     root: Some("/srv/data".into()),
+///     read_only: true,
+///     cache: Some(CachePolicy {
+///         max_bytes: Some(64 << 20),
+///         ..CachePolicy::default()
+///     }),
+///     credentials: Some(CredentialsRef::Env {
+///         var: "S3_SECRET".into(),
+///     }),
+///     ..BackendConfig::default()
+/// }
+/// .with_option("region", "eu-west-1");
+///
+/// assert_eq!(config.option("region"), Some("eu-west-1"));
+/// assert!(config.check_options(&["region"]).is_ok());
+/// assert!(config.check_options(&["bucket"]).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct BackendConfig {
+    /// Directory (or bucket prefix, database path...) the backend serves
+    /// as `/`; `None` for the backend's own default.
+    pub root: Option<PathBuf>,
+    /// Refuse every mutation with [`FsError::ReadOnly`].
+    pub read_only: bool,
+    /// Limits for the backend's caches, as for
+    /// [`FsCacheControl::set_cache_policy`](crate::FsCacheControl::set_cache_policy);
+    /// `None` keeps the backend's default.
+    pub cache: Option<CachePolicy>,
+    /// Where to find credentials; `None` for none or the backend's default
+    /// lookup.
+    pub credentials: Option<CredentialsRef>,
+    /// Backend-specific settings, by name.
+    pub options: BTreeMap<String, String>,
+}
+
+impl BackendConfig {
+    /// Set a backend-specific option.
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    /// The backend-specific option `key`, if set.
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.get(key).map(String::as_str)
+    }
+
+    /// Check that every option key is one of `known`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Backend`] naming the first unknown key
+    pub fn check_options(&self, known: &[&str]) -> Result<(), FsError> {
+        match self
+            .options
+            .keys()
+            .find(|key| !known.contains(&key.as_str()))
+        {
+            Some(key) => Err(FsError::Backend(format!(
+                "configure: unknown option `{key}`"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where a backend finds its credentials.
+///
+/// Configs are checked in and logged, so they name the secret's location
+/// and the backend reads it when applying the config.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "source", rename_all = "snake_case")
+)]
+pub enum CredentialsRef {
+    /// An environment variable.
+    Env {
+        /// Variable name.
+        var: String,
+    },
+    /// A file on the host.
+    File {
+        /// Path of the file.
+        path: PathBuf,
+    },
+    /// An entry in a secret store the orchestrator and backend agree on.
+    Named {
+        /// Name of the entry.
+        name: String,
+    },
+}
+
+/// A backend that can be configured from a [`BackendConfig`].
+///
+/// Backends are usually configured once, before they are shared, hence
+/// `&mut self`. Settings the backend has no notion of are ignored; values
+/// it can't honour are errors, so a config never silently means less than
+/// it says.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{BackendConfig, Configure, FsError};
+/// use std::path::PathBuf;
+///
+/// struct Bucket {
+///     prefix: PathBuf,
+///     read_only: bool,
+///     region: String,
+/// }
+///
+/// impl Configure for Bucket {
+///     fn apply(&mut self, config: &BackendConfig) -> Result<(), FsError> {
+///         config.check_options(&["region"])?;
+///         if let Some(root) = &config.root {
+///             self.prefix = root.clone();
+///         }
+///         self.read_only = config.read_only;
+///         if let Some(region) = config.option("region") {
+///             self.region = region.to_string();
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let mut bucket = Bucket { prefix: "/".into(), read_only: false, region: "us-east-1".into() };
+/// let config = BackendConfig { read_only: true, ..BackendConfig::default() }
+///     .with_option("region", "eu-west-1");
+/// bucket.apply(&config).unwrap();
+/// assert!(bucket.read_only);
+/// assert_eq!(bucket.region, "eu-west-1");
+/// ```
+pub trait Configure {
+    /// Apply `config`, replacing the settings it covers.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Backend`] for unknown options or values the backend
+    ///   can't use
+    /// - [`FsError::NotFound`] if [`CredentialsRef::File`] names a missing file
+    /// - Errors from the backend while re-opening its storage
+    fn apply(&mut self, config: &BackendConfig) -> Result<(), FsError>;
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_sparse_json_config() {
+        let config: BackendConfig = serde_json::from_str(
+            r#"{
+                "read_only": true,
+                "credentials": {"source": "env", "var": "TOKEN"},
+                "options": {"region": "eu-west-1"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            BackendConfig {
+                read_only: true,
+                credentials: Some(CredentialsRef::Env {
+                    var: "TOKEN".into()
+                }),
+                ..BackendConfig::default()
+            }
+            .with_option("region", "eu-west-1")
+        );
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            serde_json::from_value::<BackendConfig>(json).unwrap(),
+            config
+        );
+    }
+}
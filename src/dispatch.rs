@@ -0,0 +1,870 @@
+//! # Single-Dispatch Middleware
+//!
+//! Middleware that treats every operation the same way (auditing, rate
+//! limiting, retries, metrics) shouldn't have to implement each [`Fs`]
+//! method by hand. [`FsDispatch`] reduces the whole [`Fs`] surface to one
+//! method taking an [`FsOp`] and returning an [`FsOpResult`].
+//!
+//! | Adapter | Direction |
+//! |---------|-----------|
+//! | [`Dispatcher`] | `Fs` → `FsDispatch`: executes each op on a backend |
+//! | [`DispatchFs`] | `FsDispatch` → `Fs`: turns each method call into an op |
+//!
+//! A middleware is an `FsDispatch` wrapping another `FsDispatch`; the stack
+//! starts with a `Dispatcher` around the real backend and ends with a
+//! `DispatchFs` so callers see an ordinary [`Fs`] again.
+//!
+//! ## One Op Type
+//!
+//! [`FsOp`] is also how the rest of the crate describes an operation, so a
+//! plan built for one subsystem can be handed to another:
+//!
+//! | Subsystem | Uses `FsOp` for |
+//! |-----------|-----------------|
+//! | [`bulk::run`](crate::bulk::run) | The batch of ops to execute |
+//! | [`Journaled`](crate::Journaled) | Intent records and [`pending`](crate::Journaled::pending) ops (`serde` feature) |
+//! | [`Mirrored`](crate::Mirrored) | Ops replayed on replicas |
+//! | [`DryRun`](crate::DryRun) | The recorded [`plan`](crate::DryRun::plan) |
+//! | [`WireRequest`](crate::WireRequest) | `TryFrom` in both directions for the ops both cover (`wire` feature) |
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{
+//!     DispatchFs, Dispatcher, FsBuilder, FsDispatch, FsError, FsOp, FsOpResult, FsRead,
+//! };
+//! use std::path::Path;
+//! use std::sync::Mutex;
+//!
+//! /// Records the name of every operation before passing it on.
+//! struct Audit<D> {
+//!     inner: D,
+//!     log: Mutex<Vec<&'static str>>,
+//! }
+//!
+//! impl<D: FsDispatch> FsDispatch for Audit<D> {
+//!     fn call(&self, op: FsOp) -> Result<FsOpResult, FsError> {
+//!         self.log.lock().unwrap().push(op.operation());
+//!         self.inner.call(op)
+//!     }
+//! }
+//!
+//! let backend = FsBuilder::new().on_read(|_| Ok(b"hi".to_vec())).build();
+//! let fs = DispatchFs::new(Audit {
+//!     inner: Dispatcher::new(backend),
+//!     log: Mutex::new(Vec::new()),
+//! });
+//!
+//! assert_eq!(fs.read(Path::new("/a")).unwrap(), b"hi");
+//! assert!(fs.exists(Path::new("/a")).is_err());
+//! assert_eq!(*fs.inner().log.lock().unwrap(), ["read", "exists"]);
+//! ```
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{
+    CopyOptions, Fs, FsDir, FsError, FsRead, FsWrite, Metadata, MetadataLite, MetadataMask,
+    Permissions, ReadDirIter, ReadDirOptions, WriteOptions,
+};
+
+/// One [`Fs`] method call, with owned arguments.
+///
+/// Variants are named after the method they mirror. With the `serde`
+/// feature, ops serialize as `{"op": "read_range", "path": ..., ...}`,
+/// the same shape as the matching [`WireRequest`](crate::WireRequest).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", rename_all = "snake_case"))]
+pub enum FsOp {
+    // FsRead
+    /// [`FsRead::read`] → [`FsOpResult::Bytes`].
+    Read {
+        /// File to read.
+        path: PathBuf,
+    },
+    /// [`FsRead::read_to_string`] → [`FsOpResult::Text`].
+    ReadToString {
+        /// File to read.
+        path: PathBuf,
+    },
+    /// [`FsRead::read_range`] → [`FsOpResult::Bytes`].
+    ReadRange {
+        /// File to read.
+        path: PathBuf,
+        /// Byte offset to start at.
+        offset: u64,
+        /// Maximum number of bytes.
+        len: usize,
+    },
+    /// [`FsRead::exists`] → [`FsOpResult::Bool`].
+    Exists {
+        /// Path to check.
+        path: PathBuf,
+    },
+    /// [`FsRead::exists_fresh`] → [`FsOpResult::Bool`].
+    ExistsFresh {
+        /// Path to check.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata`] → [`FsOpResult::Metadata`].
+    Metadata {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata_lite`] → [`FsOpResult::MetadataLite`].
+    MetadataLite {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata_mask`] → [`FsOpResult::Metadata`].
+    MetadataMask {
+        /// Path to inspect.
+        path: PathBuf,
+        /// Fields the caller needs.
+        mask: MetadataMask,
+    },
+    /// [`FsRead::metadata_with_tag`] → [`FsOpResult::Metadata`].
+    MetadataWithTag {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsRead::open_read`] → [`FsOpResult::Reader`].
+    OpenRead {
+        /// File to open.
+        path: PathBuf,
+    },
+
+    // FsWrite
+    /// [`FsWrite::write`] → [`FsOpResult::Unit`].
+    Write {
+        /// File to write.
+        path: PathBuf,
+        /// New contents.
+        data: Vec<u8>,
+    },
+    /// [`FsWrite::append`] → [`FsOpResult::Unit`].
+    Append {
+        /// File to append to.
+        path: PathBuf,
+        /// Bytes to append.
+        data: Vec<u8>,
+    },
+    /// [`FsWrite::remove_file`] → [`FsOpResult::Unit`].
+    RemoveFile {
+        /// File to remove.
+        path: PathBuf,
+    },
+    /// [`FsWrite::rename`] → [`FsOpResult::Unit`].
+    Rename {
+        /// Current path.
+        from: PathBuf,
+        /// New path.
+        to: PathBuf,
+    },
+    /// [`FsWrite::copy`] → [`FsOpResult::Unit`].
+    Copy {
+        /// Source file.
+        from: PathBuf,
+        /// Destination file.
+        to: PathBuf,
+    },
+    /// [`FsWrite::copy_with`] → [`FsOpResult::Count`].
+    CopyWith {
+        /// Source file.
+        from: PathBuf,
+        /// Destination file.
+        to: PathBuf,
+        /// Overwrite and preservation options.
+        options: CopyOptions,
+    },
+    /// [`FsWrite::truncate`] → [`FsOpResult::Unit`].
+    Truncate {
+        /// File to resize.
+        path: PathBuf,
+        /// New size in bytes.
+        size: u64,
+    },
+    /// [`FsWrite::open_write`] → [`FsOpResult::Writer`].
+    OpenWrite {
+        /// File to open.
+        path: PathBuf,
+    },
+    /// [`FsWrite::open_write_sized`] → [`FsOpResult::Writer`].
+    OpenWriteSized {
+        /// File to open.
+        path: PathBuf,
+        /// Expected final size in bytes.
+        expected_len: u64,
+    },
+    /// [`FsWrite::write_with`] → [`FsOpResult::Unit`].
+    WriteWith {
+        /// File to write.
+        path: PathBuf,
+        /// New contents.
+        data: Vec<u8>,
+        /// Storage hints.
+        options: WriteOptions,
+    },
+    /// [`FsWrite::set_times`] → [`FsOpResult::Unit`].
+    SetTimes {
+        /// Entry to change.
+        path: PathBuf,
+        /// New access time, if changing.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "crate::types::system_time_serde::option")
+        )]
+        atime: Option<SystemTime>,
+        /// New modification time, if changing.
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "crate::types::system_time_serde::option")
+        )]
+        mtime: Option<SystemTime>,
+    },
+
+    // FsDir
+    /// [`FsDir::read_dir`] → [`FsOpResult::DirEntries`].
+    ReadDir {
+        /// Directory to list.
+        path: PathBuf,
+    },
+    /// [`FsDir::read_dir_with`] → [`FsOpResult::DirEntries`].
+    ReadDirWith {
+        /// Directory to list.
+        path: PathBuf,
+        /// Listing order.
+        options: ReadDirOptions,
+    },
+    /// [`FsDir::create_dir`] → [`FsOpResult::Unit`].
+    CreateDir {
+        /// Directory to create.
+        path: PathBuf,
+    },
+    /// [`FsDir::create_dir_all`] → [`FsOpResult::Unit`].
+    CreateDirAll {
+        /// Directory to create, with its parents.
+        path: PathBuf,
+    },
+    /// [`FsDir::create_dir_with`] → [`FsOpResult::Unit`].
+    CreateDirWith {
+        /// Directory to create.
+        path: PathBuf,
+        /// Mode of the new directory.
+        perm: Permissions,
+    },
+    /// [`FsDir::create_temp_dir_in`] → [`FsOpResult::Path`].
+    CreateTempDirIn {
+        /// Directory to create it in.
+        parent: PathBuf,
+        /// Name prefix.
+        prefix: String,
+    },
+    /// [`FsDir::remove_dir`] → [`FsOpResult::Unit`].
+    RemoveDir {
+        /// Empty directory to remove.
+        path: PathBuf,
+    },
+    /// [`FsDir::remove_dir_all`] → [`FsOpResult::Unit`].
+    RemoveDirAll {
+        /// Directory tree to remove.
+        path: PathBuf,
+    },
+    /// [`FsDir::dir_len`] → [`FsOpResult::Count`].
+    DirLen {
+        /// Directory to count.
+        path: PathBuf,
+    },
+    /// [`FsDir::is_dir_empty`] → [`FsOpResult::Bool`].
+    IsDirEmpty {
+        /// Directory to check.
+        path: PathBuf,
+    },
+}
+
+impl FsOp {
+    /// The trait method this op mirrors, as used for
+    /// [`FsError::operation`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsOp;
+    ///
+    /// let op = FsOp::ReadDir { path: "/docs".into() };
+    /// assert_eq!(op.operation(), "read_dir");
+    /// ```
+    pub fn operation(&self) -> &'static str {
+        match self {
+            Self::Read { .. } => "read",
+            Self::ReadToString { .. } => "read_to_string",
+            Self::ReadRange { .. } => "read_range",
+            Self::Exists { .. } => "exists",
+            Self::ExistsFresh { .. } => "exists_fresh",
+            Self::Metadata { .. } => "metadata",
+            Self::MetadataLite { .. } => "metadata_lite",
+            Self::MetadataMask { .. } => "metadata_mask",
+            Self::MetadataWithTag { .. } => "metadata_with_tag",
+            Self::OpenRead { .. } => "open_read",
+            Self::Write { .. } => "write",
+            Self::Append { .. } => "append",
+            Self::RemoveFile { .. } => "remove_file",
+            Self::Rename { .. } => "rename",
+            Self::Copy { .. } => "copy",
+            Self::CopyWith { .. } => "copy_with",
+            Self::Truncate { .. } => "truncate",
+            Self::OpenWrite { .. } => "open_write",
+            Self::OpenWriteSized { .. } => "open_write_sized",
+            Self::WriteWith { .. } => "write_with",
+            Self::SetTimes { .. } => "set_times",
+            Self::ReadDir { .. } => "read_dir",
+            Self::ReadDirWith { .. } => "read_dir_with",
+            Self::CreateDir { .. } => "create_dir",
+            Self::CreateDirAll { .. } => "create_dir_all",
+            Self::CreateDirWith { .. } => "create_dir_with",
+            Self::CreateTempDirIn { .. } => "create_temp_dir_in",
+            Self::RemoveDir { .. } => "remove_dir",
+            Self::RemoveDirAll { .. } => "remove_dir_all",
+            Self::DirLen { .. } => "dir_len",
+            Self::IsDirEmpty { .. } => "is_dir_empty",
+        }
+    }
+
+    /// The path the op acts on; the source for two-path ops and the parent
+    /// for `create_temp_dir_in`.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Read { path }
+            | Self::ReadToString { path }
+            | Self::ReadRange { path, .. }
+            | Self::Exists { path }
+            | Self::ExistsFresh { path }
+            | Self::Metadata { path }
+            | Self::MetadataLite { path }
+            | Self::MetadataMask { path, .. }
+            | Self::MetadataWithTag { path }
+            | Self::OpenRead { path }
+            | Self::Write { path, .. }
+            | Self::Append { path, .. }
+            | Self::RemoveFile { path }
+            | Self::Truncate { path, .. }
+            | Self::OpenWrite { path }
+            | Self::OpenWriteSized { path, .. }
+            | Self::WriteWith { path, .. }
+            | Self::SetTimes { path, .. }
+            | Self::ReadDir { path }
+            | Self::ReadDirWith { path, .. }
+            | Self::CreateDir { path }
+            | Self::CreateDirAll { path }
+            | Self::CreateDirWith { path, .. }
+            | Self::RemoveDir { path }
+            | Self::RemoveDirAll { path }
+            | Self::DirLen { path }
+            | Self::IsDirEmpty { path } => path,
+            Self::Rename { from, .. } | Self::Copy { from, .. } | Self::CopyWith { from, .. } => {
+                from
+            }
+            Self::CreateTempDirIn { parent, .. } => parent,
+        }
+    }
+
+    /// Returns `true` if the op can modify the filesystem.
+    pub fn is_mutation(&self) -> bool {
+        !matches!(
+            self,
+            Self::Read { .. }
+                | Self::ReadToString { .. }
+                | Self::ReadRange { .. }
+                | Self::Exists { .. }
+                | Self::ExistsFresh { .. }
+                | Self::Metadata { .. }
+                | Self::MetadataLite { .. }
+                | Self::MetadataMask { .. }
+                | Self::MetadataWithTag { .. }
+                | Self::OpenRead { .. }
+                | Self::ReadDir { .. }
+                | Self::ReadDirWith { .. }
+                | Self::DirLen { .. }
+                | Self::IsDirEmpty { .. }
+        )
+    }
+}
+
+/// The successful result of an [`FsOp`].
+///
+/// Each op documents which variant it expects back.
+#[non_exhaustive]
+pub enum FsOpResult {
+    /// The call returned `()`.
+    Unit,
+    /// A boolean result (`exists`, `is_dir_empty`).
+    Bool(bool),
+    /// Raw bytes (`read`, `read_range`).
+    Bytes(Vec<u8>),
+    /// UTF-8 text (`read_to_string`).
+    Text(String),
+    /// A count of bytes or entries (`copy_with`, `dir_len`).
+    Count(u64),
+    /// A path (`create_temp_dir_in`).
+    Path(PathBuf),
+    /// Full metadata.
+    Metadata(Metadata),
+    /// Fast-path metadata.
+    MetadataLite(MetadataLite),
+    /// A directory listing.
+    DirEntries(ReadDirIter),
+    /// A streaming reader (`open_read`).
+    Reader(Box<dyn Read + Send>),
+    /// A streaming writer (`open_write`, `open_write_sized`).
+    Writer(Box<dyn Write + Send>),
+}
+
+impl fmt::Debug for FsOpResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => f.write_str("Unit"),
+            Self::Bool(value) => f.debug_tuple("Bool").field(value).finish(),
+            Self::Bytes(data) => f.debug_tuple("Bytes").field(data).finish(),
+            Self::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Self::Count(count) => f.debug_tuple("Count").field(count).finish(),
+            Self::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Self::Metadata(meta) => f.debug_tuple("Metadata").field(meta).finish(),
+            Self::MetadataLite(meta) => f.debug_tuple("MetadataLite").field(meta).finish(),
+            Self::DirEntries(_) => f.write_str("DirEntries(..)"),
+            Self::Reader(_) => f.write_str("Reader(..)"),
+            Self::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+/// A filesystem reduced to a single method.
+///
+/// # Contract
+///
+/// `call` returns the [`FsOpResult`] variant documented on each [`FsOp`]
+/// variant. [`DispatchFs`] reports any other variant as
+/// [`FsError::Backend`].
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsDispatch`.
+pub trait FsDispatch: Send + Sync {
+    /// Perform `op`.
+    ///
+    /// # Errors
+    ///
+    /// Whatever the underlying [`Fs`] method returns for this op.
+    fn call(&self, op: FsOp) -> Result<FsOpResult, FsError>;
+}
+
+/// Executes [`FsOp`]s on an [`Fs`] backend.
+///
+/// Every op calls the matching method on the backend, so its overrides of
+/// provided methods are kept.
+#[derive(Debug, Clone, Default)]
+pub struct Dispatcher<B> {
+    inner: B,
+}
+
+impl<B> Dispatcher<B> {
+    /// Dispatch ops to `inner`.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: Fs> FsDispatch for Dispatcher<B> {
+    fn call(&self, op: FsOp) -> Result<FsOpResult, FsError> {
+        execute(&self.inner, op)
+    }
+}
+
+/// Perform `op` on `fs` by calling the matching method.
+pub(crate) fn execute<B: Fs + ?Sized>(fs: &B, op: FsOp) -> Result<FsOpResult, FsError> {
+    Ok(match op {
+        FsOp::Read { path } => FsOpResult::Bytes(fs.read(&path)?),
+        FsOp::ReadToString { path } => FsOpResult::Text(fs.read_to_string(&path)?),
+        FsOp::ReadRange { path, offset, len } => {
+            FsOpResult::Bytes(fs.read_range(&path, offset, len)?)
+        }
+        FsOp::Exists { path } => FsOpResult::Bool(fs.exists(&path)?),
+        FsOp::ExistsFresh { path } => FsOpResult::Bool(fs.exists_fresh(&path)?),
+        FsOp::Metadata { path } => FsOpResult::Metadata(fs.metadata(&path)?),
+        FsOp::MetadataLite { path } => FsOpResult::MetadataLite(fs.metadata_lite(&path)?),
+        FsOp::MetadataMask { path, mask } => FsOpResult::Metadata(fs.metadata_mask(&path, mask)?),
+        FsOp::MetadataWithTag { path } => FsOpResult::Metadata(fs.metadata_with_tag(&path)?),
+        FsOp::OpenRead { path } => FsOpResult::Reader(fs.open_read(&path)?),
+        FsOp::Write { path, data } => unit(fs.write(&path, &data))?,
+        FsOp::Append { path, data } => unit(fs.append(&path, &data))?,
+        FsOp::RemoveFile { path } => unit(fs.remove_file(&path))?,
+        FsOp::Rename { from, to } => unit(fs.rename(&from, &to))?,
+        FsOp::Copy { from, to } => unit(fs.copy(&from, &to))?,
+        FsOp::CopyWith { from, to, options } => {
+            FsOpResult::Count(fs.copy_with(&from, &to, options)?)
+        }
+        FsOp::Truncate { path, size } => unit(fs.truncate(&path, size))?,
+        FsOp::OpenWrite { path } => FsOpResult::Writer(fs.open_write(&path)?),
+        FsOp::OpenWriteSized { path, expected_len } => {
+            FsOpResult::Writer(fs.open_write_sized(&path, expected_len)?)
+        }
+        FsOp::WriteWith {
+            path,
+            data,
+            options,
+        } => unit(fs.write_with(&path, &data, options))?,
+        FsOp::SetTimes { path, atime, mtime } => unit(fs.set_times(&path, atime, mtime))?,
+        FsOp::ReadDir { path } => FsOpResult::DirEntries(fs.read_dir(&path)?),
+        FsOp::ReadDirWith { path, options } => {
+            FsOpResult::DirEntries(fs.read_dir_with(&path, options)?)
+        }
+        FsOp::CreateDir { path } => unit(fs.create_dir(&path))?,
+        FsOp::CreateDirAll { path } => unit(fs.create_dir_all(&path))?,
+        FsOp::CreateDirWith { path, perm } => unit(fs.create_dir_with(&path, perm))?,
+        FsOp::CreateTempDirIn { parent, prefix } => {
+            FsOpResult::Path(fs.create_temp_dir_in(&parent, &prefix)?)
+        }
+        FsOp::RemoveDir { path } => unit(fs.remove_dir(&path))?,
+        FsOp::RemoveDirAll { path } => unit(fs.remove_dir_all(&path))?,
+        FsOp::DirLen { path } => FsOpResult::Count(fs.dir_len(&path)?),
+        FsOp::IsDirEmpty { path } => FsOpResult::Bool(fs.is_dir_empty(&path)?),
+    })
+}
+
+fn unit(result: Result<(), FsError>) -> Result<FsOpResult, FsError> {
+    result.map(|()| FsOpResult::Unit)
+}
+
+/// An [`Fs`] that sends every method call through an [`FsDispatch`].
+#[derive(Debug, Clone, Default)]
+pub struct DispatchFs<D> {
+    inner: D,
+}
+
+impl<D> DispatchFs<D> {
+    /// Send calls to `inner`.
+    pub fn new(inner: D) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped dispatcher.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Unwrap the dispatcher.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+/// Call `$op` on `$self.inner` and unwrap the expected result variant.
+macro_rules! dispatch {
+    ($self:ident, $op:expr, $variant:ident($value:ident) => $out:expr) => {{
+        let op = $op;
+        let operation = op.operation();
+        match $self.inner.call(op)? {
+            FsOpResult::$variant($value) => Ok($out),
+            other => Err(mismatch(operation, &other)),
+        }
+    }};
+    ($self:ident, $op:expr) => {{
+        let op = $op;
+        let operation = op.operation();
+        match $self.inner.call(op)? {
+            FsOpResult::Unit => Ok(()),
+            other => Err(mismatch(operation, &other)),
+        }
+    }};
+}
+
+fn mismatch(operation: &str, result: &FsOpResult) -> FsError {
+    FsError::Backend(format!(
+        "{operation}: dispatcher returned unexpected {result:?}"
+    ))
+}
+
+impl<D: FsDispatch> FsRead for DispatchFs<D> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::Read { path }, Bytes(data) => data)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::ReadToString { path }, Text(text) => text)
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::ReadRange { path, offset, len }, Bytes(data) => data)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::Exists { path }, Bool(exists) => exists)
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::ExistsFresh { path }, Bool(exists) => exists)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::Metadata { path }, Metadata(meta) => meta)
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::MetadataLite { path }, MetadataLite(meta) => meta)
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::MetadataMask { path, mask }, Metadata(meta) => meta)
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::MetadataWithTag { path }, Metadata(meta) => meta)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::OpenRead { path }, Reader(reader) => reader)
+    }
+}
+
+impl<D: FsDispatch> FsWrite for DispatchFs<D> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let (path, data) = (path.to_path_buf(), data.to_vec());
+        dispatch!(self, FsOp::Write { path, data })
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let (path, data) = (path.to_path_buf(), data.to_vec());
+        dispatch!(self, FsOp::Append { path, data })
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::RemoveFile { path })
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let (from, to) = (from.to_path_buf(), to.to_path_buf());
+        dispatch!(self, FsOp::Rename { from, to })
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let (from, to) = (from.to_path_buf(), to.to_path_buf());
+        dispatch!(self, FsOp::Copy { from, to })
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::Truncate { path, size })
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::OpenWrite { path }, Writer(writer) => writer)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(
+            self,
+            FsOp::OpenWriteSized { path, expected_len },
+            Writer(writer) => writer
+        )
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let (from, to) = (from.to_path_buf(), to.to_path_buf());
+        dispatch!(self, FsOp::CopyWith { from, to, options }, Count(count) => count)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let (path, data) = (path.to_path_buf(), data.to_vec());
+        dispatch!(
+            self,
+            FsOp::WriteWith {
+                path,
+                data,
+                options
+            }
+        )
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::SetTimes { path, atime, mtime })
+    }
+}
+
+impl<D: FsDispatch> FsDir for DispatchFs<D> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::ReadDir { path }, DirEntries(entries) => entries)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::ReadDirWith { path, options }, DirEntries(entries) => entries)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::CreateDir { path })
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::CreateDirAll { path })
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::RemoveDir { path })
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::RemoveDirAll { path })
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::CreateDirWith { path, perm })
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        let (parent, prefix) = (parent.to_path_buf(), prefix.to_string());
+        dispatch!(self, FsOp::CreateTempDirIn { parent, prefix }, Path(path) => path)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::DirLen { path }, Count(len) => len)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        let path = path.to_path_buf();
+        dispatch!(self, FsOp::IsDirEmpty { path }, Bool(empty) => empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsBuilder;
+
+    struct Wrong;
+
+    impl FsDispatch for Wrong {
+        fn call(&self, _: FsOp) -> Result<FsOpResult, FsError> {
+            Ok(FsOpResult::Bool(true))
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_results_and_errors() {
+        let backend = FsBuilder::new()
+            .on_read(|_| Ok(b"data".to_vec()))
+            .on_write(|path, _| Err(FsError::permission_denied("write", path)))
+            .build();
+        let fs = DispatchFs::new(Dispatcher::new(backend));
+
+        assert_eq!(fs.read_range(Path::new("/f"), 1, 2).unwrap(), b"at");
+        assert!(matches!(
+            fs.write(Path::new("/f"), b"x"),
+            Err(FsError::PermissionDenied { .. })
+        ));
+        assert!(matches!(
+            fs.write_with(Path::new("/f"), b"x", WriteOptions::default()),
+            Err(FsError::PermissionDenied { .. })
+        ));
+        assert!(matches!(
+            fs.remove_dir(Path::new("/d")),
+            Err(FsError::NotSupported {
+                operation: "remove_dir"
+            })
+        ));
+    }
+
+    #[test]
+    fn unexpected_result_variant_is_a_backend_error() {
+        let fs = DispatchFs::new(Wrong);
+        assert!(fs.exists(Path::new("/a")).unwrap());
+        match fs.read(Path::new("/a")) {
+            Err(FsError::Backend(message)) => {
+                assert_eq!(message, "read: dispatcher returned unexpected Bool(true)");
+            }
+            other => panic!("expected Backend error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn op_reports_name_path_and_mutation() {
+        let op = FsOp::Rename {
+            from: "/a".into(),
+            to: "/b".into(),
+        };
+        assert_eq!(op.operation(), "rename");
+        assert_eq!(op.path(), Path::new("/a"));
+        assert!(op.is_mutation());
+        assert!(!FsOp::ReadDir { path: "/".into() }.is_mutation());
+    }
+
+    #[test]
+    fn read_dir_with_reaches_the_backend_as_its_own_op() {
+        struct Recorder(std::sync::Mutex<Vec<FsOp>>);
+
+        impl FsDispatch for Recorder {
+            fn call(&self, op: FsOp) -> Result<FsOpResult, FsError> {
+                self.0.lock().unwrap().push(op);
+                Ok(FsOpResult::DirEntries(ReadDirIter::from_vec(vec![])))
+            }
+        }
+
+        let fs = DispatchFs::new(Recorder(std::sync::Mutex::new(Vec::new())));
+        fs.read_dir_with(Path::new("/d"), ReadDirOptions::sorted())
+            .unwrap();
+        let op = FsOp::ReadDirWith {
+            path: "/d".into(),
+            options: ReadDirOptions::sorted(),
+        };
+        assert_eq!(op.operation(), "read_dir_with");
+        assert!(!op.is_mutation());
+        assert_eq!(*fs.inner().0.lock().unwrap(), [op]);
+    }
+}
@@ -0,0 +1,397 @@
+//! # Encryption Contract
+//!
+//! AnyFS leaves ciphers to the encryption layers themselves; this module
+//! fixes what they share, so data written by one layer can be read, rotated
+//! or audited by another:
+//!
+//! - [`KeyProvider`] hands out [`KeyMaterial`], the current key for a path
+//!   and any older key by id.
+//! - [`EncryptionInfo`] records how a file's contents were encrypted:
+//!   algorithm, key id and nonce.
+//!
+//! ## Where the Info Lives
+//!
+//! | Backend | Storage |
+//! |---------|---------|
+//! | Implements [`FsXattr`] | Xattrs [`XATTR_ENC_ALGORITHM`], [`XATTR_ENC_KEY_ID`] and [`XATTR_ENC_NONCE`]; the file holds only ciphertext |
+//! | Anything else | A header in front of the ciphertext, see [`EncryptionInfo::encode_header`] |
+//!
+//! A file with neither is plaintext. Algorithm names are lowercase
+//! identifiers such as `aes-256-gcm` or `xchacha20-poly1305`; a layer must
+//! refuse to decrypt an algorithm it doesn't know rather than guess.
+//!
+//! ## Rotation
+//!
+//! [`KeyProvider::rotate`] makes a new key current. Existing files keep the
+//! key id they were written with and stay readable through
+//! [`KeyProvider::key_by_id`]; a re-encryption pass rewrites the files for
+//! which [`KeyProvider::needs_reencryption`] returns `true`.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::{FsError, FsXattr, XATTR_ENC_ALGORITHM, XATTR_ENC_KEY_ID, XATTR_ENC_NONCE};
+
+/// A key and the id it is stored under.
+///
+/// `Debug` prints the id and length but never the key bytes.
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyMaterial {
+    id: String,
+    key: Vec<u8>,
+}
+
+impl KeyMaterial {
+    /// Create key material with the given id.
+    pub fn new(id: impl Into<String>, key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            id: id.into(),
+            key: key.into(),
+        }
+    }
+
+    /// Id recorded next to data encrypted with this key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The raw key bytes.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+impl fmt::Debug for KeyMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyMaterial")
+            .field("id", &self.id)
+            .field("len", &self.key.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Source of encryption keys for encryption layers.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn KeyProvider`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, KeyMaterial, KeyProvider};
+/// use std::path::Path;
+///
+/// struct SingleKey(KeyMaterial);
+///
+/// impl KeyProvider for SingleKey {
+///     fn key_for(&self, _path: &Path) -> Result<KeyMaterial, FsError> {
+///         Ok(self.0.clone())
+///     }
+///
+///     fn key_by_id(&self, id: &str) -> Result<KeyMaterial, FsError> {
+///         if id == self.0.id() {
+///             Ok(self.0.clone())
+///         } else {
+///             Err(FsError::Backend(format!("unknown key {id}")))
+///         }
+///     }
+/// }
+///
+/// let keys = SingleKey(KeyMaterial::new("k1", [7u8; 32]));
+/// assert_eq!(keys.key_for(Path::new("/a")).unwrap().id(), "k1");
+/// ```
+pub trait KeyProvider: Send + Sync {
+    /// The key to encrypt new contents of `path` with.
+    fn key_for(&self, path: &Path) -> Result<KeyMaterial, FsError>;
+
+    /// The key with the given id, to decrypt existing contents.
+    ///
+    /// Must keep returning retired keys for as long as data encrypted
+    /// with them may exist.
+    fn key_by_id(&self, id: &str) -> Result<KeyMaterial, FsError>;
+
+    /// Make a new key current and return its id.
+    ///
+    /// Defaults to [`FsError::NotSupported`].
+    fn rotate(&self) -> Result<String, FsError> {
+        Err(FsError::NotSupported {
+            operation: "rotate_key",
+        })
+    }
+
+    /// Whether contents of `path` encrypted as `info` should be rewritten.
+    ///
+    /// Defaults to `true` when `info` names a key other than the current
+    /// [`key_for`](Self::key_for) `path`.
+    fn needs_reencryption(&self, path: &Path, info: &EncryptionInfo) -> Result<bool, FsError> {
+        Ok(self.key_for(path)?.id() != info.key_id)
+    }
+}
+
+/// How a file's contents were encrypted.
+///
+/// # Header Layout
+///
+/// Used on backends without [`FsXattr`]. All lengths are little-endian
+/// `u16`; the ciphertext follows directly.
+///
+/// | Field | Size |
+/// |-------|------|
+/// | Magic [`HEADER_MAGIC`](Self::HEADER_MAGIC) | 4 |
+/// | Version [`HEADER_VERSION`](Self::HEADER_VERSION) | 1 |
+/// | Algorithm length, algorithm (UTF-8) | 2 + n |
+/// | Key id length, key id (UTF-8) | 2 + n |
+/// | Nonce length, nonce | 2 + n |
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::EncryptionInfo;
+///
+/// let info = EncryptionInfo::new("aes-256-gcm", "k1", vec![0u8; 12]);
+/// let mut file = info.encode_header().unwrap();
+/// file.extend_from_slice(b"ciphertext");
+///
+/// let (decoded, len) = EncryptionInfo::decode_header(&file).unwrap().unwrap();
+/// assert_eq!(decoded, info);
+/// assert_eq!(&file[len..], b"ciphertext");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionInfo {
+    /// Cipher name, e.g. `aes-256-gcm`.
+    pub algorithm: String,
+    /// Id of the [`KeyMaterial`] used.
+    pub key_id: String,
+    /// Nonce (IV) used.
+    pub nonce: Vec<u8>,
+}
+
+impl EncryptionInfo {
+    /// First bytes of an encrypted file carrying a header.
+    pub const HEADER_MAGIC: [u8; 4] = *b"AFSE";
+
+    /// Header version written by [`encode_header`](Self::encode_header).
+    pub const HEADER_VERSION: u8 = 1;
+
+    /// Create encryption info.
+    pub fn new(algorithm: impl Into<String>, key_id: impl Into<String>, nonce: Vec<u8>) -> Self {
+        Self {
+            algorithm: algorithm.into(),
+            key_id: key_id.into(),
+            nonce,
+        }
+    }
+
+    /// Read the info stored in `path`'s xattrs.
+    ///
+    /// Returns `Ok(None)` if the file has no [`XATTR_ENC_ALGORITHM`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidData`] if the algorithm is set but the other
+    ///   attributes are missing, or a name isn't UTF-8
+    /// - Errors from [`get_xattr`](FsXattr::get_xattr)
+    pub fn read_xattrs<B: FsXattr + ?Sized>(
+        backend: &B,
+        path: &Path,
+    ) -> Result<Option<Self>, FsError> {
+        let algorithm = match backend.get_xattr(path, XATTR_ENC_ALGORITHM) {
+            Ok(value) => value,
+            Err(FsError::XattrNotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let required = |name: &str| match backend.get_xattr(path, name) {
+            Err(FsError::XattrNotFound { .. }) => Err(FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{XATTR_ENC_ALGORITHM} is set but {name} is missing"),
+            }),
+            other => other,
+        };
+        let utf8 = |name: &str, value: Vec<u8>| {
+            String::from_utf8(value).map_err(|e| FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{name} is not UTF-8: {e}"),
+            })
+        };
+        Ok(Some(Self {
+            algorithm: utf8(XATTR_ENC_ALGORITHM, algorithm)?,
+            key_id: utf8(XATTR_ENC_KEY_ID, required(XATTR_ENC_KEY_ID)?)?,
+            nonce: required(XATTR_ENC_NONCE)?,
+        }))
+    }
+
+    /// Store the info in `path`'s xattrs.
+    ///
+    /// # Errors
+    ///
+    /// Errors from [`set_xattr`](FsXattr::set_xattr).
+    pub fn write_xattrs<B: FsXattr + ?Sized>(
+        &self,
+        backend: &B,
+        path: &Path,
+    ) -> Result<(), FsError> {
+        backend.set_xattr(path, XATTR_ENC_KEY_ID, self.key_id.as_bytes())?;
+        backend.set_xattr(path, XATTR_ENC_NONCE, &self.nonce)?;
+        // Written last: its presence marks the set as complete.
+        backend.set_xattr(path, XATTR_ENC_ALGORITHM, self.algorithm.as_bytes())
+    }
+
+    /// Encode the info as a file header.
+    ///
+    /// # Errors
+    ///
+    /// [`FsError::Serialization`] if a field is longer than `u16::MAX` bytes.
+    pub fn encode_header(&self) -> Result<Vec<u8>, FsError> {
+        let fields = [
+            self.algorithm.as_bytes(),
+            self.key_id.as_bytes(),
+            &self.nonce,
+        ];
+        let mut out = Vec::with_capacity(11 + fields.iter().map(|f| f.len()).sum::<usize>());
+        out.extend_from_slice(&Self::HEADER_MAGIC);
+        out.push(Self::HEADER_VERSION);
+        for field in fields {
+            let len = u16::try_from(field.len()).map_err(|_| {
+                FsError::Serialization(format!(
+                    "encryption header field of {} bytes exceeds {}",
+                    field.len(),
+                    u16::MAX
+                ))
+            })?;
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(field);
+        }
+        Ok(out)
+    }
+
+    /// Decode a header from the start of `data`.
+    ///
+    /// Returns the info and the header length, or `Ok(None)` if `data` does
+    /// not start with [`HEADER_MAGIC`](Self::HEADER_MAGIC).
+    ///
+    /// # Errors
+    ///
+    /// [`FsError::Deserialization`] if the header is truncated, has an
+    /// unknown version, or a name isn't UTF-8.
+    pub fn decode_header(data: &[u8]) -> Result<Option<(Self, usize)>, FsError> {
+        let Some(rest) = data.strip_prefix(&Self::HEADER_MAGIC[..]) else {
+            return Ok(None);
+        };
+        let malformed = |what: &str| FsError::Deserialization(format!("encryption header: {what}"));
+        match rest.first() {
+            Some(&Self::HEADER_VERSION) => {}
+            Some(version) => return Err(malformed(&format!("unsupported version {version}"))),
+            None => return Err(malformed("truncated")),
+        }
+        let mut pos = Self::HEADER_MAGIC.len() + 1;
+        let mut field = || {
+            let len = data
+                .get(pos..pos + 2)
+                .ok_or_else(|| malformed("truncated"))?;
+            let len = usize::from(u16::from_le_bytes([len[0], len[1]]));
+            let value = data
+                .get(pos + 2..pos + 2 + len)
+                .ok_or_else(|| malformed("truncated"))?;
+            pos += 2 + len;
+            Ok::<_, FsError>(value.to_vec())
+        };
+        let utf8 = |value: Vec<u8>| String::from_utf8(value).map_err(|e| malformed(&e.to_string()));
+        let algorithm = utf8(field()?)?;
+        let key_id = utf8(field()?)?;
+        let nonce = field()?;
+        Ok(Some((
+            Self {
+                algorithm,
+                key_id,
+                nonce,
+            },
+            pos,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Keys;
+
+    impl KeyProvider for Keys {
+        fn key_for(&self, _path: &Path) -> Result<KeyMaterial, FsError> {
+            Ok(KeyMaterial::new("k2", [2u8; 32]))
+        }
+
+        fn key_by_id(&self, id: &str) -> Result<KeyMaterial, FsError> {
+            Ok(KeyMaterial::new(id, [0u8; 32]))
+        }
+    }
+
+    #[test]
+    fn key_material_debug_hides_key() {
+        let key = KeyMaterial::new("k1", b"secret-bytes".to_vec());
+        let debug = format!("{key:?}");
+        assert!(debug.contains("k1"));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    fn reencryption_needed_for_old_key() {
+        let path = Path::new("/f");
+        let old = EncryptionInfo::new("aes-256-gcm", "k1", vec![0; 12]);
+        let current = EncryptionInfo::new("aes-256-gcm", "k2", vec![0; 12]);
+        assert!(Keys.needs_reencryption(path, &old).unwrap());
+        assert!(!Keys.needs_reencryption(path, &current).unwrap());
+        assert!(matches!(Keys.rotate(), Err(FsError::NotSupported { .. })));
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let info = EncryptionInfo::new("xchacha20-poly1305", "key/7", vec![1; 24]);
+        let header = info.encode_header().unwrap();
+        assert_eq!(header.len(), 5 + 2 + 18 + 2 + 5 + 2 + 24);
+        assert_eq!(
+            EncryptionInfo::decode_header(&header).unwrap(),
+            Some((info, header.len()))
+        );
+    }
+
+    #[test]
+    fn plaintext_has_no_header() {
+        assert_eq!(EncryptionInfo::decode_header(b"hello").unwrap(), None);
+        assert_eq!(EncryptionInfo::decode_header(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let header = EncryptionInfo::new("aes-256-gcm", "k1", vec![0; 12])
+            .encode_header()
+            .unwrap();
+        for len in 4..header.len() {
+            assert!(matches!(
+                EncryptionInfo::decode_header(&header[..len]),
+                Err(FsError::Deserialization(_))
+            ));
+        }
+        let mut future = header;
+        future[4] = 9;
+        assert!(matches!(
+            EncryptionInfo::decode_header(&future),
+            Err(FsError::Deserialization(_))
+        ));
+    }
+
+    #[test]
+    fn oversized_field_cannot_be_encoded() {
+        let info = EncryptionInfo::new("aes-256-gcm", "k1", vec![0; 70_000]);
+        assert!(matches!(
+            info.encode_header(),
+            Err(FsError::Serialization(_))
+        ));
+    }
+}
@@ -15,11 +15,11 @@
 //!
 //! | Category | Variants | Description |
 //! |----------|----------|-------------|
-//! | Path/File | `NotFound`, `AlreadyExists`, `NotAFile`, `NotADirectory` | Path existence and type errors |
+//! | Path/File | `NotFound`, `AlreadyExists`, `NotAFile`, `NotADirectory`, `CrossesDevices`, `TooManyLinks`, `FilenameTooLong` | Path existence and type errors |
 //! | Permission | `PermissionDenied`, `AccessDenied`, `ReadOnly` | Access control errors |
 //! | Resource | `QuotaExceeded`, `FileSizeExceeded`, `RateLimitExceeded` | Limit violations |
 //! | Data | `InvalidData`, `CorruptedData`, `IntegrityError` | Content problems |
-//! | Operation | `NotSupported`, `Conflict`, `Backend` | Backend/operation failures |
+//! | Operation | `NotSupported`, `Conflict`, `TimedOut`, `Cancelled`, `Backend` | Backend/operation failures |
 //!
 //! ## Quick Example
 //!
@@ -28,8 +28,9 @@
 //! use std::path::PathBuf;
 //!
 //! // Errors include the path that caused the problem
-//! let err = FsError::NotFound { path: PathBuf::from("/missing.txt") };
+//! let err = FsError::not_found("read", "/missing.txt");
 //! assert!(err.to_string().contains("/missing.txt"));
+//! assert_eq!(err.operation(), Some("read"));
 //!
 //! // Permission errors include the operation
 //! let err = FsError::PermissionDenied {
@@ -51,8 +52,24 @@
 //! let fs_err: FsError = io_err.into();
 //! assert!(matches!(fs_err, FsError::NotFound { .. }));
 //! ```
+//!
+//! ## Operation Context
+//!
+//! Path-centric variants carry the `operation` that failed, exposed through
+//! [`FsError::operation`], so a layered stack can tell a missing rename
+//! source from a missing xattr target. On `NotFound`, `NotAFile`,
+//! `NotADirectory` and `DirectoryNotEmpty` it is optional and left out of
+//! the message, which keeps their display unchanged. Constructor helpers
+//! such as [`FsError::not_found`] keep call sites short,
+//! [`FsError::with_operation`] lets a wrapper relabel an inner error, and
+//! [`FsError::map_paths`] lets it rewrite the paths.
+//!
+//! The reverse conversion, `From<FsError> for std::io::Error`, maps variants
+//! to the closest [`std::io::ErrorKind`] and unwraps [`FsError::Io`].
 
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Comprehensive filesystem error type.
 ///
@@ -71,7 +88,7 @@ use std::path::PathBuf;
 ///
 /// fn handle_error(err: FsError) {
 ///     match err {
-///         FsError::NotFound { path } => println!("Not found: {}", path.display()),
+///         FsError::NotFound { path, .. } => println!("Not found: {}", path.display()),
 ///         FsError::PermissionDenied { path, operation } => {
 ///             println!("Permission denied for {} on {}", operation, path.display())
 ///         }
@@ -97,8 +114,29 @@ use std::path::PathBuf;
 ///
 /// The [`Io`](FsError::Io) variant wraps `std::io::Error` with the `#[source]`
 /// attribute, enabling error chain traversal via `std::error::Error::source()`.
+///
+/// # Cloning
+///
+/// `FsError` is `Clone`, so a caching layer can hand the same failure to every
+/// waiter. The `Io` source is held in an `Arc`, making clones cheap.
+///
+/// # Serialization
+///
+/// With the `serde` feature, `FsError` implements `Serialize` and `Deserialize`
+/// so remote backends can send errors over the wire. Two parts are lossy:
+/// operation names this crate doesn't know arrive as `"remote"`, and `Io`
+/// sources are rebuilt from their kind and message.
+///
+/// ```rust
+/// use anyfs_backend::FsError;
+/// use std::io;
+///
+/// let err = FsError::io("read", "/data", io::Error::new(io::ErrorKind::Other, "disk gone"));
+/// let waiters: Vec<FsError> = (0..3).map(|_| err.clone()).collect();
+/// assert!(waiters.iter().all(|e| e.to_string() == err.to_string()));
+/// ```
 #[non_exhaustive]
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum FsError {
     // Path/File Errors
     /// Path does not exist.
@@ -106,6 +144,8 @@ pub enum FsError {
     NotFound {
         /// The path that was not found.
         path: PathBuf,
+        /// The operation that failed, if known.
+        operation: Option<&'static str>,
     },
 
     /// A threat was detected (e.g., path traversal, malicious content).
@@ -131,6 +171,8 @@ pub enum FsError {
     NotAFile {
         /// The path that is not a file.
         path: PathBuf,
+        /// The operation that failed, if known.
+        operation: Option<&'static str>,
     },
 
     /// Expected a directory but found something else.
@@ -138,6 +180,8 @@ pub enum FsError {
     NotADirectory {
         /// The path that is not a directory.
         path: PathBuf,
+        /// The operation that failed, if known.
+        operation: Option<&'static str>,
     },
 
     /// Directory is not empty when it should be.
@@ -145,6 +189,42 @@ pub enum FsError {
     DirectoryNotEmpty {
         /// The path to the non-empty directory.
         path: PathBuf,
+        /// The operation that failed, if known.
+        operation: Option<&'static str>,
+    },
+
+    /// A rename or link would cross a filesystem or storage boundary.
+    ///
+    /// Callers usually fall back to copy-and-delete (see
+    /// [`FsExt::move_entry`](crate::FsExt::move_entry)).
+    #[error("{operation}: crosses devices: {from} -> {to}")]
+    CrossesDevices {
+        /// The source path.
+        from: PathBuf,
+        /// The destination path.
+        to: PathBuf,
+        /// The operation that failed.
+        operation: &'static str,
+    },
+
+    /// The hard link count of the target would exceed the backend's limit.
+    #[error("{operation}: too many links: {path}")]
+    TooManyLinks {
+        /// The path whose link count is exhausted.
+        path: PathBuf,
+        /// The operation that failed.
+        operation: &'static str,
+    },
+
+    /// A path component is longer than the backend allows.
+    #[error("{operation}: filename too long: {path} (max {max})")]
+    FilenameTooLong {
+        /// The offending path.
+        path: PathBuf,
+        /// Maximum component length in bytes.
+        max: usize,
+        /// The operation that failed.
+        operation: &'static str,
     },
 
     /// Inode does not exist.
@@ -154,8 +234,8 @@ pub enum FsError {
         inode: u64,
     },
 
-    /// File handle is invalid or closed.
-    #[error("invalid handle: {}", handle.0)]
+    /// File handle is invalid, closed, or from a stale generation.
+    #[error("invalid handle: {} (generation {})", handle.id(), handle.generation())]
     InvalidHandle {
         /// The invalid handle.
         handle: crate::Handle,
@@ -290,6 +370,22 @@ pub enum FsError {
         path: PathBuf,
     },
 
+    /// The operation did not complete within its deadline.
+    #[error("{operation}: timed out: {path}")]
+    TimedOut {
+        /// The path involved in the operation.
+        path: PathBuf,
+        /// The operation that timed out.
+        operation: &'static str,
+    },
+
+    /// The operation was cancelled before it completed.
+    #[error("{operation}: cancelled")]
+    Cancelled {
+        /// The operation that was cancelled.
+        operation: &'static str,
+    },
+
     /// Generic backend error.
     #[error("backend error: {0}")]
     Backend(String),
@@ -301,18 +397,441 @@ pub enum FsError {
         operation: &'static str,
         /// The path involved in the operation.
         path: PathBuf,
-        /// The underlying I/O error.
+        /// The underlying I/O error, shared so the error stays cheap to clone.
         #[source]
-        source: std::io::Error,
+        source: Arc<std::io::Error>,
     },
 }
 
+impl FsError {
+    /// [`FsError::NotFound`] for `operation` on `path`.
+    pub fn not_found(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::NotFound {
+            path: path.into(),
+            operation: Some(operation),
+        }
+    }
+
+    /// [`FsError::AlreadyExists`] for `operation` on `path`.
+    pub fn already_exists(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::AlreadyExists {
+            path: path.into(),
+            operation,
+        }
+    }
+
+    /// [`FsError::NotAFile`] for `operation` on `path`.
+    pub fn not_a_file(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::NotAFile {
+            path: path.into(),
+            operation: Some(operation),
+        }
+    }
+
+    /// [`FsError::NotADirectory`] for `operation` on `path`.
+    pub fn not_a_directory(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::NotADirectory {
+            path: path.into(),
+            operation: Some(operation),
+        }
+    }
+
+    /// [`FsError::DirectoryNotEmpty`] for `operation` on `path`.
+    pub fn directory_not_empty(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::DirectoryNotEmpty {
+            path: path.into(),
+            operation: Some(operation),
+        }
+    }
+
+    /// [`FsError::Io`] wrapping `source` for `operation` on `path`.
+    pub fn io(operation: &'static str, path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            operation,
+            path: path.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// [`FsError::PermissionDenied`] for `operation` on `path`.
+    pub fn permission_denied(operation: &'static str, path: impl Into<PathBuf>) -> Self {
+        Self::PermissionDenied {
+            path: path.into(),
+            operation,
+        }
+    }
+
+    /// The operation that failed, for variants that record one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsError;
+    ///
+    /// assert_eq!(FsError::not_found("rename", "/a").operation(), Some("rename"));
+    /// assert_eq!(FsError::Backend("boom".into()).operation(), None);
+    /// ```
+    pub fn operation(&self) -> Option<&'static str> {
+        match self {
+            Self::NotFound { operation, .. }
+            | Self::NotAFile { operation, .. }
+            | Self::NotADirectory { operation, .. }
+            | Self::DirectoryNotEmpty { operation, .. } => *operation,
+            Self::AlreadyExists { operation, .. }
+            | Self::CrossesDevices { operation, .. }
+            | Self::TooManyLinks { operation, .. }
+            | Self::FilenameTooLong { operation, .. }
+            | Self::PermissionDenied { operation, .. }
+            | Self::ReadOnly { operation }
+            | Self::FeatureNotEnabled { operation, .. }
+            | Self::NotSupported { operation }
+            | Self::TimedOut { operation, .. }
+            | Self::Cancelled { operation }
+            | Self::Io { operation, .. } => Some(operation),
+            _ => None,
+        }
+    }
+
+    /// The path involved, for variants that record one.
+    ///
+    /// For [`FsError::CrossesDevices`] this is the source path.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::NotFound { path, .. }
+            | Self::ThreatDetected { path, .. }
+            | Self::AlreadyExists { path, .. }
+            | Self::NotAFile { path, .. }
+            | Self::NotADirectory { path, .. }
+            | Self::DirectoryNotEmpty { path, .. }
+            | Self::CrossesDevices { from: path, .. }
+            | Self::TooManyLinks { path, .. }
+            | Self::FilenameTooLong { path, .. }
+            | Self::XattrNotFound { path, .. }
+            | Self::PermissionDenied { path, .. }
+            | Self::AccessDenied { path, .. }
+            | Self::FileSizeExceeded { path, .. }
+            | Self::InvalidData { path, .. }
+            | Self::CorruptedData { path, .. }
+            | Self::IntegrityError { path }
+            | Self::Conflict { path }
+            | Self::TimedOut { path, .. }
+            | Self::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Replace the recorded operation (builder style); variants without an
+    /// operation are returned unchanged.
+    ///
+    /// Lets middleware report the operation the caller asked for when an
+    /// inner call for a different operation failed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsError;
+    ///
+    /// // A copy emulated with read + write surfaces as a failed copy
+    /// let err = FsError::permission_denied("read", "/src").with_operation("copy");
+    /// assert_eq!(err.to_string(), "copy: permission denied: /src");
+    /// ```
+    pub fn with_operation(mut self, op: &'static str) -> Self {
+        match &mut self {
+            Self::NotFound { operation, .. }
+            | Self::NotAFile { operation, .. }
+            | Self::NotADirectory { operation, .. }
+            | Self::DirectoryNotEmpty { operation, .. } => *operation = Some(op),
+            Self::AlreadyExists { operation, .. }
+            | Self::CrossesDevices { operation, .. }
+            | Self::TooManyLinks { operation, .. }
+            | Self::FilenameTooLong { operation, .. }
+            | Self::PermissionDenied { operation, .. }
+            | Self::ReadOnly { operation }
+            | Self::FeatureNotEnabled { operation, .. }
+            | Self::NotSupported { operation }
+            | Self::TimedOut { operation, .. }
+            | Self::Cancelled { operation }
+            | Self::Io { operation, .. } => *operation = op,
+            _ => {}
+        }
+        self
+    }
+
+    /// Rewrite every recorded path with `f` (builder style); variants
+    /// without a path are returned unchanged.
+    ///
+    /// Lets middleware that translates paths report them as the caller
+    /// wrote them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsError;
+    /// use std::path::Path;
+    ///
+    /// let err = FsError::not_found("read", "/tenants/acme/notes.txt")
+    ///     .map_paths(|p| Path::new("/").join(p.strip_prefix("/tenants/acme").unwrap()));
+    /// assert_eq!(err.to_string(), "not found: /notes.txt");
+    /// ```
+    pub fn map_paths(mut self, mut f: impl FnMut(&Path) -> PathBuf) -> Self {
+        match &mut self {
+            Self::CrossesDevices { from, to, .. } => {
+                *from = f(from);
+                *to = f(to);
+            }
+            Self::NotFound { path, .. }
+            | Self::ThreatDetected { path, .. }
+            | Self::AlreadyExists { path, .. }
+            | Self::NotAFile { path, .. }
+            | Self::NotADirectory { path, .. }
+            | Self::DirectoryNotEmpty { path, .. }
+            | Self::TooManyLinks { path, .. }
+            | Self::FilenameTooLong { path, .. }
+            | Self::XattrNotFound { path, .. }
+            | Self::PermissionDenied { path, .. }
+            | Self::AccessDenied { path, .. }
+            | Self::FileSizeExceeded { path, .. }
+            | Self::InvalidData { path, .. }
+            | Self::CorruptedData { path, .. }
+            | Self::IntegrityError { path }
+            | Self::Conflict { path }
+            | Self::TimedOut { path, .. }
+            | Self::Io { path, .. } => *path = f(path),
+            _ => {}
+        }
+        self
+    }
+
+    /// The POSIX `errno` value that best describes this error.
+    ///
+    /// Values follow Linux numbering, which is what FUSE expects. [`FsError::Io`]
+    /// reports the underlying OS error when it is a Unix `errno` this crate
+    /// can translate, and otherwise the closest match for its
+    /// [`ErrorKind`](std::io::ErrorKind) (`EIO` if none fits). Windows error
+    /// codes are never passed through.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsError;
+    ///
+    /// assert_eq!(FsError::not_found("lookup", "/missing").errno(), 2); // ENOENT
+    /// assert_eq!(FsError::Cancelled { operation: "read" }.errno(), 125); // ECANCELED
+    /// ```
+    pub fn errno(&self) -> i32 {
+        match self {
+            Self::NotFound { .. } | Self::InodeNotFound { .. } => errno::ENOENT,
+            Self::AlreadyExists { .. } => errno::EEXIST,
+            Self::NotAFile { .. } => errno::EISDIR,
+            Self::NotADirectory { .. } => errno::ENOTDIR,
+            Self::DirectoryNotEmpty { .. } => errno::ENOTEMPTY,
+            Self::CrossesDevices { .. } => errno::EXDEV,
+            Self::TooManyLinks { .. } => errno::EMLINK,
+            Self::FilenameTooLong { .. } => errno::ENAMETOOLONG,
+            Self::InvalidHandle { .. } => errno::EBADF,
+            Self::XattrNotFound { .. } => errno::ENODATA,
+            Self::ThreatDetected { .. }
+            | Self::PermissionDenied { .. }
+            | Self::AccessDenied { .. }
+            | Self::InvalidPassword => errno::EACCES,
+            Self::ReadOnly { .. } => errno::EROFS,
+            Self::FeatureNotEnabled { .. } => errno::EOPNOTSUPP,
+            Self::NotSupported { .. } => errno::ENOSYS,
+            Self::QuotaExceeded { .. } => errno::EDQUOT,
+            Self::FileSizeExceeded { .. } => errno::EFBIG,
+            Self::RateLimitExceeded { .. } => errno::EAGAIN,
+            Self::InvalidData { .. } => errno::EINVAL,
+            Self::Conflict { .. } => errno::EBUSY,
+            Self::TimedOut { .. } => errno::ETIMEDOUT,
+            Self::Cancelled { .. } => errno::ECANCELED,
+            Self::Io { source, .. } => {
+                linux_errno(source).unwrap_or_else(|| errno_for_kind(source.kind()))
+            }
+            Self::CorruptedData { .. }
+            | Self::IntegrityError { .. }
+            | Self::Serialization(_)
+            | Self::Deserialization(_)
+            | Self::Backend(_) => errno::EIO,
+        }
+    }
+}
+
+/// Linux `errno` values used by [`FsError::errno`].
+mod errno {
+    pub(super) const ENOENT: i32 = 2;
+    pub(super) const EIO: i32 = 5;
+    pub(super) const EBADF: i32 = 9;
+    pub(super) const EAGAIN: i32 = 11;
+    pub(super) const EACCES: i32 = 13;
+    pub(super) const EBUSY: i32 = 16;
+    pub(super) const EEXIST: i32 = 17;
+    pub(super) const EXDEV: i32 = 18;
+    pub(super) const ENOTDIR: i32 = 20;
+    pub(super) const EISDIR: i32 = 21;
+    pub(super) const EINVAL: i32 = 22;
+    pub(super) const EFBIG: i32 = 27;
+    pub(super) const EROFS: i32 = 30;
+    pub(super) const EMLINK: i32 = 31;
+    pub(super) const ENAMETOOLONG: i32 = 36;
+    pub(super) const ENOSYS: i32 = 38;
+    pub(super) const ENOTEMPTY: i32 = 39;
+    pub(super) const ENODATA: i32 = 61;
+    pub(super) const EOPNOTSUPP: i32 = 95;
+    pub(super) const ETIMEDOUT: i32 = 110;
+    pub(super) const EDQUOT: i32 = 122;
+    pub(super) const ECANCELED: i32 = 125;
+}
+
+/// Linux numbering for `error`'s OS error code, when the host reports Unix
+/// `errno` values this crate can translate.
+///
+/// Linux and Android already use Linux numbering. Other Unixes share codes
+/// 1-34 from V7 Unix, except `EAGAIN` (11); of the rest only
+/// `ENAMETOOLONG` is translated. Codes from other targets (e.g. Win32
+/// errors) are never `errno` values.
+fn linux_errno(error: &std::io::Error) -> Option<i32> {
+    let code = error.raw_os_error()?;
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        return Some(code);
+    }
+    if !cfg!(unix) {
+        return None;
+    }
+    let name_too_long = if cfg!(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    )) {
+        Some(63)
+    } else if cfg!(any(target_os = "solaris", target_os = "illumos")) {
+        Some(78)
+    } else {
+        None
+    };
+    match code {
+        1..=10 | 12..=34 => Some(code),
+        _ if Some(code) == name_too_long => Some(errno::ENAMETOOLONG),
+        _ => None,
+    }
+}
+
+/// The `errno` closest to an [`std::io::ErrorKind`].
+fn errno_for_kind(kind: std::io::ErrorKind) -> i32 {
+    match kind {
+        std::io::ErrorKind::NotFound => errno::ENOENT,
+        std::io::ErrorKind::PermissionDenied => errno::EACCES,
+        std::io::ErrorKind::AlreadyExists => errno::EEXIST,
+        std::io::ErrorKind::WouldBlock => errno::EAGAIN,
+        std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => errno::EINVAL,
+        std::io::ErrorKind::TimedOut => errno::ETIMEDOUT,
+        std::io::ErrorKind::Unsupported => errno::ENOSYS,
+        _ => errno::EIO,
+    }
+}
+
+/// A directory entry that could not be read, yielded per item by
+/// [`ReadDirIter::next_detailed`](crate::ReadDirIter::next_detailed).
+///
+/// Carries the entry's name when the backend knows it, so a listing can
+/// report which entry failed and carry on with the rest.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{DirEntryError, FsError};
+/// use std::path::PathBuf;
+///
+/// let err = DirEntryError {
+///     name: Some("bad\u{fffd}name".into()),
+///     source: FsError::InvalidData {
+///         path: PathBuf::from("/dir"),
+///         details: "undecodable name".into(),
+///     },
+/// };
+/// assert!(err.to_string().starts_with("entry \"bad"));
+///
+/// // Converts to the plain error for `?` in FsError contexts
+/// let plain: FsError = err.into();
+/// assert!(matches!(plain, FsError::InvalidData { .. }));
+/// ```
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{}{source}", DirEntryError::describe_name(.name))]
+pub struct DirEntryError {
+    /// Name of the failed entry, if the backend could determine it.
+    pub name: Option<OsString>,
+    /// What went wrong.
+    #[source]
+    pub source: FsError,
+}
+
+impl DirEntryError {
+    /// Error for an entry whose name is unknown.
+    pub fn unnamed(source: FsError) -> Self {
+        Self { name: None, source }
+    }
+
+    /// Error for the entry called `name`.
+    pub fn named(name: impl Into<OsString>, source: FsError) -> Self {
+        Self {
+            name: Some(name.into()),
+            source,
+        }
+    }
+
+    fn describe_name(name: &Option<OsString>) -> String {
+        match name {
+            Some(name) => format!("entry {:?}: ", name),
+            None => String::new(),
+        }
+    }
+}
+
+impl From<FsError> for DirEntryError {
+    fn from(source: FsError) -> Self {
+        Self::unnamed(source)
+    }
+}
+
+impl From<DirEntryError> for FsError {
+    fn from(error: DirEntryError) -> Self {
+        error.source
+    }
+}
+
 impl From<std::io::Error> for FsError {
     fn from(error: std::io::Error) -> Self {
+        // Errno values with no stable ErrorKind on our MSRV
+        match linux_errno(&error) {
+            Some(errno::EXDEV) => {
+                return FsError::CrossesDevices {
+                    from: PathBuf::new(),
+                    to: PathBuf::new(),
+                    operation: "io",
+                }
+            }
+            Some(errno::EMLINK) => {
+                return FsError::TooManyLinks {
+                    path: PathBuf::new(),
+                    operation: "io",
+                }
+            }
+            Some(errno::ENAMETOOLONG) => {
+                return FsError::FilenameTooLong {
+                    path: PathBuf::new(),
+                    max: 0,
+                    operation: "io",
+                }
+            }
+            _ => {}
+        }
         // Convert common io::ErrorKind to more specific FsError variants when possible
         match error.kind() {
             std::io::ErrorKind::NotFound => FsError::NotFound {
                 path: PathBuf::new(),
+                operation: Some("io"),
             },
             std::io::ErrorKind::PermissionDenied => FsError::PermissionDenied {
                 path: PathBuf::new(),
@@ -322,11 +841,602 @@ impl From<std::io::Error> for FsError {
                 path: PathBuf::new(),
                 operation: "io",
             },
-            _ => FsError::Io {
-                operation: "io",
+            std::io::ErrorKind::TimedOut => FsError::TimedOut {
                 path: PathBuf::new(),
-                source: error,
+                operation: "io",
             },
+            _ => FsError::io("io", PathBuf::new(), error),
+        }
+    }
+}
+
+impl From<FsError> for std::io::Error {
+    fn from(error: FsError) -> Self {
+        let error = match error {
+            FsError::Io { source, .. } => {
+                // Hand back the original when no clone shares it; otherwise
+                // rebuild an equivalent error
+                return Arc::try_unwrap(source).unwrap_or_else(|shared| {
+                    match shared.raw_os_error() {
+                        Some(code) => std::io::Error::from_raw_os_error(code),
+                        None => std::io::Error::new(shared.kind(), shared.to_string()),
+                    }
+                });
+            }
+            other => other,
+        };
+        let kind = match &error {
+            FsError::NotFound { .. } => std::io::ErrorKind::NotFound,
+            FsError::AlreadyExists { .. } => std::io::ErrorKind::AlreadyExists,
+            FsError::PermissionDenied { .. }
+            | FsError::AccessDenied { .. }
+            | FsError::ReadOnly { .. } => std::io::ErrorKind::PermissionDenied,
+            FsError::InvalidHandle { .. } | FsError::InvalidData { .. } => {
+                std::io::ErrorKind::InvalidInput
+            }
+            FsError::NotSupported { .. } => std::io::ErrorKind::Unsupported,
+            FsError::TimedOut { .. } => std::io::ErrorKind::TimedOut,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
+
+// =============================================================================
+// Serde Support (Feature-Gated)
+// =============================================================================
+
+/// Serialization of [`FsError`] for remote backends.
+///
+/// Errors travel as an internally tagged `{"kind": "...", ...}` object that
+/// mirrors the variant fields. Two parts are lossy:
+///
+/// - `operation` and `feature` are `&'static str`, so received names are
+///   matched against the operations this crate knows about; anything else
+///   becomes `"remote"`.
+/// - `Io` sends the `ErrorKind`, message and raw OS code. The receiver
+///   rebuilds the error from the kind and message; the OS code is only
+///   informational, since the peer may number errors differently.
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::FsError;
+    use crate::Handle;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    /// Stand-in for operation names a peer sends that we don't recognise.
+    const UNKNOWN_OPERATION: &str = "remote";
+
+    /// Operation and feature names that survive a round trip unchanged.
+    const KNOWN_NAMES: &[&str] = &[
+        "acquire_lease",
+        "append",
+        "append_handle",
+        "barrier",
+        "branch",
+        "branches",
+        "cache_policy",
+        "cache_stats",
+        "canonicalize",
+        "chunked_write",
+        "clear_expiry",
+        "close",
+        "close_all",
+        "close_dir",
+        "copy",
+        "copy_up",
+        "copy_with",
+        "copy_with (preserve_times)",
+        "create",
+        "create_dir",
+        "create_dir_all",
+        "create_dir_with",
+        "create_temp_dir_in",
+        "dir_len",
+        "discard_branch",
+        "dup",
+        "exists",
+        "exists_fresh",
+        "expiry",
+        "export_id",
+        "export_version",
+        "fdatasync",
+        "file_id",
+        "flush_cache",
+        "freeze",
+        "fsync",
+        "get_flags",
+        "get_xattr",
+        "handle_len",
+        "hard_link",
+        "inode_to_path",
+        "invalidate",
+        "invalidate_all",
+        "io",
+        "io_stats",
+        "is_dir_empty",
+        "layers",
+        "lease_hint",
+        "link_handle",
+        "list_xattr",
+        "lock",
+        "lock_owned",
+        "lookup",
+        "merge",
+        "metadata",
+        "metadata_at",
+        "metadata_by_inode",
+        "metadata_lite",
+        "metadata_mask",
+        "metadata_with_tag",
+        "mkdir_at",
+        "mkfifo",
+        "move_entry",
+        "move_entry (fifo)",
+        "move_entry (symlink)",
+        "not_found_hint",
+        "open",
+        "open_anonymous",
+        "open_at",
+        "open_by_id",
+        "open_dir",
+        "open_dir_at",
+        "open_handles",
+        "open_read",
+        "open_write",
+        "open_write_sized",
+        "path_by_id",
+        "path_to_inode",
+        "read",
+        "read_at",
+        "read_dir",
+        "read_dir_inode",
+        "read_dir_with",
+        "read_handle",
+        "read_link",
+        "read_range",
+        "read_to_string",
+        "release_lease",
+        "remove_dir",
+        "remove_dir_all",
+        "remove_file",
+        "remove_xattr",
+        "rename",
+        "resolve_export_id",
+        "rotate_key",
+        "safe_replace",
+        "seek",
+        "seek_handle",
+        "seq",
+        "serde",
+        "set_attr",
+        "set_cache_policy",
+        "set_expiry",
+        "set_flags",
+        "set_modified",
+        "set_permissions",
+        "set_storage_class",
+        "set_times",
+        "set_xattr",
+        "snapshot (fifo)",
+        "soft_canonicalize",
+        "special file",
+        "statfs",
+        "storage_class",
+        "sweep",
+        "symlink",
+        "symlink_metadata",
+        "sync",
+        "sync_handle",
+        "touch_times",
+        "truncate",
+        "try_lock",
+        "try_lock_owned",
+        "unlink_at",
+        "unlock",
+        "unlock_owned",
+        "usage",
+        "which_layer",
+        "wire protocol version",
+        "write",
+        "write_at",
+        "write_handle",
+        "write_with",
+    ];
+
+    fn intern(name: &str) -> &'static str {
+        KNOWN_NAMES
+            .iter()
+            .find(|known| **known == name)
+            .copied()
+            .unwrap_or(UNKNOWN_OPERATION)
+    }
+
+    /// `std::io::ErrorKind` by its `Debug` name (kinds stable on our MSRV).
+    fn io_kind(name: &str) -> std::io::ErrorKind {
+        use std::io::ErrorKind::*;
+        match name {
+            "NotFound" => NotFound,
+            "PermissionDenied" => PermissionDenied,
+            "ConnectionRefused" => ConnectionRefused,
+            "ConnectionReset" => ConnectionReset,
+            "ConnectionAborted" => ConnectionAborted,
+            "NotConnected" => NotConnected,
+            "AddrInUse" => AddrInUse,
+            "AddrNotAvailable" => AddrNotAvailable,
+            "BrokenPipe" => BrokenPipe,
+            "AlreadyExists" => AlreadyExists,
+            "WouldBlock" => WouldBlock,
+            "InvalidInput" => InvalidInput,
+            "InvalidData" => InvalidData,
+            "TimedOut" => TimedOut,
+            "WriteZero" => WriteZero,
+            "Interrupted" => Interrupted,
+            "Unsupported" => Unsupported,
+            "UnexpectedEof" => UnexpectedEof,
+            "OutOfMemory" => OutOfMemory,
+            _ => Other,
+        }
+    }
+
+    /// Wire form of [`FsError`], with owned strings in place of `&'static str`.
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "kind")]
+    enum Repr {
+        NotFound {
+            path: PathBuf,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            operation: Option<String>,
+        },
+        ThreatDetected {
+            path: PathBuf,
+            reason: String,
+        },
+        AlreadyExists {
+            path: PathBuf,
+            operation: String,
+        },
+        NotAFile {
+            path: PathBuf,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            operation: Option<String>,
+        },
+        NotADirectory {
+            path: PathBuf,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            operation: Option<String>,
+        },
+        DirectoryNotEmpty {
+            path: PathBuf,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            operation: Option<String>,
+        },
+        CrossesDevices {
+            from: PathBuf,
+            to: PathBuf,
+            operation: String,
+        },
+        TooManyLinks {
+            path: PathBuf,
+            operation: String,
+        },
+        FilenameTooLong {
+            path: PathBuf,
+            max: usize,
+            operation: String,
+        },
+        InodeNotFound {
+            inode: u64,
+        },
+        InvalidHandle {
+            handle: Handle,
+        },
+        XattrNotFound {
+            path: PathBuf,
+            name: String,
+        },
+        PermissionDenied {
+            path: PathBuf,
+            operation: String,
+        },
+        AccessDenied {
+            path: PathBuf,
+            reason: String,
+        },
+        ReadOnly {
+            operation: String,
+        },
+        FeatureNotEnabled {
+            feature: String,
+            operation: String,
+        },
+        QuotaExceeded {
+            limit: u64,
+            requested: u64,
+            usage: u64,
+        },
+        FileSizeExceeded {
+            path: PathBuf,
+            size: u64,
+            limit: u64,
+        },
+        RateLimitExceeded {
+            limit: u32,
+            window_secs: u64,
+        },
+        InvalidData {
+            path: PathBuf,
+            details: String,
+        },
+        CorruptedData {
+            path: PathBuf,
+            details: String,
+        },
+        IntegrityError {
+            path: PathBuf,
+        },
+        Serialization {
+            message: String,
+        },
+        Deserialization {
+            message: String,
+        },
+        NotSupported {
+            operation: String,
+        },
+        InvalidPassword,
+        Conflict {
+            path: PathBuf,
+        },
+        TimedOut {
+            path: PathBuf,
+            operation: String,
+        },
+        Cancelled {
+            operation: String,
+        },
+        Backend {
+            message: String,
+        },
+        Io {
+            operation: String,
+            path: PathBuf,
+            io_kind: String,
+            message: String,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            raw_os_error: Option<i32>,
+        },
+    }
+
+    impl From<&FsError> for Repr {
+        fn from(error: &FsError) -> Self {
+            let op = |operation: &'static str| operation.to_string();
+            match error.clone() {
+                FsError::NotFound { path, operation } => Repr::NotFound {
+                    path,
+                    operation: operation.map(op),
+                },
+                FsError::ThreatDetected { path, reason } => Repr::ThreatDetected { path, reason },
+                FsError::AlreadyExists { path, operation } => Repr::AlreadyExists {
+                    path,
+                    operation: op(operation),
+                },
+                FsError::NotAFile { path, operation } => Repr::NotAFile {
+                    path,
+                    operation: operation.map(op),
+                },
+                FsError::NotADirectory { path, operation } => Repr::NotADirectory {
+                    path,
+                    operation: operation.map(op),
+                },
+                FsError::DirectoryNotEmpty { path, operation } => Repr::DirectoryNotEmpty {
+                    path,
+                    operation: operation.map(op),
+                },
+                FsError::CrossesDevices {
+                    from,
+                    to,
+                    operation,
+                } => Repr::CrossesDevices {
+                    from,
+                    to,
+                    operation: op(operation),
+                },
+                FsError::TooManyLinks { path, operation } => Repr::TooManyLinks {
+                    path,
+                    operation: op(operation),
+                },
+                FsError::FilenameTooLong {
+                    path,
+                    max,
+                    operation,
+                } => Repr::FilenameTooLong {
+                    path,
+                    max,
+                    operation: op(operation),
+                },
+                FsError::InodeNotFound { inode } => Repr::InodeNotFound { inode },
+                FsError::InvalidHandle { handle } => Repr::InvalidHandle { handle },
+                FsError::XattrNotFound { path, name } => Repr::XattrNotFound { path, name },
+                FsError::PermissionDenied { path, operation } => Repr::PermissionDenied {
+                    path,
+                    operation: op(operation),
+                },
+                FsError::AccessDenied { path, reason } => Repr::AccessDenied { path, reason },
+                FsError::ReadOnly { operation } => Repr::ReadOnly {
+                    operation: op(operation),
+                },
+                FsError::FeatureNotEnabled { feature, operation } => Repr::FeatureNotEnabled {
+                    feature: op(feature),
+                    operation: op(operation),
+                },
+                FsError::QuotaExceeded {
+                    limit,
+                    requested,
+                    usage,
+                } => Repr::QuotaExceeded {
+                    limit,
+                    requested,
+                    usage,
+                },
+                FsError::FileSizeExceeded { path, size, limit } => {
+                    Repr::FileSizeExceeded { path, size, limit }
+                }
+                FsError::RateLimitExceeded { limit, window_secs } => {
+                    Repr::RateLimitExceeded { limit, window_secs }
+                }
+                FsError::InvalidData { path, details } => Repr::InvalidData { path, details },
+                FsError::CorruptedData { path, details } => Repr::CorruptedData { path, details },
+                FsError::IntegrityError { path } => Repr::IntegrityError { path },
+                FsError::Serialization(message) => Repr::Serialization { message },
+                FsError::Deserialization(message) => Repr::Deserialization { message },
+                FsError::NotSupported { operation } => Repr::NotSupported {
+                    operation: op(operation),
+                },
+                FsError::InvalidPassword => Repr::InvalidPassword,
+                FsError::Conflict { path } => Repr::Conflict { path },
+                FsError::TimedOut { path, operation } => Repr::TimedOut {
+                    path,
+                    operation: op(operation),
+                },
+                FsError::Cancelled { operation } => Repr::Cancelled {
+                    operation: op(operation),
+                },
+                FsError::Backend(message) => Repr::Backend { message },
+                FsError::Io {
+                    operation,
+                    path,
+                    source,
+                } => Repr::Io {
+                    operation: op(operation),
+                    path,
+                    io_kind: format!("{:?}", source.kind()),
+                    message: source.to_string(),
+                    raw_os_error: source.raw_os_error(),
+                },
+            }
+        }
+    }
+
+    impl From<Repr> for FsError {
+        fn from(repr: Repr) -> Self {
+            match repr {
+                Repr::NotFound { path, operation } => FsError::NotFound {
+                    path,
+                    operation: operation.as_deref().map(intern),
+                },
+                Repr::ThreatDetected { path, reason } => FsError::ThreatDetected { path, reason },
+                Repr::AlreadyExists { path, operation } => FsError::AlreadyExists {
+                    path,
+                    operation: intern(&operation),
+                },
+                Repr::NotAFile { path, operation } => FsError::NotAFile {
+                    path,
+                    operation: operation.as_deref().map(intern),
+                },
+                Repr::NotADirectory { path, operation } => FsError::NotADirectory {
+                    path,
+                    operation: operation.as_deref().map(intern),
+                },
+                Repr::DirectoryNotEmpty { path, operation } => FsError::DirectoryNotEmpty {
+                    path,
+                    operation: operation.as_deref().map(intern),
+                },
+                Repr::CrossesDevices {
+                    from,
+                    to,
+                    operation,
+                } => FsError::CrossesDevices {
+                    from,
+                    to,
+                    operation: intern(&operation),
+                },
+                Repr::TooManyLinks { path, operation } => FsError::TooManyLinks {
+                    path,
+                    operation: intern(&operation),
+                },
+                Repr::FilenameTooLong {
+                    path,
+                    max,
+                    operation,
+                } => FsError::FilenameTooLong {
+                    path,
+                    max,
+                    operation: intern(&operation),
+                },
+                Repr::InodeNotFound { inode } => FsError::InodeNotFound { inode },
+                Repr::InvalidHandle { handle } => FsError::InvalidHandle { handle },
+                Repr::XattrNotFound { path, name } => FsError::XattrNotFound { path, name },
+                Repr::PermissionDenied { path, operation } => FsError::PermissionDenied {
+                    path,
+                    operation: intern(&operation),
+                },
+                Repr::AccessDenied { path, reason } => FsError::AccessDenied { path, reason },
+                Repr::ReadOnly { operation } => FsError::ReadOnly {
+                    operation: intern(&operation),
+                },
+                Repr::FeatureNotEnabled { feature, operation } => FsError::FeatureNotEnabled {
+                    feature: intern(&feature),
+                    operation: intern(&operation),
+                },
+                Repr::QuotaExceeded {
+                    limit,
+                    requested,
+                    usage,
+                } => FsError::QuotaExceeded {
+                    limit,
+                    requested,
+                    usage,
+                },
+                Repr::FileSizeExceeded { path, size, limit } => {
+                    FsError::FileSizeExceeded { path, size, limit }
+                }
+                Repr::RateLimitExceeded { limit, window_secs } => {
+                    FsError::RateLimitExceeded { limit, window_secs }
+                }
+                Repr::InvalidData { path, details } => FsError::InvalidData { path, details },
+                Repr::CorruptedData { path, details } => FsError::CorruptedData { path, details },
+                Repr::IntegrityError { path } => FsError::IntegrityError { path },
+                Repr::Serialization { message } => FsError::Serialization(message),
+                Repr::Deserialization { message } => FsError::Deserialization(message),
+                Repr::NotSupported { operation } => FsError::NotSupported {
+                    operation: intern(&operation),
+                },
+                Repr::InvalidPassword => FsError::InvalidPassword,
+                Repr::Conflict { path } => FsError::Conflict { path },
+                Repr::TimedOut { path, operation } => FsError::TimedOut {
+                    path,
+                    operation: intern(&operation),
+                },
+                Repr::Cancelled { operation } => FsError::Cancelled {
+                    operation: intern(&operation),
+                },
+                Repr::Backend { message } => FsError::Backend(message),
+                Repr::Io {
+                    operation,
+                    path,
+                    io_kind: kind,
+                    message,
+                    raw_os_error: _,
+                } => FsError::Io {
+                    operation: intern(&operation),
+                    path,
+                    source: Arc::new(std::io::Error::new(io_kind(&kind), message)),
+                },
+            }
+        }
+    }
+
+    impl Serialize for FsError {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Repr::from(self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for FsError {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Repr::deserialize(deserializer).map(FsError::from)
         }
     }
 }
@@ -337,10 +1447,120 @@ mod tests {
 
     #[test]
     fn fs_error_not_found_display() {
+        let err = FsError::not_found("read", "/missing");
+        assert_eq!(err.to_string(), "not found: /missing");
+
+        // Literals from before the operation was recorded still display the same
         let err = FsError::NotFound {
             path: PathBuf::from("/missing"),
+            operation: None,
         };
         assert_eq!(err.to_string(), "not found: /missing");
+        assert_eq!(err.operation(), None);
+    }
+
+    #[test]
+    fn fs_error_errno_mapping() {
+        assert_eq!(FsError::not_found("lookup", "/x").errno(), 2);
+        assert_eq!(FsError::directory_not_empty("rmdir", "/d").errno(), 39);
+        assert_eq!(FsError::NotSupported { operation: "ioctl" }.errno(), 38);
+        let err = FsError::CrossesDevices {
+            from: PathBuf::from("/a"),
+            to: PathBuf::from("/mnt/b"),
+            operation: "rename",
+        };
+        assert_eq!(err.errno(), 18);
+        assert_eq!(err.to_string(), "rename: crosses devices: /a -> /mnt/b");
+        let err = FsError::io(
+            "read",
+            "/f",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "gone"),
+        );
+        assert_eq!(err.errno(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn fs_error_errno_passes_unix_codes_through() {
+        let err = FsError::io("write", "/f", std::io::Error::from_raw_os_error(28));
+        assert_eq!(err.errno(), 28); // ENOSPC
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn fs_error_errno_ignores_win32_codes() {
+        // ERROR_NO_MORE_FILES, not EXDEV
+        let err = FsError::io("read", "/f", std::io::Error::from_raw_os_error(18));
+        assert_eq!(err.errno(), 5);
+        let err: FsError = std::io::Error::from_raw_os_error(18).into();
+        assert!(matches!(err, FsError::Io { .. }));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn fs_error_from_io_maps_host_errno() {
+        // EXDEV and EMLINK share their numbers across Unixes; ENAMETOOLONG doesn't
+        let err: FsError = std::io::Error::from_raw_os_error(18).into();
+        assert!(matches!(err, FsError::CrossesDevices { .. }));
+        let err: FsError = std::io::Error::from_raw_os_error(31).into();
+        assert!(matches!(err, FsError::TooManyLinks { .. }));
+        let name_too_long = if cfg!(target_os = "macos") { 63 } else { 36 };
+        let err: FsError = std::io::Error::from_raw_os_error(name_too_long).into();
+        assert!(matches!(err, FsError::FilenameTooLong { .. }));
+        assert_eq!(err.errno(), 36);
+    }
+
+    #[test]
+    fn fs_error_from_io_maps_new_variants() {
+        let err: FsError = std::io::Error::new(std::io::ErrorKind::TimedOut, "slow").into();
+        assert!(matches!(err, FsError::TimedOut { .. }));
+        assert_eq!(
+            std::io::Error::from(err).kind(),
+            std::io::ErrorKind::TimedOut
+        );
+    }
+
+    #[test]
+    fn fs_error_constructors_record_context() {
+        let err = FsError::already_exists("create_dir", "/dir");
+        assert_eq!(err.operation(), Some("create_dir"));
+        assert_eq!(err.path(), Some(Path::new("/dir")));
+        assert_eq!(err.to_string(), "create_dir: already exists: /dir");
+
+        let err = err.with_operation("copy_with");
+        assert_eq!(err.to_string(), "copy_with: already exists: /dir");
+
+        // The operation is recorded but left out of the message
+        let err = FsError::not_a_directory("read_dir", "/file.txt");
+        assert!(
+            matches!(&err, FsError::NotADirectory { path, .. } if path == Path::new("/file.txt"))
+        );
+        assert_eq!(err.operation(), Some("read_dir"));
+        assert_eq!(err.to_string(), "not a directory: /file.txt");
+        let err = err.with_operation("walk");
+        assert_eq!(err.operation(), Some("walk"));
+        assert_eq!(err.to_string(), "not a directory: /file.txt");
+
+        let err = FsError::InodeNotFound { inode: 7 }.with_operation("walk");
+        assert_eq!(err.operation(), None);
+        assert_eq!(err.path(), None);
+    }
+
+    #[test]
+    fn fs_error_map_paths_rewrites_every_path() {
+        let err = FsError::CrossesDevices {
+            from: PathBuf::from("/a"),
+            to: PathBuf::from("/b"),
+            operation: "rename",
+        }
+        .map_paths(|p| Path::new("/mnt").join(p.strip_prefix("/").unwrap()));
+        assert!(matches!(
+            &err,
+            FsError::CrossesDevices { from, to, .. } if from == Path::new("/mnt/a") && to == Path::new("/mnt/b")
+        ));
+
+        let err = FsError::InodeNotFound { inode: 7 }.map_paths(|_| unreachable!());
+        assert!(matches!(err, FsError::InodeNotFound { inode: 7 }));
     }
 
     #[test]
@@ -352,6 +1572,14 @@ mod tests {
         assert_eq!(err.to_string(), "create: already exists: /exists");
     }
 
+    #[test]
+    fn fs_error_invalid_handle_display_includes_generation() {
+        let err = FsError::InvalidHandle {
+            handle: crate::Handle::new(5, 2),
+        };
+        assert_eq!(err.to_string(), "invalid handle: 5 (generation 2)");
+    }
+
     #[test]
     fn fs_error_quota_exceeded_display() {
         let err = FsError::QuotaExceeded {
@@ -385,6 +1613,264 @@ mod tests {
         assert!(matches!(fs_err, FsError::AlreadyExists { .. }));
     }
 
+    #[test]
+    fn io_error_from_fs_error_maps_kind() {
+        let err = std::io::Error::from(FsError::not_found("read", "/missing"));
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("/missing"));
+
+        let err = std::io::Error::from(FsError::NotSupported { operation: "seek" });
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn io_error_from_fs_error_unwraps_io_source() {
+        let err = std::io::Error::from(FsError::io(
+            "read",
+            "/f",
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof"),
+        ));
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fs_error_serde_round_trip() {
+        let err = FsError::already_exists("rename", "/a");
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(
+            json,
+            r#"{"kind":"AlreadyExists","path":"/a","operation":"rename"}"#
+        );
+        let back: FsError = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_string(), err.to_string());
+
+        let err = FsError::not_found("remove_dir", "/a");
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(
+            json,
+            r#"{"kind":"NotFound","path":"/a","operation":"remove_dir"}"#
+        );
+        let back: FsError = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.operation(), Some("remove_dir"));
+
+        // Errors serialized before the operation was recorded still load
+        let back: FsError = serde_json::from_str(r#"{"kind":"NotFound","path":"/a"}"#).unwrap();
+        assert_eq!(back.operation(), None);
+
+        let err = FsError::InvalidHandle {
+            handle: crate::Handle::new(3, 1),
+        };
+        let back: FsError = serde_json::from_str(&serde_json::to_string(&err).unwrap()).unwrap();
+        assert_eq!(back.to_string(), err.to_string());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fs_error_serde_is_lossy_for_unknown_names_and_io() {
+        let json = r#"{"kind":"ReadOnly","operation":"frobnicate"}"#;
+        let err: FsError = serde_json::from_str(json).unwrap();
+        assert_eq!(err.operation(), Some("remote"));
+
+        let err = FsError::io(
+            "read",
+            "/f",
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read"),
+        );
+        let back: FsError = serde_json::from_str(&serde_json::to_string(&err).unwrap()).unwrap();
+        assert_eq!(back.to_string(), "read failed for /f: short read");
+        let io_err = std::io::Error::from(back);
+        assert_eq!(io_err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn fs_error_serde_keeps_io_kind_and_message_over_the_os_code() {
+        // A peer's OS code means something else here; trust its kind and text
+        let json = r#"{"kind":"Io","operation":"read","path":"/f",
+            "io_kind":"NotFound","message":"gone","raw_os_error":18}"#;
+        let err: FsError = serde_json::from_str(json).unwrap();
+        assert_eq!(err.to_string(), "read failed for /f: gone");
+        let io_err = std::io::Error::from(err);
+        assert_eq!(io_err.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(io_err.raw_os_error(), None);
+    }
+
+    /// Operation names the crate's own code passes to `FsError`, found by
+    /// scanning the non-test, non-comment source for the places they appear:
+    /// `operation: "..."` fields, `Type::constructor("...")` calls, names
+    /// returned by `fn operation`, and literal arguments to helpers taking an
+    /// `operation`/`op: &'static str` parameter.
+    #[cfg(feature = "serde")]
+    fn emitted_operation_names() -> std::collections::BTreeSet<String> {
+        fn sources(dir: &Path, out: &mut Vec<String>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    sources(&path, out);
+                } else if path.extension().map_or(false, |ext| ext == "rs") {
+                    let text = std::fs::read_to_string(&path)
+                        .unwrap()
+                        .replace("\r\n", "\n");
+                    let code = match text.find("#[cfg(test)]\nmod tests") {
+                        Some(end) => &text[..end],
+                        None => &text,
+                    };
+                    let code: Vec<&str> = code
+                        .lines()
+                        .filter(|line| !line.trim_start().starts_with("//"))
+                        .collect();
+                    out.push(code.join("\n"));
+                }
+            }
+        }
+        /// String literals starting right after each `marker`.
+        fn literals_after<'a>(code: &'a str, marker: &str) -> Vec<&'a str> {
+            code.match_indices(marker)
+                .filter_map(|(at, _)| {
+                    let rest = &code[at + marker.len()..];
+                    rest.find('"').map(|end| &rest[..end])
+                })
+                .collect()
+        }
+        /// The text between `open` (just past a `(`) and its matching `)`.
+        fn call_args(code: &str, open: usize) -> &str {
+            let (mut depth, mut quoted) = (1, false);
+            for (i, c) in code[open..].char_indices() {
+                match c {
+                    '"' => quoted = !quoted,
+                    '(' if !quoted => depth += 1,
+                    ')' if !quoted => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return &code[open..open + i];
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            &code[open..]
+        }
+        fn looks_like_operation(name: &str) -> bool {
+            let base = name.split(" (").next().unwrap_or(name);
+            !base.is_empty()
+                && base
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        }
+
+        let mut files = Vec::new();
+        sources(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut files,
+        );
+        let mut names = std::collections::BTreeSet::new();
+        for code in &files {
+            names.extend(
+                literals_after(code, "operation: \"")
+                    .into_iter()
+                    .map(String::from),
+            );
+            names.extend(
+                literals_after(code, "with_operation(\"")
+                    .into_iter()
+                    .map(String::from),
+            );
+            // `FsError::not_found("read", ..)` and friends
+            for (at, _) in code.match_indices("(\"") {
+                let ident = code[..at]
+                    .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or("");
+                let owner = &code[..at - ident.len()];
+                let qualified = owner.ends_with("FsError::") || owner.ends_with("Self::");
+                if qualified && ident.starts_with(|c: char| c.is_ascii_lowercase()) {
+                    let rest = &code[at + 2..];
+                    names.insert(rest[..rest.find('"').unwrap()].to_string());
+                }
+            }
+            for (at, _) in code.match_indices("fn operation(&self) -> &'static str {") {
+                let body = &code[at..];
+                let body = &body[..body.find("\n    }").unwrap_or(body.len())];
+                for name in literals_after(body, "=> \"") {
+                    names.insert(name.to_string());
+                }
+            }
+        }
+        // Helpers that pass a literal through to an error's operation
+        let mut helpers = std::collections::BTreeSet::new();
+        for code in &files {
+            for (at, _) in code.match_indices("fn ") {
+                let rest = &code[at + 3..];
+                let name_end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let signature = &rest[..rest.find('{').unwrap_or(rest.len())];
+                if signature.contains("operation: &'static str")
+                    || signature.contains("op: &'static str")
+                {
+                    helpers.insert(rest[..name_end].to_string());
+                }
+            }
+        }
+        for code in &files {
+            for helper in &helpers {
+                let call = format!("{helper}(");
+                for (at, _) in code.match_indices(&call) {
+                    let before = code[..at].chars().next_back().unwrap_or(' ');
+                    if before.is_ascii_alphanumeric() || before == '_' {
+                        continue;
+                    }
+                    let args = call_args(code, at + call.len());
+                    let mut parts = args.split('"');
+                    parts.next();
+                    while let (Some(literal), _) = (parts.next(), parts.next()) {
+                        if looks_like_operation(literal) {
+                            names.insert(literal.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn every_emitted_operation_survives_serde() {
+        let names = emitted_operation_names();
+        assert!(names.contains("read") && names.contains("safe_replace"));
+        let lost: Vec<&String> = names
+            .iter()
+            .filter(|name| {
+                let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+                let err = FsError::NotSupported { operation: name };
+                let back: FsError =
+                    serde_json::from_str(&serde_json::to_string(&err).unwrap()).unwrap();
+                back.operation() != Some(name)
+            })
+            .collect();
+        assert!(lost.is_empty(), "operation names lost in serde: {lost:?}");
+    }
+
+    #[test]
+    fn cloned_io_errors_share_the_source() {
+        let err = FsError::io("read", "/f", std::io::Error::from_raw_os_error(5));
+        let copy = err.clone();
+        match (&err, &copy) {
+            (FsError::Io { source: a, .. }, FsError::Io { source: b, .. }) => {
+                assert!(Arc::ptr_eq(a, b));
+            }
+            _ => panic!("expected Io errors"),
+        }
+
+        // Converting back still works while the source is shared
+        let io_err = std::io::Error::from(copy);
+        assert_eq!(io_err.raw_os_error(), Some(5));
+        #[cfg(unix)]
+        assert_eq!(err.errno(), 5);
+    }
+
     #[test]
     fn fs_error_from_io_other() {
         let io_err = std::io::Error::new(std::io::ErrorKind::Other, "test");
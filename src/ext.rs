@@ -14,6 +14,24 @@
 //! |--------|-------------|
 //! | [`is_file`](FsExt::is_file) | Check if path is a regular file |
 //! | [`is_dir`](FsExt::is_dir) | Check if path is a directory |
+//! | [`content_type`](FsExt::content_type) | Read the MIME type xattr |
+//! | [`set_content_type`](FsExt::set_content_type) | Write the MIME type xattr |
+//! | [`xattr_expiry`](FsExt::xattr_expiry) | Read the expiry xattr, for [`FsExpiry`](crate::FsExpiry) emulation |
+//! | [`set_xattr_expiry`](FsExt::set_xattr_expiry) | Write or clear the expiry xattr |
+//! | [`set_modified`](FsExt::set_modified) | Set the modification time by path |
+//! | [`touch_times`](FsExt::touch_times) | Set the access and modification times to now |
+//! | [`walk`](FsExt::walk) | Iterate a directory tree recursively |
+//! | [`set_permissions_recursive`](FsExt::set_permissions_recursive) | `chmod -R` with separate file/dir modes |
+//! | [`make_readonly_tree`](FsExt::make_readonly_tree) | Clear every write bit in a tree |
+//! | [`remove_dir_all_report`](FsExt::remove_dir_all_report) | Best-effort recursive removal with per-entry failures |
+//! | [`remove_dir_all_report_with`](FsExt::remove_dir_all_report_with) | The same, reporting to a [`ProgressSink`] |
+//! | [`copy_with_fallback`](FsExt::copy_with_fallback) | Portable [`copy_with`](crate::FsWrite::copy_with) built on `copy` |
+//! | [`move_entry`](FsExt::move_entry) | `rename`, falling back to copy + verify + delete |
+//! | [`safe_replace`](FsExt::safe_replace) | Replace a path via backup-and-restore renames |
+//! | [`rename_case`](FsExt::rename_case) | Change only the case of a name, even on case-insensitive backends |
+//! | [`usage_by_walk`](FsExt::usage_by_walk) | Portable [`usage`](crate::FsStats::usage) built on [`walk`](FsExt::walk) |
+//! | [`freeze`](FsExt::freeze) | Copy a subtree into a read-only [`MemSnapshot`] |
+//! | [`tree`](FsExt::tree) | `tree`-style listing of a subtree, for debugging |
 //!
 //! ## JSON Support (Feature-Gated)
 //!
@@ -29,9 +47,24 @@
 //! [dependencies]
 //! anyfs-backend = { version = "0.1", features = ["serde"] }
 //! ```
+//!
+//! ## Parallel Walk (Feature-Gated)
+//!
+//! With the `rayon` feature enabled, `par_walk` walks a
+//! tree with sibling subtrees listed in parallel, for aggregates such as
+//! directory sizes over slow or remote backends.
 
-use crate::{Fs, FsError};
-use std::path::Path;
+#[cfg(feature = "rayon")]
+use crate::ParWalk;
+use crate::{
+    CopyOptions, DirEntry, FileType, Fs, FsError, FsPermissions, FsXattr, MemSnapshot,
+    OverwritePolicy, Permissions, Progress, ProgressSink, RemoveDirReport, RemoveFailure,
+    UsageReport, Walk, XATTR_CONTENT_TYPE, XATTR_EXPIRES,
+};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Extension methods for any filesystem backend.
 ///
@@ -161,6 +194,1028 @@ pub trait FsExt: Fs {
     fn file_size(&self, path: &Path) -> Result<u64, FsError> {
         Ok(self.metadata(path)?.size)
     }
+
+    /// Get the MIME type stored under [`XATTR_CONTENT_TYPE`].
+    ///
+    /// Returns `Ok(None)` if the file has no content type.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - [`FsError::InvalidData`] if the stored value isn't UTF-8
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsXattr, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn mime_or_default<B: Fs + FsXattr>(backend: &B, path: &Path) -> Result<String, FsError> {
+    ///     Ok(backend
+    ///         .content_type(path)?
+    ///         .unwrap_or_else(|| "application/octet-stream".to_string()))
+    /// }
+    /// ```
+    fn content_type(&self, path: &Path) -> Result<Option<String>, FsError>
+    where
+        Self: FsXattr,
+    {
+        let value = match self.get_xattr(path, XATTR_CONTENT_TYPE) {
+            Ok(value) => value,
+            Err(FsError::XattrNotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        String::from_utf8(value)
+            .map(Some)
+            .map_err(|e| FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{XATTR_CONTENT_TYPE} is not UTF-8: {e}"),
+            })
+    }
+
+    /// Store a MIME type under [`XATTR_CONTENT_TYPE`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - Other errors from [`set_xattr`](FsXattr::set_xattr)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsXattr, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn publish_page<B: Fs + FsXattr>(backend: &B, html: &str) -> Result<(), FsError> {
+    ///     let path = Path::new("/index.html");
+    ///     backend.write(path, html.as_bytes())?;
+    ///     backend.set_content_type(path, "text/html; charset=utf-8")
+    /// }
+    /// ```
+    fn set_content_type(&self, path: &Path, mime: &str) -> Result<(), FsError>
+    where
+        Self: FsXattr,
+    {
+        self.set_xattr(path, XATTR_CONTENT_TYPE, mime.as_bytes())
+    }
+
+    /// Get the expiry time stored under [`XATTR_EXPIRES`].
+    ///
+    /// Meant for backends without native TTLs:
+    /// `fn expiry(&self, path: &Path) -> .. { self.xattr_expiry(path) }`.
+    /// Returns `Ok(None)` if the file has no expiry.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - [`FsError::InvalidData`] if the stored value isn't decimal Unix seconds
+    fn xattr_expiry(&self, path: &Path) -> Result<Option<SystemTime>, FsError>
+    where
+        Self: FsXattr,
+    {
+        let value = match self.get_xattr(path, XATTR_EXPIRES) {
+            Ok(value) => value,
+            Err(FsError::XattrNotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        std::str::from_utf8(&value)
+            .ok()
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+            .map(Some)
+            .ok_or_else(|| FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{XATTR_EXPIRES} is not decimal Unix seconds"),
+            })
+    }
+
+    /// Store an expiry time under [`XATTR_EXPIRES`], or remove it with `None`.
+    ///
+    /// Sub-second precision is dropped, rounding up so a file never expires
+    /// early.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - [`FsError::InvalidData`] if `at` is before the Unix epoch
+    /// - Other errors from [`set_xattr`](FsXattr::set_xattr)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsXattr, FsError};
+    /// use std::path::Path;
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// fn cache_for<B: Fs + FsXattr>(backend: &B, path: &Path, ttl: Duration) -> Result<(), FsError> {
+    ///     backend.set_xattr_expiry(path, Some(SystemTime::now() + ttl))
+    /// }
+    /// ```
+    fn set_xattr_expiry(&self, path: &Path, at: Option<SystemTime>) -> Result<(), FsError>
+    where
+        Self: FsXattr,
+    {
+        let Some(at) = at else {
+            return match self.remove_xattr(path, XATTR_EXPIRES) {
+                Err(FsError::XattrNotFound { .. }) => Ok(()),
+                other => other,
+            };
+        };
+        let since = at
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{XATTR_EXPIRES} must not be before the Unix epoch"),
+            })?;
+        let secs = since.as_secs() + u64::from(since.subsec_nanos() > 0);
+        self.set_xattr(path, XATTR_EXPIRES, secs.to_string().as_bytes())
+    }
+
+    /// Set the modification time of `path`.
+    ///
+    /// Calls [`FsWrite::set_times`](crate::FsWrite::set_times) with only
+    /// `mtime` set, so it works on every backend and fails at runtime with
+    /// [`FsError::NotSupported`] when the backend can't set times.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - [`FsError::NotSupported`] if the backend can't set times
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError, FsExt};
+    /// use std::path::Path;
+    ///
+    /// // Keep the source's mtime on a copy, like `cp -p`, where possible
+    /// fn copy_preserving<B: Fs>(backend: &B, from: &Path, to: &Path) -> Result<(), FsError> {
+    ///     backend.copy(from, to)?;
+    ///     match backend.set_modified(to, backend.metadata(from)?.modified) {
+    ///         Err(FsError::NotSupported { .. }) => Ok(()),
+    ///         other => other,
+    ///     }
+    /// }
+    /// ```
+    fn set_modified(&self, path: &Path, time: SystemTime) -> Result<(), FsError> {
+        match self.set_times(path, None, Some(time)) {
+            Err(FsError::NotSupported { .. }) => Err(FsError::NotSupported {
+                operation: "set_modified",
+            }),
+            other => other,
+        }
+    }
+
+    /// Set the access and modification times of `path` to now, like
+    /// `touch` on an existing file.
+    ///
+    /// Goes through [`FsWrite::set_times`](crate::FsWrite::set_times) like
+    /// [`set_modified`](FsExt::set_modified). Backends without a separate
+    /// access time reject `atime`; since `set_times` changes nothing when it
+    /// fails, the call is then retried with `mtime` alone.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path doesn't exist
+    /// - [`FsError::NotSupported`] if the backend can't set times
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError, FsExt};
+    /// use std::path::Path;
+    ///
+    /// // Mark a cache entry as fresh without rewriting it
+    /// fn refresh<B: Fs>(backend: &B, entry: &Path) -> Result<(), FsError> {
+    ///     backend.touch_times(entry)
+    /// }
+    /// ```
+    fn touch_times(&self, path: &Path) -> Result<(), FsError> {
+        let now = SystemTime::now();
+        let result = match self.set_times(path, Some(now), Some(now)) {
+            Err(FsError::NotSupported { .. }) => self.set_times(path, None, Some(now)),
+            other => other,
+        };
+        match result {
+            Err(FsError::NotSupported { .. }) => Err(FsError::NotSupported {
+                operation: "touch_times",
+            }),
+            other => other,
+        }
+    }
+
+    /// Walk the tree rooted at `path`, depth-first.
+    ///
+    /// See [`Walk`] for ordering and symlink handling.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// fn list_tree<B: Fs>(backend: &B) -> Result<Vec<PathBuf>, FsError> {
+    ///     backend
+    ///         .walk(Path::new("/project"))
+    ///         .map(|entry| entry.map(|e| e.path().to_path_buf()))
+    ///         .collect()
+    /// }
+    /// ```
+    fn walk(&self, path: &Path) -> Walk<'_, Self> {
+        Walk::new(self, path)
+    }
+
+    /// Walk the tree rooted at `path`, listing sibling subtrees in parallel.
+    ///
+    /// Visits the same entries as [`walk`](FsExt::walk); see [`ParWalk`]
+    /// for concurrency and ordering. Requires the `rayon` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError, FsExt};
+    /// use std::path::Path;
+    ///
+    /// fn count_entries<B: Fs>(backend: &B) -> Result<usize, FsError> {
+    ///     backend.par_walk(Path::new("/data")).try_reduce(|_| Ok(1), |a, b| a + b)
+    /// }
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn par_walk(&self, path: &Path) -> ParWalk<'_, Self> {
+        ParWalk::new(self, path)
+    }
+
+    /// Set `file_perm` on every non-directory and `dir_perm` on every
+    /// directory in the tree rooted at `path`, including `path` itself.
+    ///
+    /// Symlinks are skipped, since changing them would change their targets.
+    /// Contents are updated before their directory, so a restrictive
+    /// `dir_perm` doesn't lock the walk out of the tree.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `path` doesn't exist
+    /// - The first error from listing the tree or setting permissions; entries
+    ///   already updated stay updated
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsPermissions, FsError, Permissions};
+    /// use std::path::Path;
+    ///
+    /// fn normalize_extracted<B: Fs + FsPermissions>(backend: &B) -> Result<(), FsError> {
+    ///     backend.set_permissions_recursive(
+    ///         Path::new("/extracted"),
+    ///         Permissions::default_file(),
+    ///         Permissions::default_dir(),
+    ///     )
+    /// }
+    /// ```
+    fn set_permissions_recursive(
+        &self,
+        path: &Path,
+        file_perm: Permissions,
+        dir_perm: Permissions,
+    ) -> Result<(), FsError>
+    where
+        Self: FsPermissions,
+    {
+        for entry in self.walk(path).contents_first(true) {
+            let entry = entry?;
+            match entry.file_type() {
+                FileType::Symlink => {}
+                FileType::Directory => self.set_permissions(entry.path(), dir_perm)?,
+                _ => self.set_permissions(entry.path(), file_perm)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear the write bits of every entry in the tree rooted at `path`,
+    /// keeping all other bits.
+    ///
+    /// Symlinks are skipped, as in
+    /// [`set_permissions_recursive`](FsExt::set_permissions_recursive).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `path` doesn't exist
+    /// - The first error from listing the tree or setting permissions
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsPermissions, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn seal_release<B: Fs + FsPermissions>(backend: &B, version: &str) -> Result<(), FsError> {
+    ///     backend.make_readonly_tree(&Path::new("/releases").join(version))
+    /// }
+    /// ```
+    fn make_readonly_tree(&self, path: &Path) -> Result<(), FsError>
+    where
+        Self: FsPermissions,
+    {
+        const WRITE_BITS: u32 =
+            Permissions::OWNER_WRITE | Permissions::GROUP_WRITE | Permissions::OTHER_WRITE;
+
+        for entry in self.walk(path).contents_first(true) {
+            let entry = entry?;
+            if entry.file_type() == FileType::Symlink {
+                continue;
+            }
+            let mut perm = self.metadata(entry.path())?.permissions;
+            if perm.mode() & WRITE_BITS != 0 {
+                perm.clear(WRITE_BITS);
+                self.set_permissions(entry.path(), perm)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the tree rooted at `path`, continuing past failures.
+    ///
+    /// Follows the [`remove_dir_all`](crate::FsDir::remove_dir_all) contract:
+    /// symlinks are unlinked, never followed. Every entry that can be removed
+    /// is; each entry that can't is listed in the report, and its ancestors
+    /// are left in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` only if nothing could be attempted:
+    ///
+    /// - [`FsError::NotFound`] if `path` doesn't exist
+    /// - [`FsError::NotADirectory`] if `path` is not a directory
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn clean_cache<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     let report = backend.remove_dir_all_report(Path::new("/cache"))?;
+    ///     for failure in &report.failures {
+    ///         eprintln!("could not remove {}: {}", failure.path.display(), failure.error);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn remove_dir_all_report(&self, path: &Path) -> Result<RemoveDirReport, FsError> {
+        self.remove_dir_all_report_with(path, |_: &Progress| {})
+    }
+
+    /// [`remove_dir_all_report`](FsExt::remove_dir_all_report), reporting
+    /// each removed entry to `sink`.
+    ///
+    /// Entries that fail to be removed are not reported; they appear in the
+    /// returned report. `bytes_done` stays `0`.
+    ///
+    /// # Errors
+    ///
+    /// See [`remove_dir_all_report`](FsExt::remove_dir_all_report).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError, Progress};
+    /// use std::path::Path;
+    ///
+    /// fn clean_cache<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     let report = backend.remove_dir_all_report_with(Path::new("/cache"), |p: &Progress| {
+    ///         eprint!("\rremoved {}", p.entries_done);
+    ///     })?;
+    ///     report.into_result()
+    /// }
+    /// ```
+    fn remove_dir_all_report_with<P>(
+        &self,
+        path: &Path,
+        mut sink: P,
+    ) -> Result<RemoveDirReport, FsError>
+    where
+        P: ProgressSink,
+    {
+        if !self.metadata(path)?.is_dir() {
+            return Err(FsError::not_a_directory("remove_dir_all", path));
+        }
+
+        let mut report = RemoveDirReport::default();
+        // Directories that can't become empty because something below failed
+        let mut blocked: HashSet<PathBuf> = HashSet::new();
+
+        let mut walk = self.walk(path).contents_first(true);
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    // The directory whose listing failed can't be emptied
+                    let dir = walk.current_dir().unwrap_or(path).to_path_buf();
+                    blocked.insert(dir.clone());
+                    block_ancestors(&mut blocked, path, &dir);
+                    report.failures.push(RemoveFailure { path: dir, error });
+                    continue;
+                }
+            };
+            let result = if entry.file_type() == FileType::Directory {
+                if blocked.contains(entry.path()) {
+                    continue;
+                }
+                self.remove_dir(entry.path())
+            } else {
+                self.remove_file(entry.path())
+            };
+            match result {
+                Ok(()) => {
+                    report.removed += 1;
+                    sink.report(&Progress {
+                        path: entry.entry.path,
+                        entries_done: report.removed,
+                        bytes_done: 0,
+                    });
+                }
+                Err(error) => {
+                    block_ancestors(&mut blocked, path, entry.path());
+                    report.failures.push(RemoveFailure {
+                        path: entry.entry.path,
+                        error,
+                    });
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// [`FsWrite::copy_with`](crate::FsWrite::copy_with) built on
+    /// [`copy`](crate::FsWrite::copy), `metadata`, `set_permissions` and
+    /// [`set_times`](crate::FsWrite::set_times).
+    ///
+    /// Meant for backends without a native `copy_with`:
+    /// `fn copy_with(..) { self.copy_with_fallback(from, to, options) }`.
+    /// The existence check and the copy are separate calls, so `Fail` and
+    /// `Skip` can race with a concurrent writer. `allow_reflink` is ignored.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the source file does not exist
+    /// - [`FsError::NotAFile`] if the source is not a regular file
+    /// - [`FsError::AlreadyExists`] if the destination exists and `overwrite` is `Fail`
+    /// - [`FsError::NotSupported`] if `preserve_times` is requested and the
+    ///   backend's `set_times` can't store them; the copy is left in place
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{CopyOptions, Fs, FsExt, FsPermissions, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn mirror<B: Fs + FsPermissions>(backend: &B) -> Result<u64, FsError> {
+    ///     let options = CopyOptions {
+    ///         preserve_permissions: true,
+    ///         ..CopyOptions::default()
+    ///     };
+    ///     backend.copy_with_fallback(Path::new("/src.bin"), Path::new("/dst.bin"), options)
+    /// }
+    /// ```
+    fn copy_with_fallback(
+        &self,
+        from: &Path,
+        to: &Path,
+        options: CopyOptions,
+    ) -> Result<u64, FsError>
+    where
+        Self: FsPermissions,
+    {
+        let source = self.metadata(from)?;
+        if !source.is_file() {
+            return Err(FsError::not_a_file("copy_with", from));
+        }
+        if self.exists(to)? {
+            match options.overwrite {
+                OverwritePolicy::Replace => {}
+                OverwritePolicy::Skip => return Ok(0),
+                OverwritePolicy::Fail => {
+                    return Err(FsError::AlreadyExists {
+                        path: to.to_path_buf(),
+                        operation: "copy_with",
+                    })
+                }
+            }
+        }
+        self.copy(from, to)?;
+        if options.preserve_permissions {
+            self.set_permissions(to, source.permissions)?;
+        }
+        if options.preserve_times {
+            self.set_times(to, Some(source.accessed), Some(source.modified))?;
+        }
+        Ok(source.size)
+    }
+
+    /// Move a file or directory tree, even where [`rename`](crate::FsWrite::rename)
+    /// can't.
+    ///
+    /// Equivalent to [`move_entry_with`](FsExt::move_entry_with) without a
+    /// progress callback.
+    ///
+    /// # Errors
+    ///
+    /// See [`move_entry_with`](FsExt::move_entry_with).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn archive<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     backend.move_entry(Path::new("/inbox/report"), Path::new("/archive/report"))
+    /// }
+    /// ```
+    fn move_entry(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.move_entry_with(from, to, |_: &Progress| {})
+    }
+
+    /// Move a file or directory tree, reporting progress to a
+    /// [`ProgressSink`] if `rename` has to be emulated.
+    ///
+    /// Tries [`rename`](crate::FsWrite::rename) first. If the backend can't
+    /// rename between these paths (e.g. they live on different mounts of a
+    /// composed backend), it falls back to copying every entry, reading each
+    /// copied file back to verify it, and only then deleting the source.
+    /// `sink` is told about each copied entry.
+    ///
+    /// The fallback triggers on [`FsError::NotSupported`] and
+    /// [`FsError::CrossesDevices`] (which `EXDEV` I/O errors convert to);
+    /// other rename errors are returned as-is. If copying fails, the partial destination is removed and the
+    /// source is left intact.
+    ///
+    /// Unlike `rename`, the fallback never replaces anything: if `to` already
+    /// exists, whether `from` is a file or a directory, it fails with
+    /// [`FsError::AlreadyExists`] before copying.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `from` doesn't exist
+    /// - [`FsError::AlreadyExists`] if the fallback finds `to` already exists
+    /// - [`FsError::IntegrityError`] if a copied file doesn't read back identical
+    /// - [`FsError::NotSupported`] if the tree contains symlinks or FIFOs,
+    ///   which can't be recreated through [`Fs`] alone
+    /// - Errors from the underlying read, write and remove operations
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError, Progress};
+    /// use std::path::Path;
+    ///
+    /// fn migrate<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     backend.move_entry_with(Path::new("/hot/data"), Path::new("/cold/data"), |p: &Progress| {
+    ///         println!("{} entries, {} bytes", p.entries_done, p.bytes_done);
+    ///     })
+    /// }
+    /// ```
+    fn move_entry_with<P>(&self, from: &Path, to: &Path, mut sink: P) -> Result<(), FsError>
+    where
+        P: ProgressSink,
+    {
+        match self.rename(from, to) {
+            Err(e) if needs_copy_fallback(&e) => {}
+            result => return result,
+        }
+
+        let source = self.metadata(from)?;
+        if self.exists(to)? {
+            return Err(FsError::AlreadyExists {
+                path: to.to_path_buf(),
+                operation: "move_entry",
+            });
+        }
+        if !source.is_dir() {
+            let data = self.read(from)?;
+            if let Err(e) = copy_verified(self, to, &data) {
+                // Best effort: `to` didn't exist, so drop the partial copy
+                let _ = self.remove_file(to);
+                return Err(e);
+            }
+            sink.report(&Progress {
+                path: from.to_path_buf(),
+                entries_done: 1,
+                bytes_done: data.len() as u64,
+            });
+            return self.remove_file(from);
+        }
+
+        let mut progress = Progress {
+            path: from.to_path_buf(),
+            entries_done: 0,
+            bytes_done: 0,
+        };
+        let copied = self.walk(from).try_for_each(|entry| {
+            let entry = entry?;
+            // `to.join("")` would add a trailing slash for the root itself
+            let target = match entry.path().strip_prefix(from) {
+                Ok(relative) if !relative.as_os_str().is_empty() => to.join(relative),
+                _ => to.to_path_buf(),
+            };
+            match entry.file_type() {
+                FileType::Directory => self.create_dir(&target)?,
+                FileType::Symlink => {
+                    return Err(FsError::NotSupported {
+                        operation: "move_entry (symlink)",
+                    })
+                }
+                FileType::Fifo => {
+                    return Err(FsError::NotSupported {
+                        operation: "move_entry (fifo)",
+                    })
+                }
+                _ => {
+                    let data = self.read(entry.path())?;
+                    copy_verified(self, &target, &data)?;
+                    progress.bytes_done += data.len() as u64;
+                }
+            }
+            progress.entries_done += 1;
+            progress.path = entry.entry.path;
+            sink.report(&progress);
+            Ok(())
+        });
+        if let Err(e) = copied {
+            // Best effort: the source is intact, so drop the partial copy
+            let _ = self.remove_dir_all_report(to);
+            return Err(e);
+        }
+        self.remove_dir_all(from)
+    }
+
+    /// Replace `to` with `from` without ever losing `to`, on backends whose
+    /// [`rename`](crate::FsWrite::rename) can't replace atomically.
+    ///
+    /// If `to` exists it is first renamed to a hidden backup beside it, then
+    /// `from` is renamed into place and the backup removed. If moving `from`
+    /// fails, the backup is renamed back, so `to` keeps its old content.
+    /// Every step renames onto a free name, so none relies on the backend
+    /// replacing anything. Names are checked with
+    /// [`exists_fresh`](crate::FsRead::exists_fresh), so a cached "not found"
+    /// can't make a taken name look free.
+    ///
+    /// This is not atomic: between the two renames `to` doesn't exist, and
+    /// an interruption there leaves the old content under the backup name.
+    /// Prefer a plain `rename` on backends that honour its
+    /// [atomicity contract](crate::FsWrite::rename). A backup that can't be
+    /// removed once `from` is in place is left behind.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `from` doesn't exist
+    /// - Errors from the underlying renames; if restoring the backup fails
+    ///   too, the old content stays under the backup name
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn publish<B: Fs>(backend: &B, config: &[u8]) -> Result<(), FsError> {
+    ///     backend.write(Path::new("/config.json.new"), config)?;
+    ///     backend.safe_replace(Path::new("/config.json.new"), Path::new("/config.json"))
+    /// }
+    /// ```
+    fn safe_replace(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        if !self.exists_fresh(from)? {
+            return Err(FsError::not_found("safe_replace", from));
+        }
+        if !self.exists_fresh(to)? {
+            return self.rename(from, to);
+        }
+        let replaced_dir = self.metadata(to)?.is_dir();
+        let backup = hidden_sibling(self, to, ".replace-backup")?;
+        self.rename(to, &backup)?;
+        if let Err(e) = self.rename(from, to) {
+            // Best effort: if this fails too, the old content is at `backup`
+            let _ = self.rename(&backup, to);
+            return Err(e);
+        }
+        let _ = if replaced_dir {
+            self.remove_dir_all(&backup)
+        } else {
+            self.remove_file(&backup)
+        };
+        Ok(())
+    }
+
+    /// Change only the case of the last component of `path`, e.g.
+    /// `File.txt` → `file.txt`.
+    ///
+    /// Goes through a hidden temporary name, so it works on backends whose
+    /// [`rename`](crate::FsWrite::rename) doesn't handle
+    /// [case-only renames](crate::FsWrite::rename), and gives the same result
+    /// whatever the backend's [`NameCollisionPolicy`](crate::NameCollisionPolicy).
+    /// Does nothing if `new_name` is already the current spelling.
+    ///
+    /// This is not atomic: between the two renames the entry only exists
+    /// under the temporary name. If the second rename fails, the first is
+    /// undone.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidData`] if `new_name` differs from the current
+    ///   name by more than case, or `path` has no file name
+    /// - Errors from the underlying renames
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn lowercase_readme<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     backend.rename_case(Path::new("/docs/README.MD"), "readme.md")
+    /// }
+    /// ```
+    fn rename_case(&self, path: &Path, new_name: &str) -> Result<(), FsError> {
+        let old_name = path.file_name().map(|name| name.to_string_lossy());
+        let Some(old_name) = old_name else {
+            return Err(FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: "path has no file name".to_string(),
+            });
+        };
+        if old_name == new_name {
+            return Ok(());
+        }
+        if old_name.to_lowercase() != new_name.to_lowercase() {
+            return Err(FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!("{new_name:?} differs from {old_name:?} by more than case"),
+            });
+        }
+        let temp = hidden_sibling(self, path, ".case-rename")?;
+        self.rename(path, &temp)?;
+        if let Err(e) = self.rename(&temp, &path.with_file_name(new_name)) {
+            // Best effort: if this fails too, the entry is at `temp`
+            let _ = self.rename(&temp, path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Report the space used at or below `path` by walking it.
+    ///
+    /// Meant for backends without a native [`usage`](crate::FsStats::usage):
+    /// `fn usage(..) { self.usage_by_walk(path) }`. Sizes come from the
+    /// [`DirEntry`] of each regular file. Symlinks are skipped, as in
+    /// [`set_permissions_recursive`](FsExt::set_permissions_recursive): they
+    /// are neither followed nor counted. Visits every entry, so it costs a
+    /// full listing of the tree.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - Errors from listing any directory in the tree
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsExt, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn home_size<B: Fs>(backend: &B) -> Result<u64, FsError> {
+    ///     Ok(backend.usage_by_walk(Path::new("/home"))?.bytes)
+    /// }
+    /// ```
+    fn usage_by_walk(&self, path: &Path) -> Result<UsageReport, FsError> {
+        let mut report = UsageReport::default();
+        let mut by_child: HashMap<String, u64> = HashMap::new();
+        for entry in self.walk(path) {
+            let entry = entry?;
+            if entry.file_type() == FileType::Symlink {
+                continue;
+            }
+            let is_dir = entry.file_type() == FileType::Directory;
+            let bytes = match entry.file_type() {
+                FileType::File => entry.entry.size,
+                _ => 0,
+            };
+            if entry.depth == 0 {
+                if !is_dir {
+                    report.files = 1;
+                    report.bytes = bytes;
+                }
+                continue;
+            }
+            if is_dir {
+                report.dirs += 1;
+            } else {
+                report.files += 1;
+            }
+            report.bytes += bytes;
+            let child = entry
+                .path()
+                .strip_prefix(path)
+                .ok()
+                .and_then(|rest| rest.components().next())
+                .map(|c| c.as_os_str().to_string_lossy().into_owned());
+            if let Some(child) = child {
+                *by_child.entry(child).or_insert(0) += bytes;
+            }
+        }
+        report.by_child = by_child.into_iter().collect();
+        report
+            .by_child
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(report)
+    }
+
+    /// Copy the subtree at `path` into a detached, read-only [`MemSnapshot`].
+    ///
+    /// `path` becomes `/` in the snapshot. Later changes to this backend
+    /// don't show through, which makes snapshots handy as test fixtures and
+    /// for serving a consistent view. Symlinks and FIFOs are left out; see
+    /// [`MemSnapshot`] for the details.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotADirectory`] if the path is not a directory
+    /// - Errors from listing, stat-ing or reading any entry in the tree
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError, FsExt, MemSnapshot};
+    /// use std::path::Path;
+    ///
+    /// fn release_view<B: Fs>(backend: &B) -> Result<MemSnapshot, FsError> {
+    ///     backend.freeze(Path::new("/releases/current"))
+    /// }
+    /// ```
+    fn freeze(&self, path: &Path) -> Result<MemSnapshot, FsError> {
+        MemSnapshot::capture(self, path)
+    }
+
+    /// Render the tree rooted at `path` like the `tree` command, down to
+    /// `depth` levels below it (0 renders only `path`).
+    ///
+    /// Meant for debugging and test failure messages. Siblings are sorted
+    /// by name, so the output is the same on every backend. Files show
+    /// their size, directories end in `/`, FIFOs in `|`, and symlinks show
+    /// their target when the backend's [`DirEntry`]
+    /// carries one (`@` otherwise). Built on [`walk`](FsExt::walk), so
+    /// symlinks are never followed.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - The first error from listing the tree
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError, FsExt};
+    /// use std::path::Path;
+    ///
+    /// fn assert_installed<B: Fs>(backend: &B) -> Result<(), FsError> {
+    ///     let app = Path::new("/opt/app");
+    ///     assert!(
+    ///         backend.exists(&app.join("bin/app"))?,
+    ///         "missing binary in:\n{}",
+    ///         backend.tree(app, 3)?
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// prints, for an incomplete install:
+    ///
+    /// ```text
+    /// /opt/app/
+    /// ├── bin/
+    /// ├── current -> /opt/app/releases/2
+    /// └── etc/
+    ///     └── app.toml (210 B)
+    /// ```
+    fn tree(&self, path: &Path, depth: usize) -> Result<String, FsError> {
+        let mut root = None;
+        let mut children: HashMap<PathBuf, Vec<DirEntry>> = HashMap::new();
+        for entry in self.walk(path).max_depth(depth) {
+            let entry = entry?;
+            if entry.depth == 0 {
+                root = Some(entry.entry);
+                continue;
+            }
+            let parent = entry.path().parent().unwrap_or(path).to_path_buf();
+            children.entry(parent).or_default().push(entry.entry);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        }
+
+        let mut out = String::new();
+        if let Some(root) = root {
+            out.push_str(&tree_label(&root, path.display().to_string()));
+            out.push('\n');
+        }
+        render_tree(&mut out, &children, path, "");
+        Ok(out)
+    }
+}
+
+/// Append the children of `dir` to a [`tree`](FsExt::tree) rendering,
+/// each line starting with `prefix`.
+fn render_tree(
+    out: &mut String,
+    children: &HashMap<PathBuf, Vec<DirEntry>>,
+    dir: &Path,
+    prefix: &str,
+) {
+    let Some(entries) = children.get(dir) else {
+        return;
+    };
+    for (i, entry) in entries.iter().enumerate() {
+        let last = i + 1 == entries.len();
+        out.push_str(prefix);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(&tree_label(entry, entry.display_name().to_string()));
+        out.push('\n');
+        let nested = format!("{prefix}{}", if last { "    " } else { "│   " });
+        render_tree(out, children, &entry.path, &nested);
+    }
+}
+
+/// One [`tree`](FsExt::tree) line for `entry`, shown as `name`.
+fn tree_label(entry: &DirEntry, mut name: String) -> String {
+    match entry.file_type {
+        FileType::File => name.push_str(&format!(" ({} B)", entry.size)),
+        FileType::Directory => {
+            if !name.ends_with('/') {
+                name.push('/');
+            }
+        }
+        FileType::Symlink => match &entry.symlink_target {
+            Some(target) => name.push_str(&format!(" -> {}", target.display())),
+            None => name.push('@'),
+        },
+        FileType::Fifo => name.push('|'),
+    }
+    name
+}
+
+/// A free hidden sibling name for `path`, used as a temporary by
+/// [`safe_replace`](FsExt::safe_replace) and
+/// [`rename_case`](FsExt::rename_case).
+fn hidden_sibling<B: Fs + ?Sized>(
+    backend: &B,
+    path: &Path,
+    suffix: &str,
+) -> Result<PathBuf, FsError> {
+    let name = path.file_name().ok_or_else(|| FsError::InvalidData {
+        path: path.to_path_buf(),
+        details: "path has no file name".to_string(),
+    })?;
+    let mut hidden = OsString::from(".");
+    hidden.push(name);
+    hidden.push(suffix);
+    let mut candidate = path.with_file_name(&hidden);
+    let mut n = 1u32;
+    while backend.exists_fresh(&candidate)? {
+        let mut numbered = hidden.clone();
+        numbered.push(format!("-{n}"));
+        candidate = path.with_file_name(numbered);
+        n += 1;
+    }
+    Ok(candidate)
+}
+
+/// Whether a failed `rename` should be retried as copy + delete.
+fn needs_copy_fallback(error: &FsError) -> bool {
+    matches!(
+        error,
+        FsError::NotSupported { .. } | FsError::CrossesDevices { .. }
+    )
+}
+
+/// Write `data` to `to` and read it back to check it landed intact.
+fn copy_verified<B: Fs + ?Sized>(backend: &B, to: &Path, data: &[u8]) -> Result<(), FsError> {
+    backend.write(to, data)?;
+    if backend.read(to)? != data {
+        return Err(FsError::IntegrityError {
+            path: to.to_path_buf(),
+        });
+    }
+    Ok(())
+}
+
+/// Mark every ancestor of `failed`, up to and including `root`, as unremovable.
+fn block_ancestors(blocked: &mut HashSet<PathBuf>, root: &Path, failed: &Path) {
+    for ancestor in failed.ancestors().skip(1) {
+        if !blocked.insert(ancestor.to_path_buf()) || ancestor == root {
+            break;
+        }
+    }
 }
 
 // Blanket implementation - any Fs backend gets FsExt for free
@@ -253,12 +1308,16 @@ pub use json::FsExtJson;
 mod tests {
     use super::*;
     use crate::{FsDir, FsRead, FsWrite, Metadata, ReadDirIter};
+    use std::collections::HashMap;
     use std::io::{Read, Write};
+    use std::sync::{Arc, RwLock};
 
     /// Mock backend for testing
     struct MockFs {
         file_exists: bool,
         dir_exists: bool,
+        xattrs: RwLock<HashMap<String, Vec<u8>>>,
+        times: RwLock<Vec<(Option<SystemTime>, Option<SystemTime>)>>,
     }
 
     impl MockFs {
@@ -266,6 +1325,8 @@ mod tests {
             Self {
                 file_exists: true,
                 dir_exists: false,
+                xattrs: RwLock::default(),
+                times: RwLock::default(),
             }
         }
 
@@ -273,6 +1334,8 @@ mod tests {
             Self {
                 file_exists: false,
                 dir_exists: true,
+                xattrs: RwLock::default(),
+                times: RwLock::default(),
             }
         }
 
@@ -280,6 +1343,8 @@ mod tests {
             Self {
                 file_exists: false,
                 dir_exists: false,
+                xattrs: RwLock::default(),
+                times: RwLock::default(),
             }
         }
     }
@@ -315,9 +1380,7 @@ mod tests {
                     ..Metadata::default()
                 })
             } else {
-                Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                })
+                Err(FsError::not_found("metadata", path))
             }
         }
 
@@ -354,6 +1417,25 @@ mod tests {
         fn open_write(&self, _: &Path) -> Result<Box<dyn Write + Send>, FsError> {
             Ok(Box::new(std::io::sink()))
         }
+
+        // Stores mtime but no separate atime
+        fn set_times(
+            &self,
+            path: &Path,
+            atime: Option<SystemTime>,
+            mtime: Option<SystemTime>,
+        ) -> Result<(), FsError> {
+            if !self.file_exists {
+                return Err(FsError::not_found("set_times", path));
+            }
+            if atime.is_some() {
+                return Err(FsError::NotSupported {
+                    operation: "set_times",
+                });
+            }
+            self.times.write().unwrap().push((atime, mtime));
+            Ok(())
+        }
     }
 
     impl FsDir for MockFs {
@@ -427,6 +1509,244 @@ mod tests {
         assert!(matches!(result, Err(FsError::NotFound { .. })));
     }
 
+    impl FsXattr for MockFs {
+        fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+            self.xattrs
+                .read()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or_else(|| FsError::XattrNotFound {
+                    path: path.to_path_buf(),
+                    name: name.to_string(),
+                })
+        }
+
+        fn set_xattr(&self, _: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+            self.xattrs
+                .write()
+                .unwrap()
+                .insert(name.to_string(), value.to_vec());
+            Ok(())
+        }
+
+        fn remove_xattr(&self, _: &Path, name: &str) -> Result<(), FsError> {
+            self.xattrs.write().unwrap().remove(name);
+            Ok(())
+        }
+
+        fn list_xattr(&self, _: &Path) -> Result<Vec<String>, FsError> {
+            Ok(self.xattrs.read().unwrap().keys().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn set_modified_sets_only_mtime() {
+        let fs = MockFs::with_file();
+        let time = UNIX_EPOCH + Duration::from_secs(42);
+        fs.set_modified(Path::new("/file"), time).unwrap();
+        assert_eq!(*fs.times.read().unwrap(), [(None, Some(time))]);
+        assert!(matches!(
+            MockFs::empty().set_modified(Path::new("/missing"), time),
+            Err(FsError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn touch_times_falls_back_to_mtime_without_atime_support() {
+        let fs = MockFs::with_file();
+        let before = SystemTime::now();
+        fs.touch_times(Path::new("/file")).unwrap();
+        let times = fs.times.read().unwrap();
+        assert_eq!(times.len(), 1);
+        assert_eq!(times[0].0, None);
+        assert!(times[0].1.unwrap() >= before);
+    }
+
+    #[test]
+    fn time_helpers_report_missing_support_at_runtime() {
+        let fs = crate::FsBuilder::new().build();
+        assert!(matches!(
+            fs.set_modified(Path::new("/file"), UNIX_EPOCH),
+            Err(FsError::NotSupported {
+                operation: "set_modified"
+            })
+        ));
+        assert!(matches!(
+            fs.touch_times(Path::new("/file")),
+            Err(FsError::NotSupported {
+                operation: "touch_times"
+            })
+        ));
+    }
+
+    #[test]
+    fn move_entry_removes_a_file_copy_that_fails_verification() {
+        let files = Arc::new(RwLock::new(HashMap::from([(
+            PathBuf::from("/from"),
+            b"data".to_vec(),
+        )])));
+        let (exists, read, write, remove) = (
+            Arc::clone(&files),
+            Arc::clone(&files),
+            Arc::clone(&files),
+            Arc::clone(&files),
+        );
+        let fs = crate::FsBuilder::new()
+            .on_rename(|_, _| {
+                Err(FsError::NotSupported {
+                    operation: "rename",
+                })
+            })
+            .on_metadata(|_| Ok(Metadata::default().with_size(4)))
+            .on_exists(move |path| Ok(exists.read().unwrap().contains_key(path)))
+            .on_read(move |path| {
+                let mut data = read.read().unwrap()[path].clone();
+                // The copy reads back corrupted
+                if path == Path::new("/to") {
+                    data.reverse();
+                }
+                Ok(data)
+            })
+            .on_write(move |path, data| {
+                write
+                    .write()
+                    .unwrap()
+                    .insert(path.to_path_buf(), data.to_vec());
+                Ok(())
+            })
+            .on_remove_file(move |path| {
+                remove.write().unwrap().remove(path);
+                Ok(())
+            })
+            .build();
+
+        assert!(matches!(
+            fs.move_entry(Path::new("/from"), Path::new("/to")),
+            Err(FsError::IntegrityError { .. })
+        ));
+        let files = files.read().unwrap();
+        assert!(!files.contains_key(Path::new("/to")));
+        assert_eq!(files[Path::new("/from")], b"data");
+    }
+
+    #[test]
+    fn move_entry_creates_the_directory_copy_at_to() {
+        let created = Arc::new(RwLock::new(Vec::new()));
+        let record = Arc::clone(&created);
+        let fs = crate::FsBuilder::new()
+            .on_rename(|_, _| {
+                Err(FsError::NotSupported {
+                    operation: "rename",
+                })
+            })
+            .on_metadata(|path| match path.to_str() {
+                Some("/from") => Ok(Metadata::default().with_file_type(FileType::Directory)),
+                _ => Err(FsError::not_found("metadata", path)),
+            })
+            .on_read_dir(|_| Ok(ReadDirIter::from_vec(Vec::new())))
+            .on_create_dir(move |path| {
+                record.write().unwrap().push(path.to_path_buf());
+                Ok(())
+            })
+            .on_remove_dir_all(|_| Ok(()))
+            .build();
+
+        fs.move_entry(Path::new("/from"), Path::new("/to")).unwrap();
+        let created = created.read().unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].as_os_str(), "/to");
+    }
+
+    #[test]
+    fn content_type_round_trips() {
+        let fs = MockFs::with_file();
+        let path = Path::new("/page.html");
+        assert_eq!(fs.content_type(path).unwrap(), None);
+
+        fs.set_content_type(path, "text/html").unwrap();
+        assert_eq!(fs.content_type(path).unwrap().as_deref(), Some("text/html"));
+        assert_eq!(
+            fs.get_xattr(path, XATTR_CONTENT_TYPE).unwrap(),
+            b"text/html"
+        );
+    }
+
+    #[test]
+    fn content_type_rejects_non_utf8() {
+        let fs = MockFs::with_file();
+        let path = Path::new("/bin");
+        fs.set_xattr(path, XATTR_CONTENT_TYPE, &[0xff]).unwrap();
+        assert!(matches!(
+            fs.content_type(path),
+            Err(FsError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn rename_case_goes_through_hidden_name() {
+        use crate::FsBuilder;
+        use std::sync::{Arc, Mutex};
+
+        let renames = Arc::new(Mutex::new(Vec::new()));
+        let log = renames.clone();
+        let fs = FsBuilder::new()
+            .on_metadata(|path| Err(FsError::not_found("metadata", path)))
+            .on_rename(move |from, to| {
+                log.lock()
+                    .unwrap()
+                    .push((from.to_path_buf(), to.to_path_buf()));
+                Ok(())
+            })
+            .build();
+
+        fs.rename_case(Path::new("/d/File.txt"), "file.txt")
+            .unwrap();
+        fs.rename_case(Path::new("/d/same"), "same").unwrap();
+        assert_eq!(
+            *renames.lock().unwrap(),
+            [
+                ("/d/File.txt".into(), "/d/.File.txt.case-rename".into()),
+                ("/d/.File.txt.case-rename".into(), "/d/file.txt".into()),
+            ] as [(PathBuf, PathBuf); 2]
+        );
+        assert!(matches!(
+            fs.rename_case(Path::new("/d/File.txt"), "other.txt"),
+            Err(FsError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn replace_helpers_check_names_with_exists_fresh() {
+        use crate::{FsBuilder, FsOp, HookLayer, LayerExt};
+        use std::sync::Mutex;
+
+        let checks = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&checks);
+        let fs = FsBuilder::new()
+            .on_exists(|path| {
+                Ok(["/d/new", "/d/old", "/d/File.txt"]
+                    .iter()
+                    .any(|p| path == Path::new(p)))
+            })
+            .on_metadata(|_| Ok(Metadata::default()))
+            .on_rename(|_, _| Ok(()))
+            .on_remove_file(|_| Ok(()))
+            .build()
+            .layer(HookLayer::new().before(move |op| {
+                if matches!(op, FsOp::Exists { .. } | FsOp::ExistsFresh { .. }) {
+                    log.lock().unwrap().push(op.operation());
+                }
+                Ok(())
+            }));
+
+        fs.safe_replace(Path::new("/d/new"), Path::new("/d/old"))
+            .unwrap();
+        fs.rename_case(Path::new("/d/File.txt"), "file.txt")
+            .unwrap();
+        assert_eq!(*checks.lock().unwrap(), ["exists_fresh"; 4]);
+    }
+
     #[test]
     fn fs_ext_available_on_dyn_fs() {
         let fs: &dyn Fs = &MockFs::with_file();
@@ -0,0 +1,578 @@
+//! # Fuzzing Helpers
+//!
+//! Building blocks for `cargo fuzz` targets over a backend. Available with
+//! the `fuzz` feature, which pulls in the [`arbitrary`] crate:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! anyfs-backend = { version = "0.1", features = ["fuzz"] }
+//! ```
+//!
+//! | Item | Purpose |
+//! |------|---------|
+//! | [`FuzzPath`] | Arbitrary path built from awkward components: `.`, `..`, empty, case variants, Unicode, NUL, very long names |
+//! | [`FuzzOp`] | One arbitrary operation, covering path resolution, renames and the handle lifecycle |
+//! | [`run_ops`] | Apply a sequence of [`FuzzOp`]s to a backend and check invariants after each |
+//! | [`check_virtual_path`] | Check the crate's own path helpers on one path |
+//!
+//! A fuzz target is a few lines:
+//!
+//! ```text
+//! #![no_main]
+//! use anyfs_backend::fuzz::{run_ops, FuzzOp};
+//!
+//! libfuzzer_sys::fuzz_target!(|ops: Vec<FuzzOp>| {
+//!     let backend = MyBackend::new();
+//!     if let Err(failure) = run_ops(&backend, &ops) {
+//!         panic!("{failure}");
+//!     }
+//! });
+//! ```
+//!
+//! Errors from the backend are expected, most generated paths are
+//! nonsense; [`run_ops`] only fails when a result contradicts another or an
+//! error is malformed. Panics inside the backend are caught by the fuzzer
+//! itself.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{
+    validate_virtual_path, virtual_ancestors, virtual_depth, FsError, FsFull, FsHandles, FsPath,
+    Handle, OpenFlags,
+};
+
+/// Components [`FuzzPath`] is built from, besides fuzzer-chosen names.
+const COMPONENTS: &[&str] = &[
+    "a",
+    "A",
+    "b",
+    "dir",
+    ".",
+    "..",
+    "",
+    ".hidden",
+    "a.b",
+    "trailing.",
+    "trailing ",
+    " leading",
+    "é",
+    "e\u{301}",
+    "CON",
+    "nul\0byte",
+    "back\\slash",
+    "c:",
+    "*?<>|",
+];
+
+/// Length of the overlong names [`FuzzPath`] sometimes generates.
+const LONG_NAME_LEN: usize = 300;
+
+/// An arbitrary path made of components backends tend to get wrong.
+///
+/// Mostly absolute, up to six components, drawn from a fixed list of
+/// awkward names (`.`, `..`, empty, case and Unicode-normalization variants,
+/// NUL bytes, separators of other platforms), fuzzer-chosen strings and
+/// names longer than most backends allow.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fuzz::FuzzPath;
+/// use arbitrary::{Arbitrary, Unstructured};
+///
+/// let mut input = Unstructured::new(&[0x41; 64]);
+/// let path = FuzzPath::arbitrary(&mut input).unwrap();
+/// println!("{}", path.as_path().display());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuzzPath(PathBuf);
+
+impl FuzzPath {
+    /// The generated path.
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Unwrap the generated path.
+    pub fn into_inner(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl AsRef<Path> for FuzzPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl<'a> Arbitrary<'a> for FuzzPath {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut path = String::new();
+        if u.ratio(7, 8)? {
+            path.push('/');
+        }
+        let len = u.int_in_range(0..=6)?;
+        for i in 0..len {
+            if i > 0 {
+                path.push('/');
+            }
+            match u.int_in_range(0..=9)? {
+                0 => path.push_str(<&str>::arbitrary(u)?),
+                1 => path.push_str(&"n".repeat(LONG_NAME_LEN)),
+                _ => path.push_str(u.choose(COMPONENTS)?),
+            }
+        }
+        if u.ratio(1, 8)? {
+            path.push('/');
+        }
+        Ok(Self(PathBuf::from(path)))
+    }
+}
+
+/// One operation of a fuzzed sequence, applied by [`run_ops`].
+///
+/// Handle operations name a `slot`: the n-th handle opened so far in the
+/// sequence, modulo the number opened, closed ones included, so sequences
+/// exercise use after close.
+#[non_exhaustive]
+#[derive(Debug, Clone, Arbitrary)]
+pub enum FuzzOp {
+    /// [`FsWrite::write`](crate::FsWrite::write).
+    Write {
+        /// File to write.
+        path: FuzzPath,
+        /// Contents.
+        data: Vec<u8>,
+    },
+    /// [`FsRead::read`](crate::FsRead::read).
+    Read {
+        /// File to read.
+        path: FuzzPath,
+    },
+    /// [`FsWrite::remove_file`](crate::FsWrite::remove_file).
+    RemoveFile {
+        /// File to remove.
+        path: FuzzPath,
+    },
+    /// [`FsWrite::rename`](crate::FsWrite::rename).
+    Rename {
+        /// Source.
+        from: FuzzPath,
+        /// Destination.
+        to: FuzzPath,
+    },
+    /// [`FsDir::create_dir`](crate::FsDir::create_dir).
+    CreateDir {
+        /// Directory to create.
+        path: FuzzPath,
+    },
+    /// [`FsDir::create_dir_all`](crate::FsDir::create_dir_all).
+    CreateDirAll {
+        /// Directory to create.
+        path: FuzzPath,
+    },
+    /// [`FsDir::remove_dir_all`](crate::FsDir::remove_dir_all).
+    RemoveDirAll {
+        /// Directory to remove.
+        path: FuzzPath,
+    },
+    /// [`FsLink::symlink`](crate::FsLink::symlink).
+    Symlink {
+        /// Where the link points.
+        target: FuzzPath,
+        /// The link to create.
+        link: FuzzPath,
+    },
+    /// [`FsPath::canonicalize`].
+    Canonicalize {
+        /// Path to resolve.
+        path: FuzzPath,
+    },
+    /// [`FsHandles::open`], read-only or read-write.
+    Open {
+        /// File to open.
+        path: FuzzPath,
+        /// Open read-write instead of read-only.
+        write: bool,
+    },
+    /// [`FsHandles::read_at`].
+    ReadAt {
+        /// Handle slot.
+        slot: u8,
+        /// Offset to read from.
+        offset: u16,
+        /// Bytes to read.
+        len: u16,
+    },
+    /// [`FsHandles::write_at`].
+    WriteAt {
+        /// Handle slot.
+        slot: u8,
+        /// Offset to write at.
+        offset: u16,
+        /// Bytes to write.
+        data: Vec<u8>,
+    },
+    /// [`FsHandles::close`].
+    Close {
+        /// Handle slot.
+        slot: u8,
+    },
+}
+
+/// A broken invariant found by [`run_ops`] or [`check_virtual_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzFailure {
+    /// Index of the operation in the sequence; 0 for
+    /// [`check_virtual_path`].
+    pub index: usize,
+    /// The operation, formatted with `Debug`.
+    pub op: String,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "op #{} {}: {}", self.index, self.op, self.message)
+    }
+}
+
+impl std::error::Error for FuzzFailure {}
+
+impl FuzzOp {
+    /// Whether the op names an empty path, which errors may then echo.
+    fn has_empty_path(&self) -> bool {
+        let empty = |path: &FuzzPath| path.0.as_os_str().is_empty();
+        match self {
+            Self::Write { path, .. }
+            | Self::Read { path }
+            | Self::RemoveFile { path }
+            | Self::CreateDir { path }
+            | Self::CreateDirAll { path }
+            | Self::RemoveDirAll { path }
+            | Self::Canonicalize { path }
+            | Self::Open { path, .. } => empty(path),
+            Self::Rename { from, to } => empty(from) || empty(to),
+            Self::Symlink { target, link } => empty(target) || empty(link),
+            Self::ReadAt { .. } | Self::WriteAt { .. } | Self::Close { .. } => false,
+        }
+    }
+}
+
+/// A handle opened during [`run_ops`].
+struct Slot {
+    handle: Handle,
+    closed: bool,
+}
+
+/// Apply `ops` to `backend` in order, checking after each one that:
+///
+/// - An error that names a path names a non-empty one (an empty path
+///   usually means a raw `io::Error` was converted with `?`)
+/// - A successful `write` reads back unchanged
+/// - A successful `rename` leaves its destination in place
+/// - A successful `remove_file` leaves nothing at the path
+/// - `canonicalize` returns an absolute path of plain names that
+///   canonicalizes to itself
+/// - `read_at`/`write_at` never report more bytes than asked for, and fail
+///   on closed handles
+///
+/// Handles still open at the end are closed.
+///
+/// # Errors
+///
+/// - The first [`FuzzFailure`]
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fuzz::{run_ops, FuzzFailure, FuzzOp};
+/// use anyfs_backend::{FsFull, FsHandles};
+/// use arbitrary::{Arbitrary, Unstructured};
+///
+/// fn fuzz_one<B: FsFull + FsHandles>(backend: &B, data: &[u8]) -> Result<(), FuzzFailure> {
+///     let mut input = Unstructured::new(data);
+///     match Vec::<FuzzOp>::arbitrary(&mut input) {
+///         Ok(ops) => run_ops(backend, &ops),
+///         Err(_) => Ok(()),
+///     }
+/// }
+/// ```
+pub fn run_ops<B: FsFull + FsHandles>(backend: &B, ops: &[FuzzOp]) -> Result<(), FuzzFailure> {
+    let mut slots: Vec<Slot> = Vec::new();
+    let result = ops.iter().enumerate().try_for_each(|(index, op)| {
+        apply(backend, &mut slots, op).map_err(|message| FuzzFailure {
+            index,
+            op: format!("{op:?}"),
+            message,
+        })
+    });
+    for slot in slots.iter().filter(|slot| !slot.closed) {
+        let _ = backend.close(slot.handle);
+    }
+    result
+}
+
+/// Apply one op, describing a broken invariant on failure.
+fn apply<B: FsFull + FsHandles>(
+    backend: &B,
+    slots: &mut Vec<Slot>,
+    op: &FuzzOp,
+) -> Result<(), String> {
+    let strict = !op.has_empty_path();
+    match op {
+        FuzzOp::Write { path, data } => {
+            if checked(strict, backend.write(path.as_path(), data))?.is_some() {
+                match backend.read(path.as_path()) {
+                    Ok(read) if read == *data => {}
+                    Ok(read) => {
+                        return Err(format!(
+                            "read back {} bytes that differ from the {} written",
+                            read.len(),
+                            data.len()
+                        ))
+                    }
+                    Err(e) => return Err(format!("written file can't be read: {e}")),
+                }
+            }
+        }
+        FuzzOp::Read { path } => {
+            checked(strict, backend.read(path.as_path()))?;
+        }
+        FuzzOp::RemoveFile { path } => {
+            if checked(strict, backend.remove_file(path.as_path()))?.is_some()
+                && backend.symlink_metadata(path.as_path()).is_ok()
+            {
+                return Err("entry still there after remove_file".to_string());
+            }
+        }
+        FuzzOp::Rename { from, to } => {
+            if checked(strict, backend.rename(from.as_path(), to.as_path()))?.is_some() {
+                if let Err(e) = backend.symlink_metadata(to.as_path()) {
+                    return Err(format!("destination missing after rename: {e}"));
+                }
+            }
+        }
+        FuzzOp::CreateDir { path } => {
+            checked(strict, backend.create_dir(path.as_path()))?;
+        }
+        FuzzOp::CreateDirAll { path } => {
+            checked(strict, backend.create_dir_all(path.as_path()))?;
+        }
+        FuzzOp::RemoveDirAll { path } => {
+            checked(strict, backend.remove_dir_all(path.as_path()))?;
+        }
+        FuzzOp::Symlink { target, link } => {
+            checked(strict, backend.symlink(target.as_path(), link.as_path()))?;
+        }
+        FuzzOp::Canonicalize { path } => {
+            if let Some(resolved) = checked(strict, backend.canonicalize(path.as_path()))? {
+                let plain = resolved
+                    .components()
+                    .all(|c| matches!(c, Component::RootDir | Component::Normal(_)));
+                if !resolved.is_absolute() || !plain {
+                    return Err(format!(
+                        "canonical path {} is not absolute and normalized",
+                        resolved.display()
+                    ));
+                }
+                match backend.canonicalize(&resolved) {
+                    Ok(again) if again == resolved => {}
+                    other => {
+                        return Err(format!(
+                            "canonicalizing {} again gave {other:?}",
+                            resolved.display()
+                        ))
+                    }
+                }
+            }
+        }
+        FuzzOp::Open { path, write } => {
+            let flags = if *write {
+                OpenFlags::READ_WRITE
+            } else {
+                OpenFlags::READ
+            };
+            if let Some(handle) = checked(strict, backend.open(path.as_path(), flags))? {
+                slots.push(Slot {
+                    handle,
+                    closed: false,
+                });
+            }
+        }
+        FuzzOp::ReadAt { slot, offset, len } => {
+            if let Some(slot) = pick(slots, *slot) {
+                let mut buf = vec![0; usize::from(*len)];
+                let read = backend.read_at(slot.handle, &mut buf, u64::from(*offset));
+                check_handle_io(slot, read, buf.len())?;
+            }
+        }
+        FuzzOp::WriteAt { slot, offset, data } => {
+            if let Some(slot) = pick(slots, *slot) {
+                let written = backend.write_at(slot.handle, data, u64::from(*offset));
+                check_handle_io(slot, written, data.len())?;
+            }
+        }
+        FuzzOp::Close { slot } => {
+            if let Some(slot) = pick(slots, *slot) {
+                let closed = backend.close(slot.handle);
+                if closed.is_ok() && slot.closed {
+                    return Err("closing a closed handle succeeded".to_string());
+                }
+                checked(strict, closed)?;
+                slot.closed = true;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The handle in `slot`, modulo the number of handles opened so far.
+fn pick(slots: &mut [Slot], slot: u8) -> Option<&mut Slot> {
+    let len = slots.len();
+    if len == 0 {
+        None
+    } else {
+        slots.get_mut(usize::from(slot) % len)
+    }
+}
+
+/// `Some(value)` on success, `None` for a well-formed error. With
+/// `strict`, set when the op named no empty path, errors must not name one.
+fn checked<T>(strict: bool, result: Result<T, FsError>) -> Result<Option<T>, String> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => match e.path() {
+            Some(path) if strict && path.as_os_str().is_empty() => {
+                Err(format!("error names an empty path: {e:?}"))
+            }
+            _ => Ok(None),
+        },
+    }
+}
+
+/// Check a `read_at`/`write_at` result against its slot and request size.
+fn check_handle_io(
+    slot: &Slot,
+    result: Result<usize, FsError>,
+    asked: usize,
+) -> Result<(), String> {
+    match checked(true, result)? {
+        Some(_) if slot.closed => Err("handle still usable after close".to_string()),
+        Some(n) if n > asked => Err(format!("reported {n} bytes for a {asked}-byte request")),
+        _ => Ok(()),
+    }
+}
+
+/// Check that the crate's path helpers agree with each other on `path`.
+///
+/// - [`virtual_ancestors`] starts at the normalized path, which has the
+///   same [`virtual_depth`], and (for absolute paths) ends at `/` after
+///   one step per name
+/// - If [`validate_virtual_path`] accepts `path`, it accepts its
+///   normalized form too
+///
+/// # Errors
+///
+/// - [`FuzzFailure`] with `index` 0 describing the disagreement
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fuzz::check_virtual_path;
+/// use std::path::Path;
+///
+/// assert!(check_virtual_path(Path::new("/a/./b/../c")).is_ok());
+/// ```
+pub fn check_virtual_path(path: &Path) -> Result<(), FuzzFailure> {
+    let fail = |message: String| FuzzFailure {
+        index: 0,
+        op: format!("{path:?}"),
+        message,
+    };
+    let ancestors: Vec<PathBuf> = virtual_ancestors(path).collect();
+    let normalized = &ancestors[0];
+    let depth = virtual_depth(path);
+    if virtual_depth(normalized) != depth {
+        return Err(fail(format!(
+            "normalized form {normalized:?} has depth {}, not {depth}",
+            virtual_depth(normalized)
+        )));
+    }
+    if validate_virtual_path(path).is_ok() {
+        if let Err(e) = validate_virtual_path(normalized) {
+            return Err(fail(format!(
+                "normalized form {normalized:?} rejected: {e}"
+            )));
+        }
+        if ancestors.len() != depth + 1
+            || ancestors.last().map(PathBuf::as_path) != Some(Path::new("/"))
+        {
+            return Err(fail(format!(
+                "{} ancestors for depth {depth}, ending at {:?}",
+                ancestors.len(),
+                ancestors.last()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random input bytes.
+    fn input(seed: u64) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        (0..512)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 33) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_paths_satisfy_path_helper_invariants() {
+        for seed in 0..500 {
+            let data = input(seed);
+            let mut u = Unstructured::new(&data);
+            while let Ok(path) = FuzzPath::arbitrary(&mut u) {
+                if u.is_empty() {
+                    break;
+                }
+                check_virtual_path(path.as_path()).unwrap();
+            }
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn mem_fs_survives_generated_sequences() {
+        use crate::testing::MemFs;
+
+        for seed in 0..200 {
+            let data = input(seed);
+            let ops = Vec::<FuzzOp>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            run_ops(&MemFs::new(), &ops).unwrap();
+        }
+    }
+
+    #[test]
+    fn errors_naming_an_empty_path_are_reported() {
+        let raw = || -> Result<(), FsError> { Err(FsError::not_found("read", PathBuf::new())) };
+        assert!(checked(true, raw()).is_err());
+        assert_eq!(checked(false, raw()), Ok(None));
+        assert_eq!(
+            checked(true, Err::<(), _>(FsError::not_found("read", "/a"))),
+            Ok(None)
+        );
+    }
+}
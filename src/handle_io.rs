@@ -0,0 +1,473 @@
+//! # std::io Adapters for Handles
+//!
+//! Wrap an open [`Handle`] so code written against [`std::io::Read`],
+//! [`std::io::Write`] and [`std::io::Seek`] can drive any [`FsHandles`]
+//! backend.
+//!
+//! ## Overview
+//!
+//! | Type | Implements | Description |
+//! |------|------------|-------------|
+//! | [`HandleReader`] | `Read`, `Seek` | Sequential reads over `read_at` |
+//! | [`HandleWriter`] | `Write`, `Seek` | Sequential writes over `write_at` |
+//!
+//! Each adapter keeps its own position, so two adapters over the same handle
+//! don't disturb each other. Adapters borrow the backend and never close the
+//! handle; use [`into_handle`](HandleReader::into_handle) to get it back.
+//!
+//! ## Seeking From the End
+//!
+//! [`SeekFrom::End`] needs the file's length, which [`FsHandles`] alone
+//! can't report. Adapters built with `seekable` ask
+//! [`FsSeek::handle_len`] on every such seek; otherwise the length can be
+//! supplied with `with_len`. Without either, [`SeekFrom::End`] fails with
+//! [`ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported). Seek
+//! positions are resolved exactly as [`FsSeek::seek_handle`] resolves them.
+//!
+//! [`SeekFrom::End`]: std::io::SeekFrom::End
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{FsHandles, HandleReader, OpenFlags, FsError};
+//! use std::io::Read;
+//! use std::path::Path;
+//!
+//! fn read_to_string<B: FsHandles>(backend: &B, path: &Path) -> Result<String, FsError> {
+//!     let handle = backend.open(path, OpenFlags::READ)?;
+//!     let mut text = String::new();
+//!     let result = HandleReader::new(backend, handle).read_to_string(&mut text);
+//!     backend.close(handle)?;
+//!     result?;
+//!     Ok(text)
+//! }
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::traits::seek_target;
+use crate::{FsError, FsHandles, FsSeek, Handle};
+
+/// Length query used by an adapter built with `seekable`.
+type LenQuery<B> = fn(&B, Handle) -> Result<u64, FsError>;
+
+/// The file length for `SeekFrom::End`: supplied, queried, or unknown.
+fn end_len<B: FsHandles + ?Sized>(
+    backend: &B,
+    handle: Handle,
+    len: Option<u64>,
+    query: Option<LenQuery<B>>,
+) -> Result<u64, FsError> {
+    match (len, query) {
+        (Some(len), _) => Ok(len),
+        (None, Some(query)) => query(backend, handle),
+        (None, None) => Err(FsError::NotSupported {
+            operation: "handle_len",
+        }),
+    }
+}
+
+/// [`Read`] + [`Seek`] adapter over an open handle.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsHandles, HandleReader, OpenFlags, FsError};
+/// use std::io::{Read, Seek, SeekFrom};
+/// use std::path::Path;
+///
+/// fn read_tail<B: FsHandles>(backend: &B, path: &Path, len: u64) -> Result<Vec<u8>, FsError> {
+///     let handle = backend.open(path, OpenFlags::READ)?;
+///     let mut reader = HandleReader::new(backend, handle).with_len(len);
+///     reader.seek(SeekFrom::End(-4))?;
+///     let mut tail = Vec::new();
+///     reader.read_to_end(&mut tail)?;
+///     backend.close(reader.into_handle())?;
+///     Ok(tail)
+/// }
+/// ```
+pub struct HandleReader<'a, B: FsHandles + ?Sized> {
+    backend: &'a B,
+    handle: Handle,
+    position: u64,
+    len: Option<u64>,
+    len_query: Option<LenQuery<B>>,
+}
+
+impl<'a, B: FsHandles + ?Sized> HandleReader<'a, B> {
+    /// Create a reader positioned at offset 0.
+    pub fn new(backend: &'a B, handle: Handle) -> Self {
+        Self {
+            backend,
+            handle,
+            position: 0,
+            len: None,
+            len_query: None,
+        }
+    }
+
+    /// Supply the file length, enabling `SeekFrom::End`.
+    pub fn with_len(mut self, len: u64) -> Self {
+        self.len = Some(len);
+        self
+    }
+
+    /// The wrapped handle.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Current read position.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Consume the adapter, returning the handle (which is still open).
+    pub fn into_handle(self) -> Handle {
+        self.handle
+    }
+}
+
+impl<B: FsHandles + ?Sized> Read for HandleReader<'_, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.backend.read_at(self.handle, buf, self.position)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a, B: FsSeek + ?Sized> HandleReader<'a, B> {
+    /// Create a reader positioned at offset 0 that takes the length for
+    /// `SeekFrom::End` from [`FsSeek::handle_len`].
+    ///
+    /// The reader still keeps its own position rather than the backend's
+    /// cursor.
+    pub fn seekable(backend: &'a B, handle: Handle) -> Self {
+        Self {
+            len_query: Some(|backend, handle| backend.handle_len(handle)),
+            ..Self::new(backend, handle)
+        }
+    }
+}
+
+impl<B: FsHandles + fmt::Debug + ?Sized> fmt::Debug for HandleReader<'_, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandleReader")
+            .field("backend", &self.backend)
+            .field("handle", &self.handle)
+            .field("position", &self.position)
+            .field("len", &self.len)
+            .field("seekable", &self.len_query.is_some())
+            .finish()
+    }
+}
+
+impl<B: FsHandles + ?Sized> Seek for HandleReader<'_, B> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_target(self.handle, self.position, pos, "seek", || {
+            end_len(self.backend, self.handle, self.len, self.len_query)
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// [`Write`] + [`Seek`] adapter over an open handle.
+///
+/// [`flush`](Write::flush) is a no-op: data reaches the backend on every
+/// `write` call.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsHandles, HandleWriter, OpenFlags, FsError};
+/// use std::io::Write;
+/// use std::path::Path;
+///
+/// fn write_lines<B: FsHandles>(backend: &B, path: &Path, lines: &[&str]) -> Result<(), FsError> {
+///     let handle = backend.open(path, OpenFlags::WRITE)?;
+///     let mut writer = HandleWriter::new(backend, handle);
+///     for line in lines {
+///         writeln!(writer, "{line}")?;
+///     }
+///     backend.close(writer.into_handle())
+/// }
+/// ```
+pub struct HandleWriter<'a, B: FsHandles + ?Sized> {
+    backend: &'a B,
+    handle: Handle,
+    position: u64,
+    len: Option<u64>,
+    len_query: Option<LenQuery<B>>,
+}
+
+impl<'a, B: FsHandles + ?Sized> HandleWriter<'a, B> {
+    /// Create a writer positioned at offset 0.
+    pub fn new(backend: &'a B, handle: Handle) -> Self {
+        Self {
+            backend,
+            handle,
+            position: 0,
+            len: None,
+            len_query: None,
+        }
+    }
+
+    /// Supply the current file length, enabling `SeekFrom::End`.
+    ///
+    /// The writer extends the length as it writes past the end.
+    pub fn with_len(mut self, len: u64) -> Self {
+        self.len = Some(len);
+        self
+    }
+
+    /// The wrapped handle.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Current write position.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Consume the adapter, returning the handle (which is still open).
+    pub fn into_handle(self) -> Handle {
+        self.handle
+    }
+}
+
+impl<B: FsHandles + ?Sized> Write for HandleWriter<'_, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.backend.write_at(self.handle, buf, self.position)?;
+        self.position += n as u64;
+        if let Some(len) = self.len.as_mut() {
+            *len = (*len).max(self.position);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, B: FsSeek + ?Sized> HandleWriter<'a, B> {
+    /// Create a writer positioned at offset 0 that takes the length for
+    /// `SeekFrom::End` from [`FsSeek::handle_len`].
+    ///
+    /// The writer still keeps its own position rather than the backend's
+    /// cursor.
+    pub fn seekable(backend: &'a B, handle: Handle) -> Self {
+        Self {
+            len_query: Some(|backend, handle| backend.handle_len(handle)),
+            ..Self::new(backend, handle)
+        }
+    }
+}
+
+impl<B: FsHandles + fmt::Debug + ?Sized> fmt::Debug for HandleWriter<'_, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandleWriter")
+            .field("backend", &self.backend)
+            .field("handle", &self.handle)
+            .field("position", &self.position)
+            .field("len", &self.len)
+            .field("seekable", &self.len_query.is_some())
+            .finish()
+    }
+}
+
+impl<B: FsHandles + ?Sized> Seek for HandleWriter<'_, B> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_target(self.handle, self.position, pos, "seek", || {
+            end_len(self.backend, self.handle, self.len, self.len_query)
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HandleCursors, OpenFlags};
+    use std::path::Path;
+    use std::sync::RwLock;
+
+    /// Mock backend exposing one shared buffer through any handle
+    struct MockFile {
+        data: RwLock<Vec<u8>>,
+    }
+
+    impl MockFile {
+        fn with_data(data: &[u8]) -> Self {
+            Self {
+                data: RwLock::new(data.to_vec()),
+            }
+        }
+    }
+
+    impl FsHandles for MockFile {
+        fn open(&self, _path: &Path, _flags: OpenFlags) -> Result<Handle, FsError> {
+            Ok(Handle(1))
+        }
+
+        fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+            if handle != Handle(1) {
+                return Err(FsError::InvalidHandle { handle });
+            }
+            let data = self.data.read().unwrap();
+            let start = (offset as usize).min(data.len());
+            let n = buf.len().min(data.len() - start);
+            buf[..n].copy_from_slice(&data[start..start + n]);
+            Ok(n)
+        }
+
+        fn write_at(&self, _handle: Handle, bytes: &[u8], offset: u64) -> Result<usize, FsError> {
+            let mut data = self.data.write().unwrap();
+            let end = offset as usize + bytes.len();
+            if data.len() < end {
+                data.resize(end, 0);
+            }
+            data[offset as usize..end].copy_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn close(&self, _handle: Handle) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reader_reads_to_end() {
+        let fs = MockFile::with_data(b"hello world");
+        let mut reader = HandleReader::new(&fs, Handle(1));
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello world");
+        assert_eq!(reader.position(), 11);
+    }
+
+    #[test]
+    fn reader_seeks() {
+        let fs = MockFile::with_data(b"0123456789");
+        let mut reader = HandleReader::new(&fs, Handle(1)).with_len(10);
+        let mut buf = [0u8; 2];
+
+        reader.seek(SeekFrom::Start(3)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"34");
+
+        reader.seek(SeekFrom::Current(-1)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"45");
+
+        reader.seek(SeekFrom::End(-2)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"89");
+    }
+
+    #[test]
+    fn seek_end_without_len_is_unsupported() {
+        let fs = MockFile::with_data(b"abc");
+        let mut reader = HandleReader::new(&fs, Handle(1));
+        let err = reader.seek(SeekFrom::End(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn seek_before_start_is_invalid_input() {
+        let fs = MockFile::with_data(b"abc");
+        let mut reader = HandleReader::new(&fs, Handle(1));
+        let err = reader.seek(SeekFrom::Current(-1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[test]
+    fn reader_surfaces_backend_errors() {
+        let fs = MockFile::with_data(b"abc");
+        let mut reader = HandleReader::new(&fs, Handle(9));
+        let mut buf = [0u8; 1];
+        let err = reader.read(&mut buf).unwrap_err();
+        assert!(err.to_string().contains("invalid handle"));
+    }
+
+    #[test]
+    fn writer_writes_sequentially_and_extends_len() {
+        let fs = MockFile::with_data(b"");
+        let mut writer = HandleWriter::new(&fs, Handle(1)).with_len(0);
+        writer.write_all(b"hello").unwrap();
+        write!(writer, " {}", 42).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(&*fs.data.read().unwrap(), b"hello 42");
+
+        writer.seek(SeekFrom::End(-2)).unwrap();
+        writer.write_all(b"!!").unwrap();
+        assert_eq!(&*fs.data.read().unwrap(), b"hello !!");
+        assert_eq!(writer.into_handle(), Handle(1));
+    }
+
+    struct SeekableFile {
+        file: MockFile,
+        cursors: HandleCursors,
+    }
+
+    impl FsHandles for SeekableFile {
+        fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+            self.file.open(path, flags)
+        }
+
+        fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+            self.file.read_at(handle, buf, offset)
+        }
+
+        fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+            self.file.write_at(handle, data, offset)
+        }
+
+        fn close(&self, handle: Handle) -> Result<(), FsError> {
+            self.file.close(handle)
+        }
+    }
+
+    impl FsSeek for SeekableFile {
+        fn cursors(&self) -> &HandleCursors {
+            &self.cursors
+        }
+
+        fn handle_len(&self, _handle: Handle) -> Result<u64, FsError> {
+            Ok(self.file.data.read().unwrap().len() as u64)
+        }
+    }
+
+    #[test]
+    fn seekable_adapters_ask_the_backend_for_the_length() {
+        let fs = SeekableFile {
+            file: MockFile::with_data(b"0123456789"),
+            cursors: HandleCursors::new(),
+        };
+        let mut writer = HandleWriter::seekable(&fs, Handle(1));
+        writer.seek(SeekFrom::End(0)).unwrap();
+        writer.write_all(b"ab").unwrap();
+
+        // The reader sees the length grown through the writer
+        let mut reader = HandleReader::seekable(&fs, Handle(1));
+        assert_eq!(reader.seek(SeekFrom::End(-3)).unwrap(), 9);
+        let mut tail = String::new();
+        reader.read_to_string(&mut tail).unwrap();
+        assert_eq!(tail, "9ab");
+        // Adapters keep their own positions, not the backend cursor
+        assert_eq!(fs.cursors.position(Handle(1)), 0);
+    }
+
+    #[test]
+    fn adapters_work_with_trait_objects() {
+        let fs = MockFile::with_data(b"xyz");
+        let dyn_fs: &dyn FsHandles = &fs;
+        let mut reader = HandleReader::new(dyn_fs, Handle(1));
+        let mut out = Vec::new();
+        io::copy(&mut reader, &mut out).unwrap();
+        assert_eq!(out, b"xyz");
+    }
+}
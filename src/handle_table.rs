@@ -0,0 +1,283 @@
+//! # Handle Table
+//!
+//! [`HandleTable`] is the id allocator and payload store behind an
+//! [`FsHandles`](crate::FsHandles) implementation: it hands out [`Handle`]s,
+//! tags reused slots with a new generation, and rejects stale handles with
+//! [`FsError::InvalidHandle`].
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{FsError, Handle};
+
+/// Thread-safe table of open handles with a typed payload per handle.
+///
+/// - Ids start at 1, so `Handle(0)` is never valid.
+/// - Closed slots are reused with their generation advanced (see
+///   [`Handle::next_generation`]), so a stale handle never reaches the file
+///   now occupying its slot.
+/// - [`snapshot`](Self::snapshot) and [`close_all`](Self::close_all) cover
+///   [`FsHandleDebug`](crate::FsHandleDebug) for leak reporting.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, HandleTable, OpenFlags};
+/// use std::path::{Path, PathBuf};
+///
+/// struct OpenFile {
+///     path: PathBuf,
+///     flags: OpenFlags,
+/// }
+///
+/// let table = HandleTable::new();
+/// let handle = table.insert(OpenFile {
+///     path: PathBuf::from("/a.txt"),
+///     flags: OpenFlags::READ,
+/// });
+///
+/// // In read_at/write_at
+/// let (path, readable) = table.with(handle, |file| (file.path.clone(), file.flags.read))?;
+/// assert_eq!(path, Path::new("/a.txt"));
+/// assert!(readable);
+///
+/// // In close
+/// table.remove(handle)?;
+/// assert!(matches!(table.with(handle, |_| ()), Err(FsError::InvalidHandle { .. })));
+///
+/// // A reused slot gets a new generation
+/// let reused = table.insert(OpenFile {
+///     path: PathBuf::from("/b.txt"),
+///     flags: OpenFlags::WRITE,
+/// });
+/// assert!(reused.same_slot(handle));
+/// assert_ne!(reused, handle);
+/// # Ok::<(), FsError>(())
+/// ```
+#[derive(Debug)]
+pub struct HandleTable<T> {
+    slots: RwLock<Slots<T>>,
+}
+
+#[derive(Debug)]
+struct Slots<T> {
+    /// Slot `i` holds handle id `i + 1`.
+    entries: Vec<Slot<T>>,
+    /// Indices of empty slots.
+    free: Vec<usize>,
+    live: usize,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    handle: Handle,
+    value: Option<T>,
+}
+
+impl<T> Slots<T> {
+    fn slot(&self, handle: Handle) -> Option<&Slot<T>> {
+        let index = usize::try_from(handle.id()).ok()?.checked_sub(1)?;
+        self.entries
+            .get(index)
+            .filter(|slot| slot.handle == handle && slot.value.is_some())
+    }
+
+    fn slot_mut(&mut self, handle: Handle) -> Option<&mut Slot<T>> {
+        let index = usize::try_from(handle.id()).ok()?.checked_sub(1)?;
+        self.entries
+            .get_mut(index)
+            .filter(|slot| slot.handle == handle && slot.value.is_some())
+    }
+}
+
+impl<T> HandleTable<T> {
+    /// An empty table.
+    pub fn new() -> Self {
+        Self {
+            slots: RwLock::new(Slots {
+                entries: Vec::new(),
+                free: Vec::new(),
+                live: 0,
+            }),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Slots<T>> {
+        self.slots.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Slots<T>> {
+        self.slots.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store `value` and return a fresh handle for it.
+    pub fn insert(&self, value: T) -> Handle {
+        let mut slots = self.write();
+        slots.live += 1;
+        if let Some(index) = slots.free.pop() {
+            let slot = &mut slots.entries[index];
+            slot.handle = slot.handle.next_generation();
+            slot.value = Some(value);
+            return slot.handle;
+        }
+        let handle = Handle::new(slots.entries.len() as u64 + 1, 0);
+        slots.entries.push(Slot {
+            handle,
+            value: Some(value),
+        });
+        handle
+    }
+
+    /// Run `f` on the payload of `handle`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is unknown, closed, or stale
+    pub fn with<R>(&self, handle: Handle, f: impl FnOnce(&T) -> R) -> Result<R, FsError> {
+        let slots = self.read();
+        match slots.slot(handle).and_then(|slot| slot.value.as_ref()) {
+            Some(value) => Ok(f(value)),
+            None => Err(FsError::InvalidHandle { handle }),
+        }
+    }
+
+    /// Run `f` on the payload of `handle`, mutably.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is unknown, closed, or stale
+    pub fn with_mut<R>(&self, handle: Handle, f: impl FnOnce(&mut T) -> R) -> Result<R, FsError> {
+        let mut slots = self.write();
+        match slots.slot_mut(handle).and_then(|slot| slot.value.as_mut()) {
+            Some(value) => Ok(f(value)),
+            None => Err(FsError::InvalidHandle { handle }),
+        }
+    }
+
+    /// Returns `true` if `handle` is open.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.read().slot(handle).is_some()
+    }
+
+    /// Close `handle`, returning its payload.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is unknown, closed, or stale
+    pub fn remove(&self, handle: Handle) -> Result<T, FsError> {
+        let mut slots = self.write();
+        let value = slots
+            .slot_mut(handle)
+            .and_then(|slot| slot.value.take())
+            .ok_or(FsError::InvalidHandle { handle })?;
+        slots.live -= 1;
+        slots.free.push(handle.id() as usize - 1);
+        Ok(value)
+    }
+
+    /// Close every open handle, returning them with their payloads in id
+    /// order.
+    pub fn close_all(&self) -> Vec<(Handle, T)> {
+        let mut slots = self.write();
+        let Slots {
+            entries,
+            free,
+            live,
+        } = &mut *slots;
+        let mut closed = Vec::with_capacity(*live);
+        for (index, slot) in entries.iter_mut().enumerate() {
+            if let Some(value) = slot.value.take() {
+                closed.push((slot.handle, value));
+                free.push(index);
+            }
+        }
+        *live = 0;
+        closed
+    }
+
+    /// Map every open handle through `f`, in id order.
+    ///
+    /// Used to build [`HandleInfo`](crate::HandleInfo)s for leak reports.
+    pub fn snapshot<R>(&self, mut f: impl FnMut(Handle, &T) -> R) -> Vec<R> {
+        let slots = self.read();
+        slots
+            .entries
+            .iter()
+            .filter_map(|slot| slot.value.as_ref().map(|value| f(slot.handle, value)))
+            .collect()
+    }
+
+    /// Number of open handles.
+    pub fn len(&self) -> usize {
+        self.read().live
+    }
+
+    /// Returns `true` if no handle is open.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for HandleTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_zero_is_never_valid() {
+        let table = HandleTable::new();
+        let first = table.insert("a");
+        assert_eq!(first, Handle(1));
+        assert!(!table.contains(Handle(0)));
+        assert!(table.remove(Handle(0)).is_err());
+    }
+
+    #[test]
+    fn stale_handle_is_rejected_after_reuse() {
+        let table = HandleTable::new();
+        let old = table.insert("old");
+        table.remove(old).unwrap();
+        let new = table.insert("new");
+
+        assert!(new.same_slot(old));
+        assert_eq!(new.generation(), 1);
+        assert!(matches!(
+            table.with(old, |v| *v),
+            Err(FsError::InvalidHandle { handle }) if handle == old
+        ));
+        assert_eq!(table.with(new, |v| *v).unwrap(), "new");
+    }
+
+    #[test]
+    fn with_mut_updates_payload() {
+        let table = HandleTable::new();
+        let handle = table.insert(0u64);
+        table.with_mut(handle, |n| *n += 5).unwrap();
+        assert_eq!(table.with(handle, |n| *n).unwrap(), 5);
+    }
+
+    #[test]
+    fn close_all_drains_in_id_order() {
+        let table = HandleTable::new();
+        let a = table.insert('a');
+        let b = table.insert('b');
+        let c = table.insert('c');
+        table.remove(b).unwrap();
+
+        assert_eq!(table.snapshot(|h, v| (h, *v)), [(a, 'a'), (c, 'c')]);
+        assert_eq!(table.close_all(), [(a, 'a'), (c, 'c')]);
+        assert!(table.is_empty());
+        assert!(!table.contains(a));
+
+        // Every slot is reusable afterwards
+        table.insert('d');
+        table.insert('e');
+        table.insert('f');
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.snapshot(|h, _| h.id()), [1, 2, 3]);
+    }
+}
@@ -0,0 +1,268 @@
+//! # Inode Table
+//!
+//! [`InodeTable`] is the bidirectional path ↔ inode map that path-based
+//! backends need to implement [`FsInode`](crate::FsInode). Embedding it
+//! instead of rolling one per backend gets renames and removals of whole
+//! subtrees right.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::ROOT_INODE;
+
+/// Thread-safe path ↔ inode map for backends without native inodes.
+///
+/// - `/` is always [`ROOT_INODE`].
+/// - [`assign`](Self::assign) hands out numbers on first use, so inodes can
+///   be allocated lazily as FUSE looks paths up.
+/// - [`rename`](Self::rename) and [`remove`](Self::remove) act on whole
+///   subtrees: descendants keep their inodes across a rename and are freed
+///   with their directory.
+/// - Freed numbers are reused, lowest first, with their
+///   [`generation`](Self::generation) advanced, so NFS-style
+///   `(inode, generation)` pairs never name two different files.
+///
+/// Each inode has exactly one path; backends with hard links need to track
+/// the extra names themselves. Renames and removals scan the whole table.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, InodeTable, ROOT_INODE};
+/// use std::path::{Path, PathBuf};
+///
+/// let table = InodeTable::new();
+/// let dir = table.assign(Path::new("/docs"));
+/// let file = table.assign(Path::new("/docs/a.txt"));
+///
+/// table.rename(Path::new("/docs"), Path::new("/archive"));
+/// assert_eq!(table.inode(Path::new("/archive/a.txt")), Some(file));
+/// assert_eq!(table.path(dir), Some(PathBuf::from("/archive")));
+///
+/// // In an FsInode implementation
+/// let inode_to_path = |inode| table.path(inode).ok_or(FsError::InodeNotFound { inode });
+/// assert_eq!(inode_to_path(ROOT_INODE).unwrap(), Path::new("/"));
+/// ```
+#[derive(Debug)]
+pub struct InodeTable {
+    tables: RwLock<Tables>,
+}
+
+#[derive(Debug)]
+struct Tables {
+    by_path: HashMap<PathBuf, u64>,
+    by_inode: HashMap<u64, PathBuf>,
+    /// Generation of every number ever handed out, live or free.
+    generations: HashMap<u64, u32>,
+    free: BTreeSet<u64>,
+    next: u64,
+}
+
+impl Tables {
+    /// Drop `path` and everything below it, freeing their numbers.
+    fn remove_subtree(&mut self, path: &Path) {
+        let doomed: Vec<PathBuf> = self
+            .by_path
+            .keys()
+            .filter(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+        for p in doomed {
+            if let Some(inode) = self.by_path.remove(&p) {
+                self.by_inode.remove(&inode);
+                self.free.insert(inode);
+            }
+        }
+    }
+}
+
+impl InodeTable {
+    /// A table holding only `/`.
+    pub fn new() -> Self {
+        let root = PathBuf::from("/");
+        Self {
+            tables: RwLock::new(Tables {
+                by_path: HashMap::from([(root.clone(), ROOT_INODE)]),
+                by_inode: HashMap::from([(ROOT_INODE, root)]),
+                generations: HashMap::from([(ROOT_INODE, 0)]),
+                free: BTreeSet::new(),
+                next: ROOT_INODE + 1,
+            }),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The inode of `path`, allocating one if it has none yet.
+    pub fn assign(&self, path: &Path) -> u64 {
+        if let Some(inode) = self.inode(path) {
+            return inode;
+        }
+        let mut tables = self.write();
+        // Another thread may have assigned it in between
+        if let Some(&inode) = tables.by_path.get(path) {
+            return inode;
+        }
+        let inode = match tables.free.pop_first() {
+            Some(inode) => {
+                let generation = tables.generations.entry(inode).or_insert(0);
+                *generation = generation.wrapping_add(1);
+                inode
+            }
+            None => {
+                let inode = tables.next;
+                tables.next += 1;
+                tables.generations.insert(inode, 0);
+                inode
+            }
+        };
+        tables.by_path.insert(path.to_path_buf(), inode);
+        tables.by_inode.insert(inode, path.to_path_buf());
+        inode
+    }
+
+    /// The inode of `path`, if it has one.
+    pub fn inode(&self, path: &Path) -> Option<u64> {
+        self.read().by_path.get(path).copied()
+    }
+
+    /// The path of `inode`, if it is live.
+    pub fn path(&self, inode: u64) -> Option<PathBuf> {
+        self.read().by_inode.get(&inode).cloned()
+    }
+
+    /// How many times `inode` has been reused, if it is live.
+    pub fn generation(&self, inode: u64) -> Option<u32> {
+        let tables = self.read();
+        if !tables.by_inode.contains_key(&inode) {
+            return None;
+        }
+        tables.generations.get(&inode).copied()
+    }
+
+    /// Move `from` and everything below it to `to`, keeping their inodes.
+    ///
+    /// Whatever was at `to` is replaced and its inodes freed, as a rename
+    /// over an existing entry does. Renaming a path without an inode only
+    /// frees what was at `to`. Renames a filesystem would reject, into the
+    /// source's own subtree or over one of its ancestors, do nothing.
+    pub fn rename(&self, from: &Path, to: &Path) {
+        if to.starts_with(from) || from.starts_with(to) {
+            return;
+        }
+        let mut tables = self.write();
+        tables.remove_subtree(to);
+        let moved: Vec<(PathBuf, u64)> = tables
+            .by_path
+            .iter()
+            .filter(|(p, _)| p.starts_with(from))
+            .map(|(p, &inode)| (p.clone(), inode))
+            .collect();
+        for (old, inode) in moved {
+            let new = match old.strip_prefix(from) {
+                Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(rest) => to.join(rest),
+                Err(_) => continue,
+            };
+            tables.by_path.remove(&old);
+            tables.by_path.insert(new.clone(), inode);
+            tables.by_inode.insert(inode, new);
+        }
+    }
+
+    /// Forget `path` and everything below it, freeing their inodes.
+    ///
+    /// Returns the inode `path` had. `/` is never removed.
+    pub fn remove(&self, path: &Path) -> Option<u64> {
+        if path == Path::new("/") {
+            return None;
+        }
+        let mut tables = self.write();
+        let inode = tables.by_path.get(path).copied();
+        tables.remove_subtree(path);
+        inode
+    }
+}
+
+impl Default for InodeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(s: &str) -> &Path {
+        Path::new(s)
+    }
+
+    #[test]
+    fn root_is_preassigned() {
+        let table = InodeTable::new();
+        assert_eq!(table.inode(p("/")), Some(ROOT_INODE));
+        assert_eq!(table.assign(p("/")), ROOT_INODE);
+        assert_eq!(table.remove(p("/")), None);
+        assert_eq!(table.path(ROOT_INODE), Some(PathBuf::from("/")));
+    }
+
+    #[test]
+    fn assign_is_idempotent() {
+        let table = InodeTable::new();
+        let a = table.assign(p("/a"));
+        assert_eq!(table.assign(p("/a")), a);
+        assert_ne!(table.assign(p("/b")), a);
+    }
+
+    #[test]
+    fn rename_moves_subtree_only() {
+        let table = InodeTable::new();
+        let dir = table.assign(p("/a"));
+        let child = table.assign(p("/a/f"));
+        let sibling = table.assign(p("/ab"));
+
+        table.rename(p("/a"), p("/z"));
+        assert_eq!(table.inode(p("/z")), Some(dir));
+        assert_eq!(table.inode(p("/z/f")), Some(child));
+        assert_eq!(table.path(child), Some(PathBuf::from("/z/f")));
+        assert_eq!(table.inode(p("/a/f")), None);
+        assert_eq!(table.inode(p("/ab")), Some(sibling));
+    }
+
+    #[test]
+    fn rename_over_existing_frees_target() {
+        let table = InodeTable::new();
+        let src = table.assign(p("/src"));
+        let old = table.assign(p("/dst"));
+        let old_child = table.assign(p("/dst/x"));
+
+        table.rename(p("/src"), p("/dst"));
+        assert_eq!(table.inode(p("/dst")), Some(src));
+        assert_eq!(table.path(old), None);
+        assert_eq!(table.path(old_child), None);
+    }
+
+    #[test]
+    fn remove_frees_subtree_and_reuse_bumps_generation() {
+        let table = InodeTable::new();
+        let dir = table.assign(p("/d"));
+        table.assign(p("/d/f"));
+        assert_eq!(table.generation(dir), Some(0));
+
+        assert_eq!(table.remove(p("/d")), Some(dir));
+        assert_eq!(table.inode(p("/d/f")), None);
+        assert_eq!(table.generation(dir), None);
+
+        let reused = table.assign(p("/new"));
+        assert_eq!(reused, dir);
+        assert_eq!(table.generation(reused), Some(1));
+    }
+}
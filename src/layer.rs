@@ -66,7 +66,7 @@
 //! //     .layer(TracingLayer::new());
 //! ```
 
-use crate::Fs;
+use crate::{CreateMode, DefaultPermissions, Fs};
 
 /// A layer that wraps a backend to add functionality.
 ///
@@ -157,6 +157,24 @@ pub trait LayerExt: Fs + Sized {
     fn layer<L: Layer<Self>>(self, layer: L) -> L::Backend {
         layer.layer(self)
     }
+
+    /// Give newly created files and directories the modes of `mode`.
+    ///
+    /// Shorthand for `self.layer(DefaultPermissionsLayer::new(mode))`; see
+    /// [`DefaultPermissions`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{CreateMode, Fs, FsPermissions, LayerExt, UmaskPolicy};
+    ///
+    /// fn group_shared<B: Fs + FsPermissions>(backend: B) -> impl Fs + FsPermissions {
+    ///     backend.with_default_permissions(CreateMode::default().with_umask(UmaskPolicy::new(0o002)))
+    /// }
+    /// ```
+    fn with_default_permissions(self, mode: CreateMode) -> DefaultPermissions<Self> {
+        DefaultPermissions::new(self, mode)
+    }
 }
 
 // Blanket implementation - any Fs backend gets LayerExt for free
@@ -91,6 +91,25 @@
 //! | [`FsXattr`] | Extended attrs | `get_xattr`, `set_xattr` | Metadata storage |
 //! | [`FsPath`] | Path resolution | `canonicalize` | Symlink handling |
 //!
+//! ### Optional Traits (Not Part of Any Composite)
+//!
+//! | Trait | Provides | Key Methods | When to Use |
+//! |-------|----------|-------------|-------------|
+//! | [`FsHandleDebug`] | Handle introspection | `open_handles`, `close_all` | Leak detection |
+//! | [`FsFileFlags`] | `chattr`-style flags | `set_flags`, `get_flags` | WORM/compliance storage |
+//! | [`FsSeek`] | Cursor-based handle I/O | `seek_handle`, `read_handle`, `write_handle` | Ported fd-style code |
+//! | [`FsFileId`] | Stable file identity | `file_id`, `path_by_id` | Sync, dedup, rename tracking |
+//! | [`FsExport`] | 9P/NFS export surface | `export_id`, `open_by_id`, `lease_hint` | Network file servers |
+//! | [`FsDirHandles`] | Directory handles | `open_dir`, `open_at`, `unlink_at` | TOCTOU-safe traversal |
+//! | [`FsMaybeFull`] | Runtime Layer 2 discovery | `as_link`, `as_permissions`, `as_sync`, `as_stats` | Tools that degrade gracefully |
+//! | [`FsTiering`] | Storage tiers | `set_storage_class`, `storage_class` | Object-store lifecycle tooling |
+//! | [`FsExpiry`] | Expiring entries | `set_expiry`, `expiry` | Cache directories, with [`ExpirySweeper`] |
+//! | [`FsLease`] | Caching leases | `acquire_lease`, `release_lease`, `on_lease_break` | SMB/NFS-style client caches |
+//! | [`FsBranch`] | Copy-on-write forks | `branch`, `merge`, `discard_branch` | Config staging, experiments |
+//! | [`FsLayers`] | Overlay introspection | `layers`, `which_layer`, `copy_up` | Union filesystem tooling |
+//! | [`FsSequence`] | Mutation ordering | `seq` | Replication, journals, change feeds |
+//! | [`FsCacheControl`] | Cache configuration | `cache_policy`, `cache_stats`, `invalidate` | Tuning and invalidating cache middleware |
+//!
 //! ### Composite Traits (What You Use in Bounds)
 //!
 //! | Trait | Combines | Typical Consumer |
@@ -129,7 +148,7 @@
 //! use std::path::PathBuf;
 //!
 //! // Errors include the path that caused the problem
-//! let err = FsError::NotFound { path: PathBuf::from("/missing.txt") };
+//! let err = FsError::not_found("read", "/missing.txt");
 //! assert_eq!(err.to_string(), "not found: /missing.txt");
 //!
 //! // Permission errors include the operation
@@ -156,7 +175,34 @@
 //!
 //! | Feature | Description |
 //! |---------|-------------|
-//! | `serde` | Enable serialization for [`Metadata`], [`DirEntry`], [`Permissions`], etc. |
+//! | `serde` | Enable serialization for [`Metadata`], [`DirEntry`], [`Permissions`], [`FsOp`], etc., and the `JournalLayer` middleware; see [Serde Stability](#serde-stability) |
+//! | `wire` | Request/response types for remote backends (`WireRequest`, `RequestFrame`, ...); implies `serde` |
+//! | `testing` | [`testing`] module: the in-memory `MemFs` backend and other test helpers |
+//! | `rayon` | `ParWalk` and `FsExt::par_walk`: directory walks with subtrees listed in parallel |
+//! | `futures-io` | `AsyncReader` and `AsyncWriter`: backend streams as `futures_io::AsyncRead`/`AsyncWrite` |
+//! | `tokio` | `TokioFs`: async methods running any backend on tokio's blocking pool, with a concurrency limit |
+//! | `vfs` | `AnyfsAsVfs` and `VfsAsAnyfs`: adapters to and from the `vfs` crate's `FileSystem` |
+//! | `fuzz` | [`fuzz`] module: arbitrary paths and operation sequences for `cargo fuzz` targets |
+//! | `cap-std` | `CapStdFs` (a `cap_std::fs::Dir` as an `Fs`) and `CapDir` (an `Fs` as a scoped directory handle) |
+//!
+//! ---
+//!
+//! ## Serde Stability
+//!
+//! Serialized values cross process boundaries, where writer and reader may
+//! be built from different versions of this crate. Their shape therefore
+//! only changes additively:
+//!
+//! - Fields added to a struct after it first shipped are
+//!   `#[serde(default)]`, so values from older writers still read.
+//! - No type uses `deny_unknown_fields`, so values from newer writers read
+//!   with the fields this build doesn't know dropped.
+//! - Renaming, removing or retyping a field or variant, or adding an enum
+//!   variant (older readers reject it), bumps [`SERDE_SCHEMA_VERSION`].
+//!
+//! Peers that exchange serialized values compare `SERDE_SCHEMA_VERSION`
+//! before trusting each other's data. The `wire` protocol carries its own
+//! `WIRE_PROTOCOL_VERSION` in every frame.
 //!
 //! ---
 //!
@@ -167,47 +213,144 @@
 //! For concrete implementations, see the `anyfs` crate which provides:
 //! - `MemoryBackend` — In-memory filesystem
 //! - `NativeBackend` — Wrapper around `std::fs`
-//! - `OverlayBackend` — UnionFS-style layering
+//! - `OverlayBackend` — UnionFS-style layering, introspected through [`FsLayers`]
 //! - `FileStorage` — Type-erased filesystem wrapper
 //! - Middleware (encryption, compression, caching, etc.)
 
 // Private modules
+mod api_version;
+#[cfg(feature = "futures-io")]
+mod async_io;
+#[cfg(feature = "cap-std")]
+mod cap_std_compat;
+mod chunked;
+mod clock;
+mod closure_fs;
+mod config;
+mod dispatch;
+mod encryption;
 mod error;
 mod ext;
+mod handle_io;
+mod handle_table;
+mod inode_table;
 mod layer;
+mod link_store;
 mod markers;
+mod mem_snapshot;
+mod middleware;
+#[cfg(feature = "rayon")]
+mod par_walk;
+mod path_locks;
 mod path_resolver;
+mod progress;
+mod read_only;
+#[cfg(feature = "tokio")]
+mod tokio_fs;
 mod traits;
 mod types;
+mod unsupported;
+#[cfg(feature = "vfs")]
+mod vfs_compat;
+mod virtual_path;
+mod walk;
+#[cfg(feature = "wire")]
+mod wire;
+
+// Public modules
+pub mod bulk;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 // Public re-exports - error types
-pub use error::FsError;
+pub use error::{DirEntryError, FsError};
 
 // Public re-exports - core types
 pub use types::{
-    DirEntry, FileType, Handle, LockType, Metadata, OpenFlags, Permissions, StatFs, ROOT_INODE,
+    CachePolicy, CacheStats, CompressionHint, ContentTag, CopyOptions, CreateMode, Credentials,
+    DirEntry, DirHandle, FileFlags, FileId, FileType, Handle, HandleInfo, IoStats, LayerId,
+    LayerInfo, LeaseHint, LeaseType, LockOwner, LockRange, LockType, MergePolicy, MergeReport,
+    Metadata, MetadataLite, MetadataMask, NameCollisionPolicy, NotFoundCacheHint, OpenFlags,
+    OpenOptions, OverwritePolicy, Permissions, ReadDirOptions, RemoveDirReport, RemoveFailure, Rwx,
+    SetAttr, StatFs, StorageClass, TagAlgorithm, UmaskPolicy, UsageReport, WriteOptions,
+    WritePolicy, ROOT_INODE, SERDE_SCHEMA_VERSION,
 };
 
 // Public re-exports - Layer 1 core traits
-pub use traits::{Fs, FsDir, FsRead, FsWrite, ReadDirIter};
+pub use traits::{Fs, FsDir, FsRead, FsWrite, ReadDirIter, SkipErrors};
 
 // Public re-exports - Layer 2 extended traits
-pub use traits::{FsFull, FsLink, FsPath, FsPermissions, FsStats, FsSync};
+pub use traits::{is_path_within, FsFull, FsLink, FsPath, FsPermissions, FsStats, FsSync};
 
 // Public re-exports - Layer 3 FUSE traits
-pub use traits::{FsFuse, FsInode};
+pub use traits::{read_dir_inode_by_index, set_attr_by_path, FsFuse, FsInode};
 
 // Public re-exports - Layer 4 POSIX traits
-pub use traits::{FsHandles, FsLock, FsPosix, FsXattr};
+pub use traits::{
+    FsHandles, FsLock, FsPosix, FsXattr, TempFileEmulation, XATTR_CONTENT_TYPE, XATTR_ENCODING,
+    XATTR_ENC_ALGORITHM, XATTR_ENC_KEY_ID, XATTR_ENC_NONCE, XATTR_EXPIRES,
+};
+
+// Public re-exports - optional traits
+pub use traits::{
+    ExpirySweeper, FsBranch, FsCacheControl, FsDirHandles, FsExpiry, FsExport, FsFileFlags,
+    FsFileId, FsHandleDebug, FsLayers, FsLease, FsMaybeFull, FsSeek, FsSequence, FsTiering,
+    HandleCursors, LeaseBreak, LeaseBreakHandler, SequenceCounter,
+};
 
 // Public re-exports - path resolution
-pub use path_resolver::PathResolver;
+pub use path_resolver::{
+    CaseSensitivity, EscapePolicy, PathResolver, PolicyResolver, ResolvePolicy,
+};
 
 // Public re-exports - infrastructure
+pub use api_version::{
+    compatible_with, DeclaredBackend, ANYFS_API_VERSION, BACKEND_API_VERSION_SYMBOL,
+};
+pub use chunked::{Chunk, ChunkPolicy, ChunkSink, ChunkedWriter, HandleChunks};
+pub use clock::{Clock, FixedClock, SteppingClock, SystemClock};
+pub use closure_fs::{ClosureFs, FsBuilder};
+pub use config::{BackendConfig, Configure, CredentialsRef};
+pub use dispatch::{DispatchFs, Dispatcher, FsDispatch, FsOp, FsOpResult};
+pub use encryption::{EncryptionInfo, KeyMaterial, KeyProvider};
 pub use ext::FsExt;
+pub use handle_io::{HandleReader, HandleWriter};
+pub use handle_table::HandleTable;
+pub use inode_table::InodeTable;
 pub use layer::{Layer, LayerExt};
-pub use markers::SelfResolving;
+pub use link_store::LinkStore;
+pub use markers::{Resolved, SelfResolving};
+pub use mem_snapshot::MemSnapshot;
+pub use middleware::{
+    DefaultPermissions, DefaultPermissionsLayer, Deterministic, DeterministicLayer, Divergence,
+    DryRun, DryRunLayer, EnforceFlags, EnforceFlagsLayer, EnforcePermissions,
+    EnforcePermissionsLayer, FsScan, HookLayer, Hooks, IoCountLayer, IoCounted, MirrorLayer,
+    MirrorPolicy, Mirrored, Namespace, NamespaceLayer, Protect, ProtectLayer, ReplicaMode,
+    ScanLayer, ScanVerdict, Scanned, StrictPaths, StrictPathsLayer,
+};
+pub use path_locks::{PathGuard, PathLocks};
+pub use progress::{MoveProgress, Progress, ProgressSink};
+pub use virtual_path::{
+    validate_virtual_path, virtual_ancestors, virtual_depth, VirtualComponent, VirtualComponents,
+};
+pub use walk::{Walk, WalkEntry};
 
 // Conditional re-exports
+#[cfg(feature = "futures-io")]
+pub use async_io::{AsyncReader, AsyncStreamPolicy, AsyncWriter};
+#[cfg(feature = "cap-std")]
+pub use cap_std_compat::{CapDir, CapStdFs};
 #[cfg(feature = "serde")]
 pub use ext::FsExtJson;
+#[cfg(feature = "serde")]
+pub use middleware::{JournalLayer, Journaled, Replayed};
+#[cfg(feature = "rayon")]
+pub use par_walk::ParWalk;
+#[cfg(feature = "tokio")]
+pub use tokio_fs::TokioFs;
+#[cfg(feature = "vfs")]
+pub use vfs_compat::{AnyfsAsVfs, VfsAsAnyfs};
+#[cfg(feature = "wire")]
+pub use wire::{RequestFrame, ResponseFrame, WireRequest, WireResponse, WIRE_PROTOCOL_VERSION};
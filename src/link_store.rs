@@ -0,0 +1,327 @@
+//! # Link Store
+//!
+//! [`LinkStore`] gives simple backends real hard links: names map to inodes,
+//! inodes own their content and count their links, and the content is freed
+//! with the last name.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{FsError, ROOT_INODE};
+
+/// Thread-safe store of file contents shared between hard links.
+///
+/// - Every name of a file refers to the same content, so a write through one
+///   is visible through all.
+/// - [`unlink`](Self::unlink) and [`rename`](Self::rename) hand back the
+///   content once its last name is gone, so the backend can keep it alive
+///   for open handles or drop it.
+/// - Inodes start after [`ROOT_INODE`] and are never reused, so they can be
+///   reported as [`Metadata::inode`](crate::Metadata::inode) directly, with
+///   [`nlink`](Self::nlink) as [`Metadata::nlink`](crate::Metadata::nlink).
+///
+/// Only files live here; directories and symlinks stay with the backend.
+/// Names are compared as given, so pass canonical paths.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, LinkStore};
+/// use std::path::Path;
+///
+/// let store = LinkStore::new();
+/// let inode = store.create(Path::new("/a.txt"), b"hello".to_vec())?;
+///
+/// // In FsLink::hard_link
+/// store.link(Path::new("/a.txt"), Path::new("/b.txt"))?;
+/// assert_eq!(store.inode(Path::new("/b.txt")), Some(inode));
+/// assert_eq!(store.nlink(Path::new("/a.txt")), Some(2));
+///
+/// // Writes through one name show through the other
+/// store.with_mut(Path::new("/b.txt"), |data| data.extend_from_slice(b"!"))?;
+/// assert_eq!(store.with(Path::new("/a.txt"), |data| data.clone())?, b"hello!");
+///
+/// // Content is freed with its last name
+/// assert_eq!(store.unlink(Path::new("/a.txt"))?, None);
+/// assert_eq!(store.unlink(Path::new("/b.txt"))?, Some(b"hello!".to_vec()));
+/// # Ok::<(), FsError>(())
+/// ```
+#[derive(Debug)]
+pub struct LinkStore<T> {
+    links: RwLock<Links<T>>,
+}
+
+#[derive(Debug)]
+struct Links<T> {
+    names: HashMap<PathBuf, u64>,
+    blobs: HashMap<u64, Blob<T>>,
+    next: u64,
+}
+
+#[derive(Debug)]
+struct Blob<T> {
+    value: T,
+    nlink: u64,
+}
+
+impl<T> Links<T> {
+    /// Drop the name `path`, returning the content if it was the last one.
+    /// `None` if `path` is not a name.
+    fn unlink(&mut self, path: &Path) -> Option<Option<T>> {
+        let inode = self.names.remove(path)?;
+        let blob = self.blobs.get_mut(&inode)?;
+        blob.nlink -= 1;
+        if blob.nlink > 0 {
+            return Some(None);
+        }
+        Some(self.blobs.remove(&inode).map(|blob| blob.value))
+    }
+}
+
+impl<T> LinkStore<T> {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self {
+            links: RwLock::new(Links {
+                names: HashMap::new(),
+                blobs: HashMap::new(),
+                next: ROOT_INODE + 1,
+            }),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Links<T>> {
+        self.links.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Links<T>> {
+        self.links.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store `value` as a new file named `path`, returning its inode.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::AlreadyExists`] if `path` is already a name
+    pub fn create(&self, path: &Path, value: T) -> Result<u64, FsError> {
+        let mut links = self.write();
+        if links.names.contains_key(path) {
+            return Err(FsError::already_exists("create", path));
+        }
+        let inode = links.next;
+        links.next += 1;
+        links.blobs.insert(inode, Blob { value, nlink: 1 });
+        links.names.insert(path.to_path_buf(), inode);
+        Ok(inode)
+    }
+
+    /// Add `link` as another name for the file at `original`, returning its
+    /// inode.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `original` is not a name
+    /// - [`FsError::AlreadyExists`] if `link` is already a name
+    pub fn link(&self, original: &Path, link: &Path) -> Result<u64, FsError> {
+        let mut links = self.write();
+        let inode = *links
+            .names
+            .get(original)
+            .ok_or_else(|| FsError::not_found("hard_link", original))?;
+        if links.names.contains_key(link) {
+            return Err(FsError::already_exists("hard_link", link));
+        }
+        if let Some(blob) = links.blobs.get_mut(&inode) {
+            blob.nlink += 1;
+        }
+        links.names.insert(link.to_path_buf(), inode);
+        Ok(inode)
+    }
+
+    /// Remove the name `path`.
+    ///
+    /// Returns the file's content if this was its last name.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `path` is not a name
+    pub fn unlink(&self, path: &Path) -> Result<Option<T>, FsError> {
+        self.write()
+            .unlink(path)
+            .ok_or_else(|| FsError::not_found("remove_file", path))
+    }
+
+    /// Move the name `from`, and every name below it, to `to`.
+    ///
+    /// Pass a directory as `from` to carry the files inside it along. A file
+    /// named `to` is unlinked first, and its content returned if that was
+    /// its last name. As in POSIX, renaming one name of a file onto another
+    /// name of the same file does nothing, and so do renames into `from`'s
+    /// own subtree or over one of its ancestors.
+    pub fn rename(&self, from: &Path, to: &Path) -> Option<T> {
+        if to.starts_with(from) || from.starts_with(to) {
+            return None;
+        }
+        let mut links = self.write();
+        if let (Some(a), Some(b)) = (links.names.get(from), links.names.get(to)) {
+            if a == b {
+                return None;
+            }
+        }
+        let freed = links.unlink(to).flatten();
+        let moved: Vec<(PathBuf, u64)> = links
+            .names
+            .iter()
+            .filter(|(p, _)| p.starts_with(from))
+            .map(|(p, &inode)| (p.clone(), inode))
+            .collect();
+        for (old, inode) in moved {
+            let new = match old.strip_prefix(from) {
+                Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(rest) => to.join(rest),
+                Err(_) => continue,
+            };
+            links.names.remove(&old);
+            links.names.insert(new, inode);
+        }
+        freed
+    }
+
+    /// Run `f` on the content of the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `path` is not a name
+    pub fn with<R>(&self, path: &Path, f: impl FnOnce(&T) -> R) -> Result<R, FsError> {
+        let links = self.read();
+        match links
+            .names
+            .get(path)
+            .and_then(|inode| links.blobs.get(inode))
+        {
+            Some(blob) => Ok(f(&blob.value)),
+            None => Err(FsError::not_found("read", path)),
+        }
+    }
+
+    /// Run `f` on the content of the file at `path`, mutably.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `path` is not a name
+    pub fn with_mut<R>(&self, path: &Path, f: impl FnOnce(&mut T) -> R) -> Result<R, FsError> {
+        let mut links = self.write();
+        let Links { names, blobs, .. } = &mut *links;
+        match names.get(path).and_then(|inode| blobs.get_mut(inode)) {
+            Some(blob) => Ok(f(&mut blob.value)),
+            None => Err(FsError::not_found("write", path)),
+        }
+    }
+
+    /// The inode of the file at `path`, if it is a name.
+    pub fn inode(&self, path: &Path) -> Option<u64> {
+        self.read().names.get(path).copied()
+    }
+
+    /// The number of names of the file at `path`, if it is a name.
+    pub fn nlink(&self, path: &Path) -> Option<u64> {
+        let links = self.read();
+        let inode = links.names.get(path)?;
+        links.blobs.get(inode).map(|blob| blob.nlink)
+    }
+
+    /// Every name of `inode`, sorted.
+    pub fn paths(&self, inode: u64) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .read()
+            .names
+            .iter()
+            .filter(|(_, &i)| i == inode)
+            .map(|(p, _)| p.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+impl<T> Default for LinkStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(s: &str) -> &Path {
+        Path::new(s)
+    }
+
+    #[test]
+    fn inodes_never_collide_with_root() {
+        let store = LinkStore::new();
+        let inode = store.create(p("/a"), ()).unwrap();
+        assert!(inode > ROOT_INODE);
+        store.unlink(p("/a")).unwrap();
+        assert_ne!(store.create(p("/a"), ()).unwrap(), inode);
+    }
+
+    #[test]
+    fn create_and_link_reject_taken_names() {
+        let store = LinkStore::new();
+        store.create(p("/a"), 1).unwrap();
+        store.create(p("/b"), 2).unwrap();
+        assert!(matches!(
+            store.create(p("/a"), 3),
+            Err(FsError::AlreadyExists { .. })
+        ));
+        assert!(matches!(
+            store.link(p("/a"), p("/b")),
+            Err(FsError::AlreadyExists { .. })
+        ));
+        assert!(matches!(
+            store.link(p("/missing"), p("/c")),
+            Err(FsError::NotFound { .. })
+        ));
+        assert_eq!(store.nlink(p("/a")), Some(1));
+    }
+
+    #[test]
+    fn rename_over_last_link_frees_target() {
+        let store = LinkStore::new();
+        store.create(p("/src"), "src").unwrap();
+        store.create(p("/dst"), "dst").unwrap();
+        store.link(p("/dst"), p("/dst2")).unwrap();
+
+        // /dst2 still names the old content
+        assert_eq!(store.rename(p("/src"), p("/dst")), None);
+        assert_eq!(store.with(p("/dst"), |v| *v).unwrap(), "src");
+        assert_eq!(store.nlink(p("/dst2")), Some(1));
+
+        store.create(p("/other"), "other").unwrap();
+        assert_eq!(store.rename(p("/other"), p("/dst2")), Some("dst"));
+    }
+
+    #[test]
+    fn rename_between_links_of_same_file_is_noop() {
+        let store = LinkStore::new();
+        let inode = store.create(p("/a"), ()).unwrap();
+        store.link(p("/a"), p("/b")).unwrap();
+        assert_eq!(store.rename(p("/a"), p("/b")), None);
+        assert_eq!(store.paths(inode), [p("/a"), p("/b")]);
+    }
+
+    #[test]
+    fn rename_directory_moves_files_below_it() {
+        let store = LinkStore::new();
+        let inner = store.create(p("/dir/f"), ()).unwrap();
+        let sibling = store.create(p("/dirx"), ()).unwrap();
+        store.link(p("/dir/f"), p("/g")).unwrap();
+
+        store.rename(p("/dir"), p("/moved"));
+        assert_eq!(store.paths(inner), [p("/g"), p("/moved/f")]);
+        assert_eq!(store.inode(p("/dirx")), Some(sibling));
+    }
+}
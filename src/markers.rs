@@ -13,6 +13,15 @@
 //! | Marker | Purpose |
 //! |--------|---------|
 //! | [`SelfResolving`] | Backend handles its own path resolution |
+//!
+//! ## Marker Types
+//!
+//! | Type | Purpose |
+//! |------|---------|
+//! | [`Resolved`] | Path that was already canonicalized by a layer above |
+
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
 
 /// Marker trait for backends that handle their own path resolution.
 ///
@@ -53,6 +62,9 @@
 ///     → Resolves paths component-by-component using PathResolver
 /// ```
 ///
+/// A layer that resolves paths itself can hand the result further down as a
+/// [`Resolved`] path so nothing below resolves it again.
+///
 /// # Example
 ///
 /// ```rust
@@ -82,6 +94,106 @@
 /// - **Documentation**: Self-documenting code through trait bounds
 pub trait SelfResolving {}
 
+/// A path that a layer above has already canonicalized.
+///
+/// Middleware that canonicalizes paths (for containment checks, caching or
+/// auditing) would otherwise force the backend below to repeat the work.
+/// `Resolved` carries the fact that resolution already happened.
+///
+/// # Invariants
+///
+/// The holder of a `Resolved` promises that, against the filesystem it was
+/// resolved on and at the time it was resolved, the path:
+///
+/// - is rooted
+/// - contains no `.` or `..` components
+/// - traverses no symlinks, except possibly as the final component when it
+///   came from [`soft_resolve`](crate::PathResolver::soft_resolve)
+///
+/// # Protocol
+///
+/// 1. Only a [`PathResolver`](crate::PathResolver) (via
+///    [`resolve`](crate::PathResolver::resolve) /
+///    [`soft_resolve`](crate::PathResolver::soft_resolve)) or code that just
+///    canonicalized against the same backend creates a `Resolved`.
+/// 2. A layer holding a `Resolved` passes it to consumers that accept one
+///    (resolver caches, backends' `Resolved`-taking fast paths) and those
+///    consumers skip resolution entirely.
+/// 3. A `Resolved` is never reused across backends, and never carried past
+///    an operation that may have changed the tree (rename, symlink
+///    creation); re-resolve instead.
+/// 4. A layer that resolves every path it forwards implements
+///    [`SelfResolving`] so the `FileStorage` above it skips resolution too.
+///
+/// `Resolved` is an optimization, not a security boundary: the tree can
+/// change between resolution and use. Use
+/// [`FsDirHandles`](crate::FsDirHandles) when that race matters.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::Resolved;
+/// use std::path::Path;
+///
+/// let resolved = Resolved::new_unchecked(Path::new("/data/file.txt").to_path_buf());
+/// assert_eq!(resolved.as_path(), Path::new("/data/file.txt"));
+/// assert_eq!(resolved.file_name().unwrap(), "file.txt");
+///
+/// let borrowed: Resolved<&Path> = resolved.as_borrowed();
+/// assert_eq!(borrowed.into_inner(), Path::new("/data/file.txt"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Resolved<P = PathBuf>(P);
+
+impl<P: AsRef<Path>> Resolved<P> {
+    /// Wrap a path the caller has already canonicalized.
+    ///
+    /// The caller vouches for the [invariants](Resolved#invariants); nothing
+    /// is checked against the filesystem. Debug builds assert that the path
+    /// is rooted and has no `.` or `..` components.
+    pub fn new_unchecked(path: P) -> Self {
+        debug_assert!(
+            path.as_ref().has_root()
+                && path.as_ref().components().all(|component| !matches!(
+                    component,
+                    Component::CurDir | Component::ParentDir
+                )),
+            "not a resolved path: {}",
+            path.as_ref().display()
+        );
+        Self(path)
+    }
+
+    /// The resolved path.
+    pub fn as_path(&self) -> &Path {
+        self.0.as_ref()
+    }
+
+    /// Borrow as a `Resolved<&Path>` without cloning.
+    pub fn as_borrowed(&self) -> Resolved<&Path> {
+        Resolved(self.0.as_ref())
+    }
+
+    /// Unwrap the inner path, dropping the resolution guarantee.
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+impl<P: AsRef<Path>> Deref for Resolved<P> {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.0.as_ref()
+    }
+}
+
+impl<P: AsRef<Path>> AsRef<Path> for Resolved<P> {
+    fn as_ref(&self) -> &Path {
+        self.0.as_ref()
+    }
+}
+
 // Note: No blanket implementation - backends must explicitly opt-in
 // by implementing this marker trait.
 
@@ -135,6 +247,21 @@ mod tests {
         assert!(process_self_resolving(&backend));
     }
 
+    #[test]
+    fn resolved_derefs_to_path() {
+        let resolved = Resolved::new_unchecked(PathBuf::from("/a/b"));
+        assert_eq!(resolved.parent(), Some(Path::new("/a")));
+        assert_eq!(resolved.as_borrowed().as_path(), Path::new("/a/b"));
+        assert_eq!(resolved.into_inner(), PathBuf::from("/a/b"));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not a resolved path")]
+    fn resolved_rejects_dot_dot_in_debug() {
+        let _ = Resolved::new_unchecked(Path::new("/a/../b"));
+    }
+
     #[test]
     fn negative_bound_simulation() {
         // We can't use negative trait bounds in stable Rust,
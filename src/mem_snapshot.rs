@@ -0,0 +1,263 @@
+//! # Memory Snapshot
+//!
+//! [`MemSnapshot`] is a detached, read-only copy of a subtree, taken with
+//! [`FsExt::freeze`]. It implements [`Fs`] itself, so
+//! tests can assert against it and servers can hand out a stable view while
+//! the source keeps changing.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{
+    impl_read_only, DirEntry, FileType, Fs, FsError, FsExt, FsRead, Metadata, ReadDirIter,
+};
+
+/// Read-only in-memory copy of a subtree.
+///
+/// The frozen subtree is re-rooted: its root is `/` in the snapshot.
+/// Every mutation fails with [`FsError::ReadOnly`]. Clones share the same
+/// contents.
+///
+/// - Metadata is kept as the source reported it, inodes included.
+/// - Symlinks are left out: [`Fs`] alone can't read their targets, and
+///   following them could leave the subtree. FIFOs are left out too.
+/// - Capture reads entry by entry, so it is only as consistent as the
+///   source is quiet while it runs.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsError, FsExt, FsRead};
+/// use std::path::Path;
+///
+/// fn frozen_config<B: Fs>(backend: &B) -> Result<Vec<u8>, FsError> {
+///     let snapshot = backend.freeze(Path::new("/etc/app"))?;
+///
+///     // The source may change now; the snapshot won't
+///     snapshot.read(Path::new("/config.toml"))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemSnapshot {
+    entries: Arc<BTreeMap<PathBuf, Frozen>>,
+}
+
+#[derive(Debug)]
+struct Frozen {
+    metadata: Metadata,
+    /// File contents; empty for directories.
+    data: Vec<u8>,
+}
+
+impl MemSnapshot {
+    /// Copy the subtree at `root` out of `backend`.
+    ///
+    /// Same as [`FsExt::freeze`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `root` does not exist
+    /// - [`FsError::NotADirectory`] if `root` is not a directory
+    /// - Errors from listing, stat-ing or reading any entry in the tree
+    pub fn capture<B: Fs + ?Sized>(backend: &B, root: &Path) -> Result<Self, FsError> {
+        let mut entries = BTreeMap::new();
+        for entry in backend.walk(root) {
+            let entry = entry?;
+            if entry.depth == 0 && entry.file_type() != FileType::Directory {
+                return Err(FsError::not_a_directory("freeze", root));
+            }
+            let data = match entry.file_type() {
+                FileType::File => backend.read(entry.path())?,
+                FileType::Directory => Vec::new(),
+                FileType::Symlink | FileType::Fifo => continue,
+            };
+            let metadata = backend.metadata(entry.path())?;
+            let relative = entry.path().strip_prefix(root).unwrap_or(Path::new(""));
+            entries.insert(Path::new("/").join(relative), Frozen { metadata, data });
+        }
+        Ok(Self {
+            entries: Arc::new(entries),
+        })
+    }
+
+    fn get(&self, operation: &'static str, path: &Path) -> Result<&Frozen, FsError> {
+        self.entries
+            .get(path)
+            .ok_or_else(|| FsError::not_found(operation, path))
+    }
+
+    fn file(&self, operation: &'static str, path: &Path) -> Result<&[u8], FsError> {
+        let frozen = self.get(operation, path)?;
+        if frozen.metadata.file_type != FileType::File {
+            return Err(FsError::not_a_file(operation, path));
+        }
+        Ok(&frozen.data)
+    }
+}
+
+impl FsRead for MemSnapshot {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        Ok(self.file("read", path)?.to_vec())
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        String::from_utf8(self.file("read_to_string", path)?.to_vec()).map_err(|e| {
+            FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: e.to_string(),
+            }
+        })
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let data = self.file("read_range", path)?;
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let end = start.saturating_add(len).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        Ok(self.entries.contains_key(path))
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        Ok(self.get("metadata", path)?.metadata.clone())
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        Ok(Box::new(Cursor::new(
+            self.file("open_read", path)?.to_vec(),
+        )))
+    }
+}
+
+impl_read_only! {
+    impl FsWrite for MemSnapshot;
+    impl FsDir for MemSnapshot {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            if self.get("read_dir", path)?.metadata.file_type != FileType::Directory {
+                return Err(FsError::not_a_directory("read_dir", path));
+            }
+            let children = self
+                .entries
+                .range::<Path, _>((Bound::Excluded(path), Bound::Unbounded))
+                .take_while(|(child, _)| child.starts_with(path))
+                .filter(|(child, _)| child.parent() == Some(path))
+                .map(|(child, frozen)| {
+                    let name = child.file_name().unwrap_or_default();
+                    Ok(DirEntry {
+                        path: child.clone(),
+                        file_type: frozen.metadata.file_type,
+                        size: frozen.metadata.size,
+                        inode: frozen.metadata.inode,
+                        ..DirEntry::default()
+                    }
+                    .with_name_os(name.to_os_string()))
+                })
+                .collect();
+            Ok(ReadDirIter::from_vec(children))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, FsDir, FsWrite};
+
+    const TREE: &[(&str, Option<&[u8]>)] = &[
+        ("/", None),
+        ("/data", None),
+        ("/data/a.txt", Some(b"alpha")),
+        ("/data/sub", None),
+        ("/data/sub/b.txt", Some(b"beta")),
+        ("/other.txt", Some(b"outside")),
+    ];
+
+    fn lookup(op: &'static str, path: &Path) -> Result<Option<&'static [u8]>, FsError> {
+        TREE.iter()
+            .find(|(p, _)| Path::new(p) == path)
+            .map(|(_, data)| *data)
+            .ok_or_else(|| FsError::not_found(op, path))
+    }
+
+    fn source() -> impl Fs {
+        FsBuilder::new()
+            .on_read(|path| {
+                lookup("read", path)?
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| FsError::not_a_file("read", path))
+            })
+            .on_metadata(|path| {
+                Ok(match lookup("metadata", path)? {
+                    Some(data) => Metadata {
+                        size: data.len() as u64,
+                        ..Metadata::default()
+                    },
+                    None => Metadata {
+                        file_type: FileType::Directory,
+                        ..Metadata::default()
+                    },
+                })
+            })
+            .on_read_dir(|path| {
+                let entries = TREE
+                    .iter()
+                    .map(|(p, data)| (Path::new(p), data))
+                    .filter(|(p, _)| p.parent() == Some(path))
+                    .map(|(p, data)| {
+                        Ok(DirEntry {
+                            name: p.file_name().unwrap().to_string_lossy().into_owned(),
+                            path: p.to_path_buf(),
+                            file_type: match data {
+                                Some(_) => FileType::File,
+                                None => FileType::Directory,
+                            },
+                            ..DirEntry::default()
+                        })
+                    })
+                    .collect();
+                Ok(ReadDirIter::from_vec(entries))
+            })
+            .build()
+    }
+
+    #[test]
+    fn freeze_reroots_subtree() {
+        let snapshot = source().freeze(Path::new("/data")).unwrap();
+
+        assert_eq!(snapshot.read(Path::new("/a.txt")).unwrap(), b"alpha");
+        assert_eq!(snapshot.read(Path::new("/sub/b.txt")).unwrap(), b"beta");
+        assert!(!snapshot.exists(Path::new("/other.txt")).unwrap());
+
+        let names: Vec<String> = snapshot
+            .read_dir(Path::new("/"))
+            .unwrap()
+            .map(|e| e.unwrap().name)
+            .collect();
+        assert_eq!(names, ["a.txt", "sub"]);
+    }
+
+    #[test]
+    fn snapshot_rejects_mutation() {
+        let snapshot = source().freeze(Path::new("/")).unwrap();
+
+        let err = snapshot.write(Path::new("/new.txt"), b"x").unwrap_err();
+        assert!(matches!(err, FsError::ReadOnly { operation: "write" }));
+        assert!(matches!(
+            snapshot.read_dir(Path::new("/other.txt")),
+            Err(FsError::NotADirectory { .. })
+        ));
+    }
+
+    #[test]
+    fn freeze_requires_directory_root() {
+        let err = source().freeze(Path::new("/other.txt")).unwrap_err();
+        assert!(matches!(err, FsError::NotADirectory { .. }));
+    }
+}
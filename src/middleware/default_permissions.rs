@@ -0,0 +1,478 @@
+//! Consistent modes for newly created entries.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_inode, forward_fs_layers, forward_fs_lease, forward_fs_link, forward_fs_lock,
+    forward_fs_permissions, forward_fs_read, forward_fs_seek, forward_fs_sequence,
+    forward_fs_stats, forward_fs_sync, forward_fs_tiering, forward_fs_xattr,
+};
+use crate::{
+    CopyOptions, CreateMode, FsDir, FsError, FsHandles, FsPermissions, FsRead, FsWrite, Handle,
+    Layer, OpenFlags, Permissions, WriteOptions,
+};
+
+/// Middleware giving newly created files and directories the modes of a
+/// [`CreateMode`], instead of whatever the backend hard-codes.
+///
+/// | Operation | Mode applied when the target did not exist |
+/// |-----------|---------------------------------------------|
+/// | `write`, `write_with`, `append`, `copy`/`copy_with` (destination), `open_write` | [`CreateMode::file_mode`] |
+/// | `open` with `create` or `create_new` | `flags.mode` under the umask if set, else [`CreateMode::file_mode`] |
+/// | `create_dir`, each directory made by `create_dir_all` | [`CreateMode::dir_mode`] |
+/// | `mkfifo` | `perm` under the umask |
+///
+/// [`FsDir::create_dir_with`] (and so [`FsDir::create_temp_dir_in`]) uses the
+/// backend's native implementation with the exact mode requested. Backends
+/// returning [`FsError::NotSupported`] get `create_dir` + `set_permissions`
+/// instead, and the directory is removed again if the mode can't be set.
+///
+/// Existing entries keep their permissions. The mode is set with
+/// [`FsPermissions::set_permissions`] right after the create, so another
+/// client may briefly observe the backend's own default.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CreateMode, FsError, FsRead, FsWrite, FsPermissions, LayerExt, Fs, UmaskPolicy};
+/// use std::path::Path;
+///
+/// fn private_files<B: Fs + FsPermissions>(backend: B) -> Result<(), FsError> {
+///     let fs = backend.with_default_permissions(
+///         CreateMode::default().with_umask(UmaskPolicy::new(0o077)),
+///     );
+///     fs.write(Path::new("/secret"), b"token")?;
+///     assert_eq!(fs.metadata(Path::new("/secret"))?.permissions.mode(), 0o600);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DefaultPermissions<B> {
+    inner: B,
+    mode: CreateMode,
+}
+
+impl<B> DefaultPermissions<B> {
+    /// Wrap a backend.
+    pub fn new(inner: B, mode: CreateMode) -> Self {
+        Self { inner, mode }
+    }
+
+    /// The modes applied to new entries.
+    pub fn create_mode(&self) -> CreateMode {
+        self.mode
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: FsRead + FsPermissions> DefaultPermissions<B> {
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.exists(path)
+    }
+
+    /// Apply the file mode to `path` if it was just created.
+    fn finish_file(&self, path: &Path, existed: bool) -> Result<(), FsError> {
+        if existed {
+            return Ok(());
+        }
+        self.inner.set_permissions(path, self.mode.file_mode())
+    }
+
+    /// Apply the directory mode to `path`, inheriting from its parent.
+    fn finish_dir(&self, path: &Path) -> Result<(), FsError> {
+        let parent = match path.parent() {
+            Some(parent) => Some(self.inner.metadata(parent)?.permissions),
+            None => None,
+        };
+        self.inner.set_permissions(path, self.mode.dir_mode(parent))
+    }
+}
+
+impl<B: FsWrite + FsRead + FsPermissions> FsWrite for DefaultPermissions<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let existed = self.exists(path)?;
+        self.inner.write(path, data)?;
+        self.finish_file(path, existed)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let existed = self.exists(path)?;
+        self.inner.append(path, data)?;
+        self.finish_file(path, existed)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let existed = self.exists(to)?;
+        self.inner.copy(from, to)?;
+        self.finish_file(to, existed)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        let existed = self.exists(path)?;
+        let writer = self.inner.open_write(path)?;
+        self.finish_file(path, existed)?;
+        Ok(writer)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        let existed = self.exists(path)?;
+        let writer = self.inner.open_write_sized(path, expected_len)?;
+        self.finish_file(path, existed)?;
+        Ok(writer)
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let existed = self.exists(to)?;
+        let copied = self.inner.copy_with(from, to, options)?;
+        if !options.preserve_permissions {
+            self.finish_file(to, existed)?;
+        }
+        Ok(copied)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let existed = self.exists(path)?;
+        self.inner.write_with(path, data, options)?;
+        self.finish_file(path, existed)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.inner.set_times(path, atime, mtime)
+    }
+}
+
+impl<B: FsDir + FsRead + FsPermissions> FsDir for DefaultPermissions<B> {
+    fn read_dir(&self, path: &Path) -> Result<crate::ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(
+        &self,
+        path: &Path,
+        options: crate::ReadDirOptions,
+    ) -> Result<crate::ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.create_dir(path)?;
+        self.finish_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let mut missing: Vec<PathBuf> = Vec::new();
+        for ancestor in path.ancestors() {
+            if ancestor.as_os_str().is_empty() || self.exists(ancestor)? {
+                break;
+            }
+            missing.push(ancestor.to_path_buf());
+        }
+        self.inner.create_dir_all(path)?;
+        // Outermost first, so setgid inheritance propagates down the chain
+        for dir in missing.iter().rev() {
+            self.finish_dir(dir)?;
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        match self.inner.create_dir_with(path, perm) {
+            Err(FsError::NotSupported { .. }) => {}
+            result => return result,
+        }
+        self.inner.create_dir(path)?;
+        if let Err(e) = self.inner.set_permissions(path, perm) {
+            let _ = self.inner.remove_dir(path);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+impl<B: FsHandles + FsRead + FsPermissions> FsHandles for DefaultPermissions<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        if !flags.create && !flags.create_new {
+            return self.inner.open(path, flags);
+        }
+        let existed = self.exists(path)?;
+        let handle = self.inner.open(path, flags)?;
+        let finished = match flags.mode {
+            Some(mode) if !existed => self
+                .inner
+                .set_permissions(path, self.mode.umask.apply(mode)),
+            _ => self.finish_file(path, existed),
+        };
+        if let Err(e) = finished {
+            let _ = self.inner.close(handle);
+            return Err(e);
+        }
+        Ok(handle)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.inner.link_handle(handle, path)?;
+        self.inner.set_permissions(path, self.mode.file_mode())
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.inner.mkfifo(path, self.mode.umask.apply(perm))
+    }
+}
+
+forward_fs_read!(DefaultPermissions);
+forward_fs_link!(DefaultPermissions);
+forward_fs_permissions!(DefaultPermissions);
+forward_fs_stats!(DefaultPermissions);
+forward_fs_sync!(DefaultPermissions);
+forward_fs_inode!(DefaultPermissions);
+forward_fs_lock!(DefaultPermissions);
+forward_fs_xattr!(DefaultPermissions);
+forward_fs_handle_debug!(DefaultPermissions, FsRead, FsPermissions);
+forward_fs_seek!(DefaultPermissions, FsRead, FsPermissions);
+forward_fs_file_flags!(DefaultPermissions);
+forward_fs_tiering!(DefaultPermissions);
+forward_fs_expiry!(DefaultPermissions);
+forward_fs_lease!(DefaultPermissions);
+forward_fs_layers!(DefaultPermissions);
+forward_fs_sequence!(DefaultPermissions);
+forward_fs_cache_control!(DefaultPermissions);
+
+/// [`Layer`] producing [`DefaultPermissions`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CreateMode, DefaultPermissionsLayer, Fs, FsPermissions, LayerExt};
+///
+/// fn with_modes<B: Fs + FsPermissions>(backend: B) -> impl Fs + FsPermissions {
+///     backend.layer(DefaultPermissionsLayer::new(CreateMode::default()))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultPermissionsLayer {
+    mode: CreateMode,
+}
+
+impl DefaultPermissionsLayer {
+    /// Layer applying `mode` to new entries.
+    pub fn new(mode: CreateMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<B> Layer<B> for DefaultPermissionsLayer {
+    type Backend = DefaultPermissions<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        DefaultPermissions::new(backend, self.mode)
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::MemFs;
+    use crate::{LayerExt, UmaskPolicy};
+
+    fn private() -> CreateMode {
+        CreateMode::default().with_umask(UmaskPolicy::new(0o077))
+    }
+
+    fn mode<B: FsRead>(fs: &B, path: &str) -> u32 {
+        fs.metadata(Path::new(path)).unwrap().permissions.mode()
+    }
+
+    #[test]
+    fn writes_set_the_mode_of_new_files_only() {
+        let backend = MemFs::new();
+        backend.write(Path::new("/old"), b"").unwrap();
+        backend
+            .set_permissions(Path::new("/old"), Permissions::from_mode(0o664))
+            .unwrap();
+        let fs = backend.with_default_permissions(private());
+
+        fs.write(Path::new("/new"), b"x").unwrap();
+        fs.write(Path::new("/old"), b"x").unwrap();
+        assert_eq!(mode(&fs, "/new"), 0o600);
+        assert_eq!(mode(&fs, "/old"), 0o664);
+    }
+
+    #[test]
+    fn create_dir_all_finishes_each_missing_directory() {
+        let backend = MemFs::new();
+        backend.create_dir(Path::new("/shared")).unwrap();
+        backend
+            .set_permissions(Path::new("/shared"), Permissions::from_mode(0o2770))
+            .unwrap();
+        let fs = backend.with_default_permissions(private());
+
+        fs.create_dir_all(Path::new("/shared/a/b")).unwrap();
+        assert_eq!(mode(&fs, "/shared"), 0o2770);
+        // setgid is inherited down the whole chain
+        assert_eq!(mode(&fs, "/shared/a"), 0o2700);
+        assert_eq!(mode(&fs, "/shared/a/b"), 0o2700);
+    }
+
+    #[test]
+    fn open_applies_flags_mode_under_the_umask() {
+        let fs = MemFs::new().with_default_permissions(private());
+        let with_mode = OpenFlags {
+            mode: Some(Permissions::from_mode(0o644)),
+            ..OpenFlags::WRITE
+        };
+
+        let handle = fs.open(Path::new("/explicit"), with_mode).unwrap();
+        fs.close(handle).unwrap();
+        let handle = fs.open(Path::new("/default"), OpenFlags::WRITE).unwrap();
+        fs.close(handle).unwrap();
+        assert_eq!(mode(&fs, "/explicit"), 0o600);
+        assert_eq!(mode(&fs, "/default"), 0o600);
+
+        fs.set_permissions(Path::new("/explicit"), Permissions::from_mode(0o640))
+            .unwrap();
+        let handle = fs.open(Path::new("/explicit"), with_mode).unwrap();
+        fs.close(handle).unwrap();
+        assert_eq!(mode(&fs, "/explicit"), 0o640);
+    }
+
+    #[test]
+    fn mkfifo_applies_the_umask() {
+        let fs = MemFs::new().with_default_permissions(private());
+        fs.mkfifo(Path::new("/pipe"), Permissions::from_mode(0o666))
+            .unwrap();
+        assert_eq!(mode(&fs, "/pipe"), 0o600);
+    }
+
+    /// `MemFs` without a native `create_dir_with`, whose `set_permissions`
+    /// fails while `broken` is set.
+    struct Legacy<B> {
+        inner: B,
+        broken: bool,
+    }
+
+    forward_fs_read!(Legacy);
+
+    impl FsDir for Legacy<MemFs> {
+        fn read_dir(&self, path: &Path) -> Result<crate::ReadDirIter, FsError> {
+            self.inner.read_dir(path)
+        }
+
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.create_dir(path)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.create_dir_all(path)
+        }
+
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.remove_dir(path)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.remove_dir_all(path)
+        }
+    }
+
+    impl FsPermissions for Legacy<MemFs> {
+        fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+            if self.broken {
+                return Err(FsError::permission_denied("set_permissions", path));
+            }
+            self.inner.set_permissions(path, perm)
+        }
+    }
+
+    #[test]
+    fn create_dir_with_falls_back_and_rolls_back() {
+        let keys = Path::new("/keys");
+        let legacy = |broken| Legacy {
+            inner: MemFs::new(),
+            broken,
+        };
+
+        let fs = DefaultPermissions::new(legacy(false), CreateMode::default());
+        fs.create_dir_with(keys, Permissions::from_mode(0o700))
+            .unwrap();
+        assert_eq!(mode(&fs, "/keys"), 0o700);
+
+        let fs = DefaultPermissions::new(legacy(true), CreateMode::default());
+        assert!(matches!(
+            fs.create_dir_with(keys, Permissions::from_mode(0o700)),
+            Err(FsError::PermissionDenied { .. })
+        ));
+        assert!(!fs.exists(keys).unwrap());
+    }
+}
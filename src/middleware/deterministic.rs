@@ -0,0 +1,232 @@
+//! Reproducible directory listing order.
+
+use std::path::{Path, PathBuf};
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_handles, forward_fs_inode, forward_fs_layers, forward_fs_lease, forward_fs_link,
+    forward_fs_lock, forward_fs_permissions, forward_fs_read, forward_fs_seek, forward_fs_sequence,
+    forward_fs_stats, forward_fs_sync, forward_fs_tiering, forward_fs_write, forward_fs_xattr,
+};
+use crate::{FsDir, FsError, Layer, Permissions, ReadDirIter, ReadDirOptions};
+
+/// Middleware listing every directory in name order.
+///
+/// [`FsDir::read_dir`] on most backends returns entries in whatever order
+/// the storage yields them, hash order for map-backed ones, so snapshots
+/// and golden files built from listings are flaky. `Deterministic` turns
+/// every `read_dir` and [`read_dir_with`](FsDir::read_dir_with) into
+/// `read_dir_with(path, ReadDirOptions::sorted())` on the wrapped backend:
+/// backends that store entries in name order stream them, the rest are
+/// sorted in memory.
+///
+/// Everything built on `read_dir`, such as
+/// [`FsExt::walk`](crate::FsExt::walk), becomes reproducible too.
+/// [`FsInode::read_dir_inode`](crate::FsInode::read_dir_inode) is forwarded
+/// unchanged, since its offsets are the backend's own cookies. All other
+/// traits are forwarded.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{DeterministicLayer, DirEntry, FsBuilder, FsDir, LayerExt, ReadDirIter};
+/// use std::path::Path;
+///
+/// let fs = FsBuilder::new()
+///     .on_read_dir(|_| {
+///         let entry = |name: &str| Ok(DirEntry::default().with_name(name));
+///         Ok(ReadDirIter::from_vec(vec![entry("b"), entry("c"), entry("a")]))
+///     })
+///     .build()
+///     .layer(DeterministicLayer);
+///
+/// let names: Vec<String> = fs
+///     .read_dir(Path::new("/"))
+///     .unwrap()
+///     .map(|entry| entry.unwrap().name)
+///     .collect();
+/// assert_eq!(names, ["a", "b", "c"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Deterministic<B> {
+    inner: B,
+}
+
+impl<B> Deterministic<B> {
+    /// Wrap a backend.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+forward_fs_read!(Deterministic);
+forward_fs_write!(Deterministic);
+
+impl<B: FsDir> FsDir for Deterministic<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, ReadDirOptions::sorted())
+    }
+
+    fn read_dir_with(
+        &self,
+        path: &Path,
+        mut options: ReadDirOptions,
+    ) -> Result<ReadDirIter, FsError> {
+        options.sorted = true;
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.inner.create_dir_with(path, perm)
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.inner.create_temp_dir_in(parent, prefix)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_dir_all(path)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+forward_fs_link!(Deterministic);
+forward_fs_permissions!(Deterministic);
+forward_fs_stats!(Deterministic);
+forward_fs_sync!(Deterministic);
+forward_fs_inode!(Deterministic);
+forward_fs_handles!(Deterministic);
+forward_fs_lock!(Deterministic);
+forward_fs_xattr!(Deterministic);
+forward_fs_handle_debug!(Deterministic);
+forward_fs_seek!(Deterministic);
+forward_fs_file_flags!(Deterministic);
+forward_fs_tiering!(Deterministic);
+forward_fs_expiry!(Deterministic);
+forward_fs_lease!(Deterministic);
+forward_fs_layers!(Deterministic);
+forward_fs_sequence!(Deterministic);
+forward_fs_cache_control!(Deterministic);
+
+/// [`Layer`] producing [`Deterministic`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{DeterministicLayer, Fs, LayerExt};
+///
+/// fn for_snapshots<B: Fs>(backend: B) -> impl Fs {
+///     backend.layer(DeterministicLayer)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeterministicLayer;
+
+impl<B> Layer<B> for DeterministicLayer {
+    type Backend = Deterministic<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Deterministic::new(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirEntry, FsBuilder, LayerExt};
+
+    #[test]
+    fn sorted_listings_are_requested_from_the_backend() {
+        struct SortedOnly;
+        impl FsDir for SortedOnly {
+            fn read_dir(&self, _: &Path) -> Result<ReadDirIter, FsError> {
+                panic!("unsorted listing requested")
+            }
+            fn read_dir_with(
+                &self,
+                _: &Path,
+                options: ReadDirOptions,
+            ) -> Result<ReadDirIter, FsError> {
+                assert!(options.sorted);
+                Ok(ReadDirIter::from_vec(Vec::new()))
+            }
+            fn create_dir(&self, _: &Path) -> Result<(), FsError> {
+                Ok(())
+            }
+            fn create_dir_all(&self, _: &Path) -> Result<(), FsError> {
+                Ok(())
+            }
+            fn remove_dir(&self, _: &Path) -> Result<(), FsError> {
+                Ok(())
+            }
+            fn remove_dir_all(&self, _: &Path) -> Result<(), FsError> {
+                Ok(())
+            }
+        }
+
+        let fs = Deterministic::new(SortedOnly);
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 0);
+        assert_eq!(
+            fs.read_dir_with(Path::new("/"), ReadDirOptions::default())
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn names_sort_by_bytes_not_by_locale() {
+        let fs = FsBuilder::new()
+            .on_read_dir(|_| {
+                let entry = |name: &str| {
+                    Ok(DirEntry {
+                        name: name.into(),
+                        ..DirEntry::default()
+                    })
+                };
+                Ok(ReadDirIter::from_vec(vec![
+                    entry("é"),
+                    entry("z"),
+                    entry("Z"),
+                ]))
+            })
+            .build()
+            .layer(DeterministicLayer);
+
+        let names: Vec<String> = fs
+            .read_dir(Path::new("/"))
+            .unwrap()
+            .map(|entry| entry.unwrap().name)
+            .collect();
+        assert_eq!(names, ["Z", "z", "é"]);
+    }
+}
@@ -0,0 +1,419 @@
+//! Recording mutations instead of applying them.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::forward::forward_fs_read;
+use crate::dispatch::FsOp;
+use crate::{
+    CopyOptions, FsDir, FsError, FsWrite, Layer, Permissions, ReadDirIter, ReadDirOptions,
+    WriteOptions,
+};
+
+/// The ops recorded so far, shared with open writers.
+type Plan = Arc<Mutex<Vec<FsOp>>>;
+
+fn record(plan: &Plan, op: FsOp) {
+    plan.lock().unwrap_or_else(|e| e.into_inner()).push(op);
+}
+
+/// Middleware recording every mutation as an [`FsOp`] without applying it.
+///
+/// Reads go to the wrapped backend; each [`FsWrite`] and [`FsDir`]
+/// mutation is appended to the [`plan`](DryRun::plan) and reports success.
+/// Running a utility such as
+/// [`FsExt::remove_dir_all_report`](crate::FsExt::remove_dir_all_report)
+/// or [`FsExt::move_entry`](crate::FsExt::move_entry) on a `DryRun` shows
+/// what it would do, for previews in CLI tools.
+///
+/// Reads see the backend as it is, not as the plan would leave it. A
+/// utility that reads back what it wrote (the copy fallback of
+/// `move_entry` verifies each copy) or that branches on entries it just
+/// created gets a different answer than it would for real. Results that
+/// only a real mutation can produce are approximated: `copy_with` returns
+/// `0` and `create_temp_dir_in` returns `parent/prefix`. Writers from
+/// `open_write` record one `write` with everything written to them when
+/// dropped.
+///
+/// Only [`FsRead`](crate::FsRead), [`FsWrite`] and [`FsDir`] are
+/// implemented: mutations through other traits can't be expressed as an
+/// [`FsOp`], so a `DryRun` doesn't offer them.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{DryRunLayer, FsBuilder, FsDir, FsOp, LayerExt};
+/// use std::path::Path;
+///
+/// // The backend refuses every mutation, but none reaches it
+/// let fs = FsBuilder::new().build().layer(DryRunLayer);
+///
+/// fs.create_dir(Path::new("/reports")).unwrap();
+/// assert_eq!(
+///     fs.plan(),
+///     [FsOp::CreateDir { path: "/reports".into() }]
+/// );
+/// ```
+pub struct DryRun<B> {
+    inner: B,
+    plan: Plan,
+}
+
+impl<B> DryRun<B> {
+    /// Wrap a backend with an empty plan.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            plan: Plan::default(),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend, discarding the plan.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// The mutations recorded so far, in call order.
+    pub fn plan(&self) -> Vec<FsOp> {
+        self.plan.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Return the recorded mutations and start a new, empty plan.
+    pub fn take_plan(&self) -> Vec<FsOp> {
+        std::mem::take(&mut *self.plan.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for DryRun<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DryRun")
+            .field("inner", &self.inner)
+            .field("plan", &self.plan().len())
+            .finish()
+    }
+}
+
+forward_fs_read!(DryRun);
+
+impl<B: FsWrite> FsWrite for DryRun<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::Write {
+                path: path.to_path_buf(),
+                data: data.to_vec(),
+            },
+        );
+        Ok(())
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::WriteWith {
+                path: path.to_path_buf(),
+                data: data.to_vec(),
+                options,
+            },
+        );
+        Ok(())
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::SetTimes {
+                path: path.to_path_buf(),
+                atime,
+                mtime,
+            },
+        );
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::Append {
+                path: path.to_path_buf(),
+                data: data.to_vec(),
+            },
+        );
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::RemoveFile {
+                path: path.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::Rename {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::Copy {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        record(
+            &self.plan,
+            FsOp::CopyWith {
+                from: from.to_path_buf(),
+                to: to.to_path_buf(),
+                options,
+            },
+        );
+        Ok(0)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::Truncate {
+                path: path.to_path_buf(),
+                size,
+            },
+        );
+        Ok(())
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        Ok(Box::new(PlanWriter::new(path, &self.plan)))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        _expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        Ok(Box::new(PlanWriter::new(path, &self.plan)))
+    }
+}
+
+impl<B: FsDir> FsDir for DryRun<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::CreateDir {
+                path: path.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::CreateDirAll {
+                path: path.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::CreateDirWith {
+                path: path.to_path_buf(),
+                perm,
+            },
+        );
+        Ok(())
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        record(
+            &self.plan,
+            FsOp::CreateTempDirIn {
+                parent: parent.to_path_buf(),
+                prefix: prefix.to_string(),
+            },
+        );
+        Ok(parent.join(prefix))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::RemoveDir {
+                path: path.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        record(
+            &self.plan,
+            FsOp::RemoveDirAll {
+                path: path.to_path_buf(),
+            },
+        );
+        Ok(())
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+/// Writer returned by `open_write`, recording its data once dropped.
+struct PlanWriter {
+    path: PathBuf,
+    plan: Plan,
+    buffer: Vec<u8>,
+}
+
+impl PlanWriter {
+    fn new(path: &Path, plan: &Plan) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            plan: plan.clone(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Write for PlanWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PlanWriter {
+    fn drop(&mut self) {
+        record(
+            &self.plan,
+            FsOp::Write {
+                path: std::mem::take(&mut self.path),
+                data: std::mem::take(&mut self.buffer),
+            },
+        );
+    }
+}
+
+/// [`Layer`] producing [`DryRun`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{DryRunLayer, Fs, FsError, FsExt, FsOp, LayerExt};
+/// use std::path::Path;
+///
+/// fn preview_clean<B: Fs>(backend: B) -> Result<Vec<FsOp>, FsError> {
+///     let fs = backend.layer(DryRunLayer);
+///     fs.remove_dir_all_report(Path::new("/cache"))?;
+///     Ok(fs.take_plan())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DryRunLayer;
+
+impl<B> Layer<B> for DryRunLayer {
+    type Backend = DryRun<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        DryRun::new(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, FsRead, LayerExt};
+
+    #[test]
+    fn reads_pass_through_and_mutations_are_recorded() {
+        let fs = FsBuilder::new()
+            .on_read(|_| Ok(b"old".to_vec()))
+            .build()
+            .layer(DryRunLayer);
+
+        fs.write(Path::new("/a"), b"new").unwrap();
+        fs.rename(Path::new("/a"), Path::new("/b")).unwrap();
+        assert_eq!(fs.read(Path::new("/a")).unwrap(), b"old");
+        assert_eq!(
+            fs.take_plan(),
+            [
+                FsOp::Write {
+                    path: "/a".into(),
+                    data: b"new".to_vec(),
+                },
+                FsOp::Rename {
+                    from: "/a".into(),
+                    to: "/b".into(),
+                },
+            ]
+        );
+        assert!(fs.plan().is_empty());
+    }
+
+    #[test]
+    fn writers_record_one_write_when_dropped() {
+        let fs = FsBuilder::new().build().layer(DryRunLayer);
+
+        let mut writer = fs.open_write(Path::new("/log")).unwrap();
+        writer.write_all(b"ab").unwrap();
+        writer.write_all(b"cd").unwrap();
+        assert!(fs.plan().is_empty());
+        drop(writer);
+        assert_eq!(
+            fs.plan(),
+            [FsOp::Write {
+                path: "/log".into(),
+                data: b"abcd".to_vec(),
+            }]
+        );
+    }
+}
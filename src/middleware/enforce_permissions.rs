@@ -0,0 +1,765 @@
+//! Enforcement of stored permission bits.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_layers, forward_fs_lease, forward_fs_lock, forward_fs_seek, forward_fs_sequence,
+    forward_fs_stats, forward_fs_sync, forward_fs_tiering,
+};
+use crate::{
+    CopyOptions, Credentials, DirEntry, FileType, FsDir, FsError, FsHandles, FsInode, FsLink,
+    FsPermissions, FsRead, FsWrite, FsXattr, Handle, Layer, Metadata, MetadataLite, MetadataMask,
+    OpenFlags, Permissions, ReadDirIter, ReadDirOptions, Rwx, SetAttr, WriteOptions,
+};
+
+/// Middleware enforcing the [`Permissions`] stored by the wrapped backend.
+///
+/// Most virtual backends record modes but never check them. This
+/// middleware checks them before each operation, the way a Unix kernel
+/// would, and fails with [`FsError::PermissionDenied`] before reaching the
+/// backend.
+///
+/// | Operation | Needs |
+/// |-----------|-------|
+/// | Any path lookup | `x` on every existing directory above the path |
+/// | Reading a file, listing a directory, reading xattrs | `r` on it |
+/// | Writing, appending, truncating, changing xattrs or times | `w` on the file |
+/// | Creating, removing or renaming an entry | `w` and `x` on its directory |
+/// | `remove_dir_all` | the above, plus `r`, `w` and `x` on every directory in the tree |
+/// | `set_permissions`, `set_attr` with a mode or owner | owning the entry |
+///
+/// Which class of bits applies comes from [`Credentials::rwx_for`]. Without
+/// credentials the caller owns every entry, so only owner bits count.
+/// Handles are checked when opened, not on each read or write. Inode calls
+/// are checked against the path [`FsInode::inode_to_path`] returns, with
+/// `lookup` needing `x` on the parent directory. Paths that
+/// don't exist are passed through, so the backend reports them. Every check
+/// costs a `metadata` call per path component.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{EnforcePermissionsLayer, FsError, FsPermissions, FsRead, FsWrite, Layer, Permissions};
+/// use std::path::Path;
+///
+/// fn sealed<B: FsRead + FsWrite + FsPermissions>(backend: B) -> Result<(), FsError> {
+///     let fs = EnforcePermissionsLayer::new().layer(backend);
+///     fs.write(Path::new("/config"), b"v1")?;
+///     fs.set_permissions(Path::new("/config"), Permissions::from_mode(0o444))?;
+///
+///     assert!(matches!(
+///         fs.write(Path::new("/config"), b"v2"),
+///         Err(FsError::PermissionDenied { .. })
+///     ));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnforcePermissions<B> {
+    inner: B,
+    credentials: Option<Credentials>,
+}
+
+impl<B> EnforcePermissions<B> {
+    /// Wrap a backend, treating the caller as the owner of every entry.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            credentials: None,
+        }
+    }
+
+    /// Wrap a backend, checking requests against `credentials`.
+    pub fn with_credentials(inner: B, credentials: Credentials) -> Self {
+        Self {
+            inner,
+            credentials: Some(credentials),
+        }
+    }
+
+    /// The credentials requests are checked against, if any.
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+/// [`FsError::PermissionDenied`] for `operation` on `path`.
+fn denied(path: &Path, operation: &'static str) -> FsError {
+    FsError::PermissionDenied {
+        path: path.to_path_buf(),
+        operation,
+    }
+}
+
+impl<B: FsRead> EnforcePermissions<B> {
+    /// Bits of `meta` that apply to the caller.
+    fn rwx(&self, meta: &Metadata) -> Rwx {
+        match &self.credentials {
+            Some(credentials) => credentials.rwx_for(meta),
+            None => meta.permissions.owner(),
+        }
+    }
+
+    /// Metadata of `path`, `None` if it doesn't exist.
+    fn existing(&self, path: &Path) -> Result<Option<Metadata>, FsError> {
+        match self.inner.metadata(path) {
+            Ok(meta) => Ok(Some(meta)),
+            Err(FsError::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deny `operation` unless every existing directory above `path` can be
+    /// searched.
+    fn check_search(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        for dir in path.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() {
+                continue;
+            }
+            if let Some(meta) = self.existing(dir)? {
+                if !self.rwx(&meta).execute {
+                    return Err(denied(path, operation));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deny `operation` unless `path` is reachable and, if it exists, its
+    /// bits satisfy `allowed`.
+    fn check(
+        &self,
+        path: &Path,
+        operation: &'static str,
+        allowed: fn(Rwx) -> bool,
+    ) -> Result<(), FsError> {
+        self.check_search(path, operation)?;
+        match self.existing(path)? {
+            Some(meta) if !allowed(self.rwx(&meta)) => Err(denied(path, operation)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Deny adding or removing the entry at `path` unless its directory is
+    /// writable and searchable.
+    fn check_parent(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                self.check(parent, operation, |p| p.write && p.execute)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Deny writing `path`: the file itself if it exists, its directory if
+    /// the write would create it.
+    fn check_write(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        match self.existing(path)? {
+            Some(meta) => {
+                self.check_search(path, operation)?;
+                if self.rwx(&meta).write {
+                    Ok(())
+                } else {
+                    Err(denied(path, operation))
+                }
+            }
+            None => self.check_parent(path, operation),
+        }
+    }
+
+    /// Deny `operation` unless the caller owns `path`.
+    fn check_owner(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check_search(path, operation)?;
+        let Some(credentials) = &self.credentials else {
+            return Ok(());
+        };
+        match self.existing(path)? {
+            Some(Metadata { uid: Some(uid), .. }) if uid != credentials.uid => {
+                Err(denied(path, operation))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<B: FsRead> FsRead for EnforcePermissions<B> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.check(path, "read", |p| p.read)?;
+        self.inner.read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        self.check(path, "read_to_string", |p| p.read)?;
+        self.inner.read_to_string(path)
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        self.check(path, "read_range", |p| p.read)?;
+        self.inner.read_range(path, offset, len)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.check_search(path, "exists")?;
+        self.inner.exists(path)
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        self.check_search(path, "exists_fresh")?;
+        self.inner.exists_fresh(path)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.check_search(path, "metadata")?;
+        self.inner.metadata(path)
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        self.check_search(path, "metadata_lite")?;
+        self.inner.metadata_lite(path)
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        self.check_search(path, "metadata_mask")?;
+        self.inner.metadata_mask(path, mask)
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.check_search(path, "metadata_with_tag")?;
+        self.inner.metadata_with_tag(path)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+        self.check(path, "open_read", |p| p.read)?;
+        self.inner.open_read(path)
+    }
+}
+
+impl<B: FsWrite + FsRead> FsWrite for EnforcePermissions<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check_write(path, "write")?;
+        self.inner.write(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check_write(path, "append")?;
+        self.inner.append(path, data)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "remove_file")?;
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check_parent(from, "rename")?;
+        self.check_parent(to, "rename")?;
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check(from, "copy", |p| p.read)?;
+        self.check_write(to, "copy")?;
+        self.inner.copy(from, to)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.check(path, "truncate", |p| p.write)?;
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check_write(path, "open_write")?;
+        self.inner.open_write(path)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check_write(path, "open_write_sized")?;
+        self.inner.open_write_sized(path, expected_len)
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.check(from, "copy_with", |p| p.read)?;
+        self.check_write(to, "copy_with")?;
+        self.inner.copy_with(from, to, options)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.check_write(path, "write_with")?;
+        self.inner.write_with(path, data, options)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.check(path, "set_times", |p| p.write)?;
+        self.inner.set_times(path, atime, mtime)
+    }
+}
+
+impl<B: FsDir + FsRead> EnforcePermissions<B> {
+    /// Deny removing a tree unless every directory in it can be listed and
+    /// emptied.
+    fn check_tree(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "remove_dir_all", |p| p.read && p.write && p.execute)?;
+        for entry in self.inner.read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type == FileType::Directory {
+                self.check_tree(&entry.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: FsDir + FsRead> FsDir for EnforcePermissions<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.check(path, "read_dir", |p| p.read)?;
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.check(path, "read_dir_with", |p| p.read)?;
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "create_dir")?;
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        // Only the deepest existing ancestor gains an entry
+        for ancestor in path.ancestors() {
+            if self.existing(ancestor)?.is_some() {
+                if ancestor == path {
+                    self.check_search(path, "create_dir_all")?;
+                } else {
+                    self.check(ancestor, "create_dir_all", |p| p.write && p.execute)?;
+                }
+                break;
+            }
+        }
+        self.inner.create_dir_all(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "remove_dir")?;
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "remove_dir_all")?;
+        self.check_tree(path)?;
+        self.inner.remove_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_parent(path, "create_dir_with")?;
+        self.inner.create_dir_with(path, perm)
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.check(parent, "create_temp_dir_in", |p| p.write && p.execute)?;
+        self.inner.create_temp_dir_in(parent, prefix)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.check(path, "dir_len", |p| p.read)?;
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.check(path, "is_dir_empty", |p| p.read)?;
+        self.inner.is_dir_empty(path)
+    }
+}
+
+impl<B: FsLink + FsRead> FsLink for EnforcePermissions<B> {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        self.check_parent(link, "symlink")?;
+        self.inner.symlink(target, link)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        self.check_search(original, "hard_link")?;
+        self.check_parent(link, "hard_link")?;
+        self.inner.hard_link(original, link)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        self.check_search(path, "read_link")?;
+        self.inner.read_link(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.check_search(path, "symlink_metadata")?;
+        self.inner.symlink_metadata(path)
+    }
+}
+
+impl<B: FsPermissions + FsRead> FsPermissions for EnforcePermissions<B> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_owner(path, "set_permissions")?;
+        self.inner.set_permissions(path, perm)
+    }
+}
+
+impl<B: FsHandles + FsRead> FsHandles for EnforcePermissions<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        match self.existing(path)? {
+            Some(meta) => {
+                self.check_search(path, "open")?;
+                let rwx = self.rwx(&meta);
+                let writes = flags.write || flags.append || flags.truncate;
+                if (flags.read && !rwx.read) || (writes && !rwx.write) {
+                    return Err(denied(path, "open"));
+                }
+            }
+            None if flags.create || flags.create_new => self.check_parent(path, "open")?,
+            None => self.check_search(path, "open")?,
+        }
+        self.inner.open(path, flags)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.check(dir, "open_anonymous", |p| p.write && p.execute)?;
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "link_handle")?;
+        self.inner.link_handle(handle, path)
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_parent(path, "mkfifo")?;
+        self.inner.mkfifo(path, perm)
+    }
+}
+
+impl<B: FsInode + FsRead> FsInode for EnforcePermissions<B> {
+    fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.path_to_inode(path)
+    }
+
+    fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+        self.inner.inode_to_path(inode)
+    }
+
+    fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+        let parent = self.inner.inode_to_path(parent_inode)?;
+        self.check(&parent, "lookup", |p| p.execute)?;
+        self.inner.lookup(parent_inode, name)
+    }
+
+    fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+        let path = self.inner.inode_to_path(inode)?;
+        self.check_search(&path, "metadata_by_inode")?;
+        self.inner.metadata_by_inode(inode)
+    }
+
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        let path = self.inner.inode_to_path(inode)?;
+        self.check(&path, "read_dir_inode", |p| p.read)?;
+        self.inner.read_dir_inode(inode, offset)
+    }
+
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        if !attr.is_empty() {
+            let path = self.inner.inode_to_path(inode)?;
+            if attr.mode.is_some() || attr.uid.is_some() || attr.gid.is_some() {
+                self.check_owner(&path, "set_attr")?;
+            }
+            if attr.size.is_some() || attr.atime.is_some() || attr.mtime.is_some() {
+                self.check(&path, "set_attr", |p| p.write)?;
+            }
+        }
+        self.inner.set_attr(inode, attr)
+    }
+}
+
+impl<B: FsXattr + FsRead> FsXattr for EnforcePermissions<B> {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        self.check(path, "get_xattr", |p| p.read)?;
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        self.check(path, "set_xattr", |p| p.write)?;
+        self.inner.set_xattr(path, name, value)
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        self.check(path, "remove_xattr", |p| p.write)?;
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        self.check(path, "list_xattr", |p| p.read)?;
+        self.inner.list_xattr(path)
+    }
+}
+
+forward_fs_stats!(EnforcePermissions);
+forward_fs_sync!(EnforcePermissions);
+forward_fs_lock!(EnforcePermissions);
+forward_fs_handle_debug!(EnforcePermissions, FsRead);
+forward_fs_seek!(EnforcePermissions, FsRead);
+forward_fs_file_flags!(EnforcePermissions);
+forward_fs_tiering!(EnforcePermissions);
+forward_fs_expiry!(EnforcePermissions);
+forward_fs_lease!(EnforcePermissions);
+forward_fs_layers!(EnforcePermissions);
+forward_fs_sequence!(EnforcePermissions);
+forward_fs_cache_control!(EnforcePermissions);
+
+/// [`Layer`] producing [`EnforcePermissions`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Credentials, EnforcePermissionsLayer, Fs, LayerExt};
+///
+/// // A sandbox where uid 1000 sees only what other users may see
+/// fn as_guest<B: Fs>(backend: B) -> impl Fs {
+///     backend.layer(EnforcePermissionsLayer::with_credentials(Credentials::new(1000, 1000)))
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnforcePermissionsLayer {
+    credentials: Option<Credentials>,
+}
+
+impl EnforcePermissionsLayer {
+    /// Layer treating the caller as the owner of every entry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Layer checking requests against `credentials`.
+    pub fn with_credentials(credentials: Credentials) -> Self {
+        Self {
+            credentials: Some(credentials),
+        }
+    }
+}
+
+impl<B> Layer<B> for EnforcePermissionsLayer {
+    type Backend = EnforcePermissions<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        EnforcePermissions {
+            inner: backend,
+            credentials: self.credentials,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClosureFs, FsBuilder, LayerExt};
+
+    /// `/` (0o755), `/home` (0o755, uid 1), `/home/private` (0o700, uid 1),
+    /// `/home/private/key` (0o600, uid 1), `/home/notes` (0o640, uid 1,
+    /// gid 10) and `/home/locked` (0o000, uid 1)
+    fn tree() -> ClosureFs {
+        FsBuilder::new()
+            .on_metadata(|path| {
+                let (mode, file_type) = match path.to_str() {
+                    Some("/") | Some("/home") => (0o755, FileType::Directory),
+                    Some("/home/private") => (0o700, FileType::Directory),
+                    Some("/home/private/key") => (0o600, FileType::File),
+                    Some("/home/notes") => (0o640, FileType::File),
+                    Some("/home/locked") => (0o000, FileType::File),
+                    _ => return Err(FsError::not_found("metadata", path)),
+                };
+                Ok(Metadata {
+                    file_type,
+                    permissions: Permissions::from_mode(mode),
+                    uid: (path != Path::new("/")).then_some(1),
+                    gid: Some(10),
+                    ..Metadata::default()
+                })
+            })
+            .on_read(|_| Ok(b"data".to_vec()))
+            .on_write(|_, _| Ok(()))
+            .on_read_dir(|_| Ok(ReadDirIter::from_vec(Vec::new())))
+            .build()
+    }
+
+    /// [`tree`] with inodes numbered in the order its paths are listed.
+    struct Indexed(ClosureFs);
+
+    const PATHS: [&str; 6] = [
+        "/",
+        "/home",
+        "/home/private",
+        "/home/private/key",
+        "/home/notes",
+        "/home/locked",
+    ];
+
+    impl FsRead for Indexed {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.0.read(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            self.0.read_to_string(path)
+        }
+
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            self.0.read_range(path, offset, len)
+        }
+
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            self.0.exists(path)
+        }
+
+        fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            self.0.metadata(path)
+        }
+
+        fn open_read(&self, path: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+            self.0.open_read(path)
+        }
+    }
+
+    impl FsInode for Indexed {
+        fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+            PATHS
+                .iter()
+                .position(|p| Path::new(p) == path)
+                .map(|i| i as u64 + 1)
+                .ok_or_else(|| FsError::not_found("path_to_inode", path))
+        }
+
+        fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+            PATHS
+                .get((inode as usize).wrapping_sub(1))
+                .map(PathBuf::from)
+                .ok_or(FsError::InodeNotFound { inode })
+        }
+
+        fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+            self.path_to_inode(&self.inode_to_path(parent_inode)?.join(name))
+        }
+
+        fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+            self.0.metadata(&self.inode_to_path(inode)?)
+        }
+
+        fn read_dir_inode(
+            &self,
+            _inode: u64,
+            _offset: u64,
+        ) -> Result<Vec<(DirEntry, u64)>, FsError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn is_denied<T>(result: Result<T, FsError>) -> bool {
+        matches!(result, Err(FsError::PermissionDenied { .. }))
+    }
+
+    #[test]
+    fn owner_bits_apply_without_credentials() {
+        let fs = tree().layer(EnforcePermissionsLayer::new());
+        assert_eq!(fs.read(Path::new("/home/private/key")).unwrap(), b"data");
+        fs.write(Path::new("/home/notes"), b"x").unwrap();
+        fs.write(Path::new("/home/new"), b"x").unwrap();
+
+        assert!(is_denied(fs.read(Path::new("/home/locked"))));
+        assert!(is_denied(fs.write(Path::new("/home/locked"), b"x")));
+        // Missing paths reach the backend
+        assert!(matches!(
+            fs.metadata(Path::new("/missing")),
+            Err(FsError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn credentials_select_group_and_other_bits() {
+        let member = tree().layer(EnforcePermissionsLayer::with_credentials(
+            Credentials::new(2, 2).with_groups([10]),
+        ));
+        assert!(member.read(Path::new("/home/notes")).is_ok());
+        assert!(is_denied(member.write(Path::new("/home/notes"), b"x")));
+
+        let stranger = tree().layer(EnforcePermissionsLayer::with_credentials(Credentials::new(
+            3, 3,
+        )));
+        assert!(is_denied(stranger.read(Path::new("/home/notes"))));
+        assert!(stranger.read_dir(Path::new("/home")).is_ok());
+        // Creating an entry needs write access to /home
+        assert!(is_denied(stranger.write(Path::new("/home/new"), b"x")));
+    }
+
+    #[test]
+    fn unsearchable_directories_hide_their_contents() {
+        let fs = tree().layer(EnforcePermissionsLayer::with_credentials(Credentials::new(
+            2, 10,
+        )));
+        assert!(fs.metadata(Path::new("/home/private")).is_ok());
+        assert!(is_denied(fs.metadata(Path::new("/home/private/key"))));
+        assert!(is_denied(fs.read_dir(Path::new("/home/private"))));
+    }
+
+    #[test]
+    fn inode_calls_are_checked_like_paths() {
+        let fs = EnforcePermissions::with_credentials(Indexed(tree()), Credentials::new(2, 10));
+        let home = fs.path_to_inode(Path::new("/home")).unwrap();
+        let private = fs.path_to_inode(Path::new("/home/private")).unwrap();
+        let key = fs.path_to_inode(Path::new("/home/private/key")).unwrap();
+
+        assert!(fs.read_dir_inode(home, 0).is_ok());
+        assert!(is_denied(fs.read_dir_inode(private, 0)));
+
+        assert_eq!(fs.lookup(home, OsStr::new("private")).unwrap(), private);
+        assert!(is_denied(fs.lookup(private, OsStr::new("key"))));
+
+        assert!(fs.metadata_by_inode(private).is_ok());
+        assert!(is_denied(fs.metadata_by_inode(key)));
+    }
+}
@@ -0,0 +1,741 @@
+//! Enforcement of `chattr`-style file flags.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_layers, forward_fs_lease, forward_fs_lock, forward_fs_read, forward_fs_seek,
+    forward_fs_sequence, forward_fs_stats, forward_fs_sync, forward_fs_tiering,
+};
+use crate::{
+    CopyOptions, DirEntry, FileFlags, FsDir, FsError, FsFileFlags, FsHandles, FsInode, FsLink,
+    FsPermissions, FsWrite, FsXattr, Handle, Layer, Metadata, OpenFlags, Permissions, SetAttr,
+    WriteOptions,
+};
+
+/// Middleware enforcing [`FileFlags`] stored by the wrapped backend.
+///
+/// Violations fail with [`FsError::PermissionDenied`] before reaching the
+/// backend.
+///
+/// | Target | Denied when |
+/// |--------|-------------|
+/// | Overwrite, truncate, remove, rename, `open` for non-append writes, `set_attr` with a size | `immutable` or `append_only` |
+/// | Append, `set_permissions`, `set_times`, other `set_attr`, xattr changes, hard link to it | `immutable` |
+/// | Creating or removing entries in a directory | directory is `immutable` |
+/// | `remove_dir_all` | any entry in the tree is protected |
+///
+/// Paths that don't exist yet carry no flags. [`FsFileFlags`] itself is
+/// forwarded unchanged, so flags can still be cleared through the wrapper.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{EnforceFlagsLayer, FsFileFlags, FsWrite, FileFlags, FsError, Layer};
+/// use std::path::Path;
+///
+/// fn write_once<B: FsWrite + FsFileFlags>(backend: B) -> Result<(), FsError> {
+///     let fs = EnforceFlagsLayer.layer(backend);
+///     fs.write(Path::new("/ledger"), b"entry 1\n")?;
+///     fs.set_flags(Path::new("/ledger"), FileFlags::APPEND_ONLY)?;
+///
+///     fs.append(Path::new("/ledger"), b"entry 2\n")?;
+///     assert!(fs.write(Path::new("/ledger"), b"rewritten").is_err());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnforceFlags<B> {
+    inner: B,
+}
+
+impl<B> EnforceFlags<B> {
+    /// Wrap a backend.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: FsFileFlags> EnforceFlags<B> {
+    /// Flags of `path`, treating missing paths as unflagged.
+    fn flags(&self, path: &Path) -> Result<FileFlags, FsError> {
+        match self.inner.get_flags(path) {
+            Ok(flags) => Ok(flags),
+            Err(FsError::NotFound { .. }) => Ok(FileFlags::NONE),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deny `operation` on `path` if its flags match `denied`.
+    fn check(
+        &self,
+        path: &Path,
+        operation: &'static str,
+        denied: fn(FileFlags) -> bool,
+    ) -> Result<(), FsError> {
+        if denied(self.flags(path)?) {
+            return Err(FsError::PermissionDenied {
+                path: path.to_path_buf(),
+                operation,
+            });
+        }
+        Ok(())
+    }
+
+    /// Deny `operation` if `path` carries content-protecting flags.
+    fn check_content(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check(path, operation, FileFlags::protects_content)
+    }
+
+    /// Deny `operation` if `path` is immutable.
+    fn check_immutable(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check(path, operation, |flags| flags.immutable)
+    }
+
+    /// Deny creating or removing `path` if its parent directory is immutable.
+    fn check_parent(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        match path.parent() {
+            Some(parent) => self.check_immutable(parent, operation),
+            None => Ok(()),
+        }
+    }
+
+    /// Deny replacing or removing the entry at `path`.
+    fn check_entry_removal(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check_parent(path, operation)?;
+        self.check_content(path, operation)
+    }
+}
+
+impl<B: FsWrite + FsFileFlags> FsWrite for EnforceFlags<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check_entry_removal(path, "write")?;
+        self.inner.write(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check_parent(path, "append")?;
+        self.check_immutable(path, "append")?;
+        self.inner.append(path, data)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.check_entry_removal(path, "remove_file")?;
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check_entry_removal(from, "rename")?;
+        self.check_entry_removal(to, "rename")?;
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check_entry_removal(to, "copy")?;
+        self.inner.copy(from, to)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.check_content(path, "truncate")?;
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check_entry_removal(path, "open_write")?;
+        self.inner.open_write(path)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check_entry_removal(path, "open_write_sized")?;
+        self.inner.open_write_sized(path, expected_len)
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.check_entry_removal(to, "copy_with")?;
+        self.inner.copy_with(from, to, options)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.check_entry_removal(path, "write_with")?;
+        self.inner.write_with(path, data, options)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.check_immutable(path, "set_times")?;
+        self.inner.set_times(path, atime, mtime)
+    }
+}
+
+impl<B: FsDir + FsFileFlags> EnforceFlags<B> {
+    /// Deny removing a tree if any entry in it is protected.
+    fn check_tree(&self, path: &Path) -> Result<(), FsError> {
+        self.check_content(path, "remove_dir_all")?;
+        for entry in self.inner.read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type == crate::FileType::Directory {
+                self.check_tree(&entry.path)?;
+            } else {
+                self.check_content(&entry.path, "remove_dir_all")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: FsDir + FsFileFlags> FsDir for EnforceFlags<B> {
+    fn read_dir(&self, path: &Path) -> Result<crate::ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(
+        &self,
+        path: &Path,
+        options: crate::ReadDirOptions,
+    ) -> Result<crate::ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "create_dir")?;
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "create_dir_all")?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check_entry_removal(path, "remove_dir")?;
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "remove_dir_all")?;
+        self.check_tree(path)?;
+        self.inner.remove_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_parent(path, "create_dir_with")?;
+        self.inner.create_dir_with(path, perm)
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.check_immutable(parent, "create_temp_dir_in")?;
+        self.inner.create_temp_dir_in(parent, prefix)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+impl<B: FsLink + FsFileFlags> FsLink for EnforceFlags<B> {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        self.check_parent(link, "symlink")?;
+        self.inner.symlink(target, link)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        self.check_immutable(original, "hard_link")?;
+        self.check_parent(link, "hard_link")?;
+        self.inner.hard_link(original, link)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        self.inner.read_link(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<crate::Metadata, FsError> {
+        self.inner.symlink_metadata(path)
+    }
+}
+
+impl<B: FsPermissions + FsFileFlags> FsPermissions for EnforceFlags<B> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_immutable(path, "set_permissions")?;
+        self.inner.set_permissions(path, perm)
+    }
+}
+
+impl<B: FsHandles + FsFileFlags> FsHandles for EnforceFlags<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        if flags.write || flags.truncate {
+            let current = self.flags(path)?;
+            let denied =
+                current.immutable || (current.append_only && (flags.truncate || !flags.append));
+            if denied {
+                return Err(FsError::PermissionDenied {
+                    path: path.to_path_buf(),
+                    operation: "open",
+                });
+            }
+            if flags.create || flags.create_new {
+                self.check_parent(path, "open")?;
+            }
+        }
+        self.inner.open(path, flags)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.check_immutable(dir, "open_anonymous")?;
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.check_parent(path, "link_handle")?;
+        self.inner.link_handle(handle, path)
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check_parent(path, "mkfifo")?;
+        self.inner.mkfifo(path, perm)
+    }
+}
+
+impl<B: FsInode + FsFileFlags> FsInode for EnforceFlags<B> {
+    fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.path_to_inode(path)
+    }
+
+    fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+        self.inner.inode_to_path(inode)
+    }
+
+    fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+        self.inner.lookup(parent_inode, name)
+    }
+
+    fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+        self.inner.metadata_by_inode(inode)
+    }
+
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        self.inner.read_dir_inode(inode, offset)
+    }
+
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        if !attr.is_empty() {
+            let path = self.inner.inode_to_path(inode)?;
+            if attr.size.is_some() {
+                self.check_content(&path, "set_attr")?;
+            } else {
+                self.check_immutable(&path, "set_attr")?;
+            }
+        }
+        self.inner.set_attr(inode, attr)
+    }
+}
+
+impl<B: FsXattr + FsFileFlags> FsXattr for EnforceFlags<B> {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        self.check_immutable(path, "set_xattr")?;
+        self.inner.set_xattr(path, name, value)
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        self.check_immutable(path, "remove_xattr")?;
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        self.inner.list_xattr(path)
+    }
+}
+
+forward_fs_read!(EnforceFlags);
+forward_fs_stats!(EnforceFlags);
+forward_fs_sync!(EnforceFlags);
+forward_fs_lock!(EnforceFlags);
+forward_fs_handle_debug!(EnforceFlags, FsFileFlags);
+forward_fs_seek!(EnforceFlags, FsFileFlags);
+forward_fs_file_flags!(EnforceFlags);
+forward_fs_tiering!(EnforceFlags);
+forward_fs_expiry!(EnforceFlags);
+forward_fs_lease!(EnforceFlags);
+forward_fs_layers!(EnforceFlags);
+forward_fs_sequence!(EnforceFlags);
+forward_fs_cache_control!(EnforceFlags);
+
+/// [`Layer`] producing [`EnforceFlags`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{EnforceFlagsLayer, FsFileFlags, Fs, LayerExt};
+///
+/// fn protect<B: Fs + FsFileFlags>(backend: B) -> impl Fs + FsFileFlags {
+///     backend.layer(EnforceFlagsLayer)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnforceFlagsLayer;
+
+impl<B> Layer<B> for EnforceFlagsLayer {
+    type Backend = EnforceFlags<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        EnforceFlags::new(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirEntry, FileType, FsRead, Metadata, ReadDirIter};
+    use std::collections::{HashMap, HashSet};
+    use std::sync::RwLock;
+
+    /// Mock backend storing files, directories and flags
+    #[derive(Default)]
+    struct MockFlagFs {
+        files: RwLock<HashMap<PathBuf, Vec<u8>>>,
+        dirs: RwLock<HashSet<PathBuf>>,
+        flags: RwLock<HashMap<PathBuf, FileFlags>>,
+    }
+
+    impl MockFlagFs {
+        fn new() -> Self {
+            let fs = Self::default();
+            fs.dirs.write().unwrap().insert(PathBuf::from("/"));
+            fs
+        }
+
+        fn exists_any(&self, path: &Path) -> bool {
+            self.files.read().unwrap().contains_key(path)
+                || self.dirs.read().unwrap().contains(path)
+        }
+    }
+
+    impl FsRead for MockFlagFs {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.files
+                .read()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| FsError::not_found("read", path))
+        }
+
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            Ok(String::from_utf8_lossy(&self.read(path)?).into_owned())
+        }
+
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            let data = self.read(path)?;
+            let start = (offset as usize).min(data.len());
+            let end = (start + len).min(data.len());
+            Ok(data[start..end].to_vec())
+        }
+
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            Ok(self.exists_any(path))
+        }
+
+        fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            if self.dirs.read().unwrap().contains(path) {
+                return Ok(Metadata {
+                    file_type: FileType::Directory,
+                    ..Metadata::default()
+                });
+            }
+            let size = self.read(path)?.len() as u64;
+            Ok(Metadata {
+                size,
+                ..Metadata::default()
+            })
+        }
+
+        fn open_read(&self, path: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+            Ok(Box::new(std::io::Cursor::new(self.read(path)?)))
+        }
+    }
+
+    impl FsWrite for MockFlagFs {
+        fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.files
+                .write()
+                .unwrap()
+                .insert(path.to_path_buf(), data.to_vec());
+            Ok(())
+        }
+
+        fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.files
+                .write()
+                .unwrap()
+                .entry(path.to_path_buf())
+                .or_default()
+                .extend_from_slice(data);
+            Ok(())
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            self.files
+                .write()
+                .unwrap()
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| FsError::not_found("remove_file", path))
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            let data = self.read(from)?;
+            self.remove_file(from)?;
+            self.write(to, &data)
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            let data = self.read(from)?;
+            self.write(to, &data)
+        }
+
+        fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+            let mut files = self.files.write().unwrap();
+            let data = files
+                .get_mut(path)
+                .ok_or_else(|| FsError::not_found("truncate", path))?;
+            data.resize(size as usize, 0);
+            Ok(())
+        }
+
+        fn open_write(&self, _path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+            Ok(Box::new(std::io::sink()))
+        }
+    }
+
+    impl FsDir for MockFlagFs {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            let mut entries = Vec::new();
+            for (file, data) in self.files.read().unwrap().iter() {
+                if file.parent() == Some(path) {
+                    entries.push(Ok(DirEntry {
+                        name: file.file_name().unwrap().to_string_lossy().into_owned(),
+                        path: file.clone(),
+                        file_type: FileType::File,
+                        size: data.len() as u64,
+                        inode: 0,
+                        ..Default::default()
+                    }));
+                }
+            }
+            for dir in self.dirs.read().unwrap().iter() {
+                if dir.parent() == Some(path) {
+                    entries.push(Ok(DirEntry {
+                        name: dir.file_name().unwrap().to_string_lossy().into_owned(),
+                        path: dir.clone(),
+                        file_type: FileType::Directory,
+                        size: 0,
+                        inode: 0,
+                        ..Default::default()
+                    }));
+                }
+            }
+            Ok(ReadDirIter::from_vec(entries))
+        }
+
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.dirs.write().unwrap().insert(path.to_path_buf());
+            Ok(())
+        }
+
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            for ancestor in path.ancestors() {
+                self.dirs.write().unwrap().insert(ancestor.to_path_buf());
+            }
+            Ok(())
+        }
+
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.dirs.write().unwrap().remove(path);
+            Ok(())
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.files
+                .write()
+                .unwrap()
+                .retain(|p, _| !p.starts_with(path));
+            self.dirs.write().unwrap().retain(|p| !p.starts_with(path));
+            Ok(())
+        }
+    }
+
+    impl FsFileFlags for MockFlagFs {
+        fn set_flags(&self, path: &Path, flags: FileFlags) -> Result<(), FsError> {
+            if !self.exists_any(path) {
+                return Err(FsError::not_found("set_flags", path));
+            }
+            self.flags
+                .write()
+                .unwrap()
+                .insert(path.to_path_buf(), flags);
+            Ok(())
+        }
+
+        fn get_flags(&self, path: &Path) -> Result<FileFlags, FsError> {
+            if !self.exists_any(path) {
+                return Err(FsError::not_found("get_flags", path));
+            }
+            Ok(self
+                .flags
+                .read()
+                .unwrap()
+                .get(path)
+                .copied()
+                .unwrap_or_default())
+        }
+    }
+
+    fn enforced() -> EnforceFlags<MockFlagFs> {
+        EnforceFlagsLayer.layer(MockFlagFs::new())
+    }
+
+    fn is_denied<T>(result: Result<T, FsError>) -> bool {
+        matches!(result, Err(FsError::PermissionDenied { .. }))
+    }
+
+    #[test]
+    fn unflagged_and_missing_paths_are_unrestricted() {
+        let fs = enforced();
+        fs.write(Path::new("/new"), b"a").unwrap();
+        fs.write(Path::new("/new"), b"b").unwrap();
+        fs.remove_file(Path::new("/new")).unwrap();
+    }
+
+    #[test]
+    fn immutable_file_rejects_all_modification() {
+        let fs = enforced();
+        let path = Path::new("/sealed");
+        fs.write(path, b"data").unwrap();
+        fs.set_flags(path, FileFlags::IMMUTABLE).unwrap();
+
+        assert!(is_denied(fs.write(path, b"x")));
+        assert!(is_denied(fs.append(path, b"x")));
+        assert!(is_denied(fs.truncate(path, 0)));
+        assert!(is_denied(fs.remove_file(path)));
+        assert!(is_denied(fs.rename(path, Path::new("/moved"))));
+        assert!(is_denied(fs.copy(Path::new("/sealed"), path)));
+        assert_eq!(fs.read(path).unwrap(), b"data");
+    }
+
+    #[test]
+    fn append_only_file_allows_appends_only() {
+        let fs = enforced();
+        let path = Path::new("/log");
+        fs.write(path, b"one\n").unwrap();
+        fs.set_flags(path, FileFlags::APPEND_ONLY).unwrap();
+
+        fs.append(path, b"two\n").unwrap();
+        assert!(is_denied(fs.write(path, b"x")));
+        assert!(is_denied(fs.truncate(path, 0)));
+        assert!(is_denied(fs.remove_file(path)));
+        assert_eq!(fs.read(path).unwrap(), b"one\ntwo\n");
+    }
+
+    #[test]
+    fn rename_onto_protected_target_is_denied() {
+        let fs = enforced();
+        fs.write(Path::new("/src"), b"new").unwrap();
+        fs.write(Path::new("/dst"), b"old").unwrap();
+        fs.set_flags(Path::new("/dst"), FileFlags::IMMUTABLE)
+            .unwrap();
+        assert!(is_denied(fs.rename(Path::new("/src"), Path::new("/dst"))));
+    }
+
+    #[test]
+    fn immutable_directory_rejects_new_entries() {
+        let fs = enforced();
+        fs.create_dir(Path::new("/dir")).unwrap();
+        fs.set_flags(Path::new("/dir"), FileFlags::IMMUTABLE)
+            .unwrap();
+
+        assert!(is_denied(fs.write(Path::new("/dir/file"), b"x")));
+        assert!(is_denied(fs.create_dir(Path::new("/dir/sub"))));
+        assert!(is_denied(fs.remove_dir(Path::new("/dir"))));
+    }
+
+    #[test]
+    fn remove_dir_all_checks_whole_tree() {
+        let fs = enforced();
+        fs.create_dir_all(Path::new("/tree/sub")).unwrap();
+        fs.write(Path::new("/tree/sub/keep"), b"x").unwrap();
+        fs.set_flags(Path::new("/tree/sub/keep"), FileFlags::APPEND_ONLY)
+            .unwrap();
+
+        assert!(is_denied(fs.remove_dir_all(Path::new("/tree"))));
+        assert!(fs.exists(Path::new("/tree/sub/keep")).unwrap());
+
+        fs.set_flags(Path::new("/tree/sub/keep"), FileFlags::NONE)
+            .unwrap();
+        fs.remove_dir_all(Path::new("/tree")).unwrap();
+        assert!(!fs.exists(Path::new("/tree")).unwrap());
+    }
+
+    #[test]
+    fn no_dump_does_not_restrict_writes() {
+        let fs = enforced();
+        let path = Path::new("/cache");
+        fs.write(path, b"a").unwrap();
+        fs.set_flags(
+            path,
+            FileFlags {
+                no_dump: true,
+                ..FileFlags::NONE
+            },
+        )
+        .unwrap();
+        fs.write(path, b"b").unwrap();
+        fs.remove_file(path).unwrap();
+    }
+}
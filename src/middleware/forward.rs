@@ -0,0 +1,731 @@
+//! Forwarding impls for middleware.
+//!
+//! Middleware intercepts a few traits and passes the rest straight through to
+//! `self.inner`. These macros generate the pass-through impls so a wrapper
+//! keeps every capability of the backend it wraps, including overridden
+//! default methods.
+//!
+//! Each macro takes the wrapper's name, optionally followed by extra bounds
+//! the backend needs (e.g. when the trait's supertrait is intercepted with
+//! extra bounds). The wrapper must be generic over a single backend parameter
+//! stored in a field named `inner`.
+
+macro_rules! forward_fs_read {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsRead $(+ $extra)*> crate::FsRead for $wrapper<B> {
+            fn read(&self, path: &std::path::Path) -> Result<Vec<u8>, crate::FsError> {
+                self.inner.read(path)
+            }
+
+            fn read_to_string(&self, path: &std::path::Path) -> Result<String, crate::FsError> {
+                self.inner.read_to_string(path)
+            }
+
+            fn read_range(
+                &self,
+                path: &std::path::Path,
+                offset: u64,
+                len: usize,
+            ) -> Result<Vec<u8>, crate::FsError> {
+                self.inner.read_range(path, offset, len)
+            }
+
+            fn exists(&self, path: &std::path::Path) -> Result<bool, crate::FsError> {
+                self.inner.exists(path)
+            }
+
+            fn exists_fresh(&self, path: &std::path::Path) -> Result<bool, crate::FsError> {
+                self.inner.exists_fresh(path)
+            }
+
+            fn metadata(&self, path: &std::path::Path) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.metadata(path)
+            }
+
+            fn metadata_lite(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<crate::MetadataLite, crate::FsError> {
+                self.inner.metadata_lite(path)
+            }
+
+            fn metadata_mask(
+                &self,
+                path: &std::path::Path,
+                mask: crate::MetadataMask,
+            ) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.metadata_mask(path, mask)
+            }
+
+            fn metadata_with_tag(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.metadata_with_tag(path)
+            }
+
+            fn open_read(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<Box<dyn std::io::Read + Send>, crate::FsError> {
+                self.inner.open_read(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_dir {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsDir $(+ $extra)*> crate::FsDir for $wrapper<B> {
+            fn read_dir(&self, path: &std::path::Path) -> Result<crate::ReadDirIter, crate::FsError> {
+                self.inner.read_dir(path)
+            }
+
+            fn create_dir(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.create_dir(path)
+            }
+
+            fn create_dir_all(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.create_dir_all(path)
+            }
+
+            fn remove_dir(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.remove_dir(path)
+            }
+
+            fn remove_dir_all(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.remove_dir_all(path)
+            }
+
+            fn create_dir_with(
+                &self,
+                path: &std::path::Path,
+                perm: crate::Permissions,
+            ) -> Result<(), crate::FsError> {
+                self.inner.create_dir_with(path, perm)
+            }
+
+            fn create_temp_dir_in(
+                &self,
+                parent: &std::path::Path,
+                prefix: &str,
+            ) -> Result<std::path::PathBuf, crate::FsError> {
+                self.inner.create_temp_dir_in(parent, prefix)
+            }
+
+            fn dir_len(&self, path: &std::path::Path) -> Result<u64, crate::FsError> {
+                self.inner.dir_len(path)
+            }
+
+            fn is_dir_empty(&self, path: &std::path::Path) -> Result<bool, crate::FsError> {
+                self.inner.is_dir_empty(path)
+            }
+
+            fn read_dir_with(
+                &self,
+                path: &std::path::Path,
+                options: crate::ReadDirOptions,
+            ) -> Result<crate::ReadDirIter, crate::FsError> {
+                self.inner.read_dir_with(path, options)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_write {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsWrite $(+ $extra)*> crate::FsWrite for $wrapper<B> {
+            fn write(&self, path: &std::path::Path, data: &[u8]) -> Result<(), crate::FsError> {
+                self.inner.write(path, data)
+            }
+
+            fn append(&self, path: &std::path::Path, data: &[u8]) -> Result<(), crate::FsError> {
+                self.inner.append(path, data)
+            }
+
+            fn remove_file(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.remove_file(path)
+            }
+
+            fn rename(
+                &self,
+                from: &std::path::Path,
+                to: &std::path::Path,
+            ) -> Result<(), crate::FsError> {
+                self.inner.rename(from, to)
+            }
+
+            fn copy(&self, from: &std::path::Path, to: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.copy(from, to)
+            }
+
+            fn truncate(&self, path: &std::path::Path, size: u64) -> Result<(), crate::FsError> {
+                self.inner.truncate(path, size)
+            }
+
+            fn open_write(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<Box<dyn std::io::Write + Send>, crate::FsError> {
+                self.inner.open_write(path)
+            }
+
+            fn copy_with(
+                &self,
+                from: &std::path::Path,
+                to: &std::path::Path,
+                options: crate::CopyOptions,
+            ) -> Result<u64, crate::FsError> {
+                self.inner.copy_with(from, to, options)
+            }
+
+            fn write_with(
+                &self,
+                path: &std::path::Path,
+                data: &[u8],
+                options: crate::WriteOptions,
+            ) -> Result<(), crate::FsError> {
+                self.inner.write_with(path, data, options)
+            }
+
+            fn open_write_sized(
+                &self,
+                path: &std::path::Path,
+                expected_len: u64,
+            ) -> Result<Box<dyn std::io::Write + Send>, crate::FsError> {
+                self.inner.open_write_sized(path, expected_len)
+            }
+
+            fn set_times(
+                &self,
+                path: &std::path::Path,
+                atime: Option<std::time::SystemTime>,
+                mtime: Option<std::time::SystemTime>,
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_times(path, atime, mtime)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_link {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsLink $(+ $extra)*> crate::FsLink for $wrapper<B> {
+            fn symlink(
+                &self,
+                target: &std::path::Path,
+                link: &std::path::Path,
+            ) -> Result<(), crate::FsError> {
+                self.inner.symlink(target, link)
+            }
+
+            fn hard_link(
+                &self,
+                original: &std::path::Path,
+                link: &std::path::Path,
+            ) -> Result<(), crate::FsError> {
+                self.inner.hard_link(original, link)
+            }
+
+            fn read_link(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<std::path::PathBuf, crate::FsError> {
+                self.inner.read_link(path)
+            }
+
+            fn symlink_metadata(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.symlink_metadata(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_permissions {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsPermissions $(+ $extra)*> crate::FsPermissions for $wrapper<B> {
+            fn set_permissions(
+                &self,
+                path: &std::path::Path,
+                perm: crate::Permissions,
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_permissions(path, perm)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_stats {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsStats $(+ $extra)*> crate::FsStats for $wrapper<B> {
+            fn statfs(&self) -> Result<crate::StatFs, crate::FsError> {
+                self.inner.statfs()
+            }
+
+            fn usage(&self, path: &std::path::Path) -> Result<crate::UsageReport, crate::FsError> {
+                self.inner.usage(path)
+            }
+
+            fn io_stats(&self) -> crate::IoStats {
+                self.inner.io_stats()
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_sync {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsSync $(+ $extra)*> crate::FsSync for $wrapper<B> {
+            fn sync(&self) -> Result<(), crate::FsError> {
+                self.inner.sync()
+            }
+
+            fn fsync(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.fsync(path)
+            }
+
+            fn fdatasync(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.fdatasync(path)
+            }
+
+            fn sync_handle(&self, handle: crate::Handle) -> Result<(), crate::FsError> {
+                self.inner.sync_handle(handle)
+            }
+
+            fn barrier(&self) -> Result<(), crate::FsError> {
+                self.inner.barrier()
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_inode {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsInode $(+ $extra)*> crate::FsInode for $wrapper<B> {
+            fn path_to_inode(&self, path: &std::path::Path) -> Result<u64, crate::FsError> {
+                self.inner.path_to_inode(path)
+            }
+
+            fn inode_to_path(&self, inode: u64) -> Result<std::path::PathBuf, crate::FsError> {
+                self.inner.inode_to_path(inode)
+            }
+
+            fn lookup(
+                &self,
+                parent_inode: u64,
+                name: &std::ffi::OsStr,
+            ) -> Result<u64, crate::FsError> {
+                self.inner.lookup(parent_inode, name)
+            }
+
+            fn metadata_by_inode(&self, inode: u64) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.metadata_by_inode(inode)
+            }
+
+            fn read_dir_inode(
+                &self,
+                inode: u64,
+                offset: u64,
+            ) -> Result<Vec<(crate::DirEntry, u64)>, crate::FsError> {
+                self.inner.read_dir_inode(inode, offset)
+            }
+
+            fn set_attr(
+                &self,
+                inode: u64,
+                attr: crate::SetAttr,
+            ) -> Result<crate::Metadata, crate::FsError> {
+                self.inner.set_attr(inode, attr)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_handles {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsHandles $(+ $extra)*> crate::FsHandles for $wrapper<B> {
+            fn open(
+                &self,
+                path: &std::path::Path,
+                flags: crate::OpenFlags,
+            ) -> Result<crate::Handle, crate::FsError> {
+                self.inner.open(path, flags)
+            }
+
+            fn read_at(
+                &self,
+                handle: crate::Handle,
+                buf: &mut [u8],
+                offset: u64,
+            ) -> Result<usize, crate::FsError> {
+                self.inner.read_at(handle, buf, offset)
+            }
+
+            fn write_at(
+                &self,
+                handle: crate::Handle,
+                data: &[u8],
+                offset: u64,
+            ) -> Result<usize, crate::FsError> {
+                self.inner.write_at(handle, data, offset)
+            }
+
+            fn close(&self, handle: crate::Handle) -> Result<(), crate::FsError> {
+                self.inner.close(handle)
+            }
+
+            fn dup(&self, handle: crate::Handle) -> Result<crate::Handle, crate::FsError> {
+                self.inner.dup(handle)
+            }
+
+            fn append_handle(
+                &self,
+                handle: crate::Handle,
+                data: &[u8],
+            ) -> Result<u64, crate::FsError> {
+                self.inner.append_handle(handle, data)
+            }
+
+            fn open_anonymous(
+                &self,
+                dir: &std::path::Path,
+                flags: crate::OpenFlags,
+            ) -> Result<crate::Handle, crate::FsError> {
+                self.inner.open_anonymous(dir, flags)
+            }
+
+            fn link_handle(
+                &self,
+                handle: crate::Handle,
+                path: &std::path::Path,
+            ) -> Result<(), crate::FsError> {
+                self.inner.link_handle(handle, path)
+            }
+
+            fn mkfifo(
+                &self,
+                path: &std::path::Path,
+                perm: crate::Permissions,
+            ) -> Result<(), crate::FsError> {
+                self.inner.mkfifo(path, perm)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_lock {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsLock $(+ $extra)*> crate::FsLock for $wrapper<B> {
+            fn lock(
+                &self,
+                handle: crate::Handle,
+                lock: crate::LockType,
+            ) -> Result<(), crate::FsError> {
+                self.inner.lock(handle, lock)
+            }
+
+            fn try_lock(
+                &self,
+                handle: crate::Handle,
+                lock: crate::LockType,
+            ) -> Result<bool, crate::FsError> {
+                self.inner.try_lock(handle, lock)
+            }
+
+            fn unlock(&self, handle: crate::Handle) -> Result<(), crate::FsError> {
+                self.inner.unlock(handle)
+            }
+
+            fn lock_owned(
+                &self,
+                handle: crate::Handle,
+                owner: crate::LockOwner,
+                lock: crate::LockType,
+                range: crate::LockRange,
+            ) -> Result<(), crate::FsError> {
+                self.inner.lock_owned(handle, owner, lock, range)
+            }
+
+            fn try_lock_owned(
+                &self,
+                handle: crate::Handle,
+                owner: crate::LockOwner,
+                lock: crate::LockType,
+                range: crate::LockRange,
+            ) -> Result<bool, crate::FsError> {
+                self.inner.try_lock_owned(handle, owner, lock, range)
+            }
+
+            fn unlock_owned(
+                &self,
+                handle: crate::Handle,
+                owner: crate::LockOwner,
+                range: crate::LockRange,
+            ) -> Result<(), crate::FsError> {
+                self.inner.unlock_owned(handle, owner, range)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_xattr {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsXattr $(+ $extra)*> crate::FsXattr for $wrapper<B> {
+            fn get_xattr(
+                &self,
+                path: &std::path::Path,
+                name: &str,
+            ) -> Result<Vec<u8>, crate::FsError> {
+                self.inner.get_xattr(path, name)
+            }
+
+            fn set_xattr(
+                &self,
+                path: &std::path::Path,
+                name: &str,
+                value: &[u8],
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_xattr(path, name, value)
+            }
+
+            fn remove_xattr(
+                &self,
+                path: &std::path::Path,
+                name: &str,
+            ) -> Result<(), crate::FsError> {
+                self.inner.remove_xattr(path, name)
+            }
+
+            fn list_xattr(&self, path: &std::path::Path) -> Result<Vec<String>, crate::FsError> {
+                self.inner.list_xattr(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_handle_debug {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsHandleDebug $(+ $extra)*> crate::FsHandleDebug for $wrapper<B> {
+            fn open_handles(&self) -> Result<Vec<crate::HandleInfo>, crate::FsError> {
+                self.inner.open_handles()
+            }
+
+            fn close_all(&self) -> Result<usize, crate::FsError> {
+                self.inner.close_all()
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_seek {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsSeek $(+ $extra)*> crate::FsSeek for $wrapper<B> {
+            fn cursors(&self) -> &crate::HandleCursors {
+                self.inner.cursors()
+            }
+
+            fn handle_len(&self, handle: crate::Handle) -> Result<u64, crate::FsError> {
+                self.inner.handle_len(handle)
+            }
+
+            fn seek_handle(
+                &self,
+                handle: crate::Handle,
+                pos: std::io::SeekFrom,
+            ) -> Result<u64, crate::FsError> {
+                self.inner.seek_handle(handle, pos)
+            }
+
+            fn read_handle(
+                &self,
+                handle: crate::Handle,
+                buf: &mut [u8],
+            ) -> Result<usize, crate::FsError> {
+                self.inner.read_handle(handle, buf)
+            }
+
+            fn write_handle(
+                &self,
+                handle: crate::Handle,
+                data: &[u8],
+            ) -> Result<usize, crate::FsError> {
+                self.inner.write_handle(handle, data)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_file_flags {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsFileFlags $(+ $extra)*> crate::FsFileFlags for $wrapper<B> {
+            fn set_flags(
+                &self,
+                path: &std::path::Path,
+                flags: crate::FileFlags,
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_flags(path, flags)
+            }
+
+            fn get_flags(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<crate::FileFlags, crate::FsError> {
+                self.inner.get_flags(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_tiering {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsTiering $(+ $extra)*> crate::FsTiering for $wrapper<B> {
+            fn set_storage_class(
+                &self,
+                path: &std::path::Path,
+                class: crate::StorageClass,
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_storage_class(path, class)
+            }
+
+            fn storage_class(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<crate::StorageClass, crate::FsError> {
+                self.inner.storage_class(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_layers {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsLayers $(+ $extra)*> crate::FsLayers for $wrapper<B> {
+            fn layers(&self) -> Vec<crate::LayerInfo> {
+                self.inner.layers()
+            }
+
+            fn which_layer(&self, path: &std::path::Path) -> Result<crate::LayerId, crate::FsError> {
+                self.inner.which_layer(path)
+            }
+
+            fn copy_up(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.copy_up(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_cache_control {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsCacheControl $(+ $extra)*> crate::FsCacheControl for $wrapper<B> {
+            fn cache_policy(&self) -> crate::CachePolicy {
+                self.inner.cache_policy()
+            }
+
+            fn set_cache_policy(&self, policy: crate::CachePolicy) -> Result<(), crate::FsError> {
+                self.inner.set_cache_policy(policy)
+            }
+
+            fn cache_stats(&self) -> crate::CacheStats {
+                self.inner.cache_stats()
+            }
+
+            fn not_found_hint(&self) -> crate::NotFoundCacheHint {
+                self.inner.not_found_hint()
+            }
+
+            fn invalidate(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.invalidate(path)
+            }
+
+            fn invalidate_all(&self) -> Result<(), crate::FsError> {
+                self.inner.invalidate_all()
+            }
+
+            fn flush_cache(&self) -> Result<(), crate::FsError> {
+                self.inner.flush_cache()
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_sequence {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsSequence $(+ $extra)*> crate::FsSequence for $wrapper<B> {
+            fn seq(&self) -> Result<u64, crate::FsError> {
+                self.inner.seq()
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_expiry {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsExpiry $(+ $extra)*> crate::FsExpiry for $wrapper<B> {
+            fn set_expiry(
+                &self,
+                path: &std::path::Path,
+                at: std::time::SystemTime,
+            ) -> Result<(), crate::FsError> {
+                self.inner.set_expiry(path, at)
+            }
+
+            fn clear_expiry(&self, path: &std::path::Path) -> Result<(), crate::FsError> {
+                self.inner.clear_expiry(path)
+            }
+
+            fn expiry(
+                &self,
+                path: &std::path::Path,
+            ) -> Result<Option<std::time::SystemTime>, crate::FsError> {
+                self.inner.expiry(path)
+            }
+        }
+    };
+}
+
+macro_rules! forward_fs_lease {
+    ($wrapper:ident $(, $extra:path)*) => {
+        impl<B: crate::FsLease $(+ $extra)*> crate::FsLease for $wrapper<B> {
+            fn acquire_lease(
+                &self,
+                handle: crate::Handle,
+                lease: crate::LeaseType,
+            ) -> Result<bool, crate::FsError> {
+                self.inner.acquire_lease(handle, lease)
+            }
+
+            fn release_lease(&self, handle: crate::Handle) -> Result<(), crate::FsError> {
+                self.inner.release_lease(handle)
+            }
+
+            fn on_lease_break(&self, handler: crate::LeaseBreakHandler) {
+                self.inner.on_lease_break(handler)
+            }
+        }
+    };
+}
+
+pub(crate) use forward_fs_cache_control;
+pub(crate) use forward_fs_dir;
+pub(crate) use forward_fs_expiry;
+pub(crate) use forward_fs_file_flags;
+pub(crate) use forward_fs_handle_debug;
+pub(crate) use forward_fs_handles;
+pub(crate) use forward_fs_inode;
+pub(crate) use forward_fs_layers;
+pub(crate) use forward_fs_lease;
+pub(crate) use forward_fs_link;
+pub(crate) use forward_fs_lock;
+pub(crate) use forward_fs_permissions;
+pub(crate) use forward_fs_read;
+pub(crate) use forward_fs_seek;
+pub(crate) use forward_fs_sequence;
+pub(crate) use forward_fs_stats;
+pub(crate) use forward_fs_sync;
+pub(crate) use forward_fs_tiering;
+pub(crate) use forward_fs_write;
+pub(crate) use forward_fs_xattr;
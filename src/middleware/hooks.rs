@@ -0,0 +1,518 @@
+//! Callbacks around every core operation.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_handles, forward_fs_inode, forward_fs_layers, forward_fs_lease, forward_fs_link,
+    forward_fs_lock, forward_fs_permissions, forward_fs_seek, forward_fs_sequence,
+    forward_fs_stats, forward_fs_sync, forward_fs_tiering, forward_fs_xattr,
+};
+use crate::{
+    CopyOptions, FsDir, FsError, FsOp, FsRead, FsWrite, Layer, Metadata, MetadataLite,
+    MetadataMask, Permissions, ReadDirIter, ReadDirOptions, WriteOptions,
+};
+
+type Before = Arc<dyn Fn(&FsOp) -> Result<(), FsError> + Send + Sync>;
+type After = Arc<dyn Fn(&FsOp, Result<(), &FsError>) + Send + Sync>;
+
+/// Middleware calling a `before` hook ahead of each [`Fs`](crate::Fs)
+/// operation and an `after` hook once it finishes.
+///
+/// Each call is described by an [`FsOp`]. `before` can veto it by returning
+/// an error, which is passed to the caller unchanged and skips both the
+/// backend and `after`; `after` sees the outcome of every call that ran.
+/// Use it for validation, content scanning ([`FsError::ThreatDetected`]),
+/// policy checks or auditing without writing a full middleware.
+///
+/// Only [`FsRead`], [`FsWrite`] and [`FsDir`] are hooked. All other traits
+/// are forwarded to the backend unchanged. Building the [`FsOp`] copies the
+/// written data, so `write`, `write_with` and `append` cost one extra copy while a hook
+/// is set.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsError, FsOp, FsWrite, HookLayer, LayerExt};
+/// use std::path::Path;
+///
+/// let backend = FsBuilder::new().on_write(|_, _| Ok(())).build();
+/// let fs = backend.layer(HookLayer::new().before(|op| match op {
+///     FsOp::Write { path, data } if data.windows(5).any(|w| w == b"EICAR") => {
+///         Err(FsError::ThreatDetected {
+///             path: path.clone(),
+///             reason: "test signature".into(),
+///         })
+///     }
+///     _ => Ok(()),
+/// }));
+///
+/// assert!(fs.write(Path::new("/ok.txt"), b"hello").is_ok());
+/// assert!(matches!(
+///     fs.write(Path::new("/bad.txt"), b"X5O!EICAR"),
+///     Err(FsError::ThreatDetected { .. })
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct Hooks<B> {
+    inner: B,
+    before: Option<Before>,
+    after: Option<After>,
+}
+
+impl<B> Hooks<B> {
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Run `call` between the hooks; `op` is only built if a hook is set.
+    fn run<T>(
+        &self,
+        op: impl FnOnce() -> FsOp,
+        call: impl FnOnce(&B) -> Result<T, FsError>,
+    ) -> Result<T, FsError> {
+        if self.before.is_none() && self.after.is_none() {
+            return call(&self.inner);
+        }
+        let op = op();
+        if let Some(before) = &self.before {
+            before(&op)?;
+        }
+        let result = call(&self.inner);
+        if let Some(after) = &self.after {
+            after(&op, result.as_ref().map(|_| ()));
+        }
+        result
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for Hooks<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("inner", &self.inner)
+            .field("before", &self.before.is_some())
+            .field("after", &self.after.is_some())
+            .finish()
+    }
+}
+
+fn owned(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+impl<B: FsRead> FsRead for Hooks<B> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.run(|| FsOp::Read { path: owned(path) }, |fs| fs.read(path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        self.run(
+            || FsOp::ReadToString { path: owned(path) },
+            |fs| fs.read_to_string(path),
+        )
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        self.run(
+            || FsOp::ReadRange {
+                path: owned(path),
+                offset,
+                len,
+            },
+            |fs| fs.read_range(path, offset, len),
+        )
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.run(|| FsOp::Exists { path: owned(path) }, |fs| fs.exists(path))
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        self.run(
+            || FsOp::ExistsFresh { path: owned(path) },
+            |fs| fs.exists_fresh(path),
+        )
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.run(
+            || FsOp::Metadata { path: owned(path) },
+            |fs| fs.metadata(path),
+        )
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        self.run(
+            || FsOp::MetadataLite { path: owned(path) },
+            |fs| fs.metadata_lite(path),
+        )
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        self.run(
+            || FsOp::MetadataMask {
+                path: owned(path),
+                mask,
+            },
+            |fs| fs.metadata_mask(path, mask),
+        )
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.run(
+            || FsOp::MetadataWithTag { path: owned(path) },
+            |fs| fs.metadata_with_tag(path),
+        )
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        self.run(
+            || FsOp::OpenRead { path: owned(path) },
+            |fs| fs.open_read(path),
+        )
+    }
+}
+
+impl<B: FsWrite> FsWrite for Hooks<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.run(
+            || FsOp::Write {
+                path: owned(path),
+                data: data.to_vec(),
+            },
+            |fs| fs.write(path, data),
+        )
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.run(
+            || FsOp::Append {
+                path: owned(path),
+                data: data.to_vec(),
+            },
+            |fs| fs.append(path, data),
+        )
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::RemoveFile { path: owned(path) },
+            |fs| fs.remove_file(path),
+        )
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::Rename {
+                from: owned(from),
+                to: owned(to),
+            },
+            |fs| fs.rename(from, to),
+        )
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::Copy {
+                from: owned(from),
+                to: owned(to),
+            },
+            |fs| fs.copy(from, to),
+        )
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.run(
+            || FsOp::Truncate {
+                path: owned(path),
+                size,
+            },
+            |fs| fs.truncate(path, size),
+        )
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.run(
+            || FsOp::OpenWrite { path: owned(path) },
+            |fs| fs.open_write(path),
+        )
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        self.run(
+            || FsOp::OpenWriteSized {
+                path: owned(path),
+                expected_len,
+            },
+            |fs| fs.open_write_sized(path, expected_len),
+        )
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.run(
+            || FsOp::CopyWith {
+                from: owned(from),
+                to: owned(to),
+                options,
+            },
+            |fs| fs.copy_with(from, to, options),
+        )
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.run(
+            || FsOp::WriteWith {
+                path: owned(path),
+                data: data.to_vec(),
+                options,
+            },
+            |fs| fs.write_with(path, data, options),
+        )
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.run(
+            || FsOp::SetTimes {
+                path: owned(path),
+                atime,
+                mtime,
+            },
+            |fs| fs.set_times(path, atime, mtime),
+        )
+    }
+}
+
+impl<B: FsDir> FsDir for Hooks<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.run(
+            || FsOp::ReadDir { path: owned(path) },
+            |fs| fs.read_dir(path),
+        )
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.run(
+            || FsOp::ReadDirWith {
+                path: owned(path),
+                options,
+            },
+            |fs| fs.read_dir_with(path, options),
+        )
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::CreateDir { path: owned(path) },
+            |fs| fs.create_dir(path),
+        )
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::CreateDirAll { path: owned(path) },
+            |fs| fs.create_dir_all(path),
+        )
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::RemoveDir { path: owned(path) },
+            |fs| fs.remove_dir(path),
+        )
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.run(
+            || FsOp::RemoveDirAll { path: owned(path) },
+            |fs| fs.remove_dir_all(path),
+        )
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.run(
+            || FsOp::CreateDirWith {
+                path: owned(path),
+                perm,
+            },
+            |fs| fs.create_dir_with(path, perm),
+        )
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.run(
+            || FsOp::CreateTempDirIn {
+                parent: owned(parent),
+                prefix: prefix.to_string(),
+            },
+            |fs| fs.create_temp_dir_in(parent, prefix),
+        )
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.run(|| FsOp::DirLen { path: owned(path) }, |fs| fs.dir_len(path))
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.run(
+            || FsOp::IsDirEmpty { path: owned(path) },
+            |fs| fs.is_dir_empty(path),
+        )
+    }
+}
+
+forward_fs_link!(Hooks);
+forward_fs_permissions!(Hooks);
+forward_fs_stats!(Hooks);
+forward_fs_sync!(Hooks);
+forward_fs_inode!(Hooks);
+forward_fs_handles!(Hooks);
+forward_fs_lock!(Hooks);
+forward_fs_xattr!(Hooks);
+forward_fs_handle_debug!(Hooks);
+forward_fs_seek!(Hooks);
+forward_fs_file_flags!(Hooks);
+forward_fs_tiering!(Hooks);
+forward_fs_expiry!(Hooks);
+forward_fs_lease!(Hooks);
+forward_fs_layers!(Hooks);
+forward_fs_sequence!(Hooks);
+forward_fs_cache_control!(Hooks);
+
+/// [`Layer`] producing [`Hooks`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, HookLayer, LayerExt};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// fn count_failures<B: Fs>(backend: B, failures: Arc<AtomicUsize>) -> impl Fs {
+///     backend.layer(HookLayer::new().after(move |_op, outcome| {
+///         if outcome.is_err() {
+///             failures.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }))
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct HookLayer {
+    before: Option<Before>,
+    after: Option<After>,
+}
+
+impl HookLayer {
+    /// A layer with no hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `hook` before each operation; an error vetoes the operation.
+    pub fn before(
+        mut self,
+        hook: impl Fn(&FsOp) -> Result<(), FsError> + Send + Sync + 'static,
+    ) -> Self {
+        self.before = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` after each operation that ran, with its outcome.
+    pub fn after(
+        mut self,
+        hook: impl Fn(&FsOp, Result<(), &FsError>) + Send + Sync + 'static,
+    ) -> Self {
+        self.after = Some(Arc::new(hook));
+        self
+    }
+}
+
+impl fmt::Debug for HookLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookLayer")
+            .field("before", &self.before.is_some())
+            .field("after", &self.after.is_some())
+            .finish()
+    }
+}
+
+impl<B> Layer<B> for HookLayer {
+    type Backend = Hooks<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Hooks {
+            inner: backend,
+            before: self.before,
+            after: self.after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+    use std::sync::Mutex;
+
+    #[test]
+    fn veto_skips_backend_and_after_hook() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (writes, seen) = (log.clone(), log.clone());
+        let backend = FsBuilder::new()
+            .on_write(move |path, _| {
+                writes
+                    .lock()
+                    .unwrap()
+                    .push(format!("backend {}", path.display()));
+                Ok(())
+            })
+            .build();
+        let fs = backend.layer(
+            HookLayer::new()
+                .before(|op| {
+                    if op.path() == Path::new("/denied") {
+                        Err(FsError::permission_denied(op.operation(), op.path()))
+                    } else {
+                        Ok(())
+                    }
+                })
+                .after(move |op, outcome| {
+                    seen.lock().unwrap().push(format!(
+                        "after {} {}",
+                        op.operation(),
+                        outcome.is_ok()
+                    ));
+                }),
+        );
+
+        fs.write(Path::new("/ok"), b"x").unwrap();
+        assert!(matches!(
+            fs.write(Path::new("/denied"), b"x"),
+            Err(FsError::PermissionDenied { .. })
+        ));
+        assert!(fs.read(Path::new("/ok")).is_err());
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["backend /ok", "after write true", "after read false"]
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Cumulative I/O counters.
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_inode, forward_fs_layers, forward_fs_lease, forward_fs_link, forward_fs_lock,
+    forward_fs_permissions, forward_fs_seek, forward_fs_sequence, forward_fs_sync,
+    forward_fs_tiering, forward_fs_xattr,
+};
+use crate::{
+    CopyOptions, FsDir, FsError, FsHandles, FsRead, FsStats, FsWrite, Handle, IoStats, Layer,
+    Metadata, MetadataLite, MetadataMask, OpenFlags, Permissions, ReadDirIter, ReadDirOptions,
+    StatFs, UsageReport, WriteOptions,
+};
+
+/// Middleware maintaining [`IoStats`] for any backend.
+///
+/// The counters are reported by [`FsStats::io_stats`] (when the backend
+/// implements [`FsStats`]) and by [`stats`](IoCounted::stats).
+///
+/// | Counted as | Operations | Bytes |
+/// |------------|------------|-------|
+/// | `read_ops` | `read`, `read_to_string`, `read_range`, `open_read`, [`FsHandles::read_at`] | Returned data; `open_read` streams as they are read |
+/// | `write_ops` | `write`, `write_with`, `append`, `truncate`, `open_write`, [`FsHandles::write_at`] | Accepted data; `open_write` streams as they are written |
+/// | `other_ops` | The rest of [`FsRead`], [`FsWrite`], [`FsDir`] and [`FsHandles`] | None |
+///
+/// A failed call counts as an op and an error. Reads and writes on the
+/// streams count bytes and errors but not ops. All other traits are
+/// forwarded to the backend uncounted.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsRead, FsWrite, IoCountLayer, LayerExt};
+/// use std::path::Path;
+///
+/// let backend = FsBuilder::new()
+///     .on_read(|_| Ok(b"hello".to_vec()))
+///     .on_write(|_, _| Ok(()))
+///     .build();
+/// let fs = backend.layer(IoCountLayer::new());
+///
+/// fs.write(Path::new("/a.txt"), b"abc").unwrap();
+/// fs.read(Path::new("/a.txt")).unwrap();
+///
+/// let stats = fs.stats();
+/// assert_eq!((stats.bytes_written, stats.bytes_read), (3, 5));
+/// assert_eq!((stats.write_ops, stats.read_ops), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct IoCounted<B> {
+    inner: B,
+    counters: Arc<Counters>,
+}
+
+/// Which counter a call goes to.
+#[derive(Clone, Copy)]
+enum Kind {
+    Read,
+    Write,
+    Other,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    read_ops: AtomicU64,
+    write_ops: AtomicU64,
+    other_ops: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Counters {
+    /// Count one call of `kind`, plus the bytes `bytes` finds in its result.
+    fn record<T>(
+        &self,
+        kind: Kind,
+        result: Result<T, FsError>,
+        bytes: impl FnOnce(&T) -> u64,
+    ) -> Result<T, FsError> {
+        let (ops, transferred) = match kind {
+            Kind::Read => (&self.read_ops, Some(&self.bytes_read)),
+            Kind::Write => (&self.write_ops, Some(&self.bytes_written)),
+            Kind::Other => (&self.other_ops, None),
+        };
+        ops.fetch_add(1, Ordering::Relaxed);
+        match &result {
+            Ok(value) => {
+                if let Some(counter) = transferred {
+                    counter.fetch_add(bytes(value), Ordering::Relaxed);
+                }
+            }
+            Err(_) => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    fn other<T>(&self, result: Result<T, FsError>) -> Result<T, FsError> {
+        self.record(Kind::Other, result, |_| 0)
+    }
+
+    /// Count the outcome of a stream read or write, without an op.
+    fn stream(&self, counter: &AtomicU64, result: io::Result<usize>) -> io::Result<usize> {
+        match &result {
+            Ok(n) => counter.fetch_add(*n as u64, Ordering::Relaxed),
+            Err(_) => self.errors.fetch_add(1, Ordering::Relaxed),
+        };
+        result
+    }
+
+    fn snapshot(&self) -> IoStats {
+        IoStats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            read_ops: self.read_ops.load(Ordering::Relaxed),
+            write_ops: self.write_ops.load(Ordering::Relaxed),
+            other_ops: self.other_ops.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<B> IoCounted<B> {
+    /// The counters so far.
+    pub fn stats(&self) -> IoStats {
+        self.counters.snapshot()
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+fn len<T: AsRef<[u8]>>(data: &T) -> u64 {
+    data.as_ref().len() as u64
+}
+
+impl<B: FsRead> FsRead for IoCounted<B> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.counters.record(Kind::Read, self.inner.read(path), len)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        self.counters
+            .record(Kind::Read, self.inner.read_to_string(path), len)
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let result = self.inner.read_range(path, offset, len);
+        self.counters
+            .record(Kind::Read, result, |data| data.len() as u64)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.counters.other(self.inner.exists(path))
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        self.counters.other(self.inner.exists_fresh(path))
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.counters.other(self.inner.metadata(path))
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        self.counters.other(self.inner.metadata_lite(path))
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        self.counters.other(self.inner.metadata_mask(path, mask))
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.counters.other(self.inner.metadata_with_tag(path))
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        let reader = self
+            .counters
+            .record(Kind::Read, self.inner.open_read(path), |_| 0)?;
+        Ok(Box::new(CountingReader {
+            inner: reader,
+            counters: self.counters.clone(),
+        }))
+    }
+}
+
+impl<B: FsWrite> FsWrite for IoCounted<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let result = self.inner.write(path, data);
+        self.counters.record(Kind::Write, result, |_| len(&data))
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let result = self.inner.append(path, data);
+        self.counters.record(Kind::Write, result, |_| len(&data))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.remove_file(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.rename(from, to))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.copy(from, to))
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let result = self.inner.truncate(path, size);
+        self.counters.record(Kind::Write, result, |_| 0)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        let writer = self
+            .counters
+            .record(Kind::Write, self.inner.open_write(path), |_| 0)?;
+        Ok(Box::new(CountingWriter {
+            inner: writer,
+            counters: self.counters.clone(),
+        }))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        let result = self.inner.open_write_sized(path, expected_len);
+        let writer = self.counters.record(Kind::Write, result, |_| 0)?;
+        Ok(Box::new(CountingWriter {
+            inner: writer,
+            counters: self.counters.clone(),
+        }))
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.counters.other(self.inner.copy_with(from, to, options))
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let result = self.inner.write_with(path, data, options);
+        self.counters.record(Kind::Write, result, |_| len(&data))
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.counters
+            .other(self.inner.set_times(path, atime, mtime))
+    }
+}
+
+impl<B: FsDir> FsDir for IoCounted<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.counters.other(self.inner.read_dir(path))
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.counters.other(self.inner.read_dir_with(path, options))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.create_dir(path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.create_dir_all(path))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.remove_dir(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.remove_dir_all(path))
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.counters.other(self.inner.create_dir_with(path, perm))
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.counters
+            .other(self.inner.create_temp_dir_in(parent, prefix))
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.counters.other(self.inner.dir_len(path))
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.counters.other(self.inner.is_dir_empty(path))
+    }
+}
+
+impl<B: FsHandles> FsHandles for IoCounted<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.counters.other(self.inner.open(path, flags))
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        let result = self.inner.read_at(handle, buf, offset);
+        self.counters.record(Kind::Read, result, |n| *n as u64)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        let result = self.inner.write_at(handle, data, offset);
+        self.counters.record(Kind::Write, result, |n| *n as u64)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.counters.other(self.inner.close(handle))
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.counters.other(self.inner.dup(handle))
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        let result = self.inner.append_handle(handle, data);
+        self.counters
+            .record(Kind::Write, result, |_| data.len() as u64)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.counters.other(self.inner.open_anonymous(dir, flags))
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.counters.other(self.inner.link_handle(handle, path))
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.counters.other(self.inner.mkfifo(path, perm))
+    }
+}
+
+impl<B: FsStats> FsStats for IoCounted<B> {
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        self.inner.statfs()
+    }
+
+    fn usage(&self, path: &Path) -> Result<UsageReport, FsError> {
+        self.inner.usage(path)
+    }
+
+    fn io_stats(&self) -> IoStats {
+        self.stats()
+    }
+}
+
+forward_fs_link!(IoCounted);
+forward_fs_permissions!(IoCounted);
+forward_fs_sync!(IoCounted);
+forward_fs_inode!(IoCounted);
+forward_fs_lock!(IoCounted);
+forward_fs_xattr!(IoCounted);
+forward_fs_handle_debug!(IoCounted);
+forward_fs_seek!(IoCounted);
+forward_fs_file_flags!(IoCounted);
+forward_fs_tiering!(IoCounted);
+forward_fs_expiry!(IoCounted);
+forward_fs_lease!(IoCounted);
+forward_fs_layers!(IoCounted);
+forward_fs_sequence!(IoCounted);
+forward_fs_cache_control!(IoCounted);
+
+/// Reader from [`IoCounted::open_read`] counting the bytes it yields.
+struct CountingReader {
+    inner: Box<dyn Read + Send>,
+    counters: Arc<Counters>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        self.counters.stream(&self.counters.bytes_read, result)
+    }
+}
+
+/// Writer from [`IoCounted::open_write`] counting the bytes it accepts.
+struct CountingWriter {
+    inner: Box<dyn Write + Send>,
+    counters: Arc<Counters>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.counters.stream(&self.counters.bytes_written, result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// [`Layer`] producing [`IoCounted`], with every counter at zero.
+#[derive(Debug, Clone, Default)]
+pub struct IoCountLayer;
+
+impl IoCountLayer {
+    /// Count I/O from now on.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<B> Layer<B> for IoCountLayer {
+    type Backend = IoCounted<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        IoCounted {
+            inner: backend,
+            counters: Arc::new(Counters::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+
+    #[test]
+    fn failures_count_as_ops_and_errors() {
+        let fs = FsBuilder::new()
+            .on_read(|p| Err(FsError::not_found("read", p)))
+            .on_exists(|_| Ok(true))
+            .build()
+            .layer(IoCountLayer::new());
+
+        assert!(fs.read(Path::new("/missing")).is_err());
+        assert!(fs.exists(Path::new("/a")).unwrap());
+        assert_eq!(
+            fs.stats(),
+            IoStats {
+                read_ops: 1,
+                other_ops: 1,
+                errors: 1,
+                ..IoStats::default()
+            }
+        );
+    }
+
+    #[test]
+    fn streams_count_bytes_as_they_flow() {
+        let fs = FsBuilder::new()
+            .on_read(|_| Ok(vec![7; 10]))
+            .on_open_write(|_| Ok(Box::new(io::sink())))
+            .build()
+            .layer(IoCountLayer::new());
+
+        let mut reader = fs.open_read(Path::new("/f")).unwrap();
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(fs.stats().bytes_read, 4);
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(fs.stats().bytes_read, 10);
+
+        let mut writer = fs.open_write(Path::new("/g")).unwrap();
+        writer.write_all(b"abc").unwrap();
+        let stats = fs.stats();
+        assert_eq!((stats.bytes_written, stats.write_ops), (3, 1));
+        assert_eq!(stats.read_ops, 1);
+    }
+}
@@ -0,0 +1,650 @@
+//! Write-ahead journaling of mutations.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use super::forward::forward_fs_read;
+use crate::dispatch::{execute, FsOp};
+use crate::{
+    CopyOptions, Fs, FsDir, FsError, FsWrite, Layer, Permissions, ReadDirIter, ReadDirOptions,
+    WriteOptions,
+};
+
+/// One line of the journal.
+#[derive(Serialize, Deserialize)]
+enum Record {
+    /// Op `id` is about to be applied.
+    Intent { id: u64, op: FsOp },
+    /// Op `id` finished, successfully or not.
+    Done { id: u64 },
+}
+
+/// An op re-applied by [`Journaled::replay`], with its outcome.
+#[derive(Debug)]
+pub struct Replayed {
+    /// The op found without a completion record.
+    pub op: FsOp,
+    /// What re-applying it returned.
+    pub result: Result<(), FsError>,
+}
+
+/// The journal file, shared with open writers.
+struct Journal {
+    backend: Arc<dyn Fs>,
+    path: PathBuf,
+    /// The next op id, read from the journal on first use. Held for the
+    /// whole of intent, apply, done: mutations are serialized.
+    next_id: Mutex<Option<u64>>,
+}
+
+impl Journal {
+    fn append(&self, record: &Record, fresh_line: bool) -> Result<(), FsError> {
+        let mut line = Vec::new();
+        if fresh_line {
+            // The last run may have crashed halfway through a record
+            line.push(b'\n');
+        }
+        serde_json::to_writer(&mut line, record)
+            .map_err(|e| FsError::Serialization(e.to_string()))?;
+        line.push(b'\n');
+        self.backend.append(&self.path, &line)
+    }
+
+    /// Log `op`, run `apply`, then log its completion.
+    ///
+    /// A failed `apply` is completed too: its effect is unknown, and
+    /// replaying it would not make it known.
+    fn run<T>(&self, op: FsOp, apply: impl FnOnce() -> Result<T, FsError>) -> Result<T, FsError> {
+        let mut next_id = self.next_id.lock().unwrap_or_else(|e| e.into_inner());
+        let fresh_line = next_id.is_none();
+        let id = match *next_id {
+            Some(id) => id,
+            None => self.records()?.1,
+        };
+        *next_id = Some(id + 1);
+        self.append(&Record::Intent { id, op }, fresh_line)?;
+        let out = apply();
+        self.append(&Record::Done { id }, false)?;
+        out
+    }
+
+    /// Run pending op `id` again and log its completion. The outer error
+    /// is the journal's, the inner one the op's.
+    fn redo(
+        &self,
+        id: u64,
+        apply: impl FnOnce() -> Result<(), FsError>,
+    ) -> Result<Result<(), FsError>, FsError> {
+        let next_id = self.next_id.lock().unwrap_or_else(|e| e.into_inner());
+        let result = apply();
+        self.append(&Record::Done { id }, next_id.is_none())?;
+        Ok(result)
+    }
+
+    /// Ops whose intent has no completion record, in journal order, and
+    /// the next free id.
+    fn records(&self) -> Result<(Vec<(u64, FsOp)>, u64), FsError> {
+        let data = match self.backend.read(&self.path) {
+            Ok(data) => data,
+            Err(FsError::NotFound { .. }) => return Ok((Vec::new(), 0)),
+            Err(e) => return Err(e),
+        };
+        let mut intents = Vec::new();
+        let mut done = HashSet::new();
+        let mut next_id = 0;
+        for line in data.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+            match serde_json::from_slice(line) {
+                Ok(Record::Intent { id, op }) => {
+                    next_id = next_id.max(id + 1);
+                    intents.push((id, op));
+                }
+                Ok(Record::Done { id }) => {
+                    done.insert(id);
+                }
+                // A record torn by a crash is an intent that never got logged
+                Err(e) if e.is_eof() => {}
+                Err(e) => {
+                    return Err(FsError::CorruptedData {
+                        path: self.path.clone(),
+                        details: e.to_string(),
+                    })
+                }
+            }
+        }
+        intents.retain(|(id, _)| !done.contains(id));
+        Ok((intents, next_id))
+    }
+}
+
+/// Middleware logging every mutation to a journal before applying it.
+///
+/// Each mutation appends an intent record (the serialized [`FsOp`]) to the
+/// journal, runs on the wrapped backend, then appends a completion record.
+/// After a crash, [`pending`](Journaled::pending) lists the ops that may
+/// have been cut short and [`replay`](Journaled::replay) applies them
+/// again, so a backend without transactions can be brought back to the
+/// state its callers were told about. [`truncate`](Journaled::truncate)
+/// checkpoints the journal once the backend is known to be consistent.
+///
+/// The journal is a file on any [`Fs`], ideally a durable one separate from
+/// the journaled backend. Records are JSON lines. Mutations are serialized
+/// so the journal holds at most one unfinished op per crash.
+///
+/// Replay re-runs ops as they were logged. `write`, `truncate`,
+/// `create_dir_all` and `remove_dir_all` are safe to repeat; `append`
+/// repeats its data if the first attempt landed, and ops such as
+/// `create_dir` or `rename` can fail on replay because the first attempt
+/// landed. [`Replayed`] reports each outcome.
+///
+/// `open_write` and `open_write_sized` writers buffer their data and log
+/// it as one `write` when dropped; a crash before then loses the streamed
+/// data, as it would without the journal. `create_temp_dir_in` is logged
+/// as a `create_temp_dir_in`, so its replay picks a new name. Only
+/// [`FsRead`](crate::FsRead), [`FsWrite`] and [`FsDir`] are implemented:
+/// mutations through other traits can't be expressed as an [`FsOp`].
+///
+/// Requires the `serde` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsError, JournalLayer, LayerExt};
+///
+/// fn recover<B: Fs>(backend: B, journal: impl Fs + 'static) -> Result<impl Fs, FsError> {
+///     let fs = backend.layer(JournalLayer::new(journal, "/fs.journal"));
+///     for replayed in fs.replay()? {
+///         if let Err(e) = replayed.result {
+///             eprintln!("{:?} not replayed: {e}", replayed.op);
+///         }
+///     }
+///     fs.truncate()?;
+///     Ok(fs)
+/// }
+/// ```
+pub struct Journaled<B> {
+    inner: B,
+    journal: Arc<Journal>,
+}
+
+impl<B> Journaled<B> {
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Ops logged as intended but never completed.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::CorruptedData`] if a record can't be parsed; records
+    ///   cut short by a crash are skipped
+    /// - Errors from reading the journal; a missing journal is empty
+    pub fn pending(&self) -> Result<Vec<FsOp>, FsError> {
+        let (pending, _) = self.journal.records()?;
+        Ok(pending.into_iter().map(|(_, op)| op).collect())
+    }
+
+    /// Empty the journal.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from writing the journal
+    pub fn truncate(&self) -> Result<(), FsError> {
+        let _lock = self
+            .journal
+            .next_id
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        self.journal.backend.write(&self.journal.path, b"")
+    }
+}
+
+impl<B: Fs> Journaled<B> {
+    /// Re-apply the [`pending`](Self::pending) ops, in order, and mark
+    /// each one completed.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from reading or appending to the journal. The failures of
+    ///   the ops themselves are reported in [`Replayed::result`].
+    pub fn replay(&self) -> Result<Vec<Replayed>, FsError> {
+        let (pending, _) = self.journal.records()?;
+        let mut replayed = Vec::with_capacity(pending.len());
+        for (id, op) in pending {
+            let result = self
+                .journal
+                .redo(id, || execute(&self.inner, op.clone()).map(drop))?;
+            replayed.push(Replayed { op, result });
+        }
+        Ok(replayed)
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for Journaled<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Journaled")
+            .field("inner", &self.inner)
+            .field("journal", &self.journal.path)
+            .finish()
+    }
+}
+
+forward_fs_read!(Journaled);
+
+impl<B: FsWrite> FsWrite for Journaled<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let op = FsOp::Write {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+        };
+        self.journal.run(op, || self.inner.write(path, data))
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let op = FsOp::WriteWith {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+            options,
+        };
+        self.journal
+            .run(op, || self.inner.write_with(path, data, options))
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        let op = FsOp::SetTimes {
+            path: path.to_path_buf(),
+            atime,
+            mtime,
+        };
+        self.journal
+            .run(op, || self.inner.set_times(path, atime, mtime))
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let op = FsOp::Append {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+        };
+        self.journal.run(op, || self.inner.append(path, data))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveFile {
+            path: path.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.remove_file(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let op = FsOp::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.rename(from, to))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let op = FsOp::Copy {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.copy(from, to))
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let op = FsOp::CopyWith {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            options,
+        };
+        self.journal
+            .run(op, || self.inner.copy_with(from, to, options))
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let op = FsOp::Truncate {
+            path: path.to_path_buf(),
+            size,
+        };
+        self.journal.run(op, || self.inner.truncate(path, size))
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        let inner = self.inner.open_write(path)?;
+        Ok(Box::new(JournalWriter::new(inner, path, &self.journal)))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        let inner = self.inner.open_write_sized(path, expected_len)?;
+        Ok(Box::new(JournalWriter::new(inner, path, &self.journal)))
+    }
+}
+
+impl<B: FsDir> FsDir for Journaled<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::CreateDir {
+            path: path.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.create_dir(path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::CreateDirAll {
+            path: path.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.create_dir_all(path))
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let op = FsOp::CreateDirWith {
+            path: path.to_path_buf(),
+            perm,
+        };
+        self.journal
+            .run(op, || self.inner.create_dir_with(path, perm))
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        let op = FsOp::CreateTempDirIn {
+            parent: parent.to_path_buf(),
+            prefix: prefix.to_string(),
+        };
+        self.journal
+            .run(op, || self.inner.create_temp_dir_in(parent, prefix))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveDir {
+            path: path.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.remove_dir(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveDirAll {
+            path: path.to_path_buf(),
+        };
+        self.journal.run(op, || self.inner.remove_dir_all(path))
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+/// Writer returned by `open_write`, journaling its data once dropped.
+struct JournalWriter {
+    inner: Box<dyn Write + Send>,
+    path: PathBuf,
+    journal: Arc<Journal>,
+    buffer: Vec<u8>,
+}
+
+impl JournalWriter {
+    fn new(inner: Box<dyn Write + Send>, path: &Path, journal: &Arc<Journal>) -> Self {
+        Self {
+            inner,
+            path: path.to_path_buf(),
+            journal: journal.clone(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl Write for JournalWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Buffered until drop: the data must be logged in full before any of
+    /// it reaches the backend.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        let data = std::mem::take(&mut self.buffer);
+        let op = FsOp::Write {
+            path: self.path.clone(),
+            data: data.clone(),
+        };
+        let (inner, path) = (&mut self.inner, &self.path);
+        // A drop can't report failures.
+        let _ = self.journal.run(op, || {
+            inner
+                .write_all(&data)
+                .and_then(|()| inner.flush())
+                .map_err(|e| FsError::io("open_write", path, e))
+        });
+    }
+}
+
+/// [`Layer`] that produces [`Journaled`].
+pub struct JournalLayer {
+    journal: Arc<dyn Fs>,
+    path: PathBuf,
+}
+
+impl JournalLayer {
+    /// Keep the journal in the file at `path` on `journal`.
+    pub fn new(journal: impl Fs + 'static, path: impl Into<PathBuf>) -> Self {
+        Self {
+            journal: Arc::new(journal),
+            path: path.into(),
+        }
+    }
+}
+
+impl fmt::Debug for JournalLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JournalLayer")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> Layer<B> for JournalLayer {
+    type Backend = Journaled<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Journaled {
+            inner: backend,
+            journal: Arc::new(Journal {
+                backend: self.journal,
+                path: self.path,
+                next_id: Mutex::new(None),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+
+    type Shared = Arc<Mutex<Vec<u8>>>;
+
+    /// A backend holding a single journal file.
+    fn journal() -> (impl Fs, Shared) {
+        let file = Shared::default();
+        let (read, append, write) = (file.clone(), file.clone(), file.clone());
+        let fs = FsBuilder::new()
+            .on_read(move |_| Ok(read.lock().unwrap().clone()))
+            .on_append(move |_, data| {
+                append.lock().unwrap().extend_from_slice(data);
+                Ok(())
+            })
+            .on_write(move |_, data| {
+                *write.lock().unwrap() = data.to_vec();
+                Ok(())
+            })
+            .build();
+        (fs, file)
+    }
+
+    fn line(record: &Record) -> Vec<u8> {
+        let mut line = serde_json::to_vec(record).unwrap();
+        line.push(b'\n');
+        line
+    }
+
+    #[test]
+    fn completed_ops_leave_nothing_pending() {
+        let (journal, _) = journal();
+        let fs = FsBuilder::new()
+            .on_write(|_, _| Ok(()))
+            .on_create_dir(|p| Err(FsError::already_exists("create_dir", p)))
+            .build()
+            .layer(JournalLayer::new(journal, "/j"));
+
+        fs.write(Path::new("/a"), b"x").unwrap();
+        assert!(fs.create_dir(Path::new("/d")).is_err());
+        assert!(fs.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn replay_applies_unfinished_intents() {
+        let (journal, file) = journal();
+        let write = FsOp::Write {
+            path: "/a".into(),
+            data: b"new".to_vec(),
+        };
+        let create = FsOp::CreateDir { path: "/d".into() };
+        {
+            let mut file = file.lock().unwrap();
+            let remove = FsOp::RemoveFile { path: "/x".into() };
+            file.extend(line(&Record::Intent { id: 0, op: remove }));
+            file.extend(line(&Record::Intent {
+                id: 1,
+                op: write.clone(),
+            }));
+            file.extend(line(&Record::Done { id: 0 }));
+            file.extend(line(&Record::Intent {
+                id: 2,
+                op: create.clone(),
+            }));
+            file.extend(b"{\"Intent\":{\"id\":3,\"op\":{\"Wri");
+        }
+
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let (w, c) = (applied.clone(), applied.clone());
+        let fs = FsBuilder::new()
+            .on_write(move |p, _| {
+                w.lock().unwrap().push(p.to_path_buf());
+                Ok(())
+            })
+            .on_create_dir(move |p| {
+                c.lock().unwrap().push(p.to_path_buf());
+                Ok(())
+            })
+            .build()
+            .layer(JournalLayer::new(journal, "/j"));
+
+        assert_eq!(fs.pending().unwrap(), [write.clone(), create.clone()]);
+        let replayed = fs.replay().unwrap();
+        assert_eq!(replayed.len(), 2);
+        assert!(replayed.iter().all(|r| r.result.is_ok()));
+        assert_eq!(*applied.lock().unwrap(), [Path::new("/a"), Path::new("/d")]);
+        assert!(fs.pending().unwrap().is_empty());
+
+        // New records start past the torn one and the ids in use
+        fs.write(Path::new("/b"), b"").unwrap();
+        let logged = file.lock().unwrap().clone();
+        assert!(logged.ends_with(b"\n\n{\"Intent\":{\"id\":3,\"op\":{\"op\":\"write\",\"path\":\"/b\",\"data\":[]}}}\n{\"Done\":{\"id\":3}}\n"));
+
+        fs.truncate().unwrap();
+        assert!(file.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn corrupt_record_is_reported() {
+        let (journal, file) = journal();
+        file.lock()
+            .unwrap()
+            .extend(b"garbage\n{\"Done\":{\"id\":0}}\n");
+        let fs = FsBuilder::new()
+            .build()
+            .layer(JournalLayer::new(journal, "/j"));
+
+        assert!(matches!(fs.pending(), Err(FsError::CorruptedData { .. })));
+    }
+
+    #[test]
+    fn streamed_writes_are_logged_before_they_land() {
+        let (journal, file) = journal();
+        let landed = Shared::default();
+        let sink = landed.clone();
+        let fs = FsBuilder::new()
+            .on_open_write(move |_| Ok(Box::new(SharedWriter(sink.clone()))))
+            .build()
+            .layer(JournalLayer::new(journal, "/j"));
+
+        let mut writer = fs.open_write(Path::new("/big")).unwrap();
+        writer.write_all(b"abc").unwrap();
+        writer.flush().unwrap();
+        assert!(landed.lock().unwrap().is_empty());
+        drop(writer);
+
+        assert_eq!(*landed.lock().unwrap(), b"abc");
+        let logged = file.lock().unwrap().clone();
+        let mut expected = b"\n".to_vec();
+        expected.extend(line(&Record::Intent {
+            id: 0,
+            op: FsOp::Write {
+                path: "/big".into(),
+                data: b"abc".to_vec(),
+            },
+        }));
+        expected.extend(line(&Record::Done { id: 0 }));
+        assert_eq!(logged, expected);
+    }
+
+    struct SharedWriter(Shared);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
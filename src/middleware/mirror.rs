@@ -0,0 +1,697 @@
+//! Primary/replica mirroring of mutations.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::SystemTime;
+
+use super::forward::forward_fs_read;
+use crate::dispatch::{execute, FsOp};
+use crate::{
+    CopyOptions, Fs, FsDir, FsError, FsWrite, Layer, Permissions, ReadDirIter, ReadDirOptions,
+    WriteOptions,
+};
+
+/// How a replica receives mutations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplicaMode {
+    /// Applied in the calling thread, before the call returns.
+    #[default]
+    Sync,
+    /// Handed to a background thread and applied in order, later.
+    /// [`Mirrored::flush_replicas`] waits for the queue to drain.
+    Queued,
+}
+
+/// What a [`Mirrored`] backend does when a replica fails to apply a
+/// mutation the primary accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MirrorPolicy {
+    /// Report the failure to the caller, and refuse further mutations with
+    /// [`FsError::Conflict`] until the divergences are taken with
+    /// [`Mirrored::take_divergences`].
+    #[default]
+    FailFast,
+    /// Record the failure and carry on.
+    BestEffort,
+}
+
+/// A mutation the primary applied but a replica did not.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// Index of the replica, in the order it was added to the [`MirrorLayer`].
+    pub replica: usize,
+    /// The operation, as named by [`FsOp::operation`].
+    pub operation: &'static str,
+    /// The path the operation acted on (the source, for renames and copies).
+    pub path: PathBuf,
+    /// The error the replica returned.
+    pub error: FsError,
+}
+
+type Divergences = Arc<Mutex<Vec<Divergence>>>;
+
+enum Job {
+    Apply(FsOp),
+    Flush(Sender<()>),
+}
+
+/// A replica fed from a queue by its own worker thread.
+struct QueuedReplica {
+    sender: Mutex<Option<Sender<Job>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl QueuedReplica {
+    fn spawn(index: usize, replica: Arc<dyn Fs>, divergences: Divergences) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || drain(index, &*replica, &divergences, receiver));
+        Self {
+            sender: Mutex::new(Some(sender)),
+            worker: Some(worker),
+        }
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(sender) = &*self.sender.lock().unwrap_or_else(|e| e.into_inner()) {
+            let _ = sender.send(job);
+        }
+    }
+}
+
+impl Drop for QueuedReplica {
+    /// Closes the queue and waits for the worker to apply what is left.
+    fn drop(&mut self) {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn drain(index: usize, replica: &dyn Fs, divergences: &Divergences, jobs: Receiver<Job>) {
+    for job in jobs {
+        match job {
+            Job::Apply(op) => {
+                let _ = apply(index, replica, divergences, op);
+            }
+            Job::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+/// Apply `op` to a replica, recording a divergence on failure.
+fn apply(
+    index: usize,
+    replica: &dyn Fs,
+    divergences: &Divergences,
+    op: FsOp,
+) -> Result<(), FsError> {
+    let operation = op.operation();
+    let path = op.path().to_path_buf();
+    execute(replica, op).map(drop).map_err(|error| {
+        divergences
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Divergence {
+                replica: index,
+                operation,
+                path,
+                error: error.clone(),
+            });
+        error
+    })
+}
+
+enum Replica {
+    Sync(Arc<dyn Fs>),
+    Queued(QueuedReplica),
+}
+
+/// State shared between a [`Mirrored`] backend and its open writers.
+struct Replicas {
+    replicas: Vec<Replica>,
+    policy: MirrorPolicy,
+    divergences: Divergences,
+}
+
+impl Replicas {
+    /// Send a mutation the primary accepted to every replica.
+    fn mirror(&self, op: FsOp) -> Result<(), FsError> {
+        let mut first_error = None;
+        for (index, replica) in self.replicas.iter().enumerate() {
+            match replica {
+                Replica::Sync(fs) => {
+                    if let Err(e) = apply(index, &**fs, &self.divergences, op.clone()) {
+                        if self.policy == MirrorPolicy::FailFast {
+                            first_error.get_or_insert(e);
+                        }
+                    }
+                }
+                Replica::Queued(queue) => queue.send(Job::Apply(op.clone())),
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Under [`MirrorPolicy::FailFast`], refuse to mutate a diverged mirror.
+    fn check(&self, path: &Path) -> Result<(), FsError> {
+        let diverged = !self
+            .divergences
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty();
+        if self.policy == MirrorPolicy::FailFast && diverged {
+            return Err(FsError::Conflict {
+                path: path.to_path_buf(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Middleware applying every mutation to a primary backend and to one or
+/// more replicas.
+///
+/// Reads are served by the primary (the wrapped backend) alone. A mutation
+/// runs on the primary first; only if it succeeds is it sent to the
+/// replicas, each of which is [`Sync`](ReplicaMode::Sync) or
+/// [`Queued`](ReplicaMode::Queued). Replicas can be any [`Fs`], so a
+/// local backend can be mirrored to a remote one. Mutations are serialized
+/// so every replica sees them in the primary's order.
+///
+/// A replica that fails to apply a mutation has diverged from the primary;
+/// the failure is kept as a [`Divergence`] and handled per the
+/// [`MirrorPolicy`]. An error from a queued replica can only be seen through
+/// [`divergences`](Mirrored::divergences), and under `FailFast` it blocks
+/// the next mutation.
+///
+/// `open_write` and `open_write_sized` writers replicate the complete
+/// contents as a single `write` when they are dropped.
+/// `create_temp_dir_in` creates the primary's chosen name on the replicas.
+///
+/// Only [`FsRead`](crate::FsRead), [`FsWrite`] and [`FsDir`] are implemented: mutations
+/// through other traits could not be replicated to a plain [`Fs`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsWrite, LayerExt, MirrorLayer, ReplicaMode};
+/// use std::path::Path;
+/// use std::sync::{Arc, Mutex};
+///
+/// let copies = Arc::new(Mutex::new(Vec::new()));
+/// let log = copies.clone();
+/// let replica = FsBuilder::new()
+///     .on_write(move |path, _| {
+///         log.lock().unwrap().push(path.to_path_buf());
+///         Ok(())
+///     })
+///     .build();
+///
+/// let fs = FsBuilder::new()
+///     .on_write(|_, _| Ok(()))
+///     .build()
+///     .layer(MirrorLayer::new().replica(replica, ReplicaMode::Queued));
+///
+/// fs.write(Path::new("/a.txt"), b"hello").unwrap();
+/// fs.flush_replicas();
+/// assert_eq!(*copies.lock().unwrap(), [Path::new("/a.txt")]);
+/// assert!(fs.divergences().is_empty());
+/// ```
+pub struct Mirrored<B> {
+    inner: B,
+    replicas: Arc<Replicas>,
+    order: Mutex<()>,
+}
+
+impl<B> Mirrored<B> {
+    /// The primary backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the primary backend, after the queued replicas caught up.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// The divergences recorded so far.
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.replicas
+            .divergences
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Remove and return the recorded divergences.
+    ///
+    /// Under [`MirrorPolicy::FailFast`] this acknowledges them and allows
+    /// mutations again; bring the replicas back in line first.
+    pub fn take_divergences(&self) -> Vec<Divergence> {
+        std::mem::take(
+            &mut *self
+                .replicas
+                .divergences
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        )
+    }
+
+    /// Wait until every queued replica has applied the mutations sent so far.
+    pub fn flush_replicas(&self) {
+        let waits: Vec<_> = self
+            .replicas
+            .replicas
+            .iter()
+            .filter_map(|replica| match replica {
+                Replica::Queued(queue) => {
+                    let (done, wait) = mpsc::channel();
+                    queue.send(Job::Flush(done));
+                    Some(wait)
+                }
+                Replica::Sync(_) => None,
+            })
+            .collect();
+        for wait in waits {
+            let _ = wait.recv();
+        }
+    }
+
+    /// Run `primary` and, if it succeeds, send `op` to the replicas.
+    fn mutate<T>(
+        &self,
+        op: FsOp,
+        primary: impl FnOnce(&B) -> Result<T, FsError>,
+    ) -> Result<T, FsError> {
+        let _order = self.order.lock().unwrap_or_else(|e| e.into_inner());
+        self.replicas.check(op.path())?;
+        let out = primary(&self.inner)?;
+        self.replicas.mirror(op)?;
+        Ok(out)
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for Mirrored<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mirrored")
+            .field("inner", &self.inner)
+            .field("replicas", &self.replicas.replicas.len())
+            .field("policy", &self.replicas.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+forward_fs_read!(Mirrored);
+
+impl<B: FsWrite> FsWrite for Mirrored<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let op = FsOp::Write {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+        };
+        self.mutate(op, |fs| fs.write(path, data))
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let op = FsOp::WriteWith {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+            options,
+        };
+        self.mutate(op, |fs| fs.write_with(path, data, options))
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        let op = FsOp::SetTimes {
+            path: path.to_path_buf(),
+            atime,
+            mtime,
+        };
+        self.mutate(op, |fs| fs.set_times(path, atime, mtime))
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let op = FsOp::Append {
+            path: path.to_path_buf(),
+            data: data.to_vec(),
+        };
+        self.mutate(op, |fs| fs.append(path, data))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveFile {
+            path: path.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.remove_file(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let op = FsOp::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.rename(from, to))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let op = FsOp::Copy {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.copy(from, to))
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let op = FsOp::CopyWith {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            options,
+        };
+        self.mutate(op, |fs| fs.copy_with(from, to, options))
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let op = FsOp::Truncate {
+            path: path.to_path_buf(),
+            size,
+        };
+        self.mutate(op, |fs| fs.truncate(path, size))
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.replicas.check(path)?;
+        let inner = self.inner.open_write(path)?;
+        Ok(Box::new(MirrorWriter::new(inner, path, &self.replicas)))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        self.replicas.check(path)?;
+        let inner = self.inner.open_write_sized(path, expected_len)?;
+        Ok(Box::new(MirrorWriter::new(inner, path, &self.replicas)))
+    }
+}
+
+impl<B: FsDir> FsDir for Mirrored<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::CreateDir {
+            path: path.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.create_dir(path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::CreateDirAll {
+            path: path.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.create_dir_all(path))
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let op = FsOp::CreateDirWith {
+            path: path.to_path_buf(),
+            perm,
+        };
+        self.mutate(op, |fs| fs.create_dir_with(path, perm))
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        let _order = self.order.lock().unwrap_or_else(|e| e.into_inner());
+        self.replicas.check(parent)?;
+        let path = self.inner.create_temp_dir_in(parent, prefix)?;
+        self.replicas
+            .mirror(FsOp::CreateDir { path: path.clone() })?;
+        Ok(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveDir {
+            path: path.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.remove_dir(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let op = FsOp::RemoveDirAll {
+            path: path.to_path_buf(),
+        };
+        self.mutate(op, |fs| fs.remove_dir_all(path))
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+/// Writer returned by `open_write`, replicating the contents once dropped.
+struct MirrorWriter {
+    inner: Box<dyn Write + Send>,
+    path: PathBuf,
+    replicas: Arc<Replicas>,
+    written: Vec<u8>,
+}
+
+impl MirrorWriter {
+    fn new(inner: Box<dyn Write + Send>, path: &Path, replicas: &Arc<Replicas>) -> Self {
+        Self {
+            inner,
+            path: path.to_path_buf(),
+            replicas: replicas.clone(),
+            written: Vec::new(),
+        }
+    }
+}
+
+impl Write for MirrorWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Drop for MirrorWriter {
+    fn drop(&mut self) {
+        if self.inner.flush().is_err() {
+            return;
+        }
+        let op = FsOp::Write {
+            path: std::mem::take(&mut self.path),
+            data: std::mem::take(&mut self.written),
+        };
+        // Failures are recorded as divergences; a drop can't report them.
+        let _ = self.replicas.mirror(op);
+    }
+}
+
+/// [`Layer`] that produces [`Mirrored`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, LayerExt, MirrorLayer, MirrorPolicy, ReplicaMode};
+///
+/// fn with_backups<B: Fs>(primary: B, local: impl Fs + 'static, remote: impl Fs + 'static) -> impl Fs {
+///     primary.layer(
+///         MirrorLayer::new()
+///             .replica(local, ReplicaMode::Sync)
+///             .replica(remote, ReplicaMode::Queued)
+///             .policy(MirrorPolicy::BestEffort),
+///     )
+/// }
+/// ```
+#[derive(Default)]
+pub struct MirrorLayer {
+    replicas: Vec<(Arc<dyn Fs>, ReplicaMode)>,
+    policy: MirrorPolicy,
+}
+
+impl MirrorLayer {
+    /// A layer with no replicas and [`MirrorPolicy::FailFast`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a replica, fed according to `mode`.
+    pub fn replica(mut self, replica: impl Fs + 'static, mode: ReplicaMode) -> Self {
+        self.replicas.push((Arc::new(replica), mode));
+        self
+    }
+
+    /// Set how replica failures are handled.
+    pub fn policy(mut self, policy: MirrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl fmt::Debug for MirrorLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modes: Vec<_> = self.replicas.iter().map(|(_, mode)| mode).collect();
+        f.debug_struct("MirrorLayer")
+            .field("replicas", &modes)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<B> Layer<B> for MirrorLayer {
+    type Backend = Mirrored<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        let divergences = Divergences::default();
+        let replicas = self
+            .replicas
+            .into_iter()
+            .enumerate()
+            .map(|(index, (replica, mode))| match mode {
+                ReplicaMode::Sync => Replica::Sync(replica),
+                ReplicaMode::Queued => {
+                    Replica::Queued(QueuedReplica::spawn(index, replica, divergences.clone()))
+                }
+            })
+            .collect();
+        Mirrored {
+            inner: backend,
+            replicas: Arc::new(Replicas {
+                replicas,
+                policy: self.policy,
+                divergences,
+            }),
+            order: Mutex::new(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+
+    type Log = Arc<Mutex<Vec<(PathBuf, Vec<u8>)>>>;
+
+    fn recording() -> (impl Fs, Log) {
+        let log = Log::default();
+        let writes = log.clone();
+        let fs = FsBuilder::new()
+            .on_write(move |path, data| {
+                writes
+                    .lock()
+                    .unwrap()
+                    .push((path.to_path_buf(), data.to_vec()));
+                Ok(())
+            })
+            .build();
+        (fs, log)
+    }
+
+    fn failing() -> impl Fs {
+        FsBuilder::new()
+            .on_write(|path, _| Err(FsError::permission_denied("write", path)))
+            .build()
+    }
+
+    #[test]
+    fn failed_primary_is_not_mirrored() {
+        let (replica, log) = recording();
+        let fs = failing().layer(MirrorLayer::new().replica(replica, ReplicaMode::Sync));
+
+        assert!(fs.write(Path::new("/a"), b"x").is_err());
+        assert!(log.lock().unwrap().is_empty());
+        assert!(fs.divergences().is_empty());
+    }
+
+    #[test]
+    fn fail_fast_blocks_until_acknowledged() {
+        let (primary, primary_log) = recording();
+        let fs = primary.layer(MirrorLayer::new().replica(failing(), ReplicaMode::Sync));
+
+        assert!(matches!(
+            fs.write(Path::new("/a"), b"x"),
+            Err(FsError::PermissionDenied { .. })
+        ));
+        assert!(matches!(
+            fs.write(Path::new("/b"), b"y"),
+            Err(FsError::Conflict { .. })
+        ));
+        assert_eq!(primary_log.lock().unwrap().len(), 1);
+
+        let taken = fs.take_divergences();
+        assert_eq!(taken.len(), 1);
+        assert_eq!((taken[0].replica, taken[0].operation), (0, "write"));
+        assert_eq!(taken[0].path, Path::new("/a"));
+        assert!(fs.divergences().is_empty());
+    }
+
+    #[test]
+    fn best_effort_keeps_going_across_modes() {
+        let (primary, _) = recording();
+        let (healthy, log) = recording();
+        let fs = primary.layer(
+            MirrorLayer::new()
+                .replica(failing(), ReplicaMode::Queued)
+                .replica(healthy, ReplicaMode::Queued)
+                .policy(MirrorPolicy::BestEffort),
+        );
+
+        fs.write(Path::new("/a"), b"1").unwrap();
+        fs.write(Path::new("/b"), b"2").unwrap();
+        fs.flush_replicas();
+
+        assert_eq!(log.lock().unwrap().len(), 2);
+        let divergences = fs.divergences();
+        assert_eq!(divergences.len(), 2);
+        assert!(divergences.iter().all(|d| d.replica == 0));
+    }
+
+    #[test]
+    fn streamed_writes_replicate_on_drop() {
+        let primary = FsBuilder::new()
+            .on_open_write(|_| Ok(Box::new(io::sink())))
+            .build();
+        let (replica, log) = recording();
+        let fs = primary.layer(MirrorLayer::new().replica(replica, ReplicaMode::Sync));
+
+        let mut writer = fs.open_write(Path::new("/big")).unwrap();
+        writer.write_all(b"part1,").unwrap();
+        writer.write_all(b"part2").unwrap();
+        assert!(log.lock().unwrap().is_empty());
+        drop(writer);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [(PathBuf::from("/big"), b"part1,part2".to_vec())]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! # Middleware
+//!
+//! Ready-made [`Layer`](crate::Layer)s provided by this crate.
+//!
+//! | Middleware | Layer | Purpose |
+//! |------------|-------|---------|
+//! | [`EnforceFlags`] | [`EnforceFlagsLayer`] | Enforce [`FileFlags`](crate::FileFlags) on any backend |
+//! | [`EnforcePermissions`] | [`EnforcePermissionsLayer`] | Enforce stored [`Permissions`](crate::Permissions), optionally for given [`Credentials`](crate::Credentials) |
+//! | [`DefaultPermissions`] | [`DefaultPermissionsLayer`] | Apply a [`CreateMode`](crate::CreateMode) to new entries |
+//! | [`Hooks`] | [`HookLayer`] | Run `before`/`after` callbacks around every core operation |
+//! | [`Protect`] | [`ProtectLayer`] | Make paths matching glob patterns read-only |
+//! | [`Namespace`] | [`NamespaceLayer`] | Confine callers to one directory, e.g. a tenant's |
+//! | [`StrictPaths`] | [`StrictPathsLayer`] | Reject relative, NUL-containing and root-escaping paths |
+//! | [`Scanned`] | [`ScanLayer`] | Reject written content flagged by an [`FsScan`] |
+//! | [`IoCounted`] | [`IoCountLayer`] | Maintain [`IoStats`](crate::IoStats) for any backend |
+//! | [`Deterministic`] | [`DeterministicLayer`] | List every directory sorted by name |
+//! | [`DryRun`] | [`DryRunLayer`] | Record each mutation as an [`FsOp`](crate::FsOp) instead of applying it |
+//! | [`Journaled`] | [`JournalLayer`] | Log each mutation to a write-ahead journal before applying it (`serde` feature) |
+//! | [`Mirrored`] | [`MirrorLayer`] | Apply every mutation to a primary and its replicas |
+//!
+//! Each middleware intercepts the operations it cares about and forwards all
+//! other trait methods to the wrapped backend unchanged, so wrapping never
+//! removes a capability. The exceptions are [`FsBranch`](crate::FsBranch),
+//! whose forks come from the wrapped backend and would bypass the
+//! middleware, [`Mirrored`], [`Journaled`] and [`DryRun`], which only
+//! implement [`Fs`](crate::Fs), and [`Namespace`], which only implements
+//! the path-based traits.
+
+mod forward;
+
+mod default_permissions;
+mod deterministic;
+mod dry_run;
+mod enforce_permissions;
+mod file_flags;
+mod hooks;
+mod io_count;
+#[cfg(feature = "serde")]
+mod journal;
+mod mirror;
+mod namespace;
+mod protect;
+mod scan;
+mod strict_paths;
+
+pub use default_permissions::{DefaultPermissions, DefaultPermissionsLayer};
+pub use deterministic::{Deterministic, DeterministicLayer};
+pub use dry_run::{DryRun, DryRunLayer};
+pub use enforce_permissions::{EnforcePermissions, EnforcePermissionsLayer};
+pub use file_flags::{EnforceFlags, EnforceFlagsLayer};
+pub use hooks::{HookLayer, Hooks};
+pub use io_count::{IoCountLayer, IoCounted};
+#[cfg(feature = "serde")]
+pub use journal::{JournalLayer, Journaled, Replayed};
+pub use mirror::{Divergence, MirrorLayer, MirrorPolicy, Mirrored, ReplicaMode};
+pub use namespace::{Namespace, NamespaceLayer};
+pub use protect::{Protect, ProtectLayer};
+pub use scan::{FsScan, ScanLayer, ScanVerdict, Scanned};
+pub use strict_paths::{StrictPaths, StrictPathsLayer};
@@ -0,0 +1,637 @@
+//! Per-tenant path prefixing.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::path_resolver::normalize;
+use crate::{
+    CopyOptions, Credentials, DirEntry, FsDir, FsError, FsLink, FsPermissions, FsRead, FsStats,
+    FsSync, FsWrite, FsXattr, IoStats, Layer, Metadata, MetadataLite, MetadataMask, Permissions,
+    ReadDirIter, ReadDirOptions, StatFs, UsageReport, VirtualComponent, VirtualComponents,
+    WriteOptions,
+};
+
+/// Middleware confining callers to one directory of the wrapped backend.
+///
+/// Every path is normalized (`..` stops at `/`) and moved under the
+/// namespace root, so `/notes.txt` becomes `/tenants/acme/notes.txt` on the
+/// backend. Paths coming back — listing entries, temp dirs, symlink targets
+/// and the paths in errors — are moved back out, so callers never see the
+/// prefix. A backend path outside the root is reported as `/`, so errors
+/// can't reveal the rest of the backend's tree. One backend can serve many
+/// tenants, each seeing its own `/`.
+///
+/// Symlink targets stay inside the namespace: absolute targets are
+/// prefixed like any other path, and relative targets that climb with `..`
+/// are resolved against the link's directory and stored as absolute paths.
+/// The namespace root itself can't be removed or renamed.
+///
+/// Only the path-based [`FsRead`], [`FsWrite`], [`FsDir`], [`FsLink`],
+/// [`FsPermissions`], [`FsStats`], [`FsSync`] and [`FsXattr`] are
+/// implemented. Inode numbers and handles aren't confined to a namespace,
+/// so traits built on them are not forwarded. [`FsStats::statfs`] and
+/// [`FsStats::io_stats`] still describe the whole backend. The root must
+/// exist on the wrapped backend before use.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsDir, FsError, Layer, NamespaceLayer};
+/// use std::path::Path;
+///
+/// fn serve_tenant<B: Fs>(backend: B, id: &str) -> Result<impl Fs, FsError> {
+///     // `/x` for this tenant is `/tenants/<id>/x` on `backend`
+///     let fs = NamespaceLayer::tenant(id)?.layer(backend);
+///     fs.create_dir_all(Path::new("/inbox"))?;
+///     Ok(fs)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Namespace<B> {
+    inner: B,
+    root: PathBuf,
+}
+
+impl<B> Namespace<B> {
+    /// Wrap a backend, confining callers to `root`.
+    pub fn new(inner: B, root: impl AsRef<Path>) -> Self {
+        Self {
+            inner,
+            root: normalize(root.as_ref()),
+        }
+    }
+
+    /// The directory of the wrapped backend callers see as `/`.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Backend path for the caller's `path`.
+    fn to_inner(&self, path: &Path) -> PathBuf {
+        into_root(&self.root, path)
+    }
+
+    /// Backend path for `path`, denying `operation` if it is the root.
+    fn to_inner_entry(&self, path: &Path, operation: &'static str) -> Result<PathBuf, FsError> {
+        let inner = self.to_inner(path);
+        if inner == self.root {
+            return Err(FsError::PermissionDenied {
+                path: path.to_path_buf(),
+                operation,
+            });
+        }
+        Ok(inner)
+    }
+
+    /// Caller's path for the backend's `path`.
+    fn to_outer(&self, path: &Path) -> PathBuf {
+        out_of_root(&self.root, path)
+    }
+
+    /// `result` with the paths of its error as the caller sees them.
+    fn outer<T>(&self, result: Result<T, FsError>) -> Result<T, FsError> {
+        result.map_err(|e| e.map_paths(|p| self.to_outer(p)))
+    }
+
+    /// Backend target for a symlink at the caller's `link`.
+    fn to_inner_target(&self, target: &Path, link: &Path) -> PathBuf {
+        if target.has_root() {
+            return self.to_inner(target);
+        }
+        let climbs =
+            VirtualComponents::new(target).any(|c| matches!(c, VirtualComponent::ParentDir));
+        if !climbs {
+            return target.to_path_buf();
+        }
+        let link = normalize(link);
+        let dir = link.parent().unwrap_or_else(|| Path::new("/"));
+        self.to_inner(&Path::new("/").join(dir).join(target))
+    }
+
+    /// Listing with entry paths and symlink targets as the caller sees them.
+    fn outer_listing(&self, mut entries: ReadDirIter) -> ReadDirIter {
+        let root = self.root.clone();
+        ReadDirIter::from_detailed(std::iter::from_fn(move || {
+            let item = entries.next_detailed()?;
+            Some(
+                item.map(|entry| outer_entry(&root, entry))
+                    .map_err(|mut e| {
+                        e.source = e.source.map_paths(|p| out_of_root(&root, p));
+                        e
+                    }),
+            )
+        }))
+    }
+}
+
+/// `path` normalized and moved under `root`.
+fn into_root(root: &Path, path: &Path) -> PathBuf {
+    let mut inner = root.to_path_buf();
+    for component in VirtualComponents::new(&normalize(path)) {
+        if let VirtualComponent::Normal(name) = component {
+            inner.push(name);
+        }
+    }
+    inner
+}
+
+/// `path` relative to `root`, as an absolute path; `/` if it isn't under
+/// `root`. Relative paths (e.g. symlink targets) are returned unchanged.
+fn out_of_root(root: &Path, path: &Path) -> PathBuf {
+    if !path.has_root() {
+        return path.to_path_buf();
+    }
+    match normalize(path).strip_prefix(root) {
+        Ok(rest) => Path::new("/").join(rest),
+        Err(_) => PathBuf::from("/"),
+    }
+}
+
+/// `entry` with its path and absolute symlink target moved out of `root`.
+fn outer_entry(root: &Path, mut entry: DirEntry) -> DirEntry {
+    entry.path = out_of_root(root, &entry.path);
+    if let Some(target) = &entry.symlink_target {
+        if target.has_root() {
+            entry.symlink_target = Some(out_of_root(root, target));
+        }
+    }
+    entry
+}
+
+impl<B: FsRead> FsRead for Namespace<B> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        self.outer(self.inner.read(&self.to_inner(path)))
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        self.outer(self.inner.read_to_string(&self.to_inner(path)))
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        self.outer(self.inner.read_range(&self.to_inner(path), offset, len))
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.outer(self.inner.exists(&self.to_inner(path)))
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        self.outer(self.inner.exists_fresh(&self.to_inner(path)))
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.outer(self.inner.metadata(&self.to_inner(path)))
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        self.outer(self.inner.metadata_lite(&self.to_inner(path)))
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        self.outer(self.inner.metadata_mask(&self.to_inner(path), mask))
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.outer(self.inner.metadata_with_tag(&self.to_inner(path)))
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        self.outer(self.inner.open_read(&self.to_inner(path)))
+    }
+}
+
+impl<B: FsWrite> FsWrite for Namespace<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.outer(self.inner.write(&self.to_inner(path), data))
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.outer(self.inner.write_with(&self.to_inner(path), data, options))
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.outer(self.inner.set_times(&self.to_inner(path), atime, mtime))
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.outer(self.inner.append(&self.to_inner(path), data))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.remove_file(&self.to_inner(path)))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let from = self.to_inner_entry(from, "rename")?;
+        let to = self.to_inner_entry(to, "rename")?;
+        self.outer(self.inner.rename(&from, &to))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.copy(&self.to_inner(from), &self.to_inner(to)))
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.outer(
+            self.inner
+                .copy_with(&self.to_inner(from), &self.to_inner(to), options),
+        )
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.outer(self.inner.truncate(&self.to_inner(path), size))
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.outer(self.inner.open_write(&self.to_inner(path)))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        self.outer(
+            self.inner
+                .open_write_sized(&self.to_inner(path), expected_len),
+        )
+    }
+}
+
+impl<B: FsDir> FsDir for Namespace<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        let entries = self.outer(self.inner.read_dir(&self.to_inner(path)))?;
+        Ok(self.outer_listing(entries))
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        let entries = self.outer(self.inner.read_dir_with(&self.to_inner(path), options))?;
+        Ok(self.outer_listing(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.create_dir(&self.to_inner(path)))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.create_dir_all(&self.to_inner(path)))
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.outer(self.inner.create_dir_with(&self.to_inner(path), perm))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        let path = self.to_inner_entry(path, "remove_dir")?;
+        self.outer(self.inner.remove_dir(&path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let path = self.to_inner_entry(path, "remove_dir_all")?;
+        self.outer(self.inner.remove_dir_all(&path))
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        let dir = self.outer(
+            self.inner
+                .create_temp_dir_in(&self.to_inner(parent), prefix),
+        )?;
+        Ok(self.to_outer(&dir))
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.outer(self.inner.dir_len(&self.to_inner(path)))
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.outer(self.inner.is_dir_empty(&self.to_inner(path)))
+    }
+}
+
+impl<B: FsLink> FsLink for Namespace<B> {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        let target = self.to_inner_target(target, link);
+        self.outer(self.inner.symlink(&target, &self.to_inner(link)))
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        self.outer(
+            self.inner
+                .hard_link(&self.to_inner(original), &self.to_inner(link)),
+        )
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        let target = self.outer(self.inner.read_link(&self.to_inner(path)))?;
+        if target.has_root() {
+            Ok(self.to_outer(&target))
+        } else {
+            Ok(target)
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.outer(self.inner.symlink_metadata(&self.to_inner(path)))
+    }
+}
+
+impl<B: FsPermissions> FsPermissions for Namespace<B> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.outer(self.inner.set_permissions(&self.to_inner(path), perm))
+    }
+}
+
+impl<B: FsStats> FsStats for Namespace<B> {
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        self.inner.statfs()
+    }
+
+    fn usage(&self, path: &Path) -> Result<UsageReport, FsError> {
+        self.outer(self.inner.usage(&self.to_inner(path)))
+    }
+
+    fn io_stats(&self) -> IoStats {
+        self.inner.io_stats()
+    }
+}
+
+impl<B: FsSync> FsSync for Namespace<B> {
+    fn sync(&self) -> Result<(), FsError> {
+        self.inner.sync()
+    }
+
+    fn fsync(&self, path: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.fsync(&self.to_inner(path)))
+    }
+
+    fn fdatasync(&self, path: &Path) -> Result<(), FsError> {
+        self.outer(self.inner.fdatasync(&self.to_inner(path)))
+    }
+
+    fn barrier(&self) -> Result<(), FsError> {
+        self.inner.barrier()
+    }
+}
+
+impl<B: FsXattr> FsXattr for Namespace<B> {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        self.outer(self.inner.get_xattr(&self.to_inner(path), name))
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        self.outer(self.inner.set_xattr(&self.to_inner(path), name, value))
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        self.outer(self.inner.remove_xattr(&self.to_inner(path), name))
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        self.outer(self.inner.list_xattr(&self.to_inner(path)))
+    }
+}
+
+/// [`Layer`] producing [`Namespace`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Credentials, Fs, LayerExt, NamespaceLayer};
+///
+/// // Each user gets `/tenants/<uid>` as their `/`
+/// fn for_user<B: Fs>(backend: B, credentials: &Credentials) -> impl Fs {
+///     backend.layer(NamespaceLayer::for_credentials(credentials))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NamespaceLayer {
+    root: PathBuf,
+}
+
+impl NamespaceLayer {
+    /// Layer confining callers to `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: normalize(root.as_ref()),
+        }
+    }
+
+    /// Layer confining callers to `/tenants/<id>`.
+    ///
+    /// # Errors
+    ///
+    /// [`FsError::InvalidData`] unless `id` is a single file name: not
+    /// empty, not `.` or `..`, and free of separators and NUL bytes.
+    pub fn tenant(id: &str) -> Result<Self, FsError> {
+        if id.is_empty() || id == "." || id == ".." || id.contains(['/', '\\', '\0']) {
+            return Err(FsError::InvalidData {
+                path: PathBuf::from(id),
+                details: "tenant id must be a single file name".into(),
+            });
+        }
+        Ok(Self::new(Path::new("/tenants").join(id)))
+    }
+
+    /// Layer confining the user `credentials` names to `/tenants/<uid>`.
+    pub fn for_credentials(credentials: &Credentials) -> Self {
+        Self::new(format!("/tenants/{}", credentials.uid))
+    }
+}
+
+impl<B> Layer<B> for NamespaceLayer {
+    type Backend = Namespace<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Namespace {
+            inner: backend,
+            root: self.root,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileType, FsBuilder, LayerExt};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn paths_are_moved_under_the_root() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let fs = FsBuilder::new()
+            .on_read(move |path| {
+                log.lock().unwrap().push(path.to_path_buf());
+                Ok(Vec::new())
+            })
+            .build()
+            .layer(NamespaceLayer::tenant("acme").unwrap());
+
+        fs.read(Path::new("/notes.txt")).unwrap();
+        fs.read(Path::new("/../../etc/passwd")).unwrap();
+        fs.read(Path::new("/a/./b/../c")).unwrap();
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                PathBuf::from("/tenants/acme/notes.txt"),
+                PathBuf::from("/tenants/acme/etc/passwd"),
+                PathBuf::from("/tenants/acme/a/c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn listings_and_errors_hide_the_root() {
+        let fs = FsBuilder::new()
+            .on_read_dir(|path| {
+                Ok(ReadDirIter::from_vec(vec![Ok(DirEntry {
+                    name: "a.txt".into(),
+                    path: path.join("a.txt"),
+                    file_type: FileType::File,
+                    ..DirEntry::default()
+                })]))
+            })
+            .on_read(|path| Err(FsError::not_found("read", path)))
+            .build()
+            .layer(NamespaceLayer::new("/tenants/acme"));
+
+        let entries = fs
+            .read_dir(Path::new("/docs"))
+            .unwrap()
+            .collect_all()
+            .unwrap();
+        assert_eq!(entries[0].path, Path::new("/docs/a.txt"));
+
+        let err = fs.read(Path::new("/missing")).unwrap_err();
+        assert_eq!(err.path(), Some(Path::new("/missing")));
+    }
+
+    #[test]
+    fn errors_outside_the_root_are_redacted() {
+        let fs = FsBuilder::new()
+            .on_read(|path| {
+                let escaped = if path.ends_with("climb") {
+                    path.join("../../../other/secret")
+                } else {
+                    PathBuf::from("/srv/backend/secret")
+                };
+                Err(FsError::not_found("read", escaped))
+            })
+            .build()
+            .layer(NamespaceLayer::new("/tenants/acme"));
+
+        let err = fs.read(Path::new("/missing")).unwrap_err();
+        assert_eq!(err.path(), Some(Path::new("/")));
+        let err = fs.read(Path::new("/climb")).unwrap_err();
+        assert_eq!(err.path(), Some(Path::new("/")));
+    }
+
+    #[test]
+    fn the_root_cannot_be_removed() {
+        let fs = FsBuilder::new()
+            .on_remove_dir_all(|_| Ok(()))
+            .on_rename(|_, _| Ok(()))
+            .build()
+            .layer(NamespaceLayer::new("/tenants/acme"));
+        let is_denied =
+            |result: Result<(), FsError>| matches!(result, Err(FsError::PermissionDenied { .. }));
+
+        assert!(is_denied(fs.remove_dir_all(Path::new("/"))));
+        assert!(is_denied(fs.remove_dir_all(Path::new("/a/.."))));
+        assert!(is_denied(fs.rename(Path::new("/"), Path::new("/x"))));
+        fs.remove_dir_all(Path::new("/a")).unwrap();
+    }
+
+    #[test]
+    fn tenant_ids_must_be_file_names() {
+        for id in ["", ".", "..", "a/b", "a\\b", "a\0b"] {
+            assert!(NamespaceLayer::tenant(id).is_err(), "{id:?}");
+        }
+        let credentials = Credentials::new(1000, 1000);
+        assert_eq!(
+            NamespaceLayer::for_credentials(&credentials)
+                .layer(())
+                .root(),
+            Path::new("/tenants/1000")
+        );
+    }
+
+    /// Symlinks only, keyed by link path.
+    #[derive(Default)]
+    struct Links(Mutex<HashMap<PathBuf, PathBuf>>);
+
+    impl FsLink for Links {
+        fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(link.to_path_buf(), target.to_path_buf());
+            Ok(())
+        }
+
+        fn hard_link(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Err(FsError::NotSupported {
+                operation: "hard_link",
+            })
+        }
+
+        fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| FsError::not_found("read_link", path))
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            Err(FsError::not_found("symlink_metadata", path))
+        }
+    }
+
+    #[test]
+    fn symlink_targets_stay_inside() {
+        let fs = Namespace::new(Links::default(), "/tenants/acme");
+        fs.symlink(Path::new("/etc/passwd"), Path::new("/abs"))
+            .unwrap();
+        fs.symlink(Path::new("v1.2.0"), Path::new("/current"))
+            .unwrap();
+        fs.symlink(Path::new("../../../etc"), Path::new("/a/up"))
+            .unwrap();
+
+        let stored = |link: &str| fs.inner().read_link(Path::new(link)).unwrap();
+        assert_eq!(
+            stored("/tenants/acme/abs"),
+            Path::new("/tenants/acme/etc/passwd")
+        );
+        assert_eq!(stored("/tenants/acme/current"), Path::new("v1.2.0"));
+        assert_eq!(stored("/tenants/acme/a/up"), Path::new("/tenants/acme/etc"));
+
+        assert_eq!(
+            fs.read_link(Path::new("/abs")).unwrap(),
+            Path::new("/etc/passwd")
+        );
+        assert_eq!(
+            fs.read_link(Path::new("/current")).unwrap(),
+            Path::new("v1.2.0")
+        );
+        assert_eq!(fs.read_link(Path::new("/a/up")).unwrap(), Path::new("/etc"));
+    }
+}
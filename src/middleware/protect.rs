@@ -0,0 +1,791 @@
+//! Read-only paths selected by glob patterns.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_handle_debug, forward_fs_lease, forward_fs_lock,
+    forward_fs_read, forward_fs_seek, forward_fs_sequence, forward_fs_stats, forward_fs_sync,
+};
+use crate::path_resolver::normalize;
+use crate::{
+    CopyOptions, DirEntry, FileFlags, FileType, FsDir, FsError, FsExpiry, FsFileFlags, FsHandles,
+    FsInode, FsLayers, FsLink, FsPermissions, FsTiering, FsWrite, FsXattr, Handle, Layer, LayerId,
+    LayerInfo, Metadata, OpenFlags, Permissions, ReadDirIter, ReadDirOptions, SetAttr,
+    StorageClass, VirtualComponent, VirtualComponents, WriteOptions,
+};
+
+/// One compiled pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Glob {
+    /// Whether the pattern matches whole paths rather than names.
+    anchored: bool,
+    /// `/`-separated segments; `**` is a segment of its own.
+    segments: Vec<Vec<char>>,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Self {
+        Self {
+            anchored: pattern.contains('/'),
+            segments: pattern
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| segment.chars().collect())
+                .collect(),
+        }
+    }
+
+    fn matches(&self, names: &[Vec<char>]) -> bool {
+        if self.anchored {
+            match_segments(&self.segments, names)
+        } else {
+            names.last().map_or(false, |name| {
+                match_segments(&self.segments, std::slice::from_ref(name))
+            })
+        }
+    }
+}
+
+/// Match path names against pattern segments, `**` spanning any number of
+/// names.
+fn match_segments(segments: &[Vec<char>], names: &[Vec<char>]) -> bool {
+    match_wildcards(
+        segments,
+        names,
+        |segment| segment[..] == ['*', '*'],
+        |segment, name| match_name(segment, name),
+    )
+}
+
+/// Match one name against a segment with `*` and `?` wildcards.
+fn match_name(pattern: &[char], name: &[char]) -> bool {
+    match_wildcards(pattern, name, |&p| p == '*', |&p, &c| p == '?' || p == c)
+}
+
+/// Match `items` against `pattern`, where a star element spans any number of
+/// items and every other element matches exactly one.
+///
+/// Only the most recent star is retried on a mismatch: a later star can
+/// absorb anything an earlier one could, so the matcher never backtracks
+/// further and stays within `pattern.len() * items.len()` steps.
+fn match_wildcards<P, T>(
+    pattern: &[P],
+    items: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches_one: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // Pattern index after the last star, and the item index it resumes at
+    let mut star = None;
+    while i < items.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            p += 1;
+            star = Some((p, i));
+        } else if p < pattern.len() && matches_one(&pattern[p], &items[i]) {
+            p += 1;
+            i += 1;
+        } else if let Some((after_star, resume)) = star {
+            // Let the star swallow one more item and try again
+            p = after_star;
+            i = resume + 1;
+            star = Some((after_star, i));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(is_star)
+}
+
+/// Middleware making paths that match glob patterns read-only.
+///
+/// Mutations of a protected path fail with [`FsError::PermissionDenied`]
+/// before reaching the backend; everything else stays writable.
+///
+/// | Pattern | Matches |
+/// |---------|---------|
+/// | `*.lock` | Any entry named `*.lock`, at any depth (no `/`: matched against the name) |
+/// | `/etc/**` | `/etc` and everything below it (`**` spans zero or more names) |
+/// | `/data/*/config.toml` | `config.toml` one level below `/data` (`*` and `?` stay within a name) |
+///
+/// Patterns containing `/` are matched against the whole path from the
+/// root, after `.` and `..` are resolved lexically. Symlinks are not
+/// followed, so a link from outside into a protected tree is writable
+/// through; combine with [`FsPath`](crate::FsPath) resolution where that
+/// matters.
+///
+/// | Operation | Denied when |
+/// |-----------|-------------|
+/// | Write, append, truncate, `open` for writing, `set_permissions`, `set_attr`, xattr and flag changes | the path is protected |
+/// | Create, remove, `symlink`, `mkfifo` | the new or removed path is protected |
+/// | `create_dir_all` | the path or any missing ancestor it would create is protected |
+/// | `hard_link` | the link or the original is protected |
+/// | `rename` | anything at or inside either path is protected |
+/// | `set_storage_class`, `set_expiry`, `clear_expiry`, `copy_up` | the path is protected |
+/// | `remove_dir_all` | anything in the tree is protected |
+///
+/// All other traits are forwarded unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsDir, FsError, FsWrite, Layer, ProtectLayer};
+/// use std::path::Path;
+///
+/// fn guarded<B: FsWrite + FsDir>(backend: B) -> Result<(), FsError> {
+///     let fs = ProtectLayer::new(["/etc/**", "*.lock"]).layer(backend);
+///     fs.write(Path::new("/data/out.txt"), b"ok")?;
+///
+///     assert!(matches!(
+///         fs.write(Path::new("/data/Cargo.lock"), b"x"),
+///         Err(FsError::PermissionDenied { .. })
+///     ));
+///     assert!(fs.is_protected(Path::new("/etc/hosts")));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Protect<B> {
+    inner: B,
+    globs: Vec<Glob>,
+}
+
+impl<B> Protect<B> {
+    /// Wrap a backend, protecting paths that match any of `patterns`.
+    pub fn new<I>(inner: B, patterns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self {
+            inner,
+            globs: compile(patterns),
+        }
+    }
+
+    /// Returns `true` if `path` matches one of the patterns.
+    pub fn is_protected(&self, path: &Path) -> bool {
+        let normalized = normalize(path);
+        let names: Vec<Vec<char>> = VirtualComponents::new(&normalized)
+            .filter_map(|component| match component {
+                VirtualComponent::Normal(name) => Some(name.to_string_lossy().chars().collect()),
+                _ => None,
+            })
+            .collect();
+        self.globs.iter().any(|glob| glob.matches(&names))
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Deny `operation` if `path` is protected.
+    fn check(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        if self.is_protected(path) {
+            return Err(FsError::PermissionDenied {
+                path: path.to_path_buf(),
+                operation,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<B: FsDir> Protect<B> {
+    /// Deny `operation` if `path` or anything below it is protected.
+    fn check_tree(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check(path, operation)?;
+        let entries = match self.inner.read_dir(path) {
+            Ok(entries) => entries,
+            Err(FsError::NotFound { .. } | FsError::NotADirectory { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type == FileType::Directory {
+                self.check_tree(&entry.path, operation)?;
+            } else {
+                self.check(&entry.path, operation)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deny `operation` if `path` or any ancestor it would create is protected.
+    fn check_chain(&self, path: &Path, operation: &'static str) -> Result<(), FsError> {
+        self.check(path, operation)?;
+        for ancestor in path.ancestors().skip(1) {
+            // Only protected names need a lookup, and only a missing one is denied
+            if ancestor.as_os_str().is_empty() || !self.is_protected(ancestor) {
+                continue;
+            }
+            match self.inner.read_dir(ancestor) {
+                Err(FsError::NotFound { .. }) => self.check(ancestor, operation)?,
+                Ok(_) | Err(FsError::NotADirectory { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compile glob patterns.
+fn compile<I>(patterns: I) -> Vec<Glob>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    patterns
+        .into_iter()
+        .map(|pattern| Glob::new(pattern.as_ref()))
+        .collect()
+}
+
+forward_fs_read!(Protect);
+
+impl<B: FsWrite + FsDir> FsWrite for Protect<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check(path, "write")?;
+        self.inner.write(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check(path, "append")?;
+        self.inner.append(path, data)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "remove_file")?;
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check_tree(from, "rename")?;
+        self.check_tree(to, "rename")?;
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.check(to, "copy")?;
+        self.inner.copy(from, to)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.check(path, "truncate")?;
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check(path, "open_write")?;
+        self.inner.open_write(path)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+        self.check(path, "open_write_sized")?;
+        self.inner.open_write_sized(path, expected_len)
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.check(to, "copy_with")?;
+        self.inner.copy_with(from, to, options)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.check(path, "write_with")?;
+        self.inner.write_with(path, data, options)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.check(path, "set_times")?;
+        self.inner.set_times(path, atime, mtime)
+    }
+}
+
+impl<B: FsDir> FsDir for Protect<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "create_dir")?;
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.check_chain(path, "create_dir_all")?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "remove_dir")?;
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        self.check_tree(path, "remove_dir_all")?;
+        self.inner.remove_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check(path, "create_dir_with")?;
+        self.inner.create_dir_with(path, perm)
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        self.check(parent, "create_temp_dir_in")?;
+        // The name is only known once created; take it back if it matches
+        let dir = self.inner.create_temp_dir_in(parent, prefix)?;
+        if let Err(e) = self.check(&dir, "create_temp_dir_in") {
+            let _ = self.inner.remove_dir(&dir);
+            return Err(e);
+        }
+        Ok(dir)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        self.inner.is_dir_empty(path)
+    }
+}
+
+impl<B: FsLink> FsLink for Protect<B> {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        self.check(link, "symlink")?;
+        self.inner.symlink(target, link)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        self.check(original, "hard_link")?;
+        self.check(link, "hard_link")?;
+        self.inner.hard_link(original, link)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        self.inner.read_link(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.inner.symlink_metadata(path)
+    }
+}
+
+impl<B: FsPermissions> FsPermissions for Protect<B> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check(path, "set_permissions")?;
+        self.inner.set_permissions(path, perm)
+    }
+}
+
+impl<B: FsHandles> FsHandles for Protect<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        let writes =
+            flags.write || flags.append || flags.truncate || flags.create || flags.create_new;
+        if writes {
+            self.check(path, "open")?;
+        }
+        self.inner.open(path, flags)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.check(path, "link_handle")?;
+        self.inner.link_handle(handle, path)
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.check(path, "mkfifo")?;
+        self.inner.mkfifo(path, perm)
+    }
+}
+
+impl<B: FsInode> FsInode for Protect<B> {
+    fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+        self.inner.path_to_inode(path)
+    }
+
+    fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+        self.inner.inode_to_path(inode)
+    }
+
+    fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+        self.inner.lookup(parent_inode, name)
+    }
+
+    fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+        self.inner.metadata_by_inode(inode)
+    }
+
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        self.inner.read_dir_inode(inode, offset)
+    }
+
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        if !attr.is_empty() {
+            self.check(&self.inner.inode_to_path(inode)?, "set_attr")?;
+        }
+        self.inner.set_attr(inode, attr)
+    }
+}
+
+impl<B: FsXattr> FsXattr for Protect<B> {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        self.check(path, "set_xattr")?;
+        self.inner.set_xattr(path, name, value)
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        self.check(path, "remove_xattr")?;
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        self.inner.list_xattr(path)
+    }
+}
+
+impl<B: FsFileFlags> FsFileFlags for Protect<B> {
+    fn set_flags(&self, path: &Path, flags: FileFlags) -> Result<(), FsError> {
+        self.check(path, "set_flags")?;
+        self.inner.set_flags(path, flags)
+    }
+
+    fn get_flags(&self, path: &Path) -> Result<FileFlags, FsError> {
+        self.inner.get_flags(path)
+    }
+}
+
+impl<B: FsTiering> FsTiering for Protect<B> {
+    fn set_storage_class(&self, path: &Path, class: StorageClass) -> Result<(), FsError> {
+        self.check(path, "set_storage_class")?;
+        self.inner.set_storage_class(path, class)
+    }
+
+    fn storage_class(&self, path: &Path) -> Result<StorageClass, FsError> {
+        self.inner.storage_class(path)
+    }
+}
+
+impl<B: FsExpiry> FsExpiry for Protect<B> {
+    fn set_expiry(&self, path: &Path, at: SystemTime) -> Result<(), FsError> {
+        self.check(path, "set_expiry")?;
+        self.inner.set_expiry(path, at)
+    }
+
+    fn clear_expiry(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "clear_expiry")?;
+        self.inner.clear_expiry(path)
+    }
+
+    fn expiry(&self, path: &Path) -> Result<Option<SystemTime>, FsError> {
+        self.inner.expiry(path)
+    }
+}
+
+impl<B: FsLayers> FsLayers for Protect<B> {
+    fn layers(&self) -> Vec<LayerInfo> {
+        self.inner.layers()
+    }
+
+    fn which_layer(&self, path: &Path) -> Result<LayerId, FsError> {
+        self.inner.which_layer(path)
+    }
+
+    fn copy_up(&self, path: &Path) -> Result<(), FsError> {
+        self.check(path, "copy_up")?;
+        self.inner.copy_up(path)
+    }
+}
+
+forward_fs_stats!(Protect);
+forward_fs_sync!(Protect);
+forward_fs_lock!(Protect);
+forward_fs_handle_debug!(Protect);
+forward_fs_seek!(Protect);
+forward_fs_lease!(Protect);
+forward_fs_sequence!(Protect);
+forward_fs_cache_control!(Protect);
+
+/// [`Layer`] producing [`Protect`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, LayerExt, ProtectLayer};
+///
+/// fn keep_lockfiles<B: Fs>(backend: B) -> impl Fs {
+///     backend.layer(ProtectLayer::new(["*.lock"]))
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProtectLayer {
+    globs: Vec<Glob>,
+}
+
+impl ProtectLayer {
+    /// Layer protecting paths that match any of `patterns`.
+    pub fn new<I>(patterns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self {
+            globs: compile(patterns),
+        }
+    }
+}
+
+impl<B> Layer<B> for ProtectLayer {
+    type Backend = Protect<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Protect {
+            inner: backend,
+            globs: self.globs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+
+    fn protected(patterns: &[&str], path: &str) -> bool {
+        Protect::new((), patterns).is_protected(Path::new(path))
+    }
+
+    #[test]
+    fn patterns_follow_glob_rules() {
+        assert!(protected(&["*.lock"], "/Cargo.lock"));
+        assert!(protected(&["*.lock"], "/a/b/yarn.lock"));
+        assert!(!protected(&["*.lock"], "/a.lock/file"));
+
+        assert!(protected(&["/etc/**"], "/etc"));
+        assert!(protected(&["/etc/**"], "/etc/ssh/sshd_config"));
+        assert!(!protected(&["/etc/**"], "/etcetera"));
+
+        assert!(protected(&["/data/*/config.?oml"], "/data/app/config.toml"));
+        assert!(!protected(
+            &["/data/*/config.toml"],
+            "/data/a/b/config.toml"
+        ));
+        assert!(protected(&["/**/secret"], "/secret"));
+        assert!(protected(&["/**/secret"], "/a/b/secret"));
+        assert!(protected(&["/a/**/b/**/c"], "/a/x/b/y/b/c"));
+        assert!(!protected(&["/a/**/b/**/c"], "/a/x/c/b"));
+        assert!(protected(&["*a*b"], "/xaab"));
+        assert!(!protected(&["*a*b"], "/xaba"));
+    }
+
+    #[test]
+    fn many_wildcards_match_in_polynomial_time() {
+        // Exponential with naive backtracking
+        let name = format!("/{}", "a".repeat(64));
+        assert!(!protected(&[&format!("{}b", "*a".repeat(32))], &name));
+        let path = "/a".repeat(64);
+        assert!(!protected(&[&format!("{}/b", "/**/a".repeat(32))], &path));
+        assert!(protected(&[&"/**/a".repeat(32)], &path));
+    }
+
+    #[test]
+    fn paths_are_normalized_before_matching() {
+        assert!(protected(&["/etc/**"], "/tmp/../etc/passwd"));
+        assert!(protected(&["/etc/passwd"], "/etc/./passwd"));
+    }
+
+    #[test]
+    fn mutations_of_protected_paths_are_denied() {
+        let fs = FsBuilder::new()
+            .on_write(|_, _| Ok(()))
+            .on_remove_file(|_| Ok(()))
+            .on_rename(|_, _| Ok(()))
+            .on_read_dir(|path| {
+                let entry = |name: &str, file_type| {
+                    Ok(DirEntry {
+                        name: name.into(),
+                        path: path.join(name),
+                        file_type,
+                        ..DirEntry::default()
+                    })
+                };
+                Ok(ReadDirIter::from_vec(match path.to_str() {
+                    Some("/project") => vec![entry("src", FileType::Directory)],
+                    Some("/project/src") => vec![entry("Cargo.lock", FileType::File)],
+                    _ => Vec::new(),
+                }))
+            })
+            .on_remove_dir_all(|_| Ok(()))
+            .build()
+            .layer(ProtectLayer::new(["*.lock"]));
+        let is_denied =
+            |result: Result<(), FsError>| matches!(result, Err(FsError::PermissionDenied { .. }));
+
+        fs.write(Path::new("/notes.txt"), b"x").unwrap();
+        assert!(is_denied(fs.write(Path::new("/Cargo.lock"), b"x")));
+        assert!(is_denied(fs.remove_file(Path::new("/Cargo.lock"))));
+        assert!(is_denied(
+            fs.rename(Path::new("/notes.txt"), Path::new("/Cargo.lock"))
+        ));
+        // Protected entries inside a directory keep it in place
+        assert!(is_denied(
+            fs.rename(Path::new("/project"), Path::new("/moved"))
+        ));
+        assert!(is_denied(fs.remove_dir_all(Path::new("/project"))));
+        fs.remove_dir_all(Path::new("/other")).unwrap();
+        // Nor can a directory be moved over them
+        assert!(is_denied(
+            fs.rename(Path::new("/other"), Path::new("/project"))
+        ));
+    }
+
+    #[test]
+    fn create_dir_all_checks_every_missing_ancestor() {
+        let fs = FsBuilder::new()
+            .on_read_dir(|path| match path.to_str() {
+                Some("/" | "/etc") => Ok(ReadDirIter::from_vec(Vec::new())),
+                _ => Err(FsError::not_found("read_dir", path)),
+            })
+            .on_create_dir_all(|_| Ok(()))
+            .build()
+            .layer(ProtectLayer::new(["/etc", "/vault", "*.d"]));
+        let is_denied =
+            |result: Result<(), FsError>| matches!(result, Err(FsError::PermissionDenied { .. }));
+
+        // Existing protected ancestors are left alone, not created
+        fs.create_dir_all(Path::new("/etc/app")).unwrap();
+        fs.create_dir_all(Path::new("/data/x/y")).unwrap();
+        assert!(is_denied(fs.create_dir_all(Path::new("/vault/keys"))));
+        assert!(is_denied(fs.create_dir_all(Path::new("/srv/conf.d/site"))));
+        assert!(is_denied(fs.create_dir_all(Path::new("/etc"))));
+    }
+
+    /// Accepts every call, so only `Protect` can refuse one.
+    struct Permissive;
+
+    impl FsLink for Permissive {
+        fn symlink(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn hard_link(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+            Err(FsError::not_found("read_link", path))
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            Err(FsError::not_found("symlink_metadata", path))
+        }
+    }
+
+    impl FsTiering for Permissive {
+        fn set_storage_class(&self, _: &Path, _: StorageClass) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn storage_class(&self, _: &Path) -> Result<StorageClass, FsError> {
+            Ok(StorageClass::Standard)
+        }
+    }
+
+    impl FsExpiry for Permissive {
+        fn set_expiry(&self, _: &Path, _: SystemTime) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn clear_expiry(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn expiry(&self, _: &Path) -> Result<Option<SystemTime>, FsError> {
+            Ok(None)
+        }
+    }
+
+    impl FsLayers for Permissive {
+        fn layers(&self) -> Vec<LayerInfo> {
+            Vec::new()
+        }
+
+        fn which_layer(&self, _: &Path) -> Result<LayerId, FsError> {
+            Ok(LayerId(0))
+        }
+
+        fn copy_up(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn links_and_metadata_changes_are_checked() {
+        let fs = Protect::new(Permissive, ["*.lock"]);
+        let lock = Path::new("/Cargo.lock");
+        let notes = Path::new("/notes.txt");
+        let is_denied =
+            |result: Result<(), FsError>| matches!(result, Err(FsError::PermissionDenied { .. }));
+
+        fs.hard_link(notes, Path::new("/copy.txt")).unwrap();
+        assert!(is_denied(fs.hard_link(notes, lock)));
+        // A second name would make the protected file writable
+        assert!(is_denied(fs.hard_link(lock, Path::new("/alias"))));
+
+        fs.set_storage_class(notes, StorageClass::Archive).unwrap();
+        assert!(is_denied(fs.set_storage_class(lock, StorageClass::Archive)));
+        assert!(is_denied(fs.set_expiry(lock, SystemTime::now())));
+        assert!(is_denied(fs.clear_expiry(lock)));
+        assert!(is_denied(fs.copy_up(lock)));
+        assert_eq!(fs.expiry(lock).unwrap(), None);
+    }
+}
@@ -0,0 +1,456 @@
+//! Content scanning of written files.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_dir, forward_fs_expiry, forward_fs_file_flags,
+    forward_fs_handle_debug, forward_fs_inode, forward_fs_layers, forward_fs_lease,
+    forward_fs_link, forward_fs_lock, forward_fs_permissions, forward_fs_read, forward_fs_seek,
+    forward_fs_sequence, forward_fs_stats, forward_fs_sync, forward_fs_tiering, forward_fs_xattr,
+};
+use crate::{
+    CopyOptions, FsError, FsHandles, FsRead, FsWrite, Handle, Layer, OpenFlags, Permissions,
+    WriteOptions,
+};
+
+/// Outcome of an [`FsScan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    /// Nothing objectionable was found.
+    Clean,
+    /// The content must be rejected, for the given reason.
+    Threat(String),
+}
+
+impl ScanVerdict {
+    /// Returns `true` for [`ScanVerdict::Clean`].
+    pub fn is_clean(&self) -> bool {
+        matches!(self, ScanVerdict::Clean)
+    }
+}
+
+/// A content scanner, such as an antivirus or DLP engine.
+///
+/// `scan` receives the path of a file and its complete contents. Any
+/// `Fn(&Path, &[u8]) -> ScanVerdict` closure is a scanner.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsScan, ScanVerdict};
+/// use std::path::Path;
+///
+/// struct NoSecrets;
+///
+/// impl FsScan for NoSecrets {
+///     fn scan(&self, _path: &Path, data: &[u8]) -> ScanVerdict {
+///         if data.windows(11).any(|w| w == b"PRIVATE KEY") {
+///             ScanVerdict::Threat("private key".into())
+///         } else {
+///             ScanVerdict::Clean
+///         }
+///     }
+/// }
+///
+/// assert!(NoSecrets.scan(Path::new("/notes.txt"), b"hello").is_clean());
+/// ```
+pub trait FsScan: Send + Sync {
+    /// Scan the contents of the file at `path`.
+    fn scan(&self, path: &Path, data: &[u8]) -> ScanVerdict;
+}
+
+impl<F> FsScan for F
+where
+    F: Fn(&Path, &[u8]) -> ScanVerdict + Send + Sync,
+{
+    fn scan(&self, path: &Path, data: &[u8]) -> ScanVerdict {
+        self(path, data)
+    }
+}
+
+/// Middleware running an [`FsScan`] over file contents as they are written.
+///
+/// Rejected content is reported as [`FsError::ThreatDetected`].
+///
+/// | Operation | When the scan runs | On a threat |
+/// |-----------|--------------------|-------------|
+/// | `write`, `write_with` | Before writing, over the new data | Nothing is written |
+/// | `append`, `copy`, `copy_with` | After the operation, over the whole file | The file is removed |
+/// | `open_write` | When the writer is flushed or dropped, over the data written since the last flush | The data is discarded |
+/// | [`FsHandles::close`] on a handle opened for writing | After closing, over the whole file | The file is removed |
+///
+/// A threat found while the `open_write` writer is dropped cannot be
+/// reported; call `flush` to see it. Data streamed through a FIFO is not
+/// scanned. Everything else is forwarded to the backend unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsError, FsWrite, LayerExt, ScanLayer, ScanVerdict};
+/// use std::path::Path;
+///
+/// let backend = FsBuilder::new().on_write(|_, _| Ok(())).build();
+/// let fs = backend.layer(ScanLayer::new(|_: &Path, data: &[u8]| {
+///     if data.starts_with(b"MZ") {
+///         ScanVerdict::Threat("executable".into())
+///     } else {
+///         ScanVerdict::Clean
+///     }
+/// }));
+///
+/// assert!(fs.write(Path::new("/a.txt"), b"text").is_ok());
+/// assert!(matches!(
+///     fs.write(Path::new("/a.exe"), b"MZ\x90\x00"),
+///     Err(FsError::ThreatDetected { .. })
+/// ));
+/// ```
+pub struct Scanned<B> {
+    inner: B,
+    scanner: Arc<dyn FsScan>,
+    writable: Mutex<HashMap<Handle, PathBuf>>,
+}
+
+impl<B> Scanned<B> {
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> Scanned<B> {
+    /// Scan `data` written to `path`.
+    fn check(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        match self.scanner.scan(path, data) {
+            ScanVerdict::Clean => Ok(()),
+            ScanVerdict::Threat(reason) => Err(FsError::ThreatDetected {
+                path: path.to_path_buf(),
+                reason,
+            }),
+        }
+    }
+}
+
+impl<B: FsRead + FsWrite> Scanned<B> {
+    /// Scan the stored file at `path`, removing it on a threat.
+    fn scan_stored(&self, path: &Path) -> Result<(), FsError> {
+        let data = self.inner.read(path)?;
+        self.check(path, &data).map_err(|e| {
+            let _ = self.inner.remove_file(path);
+            e
+        })
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for Scanned<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scanned")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+forward_fs_read!(Scanned);
+
+impl<B: FsRead + FsWrite> FsWrite for Scanned<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.check(path, data)?;
+        self.inner.write(path, data)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        self.check(path, data)?;
+        self.inner.write_with(path, data, options)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        self.inner.set_times(path, atime, mtime)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.inner.append(path, data)?;
+        self.scan_stored(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        self.inner.copy(from, to)?;
+        self.scan_stored(to)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        let inner = self.inner.open_write(path)?;
+        Ok(Box::new(ScanWriter {
+            inner,
+            path: path.to_path_buf(),
+            scanner: self.scanner.clone(),
+            pending: Vec::new(),
+        }))
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        let inner = self.inner.open_write_sized(path, expected_len)?;
+        Ok(Box::new(ScanWriter {
+            inner,
+            path: path.to_path_buf(),
+            scanner: self.scanner.clone(),
+            pending: Vec::new(),
+        }))
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let copied = self.inner.copy_with(from, to, options)?;
+        self.scan_stored(to)?;
+        Ok(copied)
+    }
+}
+
+impl<B: FsHandles + FsRead + FsWrite> FsHandles for Scanned<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        let handle = self.inner.open(path, flags)?;
+        let writing = flags.write || flags.append || flags.truncate;
+        if writing && !matches!(self.inner.metadata(path), Ok(m) if m.is_fifo()) {
+            self.writable
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(handle, path.to_path_buf());
+        }
+        Ok(handle)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)?;
+        let path = self
+            .writable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&handle);
+        match path {
+            Some(path) => self.scan_stored(&path),
+            None => Ok(()),
+        }
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        self.inner.link_handle(handle, path)?;
+        self.scan_stored(path)
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.inner.mkfifo(path, perm)
+    }
+}
+
+forward_fs_dir!(Scanned);
+forward_fs_link!(Scanned);
+forward_fs_permissions!(Scanned);
+forward_fs_stats!(Scanned);
+forward_fs_sync!(Scanned);
+forward_fs_inode!(Scanned);
+forward_fs_lock!(Scanned);
+forward_fs_xattr!(Scanned);
+forward_fs_handle_debug!(Scanned, FsRead, FsWrite);
+forward_fs_seek!(Scanned, FsRead, FsWrite);
+forward_fs_file_flags!(Scanned);
+forward_fs_tiering!(Scanned);
+forward_fs_expiry!(Scanned);
+forward_fs_lease!(Scanned);
+forward_fs_layers!(Scanned);
+forward_fs_sequence!(Scanned);
+forward_fs_cache_control!(Scanned);
+
+/// Writer holding data back until it has been scanned.
+struct ScanWriter {
+    inner: Box<dyn Write + Send>,
+    path: PathBuf,
+    scanner: Arc<dyn FsScan>,
+    pending: Vec<u8>,
+}
+
+impl ScanWriter {
+    fn release(&mut self) -> std::io::Result<()> {
+        let data = std::mem::take(&mut self.pending);
+        if let ScanVerdict::Threat(reason) = self.scanner.scan(&self.path, &data) {
+            return Err(FsError::ThreatDetected {
+                path: self.path.clone(),
+                reason,
+            }
+            .into());
+        }
+        self.inner.write_all(&data)?;
+        self.inner.flush()
+    }
+}
+
+impl Write for ScanWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.release()
+    }
+}
+
+impl Drop for ScanWriter {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let _ = self.release();
+        }
+    }
+}
+
+/// [`Layer`] producing [`Scanned`].
+#[derive(Clone)]
+pub struct ScanLayer {
+    scanner: Arc<dyn FsScan>,
+}
+
+impl ScanLayer {
+    /// Scan written content with `scanner`.
+    pub fn new(scanner: impl FsScan + 'static) -> Self {
+        Self {
+            scanner: Arc::new(scanner),
+        }
+    }
+}
+
+impl fmt::Debug for ScanLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanLayer").finish_non_exhaustive()
+    }
+}
+
+impl<B> Layer<B> for ScanLayer {
+    type Backend = Scanned<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        Scanned {
+            inner: backend,
+            scanner: self.scanner,
+            writable: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClosureFs, FsBuilder, LayerExt};
+
+    type Files = Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>;
+
+    fn store() -> (Files, ClosureFs) {
+        let files = Arc::new(Mutex::new(HashMap::new()));
+        let (r, w, a, rm) = (files.clone(), files.clone(), files.clone(), files.clone());
+        let fs = FsBuilder::new()
+            .on_read(move |p| {
+                r.lock()
+                    .unwrap()
+                    .get(p)
+                    .cloned()
+                    .ok_or_else(|| FsError::not_found("read", p))
+            })
+            .on_write(move |p, d| {
+                w.lock().unwrap().insert(p.to_path_buf(), d.to_vec());
+                Ok(())
+            })
+            .on_append(move |p, d| {
+                a.lock()
+                    .unwrap()
+                    .entry(p.to_path_buf())
+                    .or_default()
+                    .extend_from_slice(d);
+                Ok(())
+            })
+            .on_remove_file(move |p| {
+                rm.lock().unwrap().remove(p);
+                Ok(())
+            })
+            .build();
+        (files, fs)
+    }
+
+    fn no_virus(_: &Path, data: &[u8]) -> ScanVerdict {
+        if data.windows(5).any(|w| w == b"VIRUS") {
+            ScanVerdict::Threat("signature".into())
+        } else {
+            ScanVerdict::Clean
+        }
+    }
+
+    #[test]
+    fn rejected_write_stores_nothing() {
+        let (files, backend) = store();
+        let fs = backend.layer(ScanLayer::new(no_virus));
+
+        fs.write(Path::new("/ok"), b"fine").unwrap();
+        let err = fs.write(Path::new("/bad"), b"a VIRUS").unwrap_err();
+        assert!(matches!(err, FsError::ThreatDetected { ref reason, .. } if reason == "signature"));
+        assert!(!files.lock().unwrap().contains_key(Path::new("/bad")));
+        assert!(files.lock().unwrap().contains_key(Path::new("/ok")));
+    }
+
+    #[test]
+    fn append_scans_whole_file_and_removes_it() {
+        let (files, backend) = store();
+        let fs = backend.layer(ScanLayer::new(no_virus));
+
+        fs.write(Path::new("/f"), b"VI").unwrap();
+        assert!(matches!(
+            fs.append(Path::new("/f"), b"RUS"),
+            Err(FsError::ThreatDetected { .. })
+        ));
+        assert!(files.lock().unwrap().is_empty());
+    }
+}
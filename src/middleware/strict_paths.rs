@@ -0,0 +1,486 @@
+//! Rejection of malformed path arguments.
+
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::forward::{
+    forward_fs_cache_control, forward_fs_expiry, forward_fs_file_flags, forward_fs_handle_debug,
+    forward_fs_layers, forward_fs_lease, forward_fs_lock, forward_fs_seek, forward_fs_sequence,
+    forward_fs_tiering,
+};
+use crate::{
+    validate_virtual_path, CopyOptions, DirEntry, FsDir, FsError, FsHandles, FsInode, FsLink,
+    FsPermissions, FsRead, FsStats, FsSync, FsWrite, FsXattr, Handle, IoStats, Layer, Metadata,
+    MetadataLite, MetadataMask, OpenFlags, Permissions, ReadDirIter, ReadDirOptions, SetAttr,
+    StatFs, UsageReport, WriteOptions,
+};
+
+/// Middleware rejecting malformed paths before they reach the backend.
+///
+/// Every path argument of [`FsRead`], [`FsWrite`], [`FsDir`], [`FsLink`],
+/// [`FsPermissions`], [`FsStats`], [`FsSync`], [`FsInode`], [`FsHandles`]
+/// and [`FsXattr`] must pass [`validate_virtual_path`]: absolute, free of
+/// NUL bytes, and not climbing above `/`. Anything else fails with
+/// [`FsError::InvalidData`] without calling the backend, so a caller bug
+/// shows up the same way on every backend instead of as a backend-specific
+/// surprise.
+///
+/// Symlink targets are stored, not resolved, so relative targets stay
+/// allowed. The other optional traits are forwarded unchecked.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBuilder, FsError, FsRead, LayerExt, StrictPathsLayer};
+/// use std::path::Path;
+///
+/// let fs = FsBuilder::new()
+///     .on_read(|_| Ok(b"data".to_vec()))
+///     .build()
+///     .layer(StrictPathsLayer);
+///
+/// assert!(fs.read(Path::new("/etc/motd")).is_ok());
+/// assert!(matches!(
+///     fs.read(Path::new("etc/motd")),
+///     Err(FsError::InvalidData { .. })
+/// ));
+/// assert!(matches!(
+///     fs.read(Path::new("/../etc/motd")),
+///     Err(FsError::InvalidData { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StrictPaths<B> {
+    inner: B,
+}
+
+impl<B> StrictPaths<B> {
+    /// Wrap a backend.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: FsRead> FsRead for StrictPaths<B> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read_to_string(path)
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read_range(path, offset, len)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.exists(path)
+    }
+
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.exists_fresh(path)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.metadata(path)
+    }
+
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.metadata_lite(path)
+    }
+
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.metadata_mask(path, mask)
+    }
+
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.metadata_with_tag(path)
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.open_read(path)
+    }
+}
+
+impl<B: FsWrite> FsWrite for StrictPaths<B> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.write(path, data)
+    }
+
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.write_with(path, data, options)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.set_times(path, atime, mtime)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.append(path, data)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        validate_virtual_path(from)?;
+        validate_virtual_path(to)?;
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        validate_virtual_path(from)?;
+        validate_virtual_path(to)?;
+        self.inner.copy(from, to)
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        validate_virtual_path(from)?;
+        validate_virtual_path(to)?;
+        self.inner.copy_with(from, to, options)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.truncate(path, size)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.open_write(path)
+    }
+
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.open_write_sized(path, expected_len)
+    }
+}
+
+impl<B: FsDir> FsDir for StrictPaths<B> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read_dir(path)
+    }
+
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read_dir_with(path, options)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.create_dir_with(path, perm)
+    }
+
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        validate_virtual_path(parent)?;
+        self.inner.create_temp_dir_in(parent, prefix)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.remove_dir_all(path)
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.dir_len(path)
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.is_dir_empty(path)
+    }
+}
+
+impl<B: FsLink> FsLink for StrictPaths<B> {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        validate_virtual_path(link)?;
+        self.inner.symlink(target, link)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        validate_virtual_path(original)?;
+        validate_virtual_path(link)?;
+        self.inner.hard_link(original, link)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.read_link(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.symlink_metadata(path)
+    }
+}
+
+impl<B: FsPermissions> FsPermissions for StrictPaths<B> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.set_permissions(path, perm)
+    }
+}
+
+impl<B: FsStats> FsStats for StrictPaths<B> {
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        self.inner.statfs()
+    }
+
+    fn usage(&self, path: &Path) -> Result<UsageReport, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.usage(path)
+    }
+
+    fn io_stats(&self) -> IoStats {
+        self.inner.io_stats()
+    }
+}
+
+impl<B: FsSync> FsSync for StrictPaths<B> {
+    fn sync(&self) -> Result<(), FsError> {
+        self.inner.sync()
+    }
+
+    fn fsync(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.fsync(path)
+    }
+
+    fn fdatasync(&self, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.fdatasync(path)
+    }
+
+    fn sync_handle(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.sync_handle(handle)
+    }
+
+    fn barrier(&self) -> Result<(), FsError> {
+        self.inner.barrier()
+    }
+}
+
+impl<B: FsInode> FsInode for StrictPaths<B> {
+    fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.path_to_inode(path)
+    }
+
+    fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+        self.inner.inode_to_path(inode)
+    }
+
+    fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+        self.inner.lookup(parent_inode, name)
+    }
+
+    fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+        self.inner.metadata_by_inode(inode)
+    }
+
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        self.inner.read_dir_inode(inode, offset)
+    }
+
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        self.inner.set_attr(inode, attr)
+    }
+}
+
+impl<B: FsHandles> FsHandles for StrictPaths<B> {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.open(path, flags)
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.read_at(handle, buf, offset)
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.inner.write_at(handle, data, offset)
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        self.inner.close(handle)
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        self.inner.dup(handle)
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.inner.append_handle(handle, data)
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        validate_virtual_path(dir)?;
+        self.inner.open_anonymous(dir, flags)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.link_handle(handle, path)
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.mkfifo(path, perm)
+    }
+}
+
+impl<B: FsXattr> FsXattr for StrictPaths<B> {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.set_xattr(path, name, value)
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        validate_virtual_path(path)?;
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        validate_virtual_path(path)?;
+        self.inner.list_xattr(path)
+    }
+}
+
+forward_fs_lock!(StrictPaths);
+forward_fs_handle_debug!(StrictPaths);
+forward_fs_seek!(StrictPaths);
+forward_fs_file_flags!(StrictPaths);
+forward_fs_tiering!(StrictPaths);
+forward_fs_expiry!(StrictPaths);
+forward_fs_lease!(StrictPaths);
+forward_fs_layers!(StrictPaths);
+forward_fs_sequence!(StrictPaths);
+forward_fs_cache_control!(StrictPaths);
+
+/// [`Layer`] producing [`StrictPaths`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, LayerExt, StrictPathsLayer};
+///
+/// fn checked<B: Fs>(backend: B) -> impl Fs {
+///     backend.layer(StrictPathsLayer)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrictPathsLayer;
+
+impl<B> Layer<B> for StrictPathsLayer {
+    type Backend = StrictPaths<B>;
+
+    fn layer(self, backend: B) -> Self::Backend {
+        StrictPaths::new(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, LayerExt};
+
+    #[test]
+    fn both_ends_of_a_rename_are_checked() {
+        let fs = FsBuilder::new()
+            .on_rename(|_, _| Ok(()))
+            .build()
+            .layer(StrictPathsLayer);
+
+        assert!(fs.rename(Path::new("/a"), Path::new("/b")).is_ok());
+        let err = fs.rename(Path::new("/a"), Path::new("b")).unwrap_err();
+        assert!(matches!(
+            err,
+            FsError::InvalidData { ref path, .. } if path == Path::new("b")
+        ));
+    }
+
+    #[test]
+    fn rejected_paths_never_reach_the_backend() {
+        let fs = FsBuilder::new()
+            .on_exists(|p| panic!("backend called with {}", p.display()))
+            .build()
+            .layer(StrictPathsLayer);
+
+        for bad in ["", "rel", "/a/../../b", "/nul\0byte"] {
+            assert!(fs.exists(Path::new(bad)).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn read_dir_with_is_checked() {
+        let fs = FsBuilder::new()
+            .on_read_dir(|p| panic!("backend called with {}", p.display()))
+            .build()
+            .layer(StrictPathsLayer);
+
+        assert!(matches!(
+            fs.read_dir_with(Path::new("rel"), ReadDirOptions::sorted()),
+            Err(FsError::InvalidData { .. })
+        ));
+    }
+}
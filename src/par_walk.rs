@@ -0,0 +1,281 @@
+//! # Parallel Directory Walker
+//!
+//! [`ParWalk`] visits the same entries as [`Walk`](crate::Walk), listing
+//! sibling subtrees in parallel on a rayon thread pool. Obtain one with
+//! [`FsExt::par_walk`](crate::FsExt::par_walk) (requires the `rayon`
+//! feature).
+//!
+//! Results never depend on scheduling: [`entries`](ParWalk::entries) comes
+//! back in the order [`Walk`](crate::Walk) yields, and
+//! [`try_reduce`](ParWalk::try_reduce) combines values in that order, so
+//! the reducer only needs to be associative.
+
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::{DirEntry, FileType, Fs, FsError, WalkEntry};
+
+/// Parallel pre-order walk of a directory tree.
+///
+/// Symlinks below the root are never followed, as with
+/// [`Walk`](crate::Walk). An error listing one directory is reported in
+/// place of its contents and the walk goes on.
+///
+/// # Concurrency
+///
+/// By default the walk runs on the current rayon pool (the global one
+/// unless called from inside another pool).
+/// [`max_threads`](Self::max_threads) runs it on a dedicated pool instead,
+/// bounding the backend calls in flight.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsError, FsExt, FileType};
+/// use std::path::Path;
+///
+/// fn dir_size<B: Fs>(backend: &B, path: &Path) -> Result<u64, FsError> {
+///     backend.par_walk(path).max_threads(8).try_reduce(
+///         |entry| Ok(if entry.file_type() == FileType::File { entry.entry.size } else { 0 }),
+///         |a, b| a + b,
+///     )
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ParWalk<'a, B: Fs + ?Sized> {
+    backend: &'a B,
+    root: PathBuf,
+    max_depth: usize,
+    max_threads: Option<usize>,
+}
+
+impl<'a, B: Fs + ?Sized> ParWalk<'a, B> {
+    /// Walk the tree rooted at `root`.
+    pub fn new(backend: &'a B, root: &Path) -> Self {
+        Self {
+            backend,
+            root: root.to_path_buf(),
+            max_depth: usize::MAX,
+            max_threads: None,
+        }
+    }
+
+    /// Don't descend below `depth` (0 yields only the root).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Run on a dedicated pool of at most `threads` threads (at least 1).
+    pub fn max_threads(mut self, threads: usize) -> Self {
+        self.max_threads = Some(threads.max(1));
+        self
+    }
+
+    /// Every item [`Walk`](crate::Walk) would yield, in the same order.
+    ///
+    /// If the dedicated pool can't be started, the only item is an
+    /// [`FsError::Backend`].
+    pub fn entries(self) -> Vec<Result<WalkEntry, FsError>> {
+        self.run(&|item| vec![item], &|mut a, b| {
+            a.extend(b);
+            a
+        })
+    }
+
+    /// Map every entry with `map` and combine the results with `reduce`, in
+    /// walk order.
+    ///
+    /// `reduce` must be associative; it need not be commutative.
+    ///
+    /// # Errors
+    ///
+    /// - The first error in walk order, from the backend or from `map`;
+    ///   other subtrees may still be visited before it is returned
+    /// - [`FsError::Backend`] if the dedicated pool can't be started
+    pub fn try_reduce<T, M, R>(self, map: M, reduce: R) -> Result<T, FsError>
+    where
+        T: Send,
+        M: Fn(&WalkEntry) -> Result<T, FsError> + Sync,
+        R: Fn(T, T) -> T + Sync,
+    {
+        self.run(&|item| item.and_then(|entry| map(&entry)), &|a, b| {
+            Ok(reduce(a?, b?))
+        })
+    }
+
+    /// Visit the tree, mapping each item and reducing in walk order.
+    fn run<T, M, R>(&self, map: &M, reduce: &R) -> T
+    where
+        T: Send,
+        M: Fn(Result<WalkEntry, FsError>) -> T + Sync,
+        R: Fn(T, T) -> T + Sync,
+    {
+        let walk = || match self.root_entry() {
+            Ok(root) => self.subtree(root, map, reduce),
+            Err(e) => map(Err(e)),
+        };
+        match self.max_threads {
+            None => walk(),
+            Some(threads) => match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(walk),
+                Err(e) => map(Err(FsError::Backend(format!(
+                    "par_walk: cannot start thread pool: {e}"
+                )))),
+            },
+        }
+    }
+
+    fn root_entry(&self) -> Result<WalkEntry, FsError> {
+        let meta = self.backend.metadata(&self.root)?;
+        let name = self.root.file_name().unwrap_or_default().to_os_string();
+        Ok(WalkEntry {
+            depth: 0,
+            entry: DirEntry {
+                path: self.root.clone(),
+                file_type: meta.file_type,
+                size: meta.size,
+                inode: meta.inode,
+                ..Default::default()
+            }
+            .with_name_os(name),
+        })
+    }
+
+    /// `entry` followed by everything below it.
+    fn subtree<T, M, R>(&self, entry: WalkEntry, map: &M, reduce: &R) -> T
+    where
+        T: Send,
+        M: Fn(Result<WalkEntry, FsError>) -> T + Sync,
+        R: Fn(T, T) -> T + Sync,
+    {
+        if entry.file_type() != FileType::Directory || entry.depth >= self.max_depth {
+            return map(Ok(entry));
+        }
+        let depth = entry.depth + 1;
+        let path = entry.entry.path.clone();
+        let own = map(Ok(entry));
+        let children: Vec<_> = match self.backend.read_dir(&path) {
+            Ok(iter) => iter.collect(),
+            Err(e) => return reduce(own, map(Err(e))),
+        };
+        children
+            .into_par_iter()
+            .map(|child| match child {
+                Ok(entry) => self.subtree(WalkEntry { depth, entry }, map, reduce),
+                Err(e) => map(Err(e)),
+            })
+            .collect::<Vec<T>>()
+            .into_iter()
+            .fold(own, reduce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FsBuilder, FsExt, Metadata, ReadDirIter};
+
+    const TREE: &[(&str, Option<u64>)] = &[
+        ("/", None),
+        ("/a", None),
+        ("/a/1.txt", Some(1)),
+        ("/a/2.txt", Some(2)),
+        ("/b", None),
+        ("/b/c", None),
+        ("/b/c/4.txt", Some(4)),
+        ("/broken", None),
+        ("/z.txt", Some(8)),
+    ];
+
+    fn source() -> impl Fs {
+        FsBuilder::new()
+            .on_metadata(|path| {
+                let (_, size) = TREE
+                    .iter()
+                    .find(|(p, _)| Path::new(p) == path)
+                    .ok_or_else(|| FsError::not_found("metadata", path))?;
+                Ok(Metadata {
+                    file_type: size.map_or(FileType::Directory, |_| FileType::File),
+                    size: size.unwrap_or(0),
+                    ..Metadata::default()
+                })
+            })
+            .on_read_dir(|path| {
+                if path == Path::new("/broken") {
+                    return Err(FsError::PermissionDenied {
+                        path: path.to_path_buf(),
+                        operation: "read_dir",
+                    });
+                }
+                let entries = TREE
+                    .iter()
+                    .map(|(p, size)| (Path::new(p), size))
+                    .filter(|(p, _)| p.parent() == Some(path))
+                    .map(|(p, size)| {
+                        Ok(DirEntry {
+                            name: p.file_name().unwrap().to_string_lossy().into_owned(),
+                            path: p.to_path_buf(),
+                            file_type: size.map_or(FileType::Directory, |_| FileType::File),
+                            size: size.unwrap_or(0),
+                            ..DirEntry::default()
+                        })
+                    })
+                    .collect();
+                Ok(ReadDirIter::from_vec(entries))
+            })
+            .build()
+    }
+
+    fn outline(items: Vec<Result<WalkEntry, FsError>>) -> Vec<String> {
+        items
+            .into_iter()
+            .map(|item| match item {
+                Ok(entry) => format!("{}:{}", entry.depth, entry.path().display()),
+                Err(e) => format!("error:{}", e.operation().unwrap_or_default()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entries_match_sequential_walk() {
+        let backend = source();
+        let sequential = outline(backend.walk(Path::new("/")).collect());
+        let parallel = outline(backend.par_walk(Path::new("/")).max_threads(3).entries());
+        assert_eq!(parallel, sequential);
+        assert!(parallel.contains(&"error:read_dir".to_string()));
+    }
+
+    #[test]
+    fn reduce_keeps_walk_order() {
+        let backend = source();
+        let paths = backend
+            .par_walk(Path::new("/b"))
+            .try_reduce(
+                |entry| Ok(vec![entry.path().to_path_buf()]),
+                |a, b| [a, b].concat(),
+            )
+            .unwrap();
+        assert_eq!(
+            paths,
+            [Path::new("/b"), Path::new("/b/c"), Path::new("/b/c/4.txt")]
+        );
+
+        let shallow = backend
+            .par_walk(Path::new("/"))
+            .max_depth(1)
+            .try_reduce(|entry| Ok(entry.entry.size), |a, b| a + b)
+            .unwrap();
+        assert_eq!(shallow, 8);
+    }
+
+    #[test]
+    fn reduce_returns_first_error() {
+        let err = source()
+            .par_walk(Path::new("/"))
+            .try_reduce(|entry| Ok(entry.entry.size), |a, b| a + b)
+            .unwrap_err();
+        assert!(matches!(err, FsError::PermissionDenied { .. }));
+    }
+}
@@ -4,6 +4,9 @@
 //!
 //! ## Responsibility
 //! - Define the contract for path resolution (canonicalization, normalization)
+//! - Provide [`PolicyResolver`], the configurable engine behind
+//!   [`FsPath`](crate::FsPath), with a [`ResolvePolicy`] for symlink depth,
+//!   case folding, `..` escapes and root confinement
 //!
 //! ## Dependencies
 //! - [`Fs`] trait for filesystem queries
@@ -30,9 +33,16 @@
 //! }
 //! ```
 
+use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
 
-use crate::{Fs, FsError};
+use crate::{
+    FileType, Fs, FsDir, FsError, FsLink, FsRead, ReadDirIter, Resolved, VirtualComponent,
+    VirtualComponents,
+};
+
+/// Default maximum depth for symlink resolution to prevent infinite loops.
+const MAX_SYMLINK_DEPTH: usize = 40;
 
 // ============================================================================
 // Trait Definition
@@ -61,6 +71,7 @@ use crate::{Fs, FsError};
 ///
 /// # Implementors
 ///
+/// - [`PolicyResolver`]: Configurable engine shared with [`FsPath`](crate::FsPath)
 /// - `IterativeResolver` (default in `anyfs`): Walks path component by component
 /// - `NoOpResolver` (in `anyfs`): Pass-through for `SelfResolving` backends
 /// - `CachingResolver` (in `anyfs`): LRU cache wrapper for any resolver (with TTL expiration)
@@ -126,6 +137,480 @@ pub trait PathResolver: Send + Sync {
     /// - [`FsError::NotFound`] - A parent component doesn't exist
     /// - [`FsError::InvalidData`] - Symlink loop detected
     fn soft_canonicalize(&self, path: &Path, fs: &dyn Fs) -> Result<PathBuf, FsError>;
+
+    /// [`canonicalize`](Self::canonicalize), wrapped as [`Resolved`] so
+    /// layers below can skip resolving it again.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from [`canonicalize`](Self::canonicalize)
+    fn resolve(&self, path: &Path, fs: &dyn Fs) -> Result<Resolved, FsError> {
+        self.canonicalize(path, fs).map(Resolved::new_unchecked)
+    }
+
+    /// [`soft_canonicalize`](Self::soft_canonicalize), wrapped as
+    /// [`Resolved`].
+    ///
+    /// # Errors
+    ///
+    /// - Errors from [`soft_canonicalize`](Self::soft_canonicalize)
+    fn soft_resolve(&self, path: &Path, fs: &dyn Fs) -> Result<Resolved, FsError> {
+        self.soft_canonicalize(path, fs)
+            .map(Resolved::new_unchecked)
+    }
+}
+
+// ============================================================================
+// Resolution Policy
+// ============================================================================
+
+/// What happens when `..` would climb above the resolution root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EscapePolicy {
+    /// Stay at the root, like `/..` on Unix (the default).
+    #[default]
+    Clamp,
+    /// Fail with [`FsError::AccessDenied`].
+    Deny,
+}
+
+/// How path components are matched against directory entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseSensitivity {
+    /// Names must match exactly (the default).
+    #[default]
+    Sensitive,
+    /// A component that doesn't match exactly resolves to an entry whose
+    /// name is equal after Unicode lowercasing, as on Windows and macOS.
+    /// The entry's stored spelling appears in the result.
+    Insensitive,
+}
+
+/// Knobs for [`PolicyResolver`].
+///
+/// The default reproduces [`FsPath::canonicalize`](crate::FsPath::canonicalize):
+/// up to 40 nested symlinks, case-sensitive names, `..` clamped at `/`, and
+/// no root confinement.
+///
+/// # Root Confinement
+///
+/// With [`with_root`](Self::with_root), paths are resolved as if `root` were
+/// `/` (like `chroot`): absolute paths and absolute symlink targets start at
+/// `root`, and `..` cannot leave it. Results are backend paths under `root`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CaseSensitivity, EscapePolicy, ResolvePolicy};
+///
+/// let policy = ResolvePolicy::default()
+///     .with_max_symlink_depth(8)
+///     .with_case(CaseSensitivity::Insensitive)
+///     .with_escape(EscapePolicy::Deny)
+///     .with_root("/tenants/acme");
+///
+/// assert_eq!(policy.max_symlink_depth, 8);
+/// assert_eq!(policy.escape, EscapePolicy::Deny);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvePolicy {
+    /// Maximum number of nested symlink expansions before resolution fails
+    /// with a symlink-loop error. `0` rejects every symlink.
+    pub max_symlink_depth: usize,
+    /// How names are matched.
+    pub case: CaseSensitivity,
+    /// What `..` at the root does.
+    pub escape: EscapePolicy,
+    /// Directory that acts as `/` during resolution, if any.
+    pub root: Option<PathBuf>,
+}
+
+impl Default for ResolvePolicy {
+    fn default() -> Self {
+        Self {
+            max_symlink_depth: MAX_SYMLINK_DEPTH,
+            case: CaseSensitivity::Sensitive,
+            escape: EscapePolicy::Clamp,
+            root: None,
+        }
+    }
+}
+
+impl ResolvePolicy {
+    /// Set the maximum symlink nesting depth.
+    pub fn with_max_symlink_depth(mut self, depth: usize) -> Self {
+        self.max_symlink_depth = depth;
+        self
+    }
+
+    /// Set how names are matched.
+    pub fn with_case(mut self, case: CaseSensitivity) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Set what `..` at the root does.
+    pub fn with_escape(mut self, escape: EscapePolicy) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// Confine resolution to `root`.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Backend path for a path in the resolution namespace.
+    fn backend_path(&self, resolved: &Path) -> PathBuf {
+        match &self.root {
+            None => resolved.to_path_buf(),
+            Some(root) => match resolved.strip_prefix("/") {
+                Ok(rest) if !rest.as_os_str().is_empty() => root.join(rest),
+                Ok(_) => root.clone(),
+                Err(_) => root.join(resolved),
+            },
+        }
+    }
+}
+
+/// Configurable resolution engine shared by backends and layers.
+///
+/// [`FsPath::canonicalize_with`](crate::FsPath::canonicalize_with) runs it
+/// with full symlink support; the default `FsPath` methods run the same
+/// engine with [`ResolvePolicy::default`]. As a [`PathResolver`] it only sees
+/// a `&dyn Fs`, which cannot read links, so there it relies on
+/// [`metadata`](crate::FsRead::metadata) following them.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{EscapePolicy, FsError, FsPath, FsDir, PolicyResolver, ResolvePolicy};
+/// use std::path::{Path, PathBuf};
+///
+/// // Resolve an upload path inside a tenant directory, refusing `..` escapes
+/// fn tenant_path<B: FsPath + FsDir>(backend: &B, user_path: &Path) -> Result<PathBuf, FsError> {
+///     let resolver = PolicyResolver::new(
+///         ResolvePolicy::default()
+///             .with_root("/tenants/acme")
+///             .with_escape(EscapePolicy::Deny),
+///     );
+///     backend.canonicalize_with(user_path, &resolver)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyResolver {
+    policy: ResolvePolicy,
+}
+
+impl PolicyResolver {
+    /// Create a resolver with the given policy.
+    pub fn new(policy: ResolvePolicy) -> Self {
+        Self { policy }
+    }
+
+    /// The policy this resolver applies.
+    pub fn policy(&self) -> &ResolvePolicy {
+        &self.policy
+    }
+
+    /// Canonicalize `path` on `fs`, following symlinks.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] - A component doesn't exist
+    /// - [`FsError::InvalidData`] - Symlink nesting exceeded the policy limit
+    /// - [`FsError::AccessDenied`] - `..` escaped the root under
+    ///   [`EscapePolicy::Deny`]
+    pub fn canonicalize_in<F: FsRead + FsLink + FsDir + ?Sized>(
+        &self,
+        fs: &F,
+        path: &Path,
+    ) -> Result<PathBuf, FsError> {
+        resolve(&DirLinkLookup(fs), &self.policy, path)
+    }
+
+    /// Like [`canonicalize_in`](Self::canonicalize_in), but allows a
+    /// non-existent final component.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`canonicalize_in`](Self::canonicalize_in), for the parent.
+    pub fn soft_canonicalize_in<F: FsRead + FsLink + FsDir + ?Sized>(
+        &self,
+        fs: &F,
+        path: &Path,
+    ) -> Result<PathBuf, FsError> {
+        soft_resolve(&DirLinkLookup(fs), &self.policy, path)
+    }
+}
+
+impl PathResolver for PolicyResolver {
+    fn canonicalize(&self, path: &Path, fs: &dyn Fs) -> Result<PathBuf, FsError> {
+        resolve(&FollowingLookup(fs), &self.policy, path)
+    }
+
+    fn soft_canonicalize(&self, path: &Path, fs: &dyn Fs) -> Result<PathBuf, FsError> {
+        soft_resolve(&FollowingLookup(fs), &self.policy, path)
+    }
+}
+
+// ============================================================================
+// Resolution Engine
+// ============================================================================
+
+/// Filesystem queries the engine needs, so one engine serves every entry
+/// point regardless of which traits the caller can offer.
+pub(crate) trait Lookup {
+    /// Type of the entry itself, without following a final symlink.
+    fn entry_type(&self, path: &Path) -> Result<FileType, FsError>;
+    /// Target of the symlink at `path`.
+    fn link_target(&self, path: &Path) -> Result<PathBuf, FsError>;
+    /// Whether `path` exists.
+    fn exists(&self, path: &Path) -> Result<bool, FsError>;
+    /// Names in the directory at `path`, for case-insensitive matching.
+    fn names(&self, path: &Path) -> Result<Vec<OsString>, FsError>;
+}
+
+/// Symlink-aware lookups without directory listing.
+pub(crate) struct LinkLookup<'a, F: ?Sized>(pub(crate) &'a F);
+
+impl<F: FsRead + FsLink + ?Sized> Lookup for LinkLookup<'_, F> {
+    fn entry_type(&self, path: &Path) -> Result<FileType, FsError> {
+        Ok(self.0.symlink_metadata(path)?.file_type)
+    }
+
+    fn link_target(&self, path: &Path) -> Result<PathBuf, FsError> {
+        self.0.read_link(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.0.exists(path)
+    }
+
+    fn names(&self, _path: &Path) -> Result<Vec<OsString>, FsError> {
+        Err(FsError::NotSupported {
+            operation: "canonicalize",
+        })
+    }
+}
+
+/// Symlink-aware lookups with directory listing.
+struct DirLinkLookup<'a, F: ?Sized>(&'a F);
+
+impl<F: FsRead + FsLink + FsDir + ?Sized> Lookup for DirLinkLookup<'_, F> {
+    fn entry_type(&self, path: &Path) -> Result<FileType, FsError> {
+        LinkLookup(self.0).entry_type(path)
+    }
+
+    fn link_target(&self, path: &Path) -> Result<PathBuf, FsError> {
+        self.0.read_link(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.0.exists(path)
+    }
+
+    fn names(&self, path: &Path) -> Result<Vec<OsString>, FsError> {
+        list_names(self.0.read_dir(path)?)
+    }
+}
+
+/// Lookups through `&dyn Fs`, where `metadata` follows symlinks itself.
+struct FollowingLookup<'a>(&'a dyn Fs);
+
+impl Lookup for FollowingLookup<'_> {
+    fn entry_type(&self, path: &Path) -> Result<FileType, FsError> {
+        Ok(self.0.metadata(path)?.file_type)
+    }
+
+    fn link_target(&self, _path: &Path) -> Result<PathBuf, FsError> {
+        Err(FsError::NotSupported {
+            operation: "read_link",
+        })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.0.exists(path)
+    }
+
+    fn names(&self, path: &Path) -> Result<Vec<OsString>, FsError> {
+        list_names(self.0.read_dir(path)?)
+    }
+}
+
+fn list_names(entries: ReadDirIter) -> Result<Vec<OsString>, FsError> {
+    entries
+        .map(|entry| entry.map(|entry| entry.file_name().to_os_string()))
+        .collect()
+}
+
+/// Canonicalize `path` under `policy`; every component must exist.
+pub(crate) fn resolve<L: Lookup + ?Sized>(
+    lookup: &L,
+    policy: &ResolvePolicy,
+    path: &Path,
+) -> Result<PathBuf, FsError> {
+    let resolved = resolve_components(lookup, policy, path, 0, true)?;
+    let backend_path = policy.backend_path(&resolved);
+    if !lookup.exists(&backend_path)? {
+        return Err(FsError::not_found("canonicalize", backend_path));
+    }
+    Ok(backend_path)
+}
+
+/// Resolve the parent of `path` under `policy` and append the final
+/// component lexically.
+pub(crate) fn soft_resolve<L: Lookup + ?Sized>(
+    lookup: &L,
+    policy: &ResolvePolicy,
+    path: &Path,
+) -> Result<PathBuf, FsError> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            let parent = resolve(lookup, policy, parent)?;
+            let name = match policy.case {
+                CaseSensitivity::Insensitive if !lookup.exists(&parent.join(name))? => {
+                    folded_match(lookup, &parent, name)?.unwrap_or_else(|| name.to_os_string())
+                }
+                _ => name.to_os_string(),
+            };
+            Ok(parent.join(name))
+        }
+        (_, Some(_)) => Ok(policy.backend_path(&normalize(path))),
+        (_, None) => resolve(lookup, policy, path),
+    }
+}
+
+/// Walk `path` component by component in the policy's namespace (where `/`
+/// is the policy root), following symlinks.
+fn resolve_components<L: Lookup + ?Sized>(
+    lookup: &L,
+    policy: &ResolvePolicy,
+    path: &Path,
+    depth: usize,
+    require_exists: bool,
+) -> Result<PathBuf, FsError> {
+    if depth > policy.max_symlink_depth {
+        return Err(FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: format!(
+                "symlink loop detected (exceeded max depth of {})",
+                policy.max_symlink_depth
+            ),
+        });
+    }
+
+    let mut resolved = PathBuf::new();
+
+    for component in VirtualComponents::new(path) {
+        match component {
+            VirtualComponent::Root => {
+                resolved = PathBuf::from("/");
+            }
+            VirtualComponent::CurDir => {}
+            VirtualComponent::ParentDir => {
+                if resolved.parent().is_none() {
+                    if policy.escape == EscapePolicy::Deny {
+                        return Err(FsError::AccessDenied {
+                            path: path.to_path_buf(),
+                            reason: "path escapes the resolution root".to_string(),
+                        });
+                    }
+                } else {
+                    resolved.pop();
+                }
+                if resolved.as_os_str().is_empty() {
+                    resolved = PathBuf::from("/");
+                }
+            }
+            VirtualComponent::Normal(name) => {
+                resolved.push(name);
+                let mut entry = lookup.entry_type(&policy.backend_path(&resolved));
+                if policy.case == CaseSensitivity::Insensitive
+                    && matches!(entry, Err(FsError::NotFound { .. }))
+                {
+                    let parent = resolved.parent().unwrap_or(Path::new("/"));
+                    if let Some(actual) = folded_match(lookup, &policy.backend_path(parent), name)?
+                    {
+                        resolved.set_file_name(actual);
+                        entry = lookup.entry_type(&policy.backend_path(&resolved));
+                    }
+                }
+
+                match entry {
+                    Ok(FileType::Symlink) => {
+                        let target = lookup.link_target(&policy.backend_path(&resolved))?;
+                        resolved.pop();
+                        let target = if target.has_root() {
+                            target
+                        } else {
+                            resolved.join(target)
+                        };
+                        resolved =
+                            resolve_components(lookup, policy, &target, depth + 1, require_exists)?;
+                    }
+                    Ok(_) => {}
+                    Err(FsError::NotFound { .. }) if !require_exists => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+
+    if resolved.as_os_str().is_empty() {
+        resolved = PathBuf::from("/");
+    }
+    Ok(resolved)
+}
+
+/// An entry of `dir` whose name equals `name` after Unicode lowercasing.
+fn folded_match<L: Lookup + ?Sized>(
+    lookup: &L,
+    dir: &Path,
+    name: &OsStr,
+) -> Result<Option<OsString>, FsError> {
+    let wanted = match name.to_str() {
+        Some(name) => name.to_lowercase(),
+        None => return Ok(None),
+    };
+    let names = match lookup.names(dir) {
+        Ok(names) => names,
+        Err(FsError::NotFound { .. }) | Err(FsError::NotADirectory { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(names.into_iter().find(|candidate| {
+        candidate.to_str().map(str::to_lowercase).as_deref() == Some(wanted.as_str())
+    }))
+}
+
+/// Lexical normalization without filesystem access: `.` and `..` are
+/// resolved textually and `..` never climbs above `/`.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in VirtualComponents::new(path) {
+        match component {
+            VirtualComponent::Root => {
+                normalized = PathBuf::from("/");
+            }
+            VirtualComponent::CurDir => {}
+            VirtualComponent::ParentDir => {
+                normalized.pop();
+                if normalized.as_os_str().is_empty() {
+                    normalized = PathBuf::from("/");
+                }
+            }
+            VirtualComponent::Normal(name) => {
+                normalized.push(name);
+            }
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        normalized = PathBuf::from("/");
+    }
+    normalized
 }
 
 // ============================================================================
@@ -169,6 +654,7 @@ mod tests {
                 accessed: SystemTime::UNIX_EPOCH,
                 inode: 1,
                 nlink: 1,
+                ..Default::default()
             })
         }
 
@@ -274,4 +760,13 @@ mod tests {
         let result = resolver.soft_canonicalize(path, &mock_fs);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn path_resolver_resolve_wraps_canonical_path() {
+        let resolver = TestResolver;
+        let resolved = resolver
+            .resolve(Path::new("/some/file.txt"), &MockFs)
+            .unwrap();
+        assert_eq!(resolved.as_path(), Path::new("/some/file.txt"));
+    }
 }
@@ -0,0 +1,68 @@
+//! # Progress Reporting
+//!
+//! Long-running utilities report how far they got through a
+//! [`ProgressSink`], so a CLI or GUI can render one progress bar whatever
+//! the backend.
+//!
+//! | Utility | Reports after |
+//! |---------|---------------|
+//! | [`FsExt::move_entry_with`](crate::FsExt::move_entry_with) | Each entry copied by the fallback |
+//! | [`FsExt::remove_dir_all_report_with`](crate::FsExt::remove_dir_all_report_with) | Each entry removed |
+//!
+//! Any `FnMut(&Progress)` closure is a sink.
+
+use std::path::PathBuf;
+
+/// How far a long-running operation got, passed to a [`ProgressSink`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Progress {
+    /// Path of the entry just handled.
+    pub path: PathBuf,
+    /// Entries handled so far, including this one.
+    pub entries_done: u64,
+    /// File bytes transferred so far. Stays `0` for operations that move
+    /// no data, such as removal.
+    pub bytes_done: u64,
+}
+
+/// Former name of [`Progress`], from when only moves reported it.
+pub type MoveProgress = Progress;
+
+/// Receiver of [`Progress`] updates.
+///
+/// Called on the thread running the operation, after each entry; a slow
+/// sink slows the operation down.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsExt, FsError, Progress, ProgressSink};
+/// use std::path::Path;
+///
+/// /// Print a line every 100 entries.
+/// struct Every100;
+///
+/// impl ProgressSink for Every100 {
+///     fn report(&mut self, progress: &Progress) {
+///         if progress.entries_done % 100 == 0 {
+///             println!("{} entries, at {}", progress.entries_done, progress.path.display());
+///         }
+///     }
+/// }
+///
+/// fn clean<B: Fs>(backend: &B) -> Result<(), FsError> {
+///     backend
+///         .remove_dir_all_report_with(Path::new("/cache"), Every100)?
+///         .into_result()
+/// }
+/// ```
+pub trait ProgressSink {
+    /// Record that `progress` has been reached.
+    fn report(&mut self, progress: &Progress);
+}
+
+impl<F: FnMut(&Progress)> ProgressSink for F {
+    fn report(&mut self, progress: &Progress) {
+        self(progress);
+    }
+}
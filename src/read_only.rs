@@ -0,0 +1,317 @@
+//! Stub impls for read-only backends.
+//!
+//! Archives, HTTP mirrors and snapshots can't be modified, but generic code
+//! bounded on [`Fs`](crate::Fs) still needs their mutation methods. The
+//! [`impl_read_only!`](crate::impl_read_only) macro generates those methods,
+//! each failing with [`FsError::ReadOnly`](crate::FsError::ReadOnly).
+
+/// Implement mutation traits for a read-only backend.
+///
+/// Every generated method returns [`FsError::ReadOnly`] naming the
+/// operation. Supported forms, in any order and combination:
+///
+/// | Form | Generates |
+/// |------|-----------|
+/// | `impl FsWrite for T;` | All of [`FsWrite`], including `copy_with` |
+/// | `impl FsPermissions for T;` | [`FsPermissions::set_permissions`] |
+/// | `impl FsDir for T { fn read_dir(..) {..} }` | [`FsDir`] with your `read_dir` and any other methods you write, such as a native `read_dir_with`; `create_dir`, `create_dir_all`, `create_dir_with`, `remove_dir` and `remove_dir_all` are stubs |
+///
+/// Without its own `read_dir_with`, the backend gets the trait default,
+/// which lists through your `read_dir` and so rejects the same paths.
+///
+/// `T` must be a concrete type; write the impls by hand for generic
+/// backends.
+///
+/// [`FsError::ReadOnly`]: crate::FsError::ReadOnly
+/// [`FsWrite`]: crate::FsWrite
+/// [`FsPermissions::set_permissions`]: crate::FsPermissions::set_permissions
+/// [`FsDir`]: crate::FsDir
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{
+///     impl_read_only, DirEntry, FileType, FsError, FsRead, FsWrite, Metadata, ReadDirIter,
+/// };
+/// use std::io::Read;
+/// use std::path::Path;
+///
+/// /// A single read-only file at `/motd`.
+/// struct Motd;
+///
+/// impl FsRead for Motd {
+///     fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+///         match path.to_str() {
+///             Some("/motd") => Ok(b"hello".to_vec()),
+///             _ => Err(FsError::not_found("read", path)),
+///         }
+///     }
+///     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+///         Ok(String::from_utf8_lossy(&self.read(path)?).into_owned())
+///     }
+///     fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+///         let data = self.read(path)?;
+///         Ok(data.into_iter().skip(offset as usize).take(len).collect())
+///     }
+///     fn exists(&self, path: &Path) -> Result<bool, FsError> {
+///         Ok(matches!(path.to_str(), Some("/" | "/motd")))
+///     }
+///     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+///         match path.to_str() {
+///             Some("/") => Ok(Metadata::default().with_file_type(FileType::Directory)),
+///             Some("/motd") => Ok(Metadata::default().with_size(5)),
+///             _ => Err(FsError::not_found("metadata", path)),
+///         }
+///     }
+///     fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+///         Ok(Box::new(std::io::Cursor::new(self.read(path)?)))
+///     }
+/// }
+///
+/// impl_read_only! {
+///     impl FsWrite for Motd;
+///     impl FsDir for Motd {
+///         fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+///             if path != Path::new("/") {
+///                 return Err(FsError::not_a_directory("read_dir", path));
+///             }
+///             let entry = DirEntry::default().with_name("motd").with_path("/motd").with_size(5);
+///             Ok(ReadDirIter::from_vec(vec![Ok(entry)]))
+///         }
+///     }
+/// }
+///
+/// fn assert_fs<B: anyfs_backend::Fs>(_: &B) {}
+/// assert_fs(&Motd);
+///
+/// let err = Motd.write(Path::new("/motd"), b"bye").unwrap_err();
+/// assert!(matches!(err, FsError::ReadOnly { operation: "write" }));
+/// ```
+#[macro_export]
+macro_rules! impl_read_only {
+    () => {};
+    (impl FsWrite for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsWrite for $ty {
+            fn write(&self, _: &::std::path::Path, _: &[u8]) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "write" })
+            }
+
+            fn append(&self, _: &::std::path::Path, _: &[u8]) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "append" })
+            }
+
+            fn remove_file(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "remove_file" })
+            }
+
+            fn rename(
+                &self,
+                _: &::std::path::Path,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "rename" })
+            }
+
+            fn copy(
+                &self,
+                _: &::std::path::Path,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "copy" })
+            }
+
+            fn truncate(&self, _: &::std::path::Path, _: u64) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "truncate" })
+            }
+
+            fn open_write(
+                &self,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<::std::boxed::Box<dyn ::std::io::Write + Send>, $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "open_write" })
+            }
+
+            fn copy_with(
+                &self,
+                _: &::std::path::Path,
+                _: &::std::path::Path,
+                _: $crate::CopyOptions,
+            ) -> ::std::result::Result<u64, $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "copy_with" })
+            }
+
+            fn write_with(
+                &self,
+                _: &::std::path::Path,
+                _: &[u8],
+                _: $crate::WriteOptions,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "write_with" })
+            }
+
+            fn open_write_sized(
+                &self,
+                _: &::std::path::Path,
+                _: u64,
+            ) -> ::std::result::Result<::std::boxed::Box<dyn ::std::io::Write + Send>, $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "open_write_sized" })
+            }
+
+            fn set_times(
+                &self,
+                _: &::std::path::Path,
+                _: ::std::option::Option<::std::time::SystemTime>,
+                _: ::std::option::Option<::std::time::SystemTime>,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "set_times" })
+            }
+        }
+
+        $crate::impl_read_only!($($rest)*);
+    };
+    (impl FsPermissions for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsPermissions for $ty {
+            fn set_permissions(
+                &self,
+                _: &::std::path::Path,
+                _: $crate::Permissions,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "set_permissions" })
+            }
+        }
+
+        $crate::impl_read_only!($($rest)*);
+    };
+    (impl FsDir for $ty:ty { $($read_dir:tt)* } $($rest:tt)*) => {
+        impl $crate::FsDir for $ty {
+            $($read_dir)*
+
+            fn create_dir(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "create_dir" })
+            }
+
+            fn create_dir_all(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "create_dir_all" })
+            }
+
+            fn remove_dir(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "remove_dir" })
+            }
+
+            fn remove_dir_all(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "remove_dir_all" })
+            }
+
+            fn create_dir_with(
+                &self,
+                _: &::std::path::Path,
+                _: $crate::Permissions,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                ::std::result::Result::Err($crate::FsError::ReadOnly { operation: "create_dir_with" })
+            }
+        }
+
+        $crate::impl_read_only!($($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        FileType, Fs, FsError, FsPermissions, FsRead, Metadata, Permissions, ReadDirIter,
+        ReadDirOptions,
+    };
+    use std::io::Read;
+    use std::path::Path;
+
+    struct Empty;
+
+    impl FsRead for Empty {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            Err(FsError::not_found("read", path))
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            Err(FsError::not_found("read_to_string", path))
+        }
+        fn read_range(&self, path: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> {
+            Err(FsError::not_found("read_range", path))
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            Ok(path == Path::new("/"))
+        }
+        fn metadata(&self, _: &Path) -> Result<Metadata, FsError> {
+            Ok(Metadata {
+                file_type: FileType::Directory,
+                ..Metadata::default()
+            })
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+            Err(FsError::not_found("open_read", path))
+        }
+    }
+
+    impl_read_only! {
+        impl FsDir for Empty {
+            fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+                if path != Path::new("/") {
+                    return Err(FsError::not_a_directory("read_dir", path));
+                }
+                Ok(ReadDirIter::from_vec(Vec::new()))
+            }
+        }
+        impl FsWrite for Empty;
+        impl FsPermissions for Empty;
+    }
+
+    fn operation(result: Result<impl Sized, FsError>) -> &'static str {
+        match result {
+            Err(FsError::ReadOnly { operation }) => operation,
+            _ => panic!("expected ReadOnly"),
+        }
+    }
+
+    #[test]
+    fn every_mutation_is_read_only() {
+        let fs: &dyn Fs = &Empty;
+        let p = Path::new("/x");
+        assert_eq!(operation(fs.write(p, b"")), "write");
+        assert_eq!(operation(fs.append(p, b"")), "append");
+        assert_eq!(operation(fs.remove_file(p)), "remove_file");
+        assert_eq!(operation(fs.rename(p, p)), "rename");
+        assert_eq!(operation(fs.copy(p, p)), "copy");
+        assert_eq!(
+            operation(fs.copy_with(p, p, Default::default())),
+            "copy_with"
+        );
+        assert_eq!(
+            operation(fs.write_with(p, b"", Default::default())),
+            "write_with"
+        );
+        assert_eq!(operation(fs.truncate(p, 0)), "truncate");
+        assert_eq!(operation(fs.open_write(p)), "open_write");
+        assert_eq!(operation(fs.create_dir(p)), "create_dir");
+        assert_eq!(operation(fs.create_dir_all(p)), "create_dir_all");
+        assert_eq!(operation(fs.remove_dir(p)), "remove_dir");
+        assert_eq!(operation(fs.remove_dir_all(p)), "remove_dir_all");
+        assert_eq!(
+            operation(fs.create_temp_dir_in(Path::new("/"), "t")),
+            "create_dir_with"
+        );
+        assert_eq!(
+            operation(Empty.set_permissions(p, Permissions::default_file())),
+            "set_permissions"
+        );
+        assert_eq!(fs.read_dir(Path::new("/")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn read_dir_with_lists_through_read_dir() {
+        let fs: &dyn Fs = &Empty;
+        let sorted = ReadDirOptions::sorted();
+        assert_eq!(fs.read_dir_with(Path::new("/"), sorted).unwrap().count(), 0);
+        assert!(matches!(
+            fs.read_dir_with(Path::new("/x"), sorted),
+            Err(FsError::NotADirectory { .. })
+        ));
+    }
+}
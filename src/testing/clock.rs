@@ -0,0 +1,79 @@
+//! Manually driven clock for timestamp tests.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Clock;
+
+/// Seconds from the Unix epoch to 2000-01-01T00:00:00Z.
+const Y2K_SECS: u64 = 946_684_800;
+
+/// A [`Clock`] that only moves when told to.
+///
+/// [`MemFs`](super::MemFs) stamps every change with [`now`](Self::now), so
+/// tests can assert exact timestamps and move time forwards (or backwards)
+/// between operations. Clones share the same time, so a test keeps a clone
+/// while the backend owns the original.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::testing::TestClock;
+/// use std::time::Duration;
+///
+/// let clock = TestClock::new();
+/// let start = clock.now();
+///
+/// let view = clock.clone();
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(view.now(), start + Duration::from_secs(60));
+///
+/// clock.set(start);
+/// assert_eq!(view.now(), start);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl TestClock {
+    /// A clock stopped at 2000-01-01T00:00:00Z.
+    pub fn new() -> Self {
+        Self::starting_at(UNIX_EPOCH + Duration::from_secs(Y2K_SECS))
+    }
+
+    /// A clock stopped at `time`.
+    pub fn starting_at(time: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(time)),
+        }
+    }
+
+    /// The current time of this clock.
+    pub fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Move the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+
+    /// Jump to `time`, which may be in the past.
+    pub fn set(&self, time: SystemTime) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = time;
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> SystemTime {
+        TestClock::now(self)
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,470 @@
+//! Structural comparison of two backends.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{DirEntry, FileType, Fs, FsError, MetadataMask};
+
+/// One way in which two trees differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The entry exists only in the left tree.
+    OnlyInLeft(PathBuf),
+    /// The entry exists only in the right tree.
+    OnlyInRight(PathBuf),
+    /// The entry has a different type on each side.
+    FileType {
+        /// Path of the entry.
+        path: PathBuf,
+        /// Type in the left tree.
+        left: FileType,
+        /// Type in the right tree.
+        right: FileType,
+    },
+    /// Both are files, with different contents.
+    Contents {
+        /// Path of the file.
+        path: PathBuf,
+        /// First byte offset at which the contents differ.
+        offset: u64,
+        /// Length of the left file.
+        left_len: u64,
+        /// Length of the right file.
+        right_len: u64,
+    },
+    /// Both are symlinks, pointing at different targets.
+    SymlinkTarget {
+        /// Path of the symlink.
+        path: PathBuf,
+        /// Target in the left tree.
+        left: PathBuf,
+        /// Target in the right tree.
+        right: PathBuf,
+    },
+    /// A metadata field selected for comparison differs.
+    Metadata {
+        /// Path of the entry.
+        path: PathBuf,
+        /// Name of the [`Metadata`](crate::Metadata) field.
+        field: &'static str,
+        /// Left value, formatted for display.
+        left: String,
+        /// Right value, formatted for display.
+        right: String,
+    },
+}
+
+impl Difference {
+    /// Path of the entry that differs.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::OnlyInLeft(path) | Self::OnlyInRight(path) => path,
+            Self::FileType { path, .. }
+            | Self::Contents { path, .. }
+            | Self::SymlinkTarget { path, .. }
+            | Self::Metadata { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path().display();
+        match self {
+            Self::OnlyInLeft(_) => write!(f, "{path}: only in left"),
+            Self::OnlyInRight(_) => write!(f, "{path}: only in right"),
+            Self::FileType { left, right, .. } => {
+                write!(f, "{path}: type {left:?} != {right:?}")
+            }
+            Self::Contents {
+                offset,
+                left_len,
+                right_len,
+                ..
+            } => write!(
+                f,
+                "{path}: contents differ at byte {offset} ({left_len} vs {right_len} bytes)"
+            ),
+            Self::SymlinkTarget { left, right, .. } => {
+                write!(f, "{path}: symlink target {left:?} != {right:?}")
+            }
+            Self::Metadata {
+                field, left, right, ..
+            } => write!(f, "{path}: {field} {left} != {right}"),
+        }
+    }
+}
+
+/// Every [`Difference`] between two trees, in path order.
+///
+/// Returned by [`diff_trees`]. `Display` prints one difference per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    differences: Vec<Difference>,
+}
+
+impl TreeDiff {
+    /// Returns `true` if the trees are equal.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Number of differences.
+    pub fn len(&self) -> usize {
+        self.differences.len()
+    }
+
+    /// The differences, in path order.
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(f, "{difference}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for TreeDiff {
+    type Item = Difference;
+    type IntoIter = std::vec::IntoIter<Difference>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.into_iter()
+    }
+}
+
+/// Compare the types and contents of everything under `/` in two backends.
+///
+/// Symlinks are compared by target, taken from
+/// [`DirEntry::symlink_target`](crate::DirEntry::symlink_target), and are
+/// never followed. FIFOs have no contents to compare.
+///
+/// # Errors
+///
+/// - [`FsError::NotSupported`] if a symlink entry has no target
+/// - Errors from either backend's `read_dir` and `read`
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fs_tree;
+/// use anyfs_backend::testing::{diff_trees, Difference, MemFs};
+/// use std::path::Path;
+///
+/// let (left, right) = (MemFs::new(), MemFs::new());
+/// fs_tree! { "a.txt" => "one", "b.txt" => "x" }.create_in(&left, "/".as_ref()).unwrap();
+/// fs_tree! { "a.txt" => "two" }.create_in(&right, "/".as_ref()).unwrap();
+///
+/// let diff = diff_trees(&left, &right).unwrap();
+/// assert_eq!(diff.len(), 2);
+/// assert_eq!(diff.differences()[1], Difference::OnlyInLeft("/b.txt".into()));
+/// assert_eq!(diff.to_string(), "\
+/// /a.txt: contents differ at byte 0 (3 vs 3 bytes)
+/// /b.txt: only in left
+/// ");
+/// ```
+pub fn diff_trees(left: &dyn Fs, right: &dyn Fs) -> Result<TreeDiff, FsError> {
+    diff_trees_with(left, right, MetadataMask::NONE)
+}
+
+/// Like [`diff_trees`], but also compares the metadata fields selected by
+/// `metadata`.
+///
+/// `links` compares inode numbers, which only makes sense between backends
+/// that assign them deterministically.
+///
+/// # Errors
+///
+/// Same as [`diff_trees`], plus errors from either backend's `metadata`.
+pub fn diff_trees_with(
+    left: &dyn Fs,
+    right: &dyn Fs,
+    metadata: MetadataMask,
+) -> Result<TreeDiff, FsError> {
+    let mut walk = Walk {
+        left,
+        right,
+        metadata,
+        differences: Vec::new(),
+    };
+    let root = Path::new("/");
+    walk.compare_metadata(root)?;
+    walk.compare_dirs(root)?;
+    Ok(TreeDiff {
+        differences: walk.differences,
+    })
+}
+
+struct Walk<'a> {
+    left: &'a dyn Fs,
+    right: &'a dyn Fs,
+    metadata: MetadataMask,
+    differences: Vec<Difference>,
+}
+
+impl Walk<'_> {
+    fn compare_dirs(&mut self, path: &Path) -> Result<(), FsError> {
+        let left = listing(self.left, path)?;
+        let right = listing(self.right, path)?;
+        let names: BTreeSet<&OsString> = left.keys().chain(right.keys()).collect();
+        for name in names {
+            let child = path.join(name);
+            match (left.get(name), right.get(name)) {
+                (Some(l), Some(r)) => self.compare_entries(&child, l, r)?,
+                (Some(_), None) => self.differences.push(Difference::OnlyInLeft(child)),
+                (None, Some(_)) => self.differences.push(Difference::OnlyInRight(child)),
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn compare_entries(&mut self, path: &Path, l: &DirEntry, r: &DirEntry) -> Result<(), FsError> {
+        if l.file_type != r.file_type {
+            self.differences.push(Difference::FileType {
+                path: path.to_path_buf(),
+                left: l.file_type,
+                right: r.file_type,
+            });
+            return Ok(());
+        }
+        match l.file_type {
+            FileType::Directory => {
+                self.compare_metadata(path)?;
+                self.compare_dirs(path)
+            }
+            FileType::File => {
+                let (left, right) = (self.left.read(path)?, self.right.read(path)?);
+                if left != right {
+                    let offset = left
+                        .iter()
+                        .zip(&right)
+                        .position(|(a, b)| a != b)
+                        .unwrap_or_else(|| left.len().min(right.len()));
+                    self.differences.push(Difference::Contents {
+                        path: path.to_path_buf(),
+                        offset: offset as u64,
+                        left_len: left.len() as u64,
+                        right_len: right.len() as u64,
+                    });
+                }
+                self.compare_metadata(path)
+            }
+            FileType::Symlink => {
+                let (Some(left), Some(right)) = (&l.symlink_target, &r.symlink_target) else {
+                    return Err(FsError::NotSupported {
+                        operation: "read_link",
+                    });
+                };
+                if left != right {
+                    self.differences.push(Difference::SymlinkTarget {
+                        path: path.to_path_buf(),
+                        left: left.clone(),
+                        right: right.clone(),
+                    });
+                }
+                Ok(())
+            }
+            FileType::Fifo => self.compare_metadata(path),
+        }
+    }
+
+    fn compare_metadata(&mut self, path: &Path) -> Result<(), FsError> {
+        if self.metadata == MetadataMask::NONE {
+            return Ok(());
+        }
+        let (l, r) = (self.left.metadata(path)?, self.right.metadata(path)?);
+        let mask = self.metadata;
+        if mask.size {
+            self.field(path, "size", l.size, r.size);
+        }
+        if mask.permissions {
+            self.field(
+                path,
+                "permissions",
+                format_args!("{:#o}", l.permissions.mode()),
+                format_args!("{:#o}", r.permissions.mode()),
+            );
+        }
+        if mask.times {
+            self.field(path, "created", l.created, r.created);
+            self.field(path, "modified", l.modified, r.modified);
+            self.field(path, "accessed", l.accessed, r.accessed);
+        }
+        if mask.links {
+            self.field(path, "inode", l.inode, r.inode);
+            self.field(path, "nlink", l.nlink, r.nlink);
+        }
+        Ok(())
+    }
+
+    fn field(
+        &mut self,
+        path: &Path,
+        field: &'static str,
+        left: impl fmt::Debug,
+        right: impl fmt::Debug,
+    ) {
+        let (left, right) = (format!("{left:?}"), format!("{right:?}"));
+        if left != right {
+            self.differences.push(Difference::Metadata {
+                path: path.to_path_buf(),
+                field,
+                left,
+                right,
+            });
+        }
+    }
+}
+
+fn listing(fs: &dyn Fs, path: &Path) -> Result<BTreeMap<OsString, DirEntry>, FsError> {
+    fs.read_dir(path)?
+        .map(|entry| entry.map(|entry| (entry.file_name().to_os_string(), entry)))
+        .collect()
+}
+
+/// Assert that two backends hold the same tree, panicking with every
+/// difference if they do not.
+///
+/// Arguments are `&dyn Fs`-compatible references. An optional third
+/// argument is a [`MetadataMask`] of metadata fields
+/// to compare as well (see [`diff_trees_with`]).
+///
+/// Available with the `testing` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{assert_fs_eq, fs_tree, MetadataMask};
+/// use anyfs_backend::testing::{MemFs, TestClock};
+///
+/// let clock = TestClock::new();
+/// let left = MemFs::with_clock(clock.clone());
+/// let right = MemFs::with_clock(clock);
+/// let fixture = fs_tree! { "a" => { "b.txt" => "hello" } };
+/// fixture.create_in(&left, "/".as_ref()).unwrap();
+/// fixture.create_in(&right, "/".as_ref()).unwrap();
+///
+/// assert_fs_eq!(&left, &right);
+/// assert_fs_eq!(&left, &right, MetadataMask::ALL);
+/// ```
+#[macro_export]
+macro_rules! assert_fs_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_fs_eq!($left, $right, $crate::MetadataMask::NONE)
+    };
+    ($left:expr, $right:expr, $metadata:expr $(,)?) => {
+        match $crate::testing::diff_trees_with($left, $right, $metadata) {
+            ::std::result::Result::Ok(diff) => {
+                if !diff.is_empty() {
+                    panic!("filesystems differ:\n{}", diff);
+                }
+            }
+            ::std::result::Result::Err(err) => {
+                panic!("could not compare filesystems: {}", err);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FsTree, MemFs, TestClock};
+    use crate::{fs_tree, FsPermissions, Permissions};
+    use std::time::Duration;
+
+    fn populated(tree: &FsTree, clock: &TestClock) -> MemFs {
+        let fs = MemFs::with_clock(clock.clone());
+        tree.create_in_with_links(&fs, Path::new("/")).unwrap();
+        fs
+    }
+
+    #[test]
+    fn reports_every_kind_of_structural_difference() {
+        let clock = TestClock::new();
+        let left = populated(
+            &fs_tree! {
+                "same.txt" => "x",
+                "grown.txt" => "abc",
+                "kind" => "file",
+                "link" => FsTree::symlink("a"),
+                "left" => {},
+            },
+            &clock,
+        );
+        let right = populated(
+            &fs_tree! {
+                "same.txt" => "x",
+                "grown.txt" => "abcd",
+                "kind" => {},
+                "link" => FsTree::symlink("b"),
+                "right" => { "deep.txt" => "" },
+            },
+            &clock,
+        );
+
+        let diff = diff_trees(&left, &right).unwrap();
+        let paths: Vec<_> = diff.differences().iter().map(Difference::path).collect();
+        assert_eq!(
+            paths,
+            ["/grown.txt", "/kind", "/left", "/link", "/right"].map(Path::new)
+        );
+        assert_eq!(
+            diff.differences()[0],
+            Difference::Contents {
+                path: "/grown.txt".into(),
+                offset: 3,
+                left_len: 3,
+                right_len: 4,
+            }
+        );
+        assert_eq!(
+            diff.differences()[4],
+            Difference::OnlyInRight("/right".into())
+        );
+    }
+
+    #[test]
+    fn metadata_is_compared_only_when_selected() {
+        let clock = TestClock::new();
+        let tree = fs_tree! { "a.txt" => "x" };
+        let left = populated(&tree, &clock);
+        clock.advance(Duration::from_secs(5));
+        let right = populated(&tree, &clock);
+        right
+            .set_permissions(Path::new("/a.txt"), Permissions::from_mode(0o600))
+            .unwrap();
+
+        assert!(diff_trees(&left, &right).unwrap().is_empty());
+
+        let mask = MetadataMask {
+            permissions: true,
+            ..MetadataMask::NONE
+        };
+        let diff = diff_trees_with(&left, &right, mask).unwrap();
+        assert_eq!(diff.to_string(), "/a.txt: permissions 0o644 != 0o600\n");
+
+        let times = MetadataMask {
+            times: true,
+            ..MetadataMask::NONE
+        };
+        assert!(!diff_trees_with(&left, &right, times).unwrap().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "filesystems differ:\n/a.txt: only in right")]
+    fn assert_fs_eq_panics_with_the_diff() {
+        let clock = TestClock::new();
+        let left = MemFs::with_clock(clock.clone());
+        let right = populated(&fs_tree! { "a.txt" => "" }, &clock);
+        assert_fs_eq!(&left, &right);
+    }
+}
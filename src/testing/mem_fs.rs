@@ -0,0 +1,2028 @@
+//! Complete in-memory backend for tests.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use super::TestClock;
+use crate::{
+    read_dir_inode_by_index, Clock, CopyOptions, DirEntry, FileType, FsDir, FsError, FsExt,
+    FsHandles, FsInode, FsLink, FsLock, FsMaybeFull, FsPermissions, FsRead, FsSequence, FsStats,
+    FsSync, FsWrite, FsXattr, Handle, LockType, Metadata, NameCollisionPolicy, OpenFlags,
+    OverwritePolicy, Permissions, ReadDirIter, ReadDirOptions, SetAttr, StatFs, UsageReport,
+    ROOT_INODE,
+};
+
+/// Maximum number of nested symlinks followed while resolving one path.
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// Largest file a `Vec<u8>` can hold.
+const MAX_FILE_SIZE: u64 = isize::MAX as u64;
+
+/// In-memory filesystem implementing every trait up to [`FsPosix`](crate::FsPosix).
+///
+/// Meant for downstream test suites: instead of copying a mock backend into
+/// every crate, depend on `anyfs-backend` with the `testing` feature and use
+/// `MemFs`.
+///
+/// # Behavior
+///
+/// - **Real tree.** Entries are inodes linked into directories, so hard
+///   links, renames of whole subtrees, symlinks in any path component and
+///   files that stay readable through an open handle after being removed all
+///   behave like a POSIX filesystem.
+/// - **Deterministic inodes.** The root is [`ROOT_INODE`] and every new
+///   entry gets the next number, starting at 2. Inode and handle numbers are
+///   never reused, so the same sequence of operations always produces the
+///   same numbers.
+/// - **Controlled time.** Timestamps come from a [`Clock`], by default a
+///   [`TestClock`] that only moves when the test advances it. Access times
+///   are not updated by reads.
+/// - **Change counters.** Every mutation stamps the entry with a new
+///   [`Metadata::change_counter`], taken from the backend-wide
+///   [`FsSequence::seq`].
+/// - **Sorted listings.** `read_dir` and `list_xattr` return names in
+///   byte order.
+/// - **Bounded sizes.** Writes and truncates that would grow a file past
+///   what memory can hold fail with [`FsError::FileSizeExceeded`] instead
+///   of panicking.
+/// - **Native extras.** `create_dir_with`, `copy_with` (including
+///   `preserve_times`), `dup`, `open_anonymous`/`link_handle`,
+///   `sync_handle` and `mkfifo` work on the tree directly; FIFOs follow the
+///   [non-blocking contract](FsHandles#fifos). Locks are whole-file only, so
+///   the `*_owned` lock methods reject partial ranges.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::testing::{MemFs, TestClock};
+/// use anyfs_backend::{FsDir, FsRead, FsWrite};
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// let clock = TestClock::new();
+/// let fs = MemFs::with_clock(clock.clone());
+///
+/// fs.create_dir(Path::new("/docs")).unwrap();
+/// fs.write(Path::new("/docs/a.txt"), b"v1").unwrap();
+/// let created = fs.metadata(Path::new("/docs/a.txt")).unwrap();
+/// assert_eq!(created.inode, 3);
+///
+/// clock.advance(Duration::from_secs(10));
+/// fs.write(Path::new("/docs/a.txt"), b"v2").unwrap();
+/// let updated = fs.metadata(Path::new("/docs/a.txt")).unwrap();
+/// assert_eq!(updated.modified, created.modified + Duration::from_secs(10));
+/// assert!(updated.changed_since(&created));
+/// ```
+#[derive(Debug)]
+pub struct MemFs {
+    state: Arc<Mutex<State>>,
+    lock_released: Condvar,
+    clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug)]
+enum Content {
+    File(Vec<u8>),
+    Dir(BTreeMap<OsString, u64>),
+    Symlink(PathBuf),
+    /// Bytes written to a FIFO and not read yet.
+    Fifo(VecDeque<u8>),
+}
+
+#[derive(Debug)]
+struct Node {
+    content: Content,
+    permissions: Permissions,
+    created: SystemTime,
+    modified: SystemTime,
+    nlink: u64,
+    /// Directory holding the link used by `inode_to_path`.
+    parent: u64,
+    /// Name of that link.
+    name: OsString,
+    xattrs: BTreeMap<String, Vec<u8>>,
+    change_counter: u64,
+}
+
+#[derive(Debug, Clone)]
+struct OpenFile {
+    inode: u64,
+    flags: OpenFlags,
+    /// Open file shared with `dup`licates; owns the locks.
+    file: u64,
+    /// Opened with `open_anonymous` and not linked yet.
+    anonymous: bool,
+}
+
+#[derive(Debug)]
+struct State {
+    nodes: HashMap<u64, Node>,
+    next_inode: u64,
+    handles: HashMap<u64, OpenFile>,
+    next_handle: u64,
+    /// Lock holders per inode, by open file.
+    locks: HashMap<u64, Vec<(u64, LockType)>>,
+    /// Source of change counters.
+    changes: u64,
+}
+
+impl MemFs {
+    /// Create an empty filesystem (just `/`) with a new [`TestClock`].
+    pub fn new() -> Self {
+        Self::with_clock(TestClock::new())
+    }
+
+    /// Create an empty filesystem stamping times from `clock`.
+    pub fn with_clock(clock: impl Clock + 'static) -> Self {
+        let now = clock.now();
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            ROOT_INODE,
+            Node {
+                content: Content::Dir(BTreeMap::new()),
+                permissions: Permissions::default_dir(),
+                created: now,
+                modified: now,
+                nlink: 1,
+                parent: ROOT_INODE,
+                name: OsString::new(),
+                xattrs: BTreeMap::new(),
+                change_counter: 0,
+            },
+        );
+        Self {
+            state: Arc::new(Mutex::new(State {
+                nodes,
+                next_inode: ROOT_INODE + 1,
+                handles: HashMap::new(),
+                next_handle: 1,
+                locks: HashMap::new(),
+                changes: 0,
+            })),
+            lock_released: Condvar::new(),
+            clock: Arc::new(clock),
+        }
+    }
+
+    /// The clock timestamps are taken from.
+    pub fn clock(&self) -> &dyn Clock {
+        &self.clock
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Shared body of `write`, `append` and `copy`.
+    fn put(&self, path: &Path, data: &[u8], append: bool, op: &'static str) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        match state.walk(path, true, op, 0) {
+            Ok(inode) => {
+                match &mut state.node_mut(inode).content {
+                    Content::File(contents) if append => contents.extend_from_slice(data),
+                    Content::File(contents) => *contents = data.to_vec(),
+                    _ => return Err(FsError::not_a_file(op, path)),
+                }
+                state.touch(inode, now);
+                Ok(())
+            }
+            Err(FsError::NotFound { .. }) => {
+                let (parent, name) = state.parent_of(path, op)?;
+                state.vacant(parent, &name, path, op)?;
+                let content = Content::File(data.to_vec());
+                state.insert(parent, name, content, Permissions::default_file(), now);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Shared body of `read_dir` and `read_dir_with`. Entries come out of a
+    /// `BTreeMap`, so listings are always sorted by name.
+    fn list(&self, path: &Path, op: &'static str) -> Result<ReadDirIter, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, op, 0)?;
+        let entries = state
+            .entries(inode)
+            .ok_or_else(|| FsError::not_a_directory(op, path))?;
+        let listing = entries
+            .iter()
+            .map(|(name, &child)| {
+                let meta = state.metadata(child);
+                let entry = DirEntry {
+                    path: path.join(name),
+                    file_type: meta.file_type,
+                    size: meta.size,
+                    inode: child,
+                    ..DirEntry::default()
+                }
+                .with_name_os(name.clone());
+                Ok(match &state.node(child).content {
+                    Content::Symlink(target) => entry.with_symlink_target(target.clone()),
+                    _ => entry,
+                })
+            })
+            .collect();
+        Ok(ReadDirIter::from_vec(listing))
+    }
+
+    /// Shared body of `write_at` and `append_handle`: writes at `offset`, or
+    /// at the end for `None` and append handles. Returns where it wrote.
+    fn write_handle_at(
+        &self,
+        handle: Handle,
+        data: &[u8],
+        offset: Option<u64>,
+        op: &'static str,
+    ) -> Result<u64, FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let file = state.open_file(handle)?;
+        let (inode, flags) = (file.inode, file.flags);
+        if !flags.write && !flags.append {
+            return Err(FsError::permission_denied(op, state.path_of(inode)));
+        }
+        let start = match (&state.node(inode).content, offset) {
+            (Content::File(_), Some(offset)) if !flags.append => offset,
+            (Content::File(contents), _) => contents.len() as u64,
+            (Content::Fifo(queue), _) => queue.len() as u64,
+            _ => return Err(FsError::InvalidHandle { handle }),
+        };
+        let end = start.saturating_add(data.len() as u64);
+        match &mut state.node_mut(inode).content {
+            Content::File(contents) => {
+                if end > contents.len() as u64 && !resize_file(contents, end) {
+                    return Err(too_large(state.path_of(inode), end));
+                }
+                // `end` fits in memory, so both bounds fit in `usize`
+                contents[start as usize..end as usize].copy_from_slice(data);
+            }
+            Content::Fifo(queue) => queue.extend(data),
+            _ => {}
+        }
+        state.touch(inode, now);
+        Ok(start)
+    }
+
+    /// Shared body of `create_dir` and `create_dir_with`.
+    fn mkdir(&self, path: &Path, perm: Permissions, op: &'static str) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        if state.walk(path, false, op, 0).is_ok() {
+            return Err(FsError::already_exists(op, path));
+        }
+        let (parent, name) = state.parent_of(path, op)?;
+        state.insert(parent, name, Content::Dir(BTreeMap::new()), perm, now);
+        Ok(())
+    }
+}
+
+/// Resize `contents` to `size` bytes, zero-filling. Returns `false`, leaving
+/// `contents` unchanged, if `size` can't be allocated.
+fn resize_file(contents: &mut Vec<u8>, size: u64) -> bool {
+    let Some(len) = usize::try_from(size).ok().filter(|_| size <= MAX_FILE_SIZE) else {
+        return false;
+    };
+    if len > contents.len() && contents.try_reserve_exact(len - contents.len()).is_err() {
+        return false;
+    }
+    contents.resize(len, 0);
+    true
+}
+
+/// [`FsError::FileSizeExceeded`] for growing `path` to `size` bytes.
+fn too_large(path: PathBuf, size: u64) -> FsError {
+    FsError::FileSizeExceeded {
+        path,
+        size,
+        limit: MAX_FILE_SIZE,
+    }
+}
+
+impl Default for MemFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================================
+// Tree Operations
+// ============================================================================
+
+impl State {
+    fn node(&self, inode: u64) -> &Node {
+        &self.nodes[&inode]
+    }
+
+    fn node_mut(&mut self, inode: u64) -> &mut Node {
+        self.nodes
+            .get_mut(&inode)
+            .expect("directory entries point at live nodes")
+    }
+
+    fn entries(&self, dir: u64) -> Option<&BTreeMap<OsString, u64>> {
+        match &self.node(dir).content {
+            Content::Dir(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    fn entries_mut(&mut self, dir: u64) -> &mut BTreeMap<OsString, u64> {
+        match &mut self.node_mut(dir).content {
+            Content::Dir(entries) => entries,
+            _ => unreachable!("only directories have entries"),
+        }
+    }
+
+    fn is_dir(&self, inode: u64) -> bool {
+        matches!(self.node(inode).content, Content::Dir(_))
+    }
+
+    /// Record a content change on `inode`.
+    fn touch(&mut self, inode: u64, now: SystemTime) {
+        self.changes += 1;
+        let changes = self.changes;
+        let node = self.node_mut(inode);
+        node.modified = now;
+        node.change_counter = changes;
+    }
+
+    /// Record an attribute-only change on `inode`.
+    fn touch_attr(&mut self, inode: u64) {
+        self.changes += 1;
+        let changes = self.changes;
+        self.node_mut(inode).change_counter = changes;
+    }
+
+    /// Current path of `inode`, built from parent links.
+    fn path_of(&self, inode: u64) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = inode;
+        while current != ROOT_INODE {
+            let Some(node) = self.nodes.get(&current) else {
+                break;
+            };
+            names.push(node.name.clone());
+            current = node.parent;
+        }
+        let mut path = PathBuf::from("/");
+        path.extend(names.iter().rev());
+        path
+    }
+
+    /// Inodes from the root down to `inode`.
+    fn ancestry(&self, inode: u64) -> Vec<u64> {
+        let mut chain = vec![inode];
+        let mut current = inode;
+        while current != ROOT_INODE {
+            current = self.node(current).parent;
+            chain.push(current);
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Resolve `path` to an inode, following symlinks in every component
+    /// and, if `follow` is set, in the final one.
+    fn walk(
+        &self,
+        path: &Path,
+        follow: bool,
+        op: &'static str,
+        depth: usize,
+    ) -> Result<u64, FsError> {
+        if depth > MAX_SYMLINK_DEPTH {
+            return Err(FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: format!(
+                    "symlink loop detected (exceeded max depth of {MAX_SYMLINK_DEPTH})"
+                ),
+            });
+        }
+
+        let components: Vec<Component<'_>> = path.components().collect();
+        let mut stack = vec![ROOT_INODE];
+        for (index, component) in components.iter().enumerate() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => stack.truncate(1),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                }
+                Component::Normal(name) => {
+                    let dir = *stack.last().unwrap_or(&ROOT_INODE);
+                    let entries = self
+                        .entries(dir)
+                        .ok_or_else(|| FsError::not_a_directory(op, path))?;
+                    let child = *entries
+                        .get(*name)
+                        .ok_or_else(|| FsError::not_found(op, path))?;
+                    let is_last = index + 1 == components.len();
+                    match &self.node(child).content {
+                        Content::Symlink(target) if follow || !is_last => {
+                            let target = if target.has_root() {
+                                target.clone()
+                            } else {
+                                self.path_of(dir).join(target)
+                            };
+                            let resolved = self.walk(&target, true, op, depth + 1)?;
+                            stack = self.ancestry(resolved);
+                        }
+                        _ => stack.push(child),
+                    }
+                }
+            }
+        }
+        Ok(*stack.last().unwrap_or(&ROOT_INODE))
+    }
+
+    /// Directory that would hold `path`, and the final name.
+    fn parent_of(&self, path: &Path, op: &'static str) -> Result<(u64, OsString), FsError> {
+        let name = path.file_name().ok_or_else(|| FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: "path has no final component".to_string(),
+        })?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.walk(parent, true, op, 0)?,
+            _ => ROOT_INODE,
+        };
+        if !self.is_dir(parent) {
+            return Err(FsError::not_a_directory(op, path));
+        }
+        Ok((parent, name.to_os_string()))
+    }
+
+    /// Fail with `AlreadyExists` if `parent` already has an entry `name`.
+    fn vacant(
+        &self,
+        parent: u64,
+        name: &OsStr,
+        path: &Path,
+        op: &'static str,
+    ) -> Result<(), FsError> {
+        match self.entries(parent) {
+            Some(entries) if entries.contains_key(name) => Err(FsError::already_exists(op, path)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Link a new node into `parent` under the vacant `name`.
+    fn insert(
+        &mut self,
+        parent: u64,
+        name: OsString,
+        content: Content,
+        permissions: Permissions,
+        now: SystemTime,
+    ) -> u64 {
+        let inode = self.create(parent, content, permissions, now);
+        let node = self.node_mut(inode);
+        node.nlink = 1;
+        node.name = name.clone();
+        self.entries_mut(parent).insert(name, inode);
+        self.touch(parent, now);
+        inode
+    }
+
+    /// Create a node with no links yet; `parent` only seeds its path.
+    fn create(
+        &mut self,
+        parent: u64,
+        content: Content,
+        permissions: Permissions,
+        now: SystemTime,
+    ) -> u64 {
+        let inode = self.next_inode;
+        self.next_inode += 1;
+        self.nodes.insert(
+            inode,
+            Node {
+                content,
+                permissions,
+                created: now,
+                modified: now,
+                nlink: 0,
+                parent,
+                name: OsString::new(),
+                xattrs: BTreeMap::new(),
+                change_counter: 0,
+            },
+        );
+        self.touch(inode, now);
+        inode
+    }
+
+    /// Remove the entry `name` from `parent`, dropping the node once nothing
+    /// refers to it.
+    fn unlink(&mut self, parent: u64, name: &OsStr, now: SystemTime) {
+        let Some(inode) = self.entries_mut(parent).remove(name) else {
+            return;
+        };
+        self.touch(parent, now);
+
+        let node = self.node_mut(inode);
+        node.nlink -= 1;
+        if node.nlink > 0 {
+            if node.parent == parent && node.name == name {
+                self.relink(inode);
+            }
+            self.touch_attr(inode);
+        } else {
+            self.release(inode);
+        }
+    }
+
+    /// Point `inode`'s parent link at any remaining directory entry for it.
+    fn relink(&mut self, inode: u64) {
+        let other = self
+            .nodes
+            .iter()
+            .find_map(|(&dir, node)| match &node.content {
+                Content::Dir(entries) => entries
+                    .iter()
+                    .find(|(_, &child)| child == inode)
+                    .map(|(name, _)| (dir, name.clone())),
+                _ => None,
+            });
+        if let Some((dir, name)) = other {
+            let node = self.node_mut(inode);
+            node.parent = dir;
+            node.name = name;
+        }
+    }
+
+    /// Drop an unlinked node unless an open handle still refers to it.
+    fn release(&mut self, inode: u64) {
+        let linked = self.nodes.get(&inode).map_or(false, |node| node.nlink > 0);
+        let open = self.handles.values().any(|file| file.inode == inode);
+        if !linked && !open {
+            self.nodes.remove(&inode);
+            self.locks.remove(&inode);
+        }
+    }
+
+    /// Remove everything below the directory `dir`.
+    fn clear_dir(&mut self, dir: u64, now: SystemTime) {
+        let children: Vec<(OsString, u64)> = self
+            .entries(dir)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|(name, &inode)| (name.clone(), inode))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (name, child) in children {
+            if self.is_dir(child) {
+                self.clear_dir(child, now);
+            }
+            self.unlink(dir, &name, now);
+        }
+    }
+
+    fn metadata(&self, inode: u64) -> Metadata {
+        let node = self.node(inode);
+        let (file_type, size, nlink) = match &node.content {
+            Content::File(data) => (FileType::File, data.len() as u64, node.nlink),
+            Content::Symlink(target) => (
+                FileType::Symlink,
+                target.as_os_str().len() as u64,
+                node.nlink,
+            ),
+            Content::Fifo(_) => (FileType::Fifo, 0, node.nlink),
+            Content::Dir(entries) => {
+                let subdirs = entries
+                    .values()
+                    .filter(|&&child| self.is_dir(child))
+                    .count();
+                (FileType::Directory, 0, 2 + subdirs as u64)
+            }
+        };
+        Metadata {
+            file_type,
+            size,
+            permissions: node.permissions,
+            created: node.created,
+            modified: node.modified,
+            accessed: node.modified,
+            inode,
+            nlink,
+            content_tag: None,
+            change_counter: Some(node.change_counter),
+            stored_size: None,
+            storage_class: None,
+            block_size: None,
+            optimal_io_size: None,
+            uid: None,
+            gid: None,
+        }
+    }
+
+    fn open_file(&self, handle: Handle) -> Result<&OpenFile, FsError> {
+        self.handles
+            .get(&handle.0)
+            .ok_or(FsError::InvalidHandle { handle })
+    }
+
+    /// Whether the open `file` may take `lock` on `inode` right now.
+    fn lock_compatible(&self, inode: u64, file: u64, lock: LockType) -> bool {
+        self.locks.get(&inode).map_or(true, |holders| {
+            holders
+                .iter()
+                .filter(|(holder, _)| *holder != file)
+                .all(|(_, held)| lock == LockType::Shared && *held == LockType::Shared)
+        })
+    }
+
+    fn record_lock(&mut self, inode: u64, file: u64, lock: LockType) {
+        let holders = self.locks.entry(inode).or_default();
+        holders.retain(|(holder, _)| *holder != file);
+        holders.push((file, lock));
+    }
+
+    fn drop_locks(&mut self, file: u64) {
+        for holders in self.locks.values_mut() {
+            holders.retain(|(holder, _)| *holder != file);
+        }
+        self.locks.retain(|_, holders| !holders.is_empty());
+    }
+
+    /// Whether any handle has `inode` open for writing.
+    fn has_writer(&self, inode: u64) -> bool {
+        self.handles
+            .values()
+            .any(|file| file.inode == inode && (file.flags.write || file.flags.append))
+    }
+}
+
+/// Writer returned by [`MemFs::open_write`]; appends to the file it opened.
+struct MemWriter {
+    state: Arc<Mutex<State>>,
+    clock: Arc<dyn Clock>,
+    inode: u64,
+}
+
+impl Write for MemWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state
+            .nodes
+            .get_mut(&self.inode)
+            .map(|node| &mut node.content)
+        {
+            Some(Content::File(data)) => data.extend_from_slice(buf),
+            _ => return Err(io::Error::new(io::ErrorKind::NotFound, "file was removed")),
+        }
+        state.touch(self.inode, now);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Trait Implementations
+// ============================================================================
+
+impl FsRead for MemFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "read", 0)?;
+        match &state.node(inode).content {
+            Content::File(data) => Ok(data.clone()),
+            _ => Err(FsError::not_a_file("read", path)),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        let data = self
+            .read(path)
+            .map_err(|e| e.with_operation("read_to_string"))?;
+        String::from_utf8(data).map_err(|e| FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: e.to_string(),
+        })
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "read_range", 0)?;
+        match &state.node(inode).content {
+            Content::File(data) => {
+                let start = usize::try_from(offset)
+                    .unwrap_or(usize::MAX)
+                    .min(data.len());
+                let end = start.saturating_add(len).min(data.len());
+                Ok(data[start..end].to_vec())
+            }
+            _ => Err(FsError::not_a_file("read_range", path)),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        match self.state().walk(path, true, "exists", 0) {
+            Ok(_) => Ok(true),
+            Err(FsError::NotFound { .. }) | Err(FsError::NotADirectory { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "metadata", 0)?;
+        Ok(state.metadata(inode))
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        let data = self.read(path).map_err(|e| e.with_operation("open_read"))?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+}
+
+impl FsWrite for MemFs {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.put(path, data, false, "write")
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.put(path, data, true, "append")
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = state.walk(path, false, "remove_file", 0)?;
+        if state.is_dir(inode) {
+            return Err(FsError::not_a_file("remove_file", path));
+        }
+        let (parent, name) = state.parent_of(path, "remove_file")?;
+        state.unlink(parent, &name, now);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let source = state.walk(from, false, "rename", 0)?;
+        let (from_parent, from_name) = state.parent_of(from, "rename")?;
+        let (to_parent, to_name) = state.parent_of(to, "rename")?;
+
+        if state.is_dir(source) && state.ancestry(to_parent).contains(&source) {
+            return Err(FsError::InvalidData {
+                path: to.to_path_buf(),
+                details: "cannot move a directory into itself".to_string(),
+            });
+        }
+
+        let existing = state.entries_mut(to_parent).get(&to_name).copied();
+        if let Some(existing) = existing {
+            if existing == source {
+                return Ok(());
+            }
+            match (state.is_dir(source), state.is_dir(existing)) {
+                (false, true) => return Err(FsError::not_a_file("rename", to)),
+                (true, false) => return Err(FsError::not_a_directory("rename", to)),
+                (true, true) if state.entries(existing).map_or(false, |e| !e.is_empty()) => {
+                    return Err(FsError::directory_not_empty("rename", to));
+                }
+                _ => {}
+            }
+            state.unlink(to_parent, &to_name, now);
+        }
+
+        state.entries_mut(from_parent).remove(&from_name);
+        state.entries_mut(to_parent).insert(to_name.clone(), source);
+        let node = state.node_mut(source);
+        if node.parent == from_parent && node.name == from_name {
+            node.parent = to_parent;
+            node.name = to_name;
+        }
+        state.touch_attr(source);
+        state.touch(from_parent, now);
+        state.touch(to_parent, now);
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let data = self.read(from).map_err(|e| e.with_operation("copy"))?;
+        self.put(to, &data, false, "copy")
+    }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let source = state.walk(from, true, "copy_with", 0)?;
+        let data = match &state.node(source).content {
+            Content::File(data) => data.clone(),
+            _ => return Err(FsError::not_a_file("copy_with", from)),
+        };
+        let size = data.len() as u64;
+        let target = match state.walk(to, true, "copy_with", 0) {
+            Ok(_) if options.overwrite == OverwritePolicy::Fail => {
+                return Err(FsError::already_exists("copy_with", to));
+            }
+            Ok(_) if options.overwrite == OverwritePolicy::Skip => return Ok(0),
+            Ok(target) => {
+                match &mut state.node_mut(target).content {
+                    Content::File(contents) => *contents = data,
+                    _ => return Err(FsError::not_a_file("copy_with", to)),
+                }
+                state.touch(target, now);
+                target
+            }
+            Err(FsError::NotFound { .. }) => {
+                let (parent, name) = state.parent_of(to, "copy_with")?;
+                state.vacant(parent, &name, to, "copy_with")?;
+                let content = Content::File(data);
+                state.insert(parent, name, content, Permissions::default_file(), now)
+            }
+            Err(e) => return Err(e),
+        };
+        let (permissions, modified) = {
+            let node = state.node(source);
+            (node.permissions, node.modified)
+        };
+        let copy = state.node_mut(target);
+        if options.preserve_permissions {
+            copy.permissions = permissions;
+        }
+        if options.preserve_times {
+            copy.modified = modified;
+        }
+        Ok(size)
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = state.walk(path, true, "truncate", 0)?;
+        match &mut state.node_mut(inode).content {
+            Content::File(data) => {
+                if !resize_file(data, size) {
+                    return Err(too_large(path.to_path_buf(), size));
+                }
+            }
+            _ => return Err(FsError::not_a_file("truncate", path)),
+        }
+        state.touch(inode, now);
+        Ok(())
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.put(path, &[], false, "open_write")?;
+        let inode = self.state().walk(path, true, "open_write", 0)?;
+        Ok(Box::new(MemWriter {
+            state: Arc::clone(&self.state),
+            clock: Arc::clone(&self.clock),
+            inode,
+        }))
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        let attr = SetAttr {
+            atime,
+            mtime,
+            ..SetAttr::default()
+        };
+        match self.set_attr(self.path_to_inode(path)?, attr) {
+            Err(FsError::NotSupported { .. }) => Err(FsError::NotSupported {
+                operation: "set_times",
+            }),
+            other => other.map(drop),
+        }
+    }
+}
+
+impl FsDir for MemFs {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        self.list(path, "read_dir")
+    }
+
+    fn read_dir_with(&self, path: &Path, _: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        self.list(path, "read_dir_with")
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.mkdir(path, Permissions::default_dir(), "create_dir")
+    }
+
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        self.mkdir(path, perm, "create_dir_with")
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let mut partial = PathBuf::new();
+        for component in path.components() {
+            partial.push(component);
+            if !matches!(component, Component::Normal(_)) {
+                continue;
+            }
+            match self.metadata(&partial) {
+                Ok(meta) if meta.is_dir() => {}
+                Ok(_) => return Err(FsError::not_a_directory("create_dir_all", &partial)),
+                Err(FsError::NotFound { .. }) => self
+                    .create_dir(&partial)
+                    .map_err(|e| e.with_operation("create_dir_all"))?,
+                Err(e) => return Err(e.with_operation("create_dir_all")),
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = state.walk(path, false, "remove_dir", 0)?;
+        match state.entries(inode) {
+            None => return Err(FsError::not_a_directory("remove_dir", path)),
+            Some(entries) if !entries.is_empty() => {
+                return Err(FsError::directory_not_empty("remove_dir", path));
+            }
+            Some(_) => {}
+        }
+        if inode == ROOT_INODE {
+            return Err(FsError::permission_denied("remove_dir", path));
+        }
+        let (parent, name) = state.parent_of(path, "remove_dir")?;
+        state.unlink(parent, &name, now);
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = state.walk(path, false, "remove_dir_all", 0)?;
+        if !state.is_dir(inode) {
+            return Err(FsError::not_a_directory("remove_dir_all", path));
+        }
+        if inode == ROOT_INODE {
+            return Err(FsError::permission_denied("remove_dir_all", path));
+        }
+        state.clear_dir(inode, now);
+        let (parent, name) = state.parent_of(path, "remove_dir_all")?;
+        state.unlink(parent, &name, now);
+        Ok(())
+    }
+
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "dir_len", 0)?;
+        state
+            .entries(inode)
+            .map(|entries| entries.len() as u64)
+            .ok_or_else(|| FsError::not_a_directory("dir_len", path))
+    }
+
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "is_dir_empty", 0)?;
+        state
+            .entries(inode)
+            .map(|entries| entries.is_empty())
+            .ok_or_else(|| FsError::not_a_directory("is_dir_empty", path))
+    }
+}
+
+impl FsLink for MemFs {
+    fn symlink(&self, target: &Path, link: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let (parent, name) = state.parent_of(link, "symlink")?;
+        state.vacant(parent, &name, link, "symlink")?;
+        let content = Content::Symlink(target.to_path_buf());
+        state.insert(parent, name, content, Permissions::from_mode(0o777), now);
+        Ok(())
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = state.walk(original, false, "hard_link", 0)?;
+        if state.is_dir(inode) {
+            return Err(FsError::not_a_file("hard_link", original));
+        }
+        let (parent, name) = state.parent_of(link, "hard_link")?;
+        state.vacant(parent, &name, link, "hard_link")?;
+        state.entries_mut(parent).insert(name, inode);
+        state.node_mut(inode).nlink += 1;
+        state.touch_attr(inode);
+        state.touch(parent, now);
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, false, "read_link", 0)?;
+        match &state.node(inode).content {
+            Content::Symlink(target) => Ok(target.clone()),
+            _ => Err(FsError::InvalidData {
+                path: path.to_path_buf(),
+                details: "not a symlink".to_string(),
+            }),
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, false, "symlink_metadata", 0)?;
+        Ok(state.metadata(inode))
+    }
+}
+
+impl FsPermissions for MemFs {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let mut state = self.state();
+        let inode = state.walk(path, true, "set_permissions", 0)?;
+        state.node_mut(inode).permissions = perm;
+        state.touch_attr(inode);
+        Ok(())
+    }
+}
+
+impl FsSync for MemFs {
+    fn sync(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn fsync(&self, path: &Path) -> Result<(), FsError> {
+        self.state().walk(path, true, "fsync", 0).map(|_| ())
+    }
+
+    fn sync_handle(&self, handle: Handle) -> Result<(), FsError> {
+        self.state().open_file(handle).map(|_| ())
+    }
+}
+
+impl FsStats for MemFs {
+    fn statfs(&self) -> Result<StatFs, FsError> {
+        let state = self.state();
+        let used_bytes = state
+            .nodes
+            .values()
+            .map(|node| match &node.content {
+                Content::File(data) => data.len() as u64,
+                _ => 0,
+            })
+            .sum();
+        Ok(StatFs {
+            total_bytes: 0,
+            used_bytes,
+            available_bytes: u64::MAX,
+            total_inodes: 0,
+            used_inodes: state.nodes.len() as u64,
+            available_inodes: u64::MAX,
+            block_size: 4096,
+            max_name_len: 255,
+            name_collision: NameCollisionPolicy::Distinct,
+        })
+    }
+
+    fn usage(&self, path: &Path) -> Result<UsageReport, FsError> {
+        self.usage_by_walk(path)
+    }
+}
+
+impl FsMaybeFull for MemFs {
+    fn as_link(&self) -> Option<&dyn FsLink> {
+        Some(self)
+    }
+
+    fn as_permissions(&self) -> Option<&dyn FsPermissions> {
+        Some(self)
+    }
+
+    fn as_sync(&self) -> Option<&dyn FsSync> {
+        Some(self)
+    }
+
+    fn as_stats(&self) -> Option<&dyn FsStats> {
+        Some(self)
+    }
+}
+
+impl FsInode for MemFs {
+    fn path_to_inode(&self, path: &Path) -> Result<u64, FsError> {
+        self.state().walk(path, false, "path_to_inode", 0)
+    }
+
+    fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
+        let state = self.state();
+        match state.nodes.get(&inode) {
+            Some(node) if node.nlink > 0 => Ok(state.path_of(inode)),
+            _ => Err(FsError::InodeNotFound { inode }),
+        }
+    }
+
+    fn lookup(&self, parent_inode: u64, name: &OsStr) -> Result<u64, FsError> {
+        let state = self.state();
+        if !state.nodes.contains_key(&parent_inode) {
+            return Err(FsError::InodeNotFound {
+                inode: parent_inode,
+            });
+        }
+        let parent = state.path_of(parent_inode);
+        let entries = state
+            .entries(parent_inode)
+            .ok_or_else(|| FsError::not_a_directory("lookup", &parent))?;
+        entries
+            .get(name)
+            .copied()
+            .ok_or_else(|| FsError::not_found("lookup", parent.join(name)))
+    }
+
+    fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError> {
+        let state = self.state();
+        if !state.nodes.contains_key(&inode) {
+            return Err(FsError::InodeNotFound { inode });
+        }
+        Ok(state.metadata(inode))
+    }
+
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        // Entries are kept sorted by name, so positions are stable
+        read_dir_inode_by_index(self, inode, offset)
+    }
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        if !state.nodes.contains_key(&inode) {
+            return Err(FsError::InodeNotFound { inode });
+        }
+        // No owners and no separate access time; reject before changing anything
+        if attr.uid.is_some() || attr.gid.is_some() || attr.atime.is_some() {
+            return Err(FsError::NotSupported {
+                operation: "set_attr",
+            });
+        }
+        if let Some(size) = attr.size {
+            let path = state.path_of(inode);
+            match &mut state.node_mut(inode).content {
+                Content::File(data) => {
+                    if !resize_file(data, size) {
+                        return Err(too_large(path, size));
+                    }
+                }
+                _ => return Err(FsError::not_a_file("set_attr", path)),
+            }
+            state.touch(inode, now);
+        }
+        if let Some(mode) = attr.mode {
+            state.node_mut(inode).permissions = mode;
+            state.touch_attr(inode);
+        }
+        if let Some(mtime) = attr.mtime {
+            state.node_mut(inode).modified = mtime;
+            state.touch_attr(inode);
+        }
+        Ok(state.metadata(inode))
+    }
+}
+
+impl FsSequence for MemFs {
+    fn seq(&self) -> Result<u64, FsError> {
+        Ok(self.state().changes)
+    }
+}
+
+impl FsHandles for MemFs {
+    fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let inode = match state.walk(path, true, "open", 0) {
+            Ok(_) if flags.create_new => return Err(FsError::already_exists("open", path)),
+            Ok(inode) => {
+                let truncate = flags.truncate && (flags.write || flags.append);
+                let truncated = match &mut state.node_mut(inode).content {
+                    Content::File(data) if truncate => {
+                        data.clear();
+                        true
+                    }
+                    Content::File(_) | Content::Fifo(_) => false,
+                    _ => return Err(FsError::not_a_file("open", path)),
+                };
+                if truncated {
+                    state.touch(inode, now);
+                }
+                inode
+            }
+            Err(FsError::NotFound { .. }) if flags.create || flags.create_new => {
+                let (parent, name) = state.parent_of(path, "open")?;
+                state.vacant(parent, &name, path, "open")?;
+                let content = Content::File(Vec::new());
+                let perm = flags.mode.unwrap_or_default();
+                state.insert(parent, name, content, perm, now)
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(state.add_handle(inode, flags))
+    }
+
+    fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+        let mut state = self.state();
+        let file = state.open_file(handle)?;
+        let (inode, readable) = (file.inode, file.flags.read);
+        if !readable {
+            return Err(FsError::permission_denied("read_at", state.path_of(inode)));
+        }
+        if matches!(&state.node(inode).content, Content::Fifo(queue) if queue.is_empty()) {
+            if state.has_writer(inode) {
+                return Err(FsError::TimedOut {
+                    path: state.path_of(inode),
+                    operation: "read_at",
+                });
+            }
+            return Ok(0);
+        }
+        match &mut state.node_mut(inode).content {
+            Content::File(data) => {
+                let start = usize::try_from(offset)
+                    .unwrap_or(usize::MAX)
+                    .min(data.len());
+                let n = buf.len().min(data.len() - start);
+                buf[..n].copy_from_slice(&data[start..start + n]);
+                Ok(n)
+            }
+            Content::Fifo(queue) => {
+                let n = buf.len().min(queue.len());
+                for (slot, byte) in buf.iter_mut().zip(queue.drain(..n)) {
+                    *slot = byte;
+                }
+                Ok(n)
+            }
+            _ => Err(FsError::InvalidHandle { handle }),
+        }
+    }
+
+    fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError> {
+        self.write_handle_at(handle, data, Some(offset), "write_at")?;
+        Ok(data.len())
+    }
+
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        self.write_handle_at(handle, data, None, "append_handle")
+    }
+
+    fn close(&self, handle: Handle) -> Result<(), FsError> {
+        let mut state = self.state();
+        let file = state
+            .handles
+            .remove(&handle.0)
+            .ok_or(FsError::InvalidHandle { handle })?;
+        // Locks belong to the open file and outlive all but its last handle
+        if !state.handles.values().any(|other| other.file == file.file) {
+            state.drop_locks(file.file);
+        }
+        state.release(file.inode);
+        self.lock_released.notify_all();
+        Ok(())
+    }
+
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        let mut state = self.state();
+        let file = state.open_file(handle)?.clone();
+        Ok(state.add_open_file(file))
+    }
+
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let parent = state.walk(dir, true, "open_anonymous", 0)?;
+        if !state.is_dir(parent) {
+            return Err(FsError::not_a_directory("open_anonymous", dir));
+        }
+        let perm = flags.mode.unwrap_or_default();
+        let inode = state.create(parent, Content::File(Vec::new()), perm, now);
+        let handle = state.add_handle(inode, flags);
+        if let Some(file) = state.handles.get_mut(&handle.0) {
+            file.anonymous = true;
+        }
+        Ok(handle)
+    }
+
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let file = state.open_file(handle)?;
+        if !file.anonymous {
+            return Err(FsError::InvalidHandle { handle });
+        }
+        let (inode, shared) = (file.inode, file.file);
+        let (parent, name) = state.parent_of(path, "link_handle")?;
+        state.vacant(parent, &name, path, "link_handle")?;
+        state.entries_mut(parent).insert(name.clone(), inode);
+        let node = state.node_mut(inode);
+        node.nlink = 1;
+        node.parent = parent;
+        node.name = name;
+        state.touch_attr(inode);
+        state.touch(parent, now);
+        for file in state
+            .handles
+            .values_mut()
+            .filter(|file| file.file == shared)
+        {
+            file.anonymous = false;
+        }
+        Ok(())
+    }
+
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let (parent, name) = state.parent_of(path, "mkfifo")?;
+        state.vacant(parent, &name, path, "mkfifo")?;
+        state.insert(parent, name, Content::Fifo(VecDeque::new()), perm, now);
+        Ok(())
+    }
+}
+
+impl State {
+    /// Open `inode` as a new open file.
+    fn add_handle(&mut self, inode: u64, flags: OpenFlags) -> Handle {
+        let file = OpenFile {
+            inode,
+            flags,
+            file: self.next_handle,
+            anonymous: false,
+        };
+        self.add_open_file(file)
+    }
+
+    /// Register a handle to `file`, which may already have other handles.
+    fn add_open_file(&mut self, file: OpenFile) -> Handle {
+        let id = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(id, file);
+        Handle(id)
+    }
+}
+
+impl FsLock for MemFs {
+    fn lock(&self, handle: Handle, lock: LockType) -> Result<(), FsError> {
+        let mut state = self.state();
+        loop {
+            let OpenFile { inode, file, .. } = *state.open_file(handle)?;
+            if state.lock_compatible(inode, file, lock) {
+                state.record_lock(inode, file, lock);
+                return Ok(());
+            }
+            state = self
+                .lock_released
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn try_lock(&self, handle: Handle, lock: LockType) -> Result<bool, FsError> {
+        let mut state = self.state();
+        let OpenFile { inode, file, .. } = *state.open_file(handle)?;
+        if !state.lock_compatible(inode, file, lock) {
+            return Ok(false);
+        }
+        state.record_lock(inode, file, lock);
+        Ok(true)
+    }
+
+    fn unlock(&self, handle: Handle) -> Result<(), FsError> {
+        let mut state = self.state();
+        let file = state.open_file(handle)?.file;
+        state.drop_locks(file);
+        self.lock_released.notify_all();
+        Ok(())
+    }
+}
+
+impl FsXattr for MemFs {
+    fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "get_xattr", 0)?;
+        state
+            .node(inode)
+            .xattrs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| FsError::XattrNotFound {
+                path: path.to_path_buf(),
+                name: name.to_string(),
+            })
+    }
+
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
+        let mut state = self.state();
+        let inode = state.walk(path, true, "set_xattr", 0)?;
+        state
+            .node_mut(inode)
+            .xattrs
+            .insert(name.to_string(), value.to_vec());
+        state.touch_attr(inode);
+        Ok(())
+    }
+
+    fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
+        let mut state = self.state();
+        let inode = state.walk(path, true, "remove_xattr", 0)?;
+        if state.node_mut(inode).xattrs.remove(name).is_none() {
+            return Err(FsError::XattrNotFound {
+                path: path.to_path_buf(),
+                name: name.to_string(),
+            });
+        }
+        state.touch_attr(inode);
+        Ok(())
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
+        let state = self.state();
+        let inode = state.walk(path, true, "list_xattr", 0)?;
+        Ok(state.node(inode).xattrs.keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsPosix;
+    use std::time::Duration;
+
+    fn assert_posix<B: FsPosix>(_: &B) {}
+
+    #[test]
+    fn implements_fs_posix() {
+        assert_posix(&MemFs::new());
+    }
+
+    #[test]
+    fn maybe_full_exposes_every_component() {
+        let fs: &dyn FsMaybeFull = &MemFs::new();
+        let links = fs.as_link().unwrap();
+        links.symlink(Path::new("/t"), Path::new("/l")).unwrap();
+        assert_eq!(links.read_link(Path::new("/l")).unwrap(), Path::new("/t"));
+        assert!(fs.as_permissions().is_some());
+        assert!(fs.as_sync().is_some());
+        assert!(fs.as_stats().is_some());
+    }
+
+    #[test]
+    fn sizes_past_memory_fail_instead_of_panicking() {
+        let fs = MemFs::new();
+        let path = Path::new("/f");
+        fs.write(path, b"data").unwrap();
+        let handle = fs.open(path, OpenFlags::READ_WRITE).unwrap();
+
+        for offset in [u64::MAX, u64::MAX - 2, MAX_FILE_SIZE] {
+            assert!(matches!(
+                fs.write_at(handle, b"xyz", offset),
+                Err(FsError::FileSizeExceeded {
+                    limit: MAX_FILE_SIZE,
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(
+            fs.truncate(path, u64::MAX),
+            Err(FsError::FileSizeExceeded { size: u64::MAX, .. })
+        ));
+        let inode = fs.path_to_inode(path).unwrap();
+        assert!(matches!(
+            fs.set_attr(
+                inode,
+                SetAttr {
+                    size: Some(u64::MAX),
+                    ..SetAttr::default()
+                }
+            ),
+            Err(FsError::FileSizeExceeded { .. })
+        ));
+        assert_eq!(fs.read(path).unwrap(), b"data");
+    }
+
+    #[test]
+    fn inodes_are_allocated_in_order() {
+        let fs = MemFs::new();
+        fs.create_dir(Path::new("/a")).unwrap();
+        fs.write(Path::new("/a/f"), b"").unwrap();
+        fs.remove_file(Path::new("/a/f")).unwrap();
+        fs.write(Path::new("/a/g"), b"").unwrap();
+
+        assert_eq!(fs.path_to_inode(Path::new("/")).unwrap(), ROOT_INODE);
+        assert_eq!(fs.path_to_inode(Path::new("/a")).unwrap(), 2);
+        assert_eq!(fs.path_to_inode(Path::new("/a/g")).unwrap(), 4);
+    }
+
+    #[test]
+    fn read_dir_with_lists_natively_in_name_order() {
+        let fs = MemFs::new();
+        for name in ["c", "a", "b"] {
+            fs.write(&Path::new("/").join(name), b"").unwrap();
+        }
+
+        let names: Vec<_> = fs
+            .read_dir_with(Path::new("/"), ReadDirOptions::default())
+            .unwrap()
+            .map(|entry| entry.unwrap().name)
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert!(matches!(
+            fs.read_dir_with(Path::new("/a"), ReadDirOptions::sorted()),
+            Err(FsError::NotADirectory { .. })
+        ));
+    }
+
+    #[test]
+    fn read_dir_inode_resumes_from_cookie() {
+        let fs = MemFs::new();
+        for name in ["c", "a", "b"] {
+            fs.write(&Path::new("/").join(name), b"").unwrap();
+        }
+
+        let all = fs.read_dir_inode(ROOT_INODE, 0).unwrap();
+        let names: Vec<_> = all.iter().map(|(e, _)| e.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+
+        let rest = fs.read_dir_inode(ROOT_INODE, all[0].1).unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].0.name, "b");
+        assert!(fs.read_dir_inode(ROOT_INODE, all[2].1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_attr_applies_all_or_nothing() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/f"), b"hello").unwrap();
+        let inode = fs.path_to_inode(Path::new("/f")).unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(7);
+
+        let meta = fs
+            .set_attr(
+                inode,
+                SetAttr {
+                    mode: Some(Permissions::from_mode(0o600)),
+                    size: Some(2),
+                    mtime: Some(mtime),
+                    ..SetAttr::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(meta.permissions.mode(), 0o600);
+        assert_eq!(meta.size, 2);
+        assert_eq!(meta.modified, mtime);
+
+        let result = fs.set_attr(
+            inode,
+            SetAttr {
+                size: Some(0),
+                uid: Some(1000),
+                ..SetAttr::default()
+            },
+        );
+        assert!(matches!(result, Err(FsError::NotSupported { .. })));
+        assert_eq!(fs.read(Path::new("/f")).unwrap(), b"he");
+    }
+
+    #[test]
+    fn timestamps_follow_the_clock() {
+        let clock = TestClock::new();
+        let fs = MemFs::with_clock(clock.clone());
+        let start = clock.now();
+
+        fs.write(Path::new("/f"), b"1").unwrap();
+        clock.advance(Duration::from_secs(5));
+        fs.append(Path::new("/f"), b"2").unwrap();
+
+        let meta = fs.metadata(Path::new("/f")).unwrap();
+        assert_eq!(meta.created, start);
+        assert_eq!(meta.modified, start + Duration::from_secs(5));
+        assert_eq!(
+            fs.metadata(Path::new("/")).unwrap().modified,
+            start,
+            "appending doesn't touch the parent"
+        );
+    }
+
+    #[test]
+    fn any_clock_can_drive_timestamps() {
+        let start = SystemTime::UNIX_EPOCH;
+        let fs = MemFs::with_clock(crate::SteppingClock::new(start, Duration::from_secs(1)));
+        fs.write(Path::new("/a"), b"").unwrap();
+        fs.write(Path::new("/b"), b"").unwrap();
+
+        let a = fs.metadata(Path::new("/a")).unwrap().modified;
+        let b = fs.metadata(Path::new("/b")).unwrap().modified;
+        assert_eq!(b.duration_since(a).unwrap(), Duration::from_secs(1));
+        assert!(fs.clock().now() > b);
+    }
+
+    #[test]
+    fn seq_orders_mutations() {
+        let fs = MemFs::new();
+        let start = fs.seq().unwrap();
+        fs.write(Path::new("/a"), b"1").unwrap();
+        let after_write = fs.seq().unwrap();
+        assert!(after_write > start);
+        let counter = fs.metadata(Path::new("/a")).unwrap().change_counter;
+        assert!(counter > Some(start) && counter <= Some(after_write));
+
+        fs.read(Path::new("/a")).unwrap();
+        assert_eq!(fs.seq().unwrap(), after_write, "reads don't count");
+        fs.set_permissions(Path::new("/a"), Permissions::from_mode(0o600))
+            .unwrap();
+        assert!(fs.seq().unwrap() > after_write);
+    }
+
+    #[test]
+    fn rename_moves_subtree_and_keeps_inodes() {
+        let fs = MemFs::new();
+        fs.create_dir_all(Path::new("/a/b")).unwrap();
+        fs.write(Path::new("/a/b/f"), b"x").unwrap();
+        let inode = fs.path_to_inode(Path::new("/a/b/f")).unwrap();
+
+        fs.rename(Path::new("/a"), Path::new("/z")).unwrap();
+
+        assert_eq!(fs.read(Path::new("/z/b/f")).unwrap(), b"x");
+        assert_eq!(fs.inode_to_path(inode).unwrap(), PathBuf::from("/z/b/f"));
+        assert!(!fs.exists(Path::new("/a")).unwrap());
+        assert!(matches!(
+            fs.rename(Path::new("/z"), Path::new("/z/b/inner")),
+            Err(FsError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn dir_len_matches_listing() {
+        let fs = MemFs::new();
+        fs.create_dir_all(Path::new("/d/sub")).unwrap();
+        fs.write(Path::new("/d/f"), b"").unwrap();
+
+        assert_eq!(fs.dir_len(Path::new("/d")).unwrap(), 2);
+        assert!(!fs.is_dir_empty(Path::new("/d")).unwrap());
+        assert!(fs.is_dir_empty(Path::new("/d/sub")).unwrap());
+        assert!(matches!(
+            fs.dir_len(Path::new("/d/f")),
+            Err(FsError::NotADirectory { .. })
+        ));
+    }
+
+    #[test]
+    fn usage_splits_bytes_by_child() {
+        let fs = MemFs::new();
+        fs.create_dir_all(Path::new("/home/a/deep")).unwrap();
+        fs.create_dir(Path::new("/home/empty")).unwrap();
+        fs.write(Path::new("/home/a/deep/big"), &[0; 300]).unwrap();
+        fs.write(Path::new("/home/a/small"), &[0; 20]).unwrap();
+        fs.write(Path::new("/home/top"), &[0; 100]).unwrap();
+
+        let report = fs.usage(Path::new("/home")).unwrap();
+        assert_eq!(report.bytes, 420);
+        assert_eq!(report.files, 3);
+        assert_eq!(report.dirs, 3);
+        assert_eq!(
+            report.by_child,
+            [
+                ("a".to_string(), 320),
+                ("top".to_string(), 100),
+                ("empty".to_string(), 0)
+            ]
+        );
+
+        let file = fs.usage(Path::new("/home/top")).unwrap();
+        assert_eq!((file.bytes, file.files, file.dirs), (100, 1, 0));
+        assert!(file.by_child.is_empty());
+    }
+
+    #[test]
+    fn usage_skips_symlinks() {
+        let fs = MemFs::new();
+        fs.create_dir(Path::new("/home")).unwrap();
+        fs.write(Path::new("/home/data"), &[0; 50]).unwrap();
+        fs.symlink(Path::new("/home/data"), Path::new("/home/alias"))
+            .unwrap();
+        fs.symlink(Path::new("/home"), Path::new("/home/loop"))
+            .unwrap();
+
+        let report = fs.usage(Path::new("/home")).unwrap();
+        assert_eq!((report.bytes, report.files, report.dirs), (50, 1, 0));
+        assert_eq!(report.by_child, [("data".to_string(), 50)]);
+    }
+
+    #[test]
+    fn rename_replaces_destination_atomically() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/current"), b"0").unwrap();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 1..200u32 {
+                    let next = Path::new("/next");
+                    fs.write(next, i.to_string().as_bytes()).unwrap();
+                    fs.rename(next, Path::new("/current")).unwrap();
+                }
+            });
+            for _ in 0..200 {
+                // Always the old or the new file, never missing
+                fs.read(Path::new("/current")).unwrap();
+            }
+        });
+        assert_eq!(fs.read(Path::new("/current")).unwrap(), b"199");
+    }
+
+    #[test]
+    fn hard_links_share_content_until_last_unlink() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/one"), b"data").unwrap();
+        fs.hard_link(Path::new("/one"), Path::new("/two")).unwrap();
+        assert_eq!(fs.metadata(Path::new("/two")).unwrap().nlink, 2);
+
+        fs.remove_file(Path::new("/one")).unwrap();
+        let inode = fs.path_to_inode(Path::new("/two")).unwrap();
+        assert_eq!(fs.inode_to_path(inode).unwrap(), PathBuf::from("/two"));
+        assert_eq!(fs.read(Path::new("/two")).unwrap(), b"data");
+    }
+
+    #[test]
+    fn symlinks_resolve_in_any_component() {
+        let fs = MemFs::new();
+        fs.create_dir_all(Path::new("/real/dir")).unwrap();
+        fs.write(Path::new("/real/dir/f"), b"hi").unwrap();
+        fs.symlink(Path::new("real/dir"), Path::new("/link"))
+            .unwrap();
+
+        assert_eq!(fs.read(Path::new("/link/f")).unwrap(), b"hi");
+        assert_eq!(fs.read(Path::new("/link/../dir/f")).unwrap(), b"hi");
+        assert!(fs
+            .symlink_metadata(Path::new("/link"))
+            .unwrap()
+            .is_symlink());
+
+        fs.symlink(Path::new("/loop"), Path::new("/loop")).unwrap();
+        assert!(matches!(
+            fs.read(Path::new("/loop")),
+            Err(FsError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn open_handle_outlives_unlink() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/tmp.bin"), b"keep").unwrap();
+        let handle = fs.open(Path::new("/tmp.bin"), OpenFlags::READ).unwrap();
+        let inode = fs.path_to_inode(Path::new("/tmp.bin")).unwrap();
+
+        fs.remove_file(Path::new("/tmp.bin")).unwrap();
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read_at(handle, &mut buf, 0).unwrap(), 4);
+        assert_eq!(&buf, b"keep");
+
+        fs.close(handle).unwrap();
+        assert!(fs.metadata_by_inode(inode).is_err());
+    }
+
+    #[test]
+    fn open_create_new_applies_mode_once() {
+        let fs = MemFs::new();
+        let mut options = crate::OpenOptions::new();
+        options.write(true).create_new(true).mode(0o600);
+        let handle = options.open(&fs, Path::new("/lock")).unwrap();
+        fs.close(handle).unwrap();
+        let meta = fs.metadata(Path::new("/lock")).unwrap();
+        assert_eq!(meta.permissions.mode(), 0o600);
+
+        assert!(matches!(
+            options.open(&fs, Path::new("/lock")),
+            Err(FsError::AlreadyExists { .. })
+        ));
+    }
+
+    #[test]
+    fn append_handles_ignore_offset_and_never_interleave() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/log"), b"").unwrap();
+        let handle = fs.open(Path::new("/log"), OpenFlags::APPEND).unwrap();
+        fs.write_at(handle, b"ab", 100).unwrap();
+        assert_eq!(fs.read(Path::new("/log")).unwrap(), b"ab");
+
+        let offsets: Vec<u64> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8u8)
+                .map(|i| {
+                    let fs = &fs;
+                    scope.spawn(move || {
+                        let handle = fs.open(Path::new("/log"), OpenFlags::READ_WRITE).unwrap();
+                        let offset = fs.append_handle(handle, &[b'0' + i; 4]).unwrap();
+                        fs.close(handle).unwrap();
+                        offset
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        let log = fs.read(Path::new("/log")).unwrap();
+        assert_eq!(log.len(), 2 + 8 * 4);
+        for (i, offset) in offsets.iter().enumerate() {
+            let at = *offset as usize;
+            assert_eq!(&log[at..at + 4], &[b'0' + i as u8; 4]);
+        }
+        let read_only = fs.open(Path::new("/log"), OpenFlags::READ).unwrap();
+        assert!(matches!(
+            fs.append_handle(read_only, b"x"),
+            Err(FsError::PermissionDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn locks_conflict_between_handles() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/db"), b"").unwrap();
+        let a = fs.open(Path::new("/db"), OpenFlags::READ).unwrap();
+        let b = fs.open(Path::new("/db"), OpenFlags::READ).unwrap();
+
+        fs.lock(a, LockType::Shared).unwrap();
+        assert!(fs.try_lock(b, LockType::Shared).unwrap());
+        assert!(!fs.try_lock(b, LockType::Exclusive).unwrap());
+
+        fs.unlock(a).unwrap();
+        assert!(fs.try_lock(b, LockType::Exclusive).unwrap());
+        fs.close(b).unwrap();
+        assert!(fs.try_lock(a, LockType::Exclusive).unwrap());
+    }
+
+    #[test]
+    fn change_counter_bumps_on_every_mutation() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/f"), b"").unwrap();
+        let before = fs.metadata(Path::new("/f")).unwrap();
+
+        fs.set_xattr(Path::new("/f"), "user.k", b"v").unwrap();
+        let after = fs.metadata(Path::new("/f")).unwrap();
+        assert!(after.changed_since(&before));
+        assert_eq!(after.modified, before.modified);
+    }
+
+    #[test]
+    fn create_dir_with_sets_mode_for_temp_dirs() {
+        let fs = MemFs::new();
+        fs.create_dir_with(Path::new("/keys"), Permissions::from_mode(0o700))
+            .unwrap();
+        let meta = fs.metadata(Path::new("/keys")).unwrap();
+        assert_eq!(meta.permissions.mode(), 0o700);
+
+        let temp = fs.create_temp_dir_in(Path::new("/"), "build-").unwrap();
+        let meta = fs.metadata(&temp).unwrap();
+        assert!(meta.is_dir());
+        assert_eq!(meta.permissions.mode(), 0o700);
+    }
+
+    #[test]
+    fn copy_with_honors_every_option() {
+        let clock = TestClock::new();
+        let fs = MemFs::with_clock(clock.clone());
+        fs.write(Path::new("/src"), b"new").unwrap();
+        fs.set_permissions(Path::new("/src"), Permissions::from_mode(0o600))
+            .unwrap();
+        let source = fs.metadata(Path::new("/src")).unwrap();
+        clock.advance(Duration::from_secs(60));
+        fs.write(Path::new("/dst"), b"old").unwrap();
+
+        let fail = CopyOptions {
+            overwrite: OverwritePolicy::Fail,
+            ..CopyOptions::default()
+        };
+        assert!(matches!(
+            fs.copy_with(Path::new("/src"), Path::new("/dst"), fail),
+            Err(FsError::AlreadyExists { .. })
+        ));
+        let skip = CopyOptions {
+            overwrite: OverwritePolicy::Skip,
+            ..CopyOptions::default()
+        };
+        assert_eq!(
+            fs.copy_with(Path::new("/src"), Path::new("/dst"), skip)
+                .unwrap(),
+            0
+        );
+        assert_eq!(fs.read(Path::new("/dst")).unwrap(), b"old");
+
+        let preserve = CopyOptions {
+            preserve_times: true,
+            preserve_permissions: true,
+            ..CopyOptions::default()
+        };
+        for to in ["/dst", "/fresh"] {
+            let copied = fs.copy_with(Path::new("/src"), Path::new(to), preserve);
+            assert_eq!(copied.unwrap(), 3);
+            let meta = fs.metadata(Path::new(to)).unwrap();
+            assert_eq!(fs.read(Path::new(to)).unwrap(), b"new");
+            assert_eq!(meta.permissions.mode(), 0o600);
+            assert_eq!(meta.modified, source.modified);
+        }
+
+        fs.create_dir(Path::new("/dir")).unwrap();
+        assert!(matches!(
+            fs.copy_with(Path::new("/dir"), Path::new("/x"), CopyOptions::default()),
+            Err(FsError::NotAFile { .. })
+        ));
+    }
+
+    #[test]
+    fn duplicates_share_locks_until_the_last_close() {
+        let fs = MemFs::new();
+        fs.write(Path::new("/db"), b"").unwrap();
+        let a = fs.open(Path::new("/db"), OpenFlags::READ).unwrap();
+        let other = fs.open(Path::new("/db"), OpenFlags::READ).unwrap();
+
+        fs.lock(a, LockType::Exclusive).unwrap();
+        let copy = fs.dup(a).unwrap();
+        assert_ne!(copy, a);
+        // The duplicate already holds the lock it shares
+        assert!(fs.try_lock(copy, LockType::Exclusive).unwrap());
+
+        fs.close(a).unwrap();
+        assert!(!fs.try_lock(other, LockType::Shared).unwrap());
+        fs.close(copy).unwrap();
+        assert!(fs.try_lock(other, LockType::Exclusive).unwrap());
+        assert!(matches!(fs.dup(a), Err(FsError::InvalidHandle { .. })));
+    }
+
+    #[test]
+    fn anonymous_files_appear_only_once_linked() {
+        let fs = MemFs::new();
+        fs.create_dir(Path::new("/out")).unwrap();
+        let handle = fs
+            .open_anonymous(Path::new("/out"), OpenFlags::WRITE)
+            .unwrap();
+        fs.write_at(handle, b"done", 0).unwrap();
+        fs.sync_handle(handle).unwrap();
+        assert_eq!(fs.dir_len(Path::new("/out")).unwrap(), 0);
+
+        fs.write(Path::new("/out/taken"), b"").unwrap();
+        assert!(matches!(
+            fs.link_handle(handle, Path::new("/out/taken")),
+            Err(FsError::AlreadyExists { .. })
+        ));
+        fs.link_handle(handle, Path::new("/out/report")).unwrap();
+        assert_eq!(fs.read(Path::new("/out/report")).unwrap(), b"done");
+        assert!(matches!(
+            fs.link_handle(handle, Path::new("/out/again")),
+            Err(FsError::InvalidHandle { .. })
+        ));
+        fs.close(handle).unwrap();
+        assert_eq!(fs.read(Path::new("/out/report")).unwrap(), b"done");
+
+        // Closed before linking: nothing is left behind
+        let used = fs.statfs().unwrap().used_inodes;
+        let scratch = fs
+            .open_anonymous(Path::new("/out"), OpenFlags::WRITE)
+            .unwrap();
+        fs.close(scratch).unwrap();
+        assert_eq!(fs.statfs().unwrap().used_inodes, used);
+        assert!(matches!(
+            fs.sync_handle(scratch),
+            Err(FsError::InvalidHandle { .. })
+        ));
+    }
+
+    #[test]
+    fn fifos_stream_bytes_once_without_blocking() {
+        let fs = MemFs::new();
+        fs.mkfifo(Path::new("/pipe"), Permissions::from_mode(0o600))
+            .unwrap();
+        let meta = fs.metadata(Path::new("/pipe")).unwrap();
+        assert_eq!(meta.file_type, FileType::Fifo);
+        assert_eq!(meta.permissions.mode(), 0o600);
+        assert!(matches!(
+            fs.read(Path::new("/pipe")),
+            Err(FsError::NotAFile { .. })
+        ));
+
+        let writer = fs.open(Path::new("/pipe"), OpenFlags::WRITE).unwrap();
+        let reader = fs.open(Path::new("/pipe"), OpenFlags::READ).unwrap();
+        fs.write_at(writer, b"abc", 0).unwrap();
+        fs.write_at(writer, b"de", 0).unwrap();
+
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read_at(reader, &mut buf, 100).unwrap(), 4);
+        assert_eq!(&buf, b"abcd");
+        assert_eq!(fs.read_at(reader, &mut buf, 0).unwrap(), 1);
+        assert_eq!(buf[0], b'e');
+        assert!(matches!(
+            fs.read_at(reader, &mut buf, 0),
+            Err(FsError::TimedOut { .. })
+        ));
+
+        fs.close(writer).unwrap();
+        assert_eq!(fs.read_at(reader, &mut buf, 0).unwrap(), 0);
+    }
+}
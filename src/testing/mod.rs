@@ -0,0 +1,33 @@
+//! # Testing Utilities
+//!
+//! Ready-made pieces for testing code written against the AnyFS traits.
+//! Available with the `testing` feature, so the default build stays
+//! traits-only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! anyfs-backend = { version = "0.1", features = ["testing"] }
+//! ```
+//!
+//! ## Quick Reference
+//!
+//! | Item | Purpose |
+//! |------|---------|
+//! | [`MemFs`] | Complete in-memory backend implementing everything up to `FsPosix` |
+//! | [`TestClock`] | Manually advanced [`Clock`](crate::Clock) for exact timestamp assertions |
+//! | [`FsTree`] | Declarative tree fixture, built with [`fs_tree!`](crate::fs_tree) |
+//! | [`snapshot`] / [`snapshot_at`] | Read a backend's tree back into an [`FsTree`] for assertions |
+//! | [`diff_trees`] / [`assert_fs_eq!`](crate::assert_fs_eq) | Report every difference between two backends |
+//! | [`StressTest`] | Concurrent scenarios checking a backend's thread safety |
+
+mod clock;
+mod diff;
+mod mem_fs;
+mod stress;
+mod tree;
+
+pub use clock::TestClock;
+pub use diff::{diff_trees, diff_trees_with, Difference, TreeDiff};
+pub use mem_fs::MemFs;
+pub use stress::{StressFailure, StressTest};
+pub use tree::{snapshot, snapshot_at, FsTree};
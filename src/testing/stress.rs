@@ -0,0 +1,505 @@
+//! Concurrent scenarios checking a backend's thread safety.
+
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+use crate::{Fs, FsError, FsHandles, FsLock, Handle, LockType, OpenFlags};
+
+/// Length of the files the scenarios write.
+const PAYLOAD_LEN: usize = 4096;
+
+/// Scripted concurrent scenarios that check a backend's invariants.
+///
+/// Every trait requires `Send + Sync` and backends promise interior
+/// mutability that is safe to use from many threads; a `StressTest` checks
+/// the promise. Each scenario starts its threads together behind a barrier,
+/// runs the same script for a number of rounds so the operations interleave
+/// differently each time, and checks what other threads observed:
+///
+/// | Scenario | Threads | Invariant |
+/// |----------|---------|-----------|
+/// | [`write_rename`](Self::write_rename) | Writers publish files with write-then-rename; a reader reads the target | The target never disappears and is never a mix of two writes |
+/// | [`read_during_truncate`](Self::read_during_truncate) | One thread rewrites and truncates a file; readers read it | Every read is a prefix of the written content |
+/// | [`lock_contention`](Self::lock_contention) | Threads increment a counter file under an exclusive lock | No increment is lost |
+///
+/// Scenarios work in a [`root`](Self::root) directory (`/stress` by
+/// default), created if missing and left in place afterwards. A scenario
+/// passing is evidence, not proof: races that need a rare interleaving may
+/// take more [`rounds`](Self::rounds) to show up, or not show up at all.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::testing::{MemFs, StressTest};
+///
+/// StressTest::new()
+///     .threads(4)
+///     .rounds(50)
+///     .run_all(&MemFs::new())
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct StressTest {
+    root: PathBuf,
+    threads: usize,
+    rounds: usize,
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/stress"),
+            threads: 4,
+            rounds: 200,
+        }
+    }
+}
+
+impl StressTest {
+    /// Four threads, 200 rounds, working in `/stress`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Work in `root` instead of `/stress`.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Number of threads per scenario (at least 2).
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(2);
+        self
+    }
+
+    /// Number of times each thread runs its script.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Run every scenario, stopping at the first failure.
+    ///
+    /// # Errors
+    ///
+    /// - The first [`StressFailure`] from any scenario
+    pub fn run_all<B>(&self, backend: &B) -> Result<(), StressFailure>
+    where
+        B: Fs + FsHandles + FsLock,
+    {
+        self.write_rename(backend)?;
+        self.read_during_truncate(backend)?;
+        self.lock_contention(backend)
+    }
+
+    /// Writers each write a whole file to a private temporary name and
+    /// rename it over `root/target`, while a reader keeps reading the
+    /// target.
+    ///
+    /// Renaming over an existing file must replace it in one step, so the
+    /// reader must never get [`FsError::NotFound`] nor content from two
+    /// different writes. Afterwards no temporary file may be left.
+    ///
+    /// # Errors
+    ///
+    /// - [`StressFailure`] describing the first broken invariant or
+    ///   backend error
+    pub fn write_rename<B: Fs>(&self, backend: &B) -> Result<(), StressFailure> {
+        const SCENARIO: &str = "write_rename";
+        let target = self.root.join("target");
+        let temp = |writer: usize| self.root.join(format!("tmp-{writer}"));
+        let writers = self.threads - 1;
+
+        setup(SCENARIO, backend, &self.root)?;
+        backend
+            .write(&target, &uniform_payload(0, 0))
+            .map_err(|e| StressFailure::backend(SCENARIO, e))?;
+
+        let done = AtomicUsize::new(0);
+        let start = Barrier::new(self.threads);
+        let results: Vec<Result<(), StressFailure>> = thread::scope(|scope| {
+            let mut handles = Vec::new();
+            for writer in 0..writers {
+                let (done, start, target, temp) = (&done, &start, &target, temp(writer));
+                handles.push(scope.spawn(move || {
+                    start.wait();
+                    let result = (0..self.rounds).try_for_each(|round| {
+                        backend.write(&temp, &uniform_payload(writer + 1, round))?;
+                        backend.rename(&temp, target)
+                    });
+                    done.fetch_add(1, Ordering::SeqCst);
+                    result.map_err(|e| StressFailure::backend(SCENARIO, e))
+                }));
+            }
+            let (done, start, target) = (&done, &start, &target);
+            handles.push(scope.spawn(move || {
+                start.wait();
+                while done.load(Ordering::SeqCst) < writers {
+                    check_uniform(SCENARIO, backend, target)?;
+                    thread::yield_now();
+                }
+                Ok(())
+            }));
+            handles.into_iter().map(join).collect()
+        });
+        results.into_iter().collect::<Result<(), _>>()?;
+
+        check_uniform(SCENARIO, backend, &target)?;
+        for writer in 0..writers {
+            let temp = temp(writer);
+            if backend
+                .exists(&temp)
+                .map_err(|e| StressFailure::backend(SCENARIO, e))?
+            {
+                return Err(StressFailure::new(
+                    SCENARIO,
+                    format!("{} still exists after being renamed", temp.display()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// One thread writes `root/truncated` and truncates it to half its
+    /// length, round after round, while the other threads read it.
+    ///
+    /// Every read must return a prefix of the written content: bytes past
+    /// the truncation point must be gone, never zeroed or left over.
+    /// Afterwards the file must hold exactly the first half.
+    ///
+    /// # Errors
+    ///
+    /// - [`StressFailure`] describing the first broken invariant or
+    ///   backend error
+    pub fn read_during_truncate<B: Fs>(&self, backend: &B) -> Result<(), StressFailure> {
+        const SCENARIO: &str = "read_during_truncate";
+        let path = self.root.join("truncated");
+        let content = pattern_payload();
+        let half = (PAYLOAD_LEN / 2) as u64;
+
+        setup(SCENARIO, backend, &self.root)?;
+        backend
+            .write(&path, &content)
+            .map_err(|e| StressFailure::backend(SCENARIO, e))?;
+
+        let done = AtomicUsize::new(0);
+        let start = Barrier::new(self.threads);
+        let results: Vec<Result<(), StressFailure>> = thread::scope(|scope| {
+            let (done, start, path, content) = (&done, &start, &path, &content);
+            let mut handles = vec![scope.spawn(move || {
+                start.wait();
+                let result = (0..self.rounds).try_for_each(|_| {
+                    backend.write(path, content)?;
+                    backend.truncate(path, half)
+                });
+                done.fetch_add(1, Ordering::SeqCst);
+                result.map_err(|e| StressFailure::backend(SCENARIO, e))
+            })];
+            for _ in 1..self.threads {
+                handles.push(scope.spawn(move || {
+                    start.wait();
+                    while done.load(Ordering::SeqCst) == 0 {
+                        check_prefix(SCENARIO, backend, path, content)?;
+                        thread::yield_now();
+                    }
+                    Ok(())
+                }));
+            }
+            handles.into_iter().map(join).collect()
+        });
+        results.into_iter().collect::<Result<(), _>>()?;
+
+        let data = backend
+            .read(&path)
+            .map_err(|e| StressFailure::backend(SCENARIO, e))?;
+        if data != content[..half as usize] {
+            return Err(StressFailure::new(
+                SCENARIO,
+                format!(
+                    "{} holds {} bytes after the last truncate to {half}, or the wrong ones",
+                    path.display(),
+                    data.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Every thread opens `root/counter` and, round after round, takes an
+    /// exclusive lock, reads the counter, writes it back incremented and
+    /// unlocks.
+    ///
+    /// Exclusive locks must serialize the read-modify-write, so the final
+    /// count must be threads × rounds.
+    ///
+    /// # Errors
+    ///
+    /// - [`StressFailure`] describing a lost update or backend error
+    pub fn lock_contention<B>(&self, backend: &B) -> Result<(), StressFailure>
+    where
+        B: Fs + FsHandles + FsLock,
+    {
+        const SCENARIO: &str = "lock_contention";
+        let path = self.root.join("counter");
+
+        setup(SCENARIO, backend, &self.root)?;
+        backend
+            .write(&path, &0u64.to_le_bytes())
+            .map_err(|e| StressFailure::backend(SCENARIO, e))?;
+
+        let start = Barrier::new(self.threads);
+        let results: Vec<Result<(), StressFailure>> = thread::scope(|scope| {
+            let (start, path) = (&start, &path);
+            let handles: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    scope.spawn(move || {
+                        start.wait();
+                        let handle = backend.open(path, OpenFlags::READ_WRITE)?;
+                        let result =
+                            (0..self.rounds).try_for_each(|_| increment_locked(backend, handle));
+                        let closed = backend.close(handle);
+                        result.and(closed)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| join(handle).map_err(|e| StressFailure::backend(SCENARIO, e)))
+                .collect()
+        });
+        results.into_iter().collect::<Result<(), _>>()?;
+
+        let data = backend
+            .read(&path)
+            .map_err(|e| StressFailure::backend(SCENARIO, e))?;
+        let expected = (self.threads * self.rounds) as u64;
+        let count = decode_counter(&data);
+        if count != Some(expected) {
+            return Err(StressFailure::new(
+                SCENARIO,
+                format!("counter is {count:?} after {expected} locked increments"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Why a [`StressTest`] scenario failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StressFailure {
+    /// Name of the scenario, e.g. `"write_rename"`.
+    pub scenario: &'static str,
+    /// The broken invariant or the backend error.
+    pub message: String,
+}
+
+impl StressFailure {
+    fn new(scenario: &'static str, message: String) -> Self {
+        Self { scenario, message }
+    }
+
+    fn backend(scenario: &'static str, error: FsError) -> Self {
+        Self::new(scenario, format!("backend error: {error}"))
+    }
+}
+
+impl fmt::Display for StressFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.scenario, self.message)
+    }
+}
+
+impl Error for StressFailure {}
+
+/// Create the scenario's root directory.
+fn setup<B: Fs>(scenario: &'static str, backend: &B, root: &Path) -> Result<(), StressFailure> {
+    backend
+        .create_dir_all(root)
+        .map_err(|e| StressFailure::backend(scenario, e))
+}
+
+/// Join a scenario thread, re-raising its panic in the caller.
+fn join<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    match handle.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+/// `PAYLOAD_LEN` copies of one byte identifying a writer and round.
+fn uniform_payload(writer: usize, round: usize) -> Vec<u8> {
+    vec![(writer * 31 + round) as u8; PAYLOAD_LEN]
+}
+
+/// `PAYLOAD_LEN` bytes in which every position holds a distinct pattern.
+fn pattern_payload() -> Vec<u8> {
+    (0..PAYLOAD_LEN).map(|i| (i % 251) as u8 + 1).collect()
+}
+
+/// Check that `path` holds one whole [`uniform_payload`].
+fn check_uniform<B: Fs>(
+    scenario: &'static str,
+    backend: &B,
+    path: &Path,
+) -> Result<(), StressFailure> {
+    let data = match backend.read(path) {
+        Ok(data) => data,
+        Err(FsError::NotFound { .. }) => {
+            return Err(StressFailure::new(
+                scenario,
+                format!("{} was missing while being replaced", path.display()),
+            ))
+        }
+        Err(e) => return Err(StressFailure::backend(scenario, e)),
+    };
+    if data.len() != PAYLOAD_LEN || data.iter().any(|&b| b != data[0]) {
+        return Err(StressFailure::new(
+            scenario,
+            format!(
+                "{} held {} bytes mixed from several writes",
+                path.display(),
+                data.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that `path` holds a prefix of `content`.
+fn check_prefix<B: Fs>(
+    scenario: &'static str,
+    backend: &B,
+    path: &Path,
+    content: &[u8],
+) -> Result<(), StressFailure> {
+    let data = backend
+        .read(path)
+        .map_err(|e| StressFailure::backend(scenario, e))?;
+    if !content.starts_with(&data) {
+        return Err(StressFailure::new(
+            scenario,
+            format!(
+                "read {} bytes of {} that are not a prefix of what was written",
+                data.len(),
+                path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Read-modify-write the counter behind `handle` under an exclusive lock.
+fn increment_locked<B: FsHandles + FsLock>(backend: &B, handle: Handle) -> Result<(), FsError> {
+    backend.lock(handle, LockType::Exclusive)?;
+    let mut buf = [0u8; 8];
+    let result = backend.read_at(handle, &mut buf, 0).and_then(|_| {
+        let next = u64::from_le_bytes(buf) + 1;
+        backend.write_at(handle, &next.to_le_bytes(), 0).map(|_| ())
+    });
+    let unlocked = backend.unlock(handle);
+    result.and(unlocked)
+}
+
+/// The counter written by [`increment_locked`], if `data` holds one.
+fn decode_counter(data: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(data.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MemFs;
+    use crate::{FsDir, FsRead, FsWrite, ReadDirIter};
+
+    #[test]
+    fn mem_fs_passes_every_scenario() {
+        StressTest::new()
+            .threads(3)
+            .rounds(30)
+            .run_all(&MemFs::new())
+            .unwrap();
+    }
+
+    /// Truncates by zeroing the tail instead of removing it.
+    struct ZeroingTruncate(MemFs);
+
+    impl FsRead for ZeroingTruncate {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.0.read(path)
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            self.0.read_to_string(path)
+        }
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            self.0.read_range(path, offset, len)
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            self.0.exists(path)
+        }
+        fn metadata(&self, path: &Path) -> Result<crate::Metadata, FsError> {
+            self.0.metadata(path)
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+            self.0.open_read(path)
+        }
+    }
+
+    impl FsWrite for ZeroingTruncate {
+        fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.write(path, data)
+        }
+        fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.append(path, data)
+        }
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_file(path)
+        }
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.0.rename(from, to)
+        }
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.0.copy(from, to)
+        }
+        fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+            let mut data = self.0.read(path)?;
+            data[size as usize..].fill(0);
+            self.0.write(path, &data)
+        }
+        fn open_write(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+            self.0.open_write(path)
+        }
+    }
+
+    impl FsDir for ZeroingTruncate {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            self.0.read_dir(path)
+        }
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir(path)
+        }
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir_all(path)
+        }
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_dir(path)
+        }
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_dir_all(path)
+        }
+    }
+
+    #[test]
+    fn broken_truncate_is_reported() {
+        let failure = StressTest::new()
+            .threads(2)
+            .rounds(5)
+            .read_during_truncate(&ZeroingTruncate(MemFs::new()))
+            .unwrap_err();
+        assert_eq!(failure.scenario, "read_during_truncate");
+    }
+}
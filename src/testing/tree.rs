@@ -0,0 +1,324 @@
+//! Declarative filesystem trees for fixtures and assertions.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{FileType, Fs, FsError, FsLink};
+
+/// A filesystem tree held in memory: file contents, directories and
+/// symlink targets, without metadata.
+///
+/// Build one with [`fs_tree!`](crate::fs_tree), write it into any backend
+/// with [`create_in`](Self::create_in), and read one back with [`snapshot`].
+/// Trees compare with `==`, and their `Debug` output shows UTF-8 file
+/// contents as strings, so `assert_eq!` failures are readable.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fs_tree;
+/// use anyfs_backend::testing::{snapshot, FsTree, MemFs};
+///
+/// let fixture = fs_tree! {
+///     "src" => {
+///         "main.rs" => "fn main() {}",
+///     },
+///     "README.md" => "# Demo",
+///     "latest" => FsTree::symlink("src/main.rs"),
+/// };
+///
+/// let fs = MemFs::new();
+/// fixture.create_in_with_links(&fs, "/project".as_ref()).unwrap();
+///
+/// assert_eq!(snapshot(&fs).unwrap(), fs_tree! { "project" => fixture });
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub enum FsTree {
+    /// A regular file with these contents.
+    File(Vec<u8>),
+    /// A directory with these entries.
+    Dir(BTreeMap<OsString, FsTree>),
+    /// A symlink pointing at this target.
+    Symlink(PathBuf),
+}
+
+impl FsTree {
+    /// A file with `contents`.
+    pub fn file(contents: impl Into<Vec<u8>>) -> Self {
+        Self::File(contents.into())
+    }
+
+    /// An empty directory.
+    pub fn dir() -> Self {
+        Self::Dir(BTreeMap::new())
+    }
+
+    /// A symlink to `target`.
+    pub fn symlink(target: impl Into<PathBuf>) -> Self {
+        Self::Symlink(target.into())
+    }
+
+    /// The node at `path`, relative to this tree.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::fs_tree;
+    /// use anyfs_backend::testing::FsTree;
+    ///
+    /// let tree = fs_tree! { "a" => { "b.txt" => "hello" } };
+    /// assert_eq!(tree.get("a/b.txt"), Some(&FsTree::file("hello")));
+    /// assert_eq!(tree.get("a/missing"), None);
+    /// ```
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&FsTree> {
+        path.as_ref()
+            .iter()
+            .try_fold(self, |node, name| match node {
+                Self::Dir(entries) => entries.get(name),
+                _ => None,
+            })
+    }
+
+    /// Write this tree at `root` in `fs`, creating `root` and its parents.
+    ///
+    /// Existing files are overwritten; existing entries not in the tree are
+    /// left alone.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotSupported`] if the tree contains a symlink (use
+    ///   [`create_in_with_links`](Self::create_in_with_links))
+    /// - Errors from the backend's `create_dir_all` and `write`
+    pub fn create_in<B: Fs + ?Sized>(&self, fs: &B, root: &Path) -> Result<(), FsError> {
+        self.create(fs, root, &|_, _| {
+            Err(FsError::NotSupported {
+                operation: "symlink",
+            })
+        })
+    }
+
+    /// Like [`create_in`](Self::create_in), but also creates symlinks.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from the backend's `create_dir_all`, `write` and `symlink`
+    pub fn create_in_with_links<B: Fs + FsLink + ?Sized>(
+        &self,
+        fs: &B,
+        root: &Path,
+    ) -> Result<(), FsError> {
+        self.create(fs, root, &|target, link| fs.symlink(target, link))
+    }
+
+    fn create<B: Fs + ?Sized>(
+        &self,
+        fs: &B,
+        path: &Path,
+        symlink: &dyn Fn(&Path, &Path) -> Result<(), FsError>,
+    ) -> Result<(), FsError> {
+        match self {
+            Self::File(contents) => {
+                if let Some(parent) = path.parent() {
+                    fs.create_dir_all(parent)?;
+                }
+                fs.write(path, contents)
+            }
+            Self::Dir(entries) => {
+                fs.create_dir_all(path)?;
+                for (name, node) in entries {
+                    node.create(fs, &path.join(name), symlink)?;
+                }
+                Ok(())
+            }
+            Self::Symlink(target) => symlink(target, path),
+        }
+    }
+}
+
+impl fmt::Debug for FsTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(contents) => match std::str::from_utf8(contents) {
+                Ok(text) => write!(f, "{text:?}"),
+                Err(_) => write!(f, "{contents:?}"),
+            },
+            Self::Dir(entries) => f.debug_map().entries(entries.iter()).finish(),
+            Self::Symlink(target) => write!(f, "symlink({target:?})"),
+        }
+    }
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self::dir()
+    }
+}
+
+impl From<&str> for FsTree {
+    fn from(contents: &str) -> Self {
+        Self::file(contents)
+    }
+}
+
+impl From<String> for FsTree {
+    fn from(contents: String) -> Self {
+        Self::file(contents)
+    }
+}
+
+impl From<&[u8]> for FsTree {
+    fn from(contents: &[u8]) -> Self {
+        Self::file(contents)
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for FsTree {
+    fn from(contents: &[u8; N]) -> Self {
+        Self::file(&contents[..])
+    }
+}
+
+impl From<Vec<u8>> for FsTree {
+    fn from(contents: Vec<u8>) -> Self {
+        Self::File(contents)
+    }
+}
+
+/// Snapshot the whole filesystem, starting at `/`.
+///
+/// # Errors
+///
+/// Same as [`snapshot_at`].
+pub fn snapshot<B: Fs + ?Sized>(fs: &B) -> Result<FsTree, FsError> {
+    snapshot_at(fs, Path::new("/"))
+}
+
+/// Read the tree rooted at `root` back into an [`FsTree`].
+///
+/// Symlinks are not followed. Their targets come from
+/// [`DirEntry::symlink_target`](crate::DirEntry::symlink_target), which
+/// the backend must fill in for every symlink entry.
+///
+/// # Errors
+///
+/// - [`FsError::NotSupported`] if a symlink entry has no target, or the tree
+///   contains a FIFO
+/// - Errors from the backend's `metadata`, `read_dir` and `read`
+pub fn snapshot_at<B: Fs + ?Sized>(fs: &B, root: &Path) -> Result<FsTree, FsError> {
+    if !fs.metadata(root)?.is_dir() {
+        return fs.read(root).map(FsTree::File);
+    }
+    let mut entries = BTreeMap::new();
+    for entry in fs.read_dir(root)? {
+        let entry = entry?;
+        let node = match entry.file_type {
+            FileType::Directory => snapshot_at(fs, &entry.path)?,
+            FileType::File => FsTree::File(fs.read(&entry.path)?),
+            FileType::Symlink => match entry.symlink_target {
+                Some(ref target) => FsTree::Symlink(target.clone()),
+                None => {
+                    return Err(FsError::NotSupported {
+                        operation: "read_link",
+                    })
+                }
+            },
+            FileType::Fifo => {
+                return Err(FsError::NotSupported {
+                    operation: "snapshot (fifo)",
+                })
+            }
+        };
+        entries.insert(entry.file_name().to_os_string(), node);
+    }
+    Ok(FsTree::Dir(entries))
+}
+
+/// Build an [`FsTree`] directory from a nested
+/// manifest.
+///
+/// Each entry is `"name" => value`. A `{ ... }` value is a subdirectory;
+/// any other value is converted with `FsTree::from`, so string and byte
+/// literals become files and `FsTree::symlink(..)` makes a symlink.
+///
+/// Available with the `testing` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::fs_tree;
+/// use anyfs_backend::testing::FsTree;
+///
+/// let tree = fs_tree! {
+///     "a" => { "b.txt" => "hello" },
+///     "empty" => {},
+///     "blob.bin" => b"\x00\x01",
+/// };
+///
+/// assert_eq!(tree.get("a/b.txt"), Some(&FsTree::file("hello")));
+/// assert_eq!(tree.get("empty"), Some(&FsTree::dir()));
+/// ```
+#[macro_export]
+macro_rules! fs_tree {
+    (@entries $map:ident;) => {};
+    (@entries $map:ident; $name:literal => { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $map.insert(::std::ffi::OsString::from($name), $crate::fs_tree!($($inner)*));
+        $crate::fs_tree!(@entries $map; $($($rest)*)?);
+    };
+    (@entries $map:ident; $name:literal => $value:expr $(, $($rest:tt)*)?) => {
+        $map.insert(
+            ::std::ffi::OsString::from($name),
+            $crate::testing::FsTree::from($value),
+        );
+        $crate::fs_tree!(@entries $map; $($($rest)*)?);
+    };
+    () => {
+        $crate::testing::FsTree::dir()
+    };
+    ($($body:tt)*) => {{
+        let mut entries = ::std::collections::BTreeMap::new();
+        $crate::fs_tree!(@entries entries; $($body)*);
+        $crate::testing::FsTree::Dir(entries)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MemFs;
+
+    #[test]
+    fn create_then_snapshot_round_trips() {
+        let tree = fs_tree! {
+            "docs" => { "a.txt" => "A", "nested" => { "b.bin" => vec![0u8, 255] } },
+            "empty" => {},
+            "link" => FsTree::symlink("docs/a.txt"),
+        };
+        let fs = MemFs::new();
+        tree.create_in_with_links(&fs, Path::new("/")).unwrap();
+
+        assert_eq!(snapshot(&fs).unwrap(), tree);
+        assert_eq!(
+            snapshot_at(&fs, Path::new("/docs/a.txt")).unwrap(),
+            FsTree::file("A")
+        );
+    }
+
+    #[test]
+    fn create_in_rejects_symlinks_without_fs_link() {
+        let tree = fs_tree! { "link" => FsTree::symlink("x") };
+        let fs = MemFs::new();
+        let plain: &dyn Fs = &fs;
+        assert!(matches!(
+            tree.create_in(plain, Path::new("/")),
+            Err(FsError::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn debug_shows_text_contents() {
+        let tree = fs_tree! { "a" => "hi", "b" => FsTree::symlink("a") };
+        assert_eq!(format!("{tree:?}"), r#"{"a": "hi", "b": symlink("a")}"#);
+    }
+}
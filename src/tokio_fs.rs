@@ -0,0 +1,304 @@
+//! # Tokio Adapter
+//!
+//! [`TokioFs`] lets async code on a tokio runtime use any synchronous
+//! backend: each call runs on [`tokio::task::spawn_blocking`], so slow
+//! backends never stall the runtime's worker threads (requires the `tokio`
+//! feature).
+//!
+//! A semaphore bounds how many calls run at once. Without it, a burst of
+//! requests would fill tokio's blocking pool (512 threads by default) with
+//! calls against one backend.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use anyfs_backend::{Fs, FsError, TokioFs};
+//!
+//! async fn serve_file<B: Fs + 'static>(fs: &TokioFs<B>, path: &str) -> Result<Vec<u8>, FsError> {
+//!     if !fs.exists(path).await? {
+//!         return Err(FsError::not_found("read", path));
+//!     }
+//!     fs.read(path).await
+//! }
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::dispatch::execute;
+use crate::{DirEntry, Fs, FsError, FsOp, FsOpResult, Metadata};
+
+/// Async wrapper running a synchronous backend on tokio's blocking pool.
+///
+/// At most `max_blocking` calls run at once; further calls wait for a slot
+/// without occupying a thread. A call keeps its slot until the backend
+/// returns, even if the awaiting future is dropped first.
+///
+/// Clones share the backend and the limit. The named methods cover the
+/// common operations; [`call`](Self::call) takes any [`FsOp`], and
+/// [`run`](Self::run) any closure.
+///
+/// # Panics
+///
+/// A backend panic is resumed in the awaiting task. Methods must be
+/// awaited inside a tokio runtime.
+pub struct TokioFs<B> {
+    inner: Arc<B>,
+    permits: Arc<Semaphore>,
+}
+
+impl<B> Clone for TokioFs<B> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            permits: Arc::clone(&self.permits),
+        }
+    }
+}
+
+impl<B> fmt::Debug for TokioFs<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokioFs")
+            .field("available", &self.permits.available_permits())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B: Fs + 'static> TokioFs<B> {
+    /// Wrap `inner`, running at most `max_blocking` calls at once
+    /// (at least 1).
+    pub fn new(inner: B, max_blocking: usize) -> Self {
+        Self::from_arc(Arc::new(inner), max_blocking)
+    }
+
+    /// Wrap a backend that is shared with synchronous code.
+    pub fn from_arc(inner: Arc<B>, max_blocking: usize) -> Self {
+        Self {
+            inner,
+            permits: Arc::new(Semaphore::new(max_blocking.max(1))),
+        }
+    }
+
+    /// The wrapped backend, for synchronous use.
+    pub fn inner(&self) -> &Arc<B> {
+        &self.inner
+    }
+
+    /// Run `f` against the backend on the blocking pool.
+    ///
+    /// # Errors
+    ///
+    /// - Whatever `f` returns
+    /// - [`FsError::Backend`] if the runtime shuts down before `f` runs
+    pub async fn run<T, F>(&self, f: F) -> Result<T, FsError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&B) -> Result<T, FsError> + Send + 'static,
+    {
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| FsError::Backend("TokioFs: semaphore closed".into()))?;
+        let inner = Arc::clone(&self.inner);
+        let task = tokio::task::spawn_blocking(move || {
+            let result = f(&inner);
+            drop(permit);
+            result
+        });
+        match task.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Err(FsError::Backend("TokioFs: runtime shut down".into())),
+        }
+    }
+
+    /// Perform any [`FsOp`], as [`Dispatcher`](crate::Dispatcher) would.
+    ///
+    /// Streaming results ([`FsOpResult::Reader`], [`FsOpResult::Writer`],
+    /// [`FsOpResult::DirEntries`]) still block when used; consume them
+    /// inside [`run`](Self::run) instead.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`run`](Self::run).
+    pub async fn call(&self, op: FsOp) -> Result<FsOpResult, FsError> {
+        self.run(move |fs| execute(fs, op)).await
+    }
+
+    /// Async [`FsRead::read`](crate::FsRead::read).
+    pub async fn read(&self, path: impl Into<PathBuf>) -> Result<Vec<u8>, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.read(&path)).await
+    }
+
+    /// Async [`FsRead::read_to_string`](crate::FsRead::read_to_string).
+    pub async fn read_to_string(&self, path: impl Into<PathBuf>) -> Result<String, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.read_to_string(&path)).await
+    }
+
+    /// Async [`FsRead::read_range`](crate::FsRead::read_range).
+    pub async fn read_range(
+        &self,
+        path: impl Into<PathBuf>,
+        offset: u64,
+        len: usize,
+    ) -> Result<Vec<u8>, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.read_range(&path, offset, len)).await
+    }
+
+    /// Async [`FsRead::exists`](crate::FsRead::exists).
+    pub async fn exists(&self, path: impl Into<PathBuf>) -> Result<bool, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.exists(&path)).await
+    }
+
+    /// Async [`FsRead::metadata`](crate::FsRead::metadata).
+    pub async fn metadata(&self, path: impl Into<PathBuf>) -> Result<Metadata, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.metadata(&path)).await
+    }
+
+    /// Async [`FsWrite::write`](crate::FsWrite::write).
+    pub async fn write(
+        &self,
+        path: impl Into<PathBuf>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), FsError> {
+        let (path, data) = (path.into(), data.into());
+        self.run(move |fs| fs.write(&path, &data)).await
+    }
+
+    /// Async [`FsWrite::append`](crate::FsWrite::append).
+    pub async fn append(
+        &self,
+        path: impl Into<PathBuf>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), FsError> {
+        let (path, data) = (path.into(), data.into());
+        self.run(move |fs| fs.append(&path, &data)).await
+    }
+
+    /// Async [`FsWrite::remove_file`](crate::FsWrite::remove_file).
+    pub async fn remove_file(&self, path: impl Into<PathBuf>) -> Result<(), FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.remove_file(&path)).await
+    }
+
+    /// Async [`FsWrite::rename`](crate::FsWrite::rename).
+    pub async fn rename(
+        &self,
+        from: impl Into<PathBuf>,
+        to: impl Into<PathBuf>,
+    ) -> Result<(), FsError> {
+        let (from, to) = (from.into(), to.into());
+        self.run(move |fs| fs.rename(&from, &to)).await
+    }
+
+    /// Async [`FsWrite::copy`](crate::FsWrite::copy).
+    pub async fn copy(
+        &self,
+        from: impl Into<PathBuf>,
+        to: impl Into<PathBuf>,
+    ) -> Result<(), FsError> {
+        let (from, to) = (from.into(), to.into());
+        self.run(move |fs| fs.copy(&from, &to)).await
+    }
+
+    /// Async [`FsDir::read_dir`](crate::FsDir::read_dir), collected on the
+    /// blocking pool.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from `read_dir`, or the first error from the listing
+    pub async fn read_dir(&self, path: impl Into<PathBuf>) -> Result<Vec<DirEntry>, FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.read_dir(&path)?.collect()).await
+    }
+
+    /// Async [`FsDir::create_dir_all`](crate::FsDir::create_dir_all).
+    pub async fn create_dir_all(&self, path: impl Into<PathBuf>) -> Result<(), FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.create_dir_all(&path)).await
+    }
+
+    /// Async [`FsDir::remove_dir_all`](crate::FsDir::remove_dir_all).
+    pub async fn remove_dir_all(&self, path: impl Into<PathBuf>) -> Result<(), FsError> {
+        let path = path.into();
+        self.run(move |fs| fs.remove_dir_all(&path)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsBuilder;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn methods_reach_backend() {
+        let fs = TokioFs::new(
+            FsBuilder::new()
+                .on_read(|path| Ok(path.to_string_lossy().into_owned().into_bytes()))
+                .on_write(|_, _| Err(FsError::ReadOnly { operation: "write" }))
+                .build(),
+            2,
+        );
+        runtime().block_on(async {
+            assert_eq!(fs.read("/a").await.unwrap(), b"/a");
+            assert!(matches!(
+                fs.write("/a", b"x".to_vec()).await,
+                Err(FsError::ReadOnly { .. })
+            ));
+            match fs.call(FsOp::Read { path: "/b".into() }).await.unwrap() {
+                FsOpResult::Bytes(data) => assert_eq!(data, b"/b"),
+                other => panic!("unexpected {other:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn concurrency_is_bounded() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let fs = TokioFs::new(
+            FsBuilder::new()
+                .on_exists({
+                    let (active, peak) = (Arc::clone(&active), Arc::clone(&peak));
+                    move |_: &Path| {
+                        let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(5));
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        Ok(true)
+                    }
+                })
+                .build(),
+            2,
+        );
+        runtime().block_on(async {
+            let tasks: Vec<_> = (0..12)
+                .map(|i| {
+                    let fs = fs.clone();
+                    tokio::spawn(async move { fs.exists(format!("/{i}")).await })
+                })
+                .collect();
+            for task in tasks {
+                assert!(task.await.unwrap().unwrap());
+            }
+        });
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+}
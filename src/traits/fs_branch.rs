@@ -0,0 +1,86 @@
+//! Copy-on-write branches for staging and experiments.
+
+use crate::{Fs, FsError, MergePolicy, MergeReport};
+
+/// Writable forks of a backend that can be merged back.
+///
+/// This is an optional trait, not part of any composite trait. A branch
+/// starts as a copy-on-write fork of the backend's current state; writes to
+/// it stay invisible to the backend until [`merge`](Self::merge), and writes
+/// to the backend stay invisible to the branch. Config staging, A/B
+/// experiments and dry runs use the same workflow on every backend that has
+/// one.
+///
+/// Middleware don't forward this trait: a fork handed out by the wrapped
+/// backend would bypass the middleware entirely.
+///
+/// # Merge Contract
+///
+/// A path conflicts when both the branch and the backend changed it since
+/// the fork, to different results. Paths changed on one side only are
+/// applied (or kept) without question; conflicts are settled by the
+/// [`MergePolicy`]. A successful merge removes the branch. With
+/// [`MergePolicy::Abort`] and conflicts present, nothing is applied, the
+/// branch is kept, and the conflicts are reported so the caller can resolve
+/// them in the branch and try again.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsBranch`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsBranch, FsError, MergePolicy};
+/// use std::path::Path;
+///
+/// // Stage a config change, then publish it only if nobody raced us
+/// fn publish<B: FsBranch>(backend: &B, config: &[u8]) -> Result<bool, FsError> {
+///     let staging = backend.branch("staging")?;
+///     staging.write(Path::new("/app/config.toml"), config)?;
+///
+///     let report = backend.merge("staging", MergePolicy::Abort)?;
+///     if !report.merged {
+///         backend.discard_branch("staging")?;
+///     }
+///     Ok(report.merged)
+/// }
+/// ```
+pub trait FsBranch: Send + Sync {
+    /// Fork the backend's current state into a new branch called `name`.
+    ///
+    /// The returned backend reads and writes the branch. Drop it once the
+    /// branch is merged or discarded; what it does afterwards is up to the
+    /// backend.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::AlreadyExists`] if a branch called `name` exists
+    fn branch(&self, name: &str) -> Result<Box<dyn Fs>, FsError>;
+
+    /// Apply the changes made in branch `name` to the backend.
+    ///
+    /// See the [merge contract](Self#merge-contract).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if there is no branch called `name`
+    /// - Errors from applying a change; the backend may then hold part of
+    ///   the merge, and the branch is kept
+    fn merge(&self, name: &str, policy: MergePolicy) -> Result<MergeReport, FsError>;
+
+    /// Drop branch `name` and its changes.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if there is no branch called `name`
+    fn discard_branch(&self, name: &str) -> Result<(), FsError>;
+
+    /// Names of the existing branches, sorted.
+    fn branches(&self) -> Result<Vec<String>, FsError>;
+}
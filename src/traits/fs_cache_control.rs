@@ -0,0 +1,102 @@
+//! Control of cache middleware.
+
+use std::path::Path;
+
+use crate::{CachePolicy, CacheStats, FsError, NotFoundCacheHint};
+
+/// Configuration and control of a caching layer.
+///
+/// This is an optional trait, not part of any composite trait. Cache
+/// middleware implements it so callers can inspect, tune and invalidate any
+/// cache the same way, whatever its eviction strategy. Configuration is a
+/// [`CachePolicy`]; counters are [`CacheStats`].
+///
+/// Implementations also override [`FsRead::exists_fresh`](crate::FsRead::exists_fresh)
+/// to skip their cached entry, and report through
+/// [`not_found_hint`](Self::not_found_hint) how far a cached `false` from
+/// `exists` can be trusted. When caches are stacked, report the loosest
+/// hint of the stack.
+///
+/// Invalidation is needed when the cached backend changes behind the
+/// cache's back, e.g. a shared remote written by other clients. Under
+/// [`WritePolicy::WriteBack`](crate::WritePolicy::WriteBack), invalidating
+/// an entry writes its dirty data back first; nothing written through the
+/// cache is ever dropped.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsCacheControl`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsCacheControl, FsError, WritePolicy};
+/// use std::path::Path;
+///
+/// // Another client replaced the file: make sure the next read sees it
+/// fn refresh<B: FsCacheControl>(cache: &B, path: &Path) -> Result<(), FsError> {
+///     if cache.cache_policy().write_policy == WritePolicy::WriteBack {
+///         cache.flush_cache()?;
+///     }
+///     cache.invalidate(path)
+/// }
+/// ```
+pub trait FsCacheControl: Send + Sync {
+    /// The policy in effect.
+    fn cache_policy(&self) -> CachePolicy;
+
+    /// Replace the policy. Entries over the new limits are evicted.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotSupported`] if the cache can't be reconfigured
+    ///   while in use (the default)
+    /// - Errors from writing back evicted dirty entries
+    fn set_cache_policy(&self, policy: CachePolicy) -> Result<(), FsError> {
+        let _ = policy;
+        Err(FsError::NotSupported {
+            operation: "set_cache_policy",
+        })
+    }
+
+    /// Current counters.
+    fn cache_stats(&self) -> CacheStats;
+
+    /// How stale a cached "not found" result may be.
+    ///
+    /// # Default Implementation
+    ///
+    /// Derived from the [`negative_ttl`](CachePolicy::negative_ttl) of
+    /// [`cache_policy`](Self::cache_policy).
+    fn not_found_hint(&self) -> NotFoundCacheHint {
+        NotFoundCacheHint::from_policy(&self.cache_policy())
+    }
+
+    /// Drop what is cached about `path`, and everything below it if it is a
+    /// directory. Succeeds if nothing was cached.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from writing back dirty data
+    fn invalidate(&self, path: &Path) -> Result<(), FsError>;
+
+    /// Drop every cached entry.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from writing back dirty data
+    fn invalidate_all(&self) -> Result<(), FsError>;
+
+    /// Write every dirty entry back to the backend, keeping it cached.
+    /// Does nothing under [`WritePolicy::WriteThrough`](crate::WritePolicy::WriteThrough).
+    ///
+    /// # Errors
+    ///
+    /// - Errors from writing back dirty data; entries that failed stay dirty
+    fn flush_cache(&self) -> Result<(), FsError>;
+}
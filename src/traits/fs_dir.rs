@@ -1,8 +1,8 @@
 //! Directory operations for virtual filesystems.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::{DirEntry, FsError};
+use crate::{DirEntry, DirEntryError, FsError, Permissions, ReadDirOptions};
 
 /// Directory operations for a virtual filesystem.
 ///
@@ -21,6 +21,11 @@ pub trait FsDir: Send + Sync {
     /// whether the directory could be opened; each item's `Result` indicates
     /// whether that specific entry could be read.
     ///
+    /// The order of entries is unspecified: it may differ between backends
+    /// and between calls. Use [`read_dir_with`](FsDir::read_dir_with) with
+    /// [`ReadDirOptions::sorted`], or wrap the backend in
+    /// [`Deterministic`](crate::Deterministic), for a reproducible order.
+    ///
     /// # Errors
     ///
     /// - [`FsError::NotFound`] if the path does not exist
@@ -55,11 +60,243 @@ pub trait FsDir: Send + Sync {
 
     /// Remove a directory and all its contents recursively.
     ///
+    /// # Contract
+    ///
+    /// - Symlinks inside the tree are removed as links; they are never
+    ///   followed, so nothing outside `path` is touched.
+    /// - Removal is not atomic. If it fails partway, entries already removed
+    ///   stay removed and the first error is returned.
+    ///
+    /// Backends without a native recursive delete can implement this as
+    /// `self.remove_dir_all_report(path)?.into_result()` (see
+    /// [`FsExt::remove_dir_all_report`](crate::FsExt::remove_dir_all_report)),
+    /// which follows the contract using only `read_dir`, `remove_file` and
+    /// `remove_dir`.
+    ///
     /// # Errors
     ///
     /// - [`FsError::NotFound`] if the path does not exist
     /// - [`FsError::NotADirectory`] if the path is not a directory
     fn remove_dir_all(&self, path: &Path) -> Result<(), FsError>;
+
+    /// Create a directory (parent must exist) with the given permissions.
+    ///
+    /// Unlike `create_dir` followed by a separate `set_permissions`, a native
+    /// implementation never exposes the directory with a wider mode, which
+    /// matters for private `0o700` directories.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`], since `FsDir` alone cannot set
+    /// permissions and silently ignoring the mode could leak access. Backends
+    /// implementing [`FsPermissions`](crate::FsPermissions) should override
+    /// it, at minimum as `create_dir` + `set_permissions`; wrapping any such
+    /// backend in [`DefaultPermissions`](crate::DefaultPermissions) provides
+    /// exactly that layering.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if parent directory does not exist
+    /// - [`FsError::AlreadyExists`] if the path already exists
+    /// - [`FsError::NotSupported`] if the backend can't set permissions
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsDir, FsError, Permissions};
+    /// use std::path::Path;
+    ///
+    /// fn make_private<B: FsDir>(backend: &B) -> Result<(), FsError> {
+    ///     backend.create_dir_with(Path::new("/keys"), Permissions::from_mode(0o700))
+    /// }
+    /// ```
+    fn create_dir_with(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let _ = (path, perm);
+        Err(FsError::NotSupported {
+            operation: "create_dir_with",
+        })
+    }
+
+    /// Create a new, uniquely named `0o700` directory inside `parent`, like
+    /// `mkdtemp(3)`, and return its path.
+    ///
+    /// The name is `prefix` followed by random characters. The directory is
+    /// not removed automatically.
+    ///
+    /// # Default Implementation
+    ///
+    /// Picks random names and calls
+    /// [`create_dir_with`](FsDir::create_dir_with), retrying on
+    /// [`FsError::AlreadyExists`]. If `create_dir_with` is
+    /// [`FsError::NotSupported`], it falls back to
+    /// [`create_dir`](FsDir::create_dir), and the directory gets the
+    /// backend's default mode instead of `0o700`. Wrap such backends in
+    /// [`DefaultPermissions`](crate::DefaultPermissions) to keep the
+    /// directory private.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `parent` does not exist
+    /// - [`FsError::AlreadyExists`] if no free name was found after many attempts
+    /// - Errors from [`create_dir_with`](FsDir::create_dir_with) or
+    ///   [`create_dir`](FsDir::create_dir)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsDir, FsError};
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// fn scratch_space<B: FsDir>(backend: &B) -> Result<PathBuf, FsError> {
+    ///     backend.create_temp_dir_in(Path::new("/tmp"), "build-")
+    /// }
+    /// ```
+    fn create_temp_dir_in(&self, parent: &Path, prefix: &str) -> Result<PathBuf, FsError> {
+        const ATTEMPTS: usize = 64;
+
+        let mut native = true;
+        let mut last = None;
+        for _ in 0..ATTEMPTS {
+            let path = parent.join(format!("{prefix}{}", random_suffix()));
+            let mut result = if native {
+                self.create_dir_with(&path, Permissions::from_mode(0o700))
+            } else {
+                self.create_dir(&path)
+            };
+            if matches!(result, Err(FsError::NotSupported { .. })) && native {
+                native = false;
+                result = self.create_dir(&path);
+            }
+            match result {
+                Ok(()) => return Ok(path),
+                Err(FsError::AlreadyExists { path, operation }) => {
+                    last = Some(FsError::AlreadyExists { path, operation });
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last.unwrap_or(FsError::AlreadyExists {
+            path: parent.to_path_buf(),
+            operation: "create_temp_dir_in",
+        }))
+    }
+
+    /// Number of entries in a directory, not counting `.` and `..`.
+    ///
+    /// For item counts in UIs and inode accounting in quota layers.
+    ///
+    /// # Default Implementation
+    ///
+    /// Iterates [`read_dir`](FsDir::read_dir). Backends that keep an index or
+    /// a per-directory count should override it.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the directory does not exist
+    /// - [`FsError::NotADirectory`] if the path is not a directory
+    /// - Any per-entry error from the listing
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsDir, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn item_label<B: FsDir>(backend: &B, dir: &Path) -> Result<String, FsError> {
+    ///     Ok(format!("{} items", backend.dir_len(dir)?))
+    /// }
+    /// ```
+    fn dir_len(&self, path: &Path) -> Result<u64, FsError> {
+        let mut len = 0;
+        for entry in self.read_dir(path)? {
+            entry?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    /// Returns `true` if a directory has no entries.
+    ///
+    /// # Default Implementation
+    ///
+    /// Reads the first entry of [`read_dir`](FsDir::read_dir) and stops.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the directory does not exist
+    /// - [`FsError::NotADirectory`] if the path is not a directory
+    /// - An error for the first entry of the listing
+    fn is_dir_empty(&self, path: &Path) -> Result<bool, FsError> {
+        match self.read_dir(path)?.next() {
+            None => Ok(true),
+            Some(entry) => entry.map(|_| false),
+        }
+    }
+
+    /// List directory contents, in the order `options` ask for.
+    ///
+    /// Behaves like [`read_dir`](FsDir::read_dir). With
+    /// [`ReadDirOptions::sorted`], entries come sorted byte-wise by name and
+    /// unreadable entries come last, in listing order.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`read_dir`](FsDir::read_dir) and, if asked, sorts the whole
+    /// listing in memory with [`ReadDirIter::sorted`]. Backends that store
+    /// entries in name order (B-trees, sorted object listings) should
+    /// override it to stream them instead.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_dir`](FsDir::read_dir).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsDir, FsError, ReadDirOptions};
+    /// use std::path::Path;
+    ///
+    /// fn snapshot_names<B: FsDir>(backend: &B) -> Result<Vec<String>, FsError> {
+    ///     backend
+    ///         .read_dir_with(Path::new("/"), ReadDirOptions::sorted())?
+    ///         .map(|entry| entry.map(|e| e.name))
+    ///         .collect()
+    /// }
+    /// ```
+    fn read_dir_with(&self, path: &Path, options: ReadDirOptions) -> Result<ReadDirIter, FsError> {
+        let entries = self.read_dir(path)?;
+        Ok(if options.sorted {
+            entries.sorted()
+        } else {
+            entries
+        })
+    }
+}
+
+/// Unpredictable 10-character name suffix for temporary entries.
+///
+/// Seeds a randomly keyed std hasher with a process-wide counter and the
+/// current time, so no extra dependency is needed.
+fn random_suffix() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hash, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::SystemTime;
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+    let mut hasher = RandomState::new().build_hasher();
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    SystemTime::now().hash(&mut hasher);
+    let mut bits = hasher.finish();
+    (0..10)
+        .map(|_| {
+            let c = ALPHABET[(bits % ALPHABET.len() as u64) as usize];
+            bits /= ALPHABET.len() as u64;
+            char::from(c)
+        })
+        .collect()
 }
 
 /// Iterator over directory entries.
@@ -69,6 +306,12 @@ pub trait FsDir: Send + Sync {
 /// - Outer `Result` (from [`FsDir::read_dir`]) = "can I open this directory?"
 /// - Inner `Result` (per item) = "can I read this entry?"
 ///
+/// A bad entry doesn't end the listing: keep iterating to get the rest.
+/// Backends that know the failed entry's name build the iterator with
+/// [`from_detailed`](Self::from_detailed); consumers get that name through
+/// [`next_detailed`](Self::next_detailed) or [`skip_errors`](Self::skip_errors).
+/// Plain iteration yields just the [`FsError`].
+///
 /// # Example
 ///
 /// ```rust
@@ -85,13 +328,23 @@ pub trait FsDir: Send + Sync {
 ///     Ok(names)
 /// }
 /// ```
-pub struct ReadDirIter(Box<dyn Iterator<Item = Result<DirEntry, FsError>> + Send + 'static>);
+pub struct ReadDirIter(Box<dyn Iterator<Item = Result<DirEntry, DirEntryError>> + Send + 'static>);
 
 impl ReadDirIter {
     /// Create from any compatible iterator.
     pub fn new<I>(iter: I) -> Self
     where
         I: Iterator<Item = Result<DirEntry, FsError>> + Send + 'static,
+    {
+        Self(Box::new(
+            iter.map(|item| item.map_err(DirEntryError::unnamed)),
+        ))
+    }
+
+    /// Create from an iterator whose errors name the failed entry.
+    pub fn from_detailed<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Result<DirEntry, DirEntryError>> + Send + 'static,
     {
         Self(Box::new(iter))
     }
@@ -100,7 +353,7 @@ impl ReadDirIter {
     ///
     /// Useful for middleware like Overlay that merges multiple directory listings.
     pub fn from_vec(entries: Vec<Result<DirEntry, FsError>>) -> Self {
-        Self(Box::new(entries.into_iter()))
+        Self::new(entries.into_iter())
     }
 
     /// Collect all entries, short-circuiting on first error.
@@ -109,13 +362,114 @@ impl ReadDirIter {
     pub fn collect_all(self) -> Result<Vec<DirEntry>, FsError> {
         self.collect()
     }
+
+    /// Next entry, with the failed entry's name on error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsDir, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn report_bad<B: FsDir>(backend: &B) -> Result<(), FsError> {
+    ///     let mut entries = backend.read_dir(Path::new("/"))?;
+    ///     while let Some(item) = entries.next_detailed() {
+    ///         if let Err(e) = item {
+    ///             eprintln!("skipping {:?}: {}", e.name, e.source);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn next_detailed(&mut self) -> Option<Result<DirEntry, DirEntryError>> {
+        self.0.next()
+    }
+
+    /// Adapter yielding only readable entries; failures are kept for
+    /// inspection through [`SkipErrors::errors`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{DirEntry, FsDir, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn best_effort<B: FsDir>(backend: &B) -> Result<Vec<DirEntry>, FsError> {
+    ///     let mut entries = backend.read_dir(Path::new("/"))?.skip_errors();
+    ///     let good: Vec<DirEntry> = entries.by_ref().collect();
+    ///     for e in entries.errors() {
+    ///         eprintln!("unreadable entry: {e}");
+    ///     }
+    ///     Ok(good)
+    /// }
+    /// ```
+    pub fn skip_errors(self) -> SkipErrors {
+        SkipErrors {
+            inner: self,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Collect the listing and yield it sorted byte-wise by
+    /// [`file_name`](DirEntry::file_name), unreadable entries last in their
+    /// original order.
+    ///
+    /// Reads the whole directory before yielding anything.
+    pub fn sorted(self) -> Self {
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        for item in self.0 {
+            match item {
+                Ok(entry) => entries.push(entry),
+                Err(e) => errors.push(e),
+            }
+        }
+        entries.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        Self::from_detailed(
+            entries
+                .into_iter()
+                .map(Ok)
+                .chain(errors.into_iter().map(Err)),
+        )
+    }
 }
 
 impl Iterator for ReadDirIter {
     type Item = Result<DirEntry, FsError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        self.0.next().map(|item| item.map_err(FsError::from))
+    }
+}
+
+/// Iterator adapter from [`ReadDirIter::skip_errors`].
+pub struct SkipErrors {
+    inner: ReadDirIter,
+    errors: Vec<DirEntryError>,
+}
+
+impl SkipErrors {
+    /// Errors skipped so far.
+    pub fn errors(&self) -> &[DirEntryError] {
+        &self.errors
+    }
+
+    /// Consume the adapter, returning the errors skipped so far.
+    pub fn into_errors(self) -> Vec<DirEntryError> {
+        self.errors
+    }
+}
+
+impl Iterator for SkipErrors {
+    type Item = DirEntry;
+
+    fn next(&mut self) -> Option<DirEntry> {
+        loop {
+            match self.inner.next_detailed()? {
+                Ok(entry) => return Some(entry),
+                Err(e) => self.errors.push(e),
+            }
+        }
     }
 }
 
@@ -123,7 +477,6 @@ impl Iterator for ReadDirIter {
 mod tests {
     use super::*;
     use crate::FileType;
-    use std::path::PathBuf;
 
     #[test]
     fn read_dir_iter_from_vec() {
@@ -134,6 +487,7 @@ mod tests {
                 file_type: FileType::File,
                 size: 0,
                 inode: 1,
+                ..Default::default()
             }),
             Ok(DirEntry {
                 name: "b".into(),
@@ -141,6 +495,7 @@ mod tests {
                 file_type: FileType::Directory,
                 size: 0,
                 inode: 2,
+                ..Default::default()
             }),
         ];
         let iter = ReadDirIter::from_vec(entries);
@@ -156,6 +511,7 @@ mod tests {
             file_type: FileType::File,
             size: 100,
             inode: 1,
+            ..Default::default()
         })];
         let iter = ReadDirIter::from_vec(entries);
         let result = iter.collect_all();
@@ -174,6 +530,7 @@ mod tests {
                 file_type: FileType::File,
                 size: 0,
                 inode: 1,
+                ..Default::default()
             }),
             Err(FsError::PermissionDenied {
                 path: PathBuf::from("/b"),
@@ -185,9 +542,69 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn sorted_orders_by_bytes_and_keeps_errors_last() {
+        let entry = |name: &str| {
+            Ok(DirEntry {
+                name: name.into(),
+                ..Default::default()
+            })
+        };
+        let iter = ReadDirIter::from_vec(vec![
+            entry("b"),
+            Err(FsError::Backend("unreadable".into())),
+            entry("B"),
+            entry("a"),
+        ]);
+
+        let items: Vec<_> = iter.sorted().collect();
+        let names: Vec<_> = items[..3]
+            .iter()
+            .map(|item| item.as_ref().unwrap().name.as_str())
+            .collect();
+        assert_eq!(names, ["B", "a", "b"]);
+        assert!(items[3].is_err());
+    }
+
     #[test]
     fn read_dir_iter_is_send() {
         fn assert_send<T: Send>() {}
         assert_send::<ReadDirIter>();
     }
+
+    #[test]
+    fn skip_errors_continues_past_bad_entries() {
+        let entries = vec![
+            Ok(DirEntry {
+                name: "a".into(),
+                ..Default::default()
+            }),
+            Err(DirEntryError::named(
+                "b",
+                FsError::InvalidData {
+                    path: PathBuf::from("/b"),
+                    details: "bad".into(),
+                },
+            )),
+            Ok(DirEntry {
+                name: "c".into(),
+                ..Default::default()
+            }),
+        ];
+        let mut iter = ReadDirIter::from_detailed(entries.into_iter()).skip_errors();
+        let names: Vec<String> = iter.by_ref().map(|e| e.name).collect();
+        assert_eq!(names, ["a", "c"]);
+        assert_eq!(iter.errors().len(), 1);
+        assert_eq!(iter.errors()[0].name.as_deref(), Some("b".as_ref()));
+    }
+
+    #[test]
+    fn plain_iteration_drops_entry_name() {
+        let entries = vec![Err(DirEntryError::named(
+            "x",
+            FsError::not_found("read_dir", "/x"),
+        ))];
+        let mut iter = ReadDirIter::from_detailed(entries.into_iter());
+        assert!(matches!(iter.next(), Some(Err(FsError::NotFound { .. }))));
+    }
 }
@@ -0,0 +1,149 @@
+//! Directory handles for `openat`-style relative operations.
+//!
+//! This module provides the [`FsDirHandles`] trait. Security-sensitive code
+//! (extractors, sandboxes, cleanup tools) must not resolve absolute paths
+//! twice: between a check and the following use, a parent directory can be
+//! swapped for a symlink that points somewhere else. Directory handles pin
+//! the directory once and resolve single names relative to it:
+//!
+//! 1. `open_dir()` - Pin a directory and get a [`DirHandle`]
+//! 2. `open_dir_at()` - Descend into a child without re-resolving the parent
+//! 3. `open_at()` / `metadata_at()` / `unlink_at()` / `mkdir_at()` - Act on
+//!    one entry of the pinned directory
+//! 4. `close_dir()` - Release the handle
+//!
+//! # Example
+//!
+//! ```rust
+//! use anyfs_backend::{FsDirHandles, FsError, OpenFlags};
+//! use std::ffi::OsStr;
+//! use std::path::Path;
+//!
+//! // Write an upload into /uploads without following a swapped-in symlink
+//! fn store_upload<B: FsDirHandles>(backend: &B, name: &str, data: &[u8]) -> Result<(), FsError> {
+//!     let dir = backend.open_dir(Path::new("/uploads"))?;
+//!     let handle = backend.open_at(dir, OsStr::new(name), OpenFlags::WRITE)?;
+//!     backend.write_at(handle, data, 0)?;
+//!     backend.close(handle)?;
+//!     backend.close_dir(dir)
+//! }
+//! ```
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::{DirHandle, FsError, FsHandles, Handle, Metadata, OpenFlags};
+
+/// `openat`-style operations relative to an open directory.
+///
+/// This is an optional trait, not part of any composite trait. It requires
+/// [`FsHandles`] because [`open_at`](FsDirHandles::open_at) returns a file
+/// [`Handle`] used with `read_at`, `write_at` and `close`.
+///
+/// # Contract
+///
+/// - A [`DirHandle`] keeps addressing the same directory after it (or any
+///   ancestor) is renamed.
+/// - Every `name` is a single path component. Empty names, `.`, `..` and
+///   names containing a separator are rejected with
+///   [`FsError::InvalidData`].
+/// - Symlinks are never followed for `name`: `open_at` on a symlink fails
+///   with [`FsError::NotAFile`], `open_dir_at` with
+///   [`FsError::NotADirectory`], and `metadata_at` describes the link itself.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsDirHandles`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsDirHandles, FsError};
+/// use std::ffi::OsStr;
+/// use std::path::Path;
+///
+/// // Remove a file from a directory only if it is still a regular file
+/// fn remove_if_file<B: FsDirHandles>(backend: &B, parent: &Path, name: &OsStr) -> Result<bool, FsError> {
+///     let dir = backend.open_dir(parent)?;
+///     let is_file = backend.metadata_at(dir, name)?.is_file();
+///     if is_file {
+///         backend.unlink_at(dir, name)?;
+///     }
+///     backend.close_dir(dir)?;
+///     Ok(is_file)
+/// }
+/// ```
+pub trait FsDirHandles: FsHandles {
+    /// Open the directory at `path` and return a handle to it.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotADirectory`] if the path is not a directory
+    /// - [`FsError::PermissionDenied`] if access is denied
+    fn open_dir(&self, path: &Path) -> Result<DirHandle, FsError>;
+
+    /// Open the subdirectory `name` of `dir`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `name` does not exist in `dir`
+    /// - [`FsError::NotADirectory`] if `name` is not a directory (including a
+    ///   symlink to one)
+    /// - [`FsError::InvalidHandle`] if `dir` is closed or stale
+    /// - [`FsError::InvalidData`] if `name` is not a single component
+    fn open_dir_at(&self, dir: DirHandle, name: &OsStr) -> Result<DirHandle, FsError>;
+
+    /// Release a directory handle.
+    ///
+    /// Handles returned by `open_at` stay valid after their directory is
+    /// closed.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if `dir` is already closed or stale
+    fn close_dir(&self, dir: DirHandle) -> Result<(), FsError>;
+
+    /// Open the file `name` in `dir`, like `openat(2)` with `O_NOFOLLOW`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `name` does not exist and `create` is false
+    /// - [`FsError::NotAFile`] if `name` is a directory or a symlink
+    /// - [`FsError::InvalidHandle`] if `dir` is closed or stale
+    /// - [`FsError::InvalidData`] if `name` is not a single component
+    fn open_at(&self, dir: DirHandle, name: &OsStr, flags: OpenFlags) -> Result<Handle, FsError>;
+
+    /// Metadata of the entry `name` in `dir`, without following symlinks.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `name` does not exist in `dir`
+    /// - [`FsError::InvalidHandle`] if `dir` is closed or stale
+    /// - [`FsError::InvalidData`] if `name` is not a single component
+    fn metadata_at(&self, dir: DirHandle, name: &OsStr) -> Result<Metadata, FsError>;
+
+    /// Remove the file or symlink `name` from `dir`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `name` does not exist in `dir`
+    /// - [`FsError::NotAFile`] if `name` is a directory
+    /// - [`FsError::InvalidHandle`] if `dir` is closed or stale
+    /// - [`FsError::InvalidData`] if `name` is not a single component
+    fn unlink_at(&self, dir: DirHandle, name: &OsStr) -> Result<(), FsError>;
+
+    /// Create the directory `name` in `dir`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::AlreadyExists`] if `name` already exists in `dir`
+    /// - [`FsError::InvalidHandle`] if `dir` is closed or stale
+    /// - [`FsError::InvalidData`] if `name` is not a single component
+    fn mkdir_at(&self, dir: DirHandle, name: &OsStr) -> Result<(), FsError>;
+}
@@ -0,0 +1,156 @@
+//! Expiring files (TTL) and a sweeper that purges them.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{FileType, Fs, FsError, FsExt, RemoveDirReport, RemoveFailure};
+
+/// Per-entry expiry times, e.g. for cache directories.
+///
+/// This is an optional trait, not part of any composite trait. Backends with
+/// native TTLs (object-store lifecycle rules, Redis) map to them and may
+/// delete expired entries on their own. Backends without them can store the
+/// time in the [`XATTR_EXPIRES`](crate::XATTR_EXPIRES) xattr through
+/// [`FsExt::xattr_expiry`] and [`FsExt::set_xattr_expiry`], and rely on an
+/// [`ExpirySweeper`] to purge expired entries.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsExpiry`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsError, FsExpiry};
+/// use std::path::Path;
+/// use std::time::{Duration, SystemTime};
+///
+/// fn cache<B: Fs + FsExpiry>(backend: &B, path: &Path, data: &[u8]) -> Result<(), FsError> {
+///     backend.write(path, data)?;
+///     backend.set_expiry(path, SystemTime::now() + Duration::from_secs(3600))
+/// }
+/// ```
+pub trait FsExpiry: Send + Sync {
+    /// Make the entry at `path` expire at `at`, replacing any earlier expiry.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn set_expiry(&self, path: &Path, at: SystemTime) -> Result<(), FsError>;
+
+    /// Remove the expiry of the entry at `path`. Succeeds if it had none.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn clear_expiry(&self, path: &Path) -> Result<(), FsError>;
+
+    /// Get the expiry of the entry at `path`, or `None` if it never expires.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn expiry(&self, path: &Path) -> Result<Option<SystemTime>, FsError>;
+}
+
+/// Removes expired entries below a directory.
+///
+/// An entry is expired once its [`FsExpiry::expiry`] is at or before the
+/// sweep time. Expired files and symlinks are removed, expired directories
+/// with everything in them. The root itself is never removed.
+///
+/// The sweep is best-effort: entries that can't be listed, checked or
+/// removed are reported as failures and the sweep continues.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{ExpirySweeper, Fs, FsError, FsExpiry};
+///
+/// fn purge_cache<B: Fs + FsExpiry>(backend: &B) -> Result<u64, FsError> {
+///     let report = ExpirySweeper::new("/cache").sweep(backend)?;
+///     for failure in &report.failures {
+///         eprintln!("kept {}: {}", failure.path.display(), failure.error);
+///     }
+///     Ok(report.removed)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExpirySweeper {
+    root: PathBuf,
+    now: Option<SystemTime>,
+}
+
+impl ExpirySweeper {
+    /// Sweep the tree rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            now: None,
+        }
+    }
+
+    /// Judge expiry against `now` instead of the system clock.
+    pub fn at(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Remove every expired entry below the root.
+    ///
+    /// [`RemoveDirReport::removed`] counts the expired entries removed;
+    /// entries that go along with an expired directory are not counted.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the root does not exist
+    /// - [`FsError::NotADirectory`] if the root is not a directory
+    pub fn sweep<B: Fs + FsExpiry + ?Sized>(
+        &self,
+        backend: &B,
+    ) -> Result<RemoveDirReport, FsError> {
+        if !backend.metadata(&self.root)?.is_dir() {
+            return Err(FsError::not_a_directory("sweep", &self.root));
+        }
+        let now = self.now.unwrap_or_else(SystemTime::now);
+        let mut report = RemoveDirReport::default();
+
+        // Contents first, so removing a directory can't break the walk
+        let mut walk = backend.walk(&self.root).contents_first(true);
+        while let Some(entry) = walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let path = walk.current_dir().unwrap_or(&self.root).to_path_buf();
+                    report.failures.push(RemoveFailure { path, error });
+                    continue;
+                }
+            };
+            if entry.depth == 0 {
+                continue;
+            }
+            let result = match backend.expiry(entry.path()) {
+                Ok(Some(at)) if at <= now => if entry.file_type() == FileType::Directory {
+                    backend.remove_dir_all(entry.path())
+                } else {
+                    backend.remove_file(entry.path())
+                }
+                .map(|()| report.removed += 1),
+                Ok(_) => continue,
+                Err(error) => Err(error),
+            };
+            if let Err(error) = result {
+                report.failures.push(RemoveFailure {
+                    path: entry.entry.path,
+                    error,
+                });
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,148 @@
+//! Integration point for 9P / NFS export servers.
+//!
+//! This module provides the [`FsExport`] trait, which adapts [`FsPosix`]
+//! semantics into the pieces a network file server needs:
+//!
+//! 1. `export_id()` / `resolve_export_id()` - Stable [`FileId`]s that survive
+//!    renames, for NFS file handles and 9P qid paths
+//! 2. `open_by_id()` - Open a file from an id alone
+//! 3. `export_version()` - Change counter for 9P qid versions and the NFS
+//!    change attribute
+//! 4. `lease_hint()` - How long clients may cache attributes and lookups
+//!
+//! Every method has a default built on [`FsInode`](crate::FsInode), so an
+//! inode-based backend opts in with an empty `impl FsExport for MyFs {}`.
+//! Backends without stable inodes override the id methods.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::{FileId, FsError, FsPosix, Handle, LeaseHint, OpenFlags};
+
+/// What a 9P or NFS server needs from a backend beyond [`FsPosix`].
+///
+/// This is an optional trait, not part of any composite trait.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsExport`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsExport, FsError, OpenFlags};
+/// use std::path::Path;
+///
+/// // Turn an NFS LOOKUP into a file handle, then serve a later READ from it
+/// fn read_via_id<B: FsExport>(backend: &B, path: &Path) -> Result<Vec<u8>, FsError> {
+///     let id = backend.export_id(path)?;
+///     let handle = backend.open_by_id(&id, OpenFlags::READ)?;
+///     let mut buf = vec![0; 4096];
+///     let n = backend.read_at(handle, &mut buf, 0)?;
+///     backend.close(handle)?;
+///     buf.truncate(n);
+///     Ok(buf)
+/// }
+/// ```
+pub trait FsExport: FsPosix {
+    /// Stable identifier for the entry at `path`.
+    ///
+    /// The id must keep referring to the same file after it is renamed, and
+    /// must not be reused for a different file while clients may still hold
+    /// it.
+    ///
+    /// # Default Implementation
+    ///
+    /// [`FileId::from_inode`] of [`path_to_inode`](crate::FsInode::path_to_inode).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn export_id(&self, path: &Path) -> Result<FileId, FsError> {
+        Ok(FileId::from_inode(self.path_to_inode(path)?))
+    }
+
+    /// Current path of the file identified by `id`.
+    ///
+    /// # Default Implementation
+    ///
+    /// Decodes an inode id and calls
+    /// [`inode_to_path`](crate::FsInode::inode_to_path).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InodeNotFound`] if the file no longer exists (servers
+    ///   report this as a stale handle)
+    /// - [`FsError::InvalidData`] if `id` was not issued by this backend
+    fn resolve_export_id(&self, id: &FileId) -> Result<PathBuf, FsError> {
+        match id.as_inode() {
+            Some(inode) => self.inode_to_path(inode),
+            None => Err(FsError::InvalidData {
+                path: PathBuf::new(),
+                details: "file id was not issued by this backend".to_string(),
+            }),
+        }
+    }
+
+    /// Open the file identified by `id`.
+    ///
+    /// # Default Implementation
+    ///
+    /// [`resolve_export_id`](Self::resolve_export_id) followed by
+    /// [`open`](crate::FsHandles::open).
+    ///
+    /// # Errors
+    ///
+    /// - Errors from [`resolve_export_id`](Self::resolve_export_id)
+    /// - Errors from [`open`](crate::FsHandles::open)
+    fn open_by_id(&self, id: &FileId, flags: OpenFlags) -> Result<Handle, FsError> {
+        let path = self.resolve_export_id(id)?;
+        self.open(&path, flags)
+    }
+
+    /// A value that changes whenever the file's content or attributes change.
+    ///
+    /// Used for the 9P qid version and the NFSv4 change attribute. Only
+    /// equality matters; clients compare it with the value they cached.
+    ///
+    /// # Default Implementation
+    ///
+    /// [`Metadata::change_counter`](crate::Metadata::change_counter) when the
+    /// backend reports one, otherwise the modification time in nanoseconds
+    /// since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn export_version(&self, path: &Path) -> Result<u64, FsError> {
+        let meta = self.metadata(path)?;
+        if let Some(counter) = meta.change_counter {
+            return Ok(counter);
+        }
+        let nanos = meta
+            .modified
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_nanos())
+            .unwrap_or(0);
+        Ok(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// How long clients may cache attributes of and lookups for `path`.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`LeaseHint::NONE`] (no caching), which is always safe.
+    ///
+    /// # Errors
+    ///
+    /// - Backend-specific errors; the default never fails
+    fn lease_hint(&self, path: &Path) -> Result<LeaseHint, FsError> {
+        let _ = path;
+        Ok(LeaseHint::NONE)
+    }
+}
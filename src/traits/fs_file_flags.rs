@@ -0,0 +1,49 @@
+//! `chattr`-style file flags.
+
+use std::path::Path;
+
+use crate::{FileFlags, FsError};
+
+/// `chattr`-style immutable / append-only flags.
+///
+/// This is an optional trait, not part of any composite trait. Backends store
+/// the flags; [`EnforceFlags`](crate::EnforceFlags) middleware enforces them
+/// for backends that don't enforce them natively.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsFileFlags`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsFileFlags, FileFlags, FsError};
+/// use std::path::Path;
+///
+/// // Seal an audit log: it can grow but never be rewritten
+/// fn seal_log<B: FsFileFlags>(backend: &B, path: &Path) -> Result<(), FsError> {
+///     let flags = backend.get_flags(path)?;
+///     backend.set_flags(path, FileFlags { append_only: true, ..flags })
+/// }
+/// ```
+pub trait FsFileFlags: Send + Sync {
+    /// Replace the flags of a file or directory.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::PermissionDenied`] if the caller may not change flags
+    fn set_flags(&self, path: &Path, flags: FileFlags) -> Result<(), FsError>;
+
+    /// Get the flags of a file or directory.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn get_flags(&self, path: &Path) -> Result<FileFlags, FsError>;
+}
@@ -0,0 +1,65 @@
+//! Stable file identity independent of inodes.
+
+use std::path::{Path, PathBuf};
+
+use crate::{FileId, FsError};
+
+/// Look files up by a stable [`FileId`] instead of by path.
+///
+/// Cloud and database backends usually key objects by ids that don't fit in
+/// a `u64` inode. This trait lets dedup, sync and backup code track identity
+/// (and notice renames) without forcing such backends to invent an inode
+/// scheme.
+///
+/// This is an optional trait, not part of any composite trait. Unlike
+/// [`FsInode`](crate::FsInode) it has no notion of a root id or of lookups by
+/// name; it only maps between paths and ids.
+///
+/// # Contract
+///
+/// - The id of a file does not change when it is renamed or its content is
+///   rewritten in place.
+/// - Two paths with the same id are hard links to one file.
+/// - An id is not reused for another file while the original exists.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsFileId`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsFileId, FsError};
+/// use std::path::{Path, PathBuf};
+///
+/// // Detect that a file moved since the last sync
+/// fn moved_to<B: FsFileId>(backend: &B, old: &Path) -> Result<Option<PathBuf>, FsError> {
+///     let id = backend.file_id(old)?;
+///     let now = backend.path_by_id(&id)?;
+///     Ok(if now == old { None } else { Some(now) })
+/// }
+/// ```
+pub trait FsFileId: Send + Sync {
+    /// The stable id of the entry at `path`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn file_id(&self, path: &Path) -> Result<FileId, FsError>;
+
+    /// A current path of the file identified by `id`.
+    ///
+    /// For a file with several hard links, any one of its paths may be
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if no file has this id (any more)
+    /// - [`FsError::InvalidData`] if `id` was not issued by this backend
+    fn path_by_id(&self, id: &FileId) -> Result<PathBuf, FsError>;
+}
@@ -0,0 +1,217 @@
+//! Open-handle introspection for leak detection.
+//!
+//! This module provides the [`FsHandleDebug`] trait which lets debug layers
+//! and tests enumerate the handles a backend currently holds open.
+//!
+//! # Overview
+//!
+//! Leaked handles silently exhaust backend resources. Backends that track
+//! their open handles can expose them through this trait:
+//!
+//! 1. `open_handles()` - Snapshot of every open handle with its statistics
+//! 2. `close_all()` - Forcefully release every open handle
+//!
+//! # Example
+//!
+//! ```rust
+//! use anyfs_backend::{FsHandleDebug, FsError};
+//!
+//! // Generic function that works with any FsHandleDebug implementation
+//! fn assert_no_leaks<B: FsHandleDebug>(backend: &B) -> Result<(), FsError> {
+//!     let open = backend.open_handles()?;
+//!     for info in &open {
+//!         eprintln!("leaked handle {:?} on {}", info.handle, info.path.display());
+//!     }
+//!     assert!(open.is_empty());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Thread Safety
+//!
+//! Like all AnyFS traits, `FsHandleDebug` requires `Send + Sync`.
+
+use crate::{FsError, FsHandles, HandleInfo};
+
+/// Open-handle introspection for debug layers and tests.
+///
+/// This is an optional trait, not part of any composite trait. Backends that
+/// keep a handle table can implement it cheaply; debug middleware uses it to
+/// report leaks, and test suites use it to assert that every `open` was
+/// matched by a `close`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsHandleDebug, FsError};
+///
+/// // Release everything a test left behind and report how much leaked
+/// fn reset<B: FsHandleDebug>(backend: &B) -> Result<usize, FsError> {
+///     let leaked = backend.open_handles()?.len();
+///     backend.close_all()?;
+///     Ok(leaked)
+/// }
+/// ```
+pub trait FsHandleDebug: FsHandles {
+    /// List every currently open handle.
+    ///
+    /// The returned snapshot is not kept in sync with later `open`/`close`
+    /// calls. Ordering is unspecified.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Backend`] for backend-specific failures
+    fn open_handles(&self) -> Result<Vec<HandleInfo>, FsError>;
+
+    /// Close every currently open handle.
+    ///
+    /// Any locks held through the closed handles are released as if
+    /// [`close`](FsHandles::close) had been called on each of them.
+    ///
+    /// # Returns
+    ///
+    /// The number of handles that were closed.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Backend`] for backend-specific failures
+    fn close_all(&self) -> Result<usize, FsError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Handle, OpenFlags};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::RwLock;
+    use std::time::SystemTime;
+
+    /// Mock backend that only tracks open handles
+    struct MockDebugFs {
+        next_handle: AtomicU64,
+        handles: RwLock<HashMap<u64, HandleInfo>>,
+    }
+
+    impl MockDebugFs {
+        fn new() -> Self {
+            Self {
+                next_handle: AtomicU64::new(1),
+                handles: RwLock::new(HashMap::new()),
+            }
+        }
+    }
+
+    impl FsHandles for MockDebugFs {
+        fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+            let handle = Handle(self.next_handle.fetch_add(1, Ordering::SeqCst));
+            self.handles.write().unwrap().insert(
+                handle.0,
+                HandleInfo {
+                    handle,
+                    path: path.to_path_buf(),
+                    flags,
+                    opened_at: SystemTime::UNIX_EPOCH,
+                    bytes_read: 0,
+                    bytes_written: 0,
+                },
+            );
+            Ok(handle)
+        }
+
+        fn read_at(&self, handle: Handle, buf: &mut [u8], _offset: u64) -> Result<usize, FsError> {
+            let mut handles = self.handles.write().unwrap();
+            let info = handles
+                .get_mut(&handle.0)
+                .ok_or(FsError::InvalidHandle { handle })?;
+            info.bytes_read += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn write_at(&self, handle: Handle, data: &[u8], _offset: u64) -> Result<usize, FsError> {
+            let mut handles = self.handles.write().unwrap();
+            let info = handles
+                .get_mut(&handle.0)
+                .ok_or(FsError::InvalidHandle { handle })?;
+            info.bytes_written += data.len() as u64;
+            Ok(data.len())
+        }
+
+        fn close(&self, handle: Handle) -> Result<(), FsError> {
+            self.handles
+                .write()
+                .unwrap()
+                .remove(&handle.0)
+                .map(|_| ())
+                .ok_or(FsError::InvalidHandle { handle })
+        }
+    }
+
+    impl FsHandleDebug for MockDebugFs {
+        fn open_handles(&self) -> Result<Vec<HandleInfo>, FsError> {
+            Ok(self.handles.read().unwrap().values().cloned().collect())
+        }
+
+        fn close_all(&self) -> Result<usize, FsError> {
+            let mut handles = self.handles.write().unwrap();
+            let count = handles.len();
+            handles.clear();
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn open_handles_lists_open_handles() {
+        let fs = MockDebugFs::new();
+        let handle = fs.open(Path::new("/a.txt"), OpenFlags::READ).unwrap();
+
+        let open = fs.open_handles().unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].handle, handle);
+        assert_eq!(open[0].path, PathBuf::from("/a.txt"));
+        assert!(open[0].flags.read);
+    }
+
+    #[test]
+    fn open_handles_tracks_statistics() {
+        let fs = MockDebugFs::new();
+        let handle = fs.open(Path::new("/a.txt"), OpenFlags::READ_WRITE).unwrap();
+        let mut buf = [0u8; 4];
+        fs.read_at(handle, &mut buf, 0).unwrap();
+        fs.write_at(handle, b"hello", 0).unwrap();
+
+        let info = &fs.open_handles().unwrap()[0];
+        assert_eq!(info.bytes_read, 4);
+        assert_eq!(info.bytes_written, 5);
+    }
+
+    #[test]
+    fn closed_handles_are_not_listed() {
+        let fs = MockDebugFs::new();
+        let handle = fs.open(Path::new("/a.txt"), OpenFlags::READ).unwrap();
+        fs.close(handle).unwrap();
+        assert!(fs.open_handles().unwrap().is_empty());
+    }
+
+    #[test]
+    fn close_all_releases_every_handle() {
+        let fs = MockDebugFs::new();
+        let first = fs.open(Path::new("/a.txt"), OpenFlags::READ).unwrap();
+        fs.open(Path::new("/b.txt"), OpenFlags::WRITE).unwrap();
+
+        assert_eq!(fs.close_all().unwrap(), 2);
+        assert!(fs.open_handles().unwrap().is_empty());
+        assert!(matches!(
+            fs.close(first),
+            Err(FsError::InvalidHandle { .. })
+        ));
+    }
+
+    #[test]
+    fn fs_handle_debug_is_object_safe() {
+        let fs = MockDebugFs::new();
+        let debug: &dyn FsHandleDebug = &fs;
+        assert!(debug.open_handles().unwrap().is_empty());
+    }
+}
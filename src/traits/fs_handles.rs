@@ -50,9 +50,12 @@
 //! Like all AnyFS traits, `FsHandles` requires `Send + Sync`. Implementations
 //! must use interior mutability and handle concurrent access properly.
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
-use crate::{FsError, Handle, OpenFlags};
+use crate::{FsError, FsRead, FsWrite, Handle, OpenFlags, Permissions};
 
 /// Handle-based file operations for POSIX compatibility.
 ///
@@ -68,6 +71,43 @@ use crate::{FsError, Handle, OpenFlags};
 ///    [`write_at`](FsHandles::write_at)
 /// 3. Release the handle with [`close`](FsHandles::close)
 ///
+/// # Handle Reuse
+///
+/// Implementations that recycle handle ids must advance the slot's
+/// generation on reuse (see [`Handle::next_generation`]) and reject handles
+/// whose generation doesn't match the live slot with
+/// [`FsError::InvalidHandle`]. A stale handle must never reach another file.
+/// [`HandleTable`](crate::HandleTable) does all of this for you.
+///
+/// # Append Handles
+///
+/// On a handle opened with `append: true`, [`write_at`](FsHandles::write_at)
+/// ignores `offset` and writes at the end of the file, as Linux `pwrite` does
+/// on an `O_APPEND` descriptor. Finding the end and writing are one atomic
+/// step: concurrent appends through any number of handles never overlap or
+/// interleave within a call, and never overwrite each other.
+///
+/// `write_at` doesn't say where the data landed. Callers that need to know
+/// (log indexes, record offsets) use [`append_handle`](FsHandles::append_handle),
+/// which returns it.
+///
+/// # FIFOs
+///
+/// A [`FileType::Fifo`](crate::FileType::Fifo) created with
+/// [`mkfifo`](FsHandles::mkfifo) is a byte stream, not a byte array. Handles
+/// on it never block, so the contract differs from regular files:
+///
+/// - [`open`](FsHandles::open) succeeds without waiting for a peer. `create`
+///   and `truncate` have no effect on an existing FIFO.
+/// - The `offset` argument of [`read_at`](FsHandles::read_at) and
+///   [`write_at`](FsHandles::write_at) is ignored. Bytes are read once, in
+///   the order they were written.
+/// - `read_at` returns the bytes available, or `Ok(0)` once the FIFO is
+///   empty and no handle has it open for writing. If it is empty but a
+///   writer is still open, it fails with [`FsError::TimedOut`]; retry later.
+/// - Path-based [`read`](crate::FsRead::read) and
+///   [`write`](crate::FsWrite::write) fail with [`FsError::NotAFile`].
+///
 /// # Example
 ///
 /// ```rust
@@ -98,7 +138,7 @@ pub trait FsHandles: Send + Sync {
     /// # Errors
     ///
     /// - [`FsError::NotFound`] if the file doesn't exist and `create` is false
-    /// - [`FsError::AlreadyExists`] if the file exists and exclusive creation is requested
+    /// - [`FsError::AlreadyExists`] if the file exists and `flags.create_new` is set
     /// - [`FsError::NotAFile`] if the path is a directory
     /// - [`FsError::PermissionDenied`] if access is denied
     fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError>;
@@ -117,7 +157,7 @@ pub trait FsHandles: Send + Sync {
     ///
     /// # Errors
     ///
-    /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
     /// - [`FsError::PermissionDenied`] if the handle wasn't opened for reading
     fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError>;
 
@@ -135,9 +175,12 @@ pub trait FsHandles: Send + Sync {
     ///
     /// # Errors
     ///
-    /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
     /// - [`FsError::PermissionDenied`] if the handle wasn't opened for writing
     /// - [`FsError::QuotaExceeded`] if storage quota is exceeded
+    ///
+    /// On an append handle `offset` is ignored; see
+    /// [Append Handles](FsHandles#append-handles).
     fn write_at(&self, handle: Handle, data: &[u8], offset: u64) -> Result<usize, FsError>;
 
     /// Close a file handle.
@@ -151,8 +194,367 @@ pub trait FsHandles: Send + Sync {
     ///
     /// # Errors
     ///
-    /// - [`FsError::InvalidHandle`] if the handle is already closed or invalid
+    /// - [`FsError::InvalidHandle`] if the handle is already closed, invalid, or stale
     fn close(&self, handle: Handle) -> Result<(), FsError>;
+
+    /// Duplicate an open handle.
+    ///
+    /// Like POSIX `dup`, the new handle refers to the same open file as the
+    /// original rather than opening the path again. FUSE adapters use this to
+    /// represent `dup`/`fork` faithfully, and concurrent readers can share
+    /// one open file without racing a rename of its path.
+    ///
+    /// # Semantics
+    ///
+    /// - Both handles share the open flags of the original.
+    /// - Both handles must be closed independently; closing one leaves the
+    ///   other valid.
+    /// - Locks follow BSD `flock` semantics: a lock acquired through either
+    ///   handle is owned by the shared open file. [`unlock`](crate::FsLock::unlock)
+    ///   through either handle releases it, and closing one duplicate does
+    ///   **not** release it while another duplicate remains open.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends with a handle table should
+    /// override this.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::NotSupported`] if the backend cannot duplicate handles
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsHandles, FsError, Handle};
+    ///
+    /// // Give a worker its own handle to the same open file
+    /// fn share<B: FsHandles>(backend: &B, handle: Handle) -> Result<Handle, FsError> {
+    ///     match backend.dup(handle) {
+    ///         Ok(copy) => Ok(copy),
+    ///         Err(FsError::NotSupported { .. }) => Ok(handle),
+    ///         Err(e) => Err(e),
+    ///     }
+    /// }
+    /// ```
+    fn dup(&self, handle: Handle) -> Result<Handle, FsError> {
+        let _ = handle;
+        Err(FsError::NotSupported { operation: "dup" })
+    }
+
+    /// Atomically append `data` to the file behind `handle`, returning the
+    /// offset it was written at.
+    ///
+    /// Works on any handle opened for writing, with or without `append`,
+    /// and follows the [Append Handles](FsHandles#append-handles) contract:
+    /// all of `data` lands contiguously at the end of the file as it was at
+    /// the moment of the write. Unlike `write_at`, short writes are not
+    /// allowed.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]: finding the end and writing can't
+    /// be made atomic from outside the backend.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::PermissionDenied`] if the handle wasn't opened for writing
+    /// - [`FsError::QuotaExceeded`] if storage quota is exceeded
+    /// - [`FsError::NotSupported`] if the backend can't append atomically
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsHandles, Handle};
+    ///
+    /// // Append a record to a shared log and remember where it starts
+    /// fn log_record<B: FsHandles>(backend: &B, log: Handle, record: &[u8]) -> Result<u64, FsError> {
+    ///     backend.append_handle(log, record)
+    /// }
+    /// ```
+    fn append_handle(&self, handle: Handle, data: &[u8]) -> Result<u64, FsError> {
+        let _ = (handle, data);
+        Err(FsError::NotSupported {
+            operation: "append_handle",
+        })
+    }
+
+    /// Open an unnamed file in `dir` (like Linux `O_TMPFILE`).
+    ///
+    /// The file is invisible to [`read_dir`](crate::FsDir::read_dir) and path
+    /// lookups until [`link_handle`](FsHandles::link_handle) gives it a
+    /// name. If the handle is closed first, the file is discarded. This
+    /// enables crash-safe "write, then link" publishing: readers never observe
+    /// a partially written file.
+    ///
+    /// `create` and `truncate` in `flags` are implied.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends without native unnamed
+    /// files can delegate to [`TempFileEmulation`], which uses a hidden
+    /// temporary name instead.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if `dir` doesn't exist
+    /// - [`FsError::NotADirectory`] if `dir` is not a directory
+    /// - [`FsError::NotSupported`] if the backend has no unnamed files
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsHandles, FsError, OpenFlags};
+    /// use std::path::Path;
+    ///
+    /// // Publish a file atomically: it appears complete or not at all
+    /// fn publish<B: FsHandles>(backend: &B, path: &Path, data: &[u8]) -> Result<(), FsError> {
+    ///     let dir = path.parent().unwrap_or(Path::new("/"));
+    ///     let handle = backend.open_anonymous(dir, OpenFlags::WRITE)?;
+    ///     backend.write_at(handle, data, 0)?;
+    ///     backend.link_handle(handle, path)?;
+    ///     backend.close(handle)
+    /// }
+    /// ```
+    fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+        let _ = (dir, flags);
+        Err(FsError::NotSupported {
+            operation: "open_anonymous",
+        })
+    }
+
+    /// Give an anonymous file a name.
+    ///
+    /// The handle stays open and keeps referring to the now-named file.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, stale,
+    ///   or was not opened with [`open_anonymous`](FsHandles::open_anonymous)
+    /// - [`FsError::AlreadyExists`] if `path` already exists
+    /// - [`FsError::NotSupported`] if the backend has no unnamed files
+    fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+        let _ = (handle, path);
+        Err(FsError::NotSupported {
+            operation: "link_handle",
+        })
+    }
+
+    /// Create a named pipe (like POSIX `mkfifo`).
+    ///
+    /// The new entry reports [`FileType::Fifo`](crate::FileType::Fifo) and
+    /// is used through handles as described under [FIFOs](FsHandles#fifos).
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::AlreadyExists`] if `path` already exists
+    /// - [`FsError::NotFound`] if the parent directory doesn't exist
+    /// - [`FsError::NotSupported`] if the backend has no FIFOs
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsHandles, OpenFlags, Permissions};
+    /// use std::path::Path;
+    ///
+    /// // Hand a stream to another process through the filesystem
+    /// fn pipe<B: FsHandles>(backend: &B, path: &Path, data: &[u8]) -> Result<(), FsError> {
+    ///     backend.mkfifo(path, Permissions::from_mode(0o600))?;
+    ///     let handle = backend.open(path, OpenFlags::WRITE)?;
+    ///     backend.write_at(handle, data, 0)?;
+    ///     backend.close(handle)
+    /// }
+    /// ```
+    fn mkfifo(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
+        let _ = (path, perm);
+        Err(FsError::NotSupported {
+            operation: "mkfifo",
+        })
+    }
+}
+
+/// Process-wide counter making emulated temporary names unique.
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Temp-file emulation of [`open_anonymous`](FsHandles::open_anonymous) and
+/// [`link_handle`](FsHandles::link_handle).
+///
+/// Backends embed this table and delegate the two methods to it, plus call
+/// [`on_close`](Self::on_close) from [`close`](FsHandles::close). The
+/// "anonymous" file is created under a hidden `.anyfs-anon-*` name in the
+/// target directory and renamed into place when linked, so publishing is as
+/// atomic as the backend's [`rename`](FsWrite::rename).
+///
+/// Unlike native unnamed files, the temporary name is visible to directory
+/// listings and survives a crash.
+///
+/// The backend's handles must stay valid across a rename of their file.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsHandles, FsRead, FsWrite, TempFileEmulation, FsError, Handle, Metadata, OpenFlags};
+/// use std::path::Path;
+///
+/// struct MyFs {
+///     anonymous: TempFileEmulation,
+///     // ... handle table, storage ...
+/// }
+///
+/// # impl FsRead for MyFs {
+/// #     fn read(&self, _: &Path) -> Result<Vec<u8>, FsError> { Ok(Vec::new()) }
+/// #     fn read_to_string(&self, _: &Path) -> Result<String, FsError> { Ok(String::new()) }
+/// #     fn read_range(&self, _: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> { Ok(Vec::new()) }
+/// #     fn exists(&self, _: &Path) -> Result<bool, FsError> { Ok(false) }
+/// #     fn metadata(&self, _: &Path) -> Result<Metadata, FsError> { Ok(Metadata::default()) }
+/// #     fn open_read(&self, _: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+/// #         Ok(Box::new(std::io::empty()))
+/// #     }
+/// # }
+/// # impl FsWrite for MyFs {
+/// #     fn write(&self, _: &Path, _: &[u8]) -> Result<(), FsError> { Ok(()) }
+/// #     fn append(&self, _: &Path, _: &[u8]) -> Result<(), FsError> { Ok(()) }
+/// #     fn remove_file(&self, _: &Path) -> Result<(), FsError> { Ok(()) }
+/// #     fn rename(&self, _: &Path, _: &Path) -> Result<(), FsError> { Ok(()) }
+/// #     fn copy(&self, _: &Path, _: &Path) -> Result<(), FsError> { Ok(()) }
+/// #     fn truncate(&self, _: &Path, _: u64) -> Result<(), FsError> { Ok(()) }
+/// #     fn open_write(&self, _: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+/// #         Ok(Box::new(std::io::sink()))
+/// #     }
+/// # }
+/// impl FsHandles for MyFs {
+///     fn open(&self, _path: &Path, _flags: OpenFlags) -> Result<Handle, FsError> {
+///         Ok(Handle(1))
+///     }
+///     fn read_at(&self, _: Handle, _: &mut [u8], _: u64) -> Result<usize, FsError> {
+///         Ok(0)
+///     }
+///     fn write_at(&self, _: Handle, data: &[u8], _: u64) -> Result<usize, FsError> {
+///         Ok(data.len())
+///     }
+///     fn close(&self, handle: Handle) -> Result<(), FsError> {
+///         self.anonymous.on_close(self, handle)
+///     }
+///     fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+///         self.anonymous.open_anonymous(self, dir, flags)
+///     }
+///     fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+///         self.anonymous.link_handle(self, handle, path)
+///     }
+/// }
+///
+/// let fs = MyFs { anonymous: TempFileEmulation::new() };
+/// let handle = fs.open_anonymous(Path::new("/data"), OpenFlags::WRITE).unwrap();
+/// assert!(fs.anonymous.is_anonymous(handle));
+/// fs.link_handle(handle, Path::new("/data/report.txt")).unwrap();
+/// assert!(!fs.anonymous.is_anonymous(handle));
+/// ```
+#[derive(Debug, Default)]
+pub struct TempFileEmulation {
+    temps: Mutex<HashMap<Handle, PathBuf>>,
+}
+
+impl TempFileEmulation {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a handle is an emulated anonymous file that hasn't been linked.
+    pub fn is_anonymous(&self, handle: Handle) -> bool {
+        let temps = self.temps.lock().unwrap_or_else(|e| e.into_inner());
+        temps.contains_key(&handle)
+    }
+
+    /// Emulated [`open_anonymous`](FsHandles::open_anonymous).
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors from [`open`](FsHandles::open).
+    pub fn open_anonymous<B: FsHandles + ?Sized>(
+        &self,
+        backend: &B,
+        dir: &Path,
+        flags: OpenFlags,
+    ) -> Result<Handle, FsError> {
+        let id = NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed);
+        let salt = self as *const Self as usize;
+        let temp = dir.join(format!(".anyfs-anon-{salt:x}-{id}"));
+        let flags = OpenFlags {
+            create: true,
+            truncate: true,
+            ..flags
+        };
+        let handle = backend.open(&temp, flags)?;
+        let mut temps = self.temps.lock().unwrap_or_else(|e| e.into_inner());
+        temps.insert(handle, temp);
+        Ok(handle)
+    }
+
+    /// Emulated [`link_handle`](FsHandles::link_handle): renames the
+    /// temporary file to `path`.
+    ///
+    /// `path` is checked with [`exists_fresh`](FsRead::exists_fresh) first,
+    /// since [`rename`](FsWrite::rename) would replace it. The check and the
+    /// rename are separate calls, so a file created in between by another
+    /// writer can still be replaced.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle isn't an unlinked anonymous file
+    /// - [`FsError::AlreadyExists`] if `path` already exists
+    /// - Errors from [`exists_fresh`](FsRead::exists_fresh) and
+    ///   [`rename`](FsWrite::rename)
+    pub fn link_handle<B: FsRead + FsWrite + ?Sized>(
+        &self,
+        backend: &B,
+        handle: Handle,
+        path: &Path,
+    ) -> Result<(), FsError> {
+        let mut temps = self.temps.lock().unwrap_or_else(|e| e.into_inner());
+        let temp = temps
+            .get(&handle)
+            .ok_or(FsError::InvalidHandle { handle })?;
+        if backend.exists_fresh(path)? {
+            return Err(FsError::already_exists("link_handle", path));
+        }
+        backend.rename(temp, path)?;
+        temps.remove(&handle);
+        Ok(())
+    }
+
+    /// Discard the temporary file of an unlinked anonymous handle.
+    ///
+    /// Call from [`close`](FsHandles::close) after (or before) releasing the
+    /// handle itself. Does nothing for ordinary or already-linked handles.
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors from [`remove_file`](FsWrite::remove_file).
+    pub fn on_close<B: FsWrite + ?Sized>(
+        &self,
+        backend: &B,
+        handle: Handle,
+    ) -> Result<(), FsError> {
+        let temp = {
+            let mut temps = self.temps.lock().unwrap_or_else(|e| e.into_inner());
+            temps.remove(&handle)
+        };
+        match temp {
+            Some(temp) => backend.remove_file(&temp),
+            None => Ok(()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -205,9 +607,10 @@ mod tests {
                 }
             } else {
                 // Must exist
-                files.get(path).cloned().ok_or_else(|| FsError::NotFound {
-                    path: path.to_path_buf(),
-                })?
+                files
+                    .get(path)
+                    .cloned()
+                    .ok_or_else(|| FsError::not_found("open", path))?
             };
 
             drop(files);
@@ -353,7 +756,7 @@ mod tests {
             write: true,
             create: false,
             truncate: false,
-            append: false,
+            ..OpenFlags::default()
         };
         let handle = fs.open(Path::new("/test.txt"), flags).unwrap();
         let mut buf = [0u8; 5];
@@ -397,6 +800,20 @@ mod tests {
         assert!(matches!(result, Err(FsError::InvalidHandle { .. })));
     }
 
+    #[test]
+    fn dup_defaults_to_not_supported() {
+        let fs = MockHandleFs::new();
+        fs.create_file(Path::new("/test.txt"), b"hello".to_vec());
+
+        let handle = fs.open(Path::new("/test.txt"), OpenFlags::READ).unwrap();
+        let result = fs.dup(handle);
+        assert!(matches!(
+            result,
+            Err(FsError::NotSupported { operation: "dup" })
+        ));
+        fs.close(handle).unwrap();
+    }
+
     #[test]
     fn close_then_use_fails() {
         let fs = MockHandleFs::new();
@@ -410,4 +827,198 @@ mod tests {
         let result = fs.read_at(handle, &mut buf, 0);
         assert!(matches!(result, Err(FsError::InvalidHandle { .. })));
     }
+
+    /// Mock whose handles point at shared file bodies, so rename keeps them valid
+    #[derive(Default)]
+    struct AnonFs {
+        next_handle: AtomicU64,
+        files: RwLock<HashMap<PathBuf, std::sync::Arc<RwLock<Vec<u8>>>>>,
+        handles: RwLock<HashMap<Handle, std::sync::Arc<RwLock<Vec<u8>>>>>,
+        anonymous: TempFileEmulation,
+    }
+
+    impl FsHandles for AnonFs {
+        fn open(&self, path: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+            let mut files = self.files.write().unwrap();
+            let body = match files.get(path) {
+                Some(body) => body.clone(),
+                None if flags.create => {
+                    let body = std::sync::Arc::new(RwLock::new(Vec::new()));
+                    files.insert(path.to_path_buf(), body.clone());
+                    body
+                }
+                None => return Err(FsError::not_found("open", path)),
+            };
+            let handle = Handle(self.next_handle.fetch_add(1, Ordering::SeqCst) + 1);
+            self.handles.write().unwrap().insert(handle, body);
+            Ok(handle)
+        }
+
+        fn read_at(&self, _: Handle, _: &mut [u8], _: u64) -> Result<usize, FsError> {
+            Ok(0)
+        }
+
+        fn write_at(&self, handle: Handle, data: &[u8], _: u64) -> Result<usize, FsError> {
+            let handles = self.handles.read().unwrap();
+            let body = handles
+                .get(&handle)
+                .ok_or(FsError::InvalidHandle { handle })?;
+            body.write().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn close(&self, handle: Handle) -> Result<(), FsError> {
+            self.handles
+                .write()
+                .unwrap()
+                .remove(&handle)
+                .ok_or(FsError::InvalidHandle { handle })?;
+            self.anonymous.on_close(self, handle)
+        }
+
+        fn open_anonymous(&self, dir: &Path, flags: OpenFlags) -> Result<Handle, FsError> {
+            self.anonymous.open_anonymous(self, dir, flags)
+        }
+
+        fn link_handle(&self, handle: Handle, path: &Path) -> Result<(), FsError> {
+            self.anonymous.link_handle(self, handle, path)
+        }
+    }
+
+    impl FsRead for AnonFs {
+        fn read(&self, _: &Path) -> Result<Vec<u8>, FsError> {
+            Ok(Vec::new())
+        }
+        fn read_to_string(&self, _: &Path) -> Result<String, FsError> {
+            Ok(String::new())
+        }
+        fn read_range(&self, _: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> {
+            Ok(Vec::new())
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            Ok(self.files.read().unwrap().contains_key(path))
+        }
+        fn metadata(&self, _: &Path) -> Result<crate::Metadata, FsError> {
+            Ok(crate::Metadata::default())
+        }
+        fn open_read(&self, _: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+            Ok(Box::new(std::io::empty()))
+        }
+    }
+
+    impl FsWrite for AnonFs {
+        fn write(&self, _: &Path, _: &[u8]) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn append(&self, _: &Path, _: &[u8]) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            self.files
+                .write()
+                .unwrap()
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| FsError::not_found("remove_file", path))
+        }
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            let mut files = self.files.write().unwrap();
+            let body = files
+                .remove(from)
+                .ok_or_else(|| FsError::not_found("rename", from))?;
+            files.insert(to.to_path_buf(), body);
+            Ok(())
+        }
+        fn copy(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn truncate(&self, _: &Path, _: u64) -> Result<(), FsError> {
+            Ok(())
+        }
+        fn open_write(&self, _: &Path) -> Result<Box<dyn std::io::Write + Send>, FsError> {
+            Ok(Box::new(std::io::sink()))
+        }
+    }
+
+    #[test]
+    fn anonymous_defaults_to_not_supported() {
+        let fs = MockHandleFs::new();
+        let result = fs.open_anonymous(Path::new("/"), OpenFlags::WRITE);
+        assert!(matches!(
+            result,
+            Err(FsError::NotSupported {
+                operation: "open_anonymous"
+            })
+        ));
+        let result = fs.link_handle(Handle(1), Path::new("/f"));
+        assert!(matches!(
+            result,
+            Err(FsError::NotSupported {
+                operation: "link_handle"
+            })
+        ));
+    }
+
+    #[test]
+    fn emulated_anonymous_file_is_linked_into_place() {
+        let fs = AnonFs::default();
+        let handle = fs
+            .open_anonymous(Path::new("/dir"), OpenFlags::WRITE)
+            .unwrap();
+        fs.write_at(handle, b"payload", 0).unwrap();
+        assert!(!fs.files.read().unwrap().contains_key(Path::new("/dir/out")));
+
+        fs.link_handle(handle, Path::new("/dir/out")).unwrap();
+        fs.write_at(handle, b"!", 7).unwrap();
+        fs.close(handle).unwrap();
+
+        let files = fs.files.read().unwrap();
+        assert_eq!(files.len(), 1);
+        let body = files.get(Path::new("/dir/out")).unwrap();
+        assert_eq!(&*body.read().unwrap(), b"payload!");
+    }
+
+    #[test]
+    fn emulated_anonymous_file_is_discarded_on_close() {
+        let fs = AnonFs::default();
+        let handle = fs
+            .open_anonymous(Path::new("/dir"), OpenFlags::WRITE)
+            .unwrap();
+        assert_eq!(fs.files.read().unwrap().len(), 1);
+        fs.close(handle).unwrap();
+        assert!(fs.files.read().unwrap().is_empty());
+        assert!(!fs.anonymous.is_anonymous(handle));
+    }
+
+    #[test]
+    fn link_handle_keeps_an_existing_file() {
+        let fs = AnonFs::default();
+        let existing = fs.open(Path::new("/dir/out"), OpenFlags::WRITE).unwrap();
+        fs.write_at(existing, b"old", 0).unwrap();
+        let handle = fs
+            .open_anonymous(Path::new("/dir"), OpenFlags::WRITE)
+            .unwrap();
+        fs.write_at(handle, b"new", 0).unwrap();
+
+        let result = fs.link_handle(handle, Path::new("/dir/out"));
+        assert!(matches!(
+            result,
+            Err(FsError::AlreadyExists {
+                operation: "link_handle",
+                ..
+            })
+        ));
+        assert!(fs.anonymous.is_anonymous(handle));
+        let files = fs.files.read().unwrap();
+        let body = files.get(Path::new("/dir/out")).unwrap();
+        assert_eq!(&*body.read().unwrap(), b"old");
+    }
+
+    #[test]
+    fn link_handle_rejects_named_handles() {
+        let fs = AnonFs::default();
+        let handle = fs.open(Path::new("/named"), OpenFlags::WRITE).unwrap();
+        let result = fs.link_handle(handle, Path::new("/other"));
+        assert!(matches!(result, Err(FsError::InvalidHandle { .. })));
+    }
 }
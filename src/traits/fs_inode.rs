@@ -35,7 +35,7 @@
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
-use crate::{FsError, Metadata};
+use crate::{DirEntry, FsDir, FsError, FsFuse, Metadata, SetAttr};
 
 /// Inode-based filesystem operations for FUSE mounting.
 ///
@@ -48,6 +48,9 @@ use crate::{FsError, Metadata};
 /// The root inode is conventionally `1` (see [`crate::ROOT_INODE`]).
 /// Implementations should ensure that path "/" maps to inode 1.
 ///
+/// Backends without native inodes can embed an
+/// [`InodeTable`](crate::InodeTable) to keep the two directions in sync.
+///
 /// # Example
 ///
 /// ```rust
@@ -165,6 +168,189 @@ pub trait FsInode: Send + Sync {
     ///
     /// - [`FsError::NotFound`] if the inode does not exist
     fn metadata_by_inode(&self, inode: u64) -> Result<Metadata, FsError>;
+
+    /// List a directory incrementally, resuming from a cookie.
+    ///
+    /// This is FUSE `readdir`: the kernel lists large directories in several
+    /// calls, passing back the cookie of the last entry it kept.
+    ///
+    /// # Cookies
+    ///
+    /// - `offset` 0 starts at the first entry. Each returned entry is paired
+    ///   with the cookie that resumes *after* it; cookies are never 0.
+    /// - Each call may return any number of entries. An empty batch means
+    ///   the end of the directory.
+    /// - `.` and `..` are not included.
+    /// - An entry neither added nor removed during the listing is returned
+    ///   exactly once. Entries added or removed meanwhile may or may not be.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends with a stable listing
+    /// order can delegate to [`read_dir_inode_by_index`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the inode does not exist
+    /// - [`FsError::NotADirectory`] if the inode is not a directory
+    /// - [`FsError::NotSupported`] if the backend can't resume listings
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{DirEntry, FsError, FsInode};
+    ///
+    /// // Collect a directory one batch at a time
+    /// fn list_all<B: FsInode>(backend: &B, dir: u64) -> Result<Vec<DirEntry>, FsError> {
+    ///     let mut all = Vec::new();
+    ///     let mut offset = 0;
+    ///     loop {
+    ///         let batch = backend.read_dir_inode(dir, offset)?;
+    ///         let Some((_, last)) = batch.last() else {
+    ///             return Ok(all);
+    ///         };
+    ///         offset = *last;
+    ///         all.extend(batch.into_iter().map(|(entry, _)| entry));
+    ///     }
+    /// }
+    /// ```
+    fn read_dir_inode(&self, inode: u64, offset: u64) -> Result<Vec<(DirEntry, u64)>, FsError> {
+        let _ = (inode, offset);
+        Err(FsError::NotSupported {
+            operation: "read_dir_inode",
+        })
+    }
+
+    /// Change several attributes of an inode at once (FUSE `setattr`).
+    ///
+    /// The changes are atomic: either every field set in `attr` is applied
+    /// or none is. A field the backend can't store (e.g. `uid` on a backend
+    /// without owners) fails the whole call before anything changes.
+    ///
+    /// # Returns
+    ///
+    /// The metadata after the change, which FUSE replies with.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends without atomic updates
+    /// can delegate to [`set_attr_by_path`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the inode does not exist
+    /// - [`FsError::NotAFile`] if `size` is set on a non-file
+    /// - [`FsError::PermissionDenied`] if access is denied
+    /// - [`FsError::NotSupported`] if a set field can't be stored
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsInode, Metadata, Permissions, SetAttr};
+    ///
+    /// // chmod 600 and truncate in one call
+    /// fn reset<B: FsInode>(backend: &B, inode: u64) -> Result<Metadata, FsError> {
+    ///     let attr = SetAttr {
+    ///         mode: Some(Permissions::from_mode(0o600)),
+    ///         size: Some(0),
+    ///         ..SetAttr::default()
+    ///     };
+    ///     backend.set_attr(inode, attr)
+    /// }
+    /// ```
+    fn set_attr(&self, inode: u64, attr: SetAttr) -> Result<Metadata, FsError> {
+        let _ = (inode, attr);
+        Err(FsError::NotSupported {
+            operation: "set_attr",
+        })
+    }
+}
+
+/// [`FsInode::set_attr`] on top of path-based operations.
+///
+/// Applies `size` with [`truncate`](crate::FsWrite::truncate), then `mode`
+/// with [`set_permissions`](crate::FsPermissions::set_permissions). Fails
+/// with [`FsError::NotSupported`] before changing anything if `uid`, `gid`,
+/// `atime` or `mtime` is set, since [`FsFuse`] has no way to store them.
+///
+/// Not atomic: if the second step fails, the first has already happened.
+///
+/// # Errors
+///
+/// - [`FsError::NotSupported`] if an unsupported field is set
+/// - Errors from the backend's `inode_to_path`, `truncate`,
+///   `set_permissions` and `metadata_by_inode`
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{set_attr_by_path, FsError, FsFuse, Metadata, SetAttr};
+///
+/// fn emptied<B: FsFuse>(backend: &B, inode: u64) -> Result<Metadata, FsError> {
+///     let attr = SetAttr {
+///         size: Some(0),
+///         ..SetAttr::default()
+///     };
+///     set_attr_by_path(backend, inode, attr)
+/// }
+/// ```
+pub fn set_attr_by_path<B: FsFuse + ?Sized>(
+    backend: &B,
+    inode: u64,
+    attr: SetAttr,
+) -> Result<Metadata, FsError> {
+    if attr.uid.is_some() || attr.gid.is_some() || attr.atime.is_some() || attr.mtime.is_some() {
+        return Err(FsError::NotSupported {
+            operation: "set_attr",
+        });
+    }
+    let path = backend.inode_to_path(inode)?;
+    if let Some(size) = attr.size {
+        backend.truncate(&path, size)?;
+    }
+    if let Some(mode) = attr.mode {
+        backend.set_permissions(&path, mode)?;
+    }
+    backend.metadata_by_inode(inode)
+}
+
+/// [`FsInode::read_dir_inode`] on top of [`FsDir::read_dir`], using
+/// positions in the listing as cookies.
+///
+/// Returns every entry after `offset` in one batch. The cookies are only
+/// meaningful if `read_dir` lists a directory in the same order every time,
+/// e.g. sorted by name. Removing an entry during a listing shifts the ones
+/// after it, so one of them may be skipped; backends that can do better
+/// should implement native cookies.
+///
+/// # Errors
+///
+/// - Errors from the backend's `inode_to_path` and `read_dir`
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{read_dir_inode_by_index, FsDir, FsError, FsInode, ROOT_INODE};
+///
+/// fn root_names<B: FsDir + FsInode>(backend: &B) -> Result<Vec<String>, FsError> {
+///     let batch = read_dir_inode_by_index(backend, ROOT_INODE, 0)?;
+///     Ok(batch.into_iter().map(|(entry, _)| entry.name).collect())
+/// }
+/// ```
+pub fn read_dir_inode_by_index<B: FsDir + FsInode + ?Sized>(
+    backend: &B,
+    inode: u64,
+    offset: u64,
+) -> Result<Vec<(DirEntry, u64)>, FsError> {
+    let path = backend.inode_to_path(inode)?;
+    let mut batch = Vec::new();
+    for (cookie, entry) in (1..).zip(backend.read_dir(&path)?) {
+        let entry = entry?;
+        if cookie > offset {
+            batch.push((entry, cookie));
+        }
+    }
+    Ok(batch)
 }
 
 #[cfg(test)]
@@ -230,9 +416,7 @@ mod tests {
                 .unwrap()
                 .get(path)
                 .copied()
-                .ok_or_else(|| FsError::NotFound {
-                    path: path.to_path_buf(),
-                })
+                .ok_or_else(|| FsError::not_found("path_to_inode", path))
         }
 
         fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
@@ -255,7 +439,7 @@ mod tests {
                 }
                 Some((_, file_type)) if *file_type != FileType::Directory => {
                     let (path, _) = inodes.get(&parent_inode).unwrap();
-                    return Err(FsError::NotADirectory { path: path.clone() });
+                    return Err(FsError::not_a_directory("lookup", path.clone()));
                 }
                 _ => {}
             }
@@ -275,9 +459,7 @@ mod tests {
                         .get(&parent_inode)
                         .map(|(p, _)| p.clone())
                         .unwrap_or_else(|| PathBuf::from("/"));
-                    FsError::NotFound {
-                        path: parent_path.join(&name_str),
-                    }
+                    FsError::not_found("lookup", parent_path.join(&name_str))
                 })
         }
 
@@ -295,6 +477,7 @@ mod tests {
                     accessed: std::time::SystemTime::UNIX_EPOCH,
                     inode,
                     nlink: 1,
+                    ..Default::default()
                 })
                 .ok_or(FsError::InodeNotFound { inode })
         }
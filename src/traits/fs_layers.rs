@@ -0,0 +1,72 @@
+//! Introspection of overlay (union) stacks.
+
+use std::path::Path;
+
+use crate::{FsError, LayerId, LayerInfo};
+
+/// Introspection of a backend built from stacked layers.
+///
+/// This is an optional trait, not part of any composite trait. Overlay
+/// backends merge a writable upper layer with read-only lower ones; this
+/// trait lets tools show where an entry comes from and promote entries
+/// ahead of time, the same way on every overlay implementation.
+///
+/// Not to be confused with [`Layer`](crate::Layer), which wraps a backend in
+/// middleware.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsLayers`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, FsLayers};
+/// use std::path::Path;
+///
+/// // `ls`-style annotation: which layer each path comes from
+/// fn origins<B: FsLayers>(backend: &B, paths: &[&Path]) -> Result<Vec<String>, FsError> {
+///     let layers = backend.layers();
+///     paths
+///         .iter()
+///         .map(|path| {
+///             let id = backend.which_layer(path)?;
+///             let name = layers.iter().find(|l| l.id == id).map_or("?", |l| &l.name);
+///             Ok(format!("{} [{}]", path.display(), name))
+///         })
+///         .collect()
+/// }
+/// ```
+pub trait FsLayers: Send + Sync {
+    /// The layers of the stack, topmost first.
+    fn layers(&self) -> Vec<LayerInfo>;
+
+    /// The topmost layer holding `path`.
+    ///
+    /// For a directory merged from several layers, that is the highest one
+    /// that has it.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist in the merged
+    ///   view (including entries hidden by a whiteout)
+    fn which_layer(&self, path: &Path) -> Result<LayerId, FsError>;
+
+    /// Copy `path` from a lower layer into the writable one.
+    ///
+    /// Contents, permissions and timestamps are copied; missing parent
+    /// directories are copied up first. Does nothing if the entry already
+    /// lives in the writable layer. Overlays do this implicitly on the first
+    /// write; calling it up front avoids the delay on a hot path.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::ReadOnly`] if the stack has no writable layer
+    fn copy_up(&self, path: &Path) -> Result<(), FsError>;
+}
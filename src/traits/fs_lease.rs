@@ -0,0 +1,188 @@
+//! Caching leases (SMB oplocks, NFS delegations) on open handles.
+
+use crate::{FsError, Handle, LeaseType};
+
+/// Callback invoked when the backend breaks a lease.
+///
+/// Registered with [`FsLease::on_lease_break`]. It may run on any thread,
+/// including inside another caller's operation, so it should only record the
+/// break and return; flushing and [`release_lease`](FsLease::release_lease)
+/// happen elsewhere.
+pub type LeaseBreakHandler = Box<dyn Fn(LeaseBreak) + Send + Sync>;
+
+/// A lease the backend wants back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeaseBreak {
+    /// The handle holding the lease.
+    pub handle: Handle,
+    /// The lease the holder may keep, or `None` if it must give it up
+    /// entirely. A write lease broken to [`LeaseType::Read`] keeps its
+    /// read cache but must write back dirty data.
+    pub to: Option<LeaseType>,
+}
+
+/// Caching leases for coherent client-side caches over shared backends.
+///
+/// This is an optional trait, not part of any composite trait. Unlike
+/// [`LeaseHint`](crate::LeaseHint), which is a fixed time-to-live, a lease
+/// stays valid until the backend breaks it because another handle needs
+/// conflicting access.
+///
+/// # Breaking Protocol
+///
+/// 1. A handle holds a lease from [`acquire_lease`](FsLease::acquire_lease).
+/// 2. Another handle needs access the lease doesn't allow (see
+///    [`LeaseType`]). The backend calls the registered
+///    [`LeaseBreakHandler`] with a [`LeaseBreak`].
+/// 3. The holder writes back dirty data, drops the cache the lease no longer
+///    covers, and calls [`release_lease`](FsLease::release_lease), or
+///    `acquire_lease` again with the lease it was broken to.
+///
+/// Backends decide how long the conflicting access waits for the holder,
+/// and may revoke the lease themselves once that time runs out. Closing a
+/// handle releases its lease.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsLease`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, FsHandles, FsLease, LeaseType, OpenFlags};
+/// use std::path::Path;
+///
+/// // Open a file, caching its contents only if the backend agrees
+/// fn open_cached<B: FsHandles + FsLease>(backend: &B, path: &Path) -> Result<bool, FsError> {
+///     let handle = backend.open(path, OpenFlags::READ)?;
+///     match backend.acquire_lease(handle, LeaseType::Read) {
+///         Ok(granted) => Ok(granted),
+///         Err(FsError::NotSupported { .. }) => Ok(false),
+///         Err(e) => Err(e),
+///     }
+/// }
+/// ```
+pub trait FsLease: Send + Sync {
+    /// Request a lease on an open handle.
+    ///
+    /// Never waits. If other handles hold conflicting leases, the backend
+    /// starts breaking them and returns `false`; the caller may retry once
+    /// they are released. Requesting a lease on a handle that already holds
+    /// one upgrades or downgrades it.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the lease was granted.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::PermissionDenied`] if a [`LeaseType::Write`] lease is
+    ///   requested on a handle not opened for writing
+    /// - [`FsError::NotSupported`] if the backend has no leases of this type
+    fn acquire_lease(&self, handle: Handle, lease: LeaseType) -> Result<bool, FsError>;
+
+    /// Give up the lease held by a handle. Succeeds if it holds none.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    fn release_lease(&self, handle: Handle) -> Result<(), FsError>;
+
+    /// Register the callback told about lease breaks, replacing any earlier
+    /// one.
+    ///
+    /// Leases are held per backend, so there is one handler per backend.
+    /// Without a handler, breaks can only be noticed by a later
+    /// `acquire_lease` returning `false`.
+    fn on_lease_break(&self, handler: LeaseBreakHandler);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Mock implementation of FsLease for testing: every handle is on the
+    /// same file.
+    #[derive(Default)]
+    struct MockLeaseFs {
+        leases: Mutex<HashMap<Handle, LeaseType>>,
+        handler: Mutex<Option<LeaseBreakHandler>>,
+    }
+
+    impl FsLease for MockLeaseFs {
+        fn acquire_lease(&self, handle: Handle, lease: LeaseType) -> Result<bool, FsError> {
+            let mut leases = self.leases.lock().unwrap();
+            let conflicts: Vec<LeaseBreak> = leases
+                .iter()
+                .filter(|(other, held)| {
+                    **other != handle && (lease == LeaseType::Write || **held == LeaseType::Write)
+                })
+                .map(|(other, _)| LeaseBreak {
+                    handle: *other,
+                    to: (lease == LeaseType::Read).then_some(LeaseType::Read),
+                })
+                .collect();
+            if conflicts.is_empty() {
+                leases.insert(handle, lease);
+                return Ok(true);
+            }
+            drop(leases);
+            if let Some(handler) = self.handler.lock().unwrap().as_ref() {
+                conflicts.into_iter().for_each(handler);
+            }
+            Ok(false)
+        }
+
+        fn release_lease(&self, handle: Handle) -> Result<(), FsError> {
+            self.leases.lock().unwrap().remove(&handle);
+            Ok(())
+        }
+
+        fn on_lease_break(&self, handler: LeaseBreakHandler) {
+            *self.handler.lock().unwrap() = Some(handler);
+        }
+    }
+
+    #[test]
+    fn read_leases_are_shared() {
+        let fs = MockLeaseFs::default();
+        assert!(fs.acquire_lease(Handle(1), LeaseType::Read).unwrap());
+        assert!(fs.acquire_lease(Handle(2), LeaseType::Read).unwrap());
+    }
+
+    #[test]
+    fn conflicting_request_breaks_lease() {
+        let fs = MockLeaseFs::default();
+        let breaks = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&breaks);
+        fs.on_lease_break(Box::new(move |b| seen.lock().unwrap().push(b)));
+
+        assert!(fs.acquire_lease(Handle(1), LeaseType::Write).unwrap());
+        assert!(!fs.acquire_lease(Handle(2), LeaseType::Read).unwrap());
+        assert_eq!(
+            *breaks.lock().unwrap(),
+            [LeaseBreak {
+                handle: Handle(1),
+                to: Some(LeaseType::Read),
+            }]
+        );
+
+        fs.release_lease(Handle(1)).unwrap();
+        assert!(fs.acquire_lease(Handle(2), LeaseType::Read).unwrap());
+    }
+
+    #[test]
+    fn fs_lease_is_object_safe() {
+        let fs: Box<dyn FsLease> = Box::new(MockLeaseFs::default());
+        assert!(fs.acquire_lease(Handle(1), LeaseType::Write).unwrap());
+    }
+}
@@ -43,7 +43,7 @@
 //! Like all AnyFS traits, `FsLock` requires `Send + Sync`. Implementations
 //! must handle concurrent lock requests appropriately.
 
-use crate::{FsError, Handle, LockType};
+use crate::{FsError, Handle, LockOwner, LockRange, LockType};
 
 /// File locking operations for POSIX compatibility.
 ///
@@ -56,6 +56,29 @@ use crate::{FsError, Handle, LockType};
 /// - [`LockType::Shared`] - Multiple readers, blocks exclusive locks
 /// - [`LockType::Exclusive`] - Single writer, blocks all other locks
 ///
+/// # Duplicated Handles
+///
+/// Locks belong to the open file, not to an individual handle. Handles
+/// created with [`FsHandles::dup`](crate::FsHandles::dup) share the locks of
+/// the handle they were duplicated from; see its documentation for details.
+///
+/// # Lock Owners
+///
+/// [`lock`](FsLock::lock) and friends key whole-file locks by handle. POSIX
+/// record locks (`fcntl`) are keyed by owner instead: two handles opened by
+/// the same process share their locks, and a lock manager spanning several
+/// handles needs to tell owners apart. The `*_owned` methods take a
+/// [`LockOwner`] and a [`LockRange`] for this:
+///
+/// - Locks of the same owner never conflict; a new lock replaces the
+///   owner's lock on the overlapping bytes, splitting or merging ranges.
+/// - Closing any handle of an owner on a file releases all of that owner's
+///   locks on the file, as `close` does in POSIX.
+///
+/// By default they fall back to the handle-keyed methods for
+/// [`LockRange::WHOLE_FILE`] and return [`FsError::NotSupported`] for other
+/// ranges. Backends with real record locks should override all three.
+///
 /// # Example
 ///
 /// ```rust
@@ -116,6 +139,99 @@ pub trait FsLock: Send + Sync {
     ///
     /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
     fn unlock(&self, handle: Handle) -> Result<(), FsError>;
+
+    /// Acquire a record lock for `owner`, waiting until it can be granted.
+    ///
+    /// See [Lock Owners](FsLock#lock-owners) for the semantics.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
+    /// - [`FsError::NotSupported`] if the backend has no record locks
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsLock, Handle, LockOwner, LockRange, LockType};
+    ///
+    /// // Forward a FUSE setlkw request
+    /// fn setlkw<B: FsLock>(
+    ///     backend: &B,
+    ///     handle: Handle,
+    ///     lock_owner: u64,
+    ///     start: u64,
+    ///     len: u64,
+    /// ) -> Result<(), FsError> {
+    ///     let owner = LockOwner(lock_owner);
+    ///     backend.lock_owned(handle, owner, LockType::Exclusive, LockRange::new(start, len))
+    /// }
+    /// ```
+    fn lock_owned(
+        &self,
+        handle: Handle,
+        owner: LockOwner,
+        lock: LockType,
+        range: LockRange,
+    ) -> Result<(), FsError> {
+        let _ = owner;
+        if range != LockRange::WHOLE_FILE {
+            return Err(FsError::NotSupported {
+                operation: "lock_owned",
+            });
+        }
+        self.lock(handle, lock)
+    }
+
+    /// Try to acquire a record lock for `owner` without waiting.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the lock was acquired, `false` if another owner holds a
+    /// conflicting lock.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
+    /// - [`FsError::NotSupported`] if the backend has no record locks
+    fn try_lock_owned(
+        &self,
+        handle: Handle,
+        owner: LockOwner,
+        lock: LockType,
+        range: LockRange,
+    ) -> Result<bool, FsError> {
+        let _ = owner;
+        if range != LockRange::WHOLE_FILE {
+            return Err(FsError::NotSupported {
+                operation: "try_lock_owned",
+            });
+        }
+        self.try_lock(handle, lock)
+    }
+
+    /// Release the record locks `owner` holds on `range`.
+    ///
+    /// Unlocking part of a locked range splits it. Succeeds if the owner
+    /// holds no lock there.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid or closed
+    /// - [`FsError::NotSupported`] if the backend has no record locks
+    fn unlock_owned(
+        &self,
+        handle: Handle,
+        owner: LockOwner,
+        range: LockRange,
+    ) -> Result<(), FsError> {
+        let _ = owner;
+        if range != LockRange::WHOLE_FILE {
+            return Err(FsError::NotSupported {
+                operation: "unlock_owned",
+            });
+        }
+        self.unlock(handle)
+    }
 }
 
 #[cfg(test)]
@@ -302,4 +418,39 @@ mod tests {
         let result = fs.unlock(Handle(999));
         assert!(matches!(result, Err(FsError::InvalidHandle { .. })));
     }
+
+    #[test]
+    fn owned_whole_file_falls_back_to_handle_locks() {
+        let fs = MockLockFs::new();
+        fs.add_handle(1);
+        let owner = LockOwner(7);
+
+        fs.lock_owned(Handle(1), owner, LockType::Exclusive, LockRange::WHOLE_FILE)
+            .unwrap();
+        assert!(!fs.try_lock(Handle(1), LockType::Shared).unwrap());
+        fs.unlock_owned(Handle(1), owner, LockRange::WHOLE_FILE)
+            .unwrap();
+        assert!(fs
+            .try_lock_owned(Handle(1), owner, LockType::Shared, LockRange::WHOLE_FILE)
+            .unwrap());
+    }
+
+    #[test]
+    fn owned_byte_range_not_supported_by_default() {
+        let fs = MockLockFs::new();
+        fs.add_handle(1);
+
+        let result = fs.lock_owned(
+            Handle(1),
+            LockOwner(7),
+            LockType::Shared,
+            LockRange::new(0, 10),
+        );
+        assert!(matches!(
+            result,
+            Err(FsError::NotSupported {
+                operation: "lock_owned"
+            })
+        ));
+    }
 }
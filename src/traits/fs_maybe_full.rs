@@ -0,0 +1,240 @@
+//! Runtime discovery of Layer 2 features.
+//!
+//! This module provides the [`FsMaybeFull`] trait. [`FsFull`](crate::FsFull)
+//! is checked at compile time, so a backend missing any one of its
+//! components is either excluded from generic tools or forced to stub it.
+//! `FsMaybeFull` asks at runtime instead: each extension is an accessor
+//! returning `Some` when the backend has it, so a tool can copy permissions
+//! when it can and skip them when it can't.
+
+use crate::{Fs, FsLink, FsPermissions, FsStats, FsSync};
+
+/// [`Fs`] with optionally available [`FsFull`](crate::FsFull) components.
+///
+/// This is an optional trait, not part of any composite trait. Every
+/// accessor defaults to `None`; override the ones the backend supports to
+/// return `Some(self)`. A backend that implements [`FsFull`](crate::FsFull)
+/// should return `Some` from all four.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsMaybeFull`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, FsMaybeFull};
+/// use std::path::Path;
+///
+/// // Copy a file, carrying its mode over when both sides support it
+/// fn copy_across(src: &dyn FsMaybeFull, dst: &dyn FsMaybeFull, path: &Path) -> Result<(), FsError> {
+///     dst.write(path, &src.read(path)?)?;
+///     if let Some(perms) = dst.as_permissions() {
+///         perms.set_permissions(path, src.metadata(path)?.permissions)?;
+///     }
+///     if let Some(sync) = dst.as_sync() {
+///         sync.fsync(path)?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait FsMaybeFull: Fs {
+    /// Symlinks and hard links, if supported.
+    fn as_link(&self) -> Option<&dyn FsLink> {
+        None
+    }
+
+    /// Permission changes, if supported.
+    fn as_permissions(&self) -> Option<&dyn FsPermissions> {
+        None
+    }
+
+    /// Durability control, if supported.
+    fn as_sync(&self) -> Option<&dyn FsSync> {
+        None
+    }
+
+    /// Filesystem statistics, if supported.
+    fn as_stats(&self) -> Option<&dyn FsStats> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FsBuilder, FsDir, FsError, FsRead, FsWrite, Metadata, Permissions, ReadDirIter, StatFs,
+    };
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    /// Mock backend with every optional extension, advertised at runtime
+    struct FullFs;
+
+    impl FsRead for FullFs {
+        fn read(&self, _: &Path) -> Result<Vec<u8>, FsError> {
+            Ok(vec![])
+        }
+
+        fn read_to_string(&self, _: &Path) -> Result<String, FsError> {
+            Ok(String::new())
+        }
+
+        fn read_range(&self, _: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> {
+            Ok(vec![])
+        }
+
+        fn exists(&self, _: &Path) -> Result<bool, FsError> {
+            Ok(true)
+        }
+
+        fn metadata(&self, _: &Path) -> Result<Metadata, FsError> {
+            Ok(Metadata::default())
+        }
+
+        fn open_read(&self, _: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+            Ok(Box::new(std::io::empty()))
+        }
+    }
+
+    impl FsWrite for FullFs {
+        fn write(&self, _: &Path, _: &[u8]) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn append(&self, _: &Path, _: &[u8]) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn truncate(&self, _: &Path, _: u64) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn remove_file(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn rename(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn copy(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn open_write(&self, _: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+            Ok(Box::new(std::io::sink()))
+        }
+    }
+
+    impl FsDir for FullFs {
+        fn read_dir(&self, _: &Path) -> Result<ReadDirIter, FsError> {
+            Ok(ReadDirIter::from_vec(vec![]))
+        }
+
+        fn create_dir(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn create_dir_all(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn remove_dir(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn remove_dir_all(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    impl FsLink for FullFs {
+        fn symlink(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn hard_link(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn read_link(&self, _: &Path) -> Result<PathBuf, FsError> {
+            Ok(PathBuf::from("/target"))
+        }
+
+        fn symlink_metadata(&self, _: &Path) -> Result<Metadata, FsError> {
+            Ok(Metadata::default())
+        }
+    }
+
+    impl FsPermissions for FullFs {
+        fn set_permissions(&self, _: &Path, _: Permissions) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    impl FsSync for FullFs {
+        fn sync(&self) -> Result<(), FsError> {
+            Ok(())
+        }
+
+        fn fsync(&self, _: &Path) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    impl FsStats for FullFs {
+        fn statfs(&self) -> Result<StatFs, FsError> {
+            Ok(StatFs::default().with_block_size(4096))
+        }
+    }
+
+    impl FsMaybeFull for FullFs {
+        fn as_link(&self) -> Option<&dyn FsLink> {
+            Some(self)
+        }
+
+        fn as_permissions(&self) -> Option<&dyn FsPermissions> {
+            Some(self)
+        }
+
+        fn as_sync(&self) -> Option<&dyn FsSync> {
+            Some(self)
+        }
+
+        fn as_stats(&self) -> Option<&dyn FsStats> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn accessors_default_to_none() {
+        let fs: Box<dyn FsMaybeFull> = Box::new(FsBuilder::new().build());
+        assert!(fs.as_link().is_none());
+        assert!(fs.as_permissions().is_none());
+        assert!(fs.as_sync().is_none());
+        assert!(fs.as_stats().is_none());
+    }
+
+    #[test]
+    fn overridden_accessors_reach_the_backend() {
+        let fs: Box<dyn FsMaybeFull> = Box::new(FullFs);
+        let path = Path::new("/file");
+
+        let link = fs.as_link().expect("link support");
+        assert_eq!(link.read_link(path).unwrap(), Path::new("/target"));
+        fs.as_permissions()
+            .expect("permission support")
+            .set_permissions(path, Permissions::from_mode(0o644))
+            .unwrap();
+        fs.as_sync().expect("sync support").fsync(path).unwrap();
+        let stats = fs.as_stats().expect("stats support").statfs().unwrap();
+        assert_eq!(stats.block_size, 4096);
+    }
+}
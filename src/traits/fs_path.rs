@@ -27,16 +27,10 @@
 //! }
 //! ```
 
-use std::path::{Component, Path, PathBuf};
+use std::path::{Path, PathBuf};
 
-use crate::{FileType, FsError, FsLink, FsRead};
-
-// ============================================================================
-// Constants
-// ============================================================================
-
-/// Maximum depth for symlink resolution to prevent infinite loops.
-const MAX_SYMLINK_DEPTH: usize = 40;
+use crate::path_resolver::{resolve, soft_resolve, LinkLookup};
+use crate::{FsDir, FsError, FsLink, FsRead, PolicyResolver, ResolvePolicy};
 
 // ============================================================================
 // Trait Definition
@@ -109,7 +103,7 @@ pub trait FsPath: FsRead + FsLink {
     /// }
     /// ```
     fn canonicalize(&self, path: &Path) -> Result<PathBuf, FsError> {
-        default_canonicalize(self, path)
+        resolve(&LinkLookup(self), &ResolvePolicy::default(), path)
     }
 
     /// Like [`canonicalize`](Self::canonicalize), but allows non-existent final component.
@@ -146,177 +140,109 @@ pub trait FsPath: FsRead + FsLink {
     /// }
     /// ```
     fn soft_canonicalize(&self, path: &Path) -> Result<PathBuf, FsError> {
-        default_soft_canonicalize(self, path)
+        soft_resolve(&LinkLookup(self), &ResolvePolicy::default(), path)
+    }
+
+    /// Canonicalize `path` under the policy of `resolver`.
+    ///
+    /// Lets layers that confine, case-fold or limit symlinks share one
+    /// resolution engine with the backend instead of reimplementing it.
+    ///
+    /// # Default Implementation
+    ///
+    /// [`PolicyResolver::canonicalize_in`] on `self`. Backends that override
+    /// [`canonicalize`](Self::canonicalize) for speed are not consulted.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] - A component doesn't exist
+    /// - [`FsError::InvalidData`] - Symlink nesting exceeded the policy limit
+    /// - [`FsError::AccessDenied`] - `..` escaped the root under
+    ///   [`EscapePolicy::Deny`](crate::EscapePolicy::Deny)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{CaseSensitivity, FsDir, FsError, FsPath, PolicyResolver, ResolvePolicy};
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// // Resolve a path typed by a Windows user
+    /// fn resolve_user_path<B: FsPath + FsDir>(backend: &B, path: &Path) -> Result<PathBuf, FsError> {
+    ///     let resolver = PolicyResolver::new(ResolvePolicy::default().with_case(CaseSensitivity::Insensitive));
+    ///     backend.canonicalize_with(path, &resolver)
+    /// }
+    /// ```
+    fn canonicalize_with(&self, path: &Path, resolver: &PolicyResolver) -> Result<PathBuf, FsError>
+    where
+        Self: FsDir + Sized,
+    {
+        resolver.canonicalize_in(self, path)
     }
 }
 
 // Blanket implementation - any FsRead + FsLink gets FsPath for free
 impl<T: FsRead + FsLink> FsPath for T {}
 
-// ============================================================================
-// Default Implementations
-// ============================================================================
-
-/// Default implementation of canonicalize using iterative resolution.
+/// Whether `candidate` resolves to `base` or somewhere beneath it.
 ///
-/// Walks the path component by component, following symlinks and
-/// resolving `.` and `..` components.
-fn default_canonicalize<F: FsRead + FsLink + ?Sized>(
-    fs: &F,
-    path: &Path,
-) -> Result<PathBuf, FsError> {
-    resolve_path_internal(fs, path, 0, true)
-}
-
-/// Default implementation of soft_canonicalize.
+/// Both paths are resolved through symlinks before comparing, so
+/// `/sandbox/link` pointing at `/etc` is outside `/sandbox` even though its
+/// spelling is inside. `base` must exist. `candidate` may not: its parent is
+/// resolved and the final component appended, as in
+/// [`FsPath::soft_canonicalize`]. A final component that is a dangling
+/// symlink is followed too, since writing through it creates its target.
+/// Containment is decided per component, so `/sandbox2` is not within
+/// `/sandbox`.
 ///
-/// Like canonicalize, but allows the final component to not exist.
-fn default_soft_canonicalize<F: FsRead + FsLink + ?Sized>(
-    fs: &F,
-    path: &Path,
-) -> Result<PathBuf, FsError> {
-    // Get the parent and final component
-    let parent = path.parent();
-    let file_name = path.file_name();
-
-    match (parent, file_name) {
-        (Some(parent_path), Some(name)) if !parent_path.as_os_str().is_empty() => {
-            // Resolve the parent path fully
-            let resolved_parent = resolve_path_internal(fs, parent_path, 0, true)?;
-            // Append the final component lexically
-            Ok(resolved_parent.join(name))
-        }
-        (None, Some(_)) | (Some(_), Some(_)) => {
-            // Just a filename or root + filename, return as-is normalized
-            normalize_path(path)
-        }
-        (_, None) => {
-            // No filename component (e.g., "/" or empty) - just canonicalize
-            default_canonicalize(fs, path)
-        }
-    }
-}
-
-/// Internal path resolution with symlink depth tracking.
-fn resolve_path_internal<F: FsRead + FsLink + ?Sized>(
-    fs: &F,
-    path: &Path,
-    depth: usize,
-    require_exists: bool,
-) -> Result<PathBuf, FsError> {
-    if depth > MAX_SYMLINK_DEPTH {
-        return Err(FsError::InvalidData {
-            path: path.to_path_buf(),
-            details: format!("symlink loop detected (exceeded max depth of {MAX_SYMLINK_DEPTH})"),
-        });
-    }
-
-    let mut resolved = PathBuf::new();
-
-    for component in path.components() {
-        match component {
-            Component::RootDir => {
-                resolved = PathBuf::from("/");
-            }
-            Component::CurDir => {
-                // `.` - skip, don't change resolved path
-            }
-            Component::ParentDir => {
-                // `..` - go up one level
-                resolved.pop();
-                // Ensure we don't go above root
-                if resolved.as_os_str().is_empty() {
-                    resolved = PathBuf::from("/");
-                }
-            }
-            Component::Normal(name) => {
-                resolved.push(name);
-
-                // Check if this component is a symlink
-                match fs.symlink_metadata(&resolved) {
-                    Ok(meta) => {
-                        if meta.file_type == FileType::Symlink {
-                            // Read the symlink target
-                            let target = fs.read_link(&resolved)?;
-
-                            // Remove the symlink from resolved path
-                            resolved.pop();
-
-                            // Resolve the target path
-                            let target_resolved = if target.is_absolute() {
-                                resolve_path_internal(fs, &target, depth + 1, require_exists)?
-                            } else {
-                                // Relative symlink - resolve relative to current resolved path
-                                let full_target = resolved.join(&target);
-                                resolve_path_internal(fs, &full_target, depth + 1, require_exists)?
-                            };
-
-                            resolved = target_resolved;
-                        }
-                        // If not a symlink, keep it in resolved path
-                    }
-                    Err(FsError::NotFound { .. }) if !require_exists => {
-                        // Component doesn't exist but we're in soft mode
-                        // Keep it in the path
-                    }
-                    Err(e) => return Err(e),
-                }
-            }
-            Component::Prefix(_) => {
-                // Windows prefix handling - for cross-platform support
-                // Virtual backends use Unix-style paths internally
-                resolved.push(component);
-            }
-        }
-    }
-
-    // Ensure we have at least root
-    if resolved.as_os_str().is_empty() {
-        resolved = PathBuf::from("/");
-    }
-
-    // Final existence check for canonicalize (not soft)
-    if require_exists && !fs.exists(&resolved)? {
-        return Err(FsError::NotFound { path: resolved });
-    }
-
-    Ok(resolved)
-}
-
-/// Simple lexical path normalization without filesystem access.
+/// The answer describes the tree at the time of the call; a concurrent
+/// writer can swap a directory for a symlink right after it.
 ///
-/// Handles `.`, `..`, and multiple slashes but does NOT follow symlinks.
-fn normalize_path(path: &Path) -> Result<PathBuf, FsError> {
-    let mut normalized = PathBuf::new();
-
-    for component in path.components() {
-        match component {
-            Component::RootDir => {
-                normalized = PathBuf::from("/");
-            }
-            Component::CurDir => {
-                // Skip `.`
-            }
-            Component::ParentDir => {
-                normalized.pop();
-                if normalized.as_os_str().is_empty() {
-                    normalized = PathBuf::from("/");
-                }
-            }
-            Component::Normal(name) => {
-                normalized.push(name);
-            }
-            Component::Prefix(prefix) => {
-                normalized.push(prefix.as_os_str());
-            }
-        }
-    }
-
-    if normalized.as_os_str().is_empty() {
-        normalized = PathBuf::from("/");
+/// # Errors
+///
+/// - [`FsError::NotFound`] - `base` or a parent of `candidate` doesn't exist
+/// - [`FsError::InvalidData`] - Symlink loop detected
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{is_path_within, FsError, FsPath};
+/// use std::path::Path;
+///
+/// fn check_upload<B: FsPath>(backend: &B, name: &Path) -> Result<(), FsError> {
+///     let target = Path::new("/uploads").join(name);
+///     if !is_path_within(backend, Path::new("/uploads"), &target)? {
+///         return Err(FsError::AccessDenied {
+///             path: target,
+///             reason: "outside /uploads".to_string(),
+///         });
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn is_path_within<B: FsPath>(fs: &B, base: &Path, candidate: &Path) -> Result<bool, FsError> {
+    let base = fs.canonicalize(base)?;
+    let mut candidate = match fs.canonicalize(candidate) {
+        Err(FsError::NotFound { .. }) => fs.soft_canonicalize(candidate)?,
+        resolved => resolved?,
+    };
+    let max_depth = ResolvePolicy::default().max_symlink_depth;
+    let mut depth = 0;
+    while matches!(fs.symlink_metadata(&candidate), Ok(meta) if meta.is_symlink()) {
+        depth += 1;
+        if depth > max_depth {
+            return Err(FsError::InvalidData {
+                path: candidate,
+                details: format!("symlink loop detected (exceeded max depth of {max_depth})"),
+            });
+        }
+        let target = fs.read_link(&candidate)?;
+        let target = match candidate.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+        candidate = fs.soft_canonicalize(&target)?;
     }
-
-    Ok(normalized)
+    Ok(candidate.starts_with(&base))
 }
 
 // ============================================================================
@@ -326,7 +252,10 @@ fn normalize_path(path: &Path) -> Result<PathBuf, FsError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{FsDir, FsWrite, Metadata, Permissions, ReadDirIter};
+    use crate::path_resolver::normalize;
+    use crate::{
+        CaseSensitivity, EscapePolicy, FileType, FsWrite, Metadata, Permissions, ReadDirIter,
+    };
     use std::collections::HashMap;
     use std::io::{Read, Write};
     use std::sync::RwLock;
@@ -416,11 +345,10 @@ mod tests {
                         accessed: SystemTime::UNIX_EPOCH,
                         inode: 1,
                         nlink: 1,
+                        ..Default::default()
                     })
                 }
-                None => Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                }),
+                None => Err(FsError::not_found("metadata", path)),
             }
         }
 
@@ -460,8 +388,20 @@ mod tests {
     }
 
     impl FsDir for MockFs {
-        fn read_dir(&self, _path: &Path) -> Result<ReadDirIter, FsError> {
-            Ok(ReadDirIter::from_vec(vec![]))
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            let entries = self.entries.read().unwrap();
+            let children = entries
+                .keys()
+                .filter(|child| child.parent() == Some(path))
+                .map(|child| {
+                    Ok(crate::DirEntry {
+                        name: child.file_name().unwrap().to_string_lossy().into_owned(),
+                        path: child.clone(),
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            Ok(ReadDirIter::from_vec(children))
         }
 
         fn create_dir(&self, _path: &Path) -> Result<(), FsError> {
@@ -498,9 +438,7 @@ mod tests {
                     path: path.to_path_buf(),
                     details: "not a symlink".to_string(),
                 }),
-                None => Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                }),
+                None => Err(FsError::not_found("read_link", path)),
             }
         }
 
@@ -522,11 +460,10 @@ mod tests {
                         accessed: SystemTime::UNIX_EPOCH,
                         inode: 1,
                         nlink: 1,
+                        ..Default::default()
                     })
                 }
-                None => Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                }),
+                None => Err(FsError::not_found("symlink_metadata", path)),
             }
         }
     }
@@ -658,15 +595,90 @@ mod tests {
         assert_eq!(result.unwrap(), PathBuf::from("/"));
     }
 
+    #[test]
+    fn canonicalize_with_confines_to_root() {
+        let fs = MockFs::new();
+        fs.add_dir("/jail");
+        fs.add_dir("/jail/etc");
+        fs.add_dir("/etc");
+        fs.add_symlink("/jail/link", "/etc");
+
+        let resolver = PolicyResolver::new(ResolvePolicy::default().with_root("/jail"));
+        let result = fs.canonicalize_with(Path::new("/link"), &resolver);
+        assert_eq!(result.unwrap(), PathBuf::from("/jail/etc"));
+        let result = fs.canonicalize_with(Path::new("/etc/../../.."), &resolver);
+        assert_eq!(result.unwrap(), PathBuf::from("/jail"));
+
+        let deny = PolicyResolver::new(
+            ResolvePolicy::default()
+                .with_root("/jail")
+                .with_escape(EscapePolicy::Deny),
+        );
+        let result = fs.canonicalize_with(Path::new("/etc/../../etc"), &deny);
+        assert!(matches!(result, Err(FsError::AccessDenied { .. })));
+    }
+
+    #[test]
+    fn canonicalize_with_folds_case() {
+        let fs = MockFs::new();
+        fs.add_dir("/Docs");
+        fs.add_file("/Docs/Report.TXT");
+
+        let resolver =
+            PolicyResolver::new(ResolvePolicy::default().with_case(CaseSensitivity::Insensitive));
+        let result = fs.canonicalize_with(Path::new("/docs/report.txt"), &resolver);
+        assert_eq!(result.unwrap(), PathBuf::from("/Docs/Report.TXT"));
+        assert!(fs.canonicalize(Path::new("/docs/report.txt")).is_err());
+    }
+
+    #[test]
+    fn canonicalize_with_limits_symlink_depth() {
+        let fs = MockFs::new();
+        fs.add_dir("/target");
+        fs.add_symlink("/link", "/target");
+
+        let resolver = PolicyResolver::new(ResolvePolicy::default().with_max_symlink_depth(0));
+        let result = fs.canonicalize_with(Path::new("/link"), &resolver);
+        assert!(matches!(result, Err(FsError::InvalidData { .. })));
+    }
+
+    #[test]
+    fn is_path_within_follows_symlinks() {
+        let fs = MockFs::new();
+        fs.add_dir("/sandbox");
+        fs.add_dir("/sandbox/sub");
+        fs.add_dir("/etc");
+        fs.add_symlink("/sandbox/escape", "/etc");
+        fs.add_symlink("/sandbox/dangling", "../etc/new");
+        fs.add_symlink("/alias", "/sandbox");
+
+        let within = |p: &str| is_path_within(&fs, Path::new("/sandbox"), Path::new(p)).unwrap();
+        assert!(within("/sandbox"));
+        assert!(within("/sandbox/sub/new.txt"));
+        assert!(within("/alias/sub"));
+        assert!(!within("/sandbox/escape"));
+        assert!(!within("/sandbox/escape/passwd"));
+        assert!(!within("/sandbox/dangling"));
+        assert!(!within("/sandbox/sub/../../etc"));
+        assert!(!within("/sandbox2"));
+    }
+
+    #[test]
+    fn is_path_within_requires_existing_base() {
+        let fs = MockFs::new();
+        let result = is_path_within(&fs, Path::new("/missing"), Path::new("/missing/a"));
+        assert!(matches!(result, Err(FsError::NotFound { .. })));
+    }
+
     #[test]
     fn normalize_path_handles_dots() {
-        let result = normalize_path(Path::new("/a/./b/../c"));
-        assert_eq!(result.unwrap(), PathBuf::from("/a/c"));
+        let result = normalize(Path::new("/a/./b/../c"));
+        assert_eq!(result, PathBuf::from("/a/c"));
     }
 
     #[test]
     fn normalize_path_handles_root() {
-        let result = normalize_path(Path::new("/"));
-        assert_eq!(result.unwrap(), PathBuf::from("/"));
+        let result = normalize(Path::new("/"));
+        assert_eq!(result, PathBuf::from("/"));
     }
 }
@@ -3,7 +3,7 @@
 use std::io::Read;
 use std::path::Path;
 
-use crate::{FsError, Metadata};
+use crate::{FsError, Metadata, MetadataLite, MetadataMask};
 
 /// Read operations for a virtual filesystem.
 ///
@@ -56,8 +56,44 @@ pub trait FsRead: Send + Sync {
     ///
     /// Returns `Ok(true)` if the path exists, `Ok(false)` if it does not.
     /// Only returns an error for unexpected failures (e.g., I/O errors).
+    ///
+    /// The answer may come from a cache. A cached `false` can be stale for
+    /// as long as the stack's
+    /// [`NotFoundCacheHint`](crate::NotFoundCacheHint) allows, e.g. when
+    /// another client created the path. Decisions that must not act on a
+    /// stale `false`, such as create-if-missing, use
+    /// [`exists_fresh`](Self::exists_fresh).
     fn exists(&self, path: &Path) -> Result<bool, FsError>;
 
+    /// Check if a path exists, bypassing every cache.
+    ///
+    /// The answer comes from the backend of record. Caches must override
+    /// this to skip (and may refresh) their cached entry; other middleware
+    /// forwards it unchanged.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`exists`](Self::exists), which is fresh on a backend that
+    /// caches nothing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{Fs, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn create_if_missing<B: Fs>(backend: &B, path: &Path, data: &[u8]) -> Result<bool, FsError> {
+    ///     if backend.exists_fresh(path)? {
+    ///         return Ok(false);
+    ///     }
+    ///     backend.write(path, data)?;
+    ///     Ok(true)
+    /// }
+    /// ```
+    fn exists_fresh(&self, path: &Path) -> Result<bool, FsError> {
+        self.exists(path)
+    }
+
     /// Get metadata for a path (follows symlinks).
     ///
     /// # Errors
@@ -65,6 +101,99 @@ pub trait FsRead: Send + Sync {
     /// - [`FsError::NotFound`] if the path does not exist
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError>;
 
+    /// Get type, size and inode for a path (follows symlinks).
+    ///
+    /// A fast path for hot loops that don't need timestamps or permissions.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`metadata`](Self::metadata) and drops the extra fields.
+    /// Backends that build timestamps lazily should override this.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    fn metadata_lite(&self, path: &Path) -> Result<MetadataLite, FsError> {
+        self.metadata(path).map(MetadataLite::from)
+    }
+
+    /// Get metadata for a path, filling at least the fields selected by `mask`
+    /// (follows symlinks).
+    ///
+    /// Fields outside the mask hold unspecified values (typically their
+    /// [`Default`] values) and must not be relied upon. `file_type` is always
+    /// filled.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`metadata`](Self::metadata), filling every field. Remote
+    /// backends should override this to skip lookups for unselected fields.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsRead, FsError, MetadataMask};
+    /// use std::path::Path;
+    ///
+    /// fn total_size<B: FsRead>(backend: &B, paths: &[&Path]) -> Result<u64, FsError> {
+    ///     let mask = MetadataMask { size: true, ..MetadataMask::NONE };
+    ///     let mut total = 0;
+    ///     for path in paths {
+    ///         total += backend.metadata_mask(path, mask)?.size;
+    ///     }
+    ///     Ok(total)
+    /// }
+    /// ```
+    fn metadata_mask(&self, path: &Path, mask: MetadataMask) -> Result<Metadata, FsError> {
+        let _ = mask;
+        self.metadata(path)
+    }
+
+    /// Get metadata with [`content_tag`](Metadata::content_tag) filled when
+    /// the backend can provide one (follows symlinks).
+    ///
+    /// # Contract
+    ///
+    /// - A backend returns `Some` tag only if it changes whenever the file's
+    ///   content changes. Equal tags (same algorithm) imply equal content.
+    /// - `None` means "no tag available", never "content unchanged".
+    /// - Object stores surface their ETag here instead of through xattrs.
+    ///   Backends that would have to hash the whole file may return `None`.
+    /// - Directories and symlinks usually have no tag.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`metadata`](Self::metadata), returning whatever tag it carries
+    /// (usually `None`).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{ContentTag, FsRead, FsError};
+    /// use std::path::Path;
+    ///
+    /// // Skip re-downloading when the remote content is unchanged
+    /// fn is_stale<B: FsRead>(
+    ///     backend: &B,
+    ///     path: &Path,
+    ///     cached: &ContentTag,
+    /// ) -> Result<bool, FsError> {
+    ///     let meta = backend.metadata_with_tag(path)?;
+    ///     Ok(meta.content_tag.as_ref() != Some(cached))
+    /// }
+    /// ```
+    fn metadata_with_tag(&self, path: &Path) -> Result<Metadata, FsError> {
+        self.metadata(path)
+    }
+
     /// Open a file for reading, returning a boxed reader.
     ///
     /// This is a "cold path" operation that returns a trait object for flexibility.
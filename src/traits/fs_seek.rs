@@ -0,0 +1,504 @@
+//! Cursor-based handle I/O for classic file-descriptor semantics.
+//!
+//! This module provides the [`FsSeek`] trait and the [`HandleCursors`]
+//! helper. Together they add an implicit per-handle position on top of the
+//! positional [`read_at`](crate::FsHandles::read_at) /
+//! [`write_at`](crate::FsHandles::write_at) API.
+//!
+//! # Overview
+//!
+//! Ported applications often expect `read`/`write`/`seek` with an implicit
+//! position. Backends embed a [`HandleCursors`] table and return it from
+//! [`FsSeek::cursors`]; every other method is provided:
+//!
+//! 1. `seek_handle()` - Move the cursor
+//! 2. `read_handle()` / `write_handle()` - I/O at the cursor, advancing it
+//!
+//! # Example
+//!
+//! ```rust
+//! use anyfs_backend::{FsSeek, OpenFlags, FsError};
+//! use std::io::SeekFrom;
+//! use std::path::Path;
+//!
+//! // Generic function that works with any FsSeek implementation
+//! fn read_trailer<B: FsSeek>(backend: &B, path: &Path) -> Result<Vec<u8>, FsError> {
+//!     let handle = backend.open(path, OpenFlags::READ)?;
+//!     backend.seek_handle(handle, SeekFrom::End(-8))?;
+//!     let mut trailer = vec![0u8; 8];
+//!     let n = backend.read_handle(handle, &mut trailer)?;
+//!     trailer.truncate(n);
+//!     backend.close(handle)?;
+//!     Ok(trailer)
+//! }
+//! ```
+//!
+//! # Thread Safety
+//!
+//! [`HandleCursors`] uses interior mutability, so cursor updates are safe from
+//! multiple threads. Concurrent cursor I/O on the *same* handle is
+//! serialized per call but not across a seek + read pair.
+
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::sync::Mutex;
+
+use crate::{FsError, FsHandles, Handle};
+
+/// Per-handle cursor table used by [`FsSeek`] default methods.
+///
+/// Positions start at 0 for handles the table has never seen. Backends call
+/// [`forget`](Self::forget) from [`close`](FsHandles::close) so closed
+/// handles don't accumulate.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Handle, HandleCursors};
+///
+/// let cursors = HandleCursors::new();
+/// let handle = Handle(1);
+///
+/// assert_eq!(cursors.position(handle), 0);
+/// cursors.set_position(handle, 10);
+/// cursors.advance(handle, 5);
+/// assert_eq!(cursors.position(handle), 15);
+///
+/// cursors.forget(handle);
+/// assert_eq!(cursors.position(handle), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct HandleCursors {
+    positions: Mutex<HashMap<Handle, u64>>,
+}
+
+impl HandleCursors {
+    /// Create an empty cursor table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current position of a handle (0 if never moved).
+    pub fn position(&self, handle: Handle) -> u64 {
+        let positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        positions.get(&handle).copied().unwrap_or(0)
+    }
+
+    /// Set the position of a handle.
+    pub fn set_position(&self, handle: Handle, position: u64) {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        positions.insert(handle, position);
+    }
+
+    /// Advance the position of a handle by `n` bytes, returning the new position.
+    pub fn advance(&self, handle: Handle, n: u64) -> u64 {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        let position = positions.entry(handle).or_insert(0);
+        *position = position.saturating_add(n);
+        *position
+    }
+
+    /// Drop the cursor of a closed handle.
+    pub fn forget(&self, handle: Handle) {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        positions.remove(&handle);
+    }
+}
+
+/// Resolve `pos` against the current `position` of `handle`.
+///
+/// `len` is only called for [`SeekFrom::End`]. Shared by
+/// [`FsSeek::seek_handle`] and the [`HandleReader`](crate::HandleReader) /
+/// [`HandleWriter`](crate::HandleWriter) adapters, which report their own
+/// `operation`.
+pub(crate) fn seek_target(
+    handle: Handle,
+    position: u64,
+    pos: SeekFrom,
+    operation: &'static str,
+    len: impl FnOnce() -> Result<u64, FsError>,
+) -> Result<u64, FsError> {
+    let (base, delta) = match pos {
+        SeekFrom::Start(offset) => return Ok(offset),
+        SeekFrom::Current(delta) => (position, delta),
+        SeekFrom::End(delta) => (len()?, delta),
+    };
+    let target = if delta >= 0 {
+        base.checked_add(delta as u64)
+    } else {
+        base.checked_sub(delta.unsigned_abs())
+    };
+    target.ok_or_else(|| {
+        FsError::io(
+            operation,
+            format!("<handle {}>", handle.id()),
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("seek to invalid position: {base} {delta:+}"),
+            ),
+        )
+    })
+}
+
+/// Cursor-based handle I/O with an implicit position.
+///
+/// This is an optional trait, not part of any composite trait. Implementors
+/// provide [`cursors`](FsSeek::cursors) (and optionally
+/// [`handle_len`](FsSeek::handle_len)); the cursor methods are implemented
+/// over [`read_at`](FsHandles::read_at) and [`write_at`](FsHandles::write_at).
+///
+/// The methods carry a `_handle` suffix so they never clash with
+/// [`FsRead::read`](crate::FsRead::read) and
+/// [`FsWrite::write`](crate::FsWrite::write) on backends implementing both.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsHandles, FsSeek, HandleCursors, FsError, Handle, OpenFlags};
+/// use std::io::SeekFrom;
+/// use std::path::Path;
+///
+/// // A backend holding a single in-memory file
+/// struct OneFile {
+///     data: std::sync::Mutex<Vec<u8>>,
+///     cursors: HandleCursors,
+/// }
+///
+/// impl FsHandles for OneFile {
+///     fn open(&self, _: &Path, _: OpenFlags) -> Result<Handle, FsError> {
+///         Ok(Handle(1))
+///     }
+///     fn read_at(&self, _: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+///         let data = self.data.lock().unwrap();
+///         let start = (offset as usize).min(data.len());
+///         let n = buf.len().min(data.len() - start);
+///         buf[..n].copy_from_slice(&data[start..start + n]);
+///         Ok(n)
+///     }
+///     fn write_at(&self, _: Handle, bytes: &[u8], offset: u64) -> Result<usize, FsError> {
+///         let mut data = self.data.lock().unwrap();
+///         let end = offset as usize + bytes.len();
+///         if data.len() < end {
+///             data.resize(end, 0);
+///         }
+///         data[offset as usize..end].copy_from_slice(bytes);
+///         Ok(bytes.len())
+///     }
+///     fn close(&self, handle: Handle) -> Result<(), FsError> {
+///         self.cursors.forget(handle);
+///         Ok(())
+///     }
+/// }
+///
+/// impl FsSeek for OneFile {
+///     fn cursors(&self) -> &HandleCursors {
+///         &self.cursors
+///     }
+///     fn handle_len(&self, _: Handle) -> Result<u64, FsError> {
+///         Ok(self.data.lock().unwrap().len() as u64)
+///     }
+/// }
+///
+/// let fs = OneFile { data: Default::default(), cursors: HandleCursors::new() };
+/// let h = fs.open(Path::new("/f"), OpenFlags::READ_WRITE).unwrap();
+/// fs.write_handle(h, b"hello").unwrap();
+/// fs.write_handle(h, b" world").unwrap();
+///
+/// fs.seek_handle(h, SeekFrom::Start(6)).unwrap();
+/// let mut buf = [0u8; 5];
+/// fs.read_handle(h, &mut buf).unwrap();
+/// assert_eq!(&buf, b"world");
+/// ```
+pub trait FsSeek: FsHandles {
+    /// The cursor table backing the default methods.
+    fn cursors(&self) -> &HandleCursors;
+
+    /// Current length of the file behind a handle.
+    ///
+    /// Used by [`seek_handle`](Self::seek_handle) for [`SeekFrom::End`].
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`], so only `SeekFrom::Start` and
+    /// `SeekFrom::Current` work until a backend overrides it.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::NotSupported`] if the length is unavailable
+    fn handle_len(&self, handle: Handle) -> Result<u64, FsError> {
+        let _ = handle;
+        Err(FsError::NotSupported {
+            operation: "handle_len",
+        })
+    }
+
+    /// Move the cursor of a handle, returning the new position.
+    ///
+    /// Seeking past the end is allowed; a later write fills the gap with
+    /// zeros as [`write_at`](FsHandles::write_at) does.
+    ///
+    /// The handle is checked with a zero-length [`read_at`](FsHandles::read_at)
+    /// before the cursor moves, so closed and stale handles fail here rather
+    /// than on the next read or write.
+    ///
+    /// On an append handle the cursor only steers reads; see
+    /// [`write_handle`](Self::write_handle).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::Io`] with operation `seek_handle` and kind
+    ///   [`InvalidInput`](std::io::ErrorKind::InvalidInput) if the target
+    ///   position would be negative or overflow; handles carry no path, so
+    ///   the error's path names the handle as `<handle ID>`
+    /// - [`FsError::NotSupported`] for `SeekFrom::End` without
+    ///   [`handle_len`](Self::handle_len)
+    fn seek_handle(&self, handle: Handle, pos: SeekFrom) -> Result<u64, FsError> {
+        // other probe errors (a write-only handle, say) still mean it's open
+        if let Err(err @ FsError::InvalidHandle { .. }) = self.read_at(handle, &mut [], 0) {
+            return Err(err);
+        }
+        let position = self.cursors().position(handle);
+        let target = seek_target(handle, position, pos, "seek_handle", || {
+            self.handle_len(handle)
+        })?;
+        self.cursors().set_position(handle, target);
+        Ok(target)
+    }
+
+    /// Read at the cursor and advance it by the number of bytes read.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_at`](FsHandles::read_at).
+    fn read_handle(&self, handle: Handle, buf: &mut [u8]) -> Result<usize, FsError> {
+        let position = self.cursors().position(handle);
+        let n = self.read_at(handle, buf, position)?;
+        self.cursors().advance(handle, n as u64);
+        Ok(n)
+    }
+
+    /// Write at the cursor and advance it by the number of bytes written.
+    ///
+    /// On an append handle [`write_at`](FsHandles::write_at) ignores the
+    /// cursor and the data lands at the end of the file, but the cursor still
+    /// only advances by the bytes written. Unlike POSIX `O_APPEND` it does not
+    /// jump to the new end; seek with `SeekFrom::End(0)` first if reads
+    /// should continue from there.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`write_at`](FsHandles::write_at).
+    fn write_handle(&self, handle: Handle, data: &[u8]) -> Result<usize, FsError> {
+        let position = self.cursors().position(handle);
+        let n = self.write_at(handle, data, position)?;
+        self.cursors().advance(handle, n as u64);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OpenFlags;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::RwLock;
+
+    /// Mock backend exposing one shared buffer through any handle
+    struct MockSeekFs {
+        data: RwLock<Vec<u8>>,
+        cursors: HandleCursors,
+        closed: AtomicBool,
+    }
+
+    impl MockSeekFs {
+        fn with_data(data: &[u8]) -> Self {
+            Self {
+                data: RwLock::new(data.to_vec()),
+                cursors: HandleCursors::new(),
+                closed: AtomicBool::new(false),
+            }
+        }
+
+        fn check(&self, handle: Handle) -> Result<(), FsError> {
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(FsError::InvalidHandle { handle });
+            }
+            Ok(())
+        }
+    }
+
+    impl FsHandles for MockSeekFs {
+        fn open(&self, _path: &Path, _flags: OpenFlags) -> Result<Handle, FsError> {
+            Ok(Handle(1))
+        }
+
+        fn read_at(&self, handle: Handle, buf: &mut [u8], offset: u64) -> Result<usize, FsError> {
+            self.check(handle)?;
+            let data = self.data.read().unwrap();
+            let start = (offset as usize).min(data.len());
+            let n = buf.len().min(data.len() - start);
+            buf[..n].copy_from_slice(&data[start..start + n]);
+            Ok(n)
+        }
+
+        fn write_at(&self, handle: Handle, bytes: &[u8], offset: u64) -> Result<usize, FsError> {
+            self.check(handle)?;
+            let mut data = self.data.write().unwrap();
+            let end = offset as usize + bytes.len();
+            if data.len() < end {
+                data.resize(end, 0);
+            }
+            data[offset as usize..end].copy_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn close(&self, handle: Handle) -> Result<(), FsError> {
+            self.check(handle)?;
+            self.closed.store(true, Ordering::SeqCst);
+            self.cursors.forget(handle);
+            Ok(())
+        }
+    }
+
+    impl FsSeek for MockSeekFs {
+        fn cursors(&self) -> &HandleCursors {
+            &self.cursors
+        }
+
+        fn handle_len(&self, _handle: Handle) -> Result<u64, FsError> {
+            Ok(self.data.read().unwrap().len() as u64)
+        }
+    }
+
+    /// Backend relying on the default `handle_len`
+    struct NoLenFs {
+        cursors: HandleCursors,
+    }
+
+    impl FsHandles for NoLenFs {
+        fn open(&self, _path: &Path, _flags: OpenFlags) -> Result<Handle, FsError> {
+            Ok(Handle(1))
+        }
+
+        fn read_at(&self, _: Handle, _: &mut [u8], _: u64) -> Result<usize, FsError> {
+            Ok(0)
+        }
+
+        fn write_at(&self, _: Handle, data: &[u8], _: u64) -> Result<usize, FsError> {
+            Ok(data.len())
+        }
+
+        fn close(&self, _: Handle) -> Result<(), FsError> {
+            Ok(())
+        }
+    }
+
+    impl FsSeek for NoLenFs {
+        fn cursors(&self) -> &HandleCursors {
+            &self.cursors
+        }
+    }
+
+    #[test]
+    fn sequential_reads_advance_cursor() {
+        let fs = MockSeekFs::with_data(b"hello world");
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+
+        let mut buf = [0u8; 5];
+        assert_eq!(fs.read_handle(h, &mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"hello");
+        assert_eq!(fs.read_handle(h, &mut buf).unwrap(), 5);
+        assert_eq!(&buf, b" worl");
+        assert_eq!(fs.cursors().position(h), 10);
+    }
+
+    #[test]
+    fn sequential_writes_advance_cursor() {
+        let fs = MockSeekFs::with_data(b"");
+        let h = fs.open(Path::new("/f"), OpenFlags::WRITE).unwrap();
+
+        fs.write_handle(h, b"abc").unwrap();
+        fs.write_handle(h, b"def").unwrap();
+        assert_eq!(&*fs.data.read().unwrap(), b"abcdef");
+    }
+
+    #[test]
+    fn seek_start_current_end() {
+        let fs = MockSeekFs::with_data(b"0123456789");
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+
+        assert_eq!(fs.seek_handle(h, SeekFrom::Start(4)).unwrap(), 4);
+        assert_eq!(fs.seek_handle(h, SeekFrom::Current(2)).unwrap(), 6);
+        assert_eq!(fs.seek_handle(h, SeekFrom::Current(-3)).unwrap(), 3);
+        assert_eq!(fs.seek_handle(h, SeekFrom::End(-1)).unwrap(), 9);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read_handle(h, &mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'9');
+    }
+
+    #[test]
+    fn seek_before_start_fails() {
+        let fs = MockSeekFs::with_data(b"abc");
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+        let result = fs.seek_handle(h, SeekFrom::Current(-1));
+        let err = result.unwrap_err();
+        assert_eq!(err.operation(), Some("seek_handle"));
+        assert_eq!(err.path(), Some(Path::new("<handle 1>")));
+        assert_eq!(
+            std::io::Error::from(err).kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(fs.cursors().position(h), 0);
+    }
+
+    #[test]
+    fn seek_end_without_len_is_not_supported() {
+        let fs = NoLenFs {
+            cursors: HandleCursors::new(),
+        };
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+        let result = fs.seek_handle(h, SeekFrom::End(0));
+        assert!(matches!(result, Err(FsError::NotSupported { .. })));
+        assert_eq!(fs.seek_handle(h, SeekFrom::Start(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn close_forgets_cursor() {
+        let fs = MockSeekFs::with_data(b"abc");
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+        fs.seek_handle(h, SeekFrom::Start(2)).unwrap();
+        fs.close(h).unwrap();
+        assert_eq!(fs.cursors().position(h), 0);
+    }
+
+    #[test]
+    fn seek_on_closed_handle_fails() {
+        let fs = MockSeekFs::with_data(b"abc");
+        let h = fs.open(Path::new("/f"), OpenFlags::READ).unwrap();
+        fs.close(h).unwrap();
+        let result = fs.seek_handle(h, SeekFrom::Start(1));
+        assert!(matches!(result, Err(FsError::InvalidHandle { handle }) if handle == h));
+        assert_eq!(fs.cursors().position(h), 0);
+    }
+
+    #[test]
+    fn cursors_are_per_handle() {
+        let cursors = HandleCursors::new();
+        cursors.set_position(Handle(1), 10);
+        cursors.set_position(Handle::new(1, 1), 20);
+        assert_eq!(cursors.position(Handle(1)), 10);
+        assert_eq!(cursors.position(Handle::new(1, 1)), 20);
+    }
+
+    #[test]
+    fn fs_seek_is_object_safe() {
+        let fs = MockSeekFs::with_data(b"xyz");
+        let dyn_fs: &dyn FsSeek = &fs;
+        let mut buf = [0u8; 3];
+        assert_eq!(dyn_fs.read_handle(Handle(1), &mut buf).unwrap(), 3);
+    }
+}
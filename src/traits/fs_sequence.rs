@@ -0,0 +1,105 @@
+//! Monotonic mutation sequence numbers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::FsError;
+
+/// A backend-wide sequence number that grows with every mutation.
+///
+/// This is an optional trait, not part of any composite trait. Replication
+/// layers, journals and change feeds need a total order of events; wall-clock
+/// timestamps give none (clocks step backwards, and two writes can share a
+/// tick). A sequence number does.
+///
+/// # Contract
+///
+/// - Every successful mutation (content, metadata, namespace or xattr
+///   change) raises [`seq`](Self::seq) by at least 1 before the mutating
+///   call returns. Failed calls may or may not raise it.
+/// - It never decreases for the life of the backend. Persistent backends
+///   keep it across restarts.
+/// - Only order is meaningful: gaps are allowed, and numbers from different
+///   backends can't be compared.
+///
+/// Backends that also report [`Metadata::change_counter`](crate::Metadata::change_counter)
+/// can use the sequence number at the entry's last mutation, so "changed
+/// since sequence N" becomes a plain comparison.
+///
+/// [`SequenceCounter`] is a ready-made atomic counter for implementations.
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsSequence`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsError, FsSequence};
+/// use std::path::Path;
+///
+/// // Skip a resync when nothing changed since the last one
+/// fn needs_resync<B: Fs + FsSequence>(backend: &B, last_synced: u64) -> Result<bool, FsError> {
+///     Ok(backend.seq()? > last_synced)
+/// }
+/// ```
+pub trait FsSequence: Send + Sync {
+    /// The sequence number of the latest mutation.
+    ///
+    /// # Errors
+    ///
+    /// - Backend-specific errors, e.g. an unreachable remote
+    fn seq(&self) -> Result<u64, FsError>;
+}
+
+/// Atomic sequence counter for [`FsSequence`] implementations.
+///
+/// Call [`bump`](Self::bump) once per mutation, after it took effect, and
+/// return [`current`](Self::current) from [`FsSequence::seq`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::SequenceCounter;
+///
+/// let counter = SequenceCounter::new();
+/// assert_eq!(counter.current(), 0);
+/// assert_eq!(counter.bump(), 1);
+/// assert_eq!(counter.bump(), 2);
+///
+/// // A persistent backend resumes where it stopped
+/// let restored = SequenceCounter::starting_at(counter.current());
+/// assert_eq!(restored.bump(), 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct SequenceCounter {
+    value: AtomicU64,
+}
+
+impl SequenceCounter {
+    /// A counter at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A counter resuming at `value`.
+    pub fn starting_at(value: u64) -> Self {
+        Self {
+            value: AtomicU64::new(value),
+        }
+    }
+
+    /// The latest sequence number handed out.
+    pub fn current(&self) -> u64 {
+        self.value.load(Ordering::SeqCst)
+    }
+
+    /// Advance by one and return the new sequence number.
+    pub fn bump(&self) -> u64 {
+        self.value.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
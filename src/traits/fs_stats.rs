@@ -1,6 +1,8 @@
 //! Filesystem statistics operations.
 
-use crate::{FsError, StatFs};
+use std::path::Path;
+
+use crate::{FsError, IoStats, StatFs, UsageReport};
 
 /// Filesystem statistics operations.
 ///
@@ -21,4 +23,59 @@ pub trait FsStats: Send + Sync {
     ///
     /// - [`FsError::Backend`] for backend-specific failures
     fn statfs(&self) -> Result<StatFs, FsError>;
+
+    /// Report the space used at or below `path`, split by direct child.
+    ///
+    /// For dashboards and treemap views. Database-backed stores can answer
+    /// it with one aggregate query instead of visiting every entry.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends implementing
+    /// [`Fs`](crate::Fs) without a faster way can override it with
+    /// [`FsExt::usage_by_walk`](crate::FsExt::usage_by_walk).
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotSupported`] if the backend can't report usage
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsStats};
+    /// use std::path::Path;
+    ///
+    /// fn biggest_child<B: FsStats>(backend: &B) -> Result<Option<String>, FsError> {
+    ///     let report = backend.usage(Path::new("/"))?;
+    ///     Ok(report.by_child.into_iter().next().map(|(name, _)| name))
+    /// }
+    /// ```
+    fn usage(&self, path: &Path) -> Result<UsageReport, FsError> {
+        let _ = path;
+        Err(FsError::NotSupported { operation: "usage" })
+    }
+
+    /// Cumulative I/O counters since the backend was created.
+    ///
+    /// Covers the basics monitoring needs without a separate metrics trait.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns all zeros. Wrap any backend in
+    /// [`IoCounted`](crate::IoCounted) to have them maintained for it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::FsStats;
+    ///
+    /// fn error_rate<B: FsStats>(backend: &B) -> f64 {
+    ///     let stats = backend.io_stats();
+    ///     stats.errors as f64 / stats.total_ops().max(1) as f64
+    /// }
+    /// ```
+    fn io_stats(&self) -> IoStats {
+        IoStats::default()
+    }
 }
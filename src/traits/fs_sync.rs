@@ -2,10 +2,35 @@
 
 use std::path::Path;
 
-use crate::FsError;
+use crate::{FsError, Handle};
 
 /// Filesystem synchronization operations.
 ///
+/// # Durability Levels
+///
+/// | Method | Flushes | POSIX analogue |
+/// |--------|---------|----------------|
+/// | [`sync`](FsSync::sync) | Everything | `sync()` / `syncfs()` |
+/// | [`fsync`](FsSync::fsync) | One file's data and metadata | `fsync(fd)` |
+/// | [`fdatasync`](FsSync::fdatasync) | One file's data (+ size) | `fdatasync(fd)` |
+/// | [`sync_handle`](FsSync::sync_handle) | The file behind an open handle | `fsync(fd)` |
+/// | [`barrier`](FsSync::barrier) | Nothing necessarily; orders writes | `F_BARRIERFSYNC` |
+///
+/// # Ordering Guarantees
+///
+/// When a sync method returns `Ok`, every write that *completed* before the
+/// call started is durable to the level listed above. Writes issued
+/// concurrently with the call may or may not be included. No ordering is
+/// promised between writes that were never synced: a crash can persist a
+/// later write and lose an earlier one.
+///
+/// [`barrier`](FsSync::barrier) is the exception: it promises ordering
+/// without promising durability. Writes completed before the barrier become
+/// durable no later than writes issued after it.
+///
+/// `fdatasync` persists the file size along with the data (so appended bytes
+/// are readable after a crash) but may skip timestamps and permissions.
+///
 /// # Thread Safety
 ///
 /// All implementations must be `Send + Sync`. Methods use `&self` to allow
@@ -33,4 +58,135 @@ pub trait FsSync: Send + Sync {
     /// - [`FsError::NotFound`] if the path does not exist
     /// - [`FsError::Io`] for underlying I/O errors
     fn fsync(&self, path: &Path) -> Result<(), FsError>;
+
+    /// Sync a specific file's data to storage, skipping metadata that isn't
+    /// needed to read the data back.
+    ///
+    /// Similar to POSIX `fdatasync(fd)`. Database-grade backends override
+    /// this to avoid flushing timestamps on every commit.
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`fsync`](FsSync::fsync), which is always at least as strong.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::Io`] for underlying I/O errors
+    fn fdatasync(&self, path: &Path) -> Result<(), FsError> {
+        self.fsync(path)
+    }
+
+    /// Sync the file behind an open handle.
+    ///
+    /// Unlike [`fsync`](FsSync::fsync), this reaches the open file even if
+    /// its path was renamed or unlinked since it was opened.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends implementing
+    /// [`FsHandles`](crate::FsHandles) should override this.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidHandle`] if the handle is invalid, closed, or stale
+    /// - [`FsError::NotSupported`] if the backend has no handles
+    /// - [`FsError::Io`] for underlying I/O errors
+    fn sync_handle(&self, handle: Handle) -> Result<(), FsError> {
+        let _ = handle;
+        Err(FsError::NotSupported {
+            operation: "sync_handle",
+        })
+    }
+
+    /// Order writes: everything completed before the barrier becomes durable
+    /// before anything issued after it.
+    ///
+    /// Journaled applications need "record, then commit marker" ordering but
+    /// not necessarily an immediate flush. Backends with write-ahead logs or
+    /// ordered queues can implement this far cheaper than [`sync`](FsSync::sync).
+    ///
+    /// # Default Implementation
+    ///
+    /// Calls [`sync`](FsSync::sync). A full flush trivially satisfies the
+    /// ordering requirement.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::Io`] for underlying I/O errors
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsSync, FsWrite, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn commit<B: FsWrite + FsSync>(backend: &B, record: &[u8]) -> Result<(), FsError> {
+    ///     backend.append(Path::new("/journal"), record)?;
+    ///     // The record must never be lost while the marker survives
+    ///     backend.barrier()?;
+    ///     backend.append(Path::new("/journal"), b"COMMIT\n")
+    /// }
+    /// ```
+    fn barrier(&self) -> Result<(), FsError> {
+        self.sync()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::RwLock;
+
+    /// Mock that counts sync and fsync calls
+    #[derive(Default)]
+    struct MockSyncFs {
+        syncs: std::sync::atomic::AtomicU64,
+        fsyncs: RwLock<HashMap<PathBuf, u32>>,
+    }
+
+    impl FsSync for MockSyncFs {
+        fn sync(&self) -> Result<(), FsError> {
+            self.syncs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn fsync(&self, path: &Path) -> Result<(), FsError> {
+            *self
+                .fsyncs
+                .write()
+                .unwrap()
+                .entry(path.to_path_buf())
+                .or_insert(0) += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fdatasync_defaults_to_fsync() {
+        let fs = MockSyncFs::default();
+        fs.fdatasync(Path::new("/db")).unwrap();
+        assert_eq!(fs.fsyncs.read().unwrap()[Path::new("/db")], 1);
+    }
+
+    #[test]
+    fn sync_handle_defaults_to_not_supported() {
+        let fs = MockSyncFs::default();
+        let result = fs.sync_handle(Handle(1));
+        assert!(matches!(
+            result,
+            Err(FsError::NotSupported {
+                operation: "sync_handle"
+            })
+        ));
+    }
+
+    #[test]
+    fn barrier_defaults_to_sync() {
+        let fs = MockSyncFs::default();
+        fs.barrier().unwrap();
+        assert_eq!(fs.syncs.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,61 @@
+//! Storage tiers (hot/cold) for object-store backends.
+
+use std::path::Path;
+
+use crate::{FsError, StorageClass};
+
+/// Moving files between storage tiers.
+///
+/// This is an optional trait, not part of any composite trait. New files
+/// can be placed in a tier up front with
+/// [`WriteOptions::storage_class`](crate::WriteOptions::storage_class); this
+/// trait changes the tier of existing ones, so lifecycle tooling can archive
+/// old data the same way on every backend. The current tier is also reported
+/// in [`Metadata::storage_class`](crate::Metadata::storage_class).
+///
+/// # Thread Safety
+///
+/// All implementations must be `Send + Sync`. Methods use `&self` to allow
+/// concurrent access.
+///
+/// # Object Safety
+///
+/// This trait is object-safe and can be used as `dyn FsTiering`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, FsTiering, StorageClass};
+/// use std::path::Path;
+///
+/// // Archive last year's logs
+/// fn archive<B: FsTiering>(backend: &B, logs: &[&Path]) -> Result<(), FsError> {
+///     for path in logs {
+///         if backend.storage_class(path)? != StorageClass::Archive {
+///             backend.set_storage_class(path, StorageClass::Archive)?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait FsTiering: Send + Sync {
+    /// Move a file to another storage tier.
+    ///
+    /// The contents are unchanged. The move may complete asynchronously on
+    /// the backend's side.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotAFile`] if the path is a directory
+    /// - [`FsError::NotSupported`] if the backend has no tier for `class`
+    fn set_storage_class(&self, path: &Path, class: StorageClass) -> Result<(), FsError>;
+
+    /// Get the storage tier of a file.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotAFile`] if the path is a directory
+    fn storage_class(&self, path: &Path) -> Result<StorageClass, FsError>;
+}
@@ -2,8 +2,9 @@
 
 use std::io::Write;
 use std::path::Path;
+use std::time::SystemTime;
 
-use crate::FsError;
+use crate::{CopyOptions, FsError, WriteOptions};
 
 /// Write operations for a virtual filesystem.
 ///
@@ -49,7 +50,24 @@ pub trait FsWrite: Send + Sync {
 
     /// Rename/move a file or directory.
     ///
-    /// This operation should be atomic where possible.
+    /// # Atomicity
+    ///
+    /// A rename that replaces an existing `to` must do so atomically:
+    /// concurrent callers see `to` as either the old entry or the new one,
+    /// never missing, and a failed rename leaves both paths as they were.
+    /// Backends that can't promise this should refuse to replace, returning
+    /// [`FsError::AlreadyExists`], so callers can fall back to
+    /// [`FsExt::safe_replace`](crate::FsExt::safe_replace).
+    ///
+    /// # Case-Only Renames
+    ///
+    /// On a backend whose [`NameCollisionPolicy`](crate::NameCollisionPolicy)
+    /// is not `Distinct`, `from` and `to` may name the same entry with
+    /// different case (`File.txt` → `file.txt`). Such a rename must change
+    /// the stored spelling to `to`'s; it must not fail with
+    /// [`FsError::AlreadyExists`] or silently do nothing. Backends that
+    /// can't rename in place should return [`FsError::NotSupported`], and
+    /// callers can use [`FsExt::rename_case`](crate::FsExt::rename_case).
     ///
     /// # Errors
     ///
@@ -86,4 +104,167 @@ pub trait FsWrite: Send + Sync {
     /// - [`FsError::NotFound`] if parent directory does not exist
     /// - [`FsError::NotAFile`] if the path is a directory
     fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError>;
+
+    /// Copy a file with explicit overwrite and preservation semantics,
+    /// returning the number of bytes copied.
+    ///
+    /// | Option | Contract |
+    /// |--------|----------|
+    /// | `overwrite: Fail` | Existing destination is an [`FsError::AlreadyExists`] error |
+    /// | `overwrite: Replace` | Existing destination is replaced, as with [`copy`](Self::copy) |
+    /// | `overwrite: Skip` | Existing destination is kept; returns `Ok(0)` |
+    /// | `preserve_permissions` | Copy gets the source's mode, else the backend's default for new files |
+    /// | `preserve_times` | Copy gets the source's modified/accessed times |
+    /// | `allow_reflink` | Backend may share storage copy-on-write instead of copying bytes |
+    ///
+    /// A backend that can't honor a requested `preserve_*` option must fail
+    /// with [`FsError::NotSupported`] rather than silently ignore it.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. The portable implementation,
+    /// [`FsExt::copy_with_fallback`](crate::FsExt::copy_with_fallback),
+    /// needs the source's metadata and `set_permissions`, which `FsWrite`
+    /// alone can't reach, so backends opt in with a one-line override:
+    /// `fn copy_with(..) { self.copy_with_fallback(from, to, options) }`.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the source file does not exist
+    /// - [`FsError::NotAFile`] if the source is a directory
+    /// - [`FsError::AlreadyExists`] if the destination exists and `overwrite` is `Fail`
+    /// - [`FsError::NotSupported`] if a requested option can't be honored
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{CopyOptions, FsWrite, FsError, OverwritePolicy};
+    /// use std::path::Path;
+    ///
+    /// fn backup<B: FsWrite>(backend: &B, from: &Path, to: &Path) -> Result<u64, FsError> {
+    ///     let options = CopyOptions {
+    ///         overwrite: OverwritePolicy::Fail,
+    ///         preserve_permissions: true,
+    ///         ..CopyOptions::default()
+    ///     };
+    ///     backend.copy_with(from, to, options)
+    /// }
+    /// ```
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        let _ = (from, to, options);
+        Err(FsError::NotSupported {
+            operation: "copy_with",
+        })
+    }
+
+    /// Write data to a file, with hints on how to store it.
+    ///
+    /// Behaves like [`write`](Self::write); `options` only affect how the
+    /// data is stored, never what [`read`](crate::FsRead::read) returns.
+    ///
+    /// # Default Implementation
+    ///
+    /// Ignores `options` and calls [`write`](Self::write). Backends that
+    /// compress or have storage tiers override it to honor
+    /// [`WriteOptions::compression`] and [`WriteOptions::storage_class`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`write`](Self::write).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{CompressionHint, FsError, FsWrite, WriteOptions};
+    /// use std::path::Path;
+    ///
+    /// fn save_photo<B: FsWrite>(backend: &B, jpeg: &[u8]) -> Result<(), FsError> {
+    ///     let options = WriteOptions {
+    ///         compression: Some(CompressionHint::None),
+    ///         ..WriteOptions::default()
+    ///     };
+    ///     backend.write_with(Path::new("/photo.jpg"), jpeg, options)
+    /// }
+    /// ```
+    fn write_with(&self, path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FsError> {
+        let _ = options;
+        self.write(path, data)
+    }
+
+    /// Open a file for writing when the final size is known up front.
+    ///
+    /// Behaves like [`open_write`](Self::open_write). `expected_len` is a
+    /// hint that lets the backend preallocate (`fallocate`) or plan a
+    /// multipart upload; the file still ends up holding exactly the bytes
+    /// written, whether that is more or fewer than expected.
+    ///
+    /// # Default Implementation
+    ///
+    /// Ignores `expected_len` and calls [`open_write`](Self::open_write).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`open_write`](Self::open_write).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsWrite};
+    /// use std::path::Path;
+    ///
+    /// fn upload<B: FsWrite>(backend: &B, src: &mut dyn std::io::Read, len: u64) -> Result<(), FsError> {
+    ///     let path = Path::new("/uploads/video.mp4");
+    ///     let mut writer = backend.open_write_sized(path, len)?;
+    ///     std::io::copy(src, &mut writer).map_err(|e| FsError::io("open_write_sized", path, e))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    fn open_write_sized(
+        &self,
+        path: &Path,
+        expected_len: u64,
+    ) -> Result<Box<dyn Write + Send>, FsError> {
+        let _ = expected_len;
+        self.open_write(path)
+    }
+
+    /// Set the access and/or modification time of `path` (`utimensat`).
+    ///
+    /// A `None` time is left unchanged. Either both times change or
+    /// neither does, so a backend that can't store `atime` fails the whole
+    /// call with [`FsError::NotSupported`] when it is set.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`FsError::NotSupported`]. Backends implementing
+    /// [`FsInode::set_attr`](crate::FsInode::set_attr) can delegate to it
+    /// with only `atime` and `mtime` set.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`] if the path does not exist
+    /// - [`FsError::NotSupported`] if the backend can't store the times
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, FsWrite};
+    /// use std::path::Path;
+    /// use std::time::SystemTime;
+    ///
+    /// fn stamp<B: FsWrite>(backend: &B, path: &Path, at: SystemTime) -> Result<(), FsError> {
+    ///     backend.set_times(path, None, Some(at))
+    /// }
+    /// ```
+    fn set_times(
+        &self,
+        path: &Path,
+        atime: Option<SystemTime>,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), FsError> {
+        let _ = (path, atime, mtime);
+        Err(FsError::NotSupported {
+            operation: "set_times",
+        })
+    }
 }
@@ -37,6 +37,21 @@
 //! - `system.*` - System-defined attributes
 //! - `security.*` - Security-related attributes
 //! - `trusted.*` - Trusted attributes (require privileges)
+//! - `anyfs.*` - Well-known keys shared by AnyFS backends
+//!
+//! # Well-Known Keys
+//!
+//! | Constant | Key | Value |
+//! |----------|-----|-------|
+//! | [`XATTR_CONTENT_TYPE`] | `anyfs.content_type` | MIME type, e.g. `text/html; charset=utf-8` |
+//! | [`XATTR_ENCODING`] | `anyfs.encoding` | Content encoding, e.g. `gzip` |
+//! | [`XATTR_ENC_ALGORITHM`] | `anyfs.enc.algorithm` | Cipher of encrypted contents, e.g. `aes-256-gcm` |
+//! | [`XATTR_ENC_KEY_ID`] | `anyfs.enc.key_id` | Id of the key the contents are encrypted with |
+//! | [`XATTR_ENC_NONCE`] | `anyfs.enc.nonce` | Raw nonce bytes |
+//! | [`XATTR_EXPIRES`] | `anyfs.expires` | Expiry as decimal Unix seconds, e.g. `1767225600` |
+//!
+//! Web-server and object-store backends map these to their native headers
+//! (`Content-Type`, `Content-Encoding`). Values are UTF-8.
 //!
 //! # Thread Safety
 //!
@@ -47,6 +62,31 @@ use std::path::Path;
 
 use crate::FsError;
 
+/// Well-known xattr key holding a file's MIME type.
+///
+/// See [`FsExt::content_type`](crate::FsExt::content_type).
+pub const XATTR_CONTENT_TYPE: &str = "anyfs.content_type";
+
+/// Well-known xattr key holding a file's content encoding (e.g. `gzip`).
+pub const XATTR_ENCODING: &str = "anyfs.encoding";
+
+/// Well-known xattr key naming the cipher of encrypted contents.
+///
+/// See [`EncryptionInfo`](crate::EncryptionInfo).
+pub const XATTR_ENC_ALGORITHM: &str = "anyfs.enc.algorithm";
+
+/// Well-known xattr key holding the id of the key contents are encrypted with.
+pub const XATTR_ENC_KEY_ID: &str = "anyfs.enc.key_id";
+
+/// Well-known xattr key holding the raw nonce of encrypted contents.
+pub const XATTR_ENC_NONCE: &str = "anyfs.enc.nonce";
+
+/// Well-known xattr key holding a file's expiry time, for backends without
+/// native TTLs.
+///
+/// See [`FsExt::xattr_expiry`](crate::FsExt::xattr_expiry).
+pub const XATTR_EXPIRES: &str = "anyfs.expires";
+
 /// Extended attribute operations for POSIX compatibility.
 ///
 /// This trait provides access to extended attributes (xattrs), which are
@@ -167,9 +207,7 @@ mod tests {
     impl FsXattr for MockXattrFs {
         fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
             if !self.paths.read().unwrap().contains(path) {
-                return Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                });
+                return Err(FsError::not_found("get_xattr", path));
             }
 
             self.xattrs
@@ -185,9 +223,7 @@ mod tests {
 
         fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
             if !self.paths.read().unwrap().contains(path) {
-                return Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                });
+                return Err(FsError::not_found("set_xattr", path));
             }
 
             self.xattrs
@@ -201,9 +237,7 @@ mod tests {
 
         fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
             if !self.paths.read().unwrap().contains(path) {
-                return Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                });
+                return Err(FsError::not_found("remove_xattr", path));
             }
 
             let mut xattrs = self.xattrs.write().unwrap();
@@ -221,9 +255,7 @@ mod tests {
 
         fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
             if !self.paths.read().unwrap().contains(path) {
-                return Err(FsError::NotFound {
-                    path: path.to_path_buf(),
-                });
+                return Err(FsError::not_found("list_xattr", path));
             }
 
             Ok(self
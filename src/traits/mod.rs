@@ -86,38 +86,72 @@
 //! }
 //! ```
 
+mod fs_branch;
+mod fs_cache_control;
 mod fs_dir;
+mod fs_dir_handles;
+mod fs_expiry;
+mod fs_export;
+mod fs_file_flags;
+mod fs_file_id;
+mod fs_handle_debug;
 mod fs_handles;
 mod fs_inode;
+mod fs_layers;
+mod fs_lease;
 mod fs_link;
 mod fs_lock;
+mod fs_maybe_full;
 mod fs_path;
 mod fs_permissions;
 mod fs_read;
+mod fs_seek;
+mod fs_sequence;
 mod fs_stats;
 mod fs_sync;
+mod fs_tiering;
 mod fs_write;
 mod fs_xattr;
 
 // Layer 1 - Core traits
-pub use fs_dir::{FsDir, ReadDirIter};
+pub use fs_dir::{FsDir, ReadDirIter, SkipErrors};
 pub use fs_read::FsRead;
 pub use fs_write::FsWrite;
 
 // Layer 2 - Extended traits
 pub use fs_link::FsLink;
-pub use fs_path::FsPath;
+pub use fs_path::{is_path_within, FsPath};
 pub use fs_permissions::FsPermissions;
 pub use fs_stats::FsStats;
 pub use fs_sync::FsSync;
 
 // Layer 3 - FUSE traits
-pub use fs_inode::FsInode;
+pub use fs_inode::{read_dir_inode_by_index, set_attr_by_path, FsInode};
 
 // Layer 4 - POSIX traits
-pub use fs_handles::FsHandles;
+pub use fs_handles::{FsHandles, TempFileEmulation};
 pub use fs_lock::FsLock;
-pub use fs_xattr::FsXattr;
+pub use fs_xattr::{
+    FsXattr, XATTR_CONTENT_TYPE, XATTR_ENCODING, XATTR_ENC_ALGORITHM, XATTR_ENC_KEY_ID,
+    XATTR_ENC_NONCE, XATTR_EXPIRES,
+};
+
+// Optional traits - not part of any composite
+pub use fs_branch::FsBranch;
+pub use fs_cache_control::FsCacheControl;
+pub use fs_dir_handles::FsDirHandles;
+pub use fs_expiry::{ExpirySweeper, FsExpiry};
+pub use fs_export::FsExport;
+pub use fs_file_flags::FsFileFlags;
+pub use fs_file_id::FsFileId;
+pub use fs_handle_debug::FsHandleDebug;
+pub use fs_layers::FsLayers;
+pub use fs_lease::{FsLease, LeaseBreak, LeaseBreakHandler};
+pub use fs_maybe_full::FsMaybeFull;
+pub(crate) use fs_seek::seek_target;
+pub use fs_seek::{FsSeek, HandleCursors};
+pub use fs_sequence::{FsSequence, SequenceCounter};
+pub use fs_tiering::FsTiering;
 
 /// Basic filesystem — covers 90% of use cases.
 ///
@@ -6,15 +6,46 @@
 //!
 //! | Type | Purpose |
 //! |------|---------|
-//! | [`FileType`] | Enum: File, Directory, or Symlink |
+//! | [`FileType`] | Enum: File, Directory, Symlink, or Fifo |
 //! | [`Metadata`] | File/directory info: size, type, times, permissions |
+//! | [`MetadataLite`] | Cheap subset of `Metadata`: type, size, inode |
+//! | [`MetadataMask`] | `statx`-style selection of `Metadata` fields |
 //! | [`DirEntry`] | Single entry from a directory listing |
+//! | [`RemoveDirReport`] | Outcome of a best-effort recursive removal |
+//! | [`CopyOptions`] | Overwrite policy and metadata preservation for copies |
+//! | [`SetAttr`] | Attribute changes for FUSE `setattr` |
+//! | [`ReadDirOptions`] | Ordering of a directory listing |
+//! | [`ContentTag`] | ETag or checksum identifying file content |
 //! | [`Permissions`] | Unix-style permission bits (rwxrwxrwx) |
+//! | [`Rwx`] | Read/write/execute bits of one permission class |
+//! | [`Credentials`] | User and groups a request is checked against |
+//! | [`UmaskPolicy`] | Umask applied to newly created entries |
+//! | [`CreateMode`] | Modes given to newly created files and directories |
 //! | [`StatFs`] | Filesystem-level statistics (total/used/available space) |
+//! | [`NameCollisionPolicy`] | Whether names differing only in case collide |
+//! | [`UsageReport`] | Space used below a path, split by child |
+//! | [`IoStats`] | Cumulative bytes transferred and operation counts |
+//! | [`MergePolicy`] | How a branch merge settles conflicting paths |
+//! | [`MergeReport`] | Paths applied and conflicts found by a branch merge |
+//! | [`LayerId`] | Position of a layer in an overlay stack |
+//! | [`LayerInfo`] | Description of one layer of an overlay stack |
 //! | [`Handle`] | Opaque file handle for POSIX-style operations |
+//! | [`DirHandle`] | Opaque directory handle for `openat`-style operations |
+//! | [`HandleInfo`] | Snapshot of an open handle for leak detection |
+//! | [`FileId`] | Stable file identity for export servers, independent of paths |
+//! | [`LeaseHint`] | How long export clients may cache attributes and lookups |
+//! | [`CachePolicy`] | Limits, lifetimes and [`WritePolicy`] of cache middleware |
+//! | [`CacheStats`] | Hit, miss and size counters of cache middleware |
+//! | [`NotFoundCacheHint`] | How stale a cached "not found" may be |
 //! | [`OpenFlags`] | Flags for opening files (read/write/create/truncate) |
+//! | [`OpenOptions`] | `std::fs::OpenOptions`-style builder for [`OpenFlags`] |
 //! | [`LockType`] | Shared or exclusive file lock |
+//! | [`LockOwner`] | Opaque owner of POSIX record locks (FUSE `lock_owner`) |
+//! | [`LockRange`] | Byte range covered by a record lock |
+//! | [`LeaseType`] | Read or write caching lease |
+//! | [`FileFlags`] | `chattr`-style immutable / append-only flags |
 //! | [`ROOT_INODE`] | Constant: root directory inode (always 1) |
+//! | [`SERDE_SCHEMA_VERSION`] | Constant: version of the serialized form of these types |
 //!
 //! ## Serde Support
 //!
@@ -24,9 +55,13 @@
 //! [dependencies]
 //! anyfs-backend = { version = "0.1", features = ["serde"] }
 //! ```
+//!
+//! The serialized forms only change additively; see
+//! [Serde Stability](crate#serde-stability).
 
+use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 /// The root directory inode number (FUSE convention).
 ///
@@ -42,15 +77,36 @@ use std::time::SystemTime;
 /// ```
 pub const ROOT_INODE: u64 = 1;
 
+/// Version of the serialized form of this crate's types.
+///
+/// Adding a defaulted field keeps the version; renaming, removing or
+/// retyping a field or variant, or adding an enum variant, bumps it. Peers
+/// exchanging serialized values compare versions first. See
+/// [Serde Stability](crate#serde-stability).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::SERDE_SCHEMA_VERSION;
+///
+/// assert_eq!(SERDE_SCHEMA_VERSION, 1);
+/// ```
+pub const SERDE_SCHEMA_VERSION: u32 = 1;
+
 /// The type of a filesystem entry.
 ///
-/// Every path in a filesystem is one of these three types.
+/// Every path in a filesystem is one of these types.
 ///
 /// # Variants
 ///
 /// - [`File`](FileType::File) — Regular file containing data
 /// - [`Directory`](FileType::Directory) — Container for other entries
 /// - [`Symlink`](FileType::Symlink) — Symbolic link pointing to another path
+/// - [`Fifo`](FileType::Fifo) — Named pipe created with
+///   [`FsHandles::mkfifo`](crate::FsHandles::mkfifo)
+///
+/// The enum is `#[non_exhaustive]`: new entry types can be added without a
+/// breaking release, so matches outside this crate need a wildcard arm.
 ///
 /// # Example
 ///
@@ -72,10 +128,13 @@ pub const ROOT_INODE: u64 = 1;
 ///     FileType::File => println!("It's a file"),
 ///     FileType::Directory => println!("It's a directory"),
 ///     FileType::Symlink => println!("It's a symlink"),
+///     FileType::Fifo => println!("It's a named pipe"),
+///     _ => println!("Some other kind of entry"),
 /// }
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum FileType {
     /// Regular file containing data.
     File,
@@ -83,6 +142,40 @@ pub enum FileType {
     Directory,
     /// Symbolic link pointing to another path.
     Symlink,
+    /// Named pipe (FIFO). See [`FsHandles`](crate::FsHandles#fifos) for how
+    /// handles on it behave.
+    Fifo,
+}
+
+impl TryFrom<std::fs::FileType> for FileType {
+    type Error = crate::FsError;
+
+    /// Convert a native file type. FIFOs are recognized on Unix.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotSupported`](crate::FsError::NotSupported) for
+    ///   devices and sockets, which AnyFS has no type for
+    fn try_from(file_type: std::fs::FileType) -> Result<Self, Self::Error> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return Ok(FileType::Fifo);
+            }
+        }
+        if file_type.is_file() {
+            Ok(FileType::File)
+        } else if file_type.is_dir() {
+            Ok(FileType::Directory)
+        } else if file_type.is_symlink() {
+            Ok(FileType::Symlink)
+        } else {
+            Err(crate::FsError::NotSupported {
+                operation: "special file",
+            })
+        }
+    }
 }
 
 /// Complete metadata for a filesystem entry.
@@ -96,30 +189,64 @@ pub enum FileType {
 /// | Field | Type | Description |
 /// |-------|------|-------------|
 /// | `file_type` | [`FileType`] | File, Directory, or Symlink |
-/// | `size` | `u64` | Size in bytes (0 for directories) |
+/// | `size` | `u64` | Logical size in bytes (0 for directories) |
 /// | `permissions` | [`Permissions`] | Unix permission bits |
 /// | `created` | `SystemTime` | Creation timestamp |
 /// | `modified` | `SystemTime` | Last modification timestamp |
 /// | `accessed` | `SystemTime` | Last access timestamp |
 /// | `inode` | `u64` | Unique identifier within filesystem |
 /// | `nlink` | `u64` | Number of hard links |
-///
-/// # Example
+/// | `content_tag` | `Option<`[`ContentTag`]`>` | ETag/checksum, if the backend has one |
+/// | `change_counter` | `Option<u64>` | Mutation counter, if the backend keeps one |
+/// | `stored_size` | `Option<u64>` | Bytes occupied in storage, if known |
+/// | `storage_class` | `Option<`[`StorageClass`]`>` | Storage tier, if the backend has tiers |
+/// | `block_size` | `Option<u32>` | Allocation unit in bytes, if known |
+/// | `optimal_io_size` | `Option<u32>` | Preferred transfer size in bytes, if known |
+/// | `uid` | `Option<u32>` | Owner user id, if the backend tracks owners |
+/// | `gid` | `Option<u32>` | Owner group id, if the backend tracks owners |
+///
+/// # Size Contract
+///
+/// `size` is always the **logical** size: the number of bytes
+/// [`FsRead::read`](crate::FsRead::read) returns. Backends and middleware
+/// that store contents differently (compression, encryption, deduplication)
+/// must keep reporting the logical size there and put the number of bytes
+/// actually occupied in `stored_size`. `None` means the backend doesn't
+/// know, or stores contents as-is. [`StatFs`] byte counts are stored
+/// bytes, so they add up to `stored_size`, not `size`.
+///
+/// # Change Counter Contract
+///
+/// Backends that report `change_counter` (NTFS USN, `st_ctime` generation,
+/// database row version) must give it a different value after **any**
+/// mutation of the entry: content writes, truncation, permission or xattr
+/// changes, and renames of the entry itself. Incremental sync compares the
+/// counter with the one it saw last time instead of hashing content.
+///
+/// Only inequality is meaningful. Counters need not be ordered, and are not
+/// comparable between different entries or different backends. `None` means
+/// the backend doesn't track changes; callers fall back to `modified`, `size`
+/// or [`content_tag`](Self::content_tag).
+///
+/// # Construction
+///
+/// The struct is `#[non_exhaustive]` so fields can be added without a
+/// breaking change. Outside this crate, start from [`Metadata::default`]
+/// and chain the `with_*` setters:
 ///
 /// ```rust
 /// use anyfs_backend::{Metadata, FileType, Permissions};
 /// use std::time::SystemTime;
 ///
-/// let meta = Metadata {
-///     file_type: FileType::File,
-///     size: 1024,
-///     permissions: Permissions::from_mode(0o644),
-///     created: SystemTime::now(),
-///     modified: SystemTime::now(),
-///     accessed: SystemTime::now(),
-///     inode: 42,
-///     nlink: 1,
-/// };
+/// let now = SystemTime::now();
+/// let meta = Metadata::default()
+///     .with_file_type(FileType::File)
+///     .with_size(1024)
+///     .with_permissions(Permissions::from_mode(0o644))
+///     .with_created(now)
+///     .with_modified(now)
+///     .with_accessed(now)
+///     .with_inode(42);
 ///
 /// assert!(meta.is_file());
 /// assert_eq!(meta.size, 1024);
@@ -138,6 +265,7 @@ pub enum FileType {
 /// ```
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct Metadata {
     /// Type of the entry (file, directory, symlink).
     pub file_type: FileType,
@@ -158,6 +286,44 @@ pub struct Metadata {
     pub inode: u64,
     /// Number of hard links.
     pub nlink: u64,
+    /// Content fingerprint (ETag or checksum), if the backend has one.
+    ///
+    /// See [`FsRead::metadata_with_tag`](crate::FsRead::metadata_with_tag).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content_tag: Option<ContentTag>,
+    /// Counter bumped on every mutation, if the backend keeps one.
+    ///
+    /// See the [change counter contract](Self#change-counter-contract).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub change_counter: Option<u64>,
+    /// Bytes occupied in storage, if known.
+    ///
+    /// See the [size contract](Self#size-contract).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stored_size: Option<u64>,
+    /// Storage tier of the file, if the backend has tiers.
+    ///
+    /// See [`FsTiering`](crate::FsTiering).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub storage_class: Option<StorageClass>,
+    /// Allocation unit of the backing store in bytes, if known.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub block_size: Option<u32>,
+    /// Preferred transfer size in bytes (`st_blksize`), if known.
+    ///
+    /// Copy utilities and FUSE adapters size their buffers from this via
+    /// [`io_size_or`](Self::io_size_or).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optimal_io_size: Option<u32>,
+    /// Owner user id, if the backend tracks owners.
+    ///
+    /// `None` entries belong to whoever uses the backend; see
+    /// [`Credentials::rwx_for`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub uid: Option<u32>,
+    /// Owner group id, if the backend tracks owners.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gid: Option<u32>,
 }
 
 impl Metadata {
@@ -178,6 +344,188 @@ impl Metadata {
     pub fn is_symlink(&self) -> bool {
         self.file_type == FileType::Symlink
     }
+
+    /// Returns `true` if this is a named pipe.
+    #[inline]
+    pub fn is_fifo(&self) -> bool {
+        self.file_type == FileType::Fifo
+    }
+
+    /// Set the entry type (builder style).
+    pub fn with_file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = file_type;
+        self
+    }
+
+    /// Set the logical size (builder style).
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the permissions (builder style).
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Set the creation time (builder style).
+    pub fn with_created(mut self, time: SystemTime) -> Self {
+        self.created = time;
+        self
+    }
+
+    /// Set the modification time (builder style).
+    pub fn with_modified(mut self, time: SystemTime) -> Self {
+        self.modified = time;
+        self
+    }
+
+    /// Set the access time (builder style).
+    pub fn with_accessed(mut self, time: SystemTime) -> Self {
+        self.accessed = time;
+        self
+    }
+
+    /// Set the inode number (builder style).
+    pub fn with_inode(mut self, inode: u64) -> Self {
+        self.inode = inode;
+        self
+    }
+
+    /// Set the hard link count (builder style).
+    pub fn with_nlink(mut self, nlink: u64) -> Self {
+        self.nlink = nlink;
+        self
+    }
+
+    /// Set the content tag (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{ContentTag, Metadata};
+    ///
+    /// let meta = Metadata::default().with_content_tag(ContentTag::etag("\"abc\""));
+    /// assert_eq!(meta.content_tag.unwrap().value, "\"abc\"");
+    /// ```
+    pub fn with_content_tag(mut self, tag: ContentTag) -> Self {
+        self.content_tag = Some(tag);
+        self
+    }
+
+    /// Set the change counter (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Metadata;
+    ///
+    /// let seen = Metadata::default().with_change_counter(7);
+    /// let now = Metadata::default().with_change_counter(8);
+    /// assert!(now.changed_since(&seen));
+    /// ```
+    pub fn with_change_counter(mut self, counter: u64) -> Self {
+        self.change_counter = Some(counter);
+        self
+    }
+
+    /// Set the stored size (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Metadata;
+    ///
+    /// let meta = Metadata::default().with_size(4096).with_stored_size(1024);
+    /// assert_eq!(meta.stored_size_or_size(), 1024);
+    /// ```
+    pub fn with_stored_size(mut self, stored: u64) -> Self {
+        self.stored_size = Some(stored);
+        self
+    }
+
+    /// Set the allocation block size (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Metadata;
+    ///
+    /// let meta = Metadata::default().with_block_size(512);
+    /// assert_eq!(meta.io_size_or(4096), 512);
+    /// ```
+    pub fn with_block_size(mut self, size: u32) -> Self {
+        self.block_size = Some(size);
+        self
+    }
+
+    /// Set the preferred I/O size (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Metadata;
+    ///
+    /// let meta = Metadata::default()
+    ///     .with_block_size(4096)
+    ///     .with_optimal_io_size(1 << 20);
+    /// assert_eq!(meta.io_size_or(4096), 1 << 20);
+    /// ```
+    pub fn with_optimal_io_size(mut self, size: u32) -> Self {
+        self.optimal_io_size = Some(size);
+        self
+    }
+
+    /// Set the storage tier (builder style).
+    pub fn with_storage_class(mut self, class: StorageClass) -> Self {
+        self.storage_class = Some(class);
+        self
+    }
+
+    /// Set the owner user and group ids (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Metadata;
+    ///
+    /// let meta = Metadata::default().with_owner(1000, 100);
+    /// assert_eq!((meta.uid, meta.gid), (Some(1000), Some(100)));
+    /// ```
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = Some(uid);
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Buffer size to use for I/O on this entry.
+    ///
+    /// Prefers `optimal_io_size`, then `block_size`, then `fallback`. Zero
+    /// values are treated as unknown.
+    pub fn io_size_or(&self, fallback: u32) -> u32 {
+        self.optimal_io_size
+            .filter(|&n| n > 0)
+            .or(self.block_size.filter(|&n| n > 0))
+            .unwrap_or(fallback)
+    }
+
+    /// Bytes occupied in storage, falling back to the logical `size`.
+    pub fn stored_size_or_size(&self) -> u64 {
+        self.stored_size.unwrap_or(self.size)
+    }
+
+    /// Whether the entry changed between `earlier` and `self`, judged by
+    /// their change counters.
+    ///
+    /// Returns `true` when either side lacks a counter, since no change can
+    /// then be ruled out.
+    pub fn changed_since(&self, earlier: &Metadata) -> bool {
+        match (self.change_counter, earlier.change_counter) {
+            (Some(now), Some(then)) => now != then,
+            _ => true,
+        }
+    }
 }
 
 impl Default for Metadata {
@@ -191,10 +539,285 @@ impl Default for Metadata {
             accessed: SystemTime::UNIX_EPOCH,
             inode: 0,
             nlink: 1,
+            content_tag: None,
+            change_counter: None,
+            stored_size: None,
+            storage_class: None,
+            block_size: None,
+            optimal_io_size: None,
+            uid: None,
+            gid: None,
+        }
+    }
+}
+
+impl TryFrom<&std::fs::Metadata> for Metadata {
+    type Error = crate::FsError;
+
+    /// Convert native metadata, for backends over the host filesystem.
+    ///
+    /// On Unix, `permissions`, `inode`, `nlink`, `stored_size` and
+    /// `optimal_io_size` come from the `stat` fields. Elsewhere `permissions`
+    /// is `0o444` or `0o644` (`0o555`/`0o755` for directories) following the
+    /// read-only attribute, `inode` is 0 and `nlink` is 1. Timestamps the
+    /// platform doesn't record are `UNIX_EPOCH`.
+    ///
+    /// # Errors
+    ///
+    /// - Errors from the [`FileType`] conversion
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{FsError, Metadata};
+    /// use std::path::Path;
+    ///
+    /// fn host_metadata(path: &Path) -> Result<Metadata, FsError> {
+    ///     let meta = std::fs::symlink_metadata(path)
+    ///         .map_err(|e| FsError::io("metadata", path, e))?;
+    ///     Metadata::try_from(&meta)
+    /// }
+    /// ```
+    fn try_from(meta: &std::fs::Metadata) -> Result<Self, Self::Error> {
+        let time = |t: std::io::Result<SystemTime>| t.unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut converted = Metadata {
+            file_type: FileType::try_from(meta.file_type())?,
+            size: meta.len(),
+            created: time(meta.created()),
+            modified: time(meta.modified()),
+            accessed: time(meta.accessed()),
+            ..Metadata::default()
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            converted.permissions = Permissions::from_mode(meta.mode());
+            converted.inode = meta.ino();
+            converted.nlink = meta.nlink();
+            converted.stored_size = Some(meta.blocks() * 512);
+            converted.optimal_io_size = u32::try_from(meta.blksize()).ok();
+            converted.uid = Some(meta.uid());
+            converted.gid = Some(meta.gid());
+        }
+        #[cfg(not(unix))]
+        {
+            let mode = match (converted.file_type, meta.permissions().readonly()) {
+                (FileType::Directory, true) => 0o555,
+                (FileType::Directory, false) => 0o755,
+                (_, true) => 0o444,
+                (_, false) => 0o644,
+            };
+            converted.permissions = Permissions::from_mode(mode);
+        }
+
+        Ok(converted)
+    }
+}
+
+impl TryFrom<std::fs::Metadata> for Metadata {
+    type Error = crate::FsError;
+
+    /// See the impl for `&std::fs::Metadata`.
+    fn try_from(meta: std::fs::Metadata) -> Result<Self, Self::Error> {
+        Self::try_from(&meta)
+    }
+}
+
+/// Algorithm that produced a [`ContentTag`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TagAlgorithm {
+    /// Opaque HTTP/object-store entity tag; only comparable for equality.
+    ETag,
+    /// MD5 digest.
+    Md5,
+    /// SHA-1 digest.
+    Sha1,
+    /// SHA-256 digest.
+    Sha256,
+    /// CRC-32C checksum.
+    Crc32c,
+    /// Any other algorithm, by name.
+    Other(String),
+}
+
+/// Cheap content fingerprint: an ETag or checksum plus its algorithm.
+///
+/// Two tags with the same algorithm and value identify the same content.
+/// Tags with different algorithms are not comparable.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{ContentTag, TagAlgorithm};
+///
+/// let tag = ContentTag::new(TagAlgorithm::Sha256, "9f86d0");
+/// assert_eq!(tag.to_string(), "sha256:9f86d0");
+/// assert_ne!(tag, ContentTag::etag("9f86d0"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentTag {
+    /// Algorithm that produced `value`.
+    pub algorithm: TagAlgorithm,
+    /// The tag or digest (hex for digests, verbatim for ETags).
+    pub value: String,
+}
+
+impl ContentTag {
+    /// Create a tag.
+    pub fn new(algorithm: TagAlgorithm, value: impl Into<String>) -> Self {
+        Self {
+            algorithm,
+            value: value.into(),
+        }
+    }
+
+    /// Create an opaque ETag.
+    pub fn etag(value: impl Into<String>) -> Self {
+        Self::new(TagAlgorithm::ETag, value)
+    }
+}
+
+impl std::fmt::Display for ContentTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let algorithm = match &self.algorithm {
+            TagAlgorithm::ETag => "etag",
+            TagAlgorithm::Md5 => "md5",
+            TagAlgorithm::Sha1 => "sha1",
+            TagAlgorithm::Sha256 => "sha256",
+            TagAlgorithm::Crc32c => "crc32c",
+            TagAlgorithm::Other(name) => name,
+        };
+        write!(f, "{algorithm}:{}", self.value)
+    }
+}
+
+/// Selects which [`Metadata`] fields a caller needs (like Linux `statx`).
+///
+/// Passed to [`FsRead::metadata_mask`](crate::FsRead::metadata_mask) so
+/// remote backends can skip expensive lookups, such as a separate `HEAD`
+/// request for timestamps. `file_type` is always filled.
+///
+/// | Field | Selects |
+/// |-------|---------|
+/// | `size` | `size` |
+/// | `permissions` | `permissions` |
+/// | `times` | `created`, `modified`, `accessed` |
+/// | `links` | `inode`, `nlink` |
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::MetadataMask;
+///
+/// let mask = MetadataMask { size: true, ..MetadataMask::NONE };
+/// assert!(!mask.times);
+/// assert!(MetadataMask::ALL.contains(mask));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataMask {
+    /// Request `size`.
+    pub size: bool,
+    /// Request `permissions`.
+    pub permissions: bool,
+    /// Request `created`, `modified` and `accessed`.
+    pub times: bool,
+    /// Request `inode` and `nlink`.
+    pub links: bool,
+}
+
+impl MetadataMask {
+    /// Only the file type.
+    pub const NONE: Self = Self {
+        size: false,
+        permissions: false,
+        times: false,
+        links: false,
+    };
+
+    /// Every field.
+    pub const ALL: Self = Self {
+        size: true,
+        permissions: true,
+        times: true,
+        links: true,
+    };
+
+    /// Returns `true` if every field requested by `other` is requested by `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        (self.size || !other.size)
+            && (self.permissions || !other.permissions)
+            && (self.times || !other.times)
+            && (self.links || !other.links)
+    }
+}
+
+/// The cheap subset of [`Metadata`]: type, size and inode.
+///
+/// Returned by [`FsRead::metadata_lite`](crate::FsRead::metadata_lite).
+/// Walkers and caches that call it millions of times avoid building three
+/// `SystemTime`s and permissions they never look at.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Metadata, MetadataLite, FileType};
+///
+/// let meta = Metadata::default().with_size(10).with_inode(7);
+/// let lite = MetadataLite::from(&meta);
+/// assert_eq!(lite, MetadataLite { file_type: FileType::File, size: 10, inode: 7 });
+/// assert!(lite.is_file());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataLite {
+    /// Type of the entry (file, directory, symlink).
+    pub file_type: FileType,
+    /// Size in bytes.
+    pub size: u64,
+    /// Inode number (unique identifier within the filesystem).
+    pub inode: u64,
+}
+
+impl MetadataLite {
+    /// Returns `true` if this is a regular file.
+    #[inline]
+    pub fn is_file(&self) -> bool {
+        self.file_type == FileType::File
+    }
+
+    /// Returns `true` if this is a directory.
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Directory
+    }
+
+    /// Returns `true` if this is a symbolic link.
+    #[inline]
+    pub fn is_symlink(&self) -> bool {
+        self.file_type == FileType::Symlink
+    }
+}
+
+impl From<&Metadata> for MetadataLite {
+    fn from(meta: &Metadata) -> Self {
+        Self {
+            file_type: meta.file_type,
+            size: meta.size,
+            inode: meta.inode,
         }
     }
 }
 
+impl From<Metadata> for MetadataLite {
+    fn from(meta: Metadata) -> Self {
+        Self::from(&meta)
+    }
+}
+
 /// A single entry from a directory listing.
 ///
 /// Returned by [`FsDir::read_dir`](crate::FsDir::read_dir) via [`ReadDirIter`](crate::ReadDirIter).
@@ -209,20 +832,24 @@ impl Default for Metadata {
 /// | `file_type` | [`FileType`] | File, Directory, or Symlink |
 /// | `size` | `u64` | Size in bytes |
 /// | `inode` | `u64` | Inode number |
+/// | `content_tag` | `Option<`[`ContentTag`]`>` | ETag/checksum, if listed cheaply |
+/// | `name_os` | `Option<OsString>` | Exact name, when `name` is lossy |
+/// | `symlink_target` | `Option<PathBuf>` | Link target, if listed cheaply |
 ///
-/// # Example
+/// # Construction
+///
+/// Like [`Metadata`], the struct is `#[non_exhaustive]`; outside this
+/// crate, start from [`DirEntry::default`] and chain the `with_*` setters:
 ///
 /// ```rust
 /// use anyfs_backend::{DirEntry, FileType};
-/// use std::path::PathBuf;
 ///
-/// let entry = DirEntry {
-///     name: "readme.md".to_string(),
-///     path: PathBuf::from("/docs/readme.md"),
-///     file_type: FileType::File,
-///     size: 2048,
-///     inode: 123,
-/// };
+/// let entry = DirEntry::default()
+///     .with_name("readme.md")
+///     .with_path("/docs/readme.md")
+///     .with_file_type(FileType::File)
+///     .with_size(2048)
+///     .with_inode(123);
 ///
 /// assert_eq!(entry.name, "readme.md");
 /// assert_eq!(entry.file_type, FileType::File);
@@ -245,6 +872,7 @@ impl Default for Metadata {
 /// ```
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct DirEntry {
     /// Name of the entry (filename only, not full path).
     pub name: String,
@@ -256,12 +884,476 @@ pub struct DirEntry {
     pub size: u64,
     /// Inode number.
     pub inode: u64,
+    /// Content fingerprint, for backends whose listings include one
+    /// (e.g. object-store `ListObjects` ETags).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub content_tag: Option<ContentTag>,
+    /// Exact name as stored by the backend, when it may differ from
+    /// [`name`](Self::name) (i.e. it isn't valid UTF-8).
+    ///
+    /// `None` means `name` is exact. Prefer [`file_name`](Self::file_name),
+    /// which handles both cases; set it with [`with_name_os`](Self::with_name_os).
+    ///
+    /// With `serde`, this is written as `{"lossy": ..., "unix": [...]}` or
+    /// `{"lossy": ..., "wide": [...]}` rather than serde's own `OsString`
+    /// form, which only deserializes on the platform that wrote it. The
+    /// native bytes restore the exact name on a matching platform; anywhere
+    /// else the lossy UTF-8 text is used.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "os_name_serde"
+        )
+    )]
+    pub name_os: Option<OsString>,
+    /// Target of a symlink entry, for backends that know it without an extra
+    /// [`read_link`](crate::FsLink::read_link).
+    ///
+    /// `None` means "not provided", not "not a symlink": callers that need
+    /// the target of a symlink whose entry lacks it must call `read_link`.
+    /// Set it with [`with_symlink_target`](Self::with_symlink_target).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub symlink_target: Option<PathBuf>,
 }
 
-/// Unix-style permission bits.
+impl DirEntry {
+    /// Convert an entry from [`std::fs::read_dir`].
+    ///
+    /// `path` is the host path; backends exposing a different namespace
+    /// replace it. Symlinks are not followed. Fields come from the entry's
+    /// [`Metadata`] conversion, which costs a `stat` on most platforms.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotFound`](crate::FsError::NotFound) if the entry was
+    ///   removed after it was listed
+    /// - [`FsError::NotSupported`](crate::FsError::NotSupported) for devices
+    ///   and sockets
+    /// - [`FsError::Io`](crate::FsError::Io) for other failures
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{DirEntry, FsError};
+    /// use std::path::Path;
+    ///
+    /// fn host_listing(dir: &Path) -> Result<Vec<DirEntry>, FsError> {
+    ///     let io = |e| FsError::io("read_dir", dir, e);
+    ///     std::fs::read_dir(dir)
+    ///         .map_err(io)?
+    ///         .map(|entry| DirEntry::from_std(&entry.map_err(io)?))
+    ///         .collect()
+    /// }
+    /// ```
+    pub fn from_std(entry: &std::fs::DirEntry) -> Result<Self, crate::FsError> {
+        let path = entry.path();
+        let meta = entry.metadata().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => crate::FsError::not_found("read_dir", &path),
+            _ => crate::FsError::io("read_dir", &path, e),
+        })?;
+        let meta = Metadata::try_from(&meta)?;
+        Ok(DirEntry {
+            path,
+            file_type: meta.file_type,
+            size: meta.size,
+            inode: meta.inode,
+            ..DirEntry::default()
+        }
+        .with_name_os(entry.file_name()))
+    }
+
+    /// Set the name from a UTF-8 string (builder style).
+    ///
+    /// Clears `name_os`, since `name` is then exact; use
+    /// [`with_name_os`](Self::with_name_os) for native names.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.name_os = None;
+        self
+    }
+
+    /// Set the full path (builder style).
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set the entry type (builder style).
+    pub fn with_file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = file_type;
+        self
+    }
+
+    /// Set the size (builder style).
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the inode number (builder style).
+    pub fn with_inode(mut self, inode: u64) -> Self {
+        self.inode = inode;
+        self
+    }
+
+    /// Set the content tag (builder style).
+    pub fn with_content_tag(mut self, tag: ContentTag) -> Self {
+        self.content_tag = Some(tag);
+        self
+    }
+
+    /// Set the name from the backend's native name (builder style).
+    ///
+    /// `name` becomes its lossy UTF-8 form; `name_os` keeps the exact bytes
+    /// only when that conversion lost information.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::DirEntry;
+    /// use std::ffi::OsStr;
+    ///
+    /// let entry = DirEntry::default().with_name_os("notes.txt");
+    /// assert_eq!(entry.name, "notes.txt");
+    /// assert_eq!(entry.file_name(), OsStr::new("notes.txt"));
+    /// assert!(!entry.is_name_lossy());
+    /// ```
+    pub fn with_name_os(mut self, name: impl Into<OsString>) -> Self {
+        let name = name.into();
+        match name.into_string() {
+            Ok(utf8) => {
+                self.name = utf8;
+                self.name_os = None;
+            }
+            Err(raw) => {
+                self.name = raw.to_string_lossy().into_owned();
+                self.name_os = Some(raw);
+            }
+        }
+        self
+    }
+
+    /// Record the target of a symlink entry (builder style).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::{DirEntry, FileType};
+    /// use std::path::Path;
+    ///
+    /// let entry = DirEntry::default()
+    ///     .with_name("latest")
+    ///     .with_path("/releases/latest")
+    ///     .with_file_type(FileType::Symlink)
+    ///     .with_symlink_target("v1.2.0");
+    ///
+    /// assert_eq!(entry.symlink_target.as_deref(), Some(Path::new("v1.2.0")));
+    /// ```
+    pub fn with_symlink_target(mut self, target: impl Into<PathBuf>) -> Self {
+        self.symlink_target = Some(target.into());
+        self
+    }
+
+    /// The exact file name, for building paths and round-tripping.
+    pub fn file_name(&self) -> &OsStr {
+        match &self.name_os {
+            Some(raw) => raw,
+            None => OsStr::new(&self.name),
+        }
+    }
+
+    /// Returns `true` if [`name`](Self::name) is a lossy rendering of the
+    /// real name, so it must not be used to address the entry.
+    pub fn is_name_lossy(&self) -> bool {
+        self.name_os.is_some()
+    }
+
+    /// The name for display to users: [`name`](Self::name), which replaces
+    /// undecodable bytes with `U+FFFD`.
+    pub fn display_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Default for DirEntry {
+    /// An empty-named, zero-sized file entry; meant as a base for the
+    /// `with_*` setters.
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            path: PathBuf::new(),
+            file_type: FileType::File,
+            size: 0,
+            inode: 0,
+            content_tag: None,
+            name_os: None,
+            symlink_target: None,
+        }
+    }
+}
+
+/// What [`FsWrite::copy_with`](crate::FsWrite::copy_with) does when the
+/// destination already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverwritePolicy {
+    /// Fail with [`FsError::AlreadyExists`](crate::FsError::AlreadyExists).
+    Fail,
+    /// Replace the destination, like [`FsWrite::copy`](crate::FsWrite::copy).
+    #[default]
+    Replace,
+    /// Leave the destination alone and report 0 bytes copied.
+    Skip,
+}
+
+/// Options for [`FsWrite::copy_with`](crate::FsWrite::copy_with).
 ///
-/// Stores permissions as a standard Unix mode bitmask (rwxrwxrwx format).
-/// The lower 12 bits represent: owner (rwx), group (rwx), other (rwx), plus
+/// The default replaces an existing destination and preserves nothing,
+/// matching [`FsWrite::copy`](crate::FsWrite::copy).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CopyOptions, OverwritePolicy};
+///
+/// // Backup semantics: never clobber, keep mode and timestamps
+/// let backup = CopyOptions {
+///     overwrite: OverwritePolicy::Skip,
+///     preserve_times: true,
+///     preserve_permissions: true,
+///     ..CopyOptions::default()
+/// };
+/// assert!(backup.allow_reflink);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CopyOptions {
+    /// Behavior when the destination exists.
+    pub overwrite: OverwritePolicy,
+    /// Give the copy the source's modification and access times.
+    pub preserve_times: bool,
+    /// Give the copy the source's permissions.
+    pub preserve_permissions: bool,
+    /// Allow a copy-on-write clone (reflink) instead of copying bytes.
+    ///
+    /// A hint: backends without reflinks copy normally.
+    pub allow_reflink: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: OverwritePolicy::Replace,
+            preserve_times: false,
+            preserve_permissions: false,
+            allow_reflink: true,
+        }
+    }
+}
+
+/// How hard a backend should try to compress written data.
+///
+/// A hint: backends and middleware without compression ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompressionHint {
+    /// Store the data uncompressed, e.g. because it already is.
+    None,
+    /// Prefer speed over ratio.
+    Fast,
+    /// Prefer ratio over speed.
+    Best,
+}
+
+/// Storage tier of a file, for backends with storage classes.
+///
+/// A portable subset of object-store tiers:
+///
+/// | Class | S3 | GCS | Azure Blob |
+/// |-------|----|-----|------------|
+/// | `Standard` | `STANDARD` | `STANDARD` | Hot |
+/// | `InfrequentAccess` | `STANDARD_IA` | `NEARLINE` | Cool |
+/// | `Archive` | `GLACIER`, `DEEP_ARCHIVE` | `ARCHIVE` | Archive |
+///
+/// Backends map each class to their closest tier. Reading an `Archive`
+/// file may fail until the backend has restored it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StorageClass {
+    /// Frequently accessed data.
+    Standard,
+    /// Rarely read, but needed immediately when it is.
+    InfrequentAccess,
+    /// Long-term retention; reads may need a restore first.
+    Archive,
+}
+
+/// Options for [`FsWrite::write_with`](crate::FsWrite::write_with).
+///
+/// The default leaves every choice to the backend, matching
+/// [`FsWrite::write`](crate::FsWrite::write).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CompressionHint, WriteOptions};
+///
+/// // A JPEG won't shrink further
+/// let options = WriteOptions {
+///     compression: Some(CompressionHint::None),
+///     ..WriteOptions::default()
+/// };
+/// assert_ne!(options, WriteOptions::default());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WriteOptions {
+    /// Compression to apply; `None` uses the backend's default.
+    pub compression: Option<CompressionHint>,
+    /// Storage tier for the file; `None` uses the backend's default.
+    ///
+    /// Backends without tiers ignore it.
+    pub storage_class: Option<StorageClass>,
+}
+
+/// Options for [`FsDir::read_dir_with`](crate::FsDir::read_dir_with).
+///
+/// The default matches [`FsDir::read_dir`](crate::FsDir::read_dir): entries
+/// come in an unspecified order, which may differ between backends and
+/// between calls.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::ReadDirOptions;
+///
+/// assert!(ReadDirOptions::sorted().sorted);
+/// assert!(!ReadDirOptions::default().sorted);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadDirOptions {
+    /// Yield entries sorted byte-wise by file name (the order of
+    /// comparing [`DirEntry::file_name`]), so listings are reproducible.
+    pub sorted: bool,
+}
+
+impl ReadDirOptions {
+    /// Options requesting entries sorted by name.
+    pub const fn sorted() -> Self {
+        Self { sorted: true }
+    }
+}
+
+/// Attribute changes applied together by
+/// [`FsInode::set_attr`](crate::FsInode::set_attr).
+///
+/// Mirrors FUSE `setattr`: every `None` field is left unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::SetAttr;
+/// use std::time::SystemTime;
+///
+/// // `truncate -s 0` with an explicit mtime
+/// let attr = SetAttr {
+///     size: Some(0),
+///     mtime: Some(SystemTime::UNIX_EPOCH),
+///     ..SetAttr::default()
+/// };
+/// assert!(!attr.is_empty());
+/// assert!(SetAttr::default().is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetAttr {
+    /// New permission bits (`chmod`).
+    pub mode: Option<Permissions>,
+    /// New owner user id (`chown`).
+    pub uid: Option<u32>,
+    /// New owner group id (`chown`).
+    pub gid: Option<u32>,
+    /// New file size (`truncate`). Only valid on files.
+    pub size: Option<u64>,
+    /// New access time (`utimens`).
+    #[cfg_attr(feature = "serde", serde(default, with = "system_time_serde::option"))]
+    pub atime: Option<SystemTime>,
+    /// New modification time (`utimens`).
+    #[cfg_attr(feature = "serde", serde(default, with = "system_time_serde::option"))]
+    pub mtime: Option<SystemTime>,
+}
+
+impl SetAttr {
+    /// Returns `true` if no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == SetAttr::default()
+    }
+}
+
+/// Outcome of a best-effort recursive removal.
+///
+/// Returned by [`FsExt::remove_dir_all_report`](crate::FsExt::remove_dir_all_report)
+/// and [`ExpirySweeper::sweep`](crate::ExpirySweeper::sweep).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, RemoveDirReport};
+///
+/// fn summarize(report: RemoveDirReport) -> Result<(), FsError> {
+///     for failure in &report.failures {
+///         eprintln!("kept {}: {}", failure.path.display(), failure.error);
+///     }
+///     println!("removed {} entries", report.removed);
+///     report.into_result()
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RemoveDirReport {
+    /// Number of entries removed, including the root if it was removed.
+    pub removed: u64,
+    /// Entries that could not be removed, in the order they failed.
+    ///
+    /// Ancestors of a failed entry are left in place without being reported,
+    /// since they can't be empty.
+    pub failures: Vec<RemoveFailure>,
+}
+
+impl RemoveDirReport {
+    /// Returns `true` if the whole tree was removed.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// `Ok(())` if complete, otherwise the first failure's error.
+    pub fn into_result(self) -> Result<(), crate::FsError> {
+        match self.failures.into_iter().next() {
+            None => Ok(()),
+            Some(failure) => Err(failure.error),
+        }
+    }
+}
+
+/// An entry [`RemoveDirReport`] could not remove (or list).
+#[derive(Debug, Clone)]
+pub struct RemoveFailure {
+    /// The entry, or the directory whose listing failed.
+    pub path: PathBuf,
+    /// Why it failed.
+    pub error: crate::FsError,
+}
+
+/// Unix-style permission bits.
+///
+/// Stores permissions as a standard Unix mode bitmask (rwxrwxrwx format).
+/// The lower 12 bits represent: owner (rwx), group (rwx), other (rwx), plus
 /// setuid/setgid/sticky bits.
 ///
 /// # Permission Bits
@@ -342,6 +1434,430 @@ impl Permissions {
     pub const fn default_dir() -> Self {
         Self(0o755)
     }
+
+    /// Set-user-ID bit.
+    pub const SETUID: u32 = 0o4000;
+    /// Set-group-ID bit.
+    pub const SETGID: u32 = 0o2000;
+    /// Sticky bit.
+    pub const STICKY: u32 = 0o1000;
+    /// Owner read bit.
+    pub const OWNER_READ: u32 = 0o400;
+    /// Owner write bit.
+    pub const OWNER_WRITE: u32 = 0o200;
+    /// Owner execute/search bit.
+    pub const OWNER_EXEC: u32 = 0o100;
+    /// Group read bit.
+    pub const GROUP_READ: u32 = 0o040;
+    /// Group write bit.
+    pub const GROUP_WRITE: u32 = 0o020;
+    /// Group execute/search bit.
+    pub const GROUP_EXEC: u32 = 0o010;
+    /// Other read bit.
+    pub const OTHER_READ: u32 = 0o004;
+    /// Other write bit.
+    pub const OTHER_WRITE: u32 = 0o002;
+    /// Other execute/search bit.
+    pub const OTHER_EXEC: u32 = 0o001;
+
+    /// Owner class bits.
+    #[inline]
+    pub const fn owner(&self) -> Rwx {
+        Rwx::from_bits(self.0 >> 6)
+    }
+
+    /// Group class bits.
+    #[inline]
+    pub const fn group(&self) -> Rwx {
+        Rwx::from_bits(self.0 >> 3)
+    }
+
+    /// Other class bits.
+    #[inline]
+    pub const fn other(&self) -> Rwx {
+        Rwx::from_bits(self.0)
+    }
+
+    /// Returns `true` if every bit in `bits` is set.
+    #[inline]
+    pub const fn contains(&self, bits: u32) -> bool {
+        self.0 & bits == bits
+    }
+
+    /// Set the given mode bits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Permissions;
+    ///
+    /// let mut perm = Permissions::from_mode(0o644);
+    /// perm.set(Permissions::OWNER_EXEC | Permissions::GROUP_EXEC);
+    /// perm.clear(Permissions::OTHER_READ);
+    /// assert_eq!(perm.mode(), 0o750);
+    /// ```
+    #[inline]
+    pub fn set(&mut self, bits: u32) {
+        self.0 |= bits & 0o7777;
+    }
+
+    /// Clear the given mode bits.
+    #[inline]
+    pub fn clear(&mut self, bits: u32) {
+        self.0 &= !bits;
+    }
+
+    /// Parse `ls`-style symbolic permissions such as `rw-r--r--`.
+    ///
+    /// Accepts 9 characters, or 10 with a leading `ls` file-type character
+    /// (one of `-dlpscb`, as in `-rw-r--r--` or `drwxr-xr-x`) which is
+    /// validated and then ignored. The execute positions
+    /// accept `s`/`S` (setuid/setgid) and `t`/`T` (sticky), lowercase meaning
+    /// the execute bit is also set.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidData`](crate::FsError::InvalidData) if the string
+    ///   is not valid symbolic notation
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Permissions;
+    ///
+    /// assert_eq!(Permissions::from_symbolic("rw-r--r--").unwrap().mode(), 0o644);
+    /// assert_eq!(Permissions::from_symbolic("drwxrwxrwt").unwrap().mode(), 0o1777);
+    /// assert!(Permissions::from_symbolic("rw-r--r-").is_err());
+    /// ```
+    pub fn from_symbolic(symbolic: &str) -> Result<Self, crate::FsError> {
+        let invalid = || crate::FsError::InvalidData {
+            path: PathBuf::new(),
+            details: format!("invalid symbolic permissions: {symbolic:?}"),
+        };
+        let chars: Vec<char> = symbolic.chars().collect();
+        let chars = match chars.len() {
+            9 => &chars[..],
+            10 if "-dlpscb".contains(chars[0]) => &chars[1..],
+            _ => return Err(invalid()),
+        };
+
+        // (special bit, special letter) per class, in owner/group/other order
+        const SPECIAL: [(u32, char); 3] = [
+            (Permissions::SETUID, 's'),
+            (Permissions::SETGID, 's'),
+            (Permissions::STICKY, 't'),
+        ];
+        let mut mode = 0;
+        for (class, triple) in chars.chunks(3).enumerate() {
+            let shift = 6 - 3 * class as u32;
+            let (special, letter) = SPECIAL[class];
+            mode |= match triple[0] {
+                'r' => 4 << shift,
+                '-' => 0,
+                _ => return Err(invalid()),
+            };
+            mode |= match triple[1] {
+                'w' => 2 << shift,
+                '-' => 0,
+                _ => return Err(invalid()),
+            };
+            mode |= match triple[2] {
+                'x' => 1 << shift,
+                '-' => 0,
+                c if c == letter => special | 1 << shift,
+                c if c == letter.to_ascii_uppercase() => special,
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(Self(mode))
+    }
+
+    /// Format as `ls`-style symbolic permissions (9 characters).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::Permissions;
+    ///
+    /// assert_eq!(Permissions::from_mode(0o755).to_symbolic(), "rwxr-xr-x");
+    /// assert_eq!(Permissions::from_mode(0o4644).to_symbolic(), "rwSr--r--");
+    /// ```
+    pub fn to_symbolic(&self) -> String {
+        let classes = [
+            (self.owner(), self.contains(Self::SETUID), 's'),
+            (self.group(), self.contains(Self::SETGID), 's'),
+            (self.other(), self.contains(Self::STICKY), 't'),
+        ];
+        let mut out = String::with_capacity(9);
+        for (rwx, special, letter) in classes {
+            out.push(if rwx.read { 'r' } else { '-' });
+            out.push(if rwx.write { 'w' } else { '-' });
+            out.push(match (rwx.execute, special) {
+                (true, false) => 'x',
+                (false, false) => '-',
+                (true, true) => letter,
+                (false, true) => letter.to_ascii_uppercase(),
+            });
+        }
+        out
+    }
+}
+
+/// Umask applied to the mode of newly created entries.
+///
+/// Bits set in `umask` are removed from the requested mode, as with the POSIX
+/// process umask. With `inherit_setgid`, a new directory inside a setgid
+/// directory is setgid too (BSD/Linux group-inheritance semantics).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Permissions, UmaskPolicy};
+///
+/// let policy = UmaskPolicy::new(0o027);
+/// assert_eq!(policy.apply(Permissions::from_mode(0o777)).mode(), 0o750);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UmaskPolicy {
+    /// Permission bits to clear (only `0o777` is honoured).
+    pub umask: u32,
+    /// New directories inherit the setgid bit of their parent.
+    pub inherit_setgid: bool,
+}
+
+impl UmaskPolicy {
+    /// The conventional `0o022` umask with setgid inheritance.
+    pub const DEFAULT: Self = Self {
+        umask: 0o022,
+        inherit_setgid: true,
+    };
+
+    /// Umask that clears nothing.
+    pub const NONE: Self = Self {
+        umask: 0,
+        inherit_setgid: true,
+    };
+
+    /// Policy with the given umask and setgid inheritance.
+    #[inline]
+    pub const fn new(umask: u32) -> Self {
+        Self {
+            umask: umask & 0o777,
+            inherit_setgid: true,
+        }
+    }
+
+    /// Clear the umask bits from `requested`.
+    #[inline]
+    pub const fn apply(&self, requested: Permissions) -> Permissions {
+        Permissions::from_mode(requested.mode() & !(self.umask & 0o777))
+    }
+}
+
+impl Default for UmaskPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Modes given to newly created files and directories.
+///
+/// Holds the requested base modes (like the `mode` argument of `open(2)` and
+/// `mkdir(2)`) plus the [`UmaskPolicy`] filtering them. The default resolves
+/// to the crate-wide defaults [`Permissions::default_file`] (0o644) and
+/// [`Permissions::default_dir`] (0o755).
+///
+/// See [`LayerExt::with_default_permissions`](crate::LayerExt::with_default_permissions)
+/// to apply a `CreateMode` to any backend.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CreateMode, Permissions, UmaskPolicy};
+///
+/// let private = CreateMode::default().with_umask(UmaskPolicy::new(0o077));
+/// assert_eq!(private.file_mode().mode(), 0o600);
+/// assert_eq!(private.dir_mode(None).mode(), 0o700);
+///
+/// // Directories created inside a setgid directory stay setgid
+/// let parent = Permissions::from_mode(0o2775);
+/// assert_eq!(CreateMode::default().dir_mode(Some(parent)).mode(), 0o2755);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreateMode {
+    /// Requested mode for new files, before the umask.
+    pub file: Permissions,
+    /// Requested mode for new directories, before the umask.
+    pub dir: Permissions,
+    /// Umask applied to both.
+    pub umask: UmaskPolicy,
+}
+
+impl CreateMode {
+    /// Request `file` and `dir` modes exactly, with no umask.
+    #[inline]
+    pub const fn new(file: Permissions, dir: Permissions) -> Self {
+        Self {
+            file,
+            dir,
+            umask: UmaskPolicy::NONE,
+        }
+    }
+
+    /// Replace the umask policy.
+    #[inline]
+    pub const fn with_umask(mut self, umask: UmaskPolicy) -> Self {
+        self.umask = umask;
+        self
+    }
+
+    /// Final mode for a new file.
+    #[inline]
+    pub const fn file_mode(&self) -> Permissions {
+        self.umask.apply(self.file)
+    }
+
+    /// Final mode for a new directory whose parent has `parent` permissions.
+    pub fn dir_mode(&self, parent: Option<Permissions>) -> Permissions {
+        let mut mode = self.umask.apply(self.dir);
+        if let Some(parent) = parent {
+            if self.umask.inherit_setgid && parent.contains(Permissions::SETGID) {
+                mode.set(Permissions::SETGID);
+            }
+        }
+        mode
+    }
+}
+
+impl Default for CreateMode {
+    fn default() -> Self {
+        Self {
+            file: Permissions::from_mode(0o666),
+            dir: Permissions::from_mode(0o777),
+            umask: UmaskPolicy::DEFAULT,
+        }
+    }
+}
+
+/// Read/write/execute bits of one permission class (owner, group, or other).
+///
+/// Returned by [`Permissions::owner`], [`Permissions::group`] and
+/// [`Permissions::other`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Permissions, Rwx};
+///
+/// let perm = Permissions::from_mode(0o750);
+/// assert_eq!(perm.owner(), Rwx { read: true, write: true, execute: true });
+/// assert_eq!(perm.group().bits(), 0o5);
+/// assert_eq!(perm.other(), Rwx::NONE);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rwx {
+    /// Read permission.
+    pub read: bool,
+    /// Write permission.
+    pub write: bool,
+    /// Execute/search permission.
+    pub execute: bool,
+}
+
+impl Rwx {
+    /// No permission.
+    pub const NONE: Self = Self {
+        read: false,
+        write: false,
+        execute: false,
+    };
+
+    /// Build from the low three bits of `bits` (`r` = 4, `w` = 2, `x` = 1).
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self {
+            read: bits & 4 != 0,
+            write: bits & 2 != 0,
+            execute: bits & 1 != 0,
+        }
+    }
+
+    /// The triple as an octal digit (0-7).
+    #[inline]
+    pub const fn bits(&self) -> u32 {
+        (self.read as u32) << 2 | (self.write as u32) << 1 | self.execute as u32
+    }
+}
+
+/// User and groups a request is checked against.
+///
+/// Picks which class of an entry's [`Permissions`] applies, as Unix does:
+/// owner bits if `uid` owns the entry, group bits if one of the groups
+/// does, other bits otherwise. Used by
+/// [`EnforcePermissions`](crate::EnforcePermissions). Uid 0 gets no special
+/// treatment.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Credentials, Metadata, Permissions, Rwx};
+///
+/// let alice = Credentials::new(1000, 1000).with_groups([27]);
+/// let meta = Metadata::default()
+///     .with_permissions(Permissions::from_mode(0o640))
+///     .with_owner(0, 27);
+/// assert_eq!(alice.rwx_for(&meta), Rwx::from_bits(0o4));
+/// assert_eq!(Credentials::new(2000, 2000).rwx_for(&meta), Rwx::NONE);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Credentials {
+    /// User id.
+    pub uid: u32,
+    /// Primary group id.
+    pub gid: u32,
+    /// Supplementary group ids.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Credentials of user `uid` with primary group `gid`.
+    pub fn new(uid: u32, gid: u32) -> Self {
+        Self {
+            uid,
+            gid,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Add supplementary groups.
+    pub fn with_groups(mut self, groups: impl IntoIterator<Item = u32>) -> Self {
+        self.groups.extend(groups);
+        self
+    }
+
+    /// Returns `true` if `gid` is the primary or a supplementary group.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.groups.contains(&gid)
+    }
+
+    /// The permission class of `meta` that applies to these credentials.
+    ///
+    /// Entries without a recorded owner (`uid` of `None`) are treated as
+    /// owned by the caller, since backends that don't track owners are
+    /// single-user.
+    pub fn rwx_for(&self, meta: &Metadata) -> Rwx {
+        match (meta.uid, meta.gid) {
+            (None, _) => meta.permissions.owner(),
+            (Some(uid), _) if uid == self.uid => meta.permissions.owner(),
+            (_, Some(gid)) if self.in_group(gid) => meta.permissions.group(),
+            _ => meta.permissions.other(),
+        }
+    }
 }
 
 impl Default for Permissions {
@@ -368,22 +1884,22 @@ impl Default for Permissions {
 /// | `available_inodes` | `u64` | Inodes available for new entries |
 /// | `block_size` | `u64` | Filesystem block size in bytes |
 /// | `max_name_len` | `u64` | Maximum filename length |
+/// | `name_collision` | [`NameCollisionPolicy`] | Whether names differing only in case collide |
 ///
 /// # Example
 ///
+/// Like [`Metadata`], the struct is `#[non_exhaustive]`; outside this
+/// crate, start from [`StatFs::default`] and chain the `with_*` setters:
+///
 /// ```rust
-/// use anyfs_backend::StatFs;
+/// use anyfs_backend::{NameCollisionPolicy, StatFs};
 ///
-/// let stats = StatFs {
-///     total_bytes: 1_000_000_000,        // 1 GB
-///     used_bytes: 250_000_000,           // 250 MB used
-///     available_bytes: 750_000_000,      // 750 MB free
-///     total_inodes: 100_000,
-///     used_inodes: 1_234,
-///     available_inodes: 98_766,
-///     block_size: 4096,
-///     max_name_len: 255,
-/// };
+/// let stats = StatFs::default()
+///     .with_bytes(1_000_000_000, 250_000_000, 750_000_000) // 1 GB, 250 MB used
+///     .with_inodes(100_000, 1_234, 98_766)
+///     .with_block_size(4096)
+///     .with_max_name_len(255)
+///     .with_name_collision(NameCollisionPolicy::Distinct);
 ///
 /// let usage_percent = (stats.used_bytes as f64 / stats.total_bytes as f64) * 100.0;
 /// println!("Disk usage: {:.1}%", usage_percent);  // "Disk usage: 25.0%"
@@ -391,23 +1907,22 @@ impl Default for Permissions {
 ///
 /// # Unlimited Filesystems
 ///
-/// For backends without fixed limits (e.g., cloud storage), set capacity fields to 0:
+/// For backends without fixed limits (e.g., cloud storage), leave the
+/// capacity fields at 0:
 ///
 /// ```rust
 /// use anyfs_backend::StatFs;
 ///
-/// let unlimited = StatFs {
-///     total_bytes: 0,      // No limit
-///     total_inodes: 0,     // No limit
-///     ..Default::default()
-/// };
+/// let unlimited = StatFs::default();
+/// assert_eq!((unlimited.total_bytes, unlimited.total_inodes), (0, 0));
 /// ```
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct StatFs {
     /// Total size in bytes (0 = unlimited).
     pub total_bytes: u64,
-    /// Currently used bytes.
+    /// Currently used bytes, as stored (after compression).
     pub used_bytes: u64,
     /// Available bytes for use.
     pub available_bytes: u64,
@@ -421,14 +1936,425 @@ pub struct StatFs {
     pub block_size: u64,
     /// Maximum filename length.
     pub max_name_len: u64,
+    /// Whether names differing only in case collide.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name_collision: NameCollisionPolicy,
 }
 
-/// Opaque file handle for POSIX-style I/O operations.
-///
-/// Represents an open file descriptor. Used with [`FsHandles`](crate::FsHandles)
-/// for handle-based read/write operations, and with [`FsLock`](crate::FsLock)
-/// for file locking.
-///
+impl StatFs {
+    /// Set the total, used and available bytes (builder style).
+    pub fn with_bytes(mut self, total: u64, used: u64, available: u64) -> Self {
+        self.total_bytes = total;
+        self.used_bytes = used;
+        self.available_bytes = available;
+        self
+    }
+
+    /// Set the total, used and available inodes (builder style).
+    pub fn with_inodes(mut self, total: u64, used: u64, available: u64) -> Self {
+        self.total_inodes = total;
+        self.used_inodes = used;
+        self.available_inodes = available;
+        self
+    }
+
+    /// Set the block size (builder style).
+    pub fn with_block_size(mut self, size: u64) -> Self {
+        self.block_size = size;
+        self
+    }
+
+    /// Set the maximum filename length (builder style).
+    pub fn with_max_name_len(mut self, len: u64) -> Self {
+        self.max_name_len = len;
+        self
+    }
+
+    /// Set the case collision policy (builder style).
+    pub fn with_name_collision(mut self, policy: NameCollisionPolicy) -> Self {
+        self.name_collision = policy;
+        self
+    }
+}
+
+/// How a backend compares names that differ only in case, reported in
+/// [`StatFs::name_collision`].
+///
+/// Cross-platform tools check it before creating `README` next to
+/// `readme`, and use [`FsExt::rename_case`](crate::FsExt::rename_case) to
+/// change only the case of a name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NameCollisionPolicy {
+    /// Names are compared exactly: `File.txt` and `file.txt` are two
+    /// entries (ext4, most object stores).
+    #[default]
+    Distinct,
+    /// Names collide case-insensitively but keep the spelling they were
+    /// created with (NTFS, APFS).
+    CasePreserving,
+    /// Names collide case-insensitively and are stored in a normalized case
+    /// (FAT short names).
+    CaseFolding,
+}
+
+/// Space used below a path, from [`FsStats::usage`](crate::FsStats::usage).
+///
+/// Sizes are logical file sizes, as in [`Metadata::size`]. Hard links are
+/// counted once per name.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::UsageReport;
+///
+/// let report = UsageReport {
+///     bytes: 3_000,
+///     files: 3,
+///     dirs: 1,
+///     by_child: vec![("videos".into(), 2_000), ("notes.txt".into(), 1_000)],
+/// };
+///
+/// // Treemap tiles
+/// for (name, bytes) in &report.by_child {
+///     let share = *bytes as f64 / report.bytes as f64;
+///     println!("{name}: {:.0}%", share * 100.0);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsageReport {
+    /// Total bytes of every file at or below the path.
+    pub bytes: u64,
+    /// Number of files and FIFOs below the path, or 1 if the path itself is
+    /// one. Symlinks are not counted.
+    pub files: u64,
+    /// Number of directories below the path, not counting the path itself.
+    pub dirs: u64,
+    /// Each direct child's name with the total bytes at or below it,
+    /// largest first. Empty unless the path is a directory.
+    pub by_child: Vec<(String, u64)>,
+}
+
+/// Cumulative I/O counters, from [`FsStats::io_stats`](crate::FsStats::io_stats).
+///
+/// Counters only grow; sample twice and subtract for rates. Every call is
+/// counted in exactly one of `read_ops`, `write_ops` and `other_ops`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::IoStats;
+///
+/// let before = IoStats::default();
+/// let after = IoStats {
+///     bytes_read: 4096,
+///     read_ops: 2,
+///     errors: 1,
+///     ..IoStats::default()
+/// };
+/// let reads = after.read_ops - before.read_ops;
+/// assert_eq!(after.bytes_read / reads, 2048);
+/// assert_eq!(after.total_ops(), 2);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoStats {
+    /// File content bytes returned to callers.
+    pub bytes_read: u64,
+    /// File content bytes accepted from callers.
+    pub bytes_written: u64,
+    /// Calls reading file content.
+    pub read_ops: u64,
+    /// Calls writing or truncating file content.
+    pub write_ops: u64,
+    /// Every other call: metadata, directories, renames, handles.
+    pub other_ops: u64,
+    /// Calls, of any kind, that returned an error.
+    pub errors: u64,
+}
+
+impl IoStats {
+    /// All calls counted, successful or not.
+    pub fn total_ops(&self) -> u64 {
+        self.read_ops + self.write_ops + self.other_ops
+    }
+}
+
+/// When a cache passes writes on to the backend it caches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WritePolicy {
+    /// Writes reach the backend before the call returns.
+    #[default]
+    WriteThrough,
+    /// Writes stay in the cache, marked dirty, until evicted, invalidated
+    /// or flushed with [`FsCacheControl::flush_cache`](crate::FsCacheControl::flush_cache).
+    WriteBack,
+}
+
+/// Configuration shared by cache middleware.
+///
+/// Read through [`FsCacheControl::cache_policy`](crate::FsCacheControl::cache_policy).
+/// The default caches without limits, never expires entries, doesn't cache
+/// "not found" results and writes through.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CachePolicy, WritePolicy};
+/// use std::time::Duration;
+///
+/// let policy = CachePolicy {
+///     max_bytes: Some(64 << 20),
+///     ttl: Some(Duration::from_secs(30)),
+///     negative_ttl: Some(Duration::from_secs(1)),
+///     write_policy: WritePolicy::WriteBack,
+///     ..CachePolicy::default()
+/// };
+/// assert!(policy.is_fresh(Duration::from_secs(10), false));
+/// assert!(!policy.is_fresh(Duration::from_secs(10), true));
+/// assert!(policy.exceeds(1, 65 << 20));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CachePolicy {
+    /// Most bytes of content to keep; `None` for no limit.
+    pub max_bytes: Option<u64>,
+    /// Most entries to keep; `None` for no limit.
+    pub max_entries: Option<u64>,
+    /// How long a cached entry stays valid; `None` until it is evicted or
+    /// invalidated.
+    pub ttl: Option<Duration>,
+    /// How long a "not found" result stays valid; `None` to not cache
+    /// them.
+    pub negative_ttl: Option<Duration>,
+    /// When writes reach the backend.
+    pub write_policy: WritePolicy,
+}
+
+impl CachePolicy {
+    /// Returns `true` if an entry cached `age` ago may still be served.
+    /// `negative` entries record a "not found" result.
+    pub fn is_fresh(&self, age: Duration, negative: bool) -> bool {
+        let ttl = if negative {
+            match self.negative_ttl {
+                Some(ttl) => ttl,
+                None => return false,
+            }
+        } else {
+            match self.ttl {
+                Some(ttl) => ttl,
+                None => return true,
+            }
+        };
+        age < ttl
+    }
+
+    /// Returns `true` if a cache holding `entries` entries and `bytes`
+    /// bytes is over a limit and must evict.
+    pub fn exceeds(&self, entries: u64, bytes: u64) -> bool {
+        self.max_entries.map_or(false, |max| entries > max)
+            || self.max_bytes.map_or(false, |max| bytes > max)
+    }
+}
+
+/// How stale a cached "not found" may be, reported by
+/// [`FsCacheControl::not_found_hint`](crate::FsCacheControl::not_found_hint).
+///
+/// Tells callers whether a `false` from
+/// [`FsRead::exists`](crate::FsRead::exists) (or a `NotFound` from a
+/// lookup) can be trusted, or whether they need
+/// [`FsRead::exists_fresh`](crate::FsRead::exists_fresh).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{CachePolicy, NotFoundCacheHint};
+/// use std::time::Duration;
+///
+/// assert!(NotFoundCacheHint::from_policy(&CachePolicy::default()).is_authoritative());
+///
+/// let policy = CachePolicy {
+///     negative_ttl: Some(Duration::from_secs(5)),
+///     ..CachePolicy::default()
+/// };
+/// assert_eq!(
+///     NotFoundCacheHint::from_policy(&policy),
+///     NotFoundCacheHint::For(Duration::from_secs(5))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NotFoundCacheHint {
+    /// "Not found" results are never cached; `exists` is always fresh.
+    #[default]
+    Uncached,
+    /// A "not found" result may be served for up to this long after the
+    /// path was created elsewhere.
+    For(Duration),
+    /// A "not found" result is served until the entry is invalidated.
+    UntilInvalidated,
+}
+
+impl NotFoundCacheHint {
+    /// The hint for a cache configured with `policy`.
+    pub fn from_policy(policy: &CachePolicy) -> Self {
+        match policy.negative_ttl {
+            None => NotFoundCacheHint::Uncached,
+            Some(ttl) => NotFoundCacheHint::For(ttl),
+        }
+    }
+
+    /// Returns `true` if a "not found" result is always fresh.
+    pub fn is_authoritative(&self) -> bool {
+        matches!(self, NotFoundCacheHint::Uncached)
+    }
+}
+
+/// Counters reported by cache middleware through
+/// [`FsCacheControl::cache_stats`](crate::FsCacheControl::cache_stats).
+///
+/// `hits`, `misses` and `evictions` only grow; the others describe the
+/// cache right now.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::CacheStats;
+///
+/// let stats = CacheStats {
+///     hits: 3,
+///     misses: 1,
+///     ..CacheStats::default()
+/// };
+/// assert_eq!(stats.hit_ratio(), 0.75);
+/// assert_eq!(CacheStats::default().hit_ratio(), 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheStats {
+    /// Lookups served from the cache, "not found" results included.
+    pub hits: u64,
+    /// Lookups that went to the backend.
+    pub misses: u64,
+    /// Entries dropped to stay within the limits or after expiring.
+    pub evictions: u64,
+    /// Entries held.
+    pub entries: u64,
+    /// Content bytes held.
+    pub bytes: u64,
+    /// Content bytes written but not yet passed to the backend; always 0
+    /// under [`WritePolicy::WriteThrough`].
+    pub dirty_bytes: u64,
+}
+
+impl CacheStats {
+    /// The share of lookups served from the cache, 0 without lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// How [`FsBranch::merge`](crate::FsBranch::merge) settles paths changed on
+/// both sides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MergePolicy {
+    /// Apply nothing if any path conflicts, and keep the branch.
+    #[default]
+    Abort,
+    /// Keep the backend's version of conflicting paths.
+    PreferTarget,
+    /// Take the branch's version of conflicting paths.
+    PreferBranch,
+}
+
+/// Outcome of [`FsBranch::merge`](crate::FsBranch::merge).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::MergeReport;
+///
+/// let report = MergeReport {
+///     merged: false,
+///     applied: vec![],
+///     conflicts: vec!["/app/config.toml".into()],
+/// };
+/// for path in &report.conflicts {
+///     eprintln!("changed on both sides: {}", path.display());
+/// }
+/// assert!(!report.is_clean());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MergeReport {
+    /// Whether the branch was merged (and removed). `false` only when
+    /// [`MergePolicy::Abort`] found conflicts.
+    pub merged: bool,
+    /// Paths the merge changed in the backend, sorted.
+    pub applied: Vec<PathBuf>,
+    /// Paths changed on both sides, sorted, however they were settled.
+    pub conflicts: Vec<PathBuf>,
+}
+
+impl MergeReport {
+    /// Returns `true` if no path conflicted.
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Position of a layer in an overlay stack, from
+/// [`FsLayers::which_layer`](crate::FsLayers::which_layer).
+///
+/// Layers are numbered from the top: [`LayerId::TOP`] is the layer that
+/// shadows all others and takes writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerId(pub u32);
+
+impl LayerId {
+    /// The topmost layer.
+    pub const TOP: LayerId = LayerId(0);
+}
+
+/// One layer of an overlay stack, from
+/// [`FsLayers::layers`](crate::FsLayers::layers).
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{LayerId, LayerInfo};
+///
+/// let upper = LayerInfo { id: LayerId::TOP, name: "scratch".into(), writable: true };
+/// let lower = LayerInfo { id: LayerId(1), name: "base-image".into(), writable: false };
+/// assert!(upper.id < lower.id);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerInfo {
+    /// Position in the stack.
+    pub id: LayerId,
+    /// Human-readable name (backend type, mount source, image tag).
+    pub name: String,
+    /// Whether the overlay writes to this layer.
+    pub writable: bool,
+}
+
+/// Opaque file handle for POSIX-style I/O operations.
+///
+/// Represents an open file descriptor. Used with [`FsHandles`](crate::FsHandles)
+/// for handle-based read/write operations, and with [`FsLock`](crate::FsLock)
+/// for file locking.
+///
 /// # Lifecycle
 ///
 /// 1. **Open**: Call [`FsHandles::open`](crate::FsHandles::open) to get a handle
@@ -460,10 +2386,329 @@ pub struct StatFs {
 ///
 /// The `u64` value is backend-defined. It could be an inode number, array index,
 /// or any other unique identifier. Treat it as opaque.
+///
+/// # Generations
+///
+/// Backends that recycle handle ids must detect use-after-close: a stale
+/// handle must not silently address whichever file now occupies its slot.
+/// The low 48 bits of the value hold the id and the high 16 bits hold a
+/// generation counter. Bump the generation every time a slot is reused and
+/// reject handles whose generation doesn't match with
+/// [`FsError::InvalidHandle`](crate::FsError::InvalidHandle).
+///
+/// Backends that never reuse ids can ignore generations entirely —
+/// `Handle(n)` has generation 0.
+///
+/// ```rust
+/// use anyfs_backend::Handle;
+///
+/// let first = Handle::new(3, 0);
+/// let reused = first.next_generation();
+///
+/// assert_eq!(reused.id(), 3);
+/// assert_eq!(reused.generation(), 1);
+/// assert!(first.same_slot(reused));
+/// assert_ne!(first, reused);
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Handle(pub u64);
 
+impl Handle {
+    /// Number of low bits holding the handle id.
+    pub const ID_BITS: u32 = 48;
+
+    /// Largest id that fits in a handle.
+    pub const MAX_ID: u64 = (1 << Self::ID_BITS) - 1;
+
+    /// Create a handle from an id and a generation.
+    ///
+    /// Bits of `id` above [`MAX_ID`](Self::MAX_ID) are discarded.
+    #[inline]
+    pub const fn new(id: u64, generation: u16) -> Self {
+        Self(((generation as u64) << Self::ID_BITS) | (id & Self::MAX_ID))
+    }
+
+    /// The slot id, without the generation.
+    #[inline]
+    pub const fn id(self) -> u64 {
+        self.0 & Self::MAX_ID
+    }
+
+    /// The generation counter.
+    #[inline]
+    pub const fn generation(self) -> u16 {
+        (self.0 >> Self::ID_BITS) as u16
+    }
+
+    /// The same slot with the generation advanced by one (wrapping).
+    ///
+    /// Backends call this when reusing a slot freed by `close`.
+    #[inline]
+    pub const fn next_generation(self) -> Self {
+        Self::new(self.id(), self.generation().wrapping_add(1))
+    }
+
+    /// Returns `true` if both handles address the same slot, regardless of
+    /// generation.
+    #[inline]
+    pub const fn same_slot(self, other: Handle) -> bool {
+        self.id() == other.id()
+    }
+}
+
+/// Snapshot of an open file handle.
+///
+/// Returned by [`FsHandleDebug::open_handles`](crate::FsHandleDebug::open_handles)
+/// so debug layers and tests can find handles that were never closed.
+///
+/// # Fields
+///
+/// | Field | Type | Description |
+/// |-------|------|-------------|
+/// | `handle` | [`Handle`] | The open handle |
+/// | `path` | `PathBuf` | Path the handle was opened with |
+/// | `flags` | [`OpenFlags`] | Flags the handle was opened with |
+/// | `opened_at` | `SystemTime` | When the handle was opened |
+/// | `bytes_read` | `u64` | Bytes read through this handle so far |
+/// | `bytes_written` | `u64` | Bytes written through this handle so far |
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Handle, HandleInfo, OpenFlags};
+/// use std::path::PathBuf;
+/// use std::time::SystemTime;
+///
+/// let info = HandleInfo {
+///     handle: Handle(7),
+///     path: PathBuf::from("/data.bin"),
+///     flags: OpenFlags::READ,
+///     opened_at: SystemTime::now(),
+///     bytes_read: 4096,
+///     bytes_written: 0,
+/// };
+///
+/// assert_eq!(info.handle, Handle(7));
+/// assert!(info.flags.read);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandleInfo {
+    /// The open handle.
+    pub handle: Handle,
+    /// Path the handle was opened with.
+    pub path: PathBuf,
+    /// Flags the handle was opened with.
+    pub flags: OpenFlags,
+    /// When the handle was opened.
+    #[cfg_attr(feature = "serde", serde(with = "system_time_serde"))]
+    pub opened_at: SystemTime,
+    /// Bytes read through this handle so far.
+    pub bytes_read: u64,
+    /// Bytes written through this handle so far.
+    pub bytes_written: u64,
+}
+
+/// Opaque handle to an open directory.
+///
+/// Returned by [`FsDirHandles::open_dir`](crate::FsDirHandles::open_dir) and
+/// used as the anchor for `*_at` operations, which resolve a single name
+/// relative to the directory instead of re-resolving an absolute path.
+///
+/// Directory handles are a separate namespace from file [`Handle`]s; the
+/// same number may be live as both. Like `Handle`, the value is
+/// backend-defined and should be treated as opaque. Backends that recycle
+/// ids should use the same generation scheme as [`Handle`] and report stale
+/// directory handles as
+/// [`FsError::InvalidHandle`](crate::FsError::InvalidHandle) with
+/// `Handle(dir.0)`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::DirHandle;
+///
+/// let dir = DirHandle(4);
+/// assert_eq!(dir, DirHandle(4));
+/// assert_ne!(dir, DirHandle(5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DirHandle(pub u64);
+
+/// Stable identity of a file, independent of its path.
+///
+/// Export servers (9P, NFS) hand these out so clients can refer to a file
+/// after renames and across server restarts, and sync or dedup code uses them
+/// (via [`FsFileId`](crate::FsFileId)) to track identity. A `FileId` is
+/// opaque bytes:
+///
+/// | Backend kind | Constructor |
+/// |--------------|-------------|
+/// | Inode-based | [`FileId::from_inode`] |
+/// | UUID / 128-bit object ids | [`FileId::from_u128`] |
+/// | Database keys, cloud object ids | [`FileId::from_bytes`] |
+///
+/// Ids made by different constructors never compare equal.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::FileId;
+///
+/// let id = FileId::from_inode(42);
+/// assert_eq!(id.as_inode(), Some(42));
+///
+/// let uuid = FileId::from_u128(0x6ba7_b810_9dad_11d1_80b4_00c0_4fd4_30c8);
+/// assert_eq!(uuid.as_u128(), Some(0x6ba7_b810_9dad_11d1_80b4_00c0_4fd4_30c8));
+/// assert_eq!(uuid.as_inode(), None);
+///
+/// let blob = FileId::from_bytes(b"object-7f3a");
+/// assert_eq!(blob.as_inode(), None);
+/// assert_eq!(blob.as_bytes(), b"object-7f3a");
+///
+/// // Embed in a wire handle and decode it again
+/// assert_eq!(FileId::from_encoded(blob.encoded()), Some(blob));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileId(Vec<u8>);
+
+impl FileId {
+    /// Tag byte for ids from [`FileId::from_bytes`].
+    const BYTES_TAG: u8 = 0x00;
+
+    /// Tag byte for ids from [`FileId::from_inode`].
+    const INODE_TAG: u8 = 0x01;
+
+    /// Tag byte for ids from [`FileId::from_u128`].
+    const U128_TAG: u8 = 0x02;
+
+    fn tagged(tag: u8, payload: &[u8]) -> Self {
+        let mut bytes = Vec::with_capacity(payload.len() + 1);
+        bytes.push(tag);
+        bytes.extend_from_slice(payload);
+        Self(bytes)
+    }
+
+    /// An id wrapping an inode number.
+    pub fn from_inode(inode: u64) -> Self {
+        Self::tagged(Self::INODE_TAG, &inode.to_be_bytes())
+    }
+
+    /// An id wrapping a 128-bit value, such as a UUID.
+    pub fn from_u128(value: u128) -> Self {
+        Self::tagged(Self::U128_TAG, &value.to_be_bytes())
+    }
+
+    /// An id from backend-defined bytes.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Self {
+        Self::tagged(Self::BYTES_TAG, bytes.as_ref())
+    }
+
+    /// Rebuild an id from [`FileId::encoded`] output.
+    ///
+    /// Returns `None` if `encoded` is not a valid encoding (for example, a
+    /// corrupted NFS file handle).
+    pub fn from_encoded(encoded: &[u8]) -> Option<Self> {
+        let (&tag, payload) = encoded.split_first()?;
+        let valid = match tag {
+            Self::BYTES_TAG => true,
+            Self::INODE_TAG => payload.len() == 8,
+            Self::U128_TAG => payload.len() == 16,
+            _ => false,
+        };
+        valid.then(|| Self(encoded.to_vec()))
+    }
+
+    /// The inode number, if this id was made by [`FileId::from_inode`].
+    pub fn as_inode(&self) -> Option<u64> {
+        match self.0.split_first() {
+            Some((&Self::INODE_TAG, rest)) if rest.len() == 8 => {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(rest);
+                Some(u64::from_be_bytes(raw))
+            }
+            _ => None,
+        }
+    }
+
+    /// The 128-bit value, if this id was made by [`FileId::from_u128`].
+    pub fn as_u128(&self) -> Option<u128> {
+        match self.0.split_first() {
+            Some((&Self::U128_TAG, rest)) if rest.len() == 16 => {
+                let mut raw = [0u8; 16];
+                raw.copy_from_slice(rest);
+                Some(u128::from_be_bytes(raw))
+            }
+            _ => None,
+        }
+    }
+
+    /// The identifying bytes, without the encoding tag.
+    ///
+    /// For inode and 128-bit ids these are the big-endian value.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[1..]
+    }
+
+    /// The self-describing encoding, suitable for embedding in an NFS file
+    /// handle. Reverse with [`FileId::from_encoded`].
+    #[inline]
+    pub fn encoded(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// How long an export client may cache what it learned about a file.
+///
+/// Returned by [`FsExport::lease_hint`](crate::FsExport::lease_hint). Servers
+/// translate it into FUSE entry/attr timeouts, NFS attribute cache hints or
+/// 9P cache modes. The default, [`LeaseHint::NONE`], forbids caching.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::LeaseHint;
+/// use std::time::Duration;
+///
+/// // An immutable archive can be cached for a long time
+/// let hint = LeaseHint::new(Duration::from_secs(3600), Duration::from_secs(3600));
+/// assert!(hint.is_cacheable());
+/// assert!(!LeaseHint::NONE.is_cacheable());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LeaseHint {
+    /// How long attributes (size, times, permissions) stay valid.
+    pub attr_ttl: Duration,
+    /// How long a name → file lookup stays valid.
+    pub entry_ttl: Duration,
+}
+
+impl LeaseHint {
+    /// No caching: clients must revalidate on every access.
+    pub const NONE: LeaseHint = LeaseHint {
+        attr_ttl: Duration::ZERO,
+        entry_ttl: Duration::ZERO,
+    };
+
+    /// A hint with the given attribute and entry lifetimes.
+    pub const fn new(attr_ttl: Duration, entry_ttl: Duration) -> Self {
+        Self {
+            attr_ttl,
+            entry_ttl,
+        }
+    }
+
+    /// Returns `true` if either lifetime is non-zero.
+    pub fn is_cacheable(&self) -> bool {
+        !self.attr_ttl.is_zero() || !self.entry_ttl.is_zero()
+    }
+}
+
 /// Flags for opening a file.
 ///
 /// Controls how a file is opened: read/write mode, creation behavior, and
@@ -487,6 +2732,11 @@ pub struct Handle(pub u64);
 /// | `create` | Create file if it doesn't exist |
 /// | `truncate` | Truncate file to zero length on open |
 /// | `append` | Writes always go to end of file |
+/// | `create_new` | Create the file, failing if it already exists |
+/// | `mode` | Permissions for a file the open creates |
+///
+/// Code ported from `std::fs` can build flags with [`OpenOptions`] instead,
+/// which checks combinations the way std does.
 ///
 /// # Example
 ///
@@ -504,10 +2754,10 @@ pub struct Handle(pub u64);
 ///     write: true,
 ///     create: true,
 ///     truncate: false,
-///     append: false,
+///     ..OpenFlags::default()
 /// };
 /// ```
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpenFlags {
     /// Open for reading.
@@ -520,44 +2770,249 @@ pub struct OpenFlags {
     pub truncate: bool,
     /// Append to end of file.
     pub append: bool,
+    /// Create the file, failing with
+    /// [`FsError::AlreadyExists`](crate::FsError::AlreadyExists) if it
+    /// exists. Implies `create`; `truncate` has no effect.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub create_new: bool,
+    /// Permissions for a file this open creates, before the umask.
+    ///
+    /// `None` leaves it to the backend (e.g. its [`CreateMode`]). Has no
+    /// effect on existing files, and backends without permissions ignore it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: Option<Permissions>,
+}
+
+impl OpenFlags {
+    /// Read-only access.
+    pub const READ: Self = Self {
+        read: true,
+        write: false,
+        create: false,
+        truncate: false,
+        append: false,
+        create_new: false,
+        mode: None,
+    };
+
+    /// Write access with create and truncate.
+    pub const WRITE: Self = Self {
+        read: false,
+        write: true,
+        create: true,
+        truncate: true,
+        append: false,
+        create_new: false,
+        mode: None,
+    };
+
+    /// Read and write access.
+    pub const READ_WRITE: Self = Self {
+        read: true,
+        write: true,
+        create: false,
+        truncate: false,
+        append: false,
+        create_new: false,
+        mode: None,
+    };
+
+    /// Append mode - writes go to end of file.
+    pub const APPEND: Self = Self {
+        read: false,
+        write: true,
+        create: true,
+        truncate: false,
+        append: true,
+        create_new: false,
+        mode: None,
+    };
+
+    /// Equivalent [`std::fs::OpenOptions`], for backends over the host
+    /// filesystem. `mode` is applied on Unix and ignored elsewhere.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::OpenFlags;
+    ///
+    /// let path = std::env::temp_dir().join("anyfs-open-flags-doc.txt");
+    /// let file = OpenFlags::WRITE.to_std().open(&path).unwrap();
+    /// # drop(file);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn to_std(&self) -> std::fs::OpenOptions {
+        let mut options = std::fs::OpenOptions::new();
+        options
+            .read(self.read)
+            .write(self.write)
+            .append(self.append)
+            .truncate(self.truncate && !self.create_new)
+            .create(self.create)
+            .create_new(self.create_new);
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(mode.mode());
+        }
+        options
+    }
 }
 
-impl OpenFlags {
-    /// Read-only access.
-    pub const READ: Self = Self {
-        read: true,
-        write: false,
-        create: false,
-        truncate: false,
-        append: false,
-    };
+/// Builder for [`OpenFlags`] with the API and rules of
+/// [`std::fs::OpenOptions`].
+///
+/// std's options are opaque, so they can't be converted directly; porting
+/// code swaps the type instead and keeps its method chain. As in std,
+/// `append` implies `write`, and [`flags`](Self::flags) rejects
+/// combinations std rejects:
+///
+/// - no access mode (`read`, `write` or `append`)
+/// - `truncate`, `create` or `create_new` without `write` or `append`
+/// - `truncate` with `append`, unless `create_new` is set
+///
+/// `create_new` overrides `create` and `truncate`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsError, FsHandles, Handle, OpenOptions};
+/// use std::path::Path;
+///
+/// fn create_lock_file<B: FsHandles>(backend: &B, path: &Path) -> Result<Handle, FsError> {
+///     OpenOptions::new()
+///         .write(true)
+///         .create_new(true)
+///         .mode(0o600)
+///         .open(backend, path)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+    mode: Option<Permissions>,
+}
+
+impl OpenOptions {
+    /// Options with everything off, as [`std::fs::OpenOptions::new`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open for reading.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    /// Open for writing.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Open for appending; implies `write`.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    /// Truncate an existing file to zero length.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Create the file if it doesn't exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Create the file, failing if it exists.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
 
-    /// Write access with create and truncate.
-    pub const WRITE: Self = Self {
-        read: false,
-        write: true,
-        create: true,
-        truncate: true,
-        append: false,
-    };
+    /// Permission bits for a created file, as
+    /// `std::os::unix::fs::OpenOptionsExt::mode`.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(Permissions::from_mode(mode));
+        self
+    }
 
-    /// Read and write access.
-    pub const READ_WRITE: Self = Self {
-        read: true,
-        write: true,
-        create: false,
-        truncate: false,
-        append: false,
-    };
+    /// The equivalent [`OpenFlags`].
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidData`](crate::FsError::InvalidData) (`EINVAL`,
+    ///   std's `InvalidInput`) for the combinations listed on the type;
+    ///   the error's path is empty
+    pub fn flags(&self) -> Result<OpenFlags, crate::FsError> {
+        self.checked(std::path::Path::new(""))
+    }
 
-    /// Append mode - writes go to end of file.
-    pub const APPEND: Self = Self {
-        read: false,
-        write: true,
-        create: true,
-        truncate: false,
-        append: true,
-    };
+    /// Open `path` on `backend` with these options.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::InvalidData`](crate::FsError::InvalidData) as for
+    ///   [`flags`](Self::flags)
+    /// - Errors from [`FsHandles::open`](crate::FsHandles::open)
+    pub fn open<B: crate::FsHandles + ?Sized>(
+        &self,
+        backend: &B,
+        path: &std::path::Path,
+    ) -> Result<crate::Handle, crate::FsError> {
+        backend.open(path, self.checked(path)?)
+    }
+
+    fn checked(&self, path: &std::path::Path) -> Result<OpenFlags, crate::FsError> {
+        let invalid = |details: &str| crate::FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: format!("open: {details}"),
+        };
+        let writes = self.write || self.append;
+        if !self.read && !writes {
+            return Err(invalid("no access mode"));
+        }
+        if !writes && (self.truncate || self.create || self.create_new) {
+            return Err(invalid("truncate or create without write access"));
+        }
+        if self.append && self.truncate && !self.create_new {
+            return Err(invalid("truncate with append"));
+        }
+        Ok(OpenFlags {
+            read: self.read,
+            write: writes,
+            create: self.create || self.create_new,
+            truncate: self.truncate && !self.create_new,
+            append: self.append,
+            create_new: self.create_new,
+            mode: self.mode,
+        })
+    }
+}
+
+impl From<OpenFlags> for OpenOptions {
+    fn from(flags: OpenFlags) -> Self {
+        Self {
+            read: flags.read,
+            write: flags.write,
+            append: flags.append,
+            truncate: flags.truncate,
+            create: flags.create,
+            create_new: flags.create_new,
+            mode: flags.mode,
+        }
+    }
 }
 
 /// Type of file lock.
@@ -616,9 +3071,255 @@ pub enum LockType {
     Exclusive,
 }
 
+/// Opaque owner of record locks.
+///
+/// Used with [`FsLock::lock_owned`](crate::FsLock::lock_owned). Locks held by
+/// the same owner never conflict with each other, whichever handle they were
+/// taken through. Adapters pass through what their protocol provides: FUSE's
+/// `lock_owner`, an NFS lock owner, or a process id.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::LockOwner;
+///
+/// let owner = LockOwner(std::process::id() as u64);
+/// assert_eq!(owner, LockOwner(owner.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockOwner(pub u64);
+
+/// Byte range covered by a record lock.
+///
+/// Follows POSIX `fcntl`: a `len` of 0 extends the range to the end of the
+/// file, however large it grows.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::LockRange;
+///
+/// let header = LockRange::new(0, 512);
+/// assert!(header.overlaps(LockRange::WHOLE_FILE));
+/// assert!(!header.overlaps(LockRange::new(512, 0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockRange {
+    /// First byte of the range.
+    pub start: u64,
+    /// Number of bytes, or 0 for "to the end of the file".
+    pub len: u64,
+}
+
+impl LockRange {
+    /// The whole file, however large it grows.
+    pub const WHOLE_FILE: LockRange = LockRange { start: 0, len: 0 };
+
+    /// A range of `len` bytes starting at `start`.
+    pub const fn new(start: u64, len: u64) -> Self {
+        Self { start, len }
+    }
+
+    /// One past the last byte, or `None` if the range runs to the end of
+    /// the file.
+    pub fn end(&self) -> Option<u64> {
+        match self.len {
+            0 => None,
+            len => Some(self.start.saturating_add(len)),
+        }
+    }
+
+    /// Returns `true` if the two ranges share at least one byte.
+    pub fn overlaps(&self, other: LockRange) -> bool {
+        let before = |a: &LockRange, b: &LockRange| matches!(a.end(), Some(end) if end <= b.start);
+        !before(self, &other) && !before(&other, self)
+    }
+}
+
+/// Type of caching lease.
+///
+/// Used with [`FsLease::acquire_lease`](crate::FsLease::acquire_lease). A
+/// lease lets a client cache file contents until the backend breaks it.
+///
+/// | Held Lease | Read Request | Write Request |
+/// |------------|--------------|---------------|
+/// | None | ✓ Granted | ✓ Granted |
+/// | Read | ✓ Granted | ✗ Breaks the Read leases |
+/// | Write | ✗ Breaks to Read | ✗ Breaks the Write lease |
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::LeaseType;
+///
+/// assert!(LeaseType::Write.allows_write_caching());
+/// assert!(!LeaseType::Read.allows_write_caching());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LeaseType {
+    /// Read lease — the holder may cache reads.
+    ///
+    /// Many handles can hold a read lease on the same file. It is broken
+    /// when another handle writes or asks for a write lease.
+    Read,
+
+    /// Write lease — the holder may cache reads and buffer writes.
+    ///
+    /// Only one handle can hold it. It is broken when any other handle
+    /// opens, reads or writes the file.
+    Write,
+}
+
+impl LeaseType {
+    /// Returns `true` if the holder may delay writing dirty data back.
+    pub fn allows_write_caching(self) -> bool {
+        self == LeaseType::Write
+    }
+}
+
+/// `chattr`-style file flags.
+///
+/// Used with [`FsFileFlags`](crate::FsFileFlags) to protect files beyond
+/// what permission bits can express, e.g. for WORM/compliance storage.
+///
+/// | Flag | Effect | `chattr` |
+/// |------|--------|----------|
+/// | `immutable` | No modification, removal, rename, or new links | `+i` |
+/// | `append_only` | Only appending writes; no truncate, removal, or rename | `+a` |
+/// | `no_dump` | Excluded from backups (advisory) | `+d` |
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::FileFlags;
+///
+/// let flags = FileFlags { append_only: true, ..FileFlags::NONE };
+/// assert!(!flags.immutable);
+/// assert!(flags.protects_content());
+/// assert!(FileFlags::NONE.is_empty());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileFlags {
+    /// File cannot be modified, removed, renamed, or linked.
+    pub immutable: bool,
+    /// File can only be appended to.
+    pub append_only: bool,
+    /// File should be skipped by backup tools.
+    pub no_dump: bool,
+}
+
+impl FileFlags {
+    /// No flags set.
+    pub const NONE: Self = Self {
+        immutable: false,
+        append_only: false,
+        no_dump: false,
+    };
+
+    /// Immutable file.
+    pub const IMMUTABLE: Self = Self {
+        immutable: true,
+        append_only: false,
+        no_dump: false,
+    };
+
+    /// Append-only file.
+    pub const APPEND_ONLY: Self = Self {
+        immutable: false,
+        append_only: true,
+        no_dump: false,
+    };
+
+    /// Returns `true` if no flag is set.
+    pub const fn is_empty(self) -> bool {
+        !self.immutable && !self.append_only && !self.no_dump
+    }
+
+    /// Returns `true` if existing content must not be overwritten, truncated,
+    /// or removed (`immutable` or `append_only`).
+    pub const fn protects_content(self) -> bool {
+        self.immutable || self.append_only
+    }
+}
+
+/// Portable serde support for `Option<OsString>` (when serde feature is enabled).
+///
+/// Serde's own `OsString` encoding is tagged `Unix`/`Windows` and fails to
+/// deserialize on the other platform, so names are written as lossy UTF-8
+/// plus the native encoding of whichever platform wrote them.
+#[cfg(feature = "serde")]
+pub(crate) mod os_name_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::ffi::OsString;
+
+    #[derive(Serialize, Deserialize)]
+    struct Repr {
+        lossy: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unix: Option<Vec<u8>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        wide: Option<Vec<u16>>,
+    }
+
+    impl Repr {
+        fn new(name: &OsString) -> Self {
+            #[allow(unused_mut)]
+            let mut repr = Repr {
+                lossy: name.to_string_lossy().into_owned(),
+                unix: None,
+                wide: None,
+            };
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStrExt;
+                repr.unix = Some(name.as_bytes().to_vec());
+            }
+            #[cfg(windows)]
+            {
+                use std::os::windows::ffi::OsStrExt;
+                repr.wide = Some(name.encode_wide().collect());
+            }
+            repr
+        }
+
+        fn into_os_string(self) -> OsString {
+            #[cfg(unix)]
+            if let Some(bytes) = self.unix {
+                use std::os::unix::ffi::OsStringExt;
+                return OsString::from_vec(bytes);
+            }
+            #[cfg(windows)]
+            if let Some(wide) = self.wide {
+                use std::os::windows::ffi::OsStringExt;
+                return OsString::from_wide(&wide);
+            }
+            OsString::from(self.lossy)
+        }
+    }
+
+    pub fn serialize<S>(name: &Option<OsString>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        name.as_ref().map(Repr::new).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<OsString>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr: Option<Repr> = Deserialize::deserialize(deserializer)?;
+        Ok(repr.map(Repr::into_os_string))
+    }
+}
+
 /// Serde support for SystemTime (when serde feature is enabled).
 #[cfg(feature = "serde")]
-mod system_time_serde {
+pub(crate) mod system_time_serde {
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -637,12 +3338,219 @@ mod system_time_serde {
         let (secs, nanos): (u64, u32) = Deserialize::deserialize(deserializer)?;
         Ok(UNIX_EPOCH + Duration::new(secs, nanos))
     }
+
+    /// The same encoding for `Option<SystemTime>`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        pub fn serialize<S>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            time.map(|time| {
+                let duration = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+                (duration.as_secs(), duration.subsec_nanos())
+            })
+            .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let time: Option<(u64, u32)> = Deserialize::deserialize(deserializer)?;
+            Ok(time.map(|(secs, nanos)| UNIX_EPOCH + Duration::new(secs, nanos)))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn permissions_symbolic_round_trip() {
+        for mode in [0o000, 0o644, 0o755, 0o777, 0o4755, 0o2750, 0o1777, 0o7000] {
+            let perm = Permissions::from_mode(mode);
+            let parsed = Permissions::from_symbolic(&perm.to_symbolic()).unwrap();
+            assert_eq!(parsed, perm, "mode {mode:o}");
+        }
+    }
+
+    #[test]
+    fn permissions_from_symbolic_rejects_garbage() {
+        for bad in ["", "rwxrwxrw", "rwxrwxrwxx-", "xwrr--r--", "rw-r--r-s"] {
+            assert!(Permissions::from_symbolic(bad).is_err(), "{bad:?}");
+        }
+        assert!(Permissions::from_symbolic("rw-r--r-T").is_ok());
+    }
+
+    #[test]
+    fn permissions_from_symbolic_checks_the_type_char() {
+        for kind in ['-', 'd', 'l', 'p', 's', 'c', 'b'] {
+            let parsed = Permissions::from_symbolic(&format!("{kind}rwxr-x---")).unwrap();
+            assert_eq!(parsed.mode(), 0o750, "{kind:?}");
+        }
+        for bad in ["xrwxr-x---", "rrwxr-x---", "Drwxr-xr-x", "?rw-r--r--"] {
+            assert!(Permissions::from_symbolic(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dir_entry_keeps_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = OsStr::from_bytes(b"caf\xe9.txt");
+        let entry = DirEntry::default().with_name_os(raw);
+        assert!(entry.is_name_lossy());
+        assert_eq!(entry.file_name(), raw);
+        assert_eq!(entry.display_name(), "caf\u{fffd}.txt");
+    }
+
+    #[test]
+    fn std_conversions_match_host() {
+        let dir = std::env::temp_dir().join(format!("anyfs-types-std-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("f.txt"), b"hello").unwrap();
+
+        let meta = Metadata::try_from(std::fs::metadata(dir.join("f.txt")).unwrap()).unwrap();
+        assert!(meta.is_file());
+        assert_eq!(meta.size, 5);
+        assert!(meta.modified > SystemTime::UNIX_EPOCH);
+        assert!(Metadata::try_from(std::fs::metadata(&dir).unwrap())
+            .unwrap()
+            .is_dir());
+
+        let entries: Vec<DirEntry> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| DirEntry::from_std(&e.unwrap()).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "f.txt");
+        assert_eq!(entries[0].path, dir.join("f.txt"));
+        assert_eq!((entries[0].file_type, entries[0].size), (FileType::File, 5));
+        #[cfg(unix)]
+        assert_eq!(entries[0].inode, meta.inode);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn std_special_files_are_not_supported() {
+        let meta = std::fs::metadata("/dev/null").unwrap();
+        assert!(matches!(
+            Metadata::try_from(meta),
+            Err(crate::FsError::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn open_options_follow_std_rules() {
+        let flags = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .flags()
+            .unwrap();
+        assert_eq!(flags, OpenFlags::APPEND);
+        let flags = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create_new(true)
+            .mode(0o600)
+            .flags()
+            .unwrap();
+        assert!(flags.create && flags.create_new && !flags.truncate);
+        assert_eq!(flags.mode, Some(Permissions::from_mode(0o600)));
+        assert_eq!(
+            OpenOptions::from(OpenFlags::WRITE).flags().unwrap(),
+            OpenFlags::WRITE
+        );
+
+        for bad in [
+            OpenOptions::new(),
+            OpenOptions::new().read(true).create(true).clone(),
+            OpenOptions::new().read(true).truncate(true).clone(),
+            OpenOptions::new().append(true).truncate(true).clone(),
+        ] {
+            assert!(
+                matches!(bad.flags(), Err(crate::FsError::InvalidData { .. })),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn create_mode_default_matches_permission_defaults() {
+        let mode = CreateMode::default();
+        assert_eq!(mode.file_mode(), Permissions::default_file());
+        assert_eq!(mode.dir_mode(None), Permissions::default_dir());
+    }
+
+    #[test]
+    fn create_mode_setgid_inheritance_is_optional() {
+        let parent = Some(Permissions::from_mode(0o2770));
+        let mut mode =
+            CreateMode::new(Permissions::from_mode(0o660), Permissions::from_mode(0o770));
+        assert_eq!(mode.dir_mode(parent).mode(), 0o2770);
+        assert_eq!(mode.file_mode().mode(), 0o660);
+
+        mode.umask.inherit_setgid = false;
+        assert_eq!(mode.dir_mode(parent).mode(), 0o770);
+    }
+
+    #[test]
+    fn permissions_class_accessors_and_mutators() {
+        let mut perm = Permissions::from_mode(0o640);
+        assert_eq!(perm.owner().bits(), 6);
+        assert_eq!(perm.group().bits(), 4);
+        assert_eq!(perm.other().bits(), 0);
+
+        perm.set(Permissions::OTHER_READ);
+        perm.clear(Permissions::OWNER_WRITE);
+        assert_eq!(perm.mode(), 0o444);
+        assert!(perm.readonly());
+
+        perm.set(0o170000);
+        assert_eq!(perm.mode(), 0o444);
+    }
+
+    #[test]
+    fn content_tag_display_and_builders() {
+        let tag = ContentTag::new(TagAlgorithm::Other("xxh3".into()), "ff");
+        assert_eq!(tag.to_string(), "xxh3:ff");
+
+        let meta = Metadata::default().with_content_tag(ContentTag::etag("v1"));
+        assert_eq!(meta.content_tag, Some(ContentTag::etag("v1")));
+
+        let entry = DirEntry::default().with_content_tag(ContentTag::etag("v2"));
+        assert_eq!(entry.content_tag.unwrap().value, "v2");
+        assert!(Metadata::default().content_tag.is_none());
+    }
+
+    #[test]
+    fn metadata_changed_since_compares_counters() {
+        let before = Metadata::default().with_change_counter(7);
+        assert!(!before.changed_since(&before));
+        assert!(before.clone().with_change_counter(8).changed_since(&before));
+        // Without counters on both sides, assume a change
+        assert!(Metadata::default().changed_since(&before));
+        assert!(before.changed_since(&Metadata::default()));
+    }
+
+    #[test]
+    fn metadata_mask_contains() {
+        let size = MetadataMask {
+            size: true,
+            ..MetadataMask::NONE
+        };
+        assert!(MetadataMask::ALL.contains(size));
+        assert!(size.contains(MetadataMask::NONE));
+        assert!(!size.contains(MetadataMask::ALL));
+        assert!(!MetadataMask::NONE.contains(size));
+    }
+
     #[test]
     fn file_type_equality() {
         assert_eq!(FileType::File, FileType::File);
@@ -682,6 +3590,28 @@ mod tests {
         assert!(m.is_symlink());
     }
 
+    #[test]
+    fn lock_range_overlaps() {
+        let head = LockRange::new(0, 10);
+        assert!(head.overlaps(LockRange::new(9, 1)));
+        assert!(!head.overlaps(LockRange::new(10, 5)));
+        assert!(head.overlaps(LockRange::WHOLE_FILE));
+        assert!(LockRange::new(100, 0).overlaps(LockRange::new(1000, 1)));
+        assert!(!LockRange::new(100, 0).overlaps(LockRange::new(0, 100)));
+        assert_eq!(LockRange::new(u64::MAX, 2).end(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn metadata_is_fifo() {
+        let m = Metadata {
+            file_type: FileType::Fifo,
+            ..Default::default()
+        };
+        assert!(m.is_fifo());
+        assert!(!m.is_file());
+        assert!(!m.is_symlink());
+    }
+
     #[test]
     fn permissions_from_mode() {
         let p = Permissions::from_mode(0o755);
@@ -757,4 +3687,220 @@ mod tests {
         assert_eq!(Handle(42), Handle(42));
         assert_ne!(Handle(1), Handle(2));
     }
+
+    #[test]
+    fn handle_plain_value_has_generation_zero() {
+        let handle = Handle(42);
+        assert_eq!(handle.id(), 42);
+        assert_eq!(handle.generation(), 0);
+        assert_eq!(Handle::new(42, 0), handle);
+    }
+
+    #[test]
+    fn handle_packs_id_and_generation() {
+        let handle = Handle::new(7, 513);
+        assert_eq!(handle.id(), 7);
+        assert_eq!(handle.generation(), 513);
+    }
+
+    #[test]
+    fn handle_new_masks_oversized_id() {
+        let handle = Handle::new(u64::MAX, 1);
+        assert_eq!(handle.id(), Handle::MAX_ID);
+        assert_eq!(handle.generation(), 1);
+    }
+
+    #[test]
+    fn handle_next_generation_keeps_slot() {
+        let handle = Handle::new(9, 0);
+        let next = handle.next_generation();
+        assert!(handle.same_slot(next));
+        assert_ne!(handle, next);
+        assert_eq!(next.generation(), 1);
+    }
+
+    #[test]
+    fn handle_generation_wraps() {
+        let handle = Handle::new(9, u16::MAX).next_generation();
+        assert_eq!(handle.id(), 9);
+        assert_eq!(handle.generation(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    fn assert_round_trips<T>(value: &T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let json = serde_json::to_value(value).unwrap();
+        let back: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn every_public_type_round_trips_through_serde() {
+        let tag = ContentTag::new(TagAlgorithm::Other("blake3".into()), "ab12");
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 5);
+
+        assert_round_trips(&Metadata {
+            file_type: FileType::Symlink,
+            created: time,
+            content_tag: Some(tag.clone()),
+            change_counter: Some(3),
+            stored_size: Some(512),
+            storage_class: Some(StorageClass::Archive),
+            block_size: Some(4096),
+            optimal_io_size: Some(65536),
+            ..Metadata::default()
+        });
+        assert_round_trips(&DirEntry {
+            content_tag: Some(tag.clone()),
+            symlink_target: Some("/target".into()),
+            ..DirEntry::default()
+        });
+        assert_round_trips(&MetadataLite {
+            file_type: FileType::Fifo,
+            size: 1,
+            inode: 2,
+        });
+        assert_round_trips(&MetadataMask::default());
+        assert_round_trips(&CopyOptions {
+            overwrite: OverwritePolicy::Skip,
+            ..CopyOptions::default()
+        });
+        assert_round_trips(&WriteOptions {
+            compression: Some(CompressionHint::Best),
+            storage_class: Some(StorageClass::InfrequentAccess),
+        });
+        assert_round_trips(&ReadDirOptions::sorted());
+        assert_round_trips(&SetAttr {
+            mode: Some(Permissions::from_mode(0o600)),
+            mtime: Some(time),
+            ..SetAttr::default()
+        });
+        assert_round_trips(&UmaskPolicy::default());
+        assert_round_trips(&CreateMode::default());
+        assert_round_trips(&Rwx::default());
+        assert_round_trips(&Credentials::new(1000, 100).with_groups([27]));
+        assert_round_trips(&StatFs {
+            name_collision: NameCollisionPolicy::CaseFolding,
+            ..StatFs::default()
+        });
+        assert_round_trips(&UsageReport {
+            by_child: vec![("a".into(), 3)],
+            ..UsageReport::default()
+        });
+        assert_round_trips(&IoStats::default());
+        assert_round_trips(&CachePolicy {
+            ttl: Some(Duration::from_millis(1500)),
+            write_policy: WritePolicy::WriteBack,
+            ..CachePolicy::default()
+        });
+        assert_round_trips(&NotFoundCacheHint::For(Duration::from_secs(2)));
+        assert_round_trips(&CacheStats::default());
+        assert_round_trips(&MergePolicy::PreferBranch);
+        assert_round_trips(&MergeReport {
+            conflicts: vec!["/c".into()],
+            ..MergeReport::default()
+        });
+        assert_round_trips(&LayerInfo {
+            id: LayerId(1),
+            name: "upper".into(),
+            writable: true,
+        });
+        assert_round_trips(&HandleInfo {
+            handle: Handle::new(4, 2),
+            path: "/f".into(),
+            flags: OpenFlags {
+                create_new: true,
+                mode: Some(Permissions::from_mode(0o640)),
+                ..OpenFlags::default()
+            },
+            opened_at: time,
+            bytes_read: 1,
+            bytes_written: 2,
+        });
+        assert_round_trips(&DirHandle(8));
+        assert_round_trips(&FileId::from_inode(42));
+        assert_round_trips(&LeaseHint::new(Duration::from_secs(1), Duration::ZERO));
+        assert_round_trips(&OpenOptions::default());
+        assert_round_trips(&LockType::Exclusive);
+        assert_round_trips(&LockOwner(9));
+        assert_round_trips(&LockRange::new(10, 20));
+        assert_round_trips(&crate::LeaseBreak {
+            handle: Handle::new(1, 0),
+            to: Some(LeaseType::Read),
+        });
+        assert_round_trips(&FileFlags::default());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn reads_values_written_before_fields_were_added() {
+        let meta: Metadata = serde_json::from_str(
+            r#"{"file_type":"File","size":5,"permissions":420,
+                "created":[0,0],"modified":[0,0],"accessed":[0,0],
+                "inode":7,"nlink":1}"#,
+        )
+        .unwrap();
+        assert_eq!(meta.size, 5);
+        assert_eq!(meta.content_tag, None);
+        assert_eq!(meta.optimal_io_size, None);
+        assert_eq!(meta.uid, None);
+
+        let entry: DirEntry = serde_json::from_str(
+            r#"{"name":"a","path":"/a","file_type":"Directory","size":0,"inode":2}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.file_type, FileType::Directory);
+        assert_eq!(entry.symlink_target, None);
+
+        let flags: OpenFlags = serde_json::from_str(
+            r#"{"read":true,"write":false,"create":false,"truncate":false,"append":false}"#,
+        )
+        .unwrap();
+        assert!(flags.read && !flags.create_new);
+        assert_eq!(flags.mode, None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn ignores_fields_written_by_newer_versions() {
+        let entry: DirEntry = serde_json::from_str(
+            r#"{"name":"a","path":"/a","file_type":"File","size":1,"inode":2,
+                "added_in_a_later_version":{"x":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.name, "a");
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", unix))]
+    fn non_utf8_names_serialize_portably() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = OsStr::from_bytes(b"caf\xe9.txt");
+        let entry = DirEntry::default().with_name_os(raw);
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["name_os"]["lossy"], "caf\u{fffd}.txt");
+        assert_eq!(
+            json["name_os"]["unix"],
+            serde_json::json!([99, 97, 102, 0xe9, 46, 116, 120, 116])
+        );
+
+        let back: DirEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(back.file_name(), raw);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn names_from_another_platform_fall_back_to_lossy_text() {
+        let entry: DirEntry = serde_json::from_str(
+            r#"{"name":"caf\ufffd.txt","path":"/caf\ufffd.txt","file_type":"File",
+                "size":1,"inode":2,"name_os":{"lossy":"caf\ufffd.txt","other":[1]}}"#,
+        )
+        .unwrap();
+        assert!(entry.is_name_lossy());
+        assert_eq!(entry.file_name(), "caf\u{fffd}.txt");
+    }
 }
@@ -0,0 +1,357 @@
+//! Stub impls for features a backend lacks.
+//!
+//! The composite traits ([`FsFull`](crate::FsFull), [`FsFuse`](crate::FsFuse),
+//! [`FsPosix`](crate::FsPosix)) are all-or-nothing: a backend without
+//! symlinks could not be used where `FsFull` is required at all. The
+//! [`impl_unsupported!`](crate::impl_unsupported) macro implements the
+//! missing component traits with methods that fail with
+//! [`FsError::NotSupported`](crate::FsError::NotSupported), so the backend
+//! reaches the higher layer and reports the gap per call instead.
+
+/// Implement component traits whose every method fails with
+/// [`FsError::NotSupported`].
+///
+/// Takes `impl Trait for T;` items, in any order and combination, for
+/// [`FsLink`], [`FsPermissions`], [`FsSync`], [`FsStats`], [`FsInode`],
+/// [`FsHandles`], [`FsLock`] and [`FsXattr`]. Only required methods are
+/// generated; provided methods keep their defaults, which already report
+/// `NotSupported` or fall back to a stub. `T` must be a concrete type.
+///
+/// For backends that exist but can't be modified, prefer
+/// [`impl_read_only!`](crate::impl_read_only), which reports
+/// [`FsError::ReadOnly`](crate::FsError::ReadOnly) instead.
+///
+/// [`FsError::NotSupported`]: crate::FsError::NotSupported
+/// [`FsLink`]: crate::FsLink
+/// [`FsPermissions`]: crate::FsPermissions
+/// [`FsSync`]: crate::FsSync
+/// [`FsStats`]: crate::FsStats
+/// [`FsInode`]: crate::FsInode
+/// [`FsHandles`]: crate::FsHandles
+/// [`FsLock`]: crate::FsLock
+/// [`FsXattr`]: crate::FsXattr
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{impl_unsupported, FsError, FsFull};
+/// use std::path::Path;
+///
+/// /// A flat key-value store: files only, no links, stats or sync.
+/// struct Store;
+///
+/// // `Fs` and `FsPermissions` are implemented for real.
+/// # use anyfs_backend::{impl_read_only, Metadata, ReadDirIter};
+/// # impl anyfs_backend::FsRead for Store {
+/// #     fn read(&self, p: &Path) -> Result<Vec<u8>, FsError> { Err(FsError::not_found("read", p)) }
+/// #     fn read_to_string(&self, p: &Path) -> Result<String, FsError> { Err(FsError::not_found("read", p)) }
+/// #     fn read_range(&self, p: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> { Err(FsError::not_found("read", p)) }
+/// #     fn exists(&self, _: &Path) -> Result<bool, FsError> { Ok(false) }
+/// #     fn metadata(&self, p: &Path) -> Result<Metadata, FsError> { Err(FsError::not_found("metadata", p)) }
+/// #     fn open_read(&self, p: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> { Err(FsError::not_found("read", p)) }
+/// # }
+/// # impl_read_only! {
+/// #     impl FsWrite for Store;
+/// #     impl FsPermissions for Store;
+/// #     impl FsDir for Store {
+/// #         fn read_dir(&self, p: &Path) -> Result<ReadDirIter, FsError> { Err(FsError::not_found("read_dir", p)) }
+/// #     }
+/// # }
+///
+/// impl_unsupported! {
+///     impl FsLink for Store;
+///     impl FsSync for Store;
+///     impl FsStats for Store;
+/// }
+///
+/// fn needs_full<B: FsFull>(backend: &B) -> Result<(), FsError> {
+///     backend.symlink(Path::new("/target"), Path::new("/link"))
+/// }
+///
+/// assert!(matches!(
+///     needs_full(&Store),
+///     Err(FsError::NotSupported { operation: "symlink" })
+/// ));
+/// ```
+#[macro_export]
+macro_rules! impl_unsupported {
+    () => {};
+    (@stub $operation:literal) => {
+        ::std::result::Result::Err($crate::FsError::NotSupported { operation: $operation })
+    };
+    (impl FsLink for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsLink for $ty {
+            fn symlink(
+                &self,
+                _: &::std::path::Path,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "symlink")
+            }
+
+            fn hard_link(
+                &self,
+                _: &::std::path::Path,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "hard_link")
+            }
+
+            fn read_link(
+                &self,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<::std::path::PathBuf, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "read_link")
+            }
+
+            fn symlink_metadata(
+                &self,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<$crate::Metadata, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "symlink_metadata")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsPermissions for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsPermissions for $ty {
+            fn set_permissions(
+                &self,
+                _: &::std::path::Path,
+                _: $crate::Permissions,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "set_permissions")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsSync for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsSync for $ty {
+            fn sync(&self) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "sync")
+            }
+
+            fn fsync(&self, _: &::std::path::Path) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "fsync")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsStats for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsStats for $ty {
+            fn statfs(&self) -> ::std::result::Result<$crate::StatFs, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "statfs")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsInode for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsInode for $ty {
+            fn path_to_inode(&self, _: &::std::path::Path) -> ::std::result::Result<u64, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "path_to_inode")
+            }
+
+            fn inode_to_path(&self, _: u64) -> ::std::result::Result<::std::path::PathBuf, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "inode_to_path")
+            }
+
+            fn lookup(&self, _: u64, _: &::std::ffi::OsStr) -> ::std::result::Result<u64, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "lookup")
+            }
+
+            fn metadata_by_inode(&self, _: u64) -> ::std::result::Result<$crate::Metadata, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "metadata_by_inode")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsHandles for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsHandles for $ty {
+            fn open(
+                &self,
+                _: &::std::path::Path,
+                _: $crate::OpenFlags,
+            ) -> ::std::result::Result<$crate::Handle, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "open")
+            }
+
+            fn read_at(
+                &self,
+                _: $crate::Handle,
+                _: &mut [u8],
+                _: u64,
+            ) -> ::std::result::Result<usize, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "read_at")
+            }
+
+            fn write_at(
+                &self,
+                _: $crate::Handle,
+                _: &[u8],
+                _: u64,
+            ) -> ::std::result::Result<usize, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "write_at")
+            }
+
+            fn close(&self, _: $crate::Handle) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "close")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsLock for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsLock for $ty {
+            fn lock(
+                &self,
+                _: $crate::Handle,
+                _: $crate::LockType,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "lock")
+            }
+
+            fn try_lock(
+                &self,
+                _: $crate::Handle,
+                _: $crate::LockType,
+            ) -> ::std::result::Result<bool, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "try_lock")
+            }
+
+            fn unlock(&self, _: $crate::Handle) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "unlock")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+    (impl FsXattr for $ty:ty; $($rest:tt)*) => {
+        impl $crate::FsXattr for $ty {
+            fn get_xattr(
+                &self,
+                _: &::std::path::Path,
+                _: &str,
+            ) -> ::std::result::Result<::std::vec::Vec<u8>, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "get_xattr")
+            }
+
+            fn set_xattr(
+                &self,
+                _: &::std::path::Path,
+                _: &str,
+                _: &[u8],
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "set_xattr")
+            }
+
+            fn remove_xattr(
+                &self,
+                _: &::std::path::Path,
+                _: &str,
+            ) -> ::std::result::Result<(), $crate::FsError> {
+                $crate::impl_unsupported!(@stub "remove_xattr")
+            }
+
+            fn list_xattr(
+                &self,
+                _: &::std::path::Path,
+            ) -> ::std::result::Result<::std::vec::Vec<::std::string::String>, $crate::FsError> {
+                $crate::impl_unsupported!(@stub "list_xattr")
+            }
+        }
+
+        $crate::impl_unsupported!($($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Fs, FsError, FsHandles, FsInode, FsLink, FsLock, FsPermissions, FsPosix, FsRead, FsStats,
+        FsSync, FsWrite, FsXattr, Handle, LockType, OpenFlags, ReadDirIter,
+    };
+    use std::path::Path;
+
+    struct Plain;
+
+    // A bare `Fs` whose own operations are all not found.
+    impl FsRead for Plain {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            Err(FsError::not_found("read", path))
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            Err(FsError::not_found("read_to_string", path))
+        }
+        fn read_range(&self, path: &Path, _: u64, _: usize) -> Result<Vec<u8>, FsError> {
+            Err(FsError::not_found("read_range", path))
+        }
+        fn exists(&self, _: &Path) -> Result<bool, FsError> {
+            Ok(false)
+        }
+        fn metadata(&self, path: &Path) -> Result<crate::Metadata, FsError> {
+            Err(FsError::not_found("metadata", path))
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn std::io::Read + Send>, FsError> {
+            Err(FsError::not_found("open_read", path))
+        }
+    }
+
+    crate::impl_read_only! {
+        impl FsWrite for Plain;
+        impl FsDir for Plain {
+            fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+                Err(FsError::not_found("read_dir", path))
+            }
+        }
+    }
+
+    crate::impl_unsupported! {
+        impl FsLink for Plain;
+        impl FsPermissions for Plain;
+        impl FsSync for Plain;
+        impl FsStats for Plain;
+        impl FsInode for Plain;
+        impl FsHandles for Plain;
+        impl FsLock for Plain;
+        impl FsXattr for Plain;
+    }
+
+    fn operation<T>(result: Result<T, FsError>) -> &'static str {
+        match result {
+            Err(FsError::NotSupported { operation }) => operation,
+            _ => panic!("expected NotSupported"),
+        }
+    }
+
+    fn as_posix<B: FsPosix>(backend: &B) -> &B {
+        backend
+    }
+
+    #[test]
+    fn stubs_reach_fs_posix_and_report_not_supported() {
+        let fs = as_posix(&Plain);
+        let p = Path::new("/a");
+        assert_eq!(operation(fs.symlink(p, p)), "symlink");
+        assert_eq!(
+            operation(fs.set_permissions(p, crate::Permissions::default_file())),
+            "set_permissions"
+        );
+        assert_eq!(operation(fs.fdatasync(p)), "fsync");
+        assert_eq!(operation(fs.statfs()), "statfs");
+        assert_eq!(operation(fs.path_to_inode(p)), "path_to_inode");
+        assert_eq!(operation(fs.open(p, OpenFlags::READ)), "open");
+        assert_eq!(operation(fs.dup(Handle(1))), "dup");
+        assert_eq!(
+            operation(fs.try_lock(Handle(1), LockType::Shared)),
+            "try_lock"
+        );
+        assert_eq!(operation(fs.list_xattr(p)), "list_xattr");
+        assert!(matches!(fs.write(p, b""), Err(FsError::ReadOnly { .. })));
+        let _: &dyn Fs = fs;
+    }
+}
@@ -0,0 +1,577 @@
+//! # `vfs` Crate Interop
+//!
+//! Adapters between AnyFS backends and the [`vfs`] crate's
+//! [`vfs::FileSystem`] trait, so code on either side can be
+//! migrated piece by piece (requires the `vfs` feature).
+//!
+//! | Adapter | Direction |
+//! |---------|-----------|
+//! | [`AnyfsAsVfs`] | AnyFS [`Fs`] → `vfs::FileSystem`, usable as a `vfs::VfsPath` |
+//! | [`VfsAsAnyfs`] | `vfs::FileSystem` → AnyFS [`Fs`] |
+//!
+//! ## Paths
+//!
+//! `vfs` paths are UTF-8 strings with `""` as the root. AnyFS `/` maps to
+//! `""`; paths are lexically normalized before they cross over, and non-UTF-8
+//! paths fail with [`FsError::InvalidData`].
+//!
+//! ## Errors
+//!
+//! | AnyFS | `vfs` |
+//! |-------|-------|
+//! | [`FsError::NotFound`] | `FileNotFound` |
+//! | [`FsError::AlreadyExists`] | `FileExists` (either `…Exists` on the way back) |
+//! | [`FsError::NotSupported`] | `NotSupported` |
+//! | [`FsError::InvalidData`] | `InvalidPath` on the way back |
+//! | [`FsError::Backend`] | `Other` on the way back |
+//! | Anything else | `IoError`, through the [`std::io::Error`] conversion |
+
+use std::fmt;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use vfs::error::VfsErrorKind;
+use vfs::{FileSystem, SeekAndRead, VfsError, VfsFileType, VfsMetadata, VfsResult};
+
+use crate::path_resolver::normalize;
+use crate::{DirEntry, FileType, Fs, FsDir, FsError, FsRead, FsWrite, Metadata, ReadDirIter};
+
+// =============================================================================
+// Error mapping
+// =============================================================================
+
+fn to_vfs(error: FsError) -> VfsError {
+    let kind = match error {
+        FsError::NotFound { .. } => VfsErrorKind::FileNotFound,
+        FsError::AlreadyExists { .. } => VfsErrorKind::FileExists,
+        FsError::NotSupported { .. } => VfsErrorKind::NotSupported,
+        other => VfsErrorKind::IoError(other.into()),
+    };
+    kind.into()
+}
+
+fn from_vfs(error: VfsError, operation: &'static str, path: &Path) -> FsError {
+    match error.kind() {
+        VfsErrorKind::FileNotFound => FsError::not_found(operation, path),
+        VfsErrorKind::FileExists | VfsErrorKind::DirectoryExists => {
+            FsError::already_exists(operation, path)
+        }
+        VfsErrorKind::NotSupported => FsError::NotSupported { operation },
+        VfsErrorKind::InvalidPath => FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: error.to_string(),
+        },
+        VfsErrorKind::Other(message) => FsError::Backend(message.clone()),
+        VfsErrorKind::IoError(source) => {
+            let copy = match source.raw_os_error() {
+                Some(code) => io::Error::from_raw_os_error(code),
+                None => io::Error::new(source.kind(), source.to_string()),
+            };
+            FsError::io(operation, path, copy)
+        }
+    }
+}
+
+// =============================================================================
+// AnyFS → vfs
+// =============================================================================
+
+/// Exposes an AnyFS backend as a [`vfs::FileSystem`].
+///
+/// - `open_file` reads the whole file up front, since `vfs` readers must
+///   seek and [`Fs`] streams can't.
+/// - `append_file` buffers and calls [`FsWrite::append`] on each flush and
+///   on drop; errors on drop are lost, so flush before dropping.
+/// - Copies and moves map to [`FsWrite::copy`] and [`FsWrite::rename`].
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{AnyfsAsVfs, FsBuilder, Metadata};
+///
+/// let backend = FsBuilder::new()
+///     .on_metadata(|_| Ok(Metadata::default()))
+///     .on_read(|_| Ok(b"hello".to_vec()))
+///     .build();
+/// let root: vfs::VfsPath = AnyfsAsVfs::new(backend).into();
+/// assert_eq!(root.join("greeting.txt").unwrap().read_to_string().unwrap(), "hello");
+/// ```
+pub struct AnyfsAsVfs<B> {
+    inner: Arc<B>,
+}
+
+impl<B> AnyfsAsVfs<B> {
+    /// Adapt `inner`.
+    pub fn new(inner: B) -> Self {
+        Self::from_arc(Arc::new(inner))
+    }
+
+    /// Adapt a backend that is also used directly.
+    pub fn from_arc(inner: Arc<B>) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &Arc<B> {
+        &self.inner
+    }
+}
+
+impl<B> fmt::Debug for AnyfsAsVfs<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyfsAsVfs").finish_non_exhaustive()
+    }
+}
+
+/// AnyFS path for a `vfs` path.
+fn anyfs_path(path: &str) -> PathBuf {
+    if path.is_empty() {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+impl<B: Fs + 'static> FileSystem for AnyfsAsVfs<B> {
+    fn read_dir(&self, path: &str) -> VfsResult<Box<dyn Iterator<Item = String> + Send>> {
+        let names = self
+            .inner
+            .read_dir(&anyfs_path(path))
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|e| e.name))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(to_vfs)?;
+        Ok(Box::new(names.into_iter()))
+    }
+
+    fn create_dir(&self, path: &str) -> VfsResult<()> {
+        self.inner.create_dir(&anyfs_path(path)).map_err(to_vfs)
+    }
+
+    fn open_file(&self, path: &str) -> VfsResult<Box<dyn SeekAndRead + Send>> {
+        let data = self.inner.read(&anyfs_path(path)).map_err(to_vfs)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn create_file(&self, path: &str) -> VfsResult<Box<dyn Write + Send>> {
+        self.inner.open_write(&anyfs_path(path)).map_err(to_vfs)
+    }
+
+    fn append_file(&self, path: &str) -> VfsResult<Box<dyn Write + Send>> {
+        let path = anyfs_path(path);
+        if !self.inner.exists(&path).map_err(to_vfs)? {
+            return Err(VfsErrorKind::FileNotFound.into());
+        }
+        Ok(Box::new(AppendWriter {
+            backend: Arc::clone(&self.inner),
+            path,
+            pending: Vec::new(),
+        }))
+    }
+
+    fn metadata(&self, path: &str) -> VfsResult<VfsMetadata> {
+        let meta = self.inner.metadata(&anyfs_path(path)).map_err(to_vfs)?;
+        Ok(VfsMetadata {
+            file_type: match meta.file_type {
+                FileType::Directory => VfsFileType::Directory,
+                _ => VfsFileType::File,
+            },
+            len: meta.size,
+        })
+    }
+
+    fn exists(&self, path: &str) -> VfsResult<bool> {
+        self.inner.exists(&anyfs_path(path)).map_err(to_vfs)
+    }
+
+    fn remove_file(&self, path: &str) -> VfsResult<()> {
+        self.inner.remove_file(&anyfs_path(path)).map_err(to_vfs)
+    }
+
+    fn remove_dir(&self, path: &str) -> VfsResult<()> {
+        self.inner.remove_dir(&anyfs_path(path)).map_err(to_vfs)
+    }
+
+    fn copy_file(&self, src: &str, dest: &str) -> VfsResult<()> {
+        self.inner
+            .copy(&anyfs_path(src), &anyfs_path(dest))
+            .map_err(to_vfs)
+    }
+
+    fn move_file(&self, src: &str, dest: &str) -> VfsResult<()> {
+        self.inner
+            .rename(&anyfs_path(src), &anyfs_path(dest))
+            .map_err(to_vfs)
+    }
+
+    fn move_dir(&self, src: &str, dest: &str) -> VfsResult<()> {
+        self.inner
+            .rename(&anyfs_path(src), &anyfs_path(dest))
+            .map_err(to_vfs)
+    }
+}
+
+/// Buffers appended bytes and hands them to [`FsWrite::append`] on flush.
+struct AppendWriter<B: Fs> {
+    backend: Arc<B>,
+    path: PathBuf,
+    pending: Vec<u8>,
+}
+
+impl<B: Fs> Write for AppendWriter<B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            self.backend.append(&self.path, &self.pending)?;
+            self.pending.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<B: Fs> Drop for AppendWriter<B> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// =============================================================================
+// vfs → AnyFS
+// =============================================================================
+
+/// Exposes a [`vfs::FileSystem`] as an AnyFS [`Fs`].
+///
+/// `vfs` has fewer operations than [`Fs`]; the rest are built from them:
+///
+/// - `read_dir` stats every child to fill in [`DirEntry`] types and sizes
+/// - `copy` falls back to read + write when the file system has no
+///   `copy_file`
+/// - `rename` picks `move_dir` or `move_file` from the source's type
+/// - `truncate` rewrites the file
+/// - `create_dir_all` and `remove_dir_all` walk the tree
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{FsRead, FsWrite, VfsAsAnyfs};
+/// use std::path::Path;
+///
+/// let fs = VfsAsAnyfs::new(vfs::MemoryFS::new());
+/// fs.write(Path::new("/notes.txt"), b"hi").unwrap();
+/// assert_eq!(fs.read(Path::new("/notes.txt")).unwrap(), b"hi");
+/// ```
+#[derive(Debug)]
+pub struct VfsAsAnyfs<F> {
+    inner: F,
+}
+
+impl<F> VfsAsAnyfs<F> {
+    /// Adapt `inner`.
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped file system.
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    /// Unwrap the file system.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+/// `vfs` path for an AnyFS path.
+fn vfs_path(path: &Path) -> Result<String, FsError> {
+    let normalized = normalize(path);
+    let text = normalized.to_str().ok_or_else(|| FsError::InvalidData {
+        path: path.to_path_buf(),
+        details: "vfs paths must be UTF-8".into(),
+    })?;
+    Ok(match text.trim_end_matches('/') {
+        "" => String::new(),
+        rest if rest.starts_with('/') => rest.to_string(),
+        rest => format!("/{rest}"),
+    })
+}
+
+/// Forwards reads, hiding the `Seek` half of a `vfs` reader.
+struct VfsReader(Box<dyn SeekAndRead + Send>);
+
+impl Read for VfsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<F: FileSystem> VfsAsAnyfs<F> {
+    /// Call `f` with the `vfs` form of `path`, mapping its error.
+    fn with<T>(
+        &self,
+        operation: &'static str,
+        path: &Path,
+        f: impl FnOnce(&F, &str) -> VfsResult<T>,
+    ) -> Result<T, FsError> {
+        let vfs = vfs_path(path)?;
+        f(&self.inner, &vfs).map_err(|e| from_vfs(e, operation, path))
+    }
+
+    fn open(&self, operation: &'static str, path: &Path) -> Result<VfsReader, FsError> {
+        self.with(operation, path, |fs, p| fs.open_file(p))
+            .map(VfsReader)
+    }
+
+    fn write_new(&self, operation: &'static str, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        let mut file = self.with(operation, path, |fs, p| fs.create_file(p))?;
+        file.write_all(data)
+            .and_then(|()| file.flush())
+            .map_err(|e| FsError::io(operation, path, e))
+    }
+}
+
+impl<F: FileSystem> FsRead for VfsAsAnyfs<F> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+        let mut data = Vec::new();
+        self.open("read", path)?
+            .read_to_end(&mut data)
+            .map_err(|e| FsError::io("read", path, e))?;
+        Ok(data)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+        let data = self
+            .read(path)
+            .map_err(|e| e.with_operation("read_to_string"))?;
+        String::from_utf8(data).map_err(|e| FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: e.to_string(),
+        })
+    }
+
+    fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+        let mut reader = self.open("read_range", path)?;
+        let mut data = Vec::new();
+        reader
+            .0
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| reader.take(len as u64).read_to_end(&mut data))
+            .map_err(|e| FsError::io("read_range", path, e))?;
+        Ok(data)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, FsError> {
+        self.with("exists", path, |fs, p| fs.exists(p))
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+        let meta = self.with("metadata", path, |fs, p| fs.metadata(p))?;
+        Ok(Metadata {
+            file_type: match meta.file_type {
+                VfsFileType::Directory => FileType::Directory,
+                VfsFileType::File => FileType::File,
+            },
+            size: meta.len,
+            ..Metadata::default()
+        })
+    }
+
+    fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+        Ok(Box::new(self.open("open_read", path)?))
+    }
+}
+
+impl<F: FileSystem> FsWrite for VfsAsAnyfs<F> {
+    fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        self.write_new("write", path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+        if !self.exists(path)? {
+            return self.write_new("append", path, data);
+        }
+        let mut file = self.with("append", path, |fs, p| fs.append_file(p))?;
+        file.write_all(data)
+            .and_then(|()| file.flush())
+            .map_err(|e| FsError::io("append", path, e))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        self.with("remove_file", path, |fs, p| fs.remove_file(p))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let dest = vfs_path(to)?;
+        let is_dir = self.metadata(from)?.file_type == FileType::Directory;
+        self.with("rename", from, |fs, p| {
+            if is_dir {
+                fs.move_dir(p, &dest)
+            } else {
+                fs.move_file(p, &dest)
+            }
+        })
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+        let dest = vfs_path(to)?;
+        match self.with("copy", from, |fs, p| fs.copy_file(p, &dest)) {
+            Err(FsError::NotSupported { .. }) => {
+                let data = self.read(from).map_err(|e| e.with_operation("copy"))?;
+                self.write_new("copy", to, &data)
+            }
+            other => other,
+        }
+    }
+
+    fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        let mut data = self.read(path).map_err(|e| e.with_operation("truncate"))?;
+        let size = usize::try_from(size).map_err(|_| FsError::FileSizeExceeded {
+            path: path.to_path_buf(),
+            size,
+            limit: usize::MAX as u64,
+        })?;
+        data.resize(size, 0);
+        self.write_new("truncate", path, &data)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+        self.with("open_write", path, |fs, p| fs.create_file(p))
+    }
+}
+
+impl<F: FileSystem> FsDir for VfsAsAnyfs<F> {
+    fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+        let names = self.with("read_dir", path, |fs, p| fs.read_dir(p))?;
+        let base = normalize(path);
+        let entries = names
+            .map(|name| {
+                let child = base.join(&name);
+                let meta = self.metadata(&child)?;
+                Ok(DirEntry {
+                    name,
+                    path: child,
+                    file_type: meta.file_type,
+                    size: meta.size,
+                    ..DirEntry::default()
+                })
+            })
+            .collect();
+        Ok(ReadDirIter::from_vec(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.with("create_dir", path, |fs, p| fs.create_dir(p))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        let path = normalize(path);
+        let mut missing: Vec<&Path> = path
+            .ancestors()
+            .take_while(|dir| dir.parent().is_some())
+            .collect();
+        missing.reverse();
+        for dir in missing {
+            match self.metadata(dir) {
+                Ok(meta) if meta.file_type == FileType::Directory => {}
+                Ok(_) => return Err(FsError::not_a_directory("create_dir_all", dir)),
+                Err(FsError::NotFound { .. }) => {
+                    self.with("create_dir_all", dir, |fs, p| fs.create_dir(p))?;
+                }
+                Err(e) => return Err(e.with_operation("create_dir_all")),
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+        self.with("remove_dir", path, |fs, p| fs.remove_dir(p))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+        for entry in self.read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type == FileType::Directory {
+                self.remove_dir_all(&entry.path)?;
+            } else {
+                self.with("remove_dir_all", &entry.path, |fs, p| fs.remove_file(p))?;
+            }
+        }
+        self.with("remove_dir_all", path, |fs, p| fs.remove_dir(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FsExt;
+    use vfs::VfsPath;
+
+    #[test]
+    fn vfs_backend_works_as_anyfs() {
+        let fs = VfsAsAnyfs::new(vfs::MemoryFS::new());
+        fs.create_dir_all(Path::new("/a/b")).unwrap();
+        fs.write(Path::new("/a/b/f.txt"), b"hello").unwrap();
+        fs.append(Path::new("/a/b/f.txt"), b" world").unwrap();
+        assert_eq!(
+            fs.read_range(Path::new("/a/b/f.txt"), 6, 3).unwrap(),
+            b"wor"
+        );
+
+        fs.copy(Path::new("/a/b/f.txt"), Path::new("/a/g.txt"))
+            .unwrap();
+        fs.truncate(Path::new("/a/g.txt"), 2).unwrap();
+        assert_eq!(fs.read_to_string(Path::new("/a/g.txt")).unwrap(), "he");
+
+        let mut names: Vec<String> = fs
+            .read_dir(Path::new("/a"))
+            .unwrap()
+            .map(|e| e.unwrap().name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["b", "g.txt"]);
+        assert!(fs.is_dir(Path::new("/a/b")).unwrap());
+
+        fs.remove_dir_all(Path::new("/a")).unwrap();
+        assert!(!fs.exists(Path::new("/a")).unwrap());
+        assert!(matches!(
+            fs.read(Path::new("/a/g.txt")),
+            Err(FsError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn round_trip_through_both_adapters() {
+        let inner = VfsAsAnyfs::new(vfs::MemoryFS::new());
+        let root: VfsPath = AnyfsAsVfs::new(inner).into();
+
+        root.join("dir").unwrap().create_dir().unwrap();
+        let file = root.join("dir/file.txt").unwrap();
+        file.create_file().unwrap().write_all(b"one").unwrap();
+        {
+            let mut appender = file.append_file().unwrap();
+            appender.write_all(b", two").unwrap();
+            appender.flush().unwrap();
+        }
+        assert_eq!(file.read_to_string().unwrap(), "one, two");
+        assert_eq!(file.metadata().unwrap().len, 8);
+
+        let names: Vec<String> = root
+            .join("dir")
+            .unwrap()
+            .read_dir()
+            .unwrap()
+            .map(|p| p.filename())
+            .collect();
+        assert_eq!(names, ["file.txt"]);
+
+        let missing = root.join("nope").unwrap().read_to_string().unwrap_err();
+        assert!(matches!(missing.kind(), VfsErrorKind::FileNotFound));
+    }
+}
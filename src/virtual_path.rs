@@ -0,0 +1,237 @@
+//! # Virtual Path Components
+//!
+//! Backend paths are virtual: `/`-rooted, with no drive letters or UNC
+//! prefixes, on every host. [`std::path::Components`] follows the host
+//! instead, so on Windows `C:/data` starts with a prefix that has no meaning
+//! to a backend. [`VirtualComponents`] iterates a path the way backends see
+//! it, and [`virtual_depth`], [`virtual_ancestors`] and
+//! [`validate_virtual_path`] build on it.
+//! [`FsPath`](crate::FsPath) resolution uses the same rules.
+//!
+//! | Host component | Virtual component |
+//! |----------------|-------------------|
+//! | Root (`/`) | [`VirtualComponent::Root`] |
+//! | Prefix (`C:`, `\\?\`) | [`VirtualComponent::Normal`], an ordinary name; a root right after it is dropped |
+//! | `.` / `..` / names | Unchanged |
+
+use std::ffi::OsStr;
+use std::path::{Component, Components, Path, PathBuf};
+
+use crate::path_resolver::normalize;
+use crate::FsError;
+
+/// One component of a virtual path, from [`VirtualComponents`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VirtualComponent<'a> {
+    /// The root, `/`.
+    Root,
+    /// `.`, only kept at the start of a relative path.
+    CurDir,
+    /// `..`.
+    ParentDir,
+    /// A name.
+    Normal(&'a OsStr),
+}
+
+impl<'a> VirtualComponent<'a> {
+    /// The component as it would be written in a path.
+    pub fn as_os_str(self) -> &'a OsStr {
+        match self {
+            Self::Root => OsStr::new("/"),
+            Self::CurDir => OsStr::new("."),
+            Self::ParentDir => OsStr::new(".."),
+            Self::Normal(name) => name,
+        }
+    }
+}
+
+/// Iterator over the [`VirtualComponent`]s of a path.
+///
+/// Like [`Path::components`], repeated separators and interior `.` are
+/// skipped, but host prefixes are read as names.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{VirtualComponent, VirtualComponents};
+/// use std::ffi::OsStr;
+/// use std::path::Path;
+///
+/// let parts: Vec<_> = VirtualComponents::new(Path::new("/a//./b/..")).collect();
+/// assert_eq!(
+///     parts,
+///     [
+///         VirtualComponent::Root,
+///         VirtualComponent::Normal(OsStr::new("a")),
+///         VirtualComponent::Normal(OsStr::new("b")),
+///         VirtualComponent::ParentDir,
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualComponents<'a> {
+    inner: Components<'a>,
+    after_prefix: bool,
+}
+
+impl<'a> VirtualComponents<'a> {
+    /// Iterate the components of `path`.
+    pub fn new(path: &'a Path) -> Self {
+        Self {
+            inner: path.components(),
+            after_prefix: false,
+        }
+    }
+}
+
+impl<'a> Iterator for VirtualComponents<'a> {
+    type Item = VirtualComponent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let component = self.inner.next()?;
+            let after_prefix = std::mem::replace(&mut self.after_prefix, false);
+            return Some(match component {
+                Component::Prefix(prefix) => {
+                    self.after_prefix = true;
+                    VirtualComponent::Normal(prefix.as_os_str())
+                }
+                Component::RootDir if after_prefix => continue,
+                Component::RootDir => VirtualComponent::Root,
+                Component::CurDir => VirtualComponent::CurDir,
+                Component::ParentDir => VirtualComponent::ParentDir,
+                Component::Normal(name) => VirtualComponent::Normal(name),
+            });
+        }
+    }
+}
+
+/// Number of names in `path` after lexical normalization.
+///
+/// `.` and `..` are resolved textually, and `..` never climbs above `/`.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::virtual_depth;
+/// use std::path::Path;
+///
+/// assert_eq!(virtual_depth(Path::new("/")), 0);
+/// assert_eq!(virtual_depth(Path::new("/a/b/c")), 3);
+/// assert_eq!(virtual_depth(Path::new("/a/../../b")), 1);
+/// ```
+pub fn virtual_depth(path: &Path) -> usize {
+    VirtualComponents::new(&normalize(path))
+        .filter(|component| matches!(component, VirtualComponent::Normal(_)))
+        .count()
+}
+
+/// `path` and each of its ancestors after lexical normalization, deepest
+/// first, ending at `/` for absolute paths.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::virtual_ancestors;
+/// use std::path::{Path, PathBuf};
+///
+/// let all: Vec<PathBuf> = virtual_ancestors(Path::new("/a/./b/../c")).collect();
+/// assert_eq!(all, [Path::new("/a/c"), Path::new("/a"), Path::new("/")]);
+/// ```
+pub fn virtual_ancestors(path: &Path) -> impl Iterator<Item = PathBuf> {
+    std::iter::successors(Some(normalize(path)), |path| {
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    })
+}
+
+/// Check that `path` is a well-formed backend path.
+///
+/// A well-formed path is absolute (starts at the virtual root, so a host
+/// prefix such as `C:` doesn't count), contains no NUL byte, and never
+/// climbs above `/` with `..`. Backends differ in how they treat the
+/// alternatives: some resolve relative paths against `/`, some clamp `..`
+/// at the root, some truncate at the NUL. [`StrictPaths`](crate::StrictPaths)
+/// applies this check to every call.
+///
+/// # Errors
+///
+/// - [`FsError::InvalidData`] naming the first problem found
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{validate_virtual_path, FsError};
+/// use std::path::Path;
+///
+/// assert!(validate_virtual_path(Path::new("/a/../b")).is_ok());
+/// for bad in ["a/b", "/a/../..", "/a\0b", ""] {
+///     assert!(matches!(
+///         validate_virtual_path(Path::new(bad)),
+///         Err(FsError::InvalidData { .. })
+///     ));
+/// }
+/// ```
+pub fn validate_virtual_path(path: &Path) -> Result<(), FsError> {
+    let invalid = |details: &str| {
+        Err(FsError::InvalidData {
+            path: path.to_path_buf(),
+            details: details.to_string(),
+        })
+    };
+    if path.as_os_str().to_string_lossy().contains('\0') {
+        return invalid("path contains a NUL byte");
+    }
+    let mut components = VirtualComponents::new(path);
+    if components.next() != Some(VirtualComponent::Root) {
+        return invalid("path is not absolute");
+    }
+    let mut depth = 0usize;
+    for component in components {
+        match component {
+            VirtualComponent::Normal(_) => depth += 1,
+            VirtualComponent::ParentDir => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return invalid("path climbs above the root"),
+            },
+            VirtualComponent::Root | VirtualComponent::CurDir => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths_keep_leading_cur_dir() {
+        let parts: Vec<_> = VirtualComponents::new(Path::new("./a/../b/")).collect();
+        assert_eq!(
+            parts,
+            [
+                VirtualComponent::CurDir,
+                VirtualComponent::Normal(OsStr::new("a")),
+                VirtualComponent::ParentDir,
+                VirtualComponent::Normal(OsStr::new("b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn components_round_trip_through_as_os_str() {
+        let path = Path::new("/x/../y");
+        let rebuilt: PathBuf = VirtualComponents::new(path)
+            .map(VirtualComponent::as_os_str)
+            .collect();
+        assert_eq!(rebuilt, path);
+    }
+
+    #[test]
+    fn relative_ancestors_stop_at_first_name() {
+        let all: Vec<PathBuf> = virtual_ancestors(Path::new("a/b")).collect();
+        assert_eq!(all, [Path::new("a/b"), Path::new("a")]);
+        assert_eq!(virtual_depth(Path::new("a/b")), 2);
+    }
+}
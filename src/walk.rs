@@ -0,0 +1,218 @@
+//! # Recursive Directory Walker
+//!
+//! [`Walk`] iterates a directory tree depth-first using only
+//! [`FsRead::metadata`](crate::FsRead::metadata) and
+//! [`FsDir::read_dir`](crate::FsDir::read_dir), so it works on any [`Fs`]
+//! backend. Obtain one with [`FsExt::walk`](crate::FsExt::walk).
+//!
+//! ## Symlinks
+//!
+//! The walker never follows symlinks below the root: an entry is descended
+//! into only if `read_dir` reported it as [`FileType::Directory`]. Symlinks
+//! are yielded as entries of their own, so a tree operation built on the
+//! walker cannot escape the tree through a link.
+//!
+//! ## Order
+//!
+//! | Mode | Directory yielded | Use for |
+//! |------|-------------------|---------|
+//! | Default (pre-order) | Before its contents | Copying, listing |
+//! | [`contents_first`](Walk::contents_first) | After its contents | Removal, restricting permissions |
+//!
+//! Siblings come in whatever order the backend's `read_dir` returns them.
+
+use std::path::{Path, PathBuf};
+
+use crate::{DirEntry, FileType, Fs, FsError, ReadDirIter};
+
+/// An entry yielded by [`Walk`].
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    /// Depth below the walk root (the root itself is 0).
+    pub depth: usize,
+    /// The directory entry. For the root it is synthesized from metadata.
+    pub entry: DirEntry,
+}
+
+impl WalkEntry {
+    /// Full path to the entry.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.entry.path
+    }
+
+    /// Type of the entry (never followed through symlinks below the root).
+    #[inline]
+    pub fn file_type(&self) -> FileType {
+        self.entry.file_type
+    }
+}
+
+/// Open directory being iterated.
+struct Frame {
+    path: PathBuf,
+    iter: ReadDirIter,
+    depth: usize,
+    /// The directory itself, yielded when the frame is exhausted
+    /// (contents-first mode only).
+    dir: Option<WalkEntry>,
+}
+
+/// Depth-first iterator over a directory tree.
+///
+/// Yields the root first (or last, with [`contents_first`](Self::contents_first)),
+/// then every entry below it. An error reading one directory is yielded in
+/// place of that directory's contents and the walk continues with its
+/// siblings.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{Fs, FsExt, FsError, FileType};
+/// use std::path::Path;
+///
+/// fn total_size<B: Fs>(backend: &B) -> Result<u64, FsError> {
+///     let mut total = 0;
+///     for entry in backend.walk(Path::new("/data")) {
+///         let entry = entry?;
+///         if entry.file_type() == FileType::File {
+///             total += entry.entry.size;
+///         }
+///     }
+///     Ok(total)
+/// }
+/// ```
+pub struct Walk<'a, B: Fs + ?Sized> {
+    backend: &'a B,
+    root: Option<PathBuf>,
+    /// Directory to open on the next call (pre-order mode).
+    pending: Option<(PathBuf, usize)>,
+    stack: Vec<Frame>,
+    max_depth: usize,
+    contents_first: bool,
+}
+
+impl<'a, B: Fs + ?Sized> Walk<'a, B> {
+    /// Walk the tree rooted at `root`.
+    pub fn new(backend: &'a B, root: &Path) -> Self {
+        Self {
+            backend,
+            root: Some(root.to_path_buf()),
+            pending: None,
+            stack: Vec::new(),
+            max_depth: usize::MAX,
+            contents_first: false,
+        }
+    }
+
+    /// Don't descend below `depth` (0 yields only the root).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Yield each directory after its contents (post-order).
+    pub fn contents_first(mut self, yes: bool) -> Self {
+        self.contents_first = yes;
+        self
+    }
+
+    /// The directory currently being listed, if any.
+    ///
+    /// After an `Err` item this is the directory whose listing failed, which
+    /// lets callers attribute errors that don't carry a path.
+    pub fn current_dir(&self) -> Option<&Path> {
+        self.stack.last().map(|frame| frame.path.as_path())
+    }
+
+    fn root_entry(&self, root: PathBuf) -> Result<WalkEntry, FsError> {
+        let meta = self.backend.metadata(&root)?;
+        let name = root.file_name().unwrap_or_default().to_os_string();
+        Ok(WalkEntry {
+            depth: 0,
+            entry: DirEntry {
+                path: root,
+                file_type: meta.file_type,
+                size: meta.size,
+                inode: meta.inode,
+                ..Default::default()
+            }
+            .with_name_os(name),
+        })
+    }
+
+    /// Decide what to do with a freshly discovered entry. Returns `None` if
+    /// the entry was deferred until after its contents.
+    fn visit(&mut self, entry: WalkEntry) -> Option<Result<WalkEntry, FsError>> {
+        if entry.file_type() != FileType::Directory || entry.depth >= self.max_depth {
+            return Some(Ok(entry));
+        }
+        if !self.contents_first {
+            self.pending = Some((entry.entry.path.clone(), entry.depth));
+            return Some(Ok(entry));
+        }
+        let depth = entry.depth;
+        let path = entry.entry.path.clone();
+        let (iter, error) = match self.backend.read_dir(&path) {
+            Ok(iter) => (iter, None),
+            Err(e) => (ReadDirIter::from_vec(Vec::new()), Some(e)),
+        };
+        // On failure the directory itself is still yielded after the error
+        self.stack.push(Frame {
+            path,
+            iter,
+            depth,
+            dir: Some(entry),
+        });
+        error.map(Err)
+    }
+}
+
+impl<B: Fs + ?Sized> Iterator for Walk<'_, B> {
+    type Item = Result<WalkEntry, FsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((path, depth)) = self.pending.take() {
+            let (iter, error) = match self.backend.read_dir(&path) {
+                Ok(iter) => (iter, None),
+                Err(e) => (ReadDirIter::from_vec(Vec::new()), Some(e)),
+            };
+            // Pushed even on failure so `current_dir` names the directory
+            self.stack.push(Frame {
+                path,
+                iter,
+                depth,
+                dir: None,
+            });
+            if let Some(e) = error {
+                return Some(Err(e));
+            }
+        }
+        loop {
+            let entry = if let Some(root) = self.root.take() {
+                match self.root_entry(root) {
+                    Ok(entry) => entry,
+                    Err(e) => return Some(Err(e)),
+                }
+            } else {
+                let frame = self.stack.last_mut()?;
+                match frame.iter.next() {
+                    Some(Ok(entry)) => WalkEntry {
+                        depth: frame.depth + 1,
+                        entry,
+                    },
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => {
+                        if let Some(dir) = self.stack.pop().and_then(|frame| frame.dir) {
+                            return Some(Ok(dir));
+                        }
+                        continue;
+                    }
+                }
+            };
+            if let Some(item) = self.visit(entry) {
+                return Some(item);
+            }
+        }
+    }
+}
@@ -0,0 +1,1225 @@
+//! # Wire Protocol
+//!
+//! Serializable request and response types for remote backends, available
+//! with the `wire` feature. A thin client turns each trait call into a
+//! [`WireRequest`], ships it inside a [`RequestFrame`], and the server
+//! answers with a [`ResponseFrame`] carrying a [`WireResponse`] or an
+//! [`FsError`]. The framing (JSON, bincode, length-prefixed TCP, gRPC bytes)
+//! is up to the transport; this module only fixes the shape of the messages.
+//!
+//! ## Coverage
+//!
+//! Every trait method that can be expressed as one round trip has a request
+//! variant, named after the method. The exceptions are:
+//!
+//! | Method | Remote equivalent |
+//! |--------|-------------------|
+//! | `open_read` / `open_write` / `open_write_sized` | `Open` + `ReadAt` / `WriteAt` + `Close` |
+//! | `FsSeek` cursor methods | Track the cursor client-side over `ReadAt` / `WriteAt` |
+//! | `read_dir` / `read_dir_with` | Returns all entries at once as [`WireResponse::DirEntries`] |
+//! | `FsLease::on_lease_break` | The server pushes [`LeaseBreak`](crate::LeaseBreak)s outside the request/response flow |
+//! | `FsBranch::branch` | Returns a live backend; servers expose each branch as a backend of its own |
+//!
+//! Requests for [`Fs`](crate::Fs) methods convert to and from [`FsOp`] with
+//! `TryFrom` and serialize the same way, so journaled ops and dry-run plans
+//! can be sent as they are.
+//!
+//! ## Versioning
+//!
+//! Frames carry the sender's [`WIRE_PROTOCOL_VERSION`]. A receiver should call
+//! [`RequestFrame::check_version`] before acting on a frame. New request
+//! variants bump the version; peers reject versions they don't understand
+//! instead of misreading them.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CachePolicy, CacheStats, CopyOptions, DirEntry, DirHandle, FileFlags, FileId, FsError, FsOp,
+    Handle, HandleInfo, IoStats, LayerId, LayerInfo, LeaseHint, LeaseType, LockOwner, LockRange,
+    LockType, MergePolicy, MergeReport, Metadata, MetadataLite, MetadataMask, NotFoundCacheHint,
+    OpenFlags, Permissions, ReadDirOptions, SetAttr, StatFs, StorageClass, UsageReport,
+    WriteOptions,
+};
+
+/// Version of the wire protocol implemented by this crate.
+pub const WIRE_PROTOCOL_VERSION: u32 = 1;
+
+/// A single trait call, serialized for transport.
+///
+/// Variants are named after the trait method they mirror, and serialize as
+/// `{"op": "read_range", "path": ..., ...}`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum WireRequest {
+    // FsRead
+    /// [`FsRead::read`](crate::FsRead::read) → [`WireResponse::Bytes`].
+    Read {
+        /// File to read.
+        path: PathBuf,
+    },
+    /// [`FsRead::read_to_string`](crate::FsRead::read_to_string) → [`WireResponse::Text`].
+    ReadToString {
+        /// File to read.
+        path: PathBuf,
+    },
+    /// [`FsRead::read_range`](crate::FsRead::read_range) → [`WireResponse::Bytes`].
+    ReadRange {
+        /// File to read.
+        path: PathBuf,
+        /// Byte offset to start at.
+        offset: u64,
+        /// Maximum number of bytes.
+        len: usize,
+    },
+    /// [`FsRead::exists`](crate::FsRead::exists) → [`WireResponse::Bool`].
+    Exists {
+        /// Path to check.
+        path: PathBuf,
+    },
+    /// [`FsRead::exists_fresh`](crate::FsRead::exists_fresh) → [`WireResponse::Bool`].
+    ExistsFresh {
+        /// Path to check.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata`](crate::FsRead::metadata) → [`WireResponse::Metadata`].
+    Metadata {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata_lite`](crate::FsRead::metadata_lite) → [`WireResponse::MetadataLite`].
+    MetadataLite {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsRead::metadata_mask`](crate::FsRead::metadata_mask) → [`WireResponse::Metadata`].
+    MetadataMask {
+        /// Path to inspect.
+        path: PathBuf,
+        /// Fields the caller needs.
+        mask: MetadataMask,
+    },
+    /// [`FsRead::metadata_with_tag`](crate::FsRead::metadata_with_tag) → [`WireResponse::Metadata`].
+    MetadataWithTag {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+
+    // FsWrite
+    /// [`FsWrite::write`](crate::FsWrite::write) → [`WireResponse::Unit`].
+    Write {
+        /// File to write.
+        path: PathBuf,
+        /// New contents.
+        data: Vec<u8>,
+    },
+    /// [`FsWrite::append`](crate::FsWrite::append) → [`WireResponse::Unit`].
+    Append {
+        /// File to append to.
+        path: PathBuf,
+        /// Bytes to append.
+        data: Vec<u8>,
+    },
+    /// [`FsWrite::remove_file`](crate::FsWrite::remove_file) → [`WireResponse::Unit`].
+    RemoveFile {
+        /// File to remove.
+        path: PathBuf,
+    },
+    /// [`FsWrite::rename`](crate::FsWrite::rename) → [`WireResponse::Unit`].
+    Rename {
+        /// Current path.
+        from: PathBuf,
+        /// New path.
+        to: PathBuf,
+    },
+    /// [`FsWrite::copy`](crate::FsWrite::copy) → [`WireResponse::Unit`].
+    Copy {
+        /// Source file.
+        from: PathBuf,
+        /// Destination file.
+        to: PathBuf,
+    },
+    /// [`FsWrite::copy_with`](crate::FsWrite::copy_with) → [`WireResponse::Count`].
+    CopyWith {
+        /// Source file.
+        from: PathBuf,
+        /// Destination file.
+        to: PathBuf,
+        /// Overwrite and preservation options.
+        options: CopyOptions,
+    },
+    /// [`FsWrite::truncate`](crate::FsWrite::truncate) → [`WireResponse::Unit`].
+    Truncate {
+        /// File to resize.
+        path: PathBuf,
+        /// New length in bytes.
+        size: u64,
+    },
+    /// [`FsWrite::write_with`](crate::FsWrite::write_with) → [`WireResponse::Unit`].
+    WriteWith {
+        /// File to write.
+        path: PathBuf,
+        /// New contents.
+        data: Vec<u8>,
+        /// Storage hints.
+        options: WriteOptions,
+    },
+    /// [`FsWrite::set_times`](crate::FsWrite::set_times) → [`WireResponse::Unit`].
+    SetTimes {
+        /// Entry to change.
+        path: PathBuf,
+        /// New access time, if changing.
+        #[serde(default, with = "crate::types::system_time_serde::option")]
+        atime: Option<SystemTime>,
+        /// New modification time, if changing.
+        #[serde(default, with = "crate::types::system_time_serde::option")]
+        mtime: Option<SystemTime>,
+    },
+
+    // FsDir
+    /// [`FsDir::read_dir`](crate::FsDir::read_dir) → [`WireResponse::DirEntries`].
+    ReadDir {
+        /// Directory to list.
+        path: PathBuf,
+    },
+    /// [`FsDir::read_dir_with`](crate::FsDir::read_dir_with) → [`WireResponse::DirEntries`].
+    ReadDirWith {
+        /// Directory to list.
+        path: PathBuf,
+        /// Listing order.
+        options: ReadDirOptions,
+    },
+    /// [`FsDir::create_dir`](crate::FsDir::create_dir) → [`WireResponse::Unit`].
+    CreateDir {
+        /// Directory to create.
+        path: PathBuf,
+    },
+    /// [`FsDir::create_dir_all`](crate::FsDir::create_dir_all) → [`WireResponse::Unit`].
+    CreateDirAll {
+        /// Directory to create, with any missing parents.
+        path: PathBuf,
+    },
+    /// [`FsDir::create_dir_with`](crate::FsDir::create_dir_with) → [`WireResponse::Unit`].
+    CreateDirWith {
+        /// Directory to create.
+        path: PathBuf,
+        /// Permissions to create it with.
+        perm: Permissions,
+    },
+    /// [`FsDir::create_temp_dir_in`](crate::FsDir::create_temp_dir_in) → [`WireResponse::Path`].
+    CreateTempDirIn {
+        /// Directory to create it in.
+        parent: PathBuf,
+        /// Name prefix.
+        prefix: String,
+    },
+    /// [`FsDir::remove_dir`](crate::FsDir::remove_dir) → [`WireResponse::Unit`].
+    RemoveDir {
+        /// Empty directory to remove.
+        path: PathBuf,
+    },
+    /// [`FsDir::remove_dir_all`](crate::FsDir::remove_dir_all) → [`WireResponse::Unit`].
+    RemoveDirAll {
+        /// Directory tree to remove.
+        path: PathBuf,
+    },
+    /// [`FsDir::dir_len`](crate::FsDir::dir_len) → [`WireResponse::Count`].
+    DirLen {
+        /// Directory to count.
+        path: PathBuf,
+    },
+    /// [`FsDir::is_dir_empty`](crate::FsDir::is_dir_empty) → [`WireResponse::Bool`].
+    IsDirEmpty {
+        /// Directory to check.
+        path: PathBuf,
+    },
+
+    // FsLink
+    /// [`FsLink::symlink`](crate::FsLink::symlink) → [`WireResponse::Unit`].
+    Symlink {
+        /// What the link points to.
+        target: PathBuf,
+        /// Where to create the link.
+        link: PathBuf,
+    },
+    /// [`FsLink::hard_link`](crate::FsLink::hard_link) → [`WireResponse::Unit`].
+    HardLink {
+        /// Existing file.
+        original: PathBuf,
+        /// New name for it.
+        link: PathBuf,
+    },
+    /// [`FsLink::read_link`](crate::FsLink::read_link) → [`WireResponse::Path`].
+    ReadLink {
+        /// Symlink to read.
+        path: PathBuf,
+    },
+    /// [`FsLink::symlink_metadata`](crate::FsLink::symlink_metadata) → [`WireResponse::Metadata`].
+    SymlinkMetadata {
+        /// Path to inspect without following a final symlink.
+        path: PathBuf,
+    },
+
+    // FsPermissions
+    /// [`FsPermissions::set_permissions`](crate::FsPermissions::set_permissions) → [`WireResponse::Unit`].
+    SetPermissions {
+        /// Path to change.
+        path: PathBuf,
+        /// New permissions.
+        perm: Permissions,
+    },
+
+    // FsSync
+    /// [`FsSync::sync`](crate::FsSync::sync) → [`WireResponse::Unit`].
+    Sync,
+    /// [`FsSync::fsync`](crate::FsSync::fsync) → [`WireResponse::Unit`].
+    Fsync {
+        /// File to flush.
+        path: PathBuf,
+    },
+    /// [`FsSync::fdatasync`](crate::FsSync::fdatasync) → [`WireResponse::Unit`].
+    Fdatasync {
+        /// File whose data to flush.
+        path: PathBuf,
+    },
+    /// [`FsSync::sync_handle`](crate::FsSync::sync_handle) → [`WireResponse::Unit`].
+    SyncHandle {
+        /// Handle to flush.
+        handle: Handle,
+    },
+    /// [`FsSync::barrier`](crate::FsSync::barrier) → [`WireResponse::Unit`].
+    Barrier,
+
+    // FsStats
+    /// [`FsStats::statfs`](crate::FsStats::statfs) → [`WireResponse::StatFs`].
+    Statfs,
+    /// [`FsStats::io_stats`](crate::FsStats::io_stats) → [`WireResponse::IoStats`].
+    IoStats,
+    /// [`FsStats::usage`](crate::FsStats::usage) → [`WireResponse::Usage`].
+    Usage {
+        /// Root of the report.
+        path: PathBuf,
+    },
+
+    // FsPath
+    /// [`FsPath::canonicalize`](crate::FsPath::canonicalize) → [`WireResponse::Path`].
+    Canonicalize {
+        /// Path to resolve.
+        path: PathBuf,
+    },
+    /// [`FsPath::soft_canonicalize`](crate::FsPath::soft_canonicalize) → [`WireResponse::Path`].
+    SoftCanonicalize {
+        /// Path to resolve; the final component may be missing.
+        path: PathBuf,
+    },
+
+    // FsInode
+    /// [`FsInode::path_to_inode`](crate::FsInode::path_to_inode) → [`WireResponse::Inode`].
+    PathToInode {
+        /// Path to look up.
+        path: PathBuf,
+    },
+    /// [`FsInode::inode_to_path`](crate::FsInode::inode_to_path) → [`WireResponse::Path`].
+    InodeToPath {
+        /// Inode to look up.
+        inode: u64,
+    },
+    /// [`FsInode::lookup`](crate::FsInode::lookup) → [`WireResponse::Inode`].
+    Lookup {
+        /// Directory inode.
+        parent_inode: u64,
+        /// Child name.
+        name: OsString,
+    },
+    /// [`FsInode::metadata_by_inode`](crate::FsInode::metadata_by_inode) → [`WireResponse::Metadata`].
+    MetadataByInode {
+        /// Inode to inspect.
+        inode: u64,
+    },
+    /// [`FsInode::set_attr`](crate::FsInode::set_attr) → [`WireResponse::Metadata`].
+    SetAttr {
+        /// Inode to change.
+        inode: u64,
+        /// Fields to change.
+        attr: SetAttr,
+    },
+    /// [`FsInode::read_dir_inode`](crate::FsInode::read_dir_inode) → [`WireResponse::DirPage`].
+    ReadDirInode {
+        /// Directory to list.
+        inode: u64,
+        /// Cookie to resume after, or 0 to start.
+        offset: u64,
+    },
+
+    // FsHandles
+    /// [`FsHandles::open`](crate::FsHandles::open) → [`WireResponse::Handle`].
+    Open {
+        /// File to open.
+        path: PathBuf,
+        /// Access mode and creation flags.
+        flags: OpenFlags,
+    },
+    /// [`FsHandles::read_at`](crate::FsHandles::read_at) → [`WireResponse::Bytes`].
+    ///
+    /// `len` stands in for the caller's buffer size; the response may be shorter.
+    ReadAt {
+        /// Open handle.
+        handle: Handle,
+        /// Byte offset to read from.
+        offset: u64,
+        /// Maximum number of bytes.
+        len: usize,
+    },
+    /// [`FsHandles::write_at`](crate::FsHandles::write_at) → [`WireResponse::Count`].
+    WriteAt {
+        /// Open handle.
+        handle: Handle,
+        /// Byte offset to write at.
+        offset: u64,
+        /// Bytes to write.
+        data: Vec<u8>,
+    },
+    /// [`FsHandles::append_handle`](crate::FsHandles::append_handle) → [`WireResponse::Count`]
+    /// (the offset written at).
+    AppendHandle {
+        /// Open handle.
+        handle: Handle,
+        /// Bytes to append.
+        data: Vec<u8>,
+    },
+    /// [`FsHandles::close`](crate::FsHandles::close) → [`WireResponse::Unit`].
+    Close {
+        /// Handle to close.
+        handle: Handle,
+    },
+    /// [`FsHandles::dup`](crate::FsHandles::dup) → [`WireResponse::Handle`].
+    Dup {
+        /// Handle to duplicate.
+        handle: Handle,
+    },
+    /// [`FsHandles::open_anonymous`](crate::FsHandles::open_anonymous) → [`WireResponse::Handle`].
+    OpenAnonymous {
+        /// Directory the file will live in.
+        dir: PathBuf,
+        /// Access mode flags.
+        flags: OpenFlags,
+    },
+    /// [`FsHandles::link_handle`](crate::FsHandles::link_handle) → [`WireResponse::Unit`].
+    LinkHandle {
+        /// Anonymous handle to publish.
+        handle: Handle,
+        /// Name to give it.
+        path: PathBuf,
+    },
+    /// [`FsHandles::mkfifo`](crate::FsHandles::mkfifo) → [`WireResponse::Unit`].
+    Mkfifo {
+        /// Path of the new FIFO.
+        path: PathBuf,
+        /// Initial permissions.
+        perm: Permissions,
+    },
+
+    // FsDirHandles
+    /// [`FsDirHandles::open_dir`](crate::FsDirHandles::open_dir) → [`WireResponse::DirHandle`].
+    OpenDir {
+        /// Directory to open.
+        path: PathBuf,
+    },
+    /// [`FsDirHandles::open_dir_at`](crate::FsDirHandles::open_dir_at) → [`WireResponse::DirHandle`].
+    OpenDirAt {
+        /// Open parent directory.
+        dir: DirHandle,
+        /// Child directory name.
+        name: OsString,
+    },
+    /// [`FsDirHandles::close_dir`](crate::FsDirHandles::close_dir) → [`WireResponse::Unit`].
+    CloseDir {
+        /// Directory handle to close.
+        dir: DirHandle,
+    },
+    /// [`FsDirHandles::open_at`](crate::FsDirHandles::open_at) → [`WireResponse::Handle`].
+    OpenAt {
+        /// Open parent directory.
+        dir: DirHandle,
+        /// File name.
+        name: OsString,
+        /// Access mode and creation flags.
+        flags: OpenFlags,
+    },
+    /// [`FsDirHandles::metadata_at`](crate::FsDirHandles::metadata_at) → [`WireResponse::Metadata`].
+    MetadataAt {
+        /// Open parent directory.
+        dir: DirHandle,
+        /// Entry name.
+        name: OsString,
+    },
+    /// [`FsDirHandles::unlink_at`](crate::FsDirHandles::unlink_at) → [`WireResponse::Unit`].
+    UnlinkAt {
+        /// Open parent directory.
+        dir: DirHandle,
+        /// File name.
+        name: OsString,
+    },
+    /// [`FsDirHandles::mkdir_at`](crate::FsDirHandles::mkdir_at) → [`WireResponse::Unit`].
+    MkdirAt {
+        /// Open parent directory.
+        dir: DirHandle,
+        /// New directory name.
+        name: OsString,
+    },
+
+    // FsLock
+    /// [`FsLock::lock`](crate::FsLock::lock) → [`WireResponse::Unit`].
+    Lock {
+        /// Handle to lock.
+        handle: Handle,
+        /// Shared or exclusive.
+        lock: LockType,
+    },
+    /// [`FsLock::try_lock`](crate::FsLock::try_lock) → [`WireResponse::Bool`].
+    TryLock {
+        /// Handle to lock.
+        handle: Handle,
+        /// Shared or exclusive.
+        lock: LockType,
+    },
+    /// [`FsLock::unlock`](crate::FsLock::unlock) → [`WireResponse::Unit`].
+    Unlock {
+        /// Handle to unlock.
+        handle: Handle,
+    },
+    /// [`FsLock::lock_owned`](crate::FsLock::lock_owned) → [`WireResponse::Unit`].
+    LockOwned {
+        /// Handle to lock through.
+        handle: Handle,
+        /// Owner of the lock.
+        owner: LockOwner,
+        /// Shared or exclusive.
+        lock: LockType,
+        /// Bytes to lock.
+        range: LockRange,
+    },
+    /// [`FsLock::try_lock_owned`](crate::FsLock::try_lock_owned) → [`WireResponse::Bool`].
+    TryLockOwned {
+        /// Handle to lock through.
+        handle: Handle,
+        /// Owner of the lock.
+        owner: LockOwner,
+        /// Shared or exclusive.
+        lock: LockType,
+        /// Bytes to lock.
+        range: LockRange,
+    },
+    /// [`FsLock::unlock_owned`](crate::FsLock::unlock_owned) → [`WireResponse::Unit`].
+    UnlockOwned {
+        /// Handle to unlock through.
+        handle: Handle,
+        /// Owner of the locks.
+        owner: LockOwner,
+        /// Bytes to unlock.
+        range: LockRange,
+    },
+
+    // FsXattr
+    /// [`FsXattr::get_xattr`](crate::FsXattr::get_xattr) → [`WireResponse::Bytes`].
+    GetXattr {
+        /// Path holding the attribute.
+        path: PathBuf,
+        /// Attribute name.
+        name: String,
+    },
+    /// [`FsXattr::set_xattr`](crate::FsXattr::set_xattr) → [`WireResponse::Unit`].
+    SetXattr {
+        /// Path to set it on.
+        path: PathBuf,
+        /// Attribute name.
+        name: String,
+        /// Attribute value.
+        value: Vec<u8>,
+    },
+    /// [`FsXattr::remove_xattr`](crate::FsXattr::remove_xattr) → [`WireResponse::Unit`].
+    RemoveXattr {
+        /// Path holding the attribute.
+        path: PathBuf,
+        /// Attribute name.
+        name: String,
+    },
+    /// [`FsXattr::list_xattr`](crate::FsXattr::list_xattr) → [`WireResponse::Names`].
+    ListXattr {
+        /// Path to list.
+        path: PathBuf,
+    },
+
+    // FsFileFlags
+    /// [`FsFileFlags::set_flags`](crate::FsFileFlags::set_flags) → [`WireResponse::Unit`].
+    SetFlags {
+        /// Path to change.
+        path: PathBuf,
+        /// New flags.
+        flags: FileFlags,
+    },
+    /// [`FsFileFlags::get_flags`](crate::FsFileFlags::get_flags) → [`WireResponse::Flags`].
+    GetFlags {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+
+    // FsFileId
+    /// [`FsFileId::file_id`](crate::FsFileId::file_id) → [`WireResponse::FileId`].
+    FileId {
+        /// Path to identify.
+        path: PathBuf,
+    },
+    /// [`FsFileId::path_by_id`](crate::FsFileId::path_by_id) → [`WireResponse::Path`].
+    PathById {
+        /// Id to resolve.
+        id: FileId,
+    },
+
+    // FsExport
+    /// [`FsExport::export_id`](crate::FsExport::export_id) → [`WireResponse::FileId`].
+    ExportId {
+        /// Path to identify.
+        path: PathBuf,
+    },
+    /// [`FsExport::resolve_export_id`](crate::FsExport::resolve_export_id) → [`WireResponse::Path`].
+    ResolveExportId {
+        /// Id to resolve.
+        id: FileId,
+    },
+    /// [`FsExport::open_by_id`](crate::FsExport::open_by_id) → [`WireResponse::Handle`].
+    OpenById {
+        /// Id of the file to open.
+        id: FileId,
+        /// Access mode and creation flags.
+        flags: OpenFlags,
+    },
+    /// [`FsExport::export_version`](crate::FsExport::export_version) → [`WireResponse::Count`].
+    ExportVersion {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+    /// [`FsExport::lease_hint`](crate::FsExport::lease_hint) → [`WireResponse::LeaseHint`].
+    LeaseHint {
+        /// Path to inspect.
+        path: PathBuf,
+    },
+
+    // FsTiering
+    /// [`FsTiering::set_storage_class`](crate::FsTiering::set_storage_class) → [`WireResponse::Unit`].
+    SetStorageClass {
+        /// File to move.
+        path: PathBuf,
+        /// Target tier.
+        class: StorageClass,
+    },
+    /// [`FsTiering::storage_class`](crate::FsTiering::storage_class) → [`WireResponse::StorageClass`].
+    GetStorageClass {
+        /// File to inspect.
+        path: PathBuf,
+    },
+
+    // FsExpiry
+    /// [`FsExpiry::set_expiry`](crate::FsExpiry::set_expiry) → [`WireResponse::Unit`].
+    SetExpiry {
+        /// Entry to expire.
+        path: PathBuf,
+        /// Expiry time.
+        #[serde(with = "crate::types::system_time_serde")]
+        at: SystemTime,
+    },
+    /// [`FsExpiry::clear_expiry`](crate::FsExpiry::clear_expiry) → [`WireResponse::Unit`].
+    ClearExpiry {
+        /// Entry to keep.
+        path: PathBuf,
+    },
+    /// [`FsExpiry::expiry`](crate::FsExpiry::expiry) → [`WireResponse::Expiry`].
+    GetExpiry {
+        /// Entry to inspect.
+        path: PathBuf,
+    },
+
+    // FsLease
+    /// [`FsLease::acquire_lease`](crate::FsLease::acquire_lease) → [`WireResponse::Bool`].
+    AcquireLease {
+        /// Handle to lease.
+        handle: Handle,
+        /// Read or write.
+        lease: LeaseType,
+    },
+    /// [`FsLease::release_lease`](crate::FsLease::release_lease) → [`WireResponse::Unit`].
+    ReleaseLease {
+        /// Handle holding the lease.
+        handle: Handle,
+    },
+
+    // FsBranch
+    /// [`FsBranch::merge`](crate::FsBranch::merge) → [`WireResponse::Merge`].
+    Merge {
+        /// Branch to merge.
+        name: String,
+        /// How to settle conflicts.
+        policy: MergePolicy,
+    },
+    /// [`FsBranch::discard_branch`](crate::FsBranch::discard_branch) → [`WireResponse::Unit`].
+    DiscardBranch {
+        /// Branch to drop.
+        name: String,
+    },
+    /// [`FsBranch::branches`](crate::FsBranch::branches) → [`WireResponse::Names`].
+    Branches,
+
+    // FsLayers
+    /// [`FsLayers::layers`](crate::FsLayers::layers) → [`WireResponse::Layers`].
+    Layers,
+    /// [`FsLayers::which_layer`](crate::FsLayers::which_layer) → [`WireResponse::LayerId`].
+    WhichLayer {
+        /// Entry to locate.
+        path: PathBuf,
+    },
+    /// [`FsLayers::copy_up`](crate::FsLayers::copy_up) → [`WireResponse::Unit`].
+    CopyUp {
+        /// Entry to promote.
+        path: PathBuf,
+    },
+
+    // FsSequence
+    /// [`FsSequence::seq`](crate::FsSequence::seq) → [`WireResponse::Count`].
+    Seq,
+
+    // FsCacheControl
+    /// [`FsCacheControl::cache_policy`](crate::FsCacheControl::cache_policy) → [`WireResponse::CachePolicy`].
+    CachePolicy,
+    /// [`FsCacheControl::set_cache_policy`](crate::FsCacheControl::set_cache_policy) → [`WireResponse::Unit`].
+    SetCachePolicy {
+        /// The new policy.
+        policy: CachePolicy,
+    },
+    /// [`FsCacheControl::cache_stats`](crate::FsCacheControl::cache_stats) → [`WireResponse::CacheStats`].
+    CacheStats,
+    /// [`FsCacheControl::not_found_hint`](crate::FsCacheControl::not_found_hint) → [`WireResponse::NotFoundCacheHint`].
+    NotFoundHint,
+    /// [`FsCacheControl::invalidate`](crate::FsCacheControl::invalidate) → [`WireResponse::Unit`].
+    Invalidate {
+        /// Entry to drop from the cache.
+        path: PathBuf,
+    },
+    /// [`FsCacheControl::invalidate_all`](crate::FsCacheControl::invalidate_all) → [`WireResponse::Unit`].
+    InvalidateAll,
+    /// [`FsCacheControl::flush_cache`](crate::FsCacheControl::flush_cache) → [`WireResponse::Unit`].
+    FlushCache,
+
+    // FsHandleDebug
+    /// [`FsHandleDebug::open_handles`](crate::FsHandleDebug::open_handles) → [`WireResponse::Handles`].
+    OpenHandles,
+    /// [`FsHandleDebug::close_all`](crate::FsHandleDebug::close_all) → [`WireResponse::Count`].
+    CloseAll,
+}
+
+impl WireRequest {
+    /// The trait method this request mirrors, as used for
+    /// [`FsError::operation`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use anyfs_backend::WireRequest;
+    ///
+    /// let request = WireRequest::ReadDir { path: "/docs".into() };
+    /// assert_eq!(request.operation(), "read_dir");
+    /// ```
+    pub fn operation(&self) -> &'static str {
+        match self {
+            Self::Read { .. } => "read",
+            Self::ReadToString { .. } => "read_to_string",
+            Self::ReadRange { .. } => "read_range",
+            Self::Exists { .. } => "exists",
+            Self::ExistsFresh { .. } => "exists_fresh",
+            Self::Metadata { .. } => "metadata",
+            Self::MetadataLite { .. } => "metadata_lite",
+            Self::MetadataMask { .. } => "metadata_mask",
+            Self::MetadataWithTag { .. } => "metadata_with_tag",
+            Self::Write { .. } => "write",
+            Self::Append { .. } => "append",
+            Self::RemoveFile { .. } => "remove_file",
+            Self::Rename { .. } => "rename",
+            Self::Copy { .. } => "copy",
+            Self::CopyWith { .. } => "copy_with",
+            Self::Truncate { .. } => "truncate",
+            Self::WriteWith { .. } => "write_with",
+            Self::SetTimes { .. } => "set_times",
+            Self::ReadDir { .. } => "read_dir",
+            Self::ReadDirWith { .. } => "read_dir_with",
+            Self::CreateDir { .. } => "create_dir",
+            Self::CreateDirAll { .. } => "create_dir_all",
+            Self::CreateDirWith { .. } => "create_dir_with",
+            Self::CreateTempDirIn { .. } => "create_temp_dir_in",
+            Self::RemoveDir { .. } => "remove_dir",
+            Self::RemoveDirAll { .. } => "remove_dir_all",
+            Self::DirLen { .. } => "dir_len",
+            Self::IsDirEmpty { .. } => "is_dir_empty",
+            Self::Symlink { .. } => "symlink",
+            Self::HardLink { .. } => "hard_link",
+            Self::ReadLink { .. } => "read_link",
+            Self::SymlinkMetadata { .. } => "symlink_metadata",
+            Self::SetPermissions { .. } => "set_permissions",
+            Self::Sync => "sync",
+            Self::Fsync { .. } => "fsync",
+            Self::Fdatasync { .. } => "fdatasync",
+            Self::SyncHandle { .. } => "sync_handle",
+            Self::Barrier => "barrier",
+            Self::Statfs => "statfs",
+            Self::IoStats => "io_stats",
+            Self::Usage { .. } => "usage",
+            Self::Canonicalize { .. } => "canonicalize",
+            Self::SoftCanonicalize { .. } => "soft_canonicalize",
+            Self::PathToInode { .. } => "path_to_inode",
+            Self::InodeToPath { .. } => "inode_to_path",
+            Self::Lookup { .. } => "lookup",
+            Self::MetadataByInode { .. } => "metadata_by_inode",
+            Self::SetAttr { .. } => "set_attr",
+            Self::ReadDirInode { .. } => "read_dir_inode",
+            Self::Open { .. } => "open",
+            Self::ReadAt { .. } => "read_at",
+            Self::WriteAt { .. } => "write_at",
+            Self::AppendHandle { .. } => "append_handle",
+            Self::Close { .. } => "close",
+            Self::Dup { .. } => "dup",
+            Self::OpenAnonymous { .. } => "open_anonymous",
+            Self::LinkHandle { .. } => "link_handle",
+            Self::Mkfifo { .. } => "mkfifo",
+            Self::OpenDir { .. } => "open_dir",
+            Self::OpenDirAt { .. } => "open_dir_at",
+            Self::CloseDir { .. } => "close_dir",
+            Self::OpenAt { .. } => "open_at",
+            Self::MetadataAt { .. } => "metadata_at",
+            Self::UnlinkAt { .. } => "unlink_at",
+            Self::MkdirAt { .. } => "mkdir_at",
+            Self::Lock { .. } => "lock",
+            Self::TryLock { .. } => "try_lock",
+            Self::Unlock { .. } => "unlock",
+            Self::LockOwned { .. } => "lock_owned",
+            Self::TryLockOwned { .. } => "try_lock_owned",
+            Self::UnlockOwned { .. } => "unlock_owned",
+            Self::GetXattr { .. } => "get_xattr",
+            Self::SetXattr { .. } => "set_xattr",
+            Self::RemoveXattr { .. } => "remove_xattr",
+            Self::ListXattr { .. } => "list_xattr",
+            Self::SetFlags { .. } => "set_flags",
+            Self::GetFlags { .. } => "get_flags",
+            Self::FileId { .. } => "file_id",
+            Self::PathById { .. } => "path_by_id",
+            Self::ExportId { .. } => "export_id",
+            Self::ResolveExportId { .. } => "resolve_export_id",
+            Self::OpenById { .. } => "open_by_id",
+            Self::ExportVersion { .. } => "export_version",
+            Self::LeaseHint { .. } => "lease_hint",
+            Self::SetStorageClass { .. } => "set_storage_class",
+            Self::GetStorageClass { .. } => "storage_class",
+            Self::SetExpiry { .. } => "set_expiry",
+            Self::ClearExpiry { .. } => "clear_expiry",
+            Self::GetExpiry { .. } => "expiry",
+            Self::AcquireLease { .. } => "acquire_lease",
+            Self::ReleaseLease { .. } => "release_lease",
+            Self::Merge { .. } => "merge",
+            Self::DiscardBranch { .. } => "discard_branch",
+            Self::Branches => "branches",
+            Self::Layers => "layers",
+            Self::WhichLayer { .. } => "which_layer",
+            Self::CopyUp { .. } => "copy_up",
+            Self::Seq => "seq",
+            Self::CachePolicy => "cache_policy",
+            Self::SetCachePolicy { .. } => "set_cache_policy",
+            Self::CacheStats => "cache_stats",
+            Self::NotFoundHint => "not_found_hint",
+            Self::Invalidate { .. } => "invalidate",
+            Self::InvalidateAll => "invalidate_all",
+            Self::FlushCache => "flush_cache",
+            Self::OpenHandles => "open_handles",
+            Self::CloseAll => "close_all",
+        }
+    }
+}
+
+/// `TryFrom` in both directions for the variants [`FsOp`] and
+/// [`WireRequest`] share, field for field.
+macro_rules! shared_ops {
+    ($($variant:ident { $($field:ident),* }),* $(,)?) => {
+        /// An [`FsOp`] as a request, for sending a journal entry, batch or
+        /// dry-run plan to a remote backend.
+        ///
+        /// # Errors
+        ///
+        /// - [`FsError::NotSupported`] for ops with no single round trip:
+        ///   `open_read`, `open_write` and `open_write_sized` (use `Open` and
+        ///   handle requests)
+        impl TryFrom<FsOp> for WireRequest {
+            type Error = FsError;
+
+            fn try_from(op: FsOp) -> Result<Self, FsError> {
+                match op {
+                    $(FsOp::$variant { $($field),* } => Ok(Self::$variant { $($field),* }),)*
+                    other => Err(FsError::NotSupported {
+                        operation: other.operation(),
+                    }),
+                }
+            }
+        }
+
+        /// A request as an [`FsOp`], for running it through
+        /// [`Dispatcher`](crate::Dispatcher) or [`bulk::run`](crate::bulk::run).
+        ///
+        /// # Errors
+        ///
+        /// - [`FsError::NotSupported`] for requests outside the [`Fs`](crate::Fs)
+        ///   surface, such as `symlink` or handle operations
+        impl TryFrom<WireRequest> for FsOp {
+            type Error = FsError;
+
+            fn try_from(request: WireRequest) -> Result<Self, FsError> {
+                match request {
+                    $(WireRequest::$variant { $($field),* } => Ok(Self::$variant { $($field),* }),)*
+                    other => Err(FsError::NotSupported {
+                        operation: other.operation(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+shared_ops! {
+    Read { path },
+    ReadToString { path },
+    ReadRange { path, offset, len },
+    Exists { path },
+    ExistsFresh { path },
+    Metadata { path },
+    MetadataLite { path },
+    MetadataMask { path, mask },
+    MetadataWithTag { path },
+    Write { path, data },
+    Append { path, data },
+    RemoveFile { path },
+    Rename { from, to },
+    Copy { from, to },
+    CopyWith { from, to, options },
+    Truncate { path, size },
+    WriteWith { path, data, options },
+    SetTimes { path, atime, mtime },
+    ReadDir { path },
+    ReadDirWith { path, options },
+    CreateDir { path },
+    CreateDirAll { path },
+    CreateDirWith { path, perm },
+    CreateTempDirIn { parent, prefix },
+    RemoveDir { path },
+    RemoveDirAll { path },
+    DirLen { path },
+    IsDirEmpty { path },
+}
+
+/// The successful result of a [`WireRequest`].
+///
+/// Each request documents which variant it expects back.
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum WireResponse {
+    /// The call returned `()`.
+    Unit,
+    /// A boolean result (`exists`, `is_dir_empty`, `try_lock`).
+    Bool(bool),
+    /// Raw bytes (`read`, `read_range`, `read_at`, `get_xattr`).
+    Bytes(Vec<u8>),
+    /// UTF-8 text (`read_to_string`).
+    Text(String),
+    /// A count of bytes or items (`copy_with`, `dir_len`, `write_at`, `close_all`),
+    /// an offset (`append_handle`), or a sequence number (`seq`, `export_version`).
+    Count(u64),
+    /// A path (`read_link`, `canonicalize`, `inode_to_path`, ...).
+    Path(PathBuf),
+    /// An inode number.
+    Inode(u64),
+    /// A newly opened handle.
+    Handle(Handle),
+    /// A newly opened directory handle.
+    DirHandle(DirHandle),
+    /// A stable file id.
+    FileId(FileId),
+    /// How long export clients may cache attributes and lookups.
+    LeaseHint(LeaseHint),
+    /// Full metadata.
+    Metadata(Metadata),
+    /// Fast-path metadata.
+    MetadataLite(MetadataLite),
+    /// A directory listing, keeping per-entry failures in place.
+    DirEntries(Vec<Result<DirEntry, FsError>>),
+    /// A batch of a resumable listing, each entry with its resume cookie.
+    DirPage(Vec<(DirEntry, u64)>),
+    /// Filesystem statistics.
+    StatFs(StatFs),
+    /// A usage report.
+    Usage(UsageReport),
+    /// A branch merge outcome.
+    Merge(MergeReport),
+    /// The layers of an overlay stack.
+    Layers(Vec<LayerInfo>),
+    /// A layer of an overlay stack.
+    LayerId(LayerId),
+    /// I/O counters.
+    IoStats(IoStats),
+    /// A cache configuration.
+    CachePolicy(CachePolicy),
+    /// Cache counters.
+    CacheStats(CacheStats),
+    /// How long a cached "not found" may be stale.
+    NotFoundCacheHint(NotFoundCacheHint),
+    /// File flags.
+    Flags(FileFlags),
+    /// A storage tier.
+    StorageClass(StorageClass),
+    /// An expiry time, `None` if the entry never expires.
+    Expiry(#[serde(with = "crate::types::system_time_serde::option")] Option<SystemTime>),
+    /// Attribute or branch names (`list_xattr`, `branches`).
+    Names(Vec<String>),
+    /// Open handle descriptions (`open_handles`).
+    Handles(Vec<HandleInfo>),
+}
+
+/// A [`WireRequest`] with its protocol version and correlation id.
+///
+/// # Example
+///
+/// ```rust
+/// use anyfs_backend::{RequestFrame, WireRequest};
+///
+/// let frame = RequestFrame::new(7, WireRequest::Exists { path: "/a".into() });
+/// assert!(frame.check_version().is_ok());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestFrame {
+    /// Protocol version of the sender.
+    pub version: u32,
+    /// Caller-chosen id echoed back in the matching [`ResponseFrame`].
+    pub id: u64,
+    /// The call.
+    pub request: WireRequest,
+}
+
+impl RequestFrame {
+    /// Frame `request` at the current [`WIRE_PROTOCOL_VERSION`].
+    pub fn new(id: u64, request: WireRequest) -> Self {
+        Self {
+            version: WIRE_PROTOCOL_VERSION,
+            id,
+            request,
+        }
+    }
+
+    /// Check that this frame's version is one we can serve.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotSupported`] if the version differs from
+    ///   [`WIRE_PROTOCOL_VERSION`]
+    pub fn check_version(&self) -> Result<(), FsError> {
+        check_version(self.version)
+    }
+
+    /// Build the response frame for this request.
+    pub fn respond(&self, result: Result<WireResponse, FsError>) -> ResponseFrame {
+        ResponseFrame {
+            version: WIRE_PROTOCOL_VERSION,
+            id: self.id,
+            result,
+        }
+    }
+}
+
+/// The answer to a [`RequestFrame`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseFrame {
+    /// Protocol version of the sender.
+    pub version: u32,
+    /// The id of the request being answered.
+    pub id: u64,
+    /// The call's outcome.
+    pub result: Result<WireResponse, FsError>,
+}
+
+impl ResponseFrame {
+    /// Check that this frame's version is one we can read.
+    ///
+    /// # Errors
+    ///
+    /// - [`FsError::NotSupported`] if the version differs from
+    ///   [`WIRE_PROTOCOL_VERSION`]
+    pub fn check_version(&self) -> Result<(), FsError> {
+        check_version(self.version)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), FsError> {
+    if version == WIRE_PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(FsError::NotSupported {
+            operation: "wire protocol version",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn request_frame_round_trips_through_json() {
+        let frame = RequestFrame::new(
+            1,
+            WireRequest::ReadRange {
+                path: PathBuf::from("/log"),
+                offset: 10,
+                len: 4,
+            },
+        );
+        let json = serde_json::to_string(&frame).unwrap();
+        assert!(json.contains(r#""op":"read_range""#));
+
+        let back: RequestFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.id, 1);
+        assert_eq!(back.request.operation(), "read_range");
+    }
+
+    #[test]
+    fn fs_ops_convert_to_identical_requests() {
+        let op = FsOp::WriteWith {
+            path: "/a".into(),
+            data: b"x".to_vec(),
+            options: WriteOptions::default(),
+        };
+        let request = WireRequest::try_from(op.clone()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&op).unwrap(),
+            serde_json::to_value(&request).unwrap()
+        );
+        assert_eq!(FsOp::try_from(request).unwrap(), op);
+
+        let op = FsOp::MetadataWithTag { path: "/a".into() };
+        let request = WireRequest::try_from(op.clone()).unwrap();
+        assert_eq!(request.operation(), "metadata_with_tag");
+        assert_eq!(FsOp::try_from(request).unwrap(), op);
+
+        let op = FsOp::ReadDirWith {
+            path: "/d".into(),
+            options: ReadDirOptions::sorted(),
+        };
+        let request = WireRequest::try_from(op.clone()).unwrap();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["op"], "read_dir_with");
+        assert_eq!(json["options"]["sorted"], true);
+        assert_eq!(FsOp::try_from(request).unwrap(), op);
+
+        let open = FsOp::OpenRead { path: "/a".into() };
+        assert!(matches!(
+            WireRequest::try_from(open),
+            Err(FsError::NotSupported {
+                operation: "open_read"
+            })
+        ));
+        assert!(FsOp::try_from(WireRequest::Sync).is_err());
+    }
+
+    #[test]
+    fn dir_handle_requests_round_trip() {
+        let request = WireRequest::OpenAt {
+            dir: DirHandle(3),
+            name: "report.txt".into(),
+            flags: OpenFlags::READ,
+        };
+        assert_eq!(request.operation(), "open_at");
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["op"], "open_at");
+        assert_eq!(json["dir"], 3);
+        let back: WireRequest = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+
+        let response = WireResponse::DirHandle(DirHandle(7));
+        let json = serde_json::to_string(&response).unwrap();
+        let back: WireResponse = serde_json::from_str(&json).unwrap();
+        assert!(matches!(back, WireResponse::DirHandle(DirHandle(7))));
+    }
+
+    #[test]
+    fn export_requests_round_trip() {
+        let request = WireRequest::OpenById {
+            id: FileId::from_inode(42),
+            flags: OpenFlags::READ,
+        };
+        assert_eq!(request.operation(), "open_by_id");
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["op"], "open_by_id");
+        let back: WireRequest = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+
+        let hint = LeaseHint::new(Duration::from_secs(60), Duration::from_secs(5));
+        let json = serde_json::to_string(&WireResponse::LeaseHint(hint)).unwrap();
+        let back: WireResponse = serde_json::from_str(&json).unwrap();
+        assert!(matches!(back, WireResponse::LeaseHint(h) if h == hint));
+    }
+
+    #[test]
+    fn response_frame_carries_errors() {
+        let frame = RequestFrame::new(9, WireRequest::Metadata { path: "/x".into() });
+        let response = frame.respond(Err(FsError::not_found("metadata", "/x")));
+        let json = serde_json::to_string(&response).unwrap();
+
+        let back: ResponseFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.id, 9);
+        match back.result {
+            Err(err) => assert_eq!(err.to_string(), "not found: /x"),
+            Ok(other) => panic!("expected error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expiry_times_round_trip() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let at = UNIX_EPOCH + Duration::new(1_767_225_600, 5);
+        let request = WireRequest::SetExpiry {
+            path: "/cache/x".into(),
+            at,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            WireRequest::SetExpiry { at: back, .. } => assert_eq!(back, at),
+            other => panic!("expected SetExpiry, got {:?}", other),
+        }
+
+        for expiry in [Some(at), None] {
+            let json = serde_json::to_string(&WireResponse::Expiry(expiry)).unwrap();
+            match serde_json::from_str(&json).unwrap() {
+                WireResponse::Expiry(back) => assert_eq!(back, expiry),
+                other => panic!("expected Expiry, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn mismatched_version_is_rejected() {
+        let mut frame = RequestFrame::new(1, WireRequest::Sync);
+        frame.version = WIRE_PROTOCOL_VERSION + 1;
+        assert!(matches!(
+            frame.check_version(),
+            Err(FsError::NotSupported { .. })
+        ));
+    }
+}
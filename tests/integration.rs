@@ -12,7 +12,7 @@ use std::ffi::OsStr;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
 // =============================================================================
@@ -26,9 +26,11 @@ struct InMemoryFs {
     dirs: RwLock<std::collections::HashSet<PathBuf>>,
     symlinks: RwLock<HashMap<PathBuf, PathBuf>>,
     xattrs: RwLock<HashMap<PathBuf, HashMap<String, Vec<u8>>>>,
+    permissions: RwLock<HashMap<PathBuf, Permissions>>,
     inodes: RwLock<HashMap<PathBuf, u64>>,
     inode_to_path: RwLock<HashMap<u64, PathBuf>>,
     handles: RwLock<HashMap<u64, OpenFile>>,
+    dir_handles: RwLock<HashMap<u64, u64>>,
     locks: RwLock<HashMap<u64, LockState>>,
     next_inode: AtomicU64,
     next_handle: AtomicU64,
@@ -54,9 +56,11 @@ impl InMemoryFs {
             dirs: RwLock::new(std::collections::HashSet::new()),
             symlinks: RwLock::new(HashMap::new()),
             xattrs: RwLock::new(HashMap::new()),
+            permissions: RwLock::new(HashMap::new()),
             inodes: RwLock::new(HashMap::new()),
             inode_to_path: RwLock::new(HashMap::new()),
             handles: RwLock::new(HashMap::new()),
+            dir_handles: RwLock::new(HashMap::new()),
             locks: RwLock::new(HashMap::new()),
             next_inode: AtomicU64::new(2), // 1 is reserved for root
             next_handle: AtomicU64::new(1),
@@ -106,9 +110,7 @@ impl FsRead for InMemoryFs {
             .unwrap()
             .get(path)
             .cloned()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("read", path))
     }
 
     fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
@@ -135,9 +137,9 @@ impl FsRead for InMemoryFs {
     }
 
     fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let file_type = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let file_type = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("metadata", path))?;
 
         let size = if file_type == FileType::File {
             self.files
@@ -151,17 +153,21 @@ impl FsRead for InMemoryFs {
         };
 
         let inode = self.inodes.read().unwrap().get(path).copied().unwrap_or(0);
+        let permissions = match self.permissions.read().unwrap().get(path) {
+            Some(&perm) => perm,
+            None if file_type == FileType::Directory => Permissions::default_dir(),
+            None => Permissions::default_file(),
+        };
 
-        Ok(Metadata {
-            file_type,
-            size,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode,
-            nlink: 1,
-        })
+        Ok(Metadata::default()
+            .with_file_type(file_type)
+            .with_size(size)
+            .with_permissions(permissions)
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(inode)
+            .with_nlink(1))
     }
 
     fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
@@ -193,24 +199,38 @@ impl FsWrite for InMemoryFs {
     }
 
     fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+        if self.symlinks.write().unwrap().remove(path).is_some() {
+            return Ok(());
+        }
         self.files
             .write()
             .unwrap()
             .remove(path)
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })?;
+            .ok_or_else(|| FsError::not_found("remove_file", path))?;
         Ok(())
     }
 
     fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.remove(from).ok_or_else(|| FsError::NotFound {
-            path: from.to_path_buf(),
-        })?;
+        let data = files
+            .remove(from)
+            .ok_or_else(|| FsError::not_found("rename", from))?;
         files.insert(to.to_path_buf(), data);
         drop(files);
-        self.assign_inode(to);
+        // A rename keeps the file's inode
+        let moved = self.inodes.write().unwrap().remove(from);
+        match moved {
+            Some(inode) => {
+                self.inodes.write().unwrap().insert(to.to_path_buf(), inode);
+                self.inode_to_path
+                    .write()
+                    .unwrap()
+                    .insert(inode, to.to_path_buf());
+            }
+            None => {
+                self.assign_inode(to);
+            }
+        }
         Ok(())
     }
 
@@ -221,9 +241,9 @@ impl FsWrite for InMemoryFs {
 
     fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
         let mut files = self.files.write().unwrap();
-        let data = files.get_mut(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
+        let data = files
+            .get_mut(path)
+            .ok_or_else(|| FsError::not_found("truncate", path))?;
         data.resize(size as usize, 0);
         Ok(())
     }
@@ -233,15 +253,17 @@ impl FsWrite for InMemoryFs {
         // In a real impl this would write back on drop
         Ok(Box::new(std::io::Cursor::new(Vec::new())))
     }
+
+    fn copy_with(&self, from: &Path, to: &Path, options: CopyOptions) -> Result<u64, FsError> {
+        self.copy_with_fallback(from, to, options)
+    }
 }
 
 // Layer 1: FsDir
 impl FsDir for InMemoryFs {
     fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
         if !self.dirs.read().unwrap().contains(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("read_dir", path));
         }
 
         let mut entries = Vec::new();
@@ -256,19 +278,19 @@ impl FsDir for InMemoryFs {
             if let Some(parent) = file_path.parent() {
                 if parent == path {
                     if let Some(name) = file_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: file_path.clone(),
-                            file_type: FileType::File,
-                            size: data.len() as u64,
-                            inode: self
-                                .inodes
-                                .read()
-                                .unwrap()
-                                .get(file_path)
-                                .copied()
-                                .unwrap_or(0),
-                        }));
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(file_path.clone())
+                            .with_file_type(FileType::File)
+                            .with_size(data.len() as u64)
+                            .with_inode(
+                                self.inodes
+                                    .read()
+                                    .unwrap()
+                                    .get(file_path)
+                                    .copied()
+                                    .unwrap_or(0),
+                            )));
                     }
                 }
             }
@@ -279,20 +301,41 @@ impl FsDir for InMemoryFs {
             if let Some(parent) = dir_path.parent() {
                 if parent == path && dir_path != path {
                     if let Some(name) = dir_path.file_name() {
-                        entries.push(Ok(DirEntry {
-                            name: name.to_string_lossy().into_owned(),
-                            path: dir_path.clone(),
-                            file_type: FileType::Directory,
-                            size: 0,
-                            inode: self
-                                .inodes
+                        entries.push(Ok(DirEntry::default()
+                            .with_name(name.to_string_lossy().into_owned())
+                            .with_path(dir_path.clone())
+                            .with_file_type(FileType::Directory)
+                            .with_size(0)
+                            .with_inode(
+                                self.inodes
+                                    .read()
+                                    .unwrap()
+                                    .get(dir_path)
+                                    .copied()
+                                    .unwrap_or(0),
+                            )));
+                    }
+                }
+            }
+        }
+
+        // Collect symlinks
+        for (link_path, target) in self.symlinks.read().unwrap().iter() {
+            if link_path.parent() == Some(path) {
+                if let Some(name) = link_path.file_name() {
+                    entries.push(Ok(DirEntry::default()
+                        .with_name(name.to_string_lossy().into_owned())
+                        .with_path(link_path.clone())
+                        .with_file_type(FileType::Symlink)
+                        .with_inode(
+                            self.inodes
                                 .read()
                                 .unwrap()
-                                .get(dir_path)
+                                .get(link_path)
                                 .copied()
                                 .unwrap_or(0),
-                        }));
-                    }
+                        )
+                        .with_symlink_target(target.clone())));
                 }
             }
         }
@@ -326,9 +369,7 @@ impl FsDir for InMemoryFs {
 
     fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
         if !self.dirs.write().unwrap().remove(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_dir", path));
         }
         Ok(())
     }
@@ -374,31 +415,32 @@ impl FsLink for InMemoryFs {
     }
 
     fn symlink_metadata(&self, path: &Path) -> Result<Metadata, FsError> {
-        let file_type = self.get_file_type(path).ok_or_else(|| FsError::NotFound {
-            path: path.to_path_buf(),
-        })?;
-
-        Ok(Metadata {
-            file_type,
-            size: 0,
-            permissions: Permissions::default_file(),
-            created: SystemTime::UNIX_EPOCH,
-            modified: SystemTime::UNIX_EPOCH,
-            accessed: SystemTime::UNIX_EPOCH,
-            inode: self.inodes.read().unwrap().get(path).copied().unwrap_or(0),
-            nlink: 1,
-        })
+        let file_type = self
+            .get_file_type(path)
+            .ok_or_else(|| FsError::not_found("symlink_metadata", path))?;
+
+        Ok(Metadata::default()
+            .with_file_type(file_type)
+            .with_size(0)
+            .with_permissions(Permissions::default_file())
+            .with_created(SystemTime::UNIX_EPOCH)
+            .with_modified(SystemTime::UNIX_EPOCH)
+            .with_accessed(SystemTime::UNIX_EPOCH)
+            .with_inode(self.inodes.read().unwrap().get(path).copied().unwrap_or(0))
+            .with_nlink(1))
     }
 }
 
 // Layer 2: FsPermissions
 impl FsPermissions for InMemoryFs {
-    fn set_permissions(&self, path: &Path, _perm: Permissions) -> Result<(), FsError> {
+    fn set_permissions(&self, path: &Path, perm: Permissions) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("set_permissions", path));
         }
+        self.permissions
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), perm);
         Ok(())
     }
 }
@@ -411,9 +453,7 @@ impl FsSync for InMemoryFs {
 
     fn fsync(&self, path: &Path) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("fsync", path));
         }
         Ok(())
     }
@@ -422,16 +462,11 @@ impl FsSync for InMemoryFs {
 // Layer 2: FsStats
 impl FsStats for InMemoryFs {
     fn statfs(&self) -> Result<StatFs, FsError> {
-        Ok(StatFs {
-            total_bytes: 1024 * 1024 * 100, // 100MB
-            used_bytes: 1024 * 1024 * 50,
-            available_bytes: 1024 * 1024 * 50,
-            total_inodes: 10000,
-            used_inodes: 1000,
-            available_inodes: 9000,
-            block_size: 4096,
-            max_name_len: 255,
-        })
+        Ok(StatFs::default()
+            .with_bytes(1024 * 1024 * 100, 1024 * 1024 * 50, 1024 * 1024 * 50)
+            .with_inodes(10000, 1000, 9000)
+            .with_block_size(4096)
+            .with_max_name_len(255))
     }
 }
 
@@ -443,9 +478,7 @@ impl FsInode for InMemoryFs {
             .unwrap()
             .get(path)
             .copied()
-            .ok_or_else(|| FsError::NotFound {
-                path: path.to_path_buf(),
-            })
+            .ok_or_else(|| FsError::not_found("path_to_inode", path))
     }
 
     fn inode_to_path(&self, inode: u64) -> Result<PathBuf, FsError> {
@@ -477,9 +510,7 @@ impl FsHandles for InMemoryFs {
                 self.write(path, &[])?;
             }
         } else if !self.files.read().unwrap().contains_key(path) {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("open", path));
         }
 
         let handle_id = self.next_handle.fetch_add(1, Ordering::SeqCst);
@@ -508,9 +539,9 @@ impl FsHandles for InMemoryFs {
         }
 
         let files = self.files.read().unwrap();
-        let data = files.get(&open_file.path).ok_or(FsError::NotFound {
-            path: open_file.path.clone(),
-        })?;
+        let data = files
+            .get(&open_file.path)
+            .ok_or(FsError::not_found("read_at", open_file.path.clone()))?;
 
         let start = offset as usize;
         if start >= data.len() {
@@ -634,9 +665,7 @@ impl FsLock for InMemoryFs {
 impl FsXattr for InMemoryFs {
     fn get_xattr(&self, path: &Path, name: &str) -> Result<Vec<u8>, FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("get_xattr", path));
         }
         self.xattrs
             .read()
@@ -651,9 +680,7 @@ impl FsXattr for InMemoryFs {
 
     fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("set_xattr", path));
         }
         self.xattrs
             .write()
@@ -666,9 +693,7 @@ impl FsXattr for InMemoryFs {
 
     fn remove_xattr(&self, path: &Path, name: &str) -> Result<(), FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("remove_xattr", path));
         }
         self.xattrs
             .write()
@@ -684,9 +709,7 @@ impl FsXattr for InMemoryFs {
 
     fn list_xattr(&self, path: &Path) -> Result<Vec<String>, FsError> {
         if self.get_file_type(path).is_none() {
-            return Err(FsError::NotFound {
-                path: path.to_path_buf(),
-            });
+            return Err(FsError::not_found("list_xattr", path));
         }
         Ok(self
             .xattrs
@@ -698,6 +721,155 @@ impl FsXattr for InMemoryFs {
     }
 }
 
+// Optional: expiry, emulated through the well-known xattr
+impl FsExpiry for InMemoryFs {
+    fn set_expiry(&self, path: &Path, at: SystemTime) -> Result<(), FsError> {
+        self.set_xattr_expiry(path, Some(at))
+    }
+
+    fn clear_expiry(&self, path: &Path) -> Result<(), FsError> {
+        self.set_xattr_expiry(path, None)
+    }
+
+    fn expiry(&self, path: &Path) -> Result<Option<SystemTime>, FsError> {
+        self.xattr_expiry(path)
+    }
+}
+
+// Optional: export servers (inode-based defaults)
+impl FsExport for InMemoryFs {}
+
+// Optional: runtime discovery of the traits above
+impl FsMaybeFull for InMemoryFs {
+    fn as_link(&self) -> Option<&dyn FsLink> {
+        Some(self)
+    }
+
+    fn as_permissions(&self) -> Option<&dyn FsPermissions> {
+        Some(self)
+    }
+
+    fn as_sync(&self) -> Option<&dyn FsSync> {
+        Some(self)
+    }
+
+    fn as_stats(&self) -> Option<&dyn FsStats> {
+        Some(self)
+    }
+}
+
+// Optional: stable ids, here derived from inodes
+impl FsFileId for InMemoryFs {
+    fn file_id(&self, path: &Path) -> Result<FileId, FsError> {
+        Ok(FileId::from_u128(u128::from(self.path_to_inode(path)?)))
+    }
+
+    fn path_by_id(&self, id: &FileId) -> Result<PathBuf, FsError> {
+        let inode = id
+            .as_u128()
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| FsError::InvalidData {
+                path: PathBuf::new(),
+                details: "foreign file id".to_string(),
+            })?;
+        self.inode_to_path(inode)
+    }
+}
+
+// Optional: openat-style operations; directory handles pin inodes
+impl InMemoryFs {
+    fn child_at(&self, dir: DirHandle, name: &OsStr) -> Result<PathBuf, FsError> {
+        let mut components = Path::new(name).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        ) {
+            return Err(FsError::InvalidData {
+                path: PathBuf::from(name),
+                details: "not a single path component".to_string(),
+            });
+        }
+        let inode = self
+            .dir_handles
+            .read()
+            .unwrap()
+            .get(&dir.0)
+            .copied()
+            .ok_or(FsError::InvalidHandle {
+                handle: Handle(dir.0),
+            })?;
+        Ok(self.inode_to_path(inode)?.join(name))
+    }
+
+    fn pin_dir(&self, path: &Path, operation: &'static str) -> Result<DirHandle, FsError> {
+        match self.get_file_type(path) {
+            Some(FileType::Directory) => {}
+            Some(_) => return Err(FsError::not_a_directory(operation, path)),
+            None => return Err(FsError::not_found(operation, path)),
+        }
+        let id = self.next_handle.fetch_add(1, Ordering::SeqCst);
+        self.dir_handles
+            .write()
+            .unwrap()
+            .insert(id, self.path_to_inode(path)?);
+        Ok(DirHandle(id))
+    }
+}
+
+impl FsDirHandles for InMemoryFs {
+    fn open_dir(&self, path: &Path) -> Result<DirHandle, FsError> {
+        self.pin_dir(path, "open_dir")
+    }
+
+    fn open_dir_at(&self, dir: DirHandle, name: &OsStr) -> Result<DirHandle, FsError> {
+        let path = self.child_at(dir, name)?;
+        self.pin_dir(&path, "open_dir_at")
+    }
+
+    fn close_dir(&self, dir: DirHandle) -> Result<(), FsError> {
+        self.dir_handles
+            .write()
+            .unwrap()
+            .remove(&dir.0)
+            .map(|_| ())
+            .ok_or(FsError::InvalidHandle {
+                handle: Handle(dir.0),
+            })
+    }
+
+    fn open_at(&self, dir: DirHandle, name: &OsStr, flags: OpenFlags) -> Result<Handle, FsError> {
+        let path = self.child_at(dir, name)?;
+        match self.get_file_type(&path) {
+            Some(FileType::Directory) | Some(FileType::Symlink) => {
+                Err(FsError::not_a_file("open_at", &path))
+            }
+            _ => self.open(&path, flags),
+        }
+    }
+
+    fn metadata_at(&self, dir: DirHandle, name: &OsStr) -> Result<Metadata, FsError> {
+        let path = self.child_at(dir, name)?;
+        self.symlink_metadata(&path)
+    }
+
+    fn unlink_at(&self, dir: DirHandle, name: &OsStr) -> Result<(), FsError> {
+        let path = self.child_at(dir, name)?;
+        match self.get_file_type(&path) {
+            Some(FileType::Directory) => Err(FsError::not_a_file("unlink_at", &path)),
+            Some(FileType::Symlink) => {
+                self.symlinks.write().unwrap().remove(&path);
+                Ok(())
+            }
+            _ => self.remove_file(&path),
+        }
+    }
+
+    fn mkdir_at(&self, dir: DirHandle, name: &OsStr) -> Result<(), FsError> {
+        let path = self.child_at(dir, name)?;
+        self.create_dir(&path)
+    }
+}
+
 // =============================================================================
 // Tests: Trait Hierarchy Verification
 // =============================================================================
@@ -906,7 +1078,7 @@ fn error_not_found_contains_path() {
     let result = fs.read(Path::new("/nonexistent.txt"));
 
     match result {
-        Err(FsError::NotFound { path }) => {
+        Err(FsError::NotFound { path, .. }) => {
             assert_eq!(path, PathBuf::from("/nonexistent.txt"));
         }
         _ => panic!("expected NotFound error"),
@@ -1045,6 +1217,12 @@ fn workflow_create_project_structure() {
         .unwrap();
     assert_eq!(src_entries.len(), 1);
     assert_eq!(src_entries[0].name, "main.rs");
+
+    // The defaults count through read_dir
+    assert_eq!(fs.dir_len(Path::new("/project")).unwrap(), 3);
+    assert!(!fs.is_dir_empty(Path::new("/project/src")).unwrap());
+    fs.create_dir(Path::new("/project/docs")).unwrap();
+    assert!(fs.is_dir_empty(Path::new("/project/docs")).unwrap());
 }
 
 #[test]
@@ -1092,6 +1270,77 @@ fn workflow_handle_based_read_write() {
     assert_eq!(&buf, b"HEADERBODY");
 }
 
+#[test]
+fn file_id_survives_rename() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/draft.txt"), b"v1").unwrap();
+    let id = fs.file_id(Path::new("/draft.txt")).unwrap();
+
+    fs.rename(Path::new("/draft.txt"), Path::new("/final.txt"))
+        .unwrap();
+
+    assert_eq!(fs.file_id(Path::new("/final.txt")).unwrap(), id);
+    assert_eq!(fs.path_by_id(&id).unwrap(), PathBuf::from("/final.txt"));
+}
+
+#[test]
+fn workflow_dir_handles_resolve_names_in_pinned_dir() {
+    let fs = InMemoryFs::new();
+    fs.create_dir(Path::new("/jail")).unwrap();
+    let dir = fs.open_dir(Path::new("/jail")).unwrap();
+    fs.mkdir_at(dir, OsStr::new("sub")).unwrap();
+    let handle = fs
+        .open_at(dir, OsStr::new("a.txt"), OpenFlags::WRITE)
+        .unwrap();
+    fs.write_at(handle, b"pinned", 0).unwrap();
+    fs.close(handle).unwrap();
+
+    assert_eq!(fs.read(Path::new("/jail/a.txt")).unwrap(), b"pinned");
+    assert!(fs.metadata_at(dir, OsStr::new("sub")).unwrap().is_dir());
+    let sub = fs.open_dir_at(dir, OsStr::new("sub")).unwrap();
+    fs.close_dir(sub).unwrap();
+
+    assert!(matches!(
+        fs.open_at(dir, OsStr::new("../escape"), OpenFlags::WRITE),
+        Err(FsError::InvalidData { .. })
+    ));
+    fs.unlink_at(dir, OsStr::new("a.txt")).unwrap();
+    assert!(!fs.exists(Path::new("/jail/a.txt")).unwrap());
+
+    fs.close_dir(dir).unwrap();
+    assert!(matches!(
+        fs.metadata_at(dir, OsStr::new("sub")),
+        Err(FsError::InvalidHandle { .. })
+    ));
+}
+
+#[test]
+fn workflow_export_ids_round_trip() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/exported.txt"), b"served").unwrap();
+
+    let id = fs.export_id(Path::new("/exported.txt")).unwrap();
+    assert_eq!(
+        fs.resolve_export_id(&id).unwrap(),
+        PathBuf::from("/exported.txt")
+    );
+
+    let handle = fs.open_by_id(&id, OpenFlags::READ).unwrap();
+    let mut buf = [0u8; 6];
+    assert_eq!(fs.read_at(handle, &mut buf, 0).unwrap(), 6);
+    fs.close(handle).unwrap();
+    assert_eq!(&buf, b"served");
+
+    assert!(matches!(
+        fs.resolve_export_id(&FileId::from_bytes([0xff])),
+        Err(FsError::InvalidData { .. })
+    ));
+    assert_eq!(
+        fs.lease_hint(Path::new("/exported.txt")).unwrap(),
+        LeaseHint::NONE
+    );
+}
+
 #[test]
 fn workflow_metadata_and_stats() {
     let fs = InMemoryFs::new();
@@ -1118,3 +1367,881 @@ fn workflow_metadata_and_stats() {
     let stats = fs.statfs().unwrap();
     assert!(stats.total_bytes > stats.used_bytes);
 }
+
+#[test]
+fn workflow_metadata_lite_matches_metadata() {
+    let fs = InMemoryFs::new();
+
+    fs.write(Path::new("/file.txt"), b"12345").unwrap();
+    fs.create_dir(Path::new("/dir")).unwrap();
+
+    for path in [Path::new("/file.txt"), Path::new("/dir")] {
+        let full = fs.metadata(path).unwrap();
+        let lite = fs.metadata_lite(path).unwrap();
+        assert_eq!(lite, MetadataLite::from(&full));
+    }
+    assert!(matches!(
+        fs.metadata_lite(Path::new("/missing")),
+        Err(FsError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn workflow_metadata_mask_fills_requested_fields() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/file.txt"), b"12345").unwrap();
+
+    let mask = MetadataMask {
+        size: true,
+        ..MetadataMask::NONE
+    };
+    let meta = fs.metadata_mask(Path::new("/file.txt"), mask).unwrap();
+    assert_eq!(meta.file_type, FileType::File);
+    assert_eq!(meta.size, 5);
+}
+
+#[test]
+fn workflow_metadata_with_tag_defaults_to_untagged_metadata() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/file.txt"), b"12345").unwrap();
+
+    let meta = fs.metadata_with_tag(Path::new("/file.txt")).unwrap();
+    assert_eq!(meta.size, 5);
+    assert!(meta.content_tag.is_none());
+}
+
+#[test]
+fn workflow_default_permissions_applies_create_mode() {
+    let fs = InMemoryFs::new()
+        .with_default_permissions(CreateMode::default().with_umask(UmaskPolicy::new(0o077)));
+    let mode = |path: &str| fs.metadata(Path::new(path)).unwrap().permissions.mode();
+
+    fs.write(Path::new("/secret"), b"token").unwrap();
+    fs.create_dir_all(Path::new("/a/b")).unwrap();
+    assert_eq!(mode("/secret"), 0o600);
+    assert_eq!(mode("/a"), 0o700);
+    assert_eq!(mode("/a/b"), 0o700);
+
+    // Existing entries keep their mode
+    fs.set_permissions(Path::new("/secret"), Permissions::from_mode(0o640))
+        .unwrap();
+    fs.write(Path::new("/secret"), b"rotated").unwrap();
+    assert_eq!(mode("/secret"), 0o640);
+
+    // setgid propagates to every directory create_dir_all makes
+    fs.set_permissions(Path::new("/a"), Permissions::from_mode(0o2770))
+        .unwrap();
+    fs.create_dir_all(Path::new("/a/c/d")).unwrap();
+    assert_eq!(mode("/a/c"), 0o2700);
+    assert_eq!(mode("/a/c/d"), 0o2700);
+
+    let handle = fs
+        .open(
+            Path::new("/a/new.txt"),
+            OpenFlags {
+                create: true,
+                ..OpenFlags::WRITE
+            },
+        )
+        .unwrap();
+    fs.close(handle).unwrap();
+    assert_eq!(mode("/a/new.txt"), 0o600);
+}
+
+fn build_tree(fs: &InMemoryFs) {
+    fs.create_dir_all(Path::new("/tree/sub")).unwrap();
+    fs.write(Path::new("/tree/a.txt"), b"a").unwrap();
+    fs.write(Path::new("/tree/sub/b.txt"), b"bb").unwrap();
+    fs.write(Path::new("/outside.txt"), b"x").unwrap();
+    fs.symlink(Path::new("/outside.txt"), Path::new("/tree/link"))
+        .unwrap();
+}
+
+#[test]
+fn workflow_walk_visits_tree_without_following_symlinks() {
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+
+    let mut seen: Vec<(PathBuf, usize)> = fs
+        .walk(Path::new("/tree"))
+        .map(|e| e.map(|e| (e.path().to_path_buf(), e.depth)))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(seen[0], (PathBuf::from("/tree"), 0));
+    seen.sort();
+    assert_eq!(
+        seen,
+        vec![
+            (PathBuf::from("/tree"), 0),
+            (PathBuf::from("/tree/a.txt"), 1),
+            (PathBuf::from("/tree/link"), 1),
+            (PathBuf::from("/tree/sub"), 1),
+            (PathBuf::from("/tree/sub/b.txt"), 2),
+        ]
+    );
+
+    // Contents first: every directory comes after everything beneath it
+    let order: Vec<PathBuf> = fs
+        .walk(Path::new("/tree"))
+        .contents_first(true)
+        .map(|e| e.unwrap().path().to_path_buf())
+        .collect();
+    let pos = |p: &str| order.iter().position(|x| x == Path::new(p)).unwrap();
+    assert_eq!(order.last().unwrap(), Path::new("/tree"));
+    assert!(pos("/tree/sub/b.txt") < pos("/tree/sub"));
+
+    let shallow = fs.walk(Path::new("/tree")).max_depth(0).count();
+    assert_eq!(shallow, 1);
+}
+
+#[test]
+fn workflow_set_permissions_recursive_and_readonly_tree() {
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+    let mode = |path: &str| fs.metadata(Path::new(path)).unwrap().permissions.mode();
+
+    fs.set_permissions_recursive(
+        Path::new("/tree"),
+        Permissions::from_mode(0o664),
+        Permissions::from_mode(0o775),
+    )
+    .unwrap();
+    assert_eq!(mode("/tree"), 0o775);
+    assert_eq!(mode("/tree/sub"), 0o775);
+    assert_eq!(mode("/tree/sub/b.txt"), 0o664);
+    // The symlink target outside the tree is untouched
+    assert_eq!(mode("/outside.txt"), 0o644);
+
+    fs.make_readonly_tree(Path::new("/tree")).unwrap();
+    assert_eq!(mode("/tree"), 0o555);
+    assert_eq!(mode("/tree/a.txt"), 0o444);
+    assert_eq!(mode("/outside.txt"), 0o644);
+}
+
+#[test]
+fn workflow_create_dir_with_and_temp_dirs() {
+    let fs = InMemoryFs::new();
+    assert!(matches!(
+        fs.create_dir_with(Path::new("/keys"), Permissions::from_mode(0o700)),
+        Err(FsError::NotSupported { .. })
+    ));
+    // Without create_dir_with, temp dirs fall back to create_dir
+    let plain = fs.create_temp_dir_in(Path::new("/"), "tmp-").unwrap();
+    assert!(fs.is_dir(&plain).unwrap());
+
+    let fs = fs.with_default_permissions(CreateMode::default());
+    fs.create_dir_with(Path::new("/keys"), Permissions::from_mode(0o700))
+        .unwrap();
+    assert_eq!(
+        fs.metadata(Path::new("/keys")).unwrap().permissions.mode(),
+        0o700
+    );
+
+    let a = fs.create_temp_dir_in(Path::new("/keys"), "tmp-").unwrap();
+    let b = fs.create_temp_dir_in(Path::new("/keys"), "tmp-").unwrap();
+    assert_ne!(a, b);
+    assert_eq!(a.parent(), Some(Path::new("/keys")));
+    assert!(a.file_name().unwrap().to_str().unwrap().starts_with("tmp-"));
+    assert!(fs.is_dir(&a).unwrap());
+    assert_eq!(fs.metadata(&b).unwrap().permissions.mode(), 0o700);
+}
+
+#[test]
+fn workflow_remove_dir_all_report_keeps_symlink_targets() {
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+
+    let report = fs.remove_dir_all_report(Path::new("/tree")).unwrap();
+    assert!(report.is_complete(), "{:?}", report.failures);
+    assert_eq!(report.removed, 5);
+    assert!(!fs.exists(Path::new("/tree")).unwrap());
+    assert!(!fs.exists(Path::new("/tree/link")).unwrap());
+    assert!(fs.exists(Path::new("/outside.txt")).unwrap());
+
+    assert!(matches!(
+        fs.remove_dir_all_report(Path::new("/outside.txt")),
+        Err(FsError::NotADirectory { .. })
+    ));
+}
+
+#[test]
+fn workflow_remove_dir_all_report_with_reports_each_removal() {
+    struct Collect(Vec<Progress>);
+    impl ProgressSink for &mut Collect {
+        fn report(&mut self, progress: &Progress) {
+            self.0.push(progress.clone());
+        }
+    }
+
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+
+    let mut seen = Collect(Vec::new());
+    let report = fs
+        .remove_dir_all_report_with(Path::new("/tree"), &mut seen)
+        .unwrap();
+    assert_eq!(seen.0.len() as u64, report.removed);
+    let counts: Vec<u64> = seen.0.iter().map(|p| p.entries_done).collect();
+    assert_eq!(counts, (1..=report.removed).collect::<Vec<_>>());
+    assert_eq!(seen.0.last().unwrap().path, Path::new("/tree"));
+}
+
+#[test]
+fn workflow_tree_renders_sorted_listing() {
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+
+    assert_eq!(
+        fs.tree(Path::new("/tree"), usize::MAX).unwrap(),
+        "/tree/\n\
+         ├── a.txt (1 B)\n\
+         ├── link -> /outside.txt\n\
+         └── sub/\n    \
+             └── b.txt (2 B)\n"
+    );
+    assert_eq!(fs.tree(Path::new("/tree"), 0).unwrap(), "/tree/\n");
+}
+
+#[test]
+fn workflow_time_helpers_report_missing_support() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/stamp"), b"").unwrap();
+
+    assert!(matches!(
+        fs.set_modified(Path::new("/stamp"), SystemTime::UNIX_EPOCH),
+        Err(FsError::NotSupported { .. })
+    ));
+    assert!(matches!(
+        fs.touch_times(Path::new("/stamp")),
+        Err(FsError::NotSupported { .. })
+    ));
+}
+
+#[test]
+fn workflow_dry_run_previews_removal_without_applying_it() {
+    let fs = InMemoryFs::new().layer(DryRunLayer);
+    build_tree(fs.inner());
+
+    let report = fs.remove_dir_all_report(Path::new("/tree")).unwrap();
+    let plan = fs.take_plan();
+    assert_eq!(plan.len() as u64, report.removed);
+    assert!(plan.iter().all(|op| op.path().starts_with("/tree")));
+    assert_eq!(
+        plan.last(),
+        Some(&FsOp::RemoveDir {
+            path: "/tree".into()
+        })
+    );
+    assert!(fs.exists(Path::new("/tree")).unwrap());
+}
+
+#[test]
+fn workflow_remove_dir_all_report_records_failures() {
+    // Files named "locked" refuse removal
+    struct Stubborn(InMemoryFs);
+    impl FsRead for Stubborn {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.0.read(path)
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            self.0.read_to_string(path)
+        }
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            self.0.read_range(path, offset, len)
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            self.0.exists(path)
+        }
+        fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            self.0.metadata(path)
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+            self.0.open_read(path)
+        }
+    }
+    impl FsWrite for Stubborn {
+        fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.write(path, data)
+        }
+        fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.append(path, data)
+        }
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            if path.file_name() == Some(OsStr::new("locked")) {
+                return Err(FsError::PermissionDenied {
+                    path: path.to_path_buf(),
+                    operation: "remove_file",
+                });
+            }
+            self.0.remove_file(path)
+        }
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.0.rename(from, to)
+        }
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.0.copy(from, to)
+        }
+        fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+            self.0.truncate(path, size)
+        }
+        fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+            self.0.open_write(path)
+        }
+    }
+    impl FsDir for Stubborn {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            self.0.read_dir(path)
+        }
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir(path)
+        }
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir_all(path)
+        }
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_dir(path)
+        }
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.remove_dir_all_report(path)?.into_result()
+        }
+    }
+
+    let fs = Stubborn(InMemoryFs::new());
+    build_tree(&fs.0);
+    fs.write(Path::new("/tree/sub/locked"), b"").unwrap();
+
+    let report = fs.remove_dir_all_report(Path::new("/tree")).unwrap();
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].path, Path::new("/tree/sub/locked"));
+    assert_eq!(report.removed, 3); // a.txt, link, sub/b.txt
+    assert!(fs.exists(Path::new("/tree/sub")).unwrap());
+    assert!(!fs.exists(Path::new("/tree/a.txt")).unwrap());
+
+    assert!(matches!(
+        fs.remove_dir_all(Path::new("/tree")),
+        Err(FsError::PermissionDenied { .. })
+    ));
+}
+
+#[test]
+fn workflow_copy_with_overwrite_policies() {
+    let fs = InMemoryFs::new();
+    fs.write(Path::new("/src.txt"), b"hello").unwrap();
+    fs.set_permissions(Path::new("/src.txt"), Permissions::from_mode(0o600))
+        .unwrap();
+    fs.write(Path::new("/dst.txt"), b"old").unwrap();
+
+    let with = |overwrite| CopyOptions {
+        overwrite,
+        ..CopyOptions::default()
+    };
+    let (src, dst) = (Path::new("/src.txt"), Path::new("/dst.txt"));
+
+    assert!(matches!(
+        fs.copy_with(src, dst, with(OverwritePolicy::Fail)),
+        Err(FsError::AlreadyExists { .. })
+    ));
+    assert_eq!(
+        fs.copy_with(src, dst, with(OverwritePolicy::Skip)).unwrap(),
+        0
+    );
+    assert_eq!(fs.read(dst).unwrap(), b"old");
+    assert_eq!(
+        fs.copy_with(src, dst, with(OverwritePolicy::Replace))
+            .unwrap(),
+        5
+    );
+    assert_eq!(fs.read(dst).unwrap(), b"hello");
+    assert_eq!(fs.metadata(dst).unwrap().permissions.mode(), 0o644);
+
+    let preserve = CopyOptions {
+        preserve_permissions: true,
+        ..CopyOptions::default()
+    };
+    fs.copy_with(src, Path::new("/kept.txt"), preserve).unwrap();
+    assert_eq!(
+        fs.metadata(Path::new("/kept.txt"))
+            .unwrap()
+            .permissions
+            .mode(),
+        0o600
+    );
+
+    let times = CopyOptions {
+        preserve_times: true,
+        ..CopyOptions::default()
+    };
+    assert!(matches!(
+        fs.copy_with(src, dst, times),
+        Err(FsError::NotSupported { .. })
+    ));
+}
+
+#[test]
+fn workflow_move_entry_falls_back_to_copy() {
+    // Refuses rename, as a composed backend spanning two mounts would
+    struct NoRename(InMemoryFs);
+    impl FsRead for NoRename {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.0.read(path)
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            self.0.read_to_string(path)
+        }
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            self.0.read_range(path, offset, len)
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            self.0.exists(path)
+        }
+        fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            self.0.metadata(path)
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+            self.0.open_read(path)
+        }
+    }
+    impl FsWrite for NoRename {
+        fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.write(path, data)
+        }
+        fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.0.append(path, data)
+        }
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_file(path)
+        }
+        fn rename(&self, _: &Path, _: &Path) -> Result<(), FsError> {
+            Err(FsError::NotSupported {
+                operation: "rename",
+            })
+        }
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.0.copy(from, to)
+        }
+        fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+            self.0.truncate(path, size)
+        }
+        fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+            self.0.open_write(path)
+        }
+    }
+    impl FsDir for NoRename {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            self.0.read_dir(path)
+        }
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir(path)
+        }
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.0.create_dir_all(path)
+        }
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.0.remove_dir(path)
+        }
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.remove_dir_all_report(path)?.into_result()
+        }
+    }
+
+    let fs = NoRename(InMemoryFs::new());
+    fs.create_dir_all(Path::new("/hot/data/nested")).unwrap();
+    fs.write(Path::new("/hot/data/a.bin"), b"abc").unwrap();
+    fs.write(Path::new("/hot/data/nested/b.bin"), b"defg")
+        .unwrap();
+    fs.create_dir(Path::new("/cold")).unwrap();
+
+    let mut last = None;
+    fs.move_entry_with(
+        Path::new("/hot/data"),
+        Path::new("/cold/data"),
+        |p: &Progress| last = Some(p.clone()),
+    )
+    .unwrap();
+    let last = last.unwrap();
+    assert_eq!(last.entries_done, 4);
+    assert_eq!(last.bytes_done, 7);
+
+    assert!(!fs.exists(Path::new("/hot/data")).unwrap());
+    assert_eq!(
+        fs.read(Path::new("/cold/data/nested/b.bin")).unwrap(),
+        b"defg"
+    );
+
+    fs.move_entry(Path::new("/cold/data/a.bin"), Path::new("/a.bin"))
+        .unwrap();
+    assert_eq!(fs.read(Path::new("/a.bin")).unwrap(), b"abc");
+    assert!(!fs.exists(Path::new("/cold/data/a.bin")).unwrap());
+
+    // The fallback never replaces an existing destination, file or directory
+    fs.write(Path::new("/b.bin"), b"keep").unwrap();
+    assert!(matches!(
+        fs.move_entry(Path::new("/cold/data/nested/b.bin"), Path::new("/b.bin")),
+        Err(FsError::AlreadyExists { .. })
+    ));
+    assert_eq!(fs.read(Path::new("/b.bin")).unwrap(), b"keep");
+    assert!(fs.exists(Path::new("/cold/data/nested/b.bin")).unwrap());
+
+    fs.create_dir(Path::new("/taken")).unwrap();
+    assert!(matches!(
+        fs.move_entry(Path::new("/cold/data"), Path::new("/taken")),
+        Err(FsError::AlreadyExists { .. })
+    ));
+    assert!(fs.exists(Path::new("/cold/data/nested")).unwrap());
+
+    // A plain rename never reaches the fallback
+    let native = InMemoryFs::new();
+    native.write(Path::new("/x"), b"1").unwrap();
+    native
+        .move_entry_with(Path::new("/x"), Path::new("/y"), |_: &Progress| {
+            panic!("rename succeeded; no copy expected")
+        })
+        .unwrap();
+    assert!(native.exists(Path::new("/y")).unwrap());
+}
+
+#[test]
+fn workflow_safe_replace_restores_on_failure() {
+    // Never replaces, and refuses to move one chosen source
+    struct NoReplace {
+        inner: InMemoryFs,
+        stuck: RwLock<Option<PathBuf>>,
+    }
+    impl FsRead for NoReplace {
+        fn read(&self, path: &Path) -> Result<Vec<u8>, FsError> {
+            self.inner.read(path)
+        }
+        fn read_to_string(&self, path: &Path) -> Result<String, FsError> {
+            self.inner.read_to_string(path)
+        }
+        fn read_range(&self, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, FsError> {
+            self.inner.read_range(path, offset, len)
+        }
+        fn exists(&self, path: &Path) -> Result<bool, FsError> {
+            self.inner.exists(path)
+        }
+        fn metadata(&self, path: &Path) -> Result<Metadata, FsError> {
+            self.inner.metadata(path)
+        }
+        fn open_read(&self, path: &Path) -> Result<Box<dyn Read + Send>, FsError> {
+            self.inner.open_read(path)
+        }
+    }
+    impl FsWrite for NoReplace {
+        fn write(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.inner.write(path, data)
+        }
+        fn append(&self, path: &Path, data: &[u8]) -> Result<(), FsError> {
+            self.inner.append(path, data)
+        }
+        fn remove_file(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.remove_file(path)
+        }
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            if self.inner.exists(to)? {
+                return Err(FsError::already_exists("rename", to));
+            }
+            if self.stuck.read().unwrap().as_deref() == Some(from) {
+                return Err(FsError::permission_denied("rename", from));
+            }
+            self.inner.rename(from, to)
+        }
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), FsError> {
+            self.inner.copy(from, to)
+        }
+        fn truncate(&self, path: &Path, size: u64) -> Result<(), FsError> {
+            self.inner.truncate(path, size)
+        }
+        fn open_write(&self, path: &Path) -> Result<Box<dyn Write + Send>, FsError> {
+            self.inner.open_write(path)
+        }
+    }
+    impl FsDir for NoReplace {
+        fn read_dir(&self, path: &Path) -> Result<ReadDirIter, FsError> {
+            self.inner.read_dir(path)
+        }
+        fn create_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.create_dir(path)
+        }
+        fn create_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.create_dir_all(path)
+        }
+        fn remove_dir(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.remove_dir(path)
+        }
+        fn remove_dir_all(&self, path: &Path) -> Result<(), FsError> {
+            self.inner.remove_dir_all(path)
+        }
+    }
+
+    let fs = NoReplace {
+        inner: InMemoryFs::new(),
+        stuck: RwLock::new(None),
+    };
+    let (new, current) = (Path::new("/config.new"), Path::new("/config"));
+    fs.write(current, b"old").unwrap();
+    fs.write(new, b"new").unwrap();
+    assert!(matches!(
+        fs.rename(new, current),
+        Err(FsError::AlreadyExists { .. })
+    ));
+
+    fs.safe_replace(new, current).unwrap();
+    assert_eq!(fs.read(current).unwrap(), b"new");
+    assert!(!fs.exists(new).unwrap());
+    assert!(!fs.exists(Path::new("/.config.replace-backup")).unwrap());
+
+    // The backup is moved aside, but `from` can't follow: restored
+    fs.write(new, b"newer").unwrap();
+    *fs.stuck.write().unwrap() = Some(new.to_path_buf());
+    assert!(matches!(
+        fs.safe_replace(new, current),
+        Err(FsError::PermissionDenied { .. })
+    ));
+    assert_eq!(fs.read(current).unwrap(), b"new");
+    assert_eq!(fs.read(new).unwrap(), b"newer");
+    assert!(!fs.exists(Path::new("/.config.replace-backup")).unwrap());
+
+    assert!(matches!(
+        fs.safe_replace(Path::new("/missing"), current),
+        Err(FsError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn workflow_read_dir_reports_symlink_targets() {
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+
+    let entries = fs
+        .read_dir(Path::new("/tree"))
+        .unwrap()
+        .collect_all()
+        .unwrap();
+    let link = entries.iter().find(|e| e.name == "link").unwrap();
+    assert_eq!(link.file_type, FileType::Symlink);
+    assert_eq!(
+        link.symlink_target.as_deref(),
+        Some(Path::new("/outside.txt"))
+    );
+    assert!(entries
+        .iter()
+        .filter(|e| e.name != "link")
+        .all(|e| e.symlink_target.is_none()));
+}
+
+#[test]
+fn workflow_encryption_info_round_trips_through_xattrs() {
+    let fs = InMemoryFs::new();
+    let path = Path::new("/secret.bin");
+    fs.write(path, b"ciphertext").unwrap();
+    assert_eq!(EncryptionInfo::read_xattrs(&fs, path).unwrap(), None);
+
+    let info = EncryptionInfo::new("aes-256-gcm", "k1", vec![9; 12]);
+    info.write_xattrs(&fs, path).unwrap();
+    assert_eq!(fs.get_xattr(path, XATTR_ENC_KEY_ID).unwrap(), b"k1");
+    assert_eq!(EncryptionInfo::read_xattrs(&fs, path).unwrap(), Some(info));
+
+    fs.remove_xattr(path, XATTR_ENC_NONCE).unwrap();
+    assert!(matches!(
+        EncryptionInfo::read_xattrs(&fs, path),
+        Err(FsError::InvalidData { .. })
+    ));
+}
+
+#[test]
+fn workflow_expiry_sweeper_purges_expired_entries() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let fs = InMemoryFs::new();
+    build_tree(&fs);
+    fs.write(Path::new("/tree/keep.txt"), b"k").unwrap();
+    let t = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+    fs.set_expiry(Path::new("/tree/a.txt"), t(100)).unwrap();
+    fs.set_expiry(Path::new("/tree/sub"), t(200)).unwrap();
+    fs.set_expiry(Path::new("/tree/keep.txt"), t(300)).unwrap();
+    fs.set_expiry(Path::new("/tree"), t(0)).unwrap();
+    assert_eq!(fs.expiry(Path::new("/tree/sub")).unwrap(), Some(t(200)));
+
+    let report = ExpirySweeper::new("/tree").at(t(200)).sweep(&fs).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.removed, 2);
+    assert!(!fs.exists(Path::new("/tree/a.txt")).unwrap());
+    assert!(!fs.exists(Path::new("/tree/sub")).unwrap());
+    assert!(fs.exists(Path::new("/tree/keep.txt")).unwrap());
+    assert!(fs.exists(Path::new("/tree/link")).unwrap());
+
+    fs.clear_expiry(Path::new("/tree/keep.txt")).unwrap();
+    fs.clear_expiry(Path::new("/tree/keep.txt")).unwrap();
+    assert_eq!(fs.expiry(Path::new("/tree/keep.txt")).unwrap(), None);
+}
+
+#[test]
+fn workflow_xattr_expiry_rounds_up_and_rejects_garbage() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let fs = InMemoryFs::new();
+    let path = Path::new("/f");
+    fs.write(path, b"").unwrap();
+    fs.set_xattr_expiry(path, Some(UNIX_EPOCH + Duration::from_millis(1500)))
+        .unwrap();
+    assert_eq!(fs.get_xattr(path, XATTR_EXPIRES).unwrap(), b"2");
+
+    fs.set_xattr(path, XATTR_EXPIRES, b"soon").unwrap();
+    assert!(matches!(
+        fs.xattr_expiry(path),
+        Err(FsError::InvalidData { .. })
+    ));
+}
+
+/// Flat file store with [`FsBranch`] support, for the merge contract.
+#[derive(Default)]
+struct Staging {
+    files: Arc<RwLock<Files>>,
+    branches: RwLock<HashMap<String, StagedBranch>>,
+}
+
+type Files = std::collections::BTreeMap<PathBuf, Vec<u8>>;
+
+struct StagedBranch {
+    base: Files,
+    files: Arc<RwLock<Files>>,
+}
+
+fn flat_fs(files: Arc<RwLock<Files>>) -> ClosureFs {
+    let (reads, writes) = (files.clone(), files);
+    FsBuilder::new()
+        .on_read(move |path| {
+            reads
+                .read()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| FsError::not_found("read", path))
+        })
+        .on_write(move |path, data| {
+            writes
+                .write()
+                .unwrap()
+                .insert(path.to_path_buf(), data.to_vec());
+            Ok(())
+        })
+        .build()
+}
+
+impl FsBranch for Staging {
+    fn branch(&self, name: &str) -> Result<Box<dyn Fs>, FsError> {
+        let mut branches = self.branches.write().unwrap();
+        if branches.contains_key(name) {
+            return Err(FsError::AlreadyExists {
+                path: name.into(),
+                operation: "branch",
+            });
+        }
+        let base = self.files.read().unwrap().clone();
+        let files = Arc::new(RwLock::new(base.clone()));
+        branches.insert(
+            name.to_string(),
+            StagedBranch {
+                base,
+                files: files.clone(),
+            },
+        );
+        Ok(Box::new(flat_fs(files)))
+    }
+
+    fn merge(&self, name: &str, policy: MergePolicy) -> Result<MergeReport, FsError> {
+        let mut branches = self.branches.write().unwrap();
+        let branch = branches
+            .get(name)
+            .ok_or_else(|| FsError::not_found("merge", name))?;
+        let theirs = branch.files.read().unwrap().clone();
+        let mut ours = self.files.write().unwrap();
+
+        let mut paths: Vec<&PathBuf> = branch.base.keys().chain(theirs.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        let mut report = MergeReport::default();
+        let mut changes = Vec::new();
+        for path in paths {
+            let (base, theirs, current) = (branch.base.get(path), theirs.get(path), ours.get(path));
+            if theirs == base || theirs == current {
+                continue;
+            }
+            if current != base {
+                report.conflicts.push(path.clone());
+                if policy != MergePolicy::PreferBranch {
+                    continue;
+                }
+            }
+            changes.push((path.clone(), theirs.cloned()));
+        }
+        if policy == MergePolicy::Abort && !report.is_clean() {
+            return Ok(report);
+        }
+        for (path, data) in changes {
+            match data {
+                Some(data) => ours.insert(path.clone(), data),
+                None => ours.remove(&path),
+            };
+            report.applied.push(path);
+        }
+        branches.remove(name);
+        report.merged = true;
+        Ok(report)
+    }
+
+    fn discard_branch(&self, name: &str) -> Result<(), FsError> {
+        self.branches
+            .write()
+            .unwrap()
+            .remove(name)
+            .map(drop)
+            .ok_or_else(|| FsError::not_found("discard_branch", name))
+    }
+
+    fn branches(&self) -> Result<Vec<String>, FsError> {
+        let mut names: Vec<String> = self.branches.read().unwrap().keys().cloned().collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[test]
+fn workflow_branch_merge_reports_conflicts() {
+    let staging = Staging::default();
+    let main = flat_fs(staging.files.clone());
+    main.write(Path::new("/config"), b"v1").unwrap();
+    main.write(Path::new("/shared"), b"base").unwrap();
+
+    let fork = staging.branch("exp").unwrap();
+    assert!(matches!(
+        staging.branch("exp"),
+        Err(FsError::AlreadyExists { .. })
+    ));
+    assert_eq!(staging.branches().unwrap(), ["exp"]);
+    fork.write(Path::new("/config"), b"v2").unwrap();
+    fork.write(Path::new("/shared"), b"branch").unwrap();
+    main.write(Path::new("/shared"), b"main").unwrap();
+
+    // The fork and the backend don't see each other's writes
+    assert_eq!(main.read(Path::new("/config")).unwrap(), b"v1");
+    assert_eq!(fork.read(Path::new("/shared")).unwrap(), b"branch");
+
+    let report = staging.merge("exp", MergePolicy::Abort).unwrap();
+    assert!(!report.merged);
+    assert_eq!(report.conflicts, [PathBuf::from("/shared")]);
+    assert!(report.applied.is_empty());
+    assert_eq!(main.read(Path::new("/config")).unwrap(), b"v1");
+
+    let report = staging.merge("exp", MergePolicy::PreferTarget).unwrap();
+    assert!(report.merged);
+    assert_eq!(report.applied, [PathBuf::from("/config")]);
+    assert_eq!(main.read(Path::new("/config")).unwrap(), b"v2");
+    assert_eq!(main.read(Path::new("/shared")).unwrap(), b"main");
+    assert!(staging.branches().unwrap().is_empty());
+    assert!(matches!(
+        staging.discard_branch("exp"),
+        Err(FsError::NotFound { .. })
+    ));
+}